@@ -0,0 +1,25 @@
+use std::path::Path;
+use std::process::Command;
+
+/// Expose the short git commit hash of the build as `COPYTRADE_GIT_HASH`
+/// (`"unknown"` when not built from a git checkout), for `types::BuildInfo`.
+fn main() {
+    let hash = Command::new("git")
+        .args(["rev-parse", "--short=12", "HEAD"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .map(|hash| hash.trim().to_string())
+        .filter(|hash| !hash.is_empty())
+        .unwrap_or_else(|| "unknown".to_string());
+    println!("cargo:rustc-env=COPYTRADE_GIT_HASH={hash}");
+
+    // Rebuild when the checked-out commit moves
+    println!("cargo:rerun-if-changed=build.rs");
+    for path in [".git/HEAD", ".git/refs/heads", ".git/packed-refs"] {
+        if Path::new(path).exists() {
+            println!("cargo:rerun-if-changed={path}");
+        }
+    }
+}
@@ -0,0 +1,259 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+/// The parts of one `--artifacts` bundle the aggregate report reads: its
+/// `version.json` and the totals of its `exit_summary.json`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Session {
+    /// Bundle directory name.
+    pub name: String,
+    pub trader: String,
+    /// `"dry-run"` or `"live"`.
+    pub mode: String,
+    pub started_at: DateTime<Utc>,
+    pub ended_at: DateTime<Utc>,
+    /// Restored from a `--wal`, so its totals include the previous session's.
+    pub resumed: bool,
+    pub initial_budget: f64,
+    pub realized_pnl: f64,
+    pub unrealized_pnl: f64,
+    pub total_pnl: f64,
+    pub total_orders: u64,
+}
+
+#[derive(Deserialize)]
+struct RunFile {
+    trader: String,
+    mode: String,
+    started_at: DateTime<Utc>,
+    ended_at: DateTime<Utc>,
+    #[serde(default)]
+    resumed: bool,
+}
+
+#[derive(Deserialize)]
+struct SummaryFile {
+    initial_budget: f64,
+    realized_pnl: f64,
+    unrealized_pnl: f64,
+    total_pnl: f64,
+    total_orders: u64,
+}
+
+/// Read every bundle directly under `dir`, oldest first. Directories without
+/// a `version.json` are skipped with a warning.
+pub fn load_sessions(dir: &Path) -> Result<Vec<Session>> {
+    let entries =
+        std::fs::read_dir(dir).with_context(|| format!("failed to list {}", dir.display()))?;
+    let mut sessions = Vec::new();
+    for entry in entries.flatten() {
+        let bundle = entry.path();
+        if !bundle.is_dir() {
+            continue;
+        }
+        if !bundle.join("version.json").exists() {
+            warn!("Skipping {}: not a session bundle", bundle.display());
+            continue;
+        }
+        let run: RunFile = read_json(&bundle.join("version.json"))?;
+        let summary: SummaryFile = read_json(&bundle.join("exit_summary.json"))?;
+        sessions.push(Session {
+            name: entry.file_name().to_string_lossy().into_owned(),
+            trader: run.trader,
+            mode: run.mode,
+            started_at: run.started_at,
+            ended_at: run.ended_at,
+            resumed: run.resumed,
+            initial_budget: summary.initial_budget,
+            realized_pnl: summary.realized_pnl,
+            unrealized_pnl: summary.unrealized_pnl,
+            total_pnl: summary.total_pnl,
+            total_orders: summary.total_orders,
+        });
+    }
+    sessions.sort_by_key(|s| s.started_at);
+    Ok(sessions)
+}
+
+fn read_json<T: for<'de> Deserialize<'de>>(path: &Path) -> Result<T> {
+    let json = std::fs::read_to_string(path)
+        .with_context(|| format!("failed to read {}", path.display()))?;
+    serde_json::from_str(&json).with_context(|| format!("failed to parse {}", path.display()))
+}
+
+/// Sessions of several runs merged into one portfolio view.
+#[derive(Debug, Clone, Serialize)]
+pub struct AggregateReport {
+    pub sessions: usize,
+    pub total_pnl: f64,
+    /// Combined P&L after each session ended, in end order.
+    pub equity_curve: Vec<EquityPoint>,
+    /// One row per copied trader and mode, best total P&L first.
+    pub traders: Vec<TraderPerformance>,
+}
+
+/// Combined P&L of all runs when one session ended.
+#[derive(Debug, Clone, Serialize)]
+pub struct EquityPoint {
+    pub time: DateTime<Utc>,
+    /// The bundle that ended.
+    pub session: String,
+    pub trader: String,
+    pub total_pnl: f64,
+}
+
+/// Results of copying one trader in one mode, across its sessions.
+#[derive(Debug, Clone, Serialize)]
+pub struct TraderPerformance {
+    pub trader: String,
+    pub mode: String,
+    pub sessions: usize,
+    pub first_started: DateTime<Utc>,
+    pub last_ended: DateTime<Utc>,
+    /// Largest initial budget of its sessions, the base of `pnl_percent`.
+    pub budget: f64,
+    pub realized_pnl: f64,
+    pub unrealized_pnl: f64,
+    pub total_pnl: f64,
+    pub pnl_percent: f64,
+    pub total_orders: u64,
+}
+
+/// Merge `sessions` (oldest first) into one report.
+///
+/// Sessions add up, except that a resumed session replaces the previous
+/// session of the same trader and mode: its state, and so its totals, carry
+/// on from there.
+pub fn aggregate(sessions: &[Session]) -> AggregateReport {
+    // (trader, mode) → results of its chains of resumed sessions
+    let mut chains: BTreeMap<(&str, &str), Vec<&Session>> = BTreeMap::new();
+    let mut by_end: Vec<&Session> = sessions.iter().collect();
+    by_end.sort_by_key(|s| s.ended_at);
+
+    let mut equity_curve = Vec::with_capacity(sessions.len());
+    for session in by_end {
+        let results = chains
+            .entry((session.trader.as_str(), session.mode.as_str()))
+            .or_default();
+        if session.resumed && !results.is_empty() {
+            results.pop();
+        }
+        results.push(session);
+        let total_pnl = chains.values().flatten().map(|s| s.total_pnl).sum();
+        equity_curve.push(EquityPoint {
+            time: session.ended_at,
+            session: session.name.clone(),
+            trader: session.trader.clone(),
+            total_pnl,
+        });
+    }
+
+    let mut traders: Vec<TraderPerformance> = chains
+        .iter()
+        .map(|(&(trader, mode), results)| {
+            let own = || {
+                sessions
+                    .iter()
+                    .filter(|s| s.trader == trader && s.mode == mode)
+            };
+            let budget = own().map(|s| s.initial_budget).fold(0.0, f64::max);
+            let total_pnl = results.iter().map(|s| s.total_pnl).sum();
+            TraderPerformance {
+                trader: trader.to_string(),
+                mode: mode.to_string(),
+                sessions: own().count(),
+                first_started: own().map(|s| s.started_at).min().unwrap_or_default(),
+                last_ended: own().map(|s| s.ended_at).max().unwrap_or_default(),
+                budget,
+                realized_pnl: results.iter().map(|s| s.realized_pnl).sum(),
+                unrealized_pnl: results.iter().map(|s| s.unrealized_pnl).sum(),
+                total_pnl,
+                pnl_percent: if budget > 0.0 {
+                    total_pnl / budget * 100.0
+                } else {
+                    0.0
+                },
+                total_orders: results.iter().map(|s| s.total_orders).sum(),
+            }
+        })
+        .collect();
+    traders.sort_by(|a, b| b.total_pnl.total_cmp(&a.total_pnl));
+
+    AggregateReport {
+        sessions: sessions.len(),
+        total_pnl: traders.iter().map(|t| t.total_pnl).sum(),
+        equity_curve,
+        traders,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::artifacts::{RunInfo, write_bundle};
+    use crate::state::TradingState;
+    use rust_decimal_macros::dec;
+
+    fn at(hour: u32) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(&format!("2026-01-01T{hour:02}:00:00Z"))
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    #[test]
+    fn merges_sessions_and_chains_resumed_ones() {
+        let dir = std::env::temp_dir().join(format!("copytrade-aggregate-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let mut state = TradingState::new(dec!(100));
+        state.realized_pnl = dec!(5);
+        let summary = state.exit_summary(&Default::default());
+        let run = RunInfo::new("dry-run", "0xa", at(1), false);
+        let config = toml::from_str("[account]\nprivate_key = \"0x1\"").unwrap();
+        write_bundle(&dir, &run, &summary, &config).unwrap();
+        std::fs::create_dir_all(dir.join("notes")).unwrap();
+
+        let loaded = load_sessions(&dir).unwrap();
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(
+            (loaded[0].trader.as_str(), loaded[0].total_pnl),
+            ("0xa", 5.0)
+        );
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let session = |name: &str, trader: &str, hour, resumed, total_pnl| Session {
+            name: name.to_string(),
+            trader: trader.to_string(),
+            started_at: at(hour),
+            ended_at: at(hour + 1),
+            resumed,
+            total_pnl,
+            realized_pnl: total_pnl,
+            total_orders: 1,
+            ..loaded[0].clone()
+        };
+        let sessions = [
+            session("s1", "0xa", 1, false, 5.0),
+            session("s2", "0xb", 2, false, -2.0),
+            // Continues s1's state: its 8.0 includes s1's 5.0
+            session("s3", "0xa", 3, true, 8.0),
+            session("s4", "0xa", 5, false, 1.0),
+        ];
+        let report = aggregate(&sessions);
+        let curve: Vec<f64> = report.equity_curve.iter().map(|p| p.total_pnl).collect();
+        assert_eq!(curve, vec![5.0, 3.0, 6.0, 7.0]);
+        assert_eq!(report.total_pnl, 7.0);
+        let a = &report.traders[0];
+        assert_eq!(
+            (a.trader.as_str(), a.sessions, a.total_pnl),
+            ("0xa", 3, 9.0)
+        );
+        assert_eq!((a.total_orders, a.pnl_percent), (2, 9.0));
+        assert_eq!(a.last_ended, at(6));
+        assert_eq!(report.traders[1].total_pnl, -2.0);
+    }
+}
@@ -1,20 +1,75 @@
 use std::collections::HashMap;
+use std::sync::Mutex;
 
-use anyhow::Result;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use polymarket_client_sdk::auth::state::State;
+use polymarket_client_sdk::clob::Client as ClobClient;
+use polymarket_client_sdk::clob::types::TimeRange;
+use polymarket_client_sdk::clob::types::request::{OrderBookSummaryRequest, PriceHistoryRequest};
 use polymarket_client_sdk::data::Client;
-use polymarket_client_sdk::data::types::request::{PositionsRequest, TradesRequest};
-use polymarket_client_sdk::data::types::response::{Position, Trade};
+use polymarket_client_sdk::data::types::MarketFilter;
+use polymarket_client_sdk::data::types::request::{
+    ActivityRequest, PositionsRequest, TradesRequest,
+};
+use polymarket_client_sdk::data::types::response::{Activity, Position, Trade};
 use polymarket_client_sdk::gamma::Client as GammaClient;
-use polymarket_client_sdk::gamma::types::request::MarketsRequest;
+use polymarket_client_sdk::gamma::types::request::{EventBySlugRequest, MarketsRequest};
 use polymarket_client_sdk::types::Address;
 use rust_decimal::Decimal;
+use rust_decimal::prelude::ToPrimitive;
 use tracing::{debug, warn};
 
+use crate::config::RetryOperation;
+use crate::depth::OrderBook;
+use crate::market_rules::MarketRules;
+use crate::{ratelimit, retry};
+
+/// Count one request to a data or gamma API `endpoint` and send it, retrying
+/// under the `[retry.fetch]` policy.
+async fn fetch<T, Fut>(
+    endpoint: &'static str,
+    mut send: impl FnMut() -> Fut,
+) -> polymarket_client_sdk::Result<T>
+where
+    Fut: Future<Output = polymarket_client_sdk::Result<T>>,
+{
+    retry::with_retry(RetryOperation::Fetch, endpoint, || {
+        ratelimit::record(endpoint);
+        send()
+    })
+    .await
+}
+
+/// Like `fetch`, for a CLOB `endpoint`: each attempt waits for the CLOB rate
+/// limiter.
+async fn fetch_clob<T, Fut>(
+    endpoint: &'static str,
+    mut send: impl FnMut() -> Fut,
+) -> polymarket_client_sdk::Result<T>
+where
+    Fut: Future<Output = polymarket_client_sdk::Result<T>>,
+{
+    retry::with_retry(RetryOperation::Fetch, endpoint, || {
+        let request = send();
+        async move {
+            ratelimit::acquire_clob(endpoint).await;
+            request.await
+        }
+    })
+    .await
+}
+
 /// Fetch all active (unresolved) positions for the given trader address.
 ///
 /// Paginates through all positions and filters to only include those with
 /// `current_value > 0` and `0 < cur_price < 1` (excluding resolved markets).
-pub async fn fetch_active_positions(client: &Client, addr: Address) -> Result<Vec<Position>> {
+/// `filter` restricts the request to specific markets or events server-side.
+pub async fn fetch_active_positions(
+    client: &Client,
+    addr: Address,
+    filter: Option<&MarketFilter>,
+) -> Result<Vec<Position>> {
     let mut all = Vec::new();
     let mut offset: i32 = 0;
     let page_size: i32 = 100;
@@ -22,10 +77,11 @@ pub async fn fetch_active_positions(client: &Client, addr: Address) -> Result<Ve
     loop {
         let req = PositionsRequest::builder()
             .user(addr)
+            .maybe_filter(filter.cloned())
             .limit(page_size)?
             .offset(offset)?
             .build();
-        let page = client.positions(&req).await?;
+        let page = fetch("data:/positions", || client.positions(&req)).await?;
         let count = page.len() as i32;
 
         for pos in page {
@@ -47,21 +103,265 @@ pub async fn fetch_active_positions(client: &Client, addr: Address) -> Result<Ve
     Ok(all)
 }
 
-/// Fetch the most recent trades for the given trader address.
+/// Fetch the most recent trades for the given trader address, optionally
+/// restricted to specific markets or events.
 pub async fn fetch_recent_trades(
     client: &Client,
     addr: Address,
     limit: i32,
+    filter: Option<&MarketFilter>,
 ) -> Result<Vec<Trade>> {
     let req = TradesRequest::builder()
         .user(addr)
+        .maybe_filter(filter.cloned())
         .limit(limit)?
         .build();
-    let trades = client.trades(&req).await?;
+    let trades = fetch("data:/trades", || client.trades(&req)).await?;
     debug!("Fetched {} recent trades", trades.len());
     Ok(trades)
 }
 
+/// Fetch the trader's most recent `/activity` entries: trades, but also
+/// splits, merges, redemptions and conversions, which change positions
+/// without showing up on `/trades`.
+pub async fn fetch_recent_activity(
+    client: &Client,
+    addr: Address,
+    limit: i32,
+    filter: Option<&MarketFilter>,
+) -> Result<Vec<Activity>> {
+    let req = ActivityRequest::builder()
+        .user(addr)
+        .maybe_filter(filter.cloned())
+        .limit(limit)?
+        .build();
+    let activity = fetch("data:/activity", || client.activity(&req)).await?;
+    debug!("Fetched {} recent activity entries", activity.len());
+    Ok(activity)
+}
+
+/// Highest `offset` the data API accepts on `/trades`.
+const TRADES_MAX_OFFSET: i32 = 10_000;
+
+/// Fetch the trader's trade history back to `since` (Unix seconds), newest first.
+///
+/// Includes maker fills. The data API stops paginating at offset 10000, so for
+/// very active traders the history may end after `since`; the result is
+/// truncated with a warning in that case.
+pub async fn fetch_trade_history(
+    client: &Client,
+    addr: Address,
+    since: i64,
+    filter: Option<&MarketFilter>,
+) -> Result<Vec<Trade>> {
+    let mut all: Vec<Trade> = Vec::new();
+    let mut offset: i32 = 0;
+    let page_size: i32 = 500;
+
+    loop {
+        let req = TradesRequest::builder()
+            .user(addr)
+            .maybe_filter(filter.cloned())
+            .taker_only(false)
+            .limit(page_size)?
+            .offset(offset)?
+            .build();
+        let page = fetch("data:/trades", || client.trades(&req)).await?;
+        let count = page.len() as i32;
+        let reached_since = page.iter().any(|t| t.timestamp < since);
+        all.extend(page.into_iter().filter(|t| t.timestamp >= since));
+
+        if count < page_size || reached_since {
+            break;
+        }
+        if offset + page_size > TRADES_MAX_OFFSET {
+            warn!(
+                "Trade history truncated at {} trades (data API offset limit)",
+                all.len()
+            );
+            break;
+        }
+        offset += page_size;
+    }
+
+    debug!("Fetched {} historical trades", all.len());
+    Ok(all)
+}
+
+/// Fetch the CLOB price history of one token between `start_ts` and `end_ts`
+/// (Unix seconds), as `(timestamp, price)` points in time order.
+///
+/// `fidelity` is the resolution in minutes.
+pub async fn fetch_price_history(
+    clob: &ClobClient,
+    token_id: &str,
+    start_ts: i64,
+    end_ts: i64,
+    fidelity: u32,
+) -> Result<Vec<(i64, f64)>> {
+    let req = PriceHistoryRequest::builder()
+        .market(token_id)
+        .time_range(TimeRange::from_range(start_ts, end_ts))
+        .fidelity(fidelity)
+        .build();
+    let response = fetch_clob("clob:/prices-history", || clob.price_history(&req))
+        .await
+        .with_context(|| format!("failed to fetch price history for token {token_id}"))?;
+    let mut points: Vec<(i64, f64)> = response
+        .history
+        .iter()
+        .filter_map(|point| Some((point.t, point.p.to_f64()?)))
+        .collect();
+    points.sort_by_key(|&(t, _)| t);
+    Ok(points)
+}
+
+/// Fetch the CLOB order book of one token. Works with or without CLOB
+/// credentials, as the book is public.
+pub async fn fetch_order_book<S: State>(clob: &ClobClient<S>, token_id: &str) -> Result<OrderBook> {
+    let request = OrderBookSummaryRequest::builder()
+        .token_id(token_id.to_string())
+        .build();
+    let book = fetch_clob("clob:/book", || clob.order_book(&request))
+        .await
+        .with_context(|| format!("failed to fetch the order book for token {token_id}"))?;
+    let levels = |side: &[polymarket_client_sdk::clob::types::response::OrderSummary]| {
+        side.iter()
+            .filter_map(|l| Some((l.price.to_f64()?, l.size.to_f64()?)))
+            .collect()
+    };
+    Ok(OrderBook::new(
+        levels(&book.asks),
+        levels(&book.bids),
+        book.tick_size.as_decimal().to_f64().unwrap_or(0.01),
+    ))
+}
+
+/// Fetch the price increment of one CLOB token's market (cached per token by
+/// the SDK).
+pub async fn fetch_tick_size<S: State>(clob: &ClobClient<S>, token_id: &str) -> Result<f64> {
+    let tick = fetch_clob("clob:/tick-size", || clob.tick_size(token_id))
+        .await
+        .with_context(|| format!("failed to fetch the tick size for token {token_id}"))?;
+    Ok(tick.minimum_tick_size.as_decimal().to_f64().unwrap_or(0.01))
+}
+
+/// Fetch the taker fee rate of one CLOB token, in bps (cached per token by the
+/// SDK).
+pub async fn fetch_fee_rate_bps<S: State>(clob: &ClobClient<S>, token_id: &str) -> Result<u32> {
+    let rate = fetch_clob("clob:/fee-rate", || clob.fee_rate_bps(token_id))
+        .await
+        .with_context(|| format!("failed to fetch the fee rate for token {token_id}"))?;
+    Ok(rate.base_fee)
+}
+
+/// Fetch the trading rules of one CLOB token: tick size and neg-risk flag from
+/// the SDK (which caches them per token and builds orders from them) and the
+/// minimum order size from the order book.
+pub async fn fetch_market_rules<S: State>(
+    clob: &ClobClient<S>,
+    token_id: &str,
+) -> Result<MarketRules> {
+    let tick_size = fetch_tick_size(clob, token_id).await?;
+    let neg_risk = fetch_clob("clob:/neg-risk", || clob.neg_risk(token_id))
+        .await
+        .with_context(|| format!("failed to fetch the neg-risk flag for token {token_id}"))?;
+    let request = OrderBookSummaryRequest::builder()
+        .token_id(token_id.to_string())
+        .build();
+    let book = fetch_clob("clob:/book", || clob.order_book(&request))
+        .await
+        .with_context(|| format!("failed to fetch the order book for token {token_id}"))?;
+    Ok(MarketRules {
+        tick_size,
+        neg_risk: neg_risk.neg_risk,
+        min_order_size: book.min_order_size.to_f64().unwrap_or(0.0),
+    })
+}
+
+/// Resolve event slugs to gamma event IDs, for use in a `MarketFilter::EventIds`.
+///
+/// The data API filters by event ID, while event slugs are what appears in
+/// Polymarket URLs and in `Position::event_slug`.
+pub async fn resolve_event_ids(gamma: &GammaClient, slugs: &[String]) -> Result<Vec<String>> {
+    let mut ids = Vec::with_capacity(slugs.len());
+    for slug in slugs {
+        let req = EventBySlugRequest::builder().slug(slug.clone()).build();
+        let event = fetch("gamma:/events", || gamma.event_by_slug(&req))
+            .await
+            .with_context(|| format!("failed to look up event '{slug}'"))?;
+        debug!("Resolved event slug {slug} → {}", event.id);
+        ids.push(event.id);
+    }
+    Ok(ids)
+}
+
+/// Categories of a gamma event: its tag slugs and labels plus its category,
+/// lowercased.
+pub async fn fetch_event_categories(gamma: &GammaClient, slug: &str) -> Result<Vec<String>> {
+    let req = EventBySlugRequest::builder().slug(slug).build();
+    let event = fetch("gamma:/events", || gamma.event_by_slug(&req))
+        .await
+        .with_context(|| format!("failed to look up event '{slug}'"))?;
+    let tags = event.tags.unwrap_or_default();
+    let mut categories: Vec<String> = tags
+        .into_iter()
+        .flat_map(|tag| [tag.slug, tag.label])
+        .chain([event.category])
+        .flatten()
+        .map(|c| c.to_lowercase())
+        .collect();
+    categories.sort();
+    categories.dedup();
+    Ok(categories)
+}
+
+/// Gamma categories of events by slug (see `fetch_event_categories`), looked up
+/// once per event for the session.
+#[derive(Debug)]
+pub struct EventCategories {
+    gamma: GammaClient,
+    cache: Mutex<HashMap<String, Vec<String>>>,
+}
+
+impl EventCategories {
+    pub fn new(gamma: GammaClient) -> Self {
+        Self {
+            gamma,
+            cache: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Categories of each of `slugs`. Events whose lookup fails are left out,
+    /// and tried again on the next call.
+    pub async fn lookup(&self, slugs: &[String]) -> HashMap<String, Vec<String>> {
+        let mut missing: Vec<String> = {
+            let cache = self.cache.lock().unwrap();
+            slugs
+                .iter()
+                .filter(|slug| !slug.is_empty() && !cache.contains_key(*slug))
+                .cloned()
+                .collect()
+        };
+        missing.sort();
+        missing.dedup();
+        for slug in missing {
+            match fetch_event_categories(&self.gamma, &slug).await {
+                Ok(categories) => {
+                    debug!("Event {slug} categories: {}", categories.join(", "));
+                    self.cache.lock().unwrap().insert(slug, categories);
+                }
+                Err(e) => warn!("Category lookup failed: {e:#}"),
+            }
+        }
+        let cache = self.cache.lock().unwrap();
+        slugs
+            .iter()
+            .filter_map(|slug| Some((slug.clone(), cache.get(slug)?.clone())))
+            .collect()
+    }
+}
+
 /// Look up current prices for the given CLOB token IDs via the gamma API.
 ///
 /// Returns a map of `token_id → price`. Tokens not found are omitted.
@@ -81,7 +381,7 @@ pub async fn fetch_gamma_prices(
             .clob_token_ids(vec![token_id.clone()])
             .build();
 
-        match gamma.markets(&req).await {
+        match fetch("gamma:/markets", || gamma.markets(&req)).await {
             Ok(markets) => {
                 for market in &markets {
                     if let Some(price) =
@@ -101,6 +401,31 @@ pub async fn fetch_gamma_prices(
     Ok(prices)
 }
 
+/// Look up market end dates for the given CLOB token IDs via the gamma API.
+///
+/// Returns a map of `token_id → end date`. Tokens not found (or without an end
+/// date) are omitted; lookup errors are logged and skipped.
+pub async fn fetch_gamma_end_dates(
+    gamma: &GammaClient,
+    token_ids: &[String],
+) -> HashMap<String, DateTime<Utc>> {
+    let mut end_dates = HashMap::new();
+    for token_id in token_ids {
+        let req = MarketsRequest::builder()
+            .clob_token_ids(vec![token_id.clone()])
+            .build();
+        match fetch("gamma:/markets", || gamma.markets(&req)).await {
+            Ok(markets) => {
+                if let Some(end) = markets.iter().find_map(|m| m.end_date) {
+                    end_dates.insert(token_id.clone(), end);
+                }
+            }
+            Err(e) => warn!("Gamma end date lookup failed for token {token_id}: {e}"),
+        }
+    }
+    end_dates
+}
+
 /// Build a comprehensive price map for exit pricing.
 ///
 /// 1. Starts from `active_prices` (built from active positions).
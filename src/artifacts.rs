@@ -0,0 +1,221 @@
+use std::borrow::Cow;
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use crate::config::AppConfig;
+use crate::types::{ExitSummary, HoldingSummary, LedgerEntry, OrderSide};
+
+/// Build and session facts written to `version.json`.
+#[derive(Debug, Clone, Serialize)]
+pub struct RunInfo {
+    pub name: &'static str,
+    pub version: &'static str,
+    /// `"dry-run"` or `"live"`.
+    pub mode: String,
+    pub trader: String,
+    pub started_at: DateTime<Utc>,
+    pub ended_at: DateTime<Utc>,
+    /// Whether the session restored its state from a `--wal`.
+    pub resumed: bool,
+}
+
+impl RunInfo {
+    /// Info for a session of `mode` copying `trader`, ending now.
+    pub fn new(mode: &str, trader: &str, started_at: DateTime<Utc>, resumed: bool) -> Self {
+        Self {
+            name: env!("CARGO_PKG_NAME"),
+            version: env!("CARGO_PKG_VERSION"),
+            mode: mode.to_string(),
+            trader: trader.to_string(),
+            started_at,
+            ended_at: Utc::now(),
+            resumed,
+        }
+    }
+}
+
+/// The bundle directory under `dir` of a session started at `started_at`,
+/// created if missing.
+pub fn bundle_dir(dir: &Path, started_at: DateTime<Utc>) -> Result<PathBuf> {
+    let bundle = dir.join(started_at.format("%Y%m%dT%H%M%SZ").to_string());
+    std::fs::create_dir_all(&bundle)
+        .with_context(|| format!("failed to create {}", bundle.display()))?;
+    Ok(bundle)
+}
+
+/// Write the end-of-run bundle (`--artifacts`) to the session's directory
+/// under `dir` (`bundle_dir`), and return its path:
+///
+/// - `exit_summary.json` — the summary printed at shutdown
+/// - `holdings.csv` — open positions at exit
+/// - `config.toml` — the effective config, redacted (`AppConfig::redacted`)
+/// - `version.json` — `RunInfo`
+///
+/// `ledger.csv`, every fill applied during the session, is written beside them
+/// as fills are applied (`LedgerWriter`).
+pub fn write_bundle(
+    dir: &Path,
+    run: &RunInfo,
+    summary: &ExitSummary,
+    config: &AppConfig,
+) -> Result<PathBuf> {
+    let bundle = bundle_dir(dir, run.started_at)?;
+
+    let summary_json =
+        serde_json::to_string_pretty(summary).context("failed to serialize exit summary")?;
+    write(&bundle.join("exit_summary.json"), &summary_json)?;
+    write(
+        &bundle.join("holdings.csv"),
+        &holdings_csv(&summary.holdings),
+    )?;
+    let config_toml =
+        toml::to_string_pretty(&config.redacted()).context("failed to serialize config")?;
+    write(&bundle.join("config.toml"), &config_toml)?;
+    let version_json = serde_json::to_string_pretty(run).context("failed to serialize run info")?;
+    write(&bundle.join("version.json"), &version_json)?;
+    Ok(bundle)
+}
+
+fn write(path: &Path, contents: &str) -> Result<()> {
+    std::fs::write(path, contents).with_context(|| format!("failed to write {}", path.display()))
+}
+
+/// Holdings at exit, one row per position (tags joined with `;`).
+pub fn holdings_csv(holdings: &[HoldingSummary]) -> String {
+    let mut csv = String::from(
+        "asset,title,outcome,shares,avg_cost,cur_price,current_value,unrealized_pnl,tags\n",
+    );
+    for h in holdings {
+        csv.push_str(&format!(
+            "{},{},{},{},{},{},{},{},{}\n",
+            csv_field(&h.asset),
+            csv_field(&h.title),
+            csv_field(&h.outcome),
+            h.shares,
+            h.avg_cost,
+            h.cur_price,
+            h.current_value,
+            h.unrealized_pnl,
+            csv_field(&h.tags.join(";")),
+        ));
+    }
+    csv
+}
+
+/// The fill ledger CSV, appended to as fills are applied so it needn't be held
+/// in memory. One row per fill, in the order applied (tags joined with `;`).
+pub struct LedgerWriter {
+    file: File,
+}
+
+impl LedgerWriter {
+    /// Create (or truncate) the ledger at `path` and write its header.
+    pub fn create(path: &Path) -> Result<Self> {
+        let mut file =
+            File::create(path).with_context(|| format!("failed to create {}", path.display()))?;
+        file.write_all(
+            b"timestamp,asset,title,outcome,side,shares,price,cost_usd,realized_pnl,tags\n",
+        )
+        .context("failed to write ledger header")?;
+        Ok(Self { file })
+    }
+
+    pub fn append(&mut self, entry: &LedgerEntry) -> Result<()> {
+        self.file
+            .write_all(ledger_row(entry).as_bytes())
+            .context("failed to append ledger entry")
+    }
+}
+
+fn ledger_row(entry: &LedgerEntry) -> String {
+    let side = match entry.side {
+        OrderSide::Buy => "BUY",
+        OrderSide::Sell => "SELL",
+    };
+    format!(
+        "{},{},{},{},{side},{},{},{},{},{}\n",
+        entry.timestamp,
+        csv_field(&entry.asset),
+        csv_field(&entry.title),
+        csv_field(&entry.outcome),
+        entry.shares,
+        entry.price,
+        entry.cost_usd,
+        entry.realized_pnl,
+        csv_field(&entry.tags.join(";")),
+    )
+}
+
+/// Quote a CSV field if it holds a comma, quote, or line break.
+fn csv_field(value: &str) -> Cow<'_, str> {
+    if value.contains([',', '"', '\n', '\r']) {
+        Cow::Owned(format!("\"{}\"", value.replace('"', "\"\"")))
+    } else {
+        Cow::Borrowed(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal::Decimal;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn bundle_redacts_config_and_quotes_csv() {
+        let dir = std::env::temp_dir().join(format!("copytrade-artifacts-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let mut config: AppConfig =
+            toml::from_str("[account]\nprivate_key = \"0xsecret\"").unwrap();
+        config.endpoints.polygon_ws = Some("wss://polygon.example.com/v2/apikey".to_string());
+        let started_at = DateTime::parse_from_rfc3339("2026-01-01T00:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let mut ledger =
+            LedgerWriter::create(&bundle_dir(&dir, started_at).unwrap().join("ledger.csv"))
+                .unwrap();
+        ledger
+            .append(&LedgerEntry {
+                timestamp: "2026-01-01T00:00:00+00:00".to_string(),
+                asset: "a1".to_string(),
+                title: "Will \"X\" win, or not?".to_string(),
+                outcome: "Yes".to_string(),
+                side: OrderSide::Buy,
+                shares: dec!(10),
+                price: dec!(0.5),
+                cost_usd: dec!(5),
+                realized_pnl: Decimal::ZERO,
+                tags: vec!["NBA".to_string(), "Sports".to_string()],
+            })
+            .unwrap();
+        let state = crate::state::TradingState::new(dec!(100));
+        let summary = state.exit_summary(&Default::default());
+        let run = RunInfo::new("dry-run", "0xtrader", started_at, false);
+
+        let bundle = write_bundle(&dir, &run, &summary, &config).unwrap();
+        assert!(bundle.ends_with("20260101T000000Z"));
+        let config_toml = std::fs::read_to_string(bundle.join("config.toml")).unwrap();
+        assert!(!config_toml.contains("0xsecret"));
+        assert!(!config_toml.contains("apikey"));
+        assert!(config_toml.contains("wss://polygon.example.com/<redacted>"));
+        let ledger = std::fs::read_to_string(bundle.join("ledger.csv")).unwrap();
+        assert!(
+            ledger.contains(",a1,\"Will \"\"X\"\" win, or not?\",Yes,BUY,10,0.5,5,0,NBA;Sports\n")
+        );
+        for file in [
+            "exit_summary.json",
+            "holdings.csv",
+            "ledger.csv",
+            "version.json",
+        ] {
+            assert!(bundle.join(file).exists(), "{file}");
+        }
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -2,13 +2,15 @@ use std::str::FromStr;
 
 use anyhow::{Context, Result};
 use polymarket_client_sdk::auth::state::Authenticated;
-use polymarket_client_sdk::auth::{LocalSigner, Normal, Signer};
+use polymarket_client_sdk::auth::{Credentials, LocalSigner, Normal, Signer};
 use polymarket_client_sdk::clob::types::SignatureType;
 use polymarket_client_sdk::clob::{Client, Config};
 use polymarket_client_sdk::types::Address;
 use polymarket_client_sdk::{POLYGON, derive_safe_wallet};
 
-use crate::CLOB_API_BASE;
+use crate::fills::FillBook;
+use crate::market_rules::MarketRulesCache;
+use crate::secret::Secret;
 
 /// Concrete signer type produced by `LocalSigner::from_str`.
 pub type PrivateKeySigner = LocalSigner<k256::ecdsa::SigningKey>;
@@ -19,11 +21,17 @@ pub struct ClobContext {
     pub signer: PrivateKeySigner,
     pub eoa: Address,
     pub safe: Address,
+    /// L2 API credentials, also used to authenticate the user channel.
+    pub credentials: Credentials,
+    /// Order and fill events from the CLOB user channel, once started.
+    pub fills: Option<FillBook>,
+    /// Tick size, neg-risk flag, and minimum size of the tokens traded so far.
+    pub market_rules: MarketRulesCache,
 }
 
-/// Authenticate with the CLOB API using a hex-encoded private key.
-pub async fn authenticate(private_key: &str) -> Result<ClobContext> {
-    let signer = PrivateKeySigner::from_str(private_key)
+/// Authenticate with the CLOB API at `clob_host` using a hex-encoded private key.
+pub async fn authenticate(private_key: &Secret<String>, clob_host: &str) -> Result<ClobContext> {
+    let signer = PrivateKeySigner::from_str(private_key.expose())
         .context("invalid private key")?
         .with_chain_id(Some(POLYGON));
 
@@ -31,8 +39,14 @@ pub async fn authenticate(private_key: &str) -> Result<ClobContext> {
     let safe = derive_safe_wallet(eoa, POLYGON).context("failed to derive Safe address")?;
 
     let config = Config::builder().use_server_time(true).build();
-    let client = Client::new(CLOB_API_BASE, config)?
+    let client = Client::new(clob_host, config)?;
+    let credentials = client
+        .create_or_derive_api_key(&signer, None)
+        .await
+        .context("failed to derive CLOB API credentials")?;
+    let client = client
         .authentication_builder(&signer)
+        .credentials(credentials.clone())
         .signature_type(SignatureType::GnosisSafe)
         .authenticate()
         .await
@@ -43,5 +57,8 @@ pub async fn authenticate(private_key: &str) -> Result<ClobContext> {
         signer,
         eoa,
         safe,
+        credentials,
+        fills: None,
+        market_rules: MarketRulesCache::default(),
     })
 }
@@ -0,0 +1,179 @@
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+
+use crate::types::{AlertEvent, AlertKind, AlertSeverity};
+
+/// Adaptive poll interval: stretches on API errors, decays back on success.
+///
+/// Every failed cycle multiplies the interval by `multiplier` (twice for rate
+/// limit responses), capped at `max`. Every successful cycle halves the excess
+/// over `base`, snapping back once within a second of it.
+#[derive(Debug, Clone)]
+pub struct PollScheduler {
+    base: Duration,
+    max: Duration,
+    multiplier: f64,
+    current: Duration,
+}
+
+impl PollScheduler {
+    pub fn new(base: Duration, max: Duration, multiplier: f64) -> Self {
+        Self {
+            base,
+            max: max.max(base),
+            multiplier: multiplier.max(1.0),
+            current: base,
+        }
+    }
+
+    /// Interval to wait before the next cycle.
+    pub fn interval(&self) -> Duration {
+        self.current
+    }
+
+    /// Whether the interval is currently stretched above the base.
+    pub fn is_backing_off(&self) -> bool {
+        self.current > self.base
+    }
+
+    /// Record a successful cycle and return the new interval.
+    pub fn on_success(&mut self) -> Duration {
+        let excess = self.current.saturating_sub(self.base) / 2;
+        self.current = if excess < Duration::from_secs(1) {
+            self.base
+        } else {
+            self.base + excess
+        };
+        self.current
+    }
+
+    /// Record a failed cycle and return the new interval.
+    pub fn on_error(&mut self, rate_limited: bool) -> Duration {
+        let factor = if rate_limited {
+            self.multiplier * self.multiplier
+        } else {
+            self.multiplier
+        };
+        self.current = self.current.mul_f64(factor).min(self.max);
+        self.current
+    }
+}
+
+/// Error budget for poll cycles: escalates repeated failures from log lines to
+/// alerts.
+///
+/// After `threshold` consecutive failed cycles it raises a critical alert, and
+/// again every `threshold` failures after that while they continue. The first
+/// success after an alert raises an info alert that polling recovered.
+#[derive(Debug, Clone)]
+pub struct ErrorBudget {
+    threshold: u32,
+    consecutive: u32,
+}
+
+impl ErrorBudget {
+    /// A `threshold` of 0 never alerts.
+    pub fn new(threshold: u32) -> Self {
+        Self {
+            threshold,
+            consecutive: 0,
+        }
+    }
+
+    fn alerting(&self) -> bool {
+        self.threshold > 0 && self.consecutive >= self.threshold
+    }
+
+    /// Record a failed cycle with error `err`.
+    pub fn on_error(&mut self, err: &str, now: DateTime<Utc>) -> Option<AlertEvent> {
+        self.consecutive += 1;
+        if !self.alerting() || !self.consecutive.is_multiple_of(self.threshold) {
+            return None;
+        }
+        Some(AlertEvent {
+            timestamp: now.to_rfc3339(),
+            severity: AlertSeverity::Critical,
+            kind: AlertKind::PollFailures,
+            message: format!(
+                "{} consecutive poll cycles failed, last: {err}",
+                self.consecutive
+            ),
+        })
+    }
+
+    /// Record a successful cycle.
+    pub fn on_success(&mut self, now: DateTime<Utc>) -> Option<AlertEvent> {
+        let alerted = self.alerting();
+        let failures = std::mem::take(&mut self.consecutive);
+        alerted.then(|| AlertEvent {
+            timestamp: now.to_rfc3339(),
+            severity: AlertSeverity::Info,
+            kind: AlertKind::PollFailures,
+            message: format!("Poll cycles recovered after {failures} consecutive failures"),
+        })
+    }
+}
+
+/// Whether an error message looks like an HTTP 429 rate limit response.
+pub fn is_rate_limited(err: &str) -> bool {
+    let lower = err.to_lowercase();
+    crate::retry::status_code(&lower).is_some_and(|code| code.as_u16() == 429)
+        || lower.contains("too many requests")
+        || lower.contains("rate limit")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stretches_on_errors_and_decays_on_success() {
+        let mut sched = PollScheduler::new(Duration::from_secs(10), Duration::from_secs(120), 2.0);
+        assert_eq!(sched.on_error(false), Duration::from_secs(20));
+        assert_eq!(sched.on_error(true), Duration::from_secs(80));
+        assert_eq!(sched.on_error(false), Duration::from_secs(120)); // capped
+        assert!(sched.is_backing_off());
+
+        assert_eq!(sched.on_success(), Duration::from_secs(65));
+        assert_eq!(sched.on_success(), Duration::from_millis(37_500));
+        sched.on_success();
+        sched.on_success();
+        sched.on_success();
+        sched.on_success();
+        assert_eq!(sched.on_success(), Duration::from_secs(10));
+        assert!(!sched.is_backing_off());
+    }
+
+    #[test]
+    fn error_budget_escalates_repeated_failures() {
+        let mut budget = ErrorBudget::new(3);
+        let now = Utc::now();
+        assert!(budget.on_error("timeout", now).is_none());
+        assert!(budget.on_error("timeout", now).is_none());
+        // A success in between resets the count without an alert
+        assert!(budget.on_success(now).is_none());
+        for _ in 0..2 {
+            assert!(budget.on_error("timeout", now).is_none());
+        }
+        let alert = budget.on_error("503", now).unwrap();
+        assert_eq!(alert.severity, AlertSeverity::Critical);
+        assert!(alert.message.starts_with("3 consecutive"));
+        assert!(budget.on_error("503", now).is_none());
+        assert!(budget.on_error("503", now).is_none());
+        assert!(budget.on_error("503", now).is_some());
+        let recovered = budget.on_success(now).unwrap();
+        assert_eq!(recovered.severity, AlertSeverity::Info);
+        assert!(recovered.message.contains("after 6"));
+
+        let mut off = ErrorBudget::new(0);
+        assert!((0..10).all(|_| off.on_error("x", now).is_none()));
+    }
+
+    #[test]
+    fn detects_rate_limit_errors() {
+        assert!(is_rate_limited("HTTP status 429 Too Many Requests"));
+        assert!(is_rate_limited("rate limit exceeded"));
+        assert!(!is_rate_limited("503 Service Unavailable"));
+    }
+}
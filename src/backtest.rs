@@ -0,0 +1,504 @@
+//! Replay of a trader's trade history through the rebalancing pipeline.
+//!
+//! The trader's portfolio is rebuilt trade by trade from their history and
+//! marked with historical CLOB prices. After every burst of trades (trades
+//! sharing a timestamp) the copy portfolio is rebalanced with `plan_rebalance`,
+//! exactly as the polling loop would, and the planned orders are filled through
+//! a `FillModel`. Everything here is offline: `api::fetch_trade_history` and
+//! `api::fetch_price_history` supply the inputs.
+//!
+//! Limitations: redemptions are inferred from prices settling at 0 or 1, and
+//! `[engine.end_scaling]` measures time to resolution from the wall clock, not
+//! the replayed time.
+
+use std::collections::HashMap;
+
+use polymarket_client_sdk::data::types::Side;
+use polymarket_client_sdk::data::types::response::{Position, Trade};
+use rust_decimal::Decimal;
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal_macros::dec;
+use serde::Serialize;
+use serde_json::json;
+
+use crate::config::EngineParams;
+use crate::engine::plan_rebalance;
+use crate::money;
+use crate::state::TradingState;
+use crate::types::{ExitSummary, OrderSide, SimulatedOrder};
+use crate::wal::Mutation;
+
+/// Prices within this distance of 0 or 1 count as a resolved market.
+const RESOLVED_EPSILON: f64 = 0.005;
+
+/// Trader holdings below this many shares count as closed.
+const DUST_SHARES: f64 = 0.01;
+
+/// Historical prices per token.
+#[derive(Debug, Clone, Default)]
+pub struct PriceHistory {
+    series: HashMap<String, Vec<(i64, f64)>>,
+}
+
+impl PriceHistory {
+    /// Set the `(timestamp, price)` series of `asset`.
+    pub fn insert(&mut self, asset: impl Into<String>, mut points: Vec<(i64, f64)>) {
+        points.sort_by_key(|&(t, _)| t);
+        self.series.insert(asset.into(), points);
+    }
+
+    /// The last known price of `asset` at or before `ts`.
+    pub fn price_at(&self, asset: &str, ts: i64) -> Option<f64> {
+        let points = self.series.get(asset)?;
+        let idx = points.partition_point(|&(t, _)| t <= ts);
+        idx.checked_sub(1).map(|i| points[i].1)
+    }
+}
+
+/// Snap prices of resolved markets to their payout.
+fn settle(price: f64) -> f64 {
+    if price <= RESOLVED_EPSILON {
+        0.0
+    } else if price >= 1.0 - RESOLVED_EPSILON {
+        1.0
+    } else {
+        price
+    }
+}
+
+/// How planned orders fill.
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct FillModel {
+    /// Seconds between the trader's trade and our fill. Orders fill at the
+    /// historical price at that time rather than the planned price.
+    pub latency_secs: i64,
+    /// Price concession per share: buys pay this much more, sells receive this
+    /// much less.
+    pub slippage: f64,
+    /// Fraction of each order's shares that fills (0.0–1.0).
+    pub fill_ratio: f64,
+}
+
+impl Default for FillModel {
+    /// Instant, complete fills at the historical price.
+    fn default() -> Self {
+        Self {
+            latency_secs: 0,
+            slippage: 0.0,
+            fill_ratio: 1.0,
+        }
+    }
+}
+
+impl FillModel {
+    /// Fill `orders` planned at `ts`, sells first. Buys are capped at `cash`
+    /// plus the proceeds of the sells. Exits of resolved markets (planned at 0
+    /// or 1) are redemptions and fill completely at the payout.
+    fn fill(
+        &self,
+        orders: Vec<SimulatedOrder>,
+        prices: &PriceHistory,
+        ts: i64,
+        mut cash: Decimal,
+    ) -> Vec<SimulatedOrder> {
+        let (sells, buys): (Vec<_>, Vec<_>) =
+            orders.into_iter().partition(|o| o.side == OrderSide::Sell);
+        let mut filled = Vec::new();
+        for mut order in sells.into_iter().chain(buys) {
+            if order.price > Decimal::ZERO && order.price < Decimal::ONE {
+                let price = prices
+                    .price_at(&order.market.asset, ts + self.latency_secs)
+                    .map_or(order.price, money::from_f64);
+                let slippage = money::from_f64(self.slippage);
+                order.price = match order.side {
+                    OrderSide::Buy => price + slippage,
+                    OrderSide::Sell => price - slippage,
+                }
+                .clamp(dec!(0.001), dec!(0.999));
+                order.shares *= money::from_f64(self.fill_ratio);
+            }
+            if order.side == OrderSide::Buy
+                && let Some(affordable) = money::ratio(cash.max(Decimal::ZERO), order.price)
+            {
+                order.shares = order.shares.min(affordable);
+            }
+            order.cost_usd = order.shares * order.price;
+            if order.shares <= Decimal::ZERO {
+                continue;
+            }
+            match order.side {
+                OrderSide::Buy => cash -= order.cost_usd,
+                OrderSide::Sell => cash += order.cost_usd,
+            }
+            filled.push(order);
+        }
+        filled
+    }
+}
+
+/// Backtest parameters.
+#[derive(Debug, Clone)]
+pub struct BacktestConfig {
+    pub budget: f64,
+    /// Fraction of the budget to allocate (0.0–1.0).
+    pub copy_pct: f64,
+    /// Maximum fraction of the running budget per position (0.0–1.0).
+    pub max_trade_pct: f64,
+    pub engine: EngineParams,
+    pub fill: FillModel,
+    /// Start copying at this time (Unix seconds). Earlier trades only build up
+    /// the trader's portfolio, which the first rebalance then replicates.
+    pub start: Option<i64>,
+    /// Ignore trades after this time (Unix seconds).
+    pub end: Option<i64>,
+}
+
+/// Portfolio value after a rebalance.
+#[derive(Debug, Clone, Serialize)]
+pub struct EquityPoint {
+    pub timestamp: i64,
+    /// Cash plus holdings at historical prices.
+    pub equity: f64,
+    pub cash: f64,
+    pub holdings_value: f64,
+}
+
+/// Result of `run`.
+#[derive(Debug, Clone, Serialize)]
+pub struct BacktestReport {
+    pub start: i64,
+    pub end: i64,
+    /// Trades between `start` and `end`.
+    pub trades_replayed: usize,
+    /// Rebalances that placed at least one order.
+    pub rebalances: u64,
+    pub orders: u64,
+    /// Largest peak-to-trough decline of the equity curve, in percent.
+    pub max_drawdown_pct: f64,
+    pub equity_curve: Vec<EquityPoint>,
+    pub summary: ExitSummary,
+}
+
+/// One of the trader's positions, rebuilt from their trades.
+struct TraderHolding {
+    /// Latest trade in the asset, for market metadata.
+    trade: Trade,
+    shares: f64,
+    cost: f64,
+}
+
+/// The trader's portfolio as of the trades applied so far.
+#[derive(Default)]
+struct TraderBook {
+    holdings: HashMap<String, TraderHolding>,
+    last_trade_price: HashMap<String, f64>,
+}
+
+impl TraderBook {
+    fn apply(&mut self, trade: &Trade) {
+        let size = trade.size.to_f64().unwrap_or(0.0);
+        let price = trade.price.to_f64().unwrap_or(0.0);
+        self.last_trade_price.insert(trade.asset.clone(), price);
+        match trade.side {
+            Side::Buy => {
+                let holding =
+                    self.holdings
+                        .entry(trade.asset.clone())
+                        .or_insert_with(|| TraderHolding {
+                            trade: trade.clone(),
+                            shares: 0.0,
+                            cost: 0.0,
+                        });
+                holding.trade = trade.clone();
+                holding.shares += size;
+                holding.cost += size * price;
+            }
+            // Sells of shares acquired outside the history (splits, transfers,
+            // trades before it) have no position to reduce
+            Side::Sell => {
+                if let Some(holding) = self.holdings.get_mut(&trade.asset) {
+                    let sold = size.min(holding.shares);
+                    holding.cost -= holding.cost / holding.shares * sold;
+                    holding.shares -= sold;
+                    if holding.shares < DUST_SHARES {
+                        self.holdings.remove(&trade.asset);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Price of `asset` at `ts`, falling back to the last traded price.
+    fn mark(&self, prices: &PriceHistory, asset: &str, ts: i64) -> Option<f64> {
+        prices
+            .price_at(asset, ts)
+            .or_else(|| self.last_trade_price.get(asset).copied())
+            .map(settle)
+    }
+
+    /// Open positions at `ts`, in the data API's shape. Resolved markets are
+    /// left out, as `api::fetch_active_positions` does.
+    fn positions(&self, prices: &PriceHistory, ts: i64) -> Vec<Position> {
+        let mut positions: Vec<Position> = self
+            .holdings
+            .iter()
+            .filter_map(|(asset, holding)| {
+                let price = self.mark(prices, asset, ts)?;
+                (price > 0.0 && price < 1.0).then(|| position(holding, price))
+            })
+            .collect();
+        positions.sort_by(|a, b| a.asset.cmp(&b.asset));
+        positions
+    }
+}
+
+/// Build an SDK `Position` via JSON deserialization (struct is `#[non_exhaustive]`).
+fn position(holding: &TraderHolding, price: f64) -> Position {
+    let trade = &holding.trade;
+    let value = holding.shares * price;
+    serde_json::from_value(json!({
+        "proxyWallet": trade.proxy_wallet.to_string(),
+        "asset": trade.asset,
+        "conditionId": trade.condition_id.to_string(),
+        "size": holding.shares.to_string(),
+        "avgPrice": (holding.cost / holding.shares).to_string(),
+        "initialValue": holding.cost.to_string(),
+        "currentValue": value.to_string(),
+        "cashPnl": (value - holding.cost).to_string(),
+        "percentPnl": "0",
+        "totalBought": holding.shares.to_string(),
+        "realizedPnl": "0",
+        "percentRealizedPnl": "0",
+        "curPrice": price.to_string(),
+        "redeemable": false,
+        "mergeable": false,
+        "title": trade.title,
+        "slug": trade.slug,
+        "icon": trade.icon,
+        "eventSlug": trade.event_slug,
+        "outcome": trade.outcome,
+        "outcomeIndex": trade.outcome_index,
+        "oppositeOutcome": "",
+        "oppositeAsset": "",
+        "endDate": "",
+        "negativeRisk": false
+    }))
+    .expect("valid Position JSON")
+}
+
+/// Prices of the held assets at `ts`.
+fn held_prices(
+    state: &TradingState,
+    book: &TraderBook,
+    prices: &PriceHistory,
+    ts: i64,
+) -> HashMap<String, f64> {
+    state
+        .holdings
+        .keys()
+        .filter_map(|asset| Some((asset.clone(), book.mark(prices, asset, ts)?)))
+        .collect()
+}
+
+fn equity_point(state: &TradingState, held: &HashMap<String, f64>, ts: i64) -> EquityPoint {
+    let equity = money::to_f64(state.effective_capital(held));
+    EquityPoint {
+        timestamp: ts,
+        equity,
+        cash: money::to_f64(state.budget_remaining),
+        holdings_value: equity - money::to_f64(state.budget_remaining),
+    }
+}
+
+fn max_drawdown_pct(curve: &[EquityPoint]) -> f64 {
+    let mut peak = f64::MIN;
+    let mut max_drawdown: f64 = 0.0;
+    for point in curve {
+        peak = peak.max(point.equity);
+        if peak > 0.0 {
+            max_drawdown = max_drawdown.max((peak - point.equity) / peak * 100.0);
+        }
+    }
+    max_drawdown
+}
+
+/// Replay `trades` (the trader's history, in any order) against `prices`.
+pub fn run(trades: &[Trade], prices: &PriceHistory, config: &BacktestConfig) -> BacktestReport {
+    let mut trades: Vec<&Trade> = trades
+        .iter()
+        .filter(|t| config.end.is_none_or(|end| t.timestamp <= end))
+        .collect();
+    trades.sort_by_key(|t| t.timestamp);
+    let start = config
+        .start
+        .or_else(|| trades.first().map(|t| t.timestamp))
+        .unwrap_or(0);
+    let trader = trades
+        .first()
+        .map(|t| t.proxy_wallet.to_string())
+        .unwrap_or_default();
+    let trader_short_id = &trader[trader.len().saturating_sub(6)..];
+
+    let mut book = TraderBook::default();
+    let mut state = TradingState::new(money::from_f64(config.budget));
+    let mut equity_curve = Vec::new();
+    let mut trades_replayed = 0;
+    let mut rebalances = 0;
+
+    for burst in trades.chunk_by(|a, b| a.timestamp == b.timestamp) {
+        for trade in burst {
+            book.apply(trade);
+        }
+        let ts = burst[0].timestamp;
+        if ts < start {
+            continue;
+        }
+        trades_replayed += burst.len();
+
+        let positions = book.positions(prices, ts);
+        let exit_prices = held_prices(&state, &book, prices, ts);
+        let planned = plan_rebalance(
+            &positions,
+            &state,
+            &exit_prices,
+            config.copy_pct,
+            config.max_trade_pct,
+            trader_short_id,
+            &config.engine,
+        );
+        let orders = config
+            .fill
+            .fill(planned, prices, ts, state.budget_remaining);
+        if !orders.is_empty() {
+            state.commit(Mutation::ApplyOrders { orders });
+            state.total_events += 1;
+            rebalances += 1;
+        }
+
+        let fill_ts = ts + config.fill.latency_secs;
+        let held = held_prices(&state, &book, prices, fill_ts);
+        push_point(&mut equity_curve, equity_point(&state, &held, fill_ts));
+    }
+
+    let end = config
+        .end
+        .or_else(|| trades.last().map(|t| t.timestamp))
+        .unwrap_or(start)
+        .max(start);
+    let final_prices = held_prices(&state, &book, prices, end);
+    push_point(&mut equity_curve, equity_point(&state, &final_prices, end));
+
+    BacktestReport {
+        start,
+        end,
+        trades_replayed,
+        rebalances,
+        orders: state.total_orders,
+        max_drawdown_pct: max_drawdown_pct(&equity_curve),
+        equity_curve,
+        summary: state.exit_summary(&final_prices),
+    }
+}
+
+/// Append `point`, replacing the last one when it has the same timestamp.
+fn push_point(curve: &mut Vec<EquityPoint>, point: EquityPoint) {
+    if curve
+        .last()
+        .is_some_and(|last| last.timestamp == point.timestamp)
+    {
+        curve.pop();
+    }
+    curve.push(point);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testkit::make_trade;
+    use crate::types::MarketPosition;
+
+    fn approx_eq(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-6
+    }
+
+    fn order(asset: &str, side: OrderSide, shares: Decimal, price: Decimal) -> SimulatedOrder {
+        SimulatedOrder {
+            market: MarketPosition {
+                condition_id: "c".to_string(),
+                asset: asset.to_string(),
+                title: String::new(),
+                outcome: "Yes".to_string(),
+                outcome_index: 0,
+                event_slug: String::new(),
+                tags: Vec::new(),
+                opposite_asset: String::new(),
+            },
+            side,
+            shares,
+            price,
+            cost_usd: shares * price,
+        }
+    }
+
+    #[test]
+    fn fill_model_applies_latency_slippage_and_cash() {
+        let mut prices = PriceHistory::default();
+        prices.insert("a", vec![(200, 0.60), (100, 0.50)]);
+        assert_eq!(prices.price_at("a", 99), None);
+        assert_eq!(prices.price_at("a", 150), Some(0.50));
+
+        let model = FillModel {
+            latency_secs: 100,
+            slippage: 0.02,
+            fill_ratio: 0.5,
+        };
+        let orders = vec![
+            order("a", OrderSide::Buy, dec!(100), dec!(0.50)),
+            order("resolved", OrderSide::Sell, dec!(10), Decimal::ONE),
+        ];
+        let filled = model.fill(orders, &prices, 100, dec!(10));
+        // The redemption fills first and completely, adding $10 of cash
+        assert_eq!(filled[0].market.asset, "resolved");
+        assert_eq!(filled[0].cost_usd, dec!(10));
+        // 50 shares at 0.62 would cost $31; capped at the $20 available
+        assert_eq!(filled[1].price, dec!(0.62));
+        assert!(approx_eq(money::to_f64(filled[1].cost_usd), 20.0));
+    }
+
+    #[test]
+    fn replays_trader_history() {
+        let trades = vec![
+            make_trade("a", "c1", "BUY", 100.0, 0.40, 1_000, "0x1"),
+            make_trade("b", "c2", "BUY", 100.0, 0.40, 1_000, "0x2"),
+            make_trade("a", "c1", "SELL", 100.0, 0.80, 3_000, "0x3"),
+        ];
+        let mut prices = PriceHistory::default();
+        prices.insert("a", vec![(1_000, 0.40), (2_000, 0.60), (3_000, 0.80)]);
+        // "b" resolves to 0 after the trader's last trade
+        prices.insert("b", vec![(1_000, 0.40), (4_000, 0.0)]);
+        let config = BacktestConfig {
+            budget: 100.0,
+            copy_pct: 1.0,
+            max_trade_pct: 0.5,
+            engine: EngineParams::default(),
+            fill: FillModel::default(),
+            start: None,
+            end: Some(5_000),
+        };
+
+        let report = run(&trades, &prices, &config);
+        assert_eq!((report.start, report.end), (1_000, 5_000));
+        assert_eq!(report.trades_replayed, 3);
+        assert_eq!(report.rebalances, 2);
+        let equity: Vec<f64> = report.equity_curve.iter().map(|p| p.equity).collect();
+        // $50 into each at 0.40; "a" exits at 0.80 (+$50), "b" expires worthless
+        assert_eq!(equity.len(), 3);
+        assert!(approx_eq(equity[0], 100.0));
+        assert!(approx_eq(equity[1], 150.0));
+        assert!(approx_eq(equity[2], 100.0));
+        assert!(approx_eq(report.max_drawdown_pct, 100.0 / 3.0));
+        assert!(approx_eq(report.summary.realized_pnl, 50.0));
+        assert!(approx_eq(report.summary.unrealized_pnl, -50.0));
+    }
+}
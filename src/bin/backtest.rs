@@ -0,0 +1,202 @@
+//! backtest — replay a trader's history through the rebalancing engine.
+//!
+//! Downloads the trader's trades and the CLOB price history of every traded
+//! token, runs them through `backtest::run`, and prints the report (equity
+//! curve and exit summary) as JSON on stdout. Engine settings and endpoints
+//! come from `config.toml` when it exists.
+
+use std::collections::BTreeSet;
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use chrono::Duration;
+use clap::Parser;
+use polymarket_client_sdk::clob::{Client as ClobClient, Config as ClobConfig};
+use polymarket_client_sdk::data::Client as DataClient;
+use polymarket_client_sdk::types::Address;
+use tracing::{info, warn};
+
+use polymarket_copytrade::api::{fetch_price_history, fetch_trade_history};
+use polymarket_copytrade::backtest::{self, BacktestConfig, FillModel, PriceHistory};
+use polymarket_copytrade::clock;
+use polymarket_copytrade::config::{AppConfig, CONFIG_PATH, EndpointsConfig, EngineParams};
+use polymarket_copytrade::ratelimit;
+
+#[derive(Parser)]
+#[command(
+    name = "backtest",
+    about = "Replay a trader's history through the copytrade engine"
+)]
+struct Args {
+    /// Config file for engine settings and endpoints (defaults apply if missing)
+    #[arg(long, default_value = CONFIG_PATH)]
+    config: PathBuf,
+
+    /// Named config profile (`[profile.<name>]` in config.toml)
+    #[arg(long)]
+    profile: Option<String>,
+
+    /// Trader proxy wallet address (defaults to the profile's `trader_address`)
+    #[arg(long)]
+    trader_address: Option<String>,
+
+    /// Starting budget in USD (defaults to the profile's `budget`)
+    #[arg(long)]
+    budget: Option<f64>,
+
+    /// Percentage of budget to allocate (0-100) (defaults to the profile's `copy_percentage`)
+    #[arg(long)]
+    copy_percentage: Option<f64>,
+
+    /// Maximum percentage of running budget per position (0-100) (defaults to the profile's `max_trade_size`)
+    #[arg(long)]
+    max_trade_size: Option<f64>,
+
+    /// Length of the backtest window, ending now
+    #[arg(long, default_value_t = 30)]
+    days: i64,
+
+    /// Days of history before the window used to rebuild the trader's portfolio,
+    /// which is replicated when the window starts
+    #[arg(long, default_value_t = 30)]
+    warmup_days: i64,
+
+    /// Seconds between the trader's trade and our fill
+    #[arg(long, default_value_t = 5)]
+    latency_secs: i64,
+
+    /// Price concession per share on every fill (e.g. 0.01 = one cent)
+    #[arg(long, default_value_t = 0.0)]
+    slippage: f64,
+
+    /// Fraction of each order that fills (0-1)
+    #[arg(long, default_value_t = 1.0)]
+    fill_ratio: f64,
+
+    /// Price history resolution in minutes
+    #[arg(long, default_value_t = 60)]
+    fidelity: u32,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let args = Args::parse();
+    tracing_subscriber::fmt()
+        .with_env_filter(
+            tracing_subscriber::EnvFilter::try_from_default_env()
+                .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new("info")),
+        )
+        .with_writer(std::io::stderr)
+        .init();
+
+    let (endpoints, engine, profile) = if args.config.exists() {
+        let mut config = AppConfig::load(&args.config)?;
+        ratelimit::USAGE.set_limits(&config.rate_limits);
+        let profile = config.apply_profile(args.profile.as_deref())?;
+        (config.endpoints, config.engine, profile)
+    } else if args.profile.is_some() {
+        anyhow::bail!("--profile requires {}", args.config.display());
+    } else {
+        (
+            EndpointsConfig::default(),
+            EngineParams::default(),
+            Default::default(),
+        )
+    };
+
+    // CLI args take precedence over profile values
+    let trader_address = args
+        .trader_address
+        .or(profile.trader_address)
+        .context("--trader-address is required (or set trader_address in the profile)")?;
+    let budget = args
+        .budget
+        .or(profile.budget)
+        .context("--budget is required (or set budget in the profile)")?;
+    let copy_percentage = args
+        .copy_percentage
+        .or(profile.copy_percentage)
+        .context("--copy-percentage is required (or set copy_percentage in the profile)")?;
+    let max_trade_size = args
+        .max_trade_size
+        .or(profile.max_trade_size)
+        .context("--max-trade-size is required (or set max_trade_size in the profile)")?;
+    if budget <= 0.0 {
+        anyhow::bail!("--budget must be positive");
+    }
+    if !(0.0..=100.0).contains(&copy_percentage) {
+        anyhow::bail!("--copy-percentage must be between 0 and 100");
+    }
+    if !(0.0..=100.0).contains(&max_trade_size) {
+        anyhow::bail!("--max-trade-size must be between 0 and 100");
+    }
+    if args.days <= 0 || args.warmup_days < 0 {
+        anyhow::bail!("--days must be positive and --warmup-days non-negative");
+    }
+    if args.latency_secs < 0 || args.slippage < 0.0 {
+        anyhow::bail!("--latency-secs and --slippage must be non-negative");
+    }
+    if !(0.0..=1.0).contains(&args.fill_ratio) {
+        anyhow::bail!("--fill-ratio must be between 0 and 1");
+    }
+    let trader_addr: Address = trader_address
+        .parse()
+        .map_err(|e| anyhow::anyhow!("Invalid trader address: {e}"))?;
+
+    let now = clock::now();
+    let start = now - Duration::days(args.days);
+    let history_start = start - Duration::days(args.warmup_days);
+
+    let data = DataClient::new(&endpoints.data_api)?;
+    let trades = fetch_trade_history(&data, trader_addr, history_start.timestamp(), None).await?;
+    let assets: BTreeSet<&str> = trades.iter().map(|t| t.asset.as_str()).collect();
+    info!(
+        "Fetched {} trades in {} tokens since {}",
+        trades.len(),
+        assets.len(),
+        history_start.format("%Y-%m-%d")
+    );
+
+    // Tokens without a price history are marked at their last traded price
+    let clob = ClobClient::new(&endpoints.clob_api, ClobConfig::default())?;
+    let mut prices = PriceHistory::default();
+    for asset in assets {
+        let points = fetch_price_history(
+            &clob,
+            asset,
+            history_start.timestamp(),
+            now.timestamp(),
+            args.fidelity,
+        );
+        match points.await {
+            Ok(points) => prices.insert(asset, points),
+            Err(e) => warn!("{e:#}"),
+        }
+    }
+
+    let config = BacktestConfig {
+        budget,
+        copy_pct: copy_percentage / 100.0,
+        max_trade_pct: max_trade_size / 100.0,
+        engine,
+        fill: FillModel {
+            latency_secs: args.latency_secs,
+            slippage: args.slippage,
+            fill_ratio: args.fill_ratio,
+        },
+        start: Some(start.timestamp()),
+        end: Some(now.timestamp()),
+    };
+    let report = backtest::run(&trades, &prices, &config);
+    info!(
+        "Replayed {} trades: {} rebalances, {} orders, P&L ${:.2} ({:.1}%), max drawdown {:.1}%",
+        report.trades_replayed,
+        report.rebalances,
+        report.orders,
+        report.summary.total_pnl,
+        report.summary.pnl_percent,
+        report.max_drawdown_pct
+    );
+    println!("{}", serde_json::to_string_pretty(&report)?);
+    Ok(())
+}
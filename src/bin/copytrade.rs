@@ -1,25 +1,94 @@
-use std::collections::{HashMap, HashSet};
-use std::path::Path;
+use std::collections::{BTreeSet, HashMap};
+use std::io::IsTerminal;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use std::time::Duration;
 
-use anyhow::Result;
-use clap::Parser;
-use polymarket_client_sdk::data::Client;
+use anyhow::{Context, Result};
+use clap::{Parser, Subcommand, ValueEnum};
+use polymarket_client_sdk::clob::{Client as ClobClient, Config as ClobConfig};
+use polymarket_client_sdk::data::types::MarketFilter;
+use polymarket_client_sdk::data::types::response::Position;
 use polymarket_client_sdk::gamma::Client as GammaClient;
 use polymarket_client_sdk::types::Address;
+use rust_decimal::Decimal;
 use rust_decimal::prelude::ToPrimitive;
-use tracing::{info, warn};
+use tokio::time::Instant;
+use tracing::{error, info, warn};
 
 use polymarket_copytrade::api::{
-    build_exit_price_map, fetch_active_positions, fetch_recent_trades,
+    EventCategories, build_exit_price_map, fetch_gamma_end_dates, fetch_gamma_prices,
+    fetch_tick_size, resolve_event_ids,
 };
+use polymarket_copytrade::aggregate;
+use polymarket_copytrade::artifacts;
 use polymarket_copytrade::auth::{self, ClobContext};
-use polymarket_copytrade::config::{AppConfig, CONFIG_PATH};
-use polymarket_copytrade::engine::{compute_orders, compute_target_state, compute_weights};
+use polymarket_copytrade::backoff::{self, ErrorBudget, PollScheduler};
+use polymarket_copytrade::chain;
+use polymarket_copytrade::clock;
+use polymarket_copytrade::config::{
+    AppConfig, BudgetSpec, CONFIG_PATH, EngineParams, ExecutorParams, PersistenceConfig,
+    RetryOperation, RiskConfig, StartupOrders,
+};
+use polymarket_copytrade::consensus::{DetectionConsensus, DetectionSource};
+use polymarket_copytrade::depth;
+use polymarket_copytrade::engine::{
+    DEFAULT_TICK_SIZE, MarketSelection, MarketWeighting, build_price_map, cap_buys,
+    charge_taker_fees, parse_end_date, plan_rebalance, plan_rebalance_with_skips,
+};
 use polymarket_copytrade::executor;
+use polymarket_copytrade::failover::{DataApi, FailoverPolicy};
+use polymarket_copytrade::fills::FillBook;
+use polymarket_copytrade::filters::CategoryVerdicts;
+use polymarket_copytrade::gap::GapDetector;
+use polymarket_copytrade::graduation::PaperSample;
+use polymarket_copytrade::health::{self, HealthMonitor};
+use polymarket_copytrade::intent::{IntentTracker, TradeCluster};
+use polymarket_copytrade::latency::LatencyHistogram;
+use polymarket_copytrade::journal::Journal;
+use polymarket_copytrade::lease::{self, Lease};
+use polymarket_copytrade::milestones::MilestoneTracker;
+use polymarket_copytrade::money;
+use polymarket_copytrade::overrides::Overrides;
+use polymarket_copytrade::prices::{self, PriceCache};
+use polymarket_copytrade::ratelimit;
+use polymarket_copytrade::recording::{self, CycleKind, CycleRecord, Recorder, Tap};
 use polymarket_copytrade::reporter;
+use polymarket_copytrade::retry;
+use polymarket_copytrade::risk;
+use polymarket_copytrade::rtds;
+use polymarket_copytrade::selftest::{self, WsCheck};
+use polymarket_copytrade::shadow::Shadow;
 use polymarket_copytrade::state::TradingState;
-use polymarket_copytrade::types::{CopytradeEvent, EventTrigger, HeldPosition};
+use polymarket_copytrade::store;
+use polymarket_copytrade::stress;
+use polymarket_copytrade::trades::{self, ActivityEvent, RawTrade, TokenPairs};
+use polymarket_copytrade::types::{
+    AlertEvent, AlertKind, AlertSeverity, BuildInfo, CopytradeEvent, EventTrigger, ExecutionStatus,
+    HeldPosition, ModeTransitionEvent, OrderSide, PositionChangeKind, RestingOrder, SimulatedOrder,
+    TraderPositionChange, TradingMode,
+};
+use polymarket_copytrade::wal::{self, Mutation, Wal};
+use polymarket_copytrade::ws::WsEvent;
+
+/// Page size for trade detection requests.
+const TRADE_FETCH_LIMIT: i32 = 50;
+
+/// Warn after a poll cycle when any endpoint is at this share of its rate limit.
+const RATE_LIMIT_WARN_THRESHOLD: f64 = 0.8;
+
+/// Wait before retrying a failed `--graduate` switch to live.
+const GRADUATION_RETRY_INTERVAL: Duration = Duration::from_secs(15 * 60);
+
+/// After a WebSocket trade detection, wait this long for the rest of the burst
+/// (a single order often fills against several makers) before rebalancing.
+const WS_BATCH_WINDOW: Duration = Duration::from_millis(250);
+
+/// How often `--rebase-budget-daily` re-values the wallet.
+const BUDGET_REBASE_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// How often a daily loss pause is re-announced while it lasts.
 
 #[derive(Parser)]
 #[command(name = "copytrade", about = "Polymarket portfolio copytrade bot")]
@@ -32,99 +101,1670 @@ struct Args {
     #[arg(long, conflicts_with = "dry_run")]
     live: bool,
 
-    /// Trader proxy wallet address to copy
+    /// Named config profile (`[profile.<name>]` in config.toml)
     #[arg(long)]
-    trader_address: String,
+    profile: Option<String>,
 
-    /// Total budget in USD
+    /// Trader proxy wallet address to copy (defaults to the profile's `trader_address`)
     #[arg(long)]
-    budget: f64,
+    trader_address: Option<String>,
+
+    /// Total budget: USD amount, `N%` of wallet value (USDC + holdings), or `auto`
+    /// for 100% (defaults to the profile's `budget` or `budget_pct`)
+    #[arg(long, value_name = "USD|N%|auto")]
+    budget: Option<BudgetSpec>,
+
+    /// Re-base a percentage budget on the current wallet value every 24 hours
+    #[arg(long)]
+    rebase_budget_daily: bool,
 
-    /// Percentage of budget to allocate (0-100)
+    /// With --live: simulate alongside live execution and report the divergence
+    #[arg(long, requires = "live")]
+    shadow: bool,
+
+    /// With --live: hard-cap what this session spends on buys, in USD, whatever
+    /// the budget (for validating live execution with little at risk)
+    #[arg(long, value_name = "USD", requires = "live")]
+    live_test_cap: Option<f64>,
+
+    /// With --live or --graduate: place the initial replication orders without
+    /// asking for confirmation (required when stdin is not a terminal)
     #[arg(long)]
-    copy_percentage: f64,
+    yes: bool,
+
+    /// With --dry-run: switch to live once the `[graduation]` criteria are met
+    #[arg(long, requires = "dry_run")]
+    graduate: bool,
+
+    /// Stop opening or adding to positions, follow the trader's exits until flat,
+    /// then exit with the summary. A running instance starts draining on SIGUSR1
+    #[arg(long, conflicts_with = "graduate")]
+    drain: bool,
+
+    /// Give up draining after this many seconds and exit with what is still held
+    #[arg(long, default_value_t = 3600)]
+    drain_timeout_secs: u64,
+
+    /// Start draining at this time (RFC 3339, e.g. `2026-01-31T18:00:00Z`)
+    #[arg(long, value_name = "TIME", conflicts_with_all = ["run_for", "replay"])]
+    run_until: Option<chrono::DateTime<chrono::Utc>>,
+
+    /// Start draining this long after trading starts (e.g. `8h`, `90m`, `1h30m`)
+    #[arg(long, value_name = "SPAN", value_parser = clock::parse_span, conflicts_with = "replay")]
+    run_for: Option<Duration>,
 
-    /// Maximum percentage of running budget per position (0-100)
+    /// Build the initial portfolio gradually over this span (e.g. `6h`), largest
+    /// trader weights first, instead of deploying the whole budget at once
+    #[arg(long, value_name = "SPAN", value_parser = clock::parse_span, conflicts_with = "replay")]
+    ramp_in: Option<Duration>,
+
+    /// Number of equal steps of --ramp-in, the first at startup
+    #[arg(long, default_value_t = 4, requires = "ramp_in")]
+    ramp_in_steps: u32,
+
+    /// Percentage of budget to allocate (0-100) (defaults to the profile's `copy_percentage`)
+    #[arg(long)]
+    copy_percentage: Option<f64>,
+
+    /// Maximum percentage of running budget per position (0-100) (defaults to the profile's `max_trade_size`)
     #[arg(long)]
-    max_trade_size: f64,
+    max_trade_size: Option<f64>,
+
+    /// Only copy these markets (condition IDs); repeatable (defaults to the profile's `markets`)
+    #[arg(long = "market", value_name = "CONDITION_ID", conflicts_with = "event_slugs")]
+    markets: Vec<String>,
+
+    /// Only copy markets in these events (slugs, as in Polymarket URLs); repeatable
+    /// (defaults to the profile's `event_slugs`)
+    #[arg(long = "event-slug", value_name = "SLUG")]
+    event_slugs: Vec<String>,
+
+    /// How selected markets are weighted (defaults to the profile's `market_weighting`,
+    /// else `selected`)
+    #[arg(long, value_enum)]
+    market_weighting: Option<Weighting>,
+
+    /// Write-ahead log for state mutations; replayed on restart to rebuild state
+    #[arg(long)]
+    wal: Option<PathBuf>,
+
+    /// Audit journal of every state mutation, event, and snapshot (JSON lines),
+    /// checked with `diagnose replay`
+    #[arg(long)]
+    journal: Option<PathBuf>,
+
+    /// Record every cycle's API responses (positions, trades, prices) into this
+    /// directory, one JSON file per cycle, for `--replay`
+    #[arg(long, value_name = "DIR")]
+    record: Option<PathBuf>,
+
+    /// Write the fill ledger CSV as fills are applied and, at shutdown, the exit
+    /// summary, holdings CSV, redacted config, and version info, all to a new
+    /// session directory under this one
+    #[arg(long, value_name = "DIR")]
+    artifacts: Option<PathBuf>,
+
+    /// Replay a `--record` directory through the engine and state instead of
+    /// calling the APIs, then print the exit summary (dry-run only)
+    #[arg(
+        long,
+        value_name = "DIR",
+        requires = "dry_run",
+        conflicts_with_all = [
+            "record", "graduate", "wal", "journal", "lease", "drain", "overrides", "artifacts"
+        ]
+    )]
+    replay: Option<PathBuf>,
+
+    /// Manual overrides file: when a JSON file of targets, orders, and/or pins
+    /// and exclusions appears here, it is applied on the next cycle (over the
+    /// trader-derived targets) and renamed to `<FILE>.applied`
+    #[arg(long, value_name = "FILE")]
+    overrides: Option<PathBuf>,
+
+    /// Lease file for hot-standby deployments: only the lease holder trades, other
+    /// instances wait (following `--wal`, if shared) and take over when it expires
+    #[arg(long)]
+    lease: Option<PathBuf>,
+
+    /// Lease time-to-live in seconds; must exceed the poll interval
+    #[arg(long, default_value_t = 120)]
+    lease_ttl_secs: u64,
+
+    /// Identity recorded in the lease file (defaults to `$HOSTNAME-<pid>`)
+    #[arg(long)]
+    instance_id: Option<String>,
+
+    /// Serve `GET /healthz` on this address (e.g. `0.0.0.0:8080`) for liveness/readiness probes
+    #[arg(long)]
+    health_addr: Option<SocketAddr>,
+
+    /// Skip the startup connectivity self-test of the API endpoints
+    #[arg(long)]
+    skip_self_test: bool,
+
+    /// Format of the tracing logs written to stderr
+    #[arg(long, value_enum, default_value_t = LogFormat::Text)]
+    log_format: LogFormat,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+/// Maintenance commands, run instead of a trading session.
+#[derive(Subcommand)]
+enum Command {
+    /// Upgrade a WAL written by an older version to the current state schema,
+    /// keeping the original as `<wal>.v<old version>.bak`
+    Migrate {
+        /// WAL file to upgrade in place
+        #[arg(long)]
+        wal: PathBuf,
+    },
+    /// Run the holdings restored from a WAL through adverse scenarios and
+    /// report the P&L and budget impact (JSON on stdout)
+    Stress {
+        /// WAL holding the state to stress
+        #[arg(long)]
+        wal: PathBuf,
+        /// Tag of the holdings that all resolve against us
+        #[arg(long, default_value = "sports")]
+        tag: String,
+        /// Price gap against us, in percent, alone and under a full liquidation
+        #[arg(long, default_value_t = 20.0)]
+        gap_pct: f64,
+    },
+    /// Reports over past sessions
+    Report {
+        #[command(subcommand)]
+        report: ReportCommand,
+    },
+}
+
+/// `copytrade report` commands.
+#[derive(Subcommand)]
+enum ReportCommand {
+    /// Merge the session bundles in an `--artifacts` directory into a combined
+    /// equity curve and per-trader comparison (JSON on stdout)
+    Aggregate {
+        /// Directory that `--artifacts` wrote the bundles to
+        #[arg(long)]
+        dir: PathBuf,
+    },
+}
+
+/// Tracing log format. Reporter events on stdout are always JSON lines.
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum LogFormat {
+    /// Human-readable text
+    Text,
+    /// One JSON object per line (for Loki/ELK-style pipelines)
+    Json,
+}
+
+/// CLI form of `MarketWeighting`.
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Weighting {
+    /// Renormalize weights across the selected markets (they share the whole budget)
+    Selected,
+    /// Keep each market's weight within the trader's full portfolio
+    Portfolio,
+}
+
+impl From<Weighting> for MarketWeighting {
+    fn from(w: Weighting) -> Self {
+        match w {
+            Weighting::Selected => MarketWeighting::Selected,
+            Weighting::Portfolio => MarketWeighting::Portfolio,
+        }
+    }
+}
+
+/// Copytrade parameters shared by initial replication and every poll cycle.
+struct CopyParams {
+    copy_pct: f64,
+    max_trade_pct: f64,
+    engine: EngineParams,
+    executor: ExecutorParams,
+    rebalance_on_gap: bool,
+    /// Poll `/activity` instead of `/trades` (`settings.activity_detection`).
+    activity_detection: bool,
+    /// Clustering window for the trader's intents (`settings.intent_window_secs`).
+    intent_window_secs: u64,
+    /// Server-side market/event filter for the copied markets.
+    market_filter: Option<MarketFilter>,
+    /// Single-market copy mode (set together with `market_filter`).
+    selection: Option<MarketSelection>,
+    /// Draining before shutdown: only orders that reduce positions go out.
+    draining: bool,
+    /// Stop-loss/take-profit exits, checked every cycle.
+    risk: RiskConfig,
+    /// Gamma lookups for `[engine.filters.categories]` (unset without rules).
+    categories: Option<EventCategories>,
+    /// Gradual initial build (`--ramp-in`).
+    ramp: Option<RampIn>,
+    /// Public CLOB client: tick sizes, and order books for `[engine.depth_limit]`
+    /// (unset in --replay).
+    clob: Option<ClobClient>,
+    /// Session spend cap (`--live-test-cap`).
+    spend_cap: Option<SpendCap>,
+    /// Ask before placing the live initial replication orders (`--live` without `--yes`).
+    confirm_initial: bool,
+    /// Version, commit, and config hash stamped on every event and the exit summary.
+    build: BuildInfo,
+}
+
+/// Cap on the buys placed this session: `limit` USD on top of `baseline`, the
+/// state's committed spend when trading started.
+struct SpendCap {
+    limit: Decimal,
+    baseline: Decimal,
+}
+
+/// Spreads the initial build over `steps` equal steps of `span`: step `k` of
+/// `n` deploys `k/n` of the total target value (see `engine::apply_ramp_in`).
+struct RampIn {
+    start: chrono::DateTime<chrono::Utc>,
+    span: Duration,
+    steps: u32,
+    /// Last step a rebalance was triggered for.
+    reached: std::cell::Cell<u32>,
+}
+
+impl RampIn {
+    fn new(span: Duration, steps: u32) -> Self {
+        Self {
+            start: clock::now(),
+            span,
+            steps: steps.max(1),
+            reached: std::cell::Cell::new(1),
+        }
+    }
+
+    /// Start over from step 1 (at initial replication).
+    fn restart(&mut self) {
+        self.start = clock::now();
+        self.reached.set(1);
+    }
+
+    /// Current step, from 1 to `steps`.
+    fn step(&self) -> u32 {
+        let elapsed = (clock::now() - self.start).to_std().unwrap_or_default();
+        let step_len = self.span / self.steps;
+        let done = (elapsed.as_secs_f64() / step_len.as_secs_f64()) as u32;
+        (done + 1).min(self.steps)
+    }
+
+    /// Share of the targets to deploy now; `None` once the ramp is complete.
+    fn fraction(&self) -> Option<f64> {
+        let step = self.step();
+        (step < self.steps).then(|| f64::from(step) / f64::from(self.steps))
+    }
+
+    /// Whether a new step began since the last call (which marks it reached).
+    fn advance(&self) -> bool {
+        let step = self.step();
+        step > self.reached.replace(step)
+    }
+}
+
+impl CopyParams {
+    /// Filter for the trader's position requests. `Portfolio` weighting needs the
+    /// full portfolio to size the selected markets, so it fetches unfiltered.
+    fn trader_position_filter(&self) -> Option<&MarketFilter> {
+        match &self.selection {
+            Some(sel) if sel.weighting == MarketWeighting::Portfolio => None,
+            _ => self.market_filter.as_ref(),
+        }
+    }
+
+    /// Whether the trader's `/trades` responses, narrowed by `market_filter`,
+    /// would include the pushed `trade`, so it can vouch for their freshness
+    /// (`DataApi::note_trade`). Under an event filter only trades in the markets
+    /// we hold are known to be in it.
+    fn rest_reports(&self, trade: &RawTrade, state: &TradingState) -> bool {
+        let Some(selection) = &self.selection else {
+            return true;
+        };
+        selection.selects_market(&trade.condition_id)
+            || state.holdings.contains_key(&trade.asset)
+            || state.opposite_asset(&trade.asset).is_some()
+    }
+
+    /// Narrow the trader's positions to the selected markets, returning them with
+    /// the effective copy fraction.
+    fn select(&self, positions: Vec<Position>) -> (Vec<Position>, f64) {
+        match &self.selection {
+            Some(sel) => {
+                let (selected, scale) = sel.apply(&positions);
+                (selected, self.copy_pct * scale)
+            }
+            None => (positions, self.copy_pct),
+        }
+    }
+
+    /// `[engine.filters.categories]` verdicts on the events of the trader's
+    /// positions, looked up through the tap (so --replay sees the recorded
+    /// lookups). A replay without them leaves every event unverified.
+    async fn category_verdicts(
+        &self,
+        positions: &[Position],
+        tap: &mut Tap,
+    ) -> CategoryVerdicts {
+        let rules = &self.engine.filters.categories;
+        if rules.is_empty() {
+            return CategoryVerdicts::default();
+        }
+        let slugs: Vec<String> = positions.iter().map(|p| p.event_slug.clone()).collect();
+        let lookup = async {
+            match &self.categories {
+                Some(categories) => categories.lookup(&slugs).await,
+                None => HashMap::new(),
+            }
+        };
+        let known = tap.categories(lookup).await.unwrap_or_else(|e| {
+            warn!("{e:#} — holding the trader's markets");
+            HashMap::new()
+        });
+        rules.verdicts(&slugs, &known)
+    }
+
+    /// Engine parameters for this cycle, with the `--ramp-in` share, if ramping,
+    /// the cycle's category verdicts and the trader's open trade clusters.
+    fn cycle_engine(
+        &self,
+        category_verdicts: CategoryVerdicts,
+        trader_intents: Vec<TradeCluster>,
+    ) -> EngineParams {
+        EngineParams {
+            ramp_in: self.ramp.as_ref().and_then(RampIn::fraction),
+            category_verdicts,
+            trader_intents,
+            ..self.engine.clone()
+        }
+    }
+
+    /// Look up the tick size and taker fee rate of every market traded or held
+    /// that isn't known yet, so the engine rounds prices as the CLOB will and
+    /// budgets the fees. Failed lookups (and --replay) leave
+    /// `DEFAULT_TICK_SIZE` and no fee in place.
+    async fn load_market_rules(&self, positions: &[Position], state: &mut TradingState) {
+        let Some(clob) = &self.clob else {
+            return;
+        };
+        let traded = positions.iter().map(|p| p.asset.to_string());
+        let mut missing: Vec<String> = traded
+            .chain(state.holdings.keys().cloned())
+            .filter(|asset| !state.tick_sizes.contains_key(asset))
+            .collect();
+        missing.sort();
+        missing.dedup();
+        for asset in missing {
+            let fee_rate = executor::fee_rate_bps(clob, &asset).await;
+            state.fee_rates.insert(asset.clone(), fee_rate);
+            match fetch_tick_size(clob, &asset).await {
+                Ok(tick) => {
+                    state.tick_sizes.insert(asset, money::from_f64(tick));
+                }
+                Err(e) => warn!("{e:#} — assuming a {DEFAULT_TICK_SIZE} tick"),
+            }
+        }
+    }
+
+    /// Cut buys to `[engine.depth_limit]`'s share of the order book depth.
+    async fn limit_to_depth(&self, orders: Vec<SimulatedOrder>) -> Vec<SimulatedOrder> {
+        match (&self.clob, &self.engine.depth_limit) {
+            (Some(clob), Some(limit)) => {
+                depth::limit_buys(clob, orders, limit, &self.engine).await
+            }
+            _ => orders,
+        }
+    }
+
+    /// Fit buys into what is left of `--live-test-cap`.
+    fn cap_spend(&self, orders: Vec<SimulatedOrder>, state: &TradingState) -> Vec<SimulatedOrder> {
+        let Some(cap) = &self.spend_cap else {
+            return orders;
+        };
+        let left = cap.limit - (state.committed_spend() - cap.baseline);
+        let buy_cost = |orders: &[SimulatedOrder]| -> Decimal {
+            let buys = orders.iter().filter(|o| o.side == OrderSide::Buy);
+            buys.map(|o| o.cost_usd).sum()
+        };
+        let planned = buy_cost(&orders);
+        let orders = cap_buys(orders, left, &self.engine);
+        let capped = buy_cost(&orders);
+        if capped < planned {
+            info!(
+                "Live test cap: buys cut from ${planned:.2} to ${capped:.2} \
+                 (${:.2} of ${:.2} left)",
+                left.max(Decimal::ZERO),
+                cap.limit
+            );
+        }
+        orders
+    }
+
+    /// Drop buys while draining.
+    fn admit(&self, orders: Vec<SimulatedOrder>) -> Vec<SimulatedOrder> {
+        if self.draining {
+            orders
+                .into_iter()
+                .filter(|o| o.side == OrderSide::Sell)
+                .collect()
+        } else {
+            orders
+        }
+    }
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    tracing_subscriber::fmt()
+    let mut args = Args::parse();
+
+    let subscriber = tracing_subscriber::fmt()
         .with_env_filter(
             tracing_subscriber::EnvFilter::try_from_default_env()
                 .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new("info")),
         )
-        .with_writer(std::io::stderr)
-        .init();
+        .with_writer(std::io::stderr);
+    match args.log_format {
+        LogFormat::Text => subscriber.init(),
+        LogFormat::Json => subscriber.json().with_ansi(false).init(),
+    }
 
-    let args = Args::parse();
+    match &args.command {
+        Some(Command::Migrate { wal }) => {
+            let from = Wal::migrate(wal)?;
+            if from == wal::SCHEMA_VERSION {
+                info!("{} is already at state schema v{from}", wal.display());
+            } else {
+                info!(
+                    "Upgraded {} from state schema v{from} to v{} (original kept as {}.v{from}.bak)",
+                    wal.display(),
+                    wal::SCHEMA_VERSION,
+                    wal.display()
+                );
+            }
+            return Ok(());
+        }
+        Some(Command::Stress { wal, tag, gap_pct }) => {
+            return run_stress(wal, tag, *gap_pct).await;
+        }
+        Some(Command::Report {
+            report: ReportCommand::Aggregate { dir },
+        }) => {
+            let sessions = aggregate::load_sessions(dir)?;
+            if sessions.is_empty() {
+                anyhow::bail!("no session bundles in {}", dir.display());
+            }
+            let report = aggregate::aggregate(&sessions);
+            println!("{}", serde_json::to_string_pretty(&report)?);
+            return Ok(());
+        }
+        None => {}
+    }
 
     // Require exactly one mode
     if !args.dry_run && !args.live {
         anyhow::bail!("Must specify either --dry-run or --live");
     }
-    if args.budget <= 0.0 {
-        anyhow::bail!("--budget must be positive");
+
+    // Load config and apply the selected profile
+    let config_path = Path::new(CONFIG_PATH);
+    let mut config = AppConfig::load(config_path)?;
+    ratelimit::USAGE.set_limits(&config.rate_limits);
+    let profile = config.apply_profile(args.profile.as_deref())?;
+    match &args.profile {
+        Some(name) => info!(
+            "Loaded config from {} (profile: {name})",
+            config_path.display()
+        ),
+        None => info!("Loaded config from {}", config_path.display()),
+    }
+    if args.graduate
+        && let Some(path) = &args.wal
+    {
+        let live_wal = graduated_wal(path);
+        if store::stored_budget(&config.persistence, &live_wal)?.is_some() {
+            info!(
+                "{} has already graduated to live — resuming live from {}",
+                path.display(),
+                live_wal.display()
+            );
+            args.dry_run = false;
+            args.live = true;
+            args.graduate = false;
+            args.wal = Some(live_wal);
+        }
     }
-    if !(0.0..=100.0).contains(&args.copy_percentage) {
+
+    // CLI args take precedence over profile values
+    let trader_address = args
+        .trader_address
+        .or(profile.trader_address)
+        .context("--trader-address is required (or set trader_address in the profile)")?;
+    let budget_spec = match (args.budget, profile.budget, profile.budget_pct) {
+        (Some(spec), _, _) => spec,
+        (None, Some(_), Some(_)) => {
+            anyhow::bail!("set either budget or budget_pct in the profile, not both")
+        }
+        (None, Some(usd), None) => BudgetSpec::Usd(usd),
+        (None, None, Some(pct)) => format!("{pct}%").parse()?,
+        (None, None, None) => {
+            anyhow::bail!("--budget is required (or set budget or budget_pct in the profile)")
+        }
+    };
+    let rebase_budget_daily =
+        args.rebase_budget_daily || profile.rebase_budget_daily.unwrap_or(false);
+    let copy_percentage = args
+        .copy_percentage
+        .or(profile.copy_percentage)
+        .context("--copy-percentage is required (or set copy_percentage in the profile)")?;
+    let max_trade_size = args
+        .max_trade_size
+        .or(profile.max_trade_size)
+        .context("--max-trade-size is required (or set max_trade_size in the profile)")?;
+
+    // A wallet-based budget starts from the WAL's pinned budget (if any) and is
+    // re-based once the wallet has been valued after authentication
+    let budget = match budget_spec {
+        BudgetSpec::Usd(usd) => {
+            if usd <= 0.0 {
+                anyhow::bail!("--budget must be positive");
+            }
+            usd
+        }
+        BudgetSpec::WalletPct(_) => {
+            if !args.live {
+                anyhow::bail!("a wallet-based budget requires --live (dry-run has no wallet)");
+            }
+            match &args.wal {
+                Some(path) => store::stored_budget(&config.persistence, path)?.unwrap_or(0.0),
+                None => 0.0,
+            }
+        }
+    };
+    if args.yes && !args.live && !args.graduate {
+        anyhow::bail!("--yes requires --live or --graduate");
+    }
+    if args.graduate && !args.yes && !std::io::stdin().is_terminal() {
+        // Found out now rather than when the criteria are met, days later
+        anyhow::bail!(
+            "stdin is not a terminal: pass --yes to place the orders --graduate makes unattended"
+        );
+    }
+    if args.graduate && args.journal.is_some() {
+        anyhow::bail!("--graduate can't be combined with --journal");
+    }
+    if rebase_budget_daily && matches!(budget_spec, BudgetSpec::Usd(_)) {
+        anyhow::bail!("--rebase-budget-daily requires a percentage budget (N% or auto)");
+    }
+    if !(0.0..=100.0).contains(&copy_percentage) {
         anyhow::bail!("--copy-percentage must be between 0 and 100");
     }
-    if !(0.0..=100.0).contains(&args.max_trade_size) {
+    if !(0.0..=100.0).contains(&max_trade_size) {
         anyhow::bail!("--max-trade-size must be between 0 and 100");
     }
+    let fraction = config.engine.rebalance_fraction;
+    if !(fraction > 0.0 && fraction <= 1.0) {
+        anyhow::bail!("engine.rebalance_fraction must be in (0, 1], got {fraction}");
+    }
+    if config.engine.buy_band < 0.0 || config.engine.sell_band < 0.0 {
+        anyhow::bail!("engine.buy_band and engine.sell_band must not be negative");
+    }
+    if let Some(averaging) = &config.engine.averaging_down
+        && !((0.0..1.0).contains(&averaging.reserve_pct)
+            && (0.0..=1.0).contains(&averaging.max_extra_pct)
+            && (0.0..=1.0).contains(&averaging.min_loss_pct))
+    {
+        anyhow::bail!(
+            "engine.averaging_down: reserve_pct must be in [0, 1), max_extra_pct and min_loss_pct in [0, 1]"
+        );
+    }
 
-    // Load config
-    let config_path = Path::new(CONFIG_PATH);
-    let config = AppConfig::load(config_path)?;
-    info!("Loaded config from {}", config_path.display());
+    if let Some(premium) = config.engine.max_entry_premium
+        && (premium.is_nan() || premium < 0.0)
+    {
+        anyhow::bail!("engine.max_entry_premium must not be negative, got {premium}");
+    }
 
-    let copy_pct = args.copy_percentage / 100.0;
-    let max_trade_pct = args.max_trade_size / 100.0;
-    let trader_addr: Address = args
-        .trader_address
+    if config.engine.merge_complete_sets && args.live {
+        anyhow::bail!(
+            "engine.merge_complete_sets is dry-run only: merges are on-chain transactions the bot \
+             doesn't send"
+        );
+    }
+
+    if let Some(multiple) = config.engine.max_capital_multiple
+        && (multiple.is_nan() || multiple <= 0.0)
+    {
+        anyhow::bail!("engine.max_capital_multiple must be positive, got {multiple}");
+    }
+
+    if let Some(limit) = &config.engine.depth_limit
+        && !(limit.max_depth_pct > 0.0 && limit.max_depth_pct <= 1.0)
+    {
+        anyhow::bail!("engine.depth_limit.max_depth_pct must be in (0, 1]");
+    }
+
+    let (min_price, max_price) = (config.engine.min_copy_price, config.engine.max_copy_price);
+    if min_price.is_some_and(|p| !(0.0..=1.0).contains(&p))
+        || max_price.is_some_and(|p| !(0.0..=1.0).contains(&p))
+        || min_price.zip(max_price).is_some_and(|(min, max)| min > max)
+    {
+        anyhow::bail!(
+            "engine.min_copy_price and engine.max_copy_price must be in [0, 1], min <= max"
+        );
+    }
+
+    if !(0.0..1.0).contains(&config.executor.max_chase) {
+        anyhow::bail!("executor.max_chase must be in [0, 1)");
+    }
+    if args.live_test_cap.is_some_and(|cap| cap.is_nan() || cap <= 0.0) {
+        anyhow::bail!("--live-test-cap must be positive");
+    }
+    if let Some(pricing) = &config.executor.book_pricing
+        && !(0.0..1.0).contains(&pricing.max_premium)
+    {
+        anyhow::bail!("executor.book_pricing.max_premium must be in [0, 1)");
+    }
+    if config.executor.max_slippage_pct.is_some_and(|pct| pct.is_nan() || pct < 0.0) {
+        anyhow::bail!("executor.max_slippage_pct must be non-negative");
+    }
+    let risk_exit_slippage = config.executor.risk_exit_slippage_pct;
+    if risk_exit_slippage.is_some_and(|pct| pct.is_nan() || pct < 0.0) {
+        anyhow::bail!("executor.risk_exit_slippage_pct must be non-negative");
+    }
+    if let Some(twap) = &config.executor.twap {
+        if twap.min_order_usd.is_nan() || twap.min_order_usd <= 0.0 {
+            anyhow::bail!("executor.twap.min_order_usd must be positive");
+        }
+        if twap.slices < 2 {
+            anyhow::bail!("executor.twap.slices must be at least 2");
+        }
+    }
+    if config.executor.resting_ttl_secs == Some(0) {
+        anyhow::bail!("executor.resting_ttl_secs must be positive");
+    }
+    let clob_rate = &config.executor.clob_rate;
+    if clob_rate.requests_per_sec.is_nan() || clob_rate.requests_per_sec <= 0.0 {
+        anyhow::bail!("executor.clob_rate.requests_per_sec must be positive");
+    }
+    if clob_rate.burst == 0 {
+        anyhow::bail!("executor.clob_rate.burst must be at least 1");
+    }
+    ratelimit::CLOB_LIMITER.configure(clob_rate.requests_per_sec, clob_rate.burst);
+    for operation in [
+        RetryOperation::PostOrder,
+        RetryOperation::OrderStatus,
+        RetryOperation::Fetch,
+    ] {
+        retry::validate(&config.retry.policy(operation))
+            .with_context(|| format!("invalid retry policy ({operation:?})"))?;
+    }
+    retry::configure(&config.retry);
+    let events = &config.persistence.events;
+    if events.max_file_mb.is_nan() || events.max_file_mb <= 0.0 {
+        anyhow::bail!("persistence.events.max_file_mb must be positive");
+    }
+    if config.settings.ws_prices && config.settings.ws_prices_max_assets == 0 {
+        anyhow::bail!("settings.ws_prices_max_assets must be at least 1");
+    }
+
+    let polygon_ws = match (config.settings.chain_detection, &config.endpoints.polygon_ws) {
+        (true, None) => anyhow::bail!("settings.chain_detection requires endpoints.polygon_ws"),
+        (true, Some(url)) => Some(url.clone()),
+        (false, _) => None,
+    };
+
+    let trader_addr: Address = trader_address
         .parse()
         .map_err(|e| anyhow::anyhow!("Invalid trader address: {e}"))?;
-    let trader_short_id = &args.trader_address[args.trader_address.len().saturating_sub(6)..];
+    if args.skip_self_test {
+        info!("Skipping connectivity self-test");
+    } else if args.replay.is_some() {
+        // Replays make no API calls
+    } else {
+        // The CLOB is only needed for live trading. Each WebSocket feed in use
+        // is subscribed to as it will be; only the Polygon node is required,
+        // the others fall back to REST polling
+        let mut ws = Vec::new();
+        if config.settings.ws_detection {
+            ws.push(WsCheck {
+                name: "rtds_ws",
+                url: &config.endpoints.rtds_ws,
+                subscription: Some(rtds::trades_subscription()),
+                required: false,
+            });
+        }
+        if config.settings.ws_prices {
+            ws.push(WsCheck {
+                name: "clob_market_ws",
+                url: &config.endpoints.clob_market_ws,
+                subscription: Some(prices::market_subscription(&BTreeSet::new())),
+                required: false,
+            });
+        }
+        if let Some(url) = &polygon_ws {
+            ws.push(WsCheck {
+                name: "polygon_ws",
+                url,
+                subscription: Some(chain::logs_subscription(trader_addr)),
+                required: true,
+            });
+        }
+        let checks = selftest::run(&config.endpoints, args.live, &ws).await;
+        for check in &checks {
+            match &check.error {
+                None => info!(
+                    "Self-test: {} reachable ({:.0}ms)",
+                    check.name, check.latency_ms
+                ),
+                Some(e) if !check.required => warn!(
+                    "Self-test: {} unreachable ({e}) — optional, continuing",
+                    check.name
+                ),
+                Some(_) => {}
+            }
+        }
+        selftest::ensure_reachable(&checks)?;
+    }
+
+    // Order signing already uses server time; this keeps our own timestamps
+    // (latency, cooldowns, end dates) on the same clock
+    let max_clock_skew = Duration::from_secs(config.settings.max_clock_skew_secs);
+    let clock_check_interval = Duration::from_secs(config.settings.clock_check_interval_secs);
+    if args.replay.is_none()
+        && let Some(sample) = clock::sync(&config.endpoints.clob_api, max_clock_skew).await
+    {
+        info!(
+            "Clock skew vs CLOB server: {:+.2}s (correction {:+}ms)",
+            sample.offset_ms as f64 / 1000.0,
+            clock::offset_ms()
+        );
+    }
+    let mut last_clock_check = std::time::Instant::now();
+    let mut last_budget_rebase = std::time::Instant::now();
+    let mut milestones = MilestoneTracker::new(config.milestones.clone());
+    // Latest prices of held assets, as of the last rebalancing cycle
+    let mut milestone_prices: HashMap<String, f64> = HashMap::new();
+    let mut next_graduation_attempt = std::time::Instant::now();
+
+    let data_client = DataApi::new(
+        &config.endpoints.data_api,
+        config.endpoints.data_api_secondary.as_deref(),
+        FailoverPolicy {
+            max_failures: config.settings.data_api_failover_errors,
+            stale_after: Duration::from_secs(config.settings.data_api_stale_secs),
+            failback_after: Duration::from_secs(config.settings.data_api_failback_secs),
+        },
+    )?;
+    let gamma_client = GammaClient::new(&config.endpoints.gamma_api)?;
+    // Live marks for held and target tokens, subscribed as rebalances touch them
+    let prices = config.settings.ws_prices.then(|| {
+        let max_assets = config.settings.ws_prices_max_assets;
+        PriceCache::spawn(&config.endpoints.clob_market_ws, max_assets).0
+    });
+
+    // Single-market copy mode: CLI lists replace the profile's
+    let markets = if args.markets.is_empty() {
+        profile.markets.clone().unwrap_or_default()
+    } else {
+        args.markets.clone()
+    };
+    let event_slugs = if args.event_slugs.is_empty() {
+        profile.event_slugs.clone().unwrap_or_default()
+    } else {
+        args.event_slugs.clone()
+    };
+    if !markets.is_empty() && !event_slugs.is_empty() {
+        anyhow::bail!("markets and event slugs are mutually exclusive; set only one");
+    }
+    let weighting = args
+        .market_weighting
+        .map(MarketWeighting::from)
+        .or(profile.market_weighting)
+        .unwrap_or_default();
+    let market_filter = if !markets.is_empty() {
+        info!("Copying only {} market(s): {}", markets.len(), markets.join(", "));
+        Some(MarketFilter::markets(markets.clone()))
+    } else if !event_slugs.is_empty() && args.replay.is_some() {
+        // Replays only apply the selection; the server-side filter is never sent
+        Some(MarketFilter::event_ids(Vec::new()))
+    } else if !event_slugs.is_empty() {
+        let ids = resolve_event_ids(&gamma_client, &event_slugs).await?;
+        info!(
+            "Copying only markets in event(s): {}",
+            event_slugs.join(", ")
+        );
+        Some(MarketFilter::event_ids(ids))
+    } else {
+        None
+    };
+    let selection = market_filter.as_ref().map(|_| MarketSelection {
+        condition_ids: markets.into_iter().collect(),
+        event_slugs: event_slugs.into_iter().collect(),
+        weighting,
+    });
+
+    let mut params = CopyParams {
+        copy_pct: copy_percentage / 100.0,
+        max_trade_pct: max_trade_size / 100.0,
+        engine: config.engine.clone(),
+        executor: config.executor.clone(),
+        rebalance_on_gap: config.settings.rebalance_on_gap,
+        activity_detection: config.settings.activity_detection,
+        intent_window_secs: config.settings.intent_window_secs,
+        market_filter,
+        selection,
+        draining: false,
+        risk: config.risk.clone(),
+        categories: None,
+        ramp: args.ramp_in.map(|span| RampIn::new(span, args.ramp_in_steps)),
+        confirm_initial: (args.live || args.graduate) && !args.yes,
+        build: BuildInfo::new(config.hash()?),
+        clob: None,
+        spend_cap: args.live_test_cap.map(|limit| SpendCap {
+            limit: money::from_f64(limit),
+            baseline: Decimal::ZERO,
+        }),
+    };
+    if !params.engine.filters.categories.is_empty() && args.replay.is_none() {
+        params.categories = Some(EventCategories::new(gamma_client.clone()));
+    }
+    if args.replay.is_some() {
+        if params.engine.depth_limit.is_some() {
+            warn!("[engine.depth_limit] needs order books — ignored in --replay");
+        }
+    } else {
+        let clob = ClobClient::new(&config.endpoints.clob_api, ClobConfig::default())?;
+        params.clob = Some(clob);
+    }
+    let trader_short_id = &trader_address[trader_address.len().saturating_sub(6)..];
 
     let poll_interval_secs = config.settings.poll_interval_secs;
+    let poll_duration = Duration::from_secs(poll_interval_secs);
+    let ws_detection = config.settings.ws_detection;
+    // With WebSocket detection, REST polls (and so completed cycles on a quiet
+    // trader) may be as far apart as the fallback interval
+    let fallback_poll_duration = Duration::from_secs(config.settings.ws_fallback_poll_secs);
+    let cycle_duration = if ws_detection || polygon_ws.is_some() {
+        poll_duration.max(fallback_poll_duration)
+    } else {
+        poll_duration
+    };
+    if let Some(dir) = &args.replay {
+        let replay = replay_session(
+            dir,
+            budget,
+            trader_addr,
+            trader_short_id,
+            &data_client,
+            cycle_duration,
+            &params,
+        );
+        return replay.await;
+    }
+    let mut recorder = args.record.as_deref().map(Recorder::create).transpose()?;
+
     let is_live = args.live;
+    if args.lease.is_some() && args.lease_ttl_secs <= cycle_duration.as_secs() {
+        anyhow::bail!(
+            "--lease-ttl-secs must exceed the poll interval ({}s)",
+            cycle_duration.as_secs()
+        );
+    }
 
-    let mode = if args.dry_run { "dry-run" } else { "live" };
+    let started_at = chrono::Utc::now();
+    let mode = match (args.dry_run, args.graduate) {
+        (true, true) => "dry-run, graduating to live",
+        (true, false) => "dry-run",
+        _ => "live",
+    };
     info!(
         "Starting copytrade ({mode}) — trader={} budget={} copy%={} max_trade%={} poll={}s",
-        args.trader_address, args.budget, args.copy_percentage, args.max_trade_size, poll_interval_secs,
+        trader_address, budget_spec, copy_percentage, max_trade_size, poll_interval_secs,
     );
 
-    let data_client = Client::default();
-    let gamma_client = GammaClient::default();
-    let mut state = TradingState::new(args.budget);
-    let mut seen_hashes: HashSet<String> = HashSet::new();
+    // Unhealthy after three poll intervals without a completed cycle
+    let health = HealthMonitor::new(cycle_duration * 3, budget);
+    if let Some(addr) = args.health_addr {
+        let listener = tokio::net::TcpListener::bind(addr)
+            .await
+            .with_context(|| format!("failed to bind health check address {addr}"))?;
+        tokio::spawn(health::serve(listener, health.clone()));
+    }
+
+    // --- Leader election (hot standby) ---
+    let lease = match &args.lease {
+        Some(path) => {
+            let holder = args.instance_id.clone().unwrap_or_else(default_instance_id);
+            let lease = Lease::new(path, &holder, Duration::from_secs(args.lease_ttl_secs));
+            let standby = wait_for_leadership(
+                &lease,
+                args.wal.as_deref(),
+                &config.persistence,
+                budget,
+                poll_duration,
+                &health,
+            );
+            if !standby.await {
+                return Ok(());
+            }
+            // Renewed off the poll loop, and checked before every order post
+            let lease = Arc::new(lease);
+            lease.spawn_renewal();
+            lease::install_fence(Arc::clone(&lease));
+            Some(lease)
+        }
+        None => None,
+    };
+
+    let mut state = TradingState::new(money::from_f64(budget));
+    if let Some(dir) = &args.artifacts {
+        // Before replaying, so fills re-applied after a crash are in the ledger
+        let bundle = artifacts::bundle_dir(dir, started_at)?;
+        state.attach_ledger(artifacts::LedgerWriter::create(&bundle.join("ledger.csv"))?);
+    }
+    let mut replayed_from_wal = false;
+    let mut journal = args.journal.as_deref().map(Journal::open).transpose()?;
+    if let Some(path) = &args.wal {
+        let mut store = store::open_store(&config.persistence, path, budget)?;
+        let entries = store.load()?;
+        state.attach_store(store);
+        // Before replaying, so entries re-applied after a crash are journaled
+        if let Some(journal) = journal.take() {
+            state.attach_journal(journal, !entries.is_empty());
+        }
+        if !entries.is_empty() {
+            let unacked = state.replay(&entries);
+            replayed_from_wal = true;
+            info!(
+                "Replayed {} WAL entries from {} ({unacked} unacknowledged) — {} holding(s), {} resting order(s), ${:.2} remaining",
+                entries.len(),
+                path.display(),
+                state.holdings.len(),
+                state.resting_orders.len(),
+                state.budget_remaining,
+            );
+        }
+    }
+    if let Some(journal) = journal {
+        state.attach_journal(journal, false);
+    }
+    if args.graduate && state.paper_track.is_none() {
+        // Replayed from --wal, the evaluation carries on from the first start
+        state.commit(Mutation::PaperStart {
+            started_at: clock::now(),
+        });
+    }
+    let mut detections = DetectionConsensus::new();
+    let mut latency = LatencyHistogram::new();
+    // Flag a fetch outage after three poll intervals without a successful fetch
+    let mut gaps = GapDetector::new(TRADE_FETCH_LIMIT as usize, cycle_duration * 3);
+    let mut intents = IntentTracker::new(params.intent_window_secs);
 
     // Authenticate with CLOB if live mode
-    let clob_ctx = if is_live {
-        info!("Authenticating with CLOB API...");
-        let ctx = auth::authenticate(&config.account.private_key).await?;
-        info!("Authenticated — EOA: {} Safe: {}", ctx.eoa, ctx.safe);
-
-        // Cancel any stale orders from previous runs
-        info!("Cancelling stale orders from previous runs...");
-        match ctx.client.cancel_all_orders().await {
-            Ok(resp) => {
-                if !resp.canceled.is_empty() {
-                    info!("Cancelled {} stale order(s)", resp.canceled.len());
+    let mut clob_ctx = if is_live {
+        let ctx = go_live(
+            &config,
+            &data_client,
+            &params,
+            &mut state,
+            budget_spec,
+            replayed_from_wal,
+            &health,
+        );
+        Some(ctx.await?)
+    } else {
+        None
+    };
+
+    let drain_timeout = Duration::from_secs(args.drain_timeout_secs);
+    // Deadlines are `Instant`s, which panic on overflow: refuse spans too long for one
+    Instant::now()
+        .checked_add(drain_timeout)
+        .context("--drain-timeout-secs is too long")?;
+    let mut drain_deadline: Option<Instant> = None;
+    // Scheduled end of the session: draining starts there, like on SIGUSR1
+    let mut session_end = match (args.run_until, args.run_for) {
+        (Some(until), _) => {
+            let left = (until - clock::now()).to_std().ok().filter(|left| !left.is_zero());
+            let left = left.with_context(|| format!("--run-until {until} is in the past"))?;
+            info!("Session ends at {until} (in {}s)", left.as_secs());
+            let end = Instant::now().checked_add(left);
+            Some(end.with_context(|| format!("--run-until {until} is too far away"))?)
+        }
+        (None, Some(span)) => {
+            info!("Session ends in {}s", span.as_secs());
+            Some(Instant::now().checked_add(span).context("--run-for is too long")?)
+        }
+        (None, None) => None,
+    };
+    if args.drain {
+        start_drain(clob_ctx.as_ref(), &mut state, &mut params).await;
+        drain_deadline = Some(Instant::now() + drain_timeout);
+    }
+    let mut drain_requests = drain_requests();
+
+    // --- Initial replication ---
+    // Shadow twin: simulate alongside live execution on the same signals
+    let mut shadow = (args.shadow && clob_ctx.is_some()).then(|| Shadow::new(&state));
+    if let Some(cap) = &mut params.spend_cap {
+        cap.baseline = state.committed_spend();
+        warn!("Live test mode: spending at most ${:.2} on buys this session", cap.limit);
+    }
+    if let Some(ramp) = &mut params.ramp {
+        ramp.restart();
+        info!(
+            "Ramping in over {}s in {} step(s)",
+            ramp.span.as_secs(),
+            ramp.steps
+        );
+    }
+    let mut tap = live_tap(recorder.as_ref(), CycleKind::InitialReplication, false, &[]);
+    initial_replication(
+        &data_client,
+        clob_ctx.as_ref(),
+        trader_addr,
+        trader_short_id,
+        &mut state,
+        shadow.as_mut(),
+        &params,
+        &mut tap,
+    )
+    .await?;
+    save_tap(recorder.as_mut(), tap);
+
+    // --- Seed dedup set ---
+    let mut tap = live_tap(recorder.as_ref(), CycleKind::Seed, true, &[]);
+    seed_detections(&data_client, trader_addr, &params, &mut detections, &mut gaps, &mut tap)
+        .await;
+    save_tap(recorder.as_mut(), tap);
+
+    // --- Polling loop ---
+    // Check if any initial orders are resting (give them a moment to fill)
+    if !state.resting_orders.is_empty() {
+        info!(
+            "Tracking {} resting order(s) from initial replication",
+            state.resting_orders.len()
+        );
+    }
+
+    info!("Entering polling loop (interval: {poll_interval_secs}s). Press Ctrl+C to stop.");
+
+    let mut scheduler = PollScheduler::new(
+        poll_duration,
+        Duration::from_secs(config.settings.max_poll_interval_secs),
+        config.settings.poll_backoff_multiplier,
+    );
+    health.record_poll_interval(scheduler.interval());
+    let mut poll_errors = ErrorBudget::new(config.settings.poll_error_alert_threshold);
+
+    // --- WebSocket trade detection ---
+    // Detected trades are rebalanced on as they arrive; REST polling continues
+    // as a fallback (slower while the feed is connected) and catches up after
+    // feed outages. Both go through `detections`, so each trade acts once.
+    let trader_wallet = trader_addr.to_string();
+    let (mut ws_events, _ws_subscriptions) = if ws_detection {
+        let (feed, subscriptions) = rtds::trades_feed(&config.endpoints.rtds_ws);
+        let (events, _) = feed.spawn();
+        (Some(events), Some(subscriptions))
+    } else {
+        (None, None)
+    };
+    // On-chain detection reads the exchange's fill logs straight from a Polygon
+    // node, ahead of (and independent of) Polymarket's own feeds
+    let (mut chain_events, _chain_subscriptions) = match &polygon_ws {
+        Some(url) => {
+            let (feed, subscriptions) = chain::fills_feed(url, trader_addr);
+            let (events, _) = feed.spawn();
+            (Some(events), Some(subscriptions))
+        }
+        None => (None, None),
+    };
+    let mut ws_live = false;
+    let mut chain_live = false;
+    let mut pending_ws: Vec<RawTrade> = Vec::new();
+    let mut ws_batch_at: Option<Instant> = None;
+    let mut next_poll = Instant::now() + scheduler.interval();
+    let mut pending_overrides: Option<Overrides> = None;
+
+    loop {
+        if drain_deadline.is_some()
+            && state.holdings.is_empty()
+            && state.resting_orders.is_empty()
+        {
+            info!("Drained — no holdings or resting orders left");
+            break;
+        }
+        let rest_poll = tokio::select! {
+            _ = tokio::signal::ctrl_c() => {
+                info!("Shutdown signal received");
+                break;
+            }
+            _ = sleep_until_some(drain_deadline) => {
+                warn!(
+                    "Drain timed out after {}s with {} holding(s) and {} resting order(s) left",
+                    drain_timeout.as_secs(),
+                    state.holdings.len(),
+                    state.resting_orders.len()
+                );
+                break;
+            }
+            _ = sleep_until_some(session_end) => {
+                info!("Scheduled end of session reached");
+                session_end = None;
+                if drain_deadline.is_none() {
+                    start_drain(clob_ctx.as_ref(), &mut state, &mut params).await;
+                    drain_deadline = Some(Instant::now() + drain_timeout);
+                }
+                continue;
+            }
+            Some(()) = drain_requests.recv() => {
+                if drain_deadline.is_none() {
+                    start_drain(clob_ctx.as_ref(), &mut state, &mut params).await;
+                    drain_deadline = Some(Instant::now() + drain_timeout);
+                }
+                continue;
+            }
+            _ = tokio::time::sleep_until(next_poll) => true,
+            _ = sleep_until_some(ws_batch_at) => false,
+            _ = fills_changed(clob_ctx.as_ref()) => {
+                if let Some(fills) = clob_ctx.as_ref().and_then(|ctx| ctx.fills.as_ref())
+                    && fills.is_connected()
+                    && !fills.needs_resync()
+                {
+                    executor::resolve_resting_from_fills(fills, &mut state);
+                }
+                continue;
+            }
+            Some(event) = recv_ws(&mut ws_events) => {
+                match event {
+                    WsEvent::Message(msg) => {
+                        if let Some(trade) = rtds::parse_trade(&msg, &trader_wallet)
+                            .as_ref()
+                            .and_then(RawTrade::from_rtds)
+                            && let Some(detection) = detections.observe(
+                                DetectionSource::Ws,
+                                &trade.tx_hash,
+                                trade.timestamp,
+                                clock::now(),
+                            )
+                        {
+                            info!(
+                                "Trade {} detected via {} ({:.1}s after execution)",
+                                detection.tx_hash, detection.source, detection.latency_secs
+                            );
+                            if params.rest_reports(&trade, &state) {
+                                data_client.note_trade(trade.timestamp);
+                            }
+                            pending_ws.push(trade);
+                            ws_batch_at.get_or_insert_with(|| Instant::now() + WS_BATCH_WINDOW);
+                        }
+                    }
+                    WsEvent::Connected => {
+                        ws_live = true;
+                        info!(
+                            "Trade detection via WebSocket; REST fallback every {}s",
+                            fallback_poll_duration.as_secs().max(poll_interval_secs)
+                        );
+                    }
+                    WsEvent::Reconnected { .. } => {
+                        ws_live = true;
+                        // Catch up on trades made during the outage
+                        next_poll = Instant::now();
+                    }
+                    WsEvent::Disconnected { .. } => {
+                        ws_live = false;
+                        warn!(
+                            "WebSocket trade detection down — polling every {:.1}s",
+                            scheduler.interval().as_secs_f64()
+                        );
+                        if !chain_live {
+                            next_poll = next_poll.min(Instant::now() + scheduler.interval());
+                        }
+                    }
+                }
+                health.record_ws_connected(ws_live || chain_live);
+                continue;
+            }
+            Some(event) = recv_ws(&mut chain_events) => {
+                match event {
+                    WsEvent::Message(msg) => {
+                        if let Some(trade) = chain::parse_fill(&msg, trader_addr)
+                            .map(|fill| RawTrade::from_chain(&fill, clock::now().timestamp()))
+                            && let Some(detection) = detections.observe(
+                                DetectionSource::Chain,
+                                &trade.tx_hash,
+                                trade.timestamp,
+                                clock::now(),
+                            )
+                        {
+                            info!(
+                                "Trade {} detected via {} ({:.1}s after execution)",
+                                detection.tx_hash, detection.source, detection.latency_secs
+                            );
+                            if params.rest_reports(&trade, &state) {
+                                data_client.note_trade(trade.timestamp);
+                            }
+                            pending_ws.push(trade);
+                            ws_batch_at.get_or_insert_with(|| Instant::now() + WS_BATCH_WINDOW);
+                        }
+                    }
+                    WsEvent::Connected => {
+                        chain_live = true;
+                        info!("Trade detection via Polygon OrderFilled logs");
+                    }
+                    WsEvent::Reconnected { .. } => {
+                        chain_live = true;
+                        // Logs aren't replayed: catch up on the outage over REST
+                        next_poll = Instant::now();
+                    }
+                    WsEvent::Disconnected { .. } => {
+                        chain_live = false;
+                        warn!("On-chain trade detection down");
+                        if !ws_live {
+                            next_poll = next_poll.min(Instant::now() + scheduler.interval());
+                        }
+                    }
+                }
+                health.record_ws_connected(ws_live || chain_live);
+                continue;
+            }
+        };
+        ws_batch_at = None;
+        let detector_live = ws_live || chain_live;
+        if rest_poll {
+            next_poll =
+                Instant::now() + poll_delay(&scheduler, detector_live, fallback_poll_duration);
+        }
+        if lease.as_ref().is_some_and(|lease| lease.is_lost()) {
+            warn!("Lost leader lease to another instance — stopping");
+            break;
+        }
+        if let Some(until) = state.paused_until
+            && clock::now() >= until
+        {
+            info!("Daily loss pause over — resuming buys");
+            state.paused_until = None;
+        }
+        if let Some(path) = &args.overrides
+            && pending_overrides.is_none()
+        {
+            match Overrides::take(path) {
+                Ok(Some(overrides)) => {
+                    info!("Read manual overrides from {}", path.display());
+                    overrides.apply_controls(&mut state);
+                    pending_overrides = Some(overrides);
+                }
+                Ok(None) => {}
+                Err(e) => warn!("Ignoring manual overrides: {e:#}"),
+            }
+        }
+        let pushed = std::mem::take(&mut pending_ws);
+        let mut tap = live_tap(recorder.as_ref(), CycleKind::Poll, rest_poll, &pushed);
+        let outcome = poll_cycle(
+            &data_client,
+            &gamma_client,
+            clob_ctx.as_ref(),
+            trader_addr,
+            trader_short_id,
+            &mut state,
+            &mut detections,
+            &mut latency,
+            &mut gaps,
+            &mut intents,
+            rest_poll,
+            &pushed,
+            prices.as_ref(),
+            shadow.as_mut(),
+            &params,
+            &mut pending_overrides,
+            &mut tap,
+        ).await;
+        save_tap(recorder.as_mut(), tap);
+        match outcome {
+            Ok(sample) => {
+                if let Some(alert) = poll_errors.on_success(clock::now()) {
+                    info!("{}", alert.message);
+                    reporter::report_alert(&alert);
+                }
+                health.record_data_api(Ok(()));
+                health.record_data_api_endpoint(data_client.active());
+                health.record_cycle(money::to_f64(state.budget_remaining));
+                if let Some(sample) = &sample {
+                    milestone_prices = sample.prices.clone();
+                }
+                health.record_resting_orders(
+                    state.resting_order_status(&milestone_prices, clock::now()),
+                );
+                if let Some(threshold) = params.risk.lock_profit_usd
+                    && let Some(amount) = state.lock_profit(money::from_f64(threshold))
+                {
+                    info!(
+                        "Locked ${amount:.2} of realized profit (${:.2} locked in total)",
+                        state.locked_profit
+                    );
+                }
+                // Dry-run only, also once --graduate has switched to live
+                if params.engine.merge_complete_sets && clob_ctx.is_none() {
+                    for (title, shares) in state.merge_sets() {
+                        info!("Merged {shares:.2} complete set(s) of \"{title}\" into USDC");
+                    }
+                }
+                if let Some(sample) = sample
+                    && state.paper_track.is_some()
+                {
+                    state.commit(Mutation::PaperSample { sample });
+                }
+                if scheduler.is_backing_off() {
+                    let interval = scheduler.on_success();
+                    info!("Poll interval recovering: {:.1}s", interval.as_secs_f64());
                 }
             }
             Err(e) => {
-                warn!("Failed to cancel stale orders: {e}");
+                let err = format!("{e:#}");
+                let interval = scheduler.on_error(backoff::is_rate_limited(&err));
+                warn!(
+                    "Poll cycle error: {e} — next poll in {:.1}s",
+                    interval.as_secs_f64()
+                );
+                if let Some(alert) = poll_errors.on_error(&err, clock::now()) {
+                    error!("{}", alert.message);
+                    reporter::report_alert(&alert);
+                }
+                health.record_data_api(Err(err));
+                // Retry the WebSocket detections with the next cycle
+                pending_ws.extend(pushed);
             }
         }
+        if rest_poll {
+            next_poll =
+                Instant::now() + poll_delay(&scheduler, detector_live, fallback_poll_duration);
+        }
+        if !pending_ws.is_empty() {
+            // Failed WebSocket detections retry at the regular interval
+            next_poll = next_poll.min(Instant::now() + scheduler.interval());
+        }
+        health.record_poll_interval(scheduler.interval());
+        ratelimit::warn_near_limits(RATE_LIMIT_WARN_THRESHOLD);
+        if milestones.is_enabled() {
+            if let Some(prices) = &prices {
+                prices.overlay(&mut milestone_prices);
+            }
+            for alert in milestones.observe(&state, &milestone_prices, clock::now()) {
+                info!("Milestone: {}", alert.message);
+                reporter::report_alert(&alert);
+            }
+        }
+        let graduation = state
+            .paper_track
+            .as_ref()
+            .filter(|_| !params.draining && std::time::Instant::now() >= next_graduation_attempt)
+            .map(|track| {
+                let status = track.evaluate(&config.graduation, clock::now());
+                (status, track.prices().clone())
+            });
+        if let Some((status, paper_prices)) = graduation
+            && status.is_met()
+        {
+            info!(
+                "Graduation criteria met after {:.1} days — switching to live",
+                status.days
+            );
+            // Simulated holdings aren't real: the live session starts from the
+            // Safe wallet and replicates the trader's current portfolio
+            let mut live_state = TradingState::new(money::from_f64(budget));
+            if let Some(path) = &args.wal {
+                // The paper log stays as it is; the live session gets its own
+                let live_wal = graduated_wal(path);
+                let store = store::open_store(&config.persistence, &live_wal, budget)?;
+                live_state.attach_store(store);
+            }
+            let live = go_live(
+                &config,
+                &data_client,
+                &params,
+                &mut live_state,
+                budget_spec,
+                false,
+                &health,
+            );
+            match live.await {
+                Ok(ctx) => {
+                    reporter::report_mode_transition(&ModeTransitionEvent {
+                        timestamp: clock::now().to_rfc3339(),
+                        from: TradingMode::DryRun,
+                        to: TradingMode::Live,
+                        graduation: status,
+                        paper_summary: state.exit_summary(&paper_prices),
+                    });
+                    if let Some(ledger) = state.take_ledger() {
+                        live_state.attach_ledger(ledger);
+                    }
+                    state = live_state;
+                    clob_ctx = Some(ctx);
+                    if params.engine.merge_complete_sets {
+                        warn!("[engine] merge_complete_sets is dry-run only — no more merges");
+                    }
+                    milestones = MilestoneTracker::new(config.milestones.clone());
+                    latency = LatencyHistogram::new();
+                    if let Some(ramp) = &mut params.ramp {
+                        ramp.restart();
+                    }
+                    let kind = CycleKind::InitialReplication;
+                    let mut tap = live_tap(recorder.as_ref(), kind, false, &[]);
+                    initial_replication(
+                        &data_client,
+                        clob_ctx.as_ref(),
+                        trader_addr,
+                        trader_short_id,
+                        &mut state,
+                        None,
+                        &params,
+                        &mut tap,
+                    )
+                    .await?;
+                    save_tap(recorder.as_mut(), tap);
+                }
+                Err(e) => {
+                    warn!(
+                        "Switch to live failed: {e:#} — staying in dry-run, retrying in {} min",
+                        GRADUATION_RETRY_INTERVAL.as_secs() / 60
+                    );
+                    if let Some(path) = &args.wal {
+                        // A restart would otherwise resume live from it
+                        drop(live_state);
+                        store::remove_store(&config.persistence, &graduated_wal(path))?;
+                    }
+                    next_graduation_attempt =
+                        std::time::Instant::now() + GRADUATION_RETRY_INTERVAL;
+                }
+            }
+        }
+        if rebase_budget_daily
+            && last_budget_rebase.elapsed() >= BUDGET_REBASE_INTERVAL
+            && let Some(ctx) = &clob_ctx
+        {
+            let filter = params.market_filter.as_ref();
+            match wallet_capital(&data_client, ctx, &state, filter).await {
+                Ok(capital) => {
+                    // Locked profit stays out of the budget
+                    let capital = capital - money::to_f64(state.locked_profit);
+                    let resolved = money::from_f64(budget_spec.resolve(capital));
+                    info!(
+                        "Re-based budget: {budget_spec} of ${capital:.2} = ${resolved:.2} (was ${:.2})",
+                        state.initial_budget
+                    );
+                    state.commit(Mutation::RebaseBudget { budget: resolved });
+                }
+                Err(e) => warn!(
+                    "Budget re-base failed, keeping ${:.2}: {e:#}",
+                    state.initial_budget
+                ),
+            }
+            last_budget_rebase = std::time::Instant::now();
+        }
+        if last_clock_check.elapsed() >= clock_check_interval {
+            clock::sync(&config.endpoints.clob_api, max_clock_skew).await;
+            last_clock_check = std::time::Instant::now();
+        }
+    }
+
+    // --- Cancel resting orders on shutdown (live mode) ---
+    if let Some(ctx) = &clob_ctx {
+        cancel_resting(ctx, &mut state, |_| true).await;
+    }
 
-        // Seed holdings from actual Safe wallet positions
-        let mut seeded_prices: HashMap<String, f64> = HashMap::new();
+    state.journal_snapshot();
+
+    // --- Exit summary ---
+    info!("Computing exit summary...");
+    let final_positions =
+        data_client.fetch_active_positions(trader_addr, params.market_filter.as_ref());
+    let final_positions = match final_positions.await {
+        Ok(positions) => positions,
+        Err(e) => {
+            warn!("Failed to fetch final positions for exit summary: {e}");
+            Vec::new()
+        }
+    };
+    let active_prices = build_price_map(&final_positions);
+    let held_assets: Vec<String> = state.holdings.keys().cloned().collect();
+    let mut latest_prices =
+        build_exit_price_map(&gamma_client, &active_prices, &held_assets).await?;
+    if let Some(prices) = &prices {
+        prices.overlay(&mut latest_prices);
+    }
+    let mut summary = state.exit_summary(&latest_prices);
+
+    // Resolution-risk report: end dates from the trader's positions, else gamma
+    let mut end_dates: HashMap<String, chrono::DateTime<chrono::Utc>> = final_positions
+        .iter()
+        .filter_map(|p| Some((p.asset.to_string(), parse_end_date(&p.end_date)?)))
+        .collect();
+    let missing: Vec<String> = held_assets
+        .iter()
+        .filter(|a| !end_dates.contains_key(a.as_str()))
+        .cloned()
+        .collect();
+    end_dates.extend(fetch_gamma_end_dates(&gamma_client, &missing).await);
+    summary.resolution_risk = state.resolution_risk(&latest_prices, &end_dates, clock::now());
+    summary.resting_orders = state.resting_order_status(&latest_prices, clock::now());
+    summary.copy_latency = latency.summary();
+    summary.detection = detections.summary();
+    summary.shadow = shadow.map(|s| s.summary(&state, &latest_prices));
+    summary.build = Some(params.build.clone());
+    reporter::report_exit_summary(&summary);
+    if let Some(dir) = &args.artifacts {
+        let run = artifacts::RunInfo::new(mode, &trader_address, started_at, replayed_from_wal);
+        match artifacts::write_bundle(dir, &run, &summary, &config) {
+            Ok(bundle) => info!("Wrote session artifacts to {}", bundle.display()),
+            Err(e) => warn!("Failed to write session artifacts: {e:#}"),
+        }
+    }
+
+    if let Some(lease) = &lease
+        && let Err(e) = lease.release()
+    {
+        warn!("Failed to release leader lease: {e:#}");
+    }
+
+    Ok(())
+}
+
+/// Lease holder identity when `--instance-id` isn't given.
+/// The log a `--graduate` session keeps once live, beside its paper `--wal`.
+fn graduated_wal(wal: &Path) -> PathBuf {
+    let mut name = wal.file_name().unwrap_or_default().to_os_string();
+    name.push(".live");
+    wal.with_file_name(name)
+}
+
+fn default_instance_id() -> String {
+    let host = std::env::var("HOSTNAME").unwrap_or_else(|_| "copytrade".to_string());
+    format!("{host}-{}", std::process::id())
+}
+
+/// Run as a hot standby until `lease` is acquired. Returns `false` on Ctrl+C.
+///
+/// While waiting, the leader's write-ahead log (if shared) is replayed read-only
+/// each interval so the standby reports the state it will take over with. Each
+/// wait counts as a cycle for the health check, so a healthy standby isn't restarted.
+/// `copytrade stress`: rebuild the state from `wal`, mark its holdings at
+/// gamma prices (their average cost where gamma has none), and print the
+/// `stress::run` report of the standard scenarios.
+async fn run_stress(wal: &Path, tag: &str, gap_pct: f64) -> Result<()> {
+    if !(0.0..=100.0).contains(&gap_pct) {
+        anyhow::bail!("--gap-pct must be between 0 and 100");
+    }
+    let config = AppConfig::load(Path::new(CONFIG_PATH))?;
+    let budget = store::stored_budget(&config.persistence, wal)?
+        .with_context(|| format!("{} is not a WAL with a header", wal.display()))?;
+    let entries = store::read_entries(&config.persistence, wal, budget)?;
+    let restore = || {
+        let mut state = TradingState::new(money::from_f64(budget));
+        state.replay(&entries);
+        state
+    };
+    let state = restore();
+    if state.holdings.is_empty() {
+        anyhow::bail!("{} holds no positions", wal.display());
+    }
+
+    let gamma = GammaClient::new(&config.endpoints.gamma_api)?;
+    let held: Vec<String> = state.holdings.keys().cloned().collect();
+    let mut prices = fetch_gamma_prices(&gamma, &held).await?;
+    for (asset, holding) in &state.holdings {
+        if !prices.contains_key(asset) {
+            warn!("No gamma price for \"{}\" — marking at its average cost", holding.title);
+            prices.insert(asset.clone(), money::to_f64(holding.avg_cost));
+        }
+    }
+    let scenarios = stress::Scenario::standard(tag, gap_pct);
+    let report = stress::run(restore, &prices, &scenarios, &config.engine);
+    println!("{}", serde_json::to_string_pretty(&report)?);
+    Ok(())
+}
+
+/// Authenticate with the CLOB, seed holdings from the Safe wallet (unless
+/// restored from the WAL), cancel or adopt orders from previous runs, and check
+/// the wallet covers the budget.
+async fn go_live(
+    config: &AppConfig,
+    data_client: &DataApi,
+    params: &CopyParams,
+    state: &mut TradingState,
+    budget_spec: BudgetSpec,
+    replayed_from_wal: bool,
+    health: &HealthMonitor,
+) -> Result<ClobContext> {
+    info!("Authenticating with CLOB API...");
+    let mut ctx =
+        auth::authenticate(&config.account.private_key, &config.endpoints.clob_api).await?;
+    info!("Authenticated — EOA: {} Safe: {}", ctx.eoa, ctx.safe);
+    health.record_clob_auth(Ok(()));
+    if config.settings.ws_fills {
+        let (fills, _) = FillBook::spawn(&config.endpoints.clob_user_ws, &ctx.credentials);
+        info!("Tracking fills on the CLOB user channel");
+        ctx.fills = Some(fills);
+    }
+
+    // Book orders a crash left unsettled before their open orders are cancelled
+    executor::reconcile_pending_orders(&ctx, state).await;
+
+    // Seed holdings from actual Safe wallet positions (unless the WAL already rebuilt them)
+    let mut seeded_prices: HashMap<String, f64> = HashMap::new();
+    if replayed_from_wal {
+        info!("Skipping Safe wallet seeding — holdings restored from WAL");
+    } else {
         info!("Fetching existing Safe wallet positions...");
-        match fetch_active_positions(&data_client, ctx.safe).await {
+        // Only seed holdings in the copied markets so unrelated positions are left alone
+        let safe_positions =
+            data_client.fetch_active_positions(ctx.safe, params.market_filter.as_ref());
+        match safe_positions.await {
             Ok(positions) => {
                 if !positions.is_empty() {
                     info!(
@@ -132,26 +1772,24 @@ async fn main() -> Result<()> {
                         positions.len()
                     );
                     for pos in &positions {
-                        let shares = pos.size.to_f64().unwrap_or(0.0);
-                        let avg_cost = pos.avg_price.to_f64().unwrap_or(0.0);
+                        let shares = pos.size;
+                        let avg_cost = pos.avg_price;
                         let cur_price = pos.cur_price.to_f64().unwrap_or(0.0);
                         let total_cost = shares * avg_cost;
                         let asset = pos.asset.to_string();
 
                         seeded_prices.insert(asset.clone(), cur_price);
-                        state.holdings.insert(
-                            asset.clone(),
-                            HeldPosition {
+                        state.commit(Mutation::SeedHolding {
+                            position: HeldPosition {
                                 asset,
                                 title: pos.title.clone(),
                                 outcome: pos.outcome.clone(),
                                 shares,
                                 total_cost,
                                 avg_cost,
+                                opposite_asset: pos.opposite_asset.clone(),
                             },
-                        );
-                        state.budget_remaining -= total_cost;
-                        state.total_spent += total_cost;
+                        });
                     }
                     info!(
                         "Seeded {} holding(s) (${:.2} committed, ${:.2} remaining)",
@@ -161,272 +1799,1071 @@ async fn main() -> Result<()> {
                     );
                 }
             }
-            Err(e) => {
-                warn!("Failed to fetch Safe wallet positions: {e}");
-            }
+            Err(e) => {
+                warn!("Failed to fetch Safe wallet positions: {e}");
+            }
+        }
+    }
+
+    // Orders from previous runs, after seeding so held markets count as ours
+    match params.executor.startup_orders {
+        StartupOrders::CancelAll => {
+            info!("Cancelling stale orders from previous runs...");
+            ratelimit::acquire_clob("clob:DELETE /cancel-all").await;
+            match ctx.client.cancel_all_orders().await {
+                Ok(resp) => {
+                    if !resp.canceled.is_empty() {
+                        info!("Cancelled {} stale order(s)", resp.canceled.len());
+                    }
+                }
+                Err(e) => {
+                    warn!("Failed to cancel stale orders: {e}");
+                }
+            }
         }
-
-        // Check balance + holdings current value >= budget
-        let balance = executor::check_balance(&ctx).await?;
-        let holdings_value: f64 = state
-            .holdings
-            .iter()
-            .map(|(asset, h)| {
-                // Use seeded_prices (cur_price from data API) if available, fall back to avg_cost
-                let price = seeded_prices.get(asset).copied().unwrap_or(h.avg_cost);
-                h.shares * price
-            })
-            .sum();
-        let total_capital = balance + holdings_value;
-        info!("USDC balance: ${balance:.2}, holdings value: ${holdings_value:.2}, total: ${total_capital:.2}");
-        if total_capital < args.budget {
-            anyhow::bail!(
-                "Insufficient capital: ${total_capital:.2} (${balance:.2} cash + ${holdings_value:.2} holdings) but --budget is ${:.2}",
-                args.budget
-            );
+        StartupOrders::Adopt => {
+            let filter = params.market_filter.as_ref();
+            executor::adopt_open_orders(&ctx, state, &params.executor, filter).await;
         }
+    }
 
-        Some(ctx)
-    } else {
-        None
-    };
+    // Check balance + holdings current value >= budget
+    let balance = executor::check_balance(&ctx).await?;
+    // Use seeded_prices (cur_price from data API) if available, fall back to avg_cost
+    let holdings_value = holdings_value(state, &seeded_prices);
+    let total_capital = balance + holdings_value;
+    info!("USDC balance: ${balance:.2}, holdings value: ${holdings_value:.2}, total: ${total_capital:.2}");
+    if let BudgetSpec::WalletPct(_) = budget_spec {
+        let resolved = budget_spec.resolve(total_capital);
+        info!("Budget: {budget_spec} = ${resolved:.2}");
+        state.commit(Mutation::RebaseBudget {
+            budget: money::from_f64(resolved),
+        });
+    }
+    let budget = money::to_f64(state.initial_budget);
+    if total_capital < budget {
+        anyhow::bail!(
+            "Insufficient capital: ${total_capital:.2} (${balance:.2} cash + ${holdings_value:.2} holdings) but --budget is ${budget:.2}"
+        );
+    }
 
-    // --- Initial replication ---
+    Ok(ctx)
+}
+
+/// Replicate the trader's current portfolio in one step, straight to the target.
+#[allow(clippy::too_many_arguments)]
+async fn initial_replication(
+    data_client: &DataApi,
+    clob_ctx: Option<&ClobContext>,
+    trader_addr: Address,
+    trader_short_id: &str,
+    state: &mut TradingState,
+    mut shadow: Option<&mut Shadow>,
+    params: &CopyParams,
+    tap: &mut Tap,
+) -> Result<()> {
     info!("Fetching trader portfolio...");
-    match fetch_active_positions(&data_client, trader_addr).await {
+    let filter = params.trader_position_filter();
+    match tap.positions(data_client.fetch_active_positions(trader_addr, filter)).await {
         Ok(positions) => {
+            let verdicts = params.category_verdicts(&positions, tap).await;
+            let (positions, copy_pct) = params.select(positions);
             if positions.is_empty() {
                 warn!("Trader has no active (unresolved) positions");
             } else {
                 info!("Found {} active positions", positions.len());
-                let weights = compute_weights(&positions);
-                let prices = build_price_map(&positions);
-                let running_budget = state.effective_capital(&prices);
-                let targets =
-                    compute_target_state(&weights, running_budget, copy_pct, max_trade_pct);
-                let orders = compute_orders(
-                    &targets,
-                    &state,
-                    state.budget_remaining,
+                // Initial replication always goes straight to the target
+                let engine = EngineParams {
+                    rebalance_fraction: 1.0,
+                    ..params.cycle_engine(verdicts, Vec::new())
+                };
+                params.load_market_rules(&positions, state).await;
+                let (orders, skipped_orders) = plan_rebalance_with_skips(
+                    &positions,
+                    state,
                     &HashMap::new(),
+                    copy_pct,
+                    params.max_trade_pct,
                     trader_short_id,
+                    &engine,
                 );
+                if let Some(shadow) = shadow.as_deref_mut() {
+                    shadow.set_market_rules(state);
+                    shadow_rebalance(
+                        shadow,
+                        clob_ctx,
+                        &positions,
+                        &HashMap::new(),
+                        copy_pct,
+                        trader_short_id,
+                        params,
+                        &engine,
+                    )
+                    .await;
+                }
+                let orders = order_pipeline(orders, state, clob_ctx, None, params).await;
+                if params.confirm_initial && clob_ctx.is_some() && !orders.is_empty() {
+                    confirm_orders(&orders).await?;
+                }
 
-                let execution_results = if let Some(ctx) = &clob_ctx {
-                    let results = executor::execute_orders(ctx, &orders).await;
-                    state.apply_execution_results(&orders, &results);
+                let execution_results = if let Some(ctx) = clob_ctx {
+                    let results =
+                        executor::submit_orders(ctx, state, &orders, &params.executor).await;
+                    if let Some(shadow) = shadow {
+                        shadow.record_execution(&orders, &results);
+                    }
                     Some(results)
                 } else {
-                    state.apply_orders(&orders);
+                    state.commit(Mutation::ApplyOrders {
+                        orders: charge_taker_fees(&orders, state),
+                    });
                     None
                 };
 
                 let event = CopytradeEvent {
-                    timestamp: chrono::Utc::now().to_rfc3339(),
+                    timestamp: clock::now().to_rfc3339(),
+                    build: params.build.clone(),
+                    strategy_id: params.executor.strategy_id.clone(),
                     trigger: EventTrigger::InitialReplication,
                     detected_trade_hashes: vec![],
+                    detected_trade_timestamps: vec![],
+                    trader_trades: vec![],
+                    trader_intents: vec![],
+                    position_changes: vec![],
+                    copy_latency_secs: None,
                     orders,
-                    budget_remaining: state.budget_remaining,
-                    total_spent: state.total_spent,
+                    skipped_orders,
+                    budget_remaining: money::to_f64(state.budget_remaining),
+                    total_spent: money::to_f64(state.total_spent),
                     execution_results,
                 };
                 reporter::report_event(&event);
-                state.total_events += 1;
+                state.record_event(&event);
             }
         }
         Err(e) => {
             warn!("Failed to fetch positions: {e}");
         }
     }
+    Ok(())
+}
 
-    // --- Seed dedup set ---
+/// Print the planned live orders and their totals, and ask before placing
+/// them. Errors when declined, or when there is no terminal to ask on.
+async fn confirm_orders(orders: &[SimulatedOrder]) -> Result<()> {
+    use std::io::{BufRead, Write};
+
+    if !std::io::stdin().is_terminal() {
+        anyhow::bail!("stdin is not a terminal: pass --yes to place the initial orders unattended");
+    }
+    let mut stderr = std::io::stderr().lock();
+    writeln!(stderr, "\nInitial replication — {} order(s):", orders.len())?;
+    for o in orders {
+        writeln!(
+            stderr,
+            "  {:<4} {:>10.2} @ {:.4}  ${:>9.2}  {} ({})",
+            if o.side == OrderSide::Buy { "BUY" } else { "SELL" },
+            o.shares,
+            o.price,
+            o.cost_usd,
+            o.market.title,
+            o.market.outcome
+        )?;
+    }
+    let total = |side| -> Decimal {
+        orders.iter().filter(|o| o.side == side).map(|o| o.cost_usd).sum()
+    };
+    writeln!(
+        stderr,
+        "Total: ${:.2} to spend, ${:.2} from sells",
+        total(OrderSide::Buy),
+        total(OrderSide::Sell)
+    )?;
+    write!(stderr, "Place these orders? [y/N] ")?;
+    stderr.flush()?;
+    drop(stderr);
+
+    let answer = tokio::task::spawn_blocking(|| {
+        let mut line = String::new();
+        std::io::stdin().lock().read_line(&mut line).map(|_| line)
+    })
+    .await??;
+    if !matches!(answer.trim().to_lowercase().as_str(), "y" | "yes") {
+        anyhow::bail!("Initial replication declined, no orders placed");
+    }
+    Ok(())
+}
+
+/// Seed the dedup set (and the gap detector) with the trader's recent trades,
+/// so only later trades trigger rebalances.
+async fn seed_detections(
+    data_client: &DataApi,
+    trader_addr: Address,
+    params: &CopyParams,
+    detections: &mut DetectionConsensus,
+    gaps: &mut GapDetector,
+    tap: &mut Tap,
+) {
     info!("Seeding dedup set from recent trades...");
-    match fetch_recent_trades(&data_client, trader_addr, 50).await {
-        Ok(trades) => {
-            for trade in &trades {
-                seen_hashes.insert(format!("{}", trade.transaction_hash));
+    match fetch_detections(data_client, trader_addr, params, tap).await {
+        Ok(events) => {
+            for (hash, _, _) in &events {
+                detections.seed(hash.clone());
             }
-            info!("Seeded {} trade hashes", seen_hashes.len());
+            let timestamps: Vec<i64> = events.iter().map(|(_, ts, _)| *ts).collect();
+            gaps.observe(clock::now(), &timestamps, 0);
+            info!("Seeded {} trade hashes", detections.len());
         }
         Err(e) => {
             warn!("Failed to seed trades: {e}");
         }
     }
+}
 
-    // --- Polling loop ---
-    // Check if any initial orders are resting (give them a moment to fill)
-    if !state.resting_orders.is_empty() {
+/// The trader's recent trades as `(tx hash, timestamp, event)`, from `/trades`,
+/// or from `/activity` with `params.activity_detection` (which adds their
+/// splits, merges, redemptions and conversions). `None` events are trades with
+/// an unknown side.
+async fn fetch_detections(
+    client: &DataApi,
+    addr: Address,
+    params: &CopyParams,
+    tap: &mut Tap,
+) -> Result<Vec<(String, i64, Option<ActivityEvent>)>> {
+    let filter = params.market_filter.as_ref();
+    if params.activity_detection {
+        let activity = tap
+            .activity(client.fetch_recent_activity(addr, TRADE_FETCH_LIMIT, filter))
+            .await?;
+        Ok(activity
+            .iter()
+            .filter_map(ActivityEvent::from_activity)
+            .map(|e| (e.tx_hash().to_string(), e.timestamp(), Some(e)))
+            .collect())
+    } else {
+        let trades = tap
+            .trades(client.fetch_recent_trades(addr, TRADE_FETCH_LIMIT, filter))
+            .await?;
+        Ok(trades
+            .iter()
+            .map(|t| {
+                let event = RawTrade::from_rest(t).map(ActivityEvent::Trade);
+                (t.transaction_hash.to_string(), t.timestamp, event)
+            })
+            .collect())
+    }
+}
+
+/// Prices of the held assets: live marks where known, gamma for the rest.
+async fn held_price_map(
+    gamma: &GammaClient,
+    state: &TradingState,
+    prices: Option<&PriceCache>,
+) -> Result<HashMap<String, f64>> {
+    let held_assets: Vec<String> = state.holdings.keys().cloned().collect();
+    let live: HashMap<String, f64> = held_assets
+        .iter()
+        .filter_map(|asset| Some((asset.clone(), prices?.price(asset)?)))
+        .collect();
+    build_exit_price_map(gamma, &live, &held_assets).await
+}
+
+/// Sell the holdings past a `[risk]` stop-loss or take-profit, independently
+/// of the trader, and block re-buys of them for `rebuy_cooldown_secs`.
+async fn apply_risk_exits(
+    clob_ctx: Option<&ClobContext>,
+    state: &mut TradingState,
+    price_map: &HashMap<String, f64>,
+    params: &CopyParams,
+    trader_short_id: &str,
+) {
+    let exits = risk::plan_exits(state, price_map, &params.risk);
+    if exits.is_empty() {
+        return;
+    }
+    let cooldown = Duration::from_secs(params.engine.rebuy_cooldown_secs);
+    for exit in &exits {
+        let order = &exit.order;
         info!(
-            "Tracking {} resting order(s) from initial replication",
-            state.resting_orders.len()
+            "[{trader_short_id}] {} on \"{}\" ({}): {:+.1}% from average cost — selling {:.2} shares at {:.4}",
+            exit.rule,
+            order.market.title,
+            order.market.outcome,
+            exit.move_pct,
+            order.shares,
+            order.price
         );
+        state.block_rebuy(&order.market.asset, cooldown);
     }
+    let orders: Vec<SimulatedOrder> = exits.into_iter().map(|exit| exit.order).collect();
+    execute_own_orders(clob_ctx, state, orders, EventTrigger::RiskExit, params).await;
+}
 
-    info!("Entering polling loop (interval: {poll_interval_secs}s). Press Ctrl+C to stop.");
-    let poll_duration = Duration::from_secs(poll_interval_secs);
+/// Trip the `[risk] max_drawdown_pct` kill switch once effective capital falls
+/// that far below its peak: buys stop for good, resting orders are cancelled,
+/// and with `liquidate_on_kill` every holding is sold.
+async fn check_kill_switch(
+    clob_ctx: Option<&ClobContext>,
+    state: &mut TradingState,
+    price_map: &HashMap<String, f64>,
+    params: &CopyParams,
+) {
+    let Some(max_drawdown_pct) = params.risk.max_drawdown_pct else {
+        return;
+    };
+    let capital = money::to_f64(state.effective_capital(price_map));
+    let drawdown_pct = state.record_capital(capital);
+    if state.killed || drawdown_pct < max_drawdown_pct {
+        return;
+    }
+    warn!(
+        "Kill switch: capital ${capital:.2} is {drawdown_pct:.1}% below its peak ${:.2} \
+         (max {max_drawdown_pct}%) — no more buys",
+        state.peak_capital
+    );
+    state.trip_kill_switch(drawdown_pct);
+    if let Some(ctx) = clob_ctx {
+        cancel_resting(ctx, state, |_| true).await;
+    }
+    let orders = if params.risk.liquidate_on_kill {
+        risk::liquidation_orders(state, price_map)
+    } else {
+        Vec::new()
+    };
+    execute_own_orders(clob_ctx, state, orders, EventTrigger::KillSwitch, params).await;
+}
 
-    loop {
-        tokio::select! {
-            _ = tokio::signal::ctrl_c() => {
-                info!("Shutdown signal received");
-                break;
+/// Pause buys until the next UTC day once today's P&L falls below
+/// `-[risk] max_daily_loss_usd`, with one alert. Sells, risk exits and resting
+/// order checks go on during the pause.
+fn check_daily_loss(
+    state: &mut TradingState,
+    price_map: &HashMap<String, f64>,
+    params: &CopyParams,
+) {
+    let Some(max_loss) = params.risk.max_daily_loss_usd else {
+        return;
+    };
+    let now = clock::now();
+    let capital = money::to_f64(state.effective_capital(price_map));
+    let pnl = state.daily_pnl(capital, now);
+    if pnl > -max_loss || state.buys_paused(now.timestamp_millis()) {
+        return;
+    }
+    let until = state.pause_for_day(now);
+    let message = format!(
+        "Buys paused until {}: P&L today ${pnl:+.2} hit the daily loss limit (${max_loss:.2})",
+        until.format("%Y-%m-%d %H:%M UTC")
+    );
+    warn!("{message}");
+    reporter::report_alert(&AlertEvent {
+        timestamp: now.to_rfc3339(),
+        severity: AlertSeverity::Warning,
+        kind: AlertKind::DailyLossPause,
+        message,
+    });
+}
+
+/// Execute (or, in dry-run, apply) orders the bot decided on by itself, and
+/// report them as a `trigger` event. These are risk exits, so they take
+/// `[executor] risk_exit_slippage_pct` as their slippage cap.
+async fn execute_own_orders(
+    clob_ctx: Option<&ClobContext>,
+    state: &mut TradingState,
+    orders: Vec<SimulatedOrder>,
+    trigger: EventTrigger,
+    params: &CopyParams,
+) {
+    let executor_params = params.executor.for_risk_exit();
+    let orders = executor::split_orders(orders, &executor_params, &params.engine);
+    let execution_results = if orders.is_empty() {
+        None
+    } else if let Some(ctx) = clob_ctx {
+        Some(executor::submit_orders(ctx, state, &orders, &executor_params).await)
+    } else {
+        state.commit(Mutation::ApplyOrders {
+            orders: charge_taker_fees(&orders, state),
+        });
+        None
+    };
+    let event = CopytradeEvent {
+        timestamp: clock::now().to_rfc3339(),
+        build: params.build.clone(),
+        strategy_id: params.executor.strategy_id.clone(),
+        trigger,
+        detected_trade_hashes: Vec::new(),
+        detected_trade_timestamps: Vec::new(),
+        trader_trades: Vec::new(),
+        trader_intents: Vec::new(),
+        position_changes: Vec::new(),
+        copy_latency_secs: None,
+        orders,
+        skipped_orders: Vec::new(),
+        budget_remaining: money::to_f64(state.budget_remaining),
+        total_spent: money::to_f64(state.total_spent),
+        execution_results,
+    };
+    reporter::report_event(&event);
+    state.record_event(&event);
+}
+
+/// Tap for a live cycle: records its inputs when `--record` is set.
+fn live_tap(
+    recorder: Option<&Recorder>,
+    kind: CycleKind,
+    rest_poll: bool,
+    pushed: &[RawTrade],
+) -> Tap {
+    match recorder {
+        Some(_) => Tap::Record(CycleRecord::new(kind, rest_poll, pushed)),
+        None => Tap::Off,
+    }
+}
+
+fn save_tap(recorder: Option<&mut Recorder>, tap: Tap) {
+    if let (Some(recorder), Some(record)) = (recorder, tap.into_record())
+        && let Err(e) = recorder.write(&record)
+    {
+        warn!("Failed to record cycle: {e:#}");
+    }
+}
+
+/// `--replay`: run recorded cycles through initial replication, seeding, and
+/// the poll cycle as a dry-run with the clock pinned to each cycle's start, then
+/// print the exit summary. Makes no API calls.
+async fn replay_session(
+    dir: &Path,
+    budget: f64,
+    trader_addr: Address,
+    trader_short_id: &str,
+    data_client: &DataApi,
+    cycle_duration: Duration,
+    params: &CopyParams,
+) -> Result<()> {
+    let records = recording::read_recording(dir)?;
+    if records.is_empty() {
+        anyhow::bail!("{} holds no recorded cycles", dir.display());
+    }
+    info!("Replaying {} recorded cycle(s) from {}", records.len(), dir.display());
+    // Unused: every fetch is answered from the recording
+    let gamma = GammaClient::default();
+    let mut state = TradingState::new(money::from_f64(budget));
+    let mut detections = DetectionConsensus::new();
+    let mut latency = LatencyHistogram::new();
+    let mut gaps = GapDetector::new(TRADE_FETCH_LIMIT as usize, cycle_duration * 3);
+    let mut intents = IntentTracker::new(params.intent_window_secs);
+    let mut latest_prices = HashMap::new();
+
+    for record in records {
+        let started = chrono::DateTime::from_timestamp_millis(record.timestamp_ms)
+            .context("recorded cycle timestamp out of range")?;
+        clock::pin(started);
+        if let Some(prices) = &record.prices {
+            latest_prices.extend(prices.clone());
+        }
+        let (kind, rest_poll, pushed) = (record.kind, record.rest_poll, record.pushed.clone());
+        let mut tap = Tap::Replay(record);
+        match kind {
+            CycleKind::InitialReplication => {
+                let replication = initial_replication(
+                    data_client,
+                    None,
+                    trader_addr,
+                    trader_short_id,
+                    &mut state,
+                    None,
+                    params,
+                    &mut tap,
+                );
+                replication.await?;
+            }
+            CycleKind::Seed => {
+                let (detections, gaps) = (&mut detections, &mut gaps);
+                seed_detections(data_client, trader_addr, params, detections, gaps, &mut tap).await;
             }
-            _ = tokio::time::sleep(poll_duration) => {
-                if let Err(e) = poll_cycle(
-                    &data_client,
-                    &gamma_client,
-                    clob_ctx.as_ref(),
+            CycleKind::Poll => {
+                let mut no_overrides = None;
+                let cycle = poll_cycle(
+                    data_client,
+                    &gamma,
+                    None,
                     trader_addr,
                     trader_short_id,
                     &mut state,
-                    &mut seen_hashes,
-                    copy_pct,
-                    max_trade_pct,
-                ).await {
+                    &mut detections,
+                    &mut latency,
+                    &mut gaps,
+                    &mut intents,
+                    rest_poll,
+                    &pushed,
+                    None,
+                    None,
+                    params,
+                    &mut no_overrides,
+                    &mut tap,
+                );
+                if let Err(e) = cycle.await {
                     warn!("Poll cycle error: {e}");
                 }
             }
         }
     }
 
-    // --- Cancel resting orders on shutdown (live mode) ---
-    if let Some(ctx) = &clob_ctx {
-        if !state.resting_orders.is_empty() {
-            info!(
-                "Cancelling {} resting order(s) on shutdown...",
-                state.resting_orders.len()
-            );
-            let order_ids: Vec<String> = state
-                .resting_orders
-                .iter()
-                .map(|r| r.order_id.clone())
-                .collect();
-            let id_refs: Vec<&str> = order_ids.iter().map(|s| s.as_str()).collect();
-            match ctx.client.cancel_orders(&id_refs).await {
-                Ok(resp) => {
-                    if !resp.canceled.is_empty() {
-                        info!("Cancelled {} order(s)", resp.canceled.len());
-                    }
-                    for (id, err) in &resp.not_canceled {
-                        warn!("Failed to cancel order {id}: {err}");
-                    }
-                }
-                Err(e) => {
-                    warn!("Failed to cancel resting orders: {e}");
-                }
+    let mut summary = state.exit_summary(&latest_prices);
+    summary.copy_latency = latency.summary();
+    summary.detection = detections.summary();
+    summary.build = Some(params.build.clone());
+    reporter::report_exit_summary(&summary);
+    Ok(())
+}
+
+/// Cancel the resting orders matching `filter`, resolving them as cancelled in
+/// state.
+async fn cancel_resting(
+    ctx: &ClobContext,
+    state: &mut TradingState,
+    filter: impl Fn(&RestingOrder) -> bool,
+) {
+    let order_ids: Vec<String> = state
+        .resting_orders
+        .iter()
+        .filter(|r| filter(r))
+        .map(|r| r.order_id.clone())
+        .collect();
+    if order_ids.is_empty() {
+        return;
+    }
+    info!("Cancelling {} resting order(s)...", order_ids.len());
+    let id_refs: Vec<&str> = order_ids.iter().map(|s| s.as_str()).collect();
+    ratelimit::acquire_clob("clob:DELETE /orders").await;
+    match ctx.client.cancel_orders(&id_refs).await {
+        Ok(resp) => {
+            if !resp.canceled.is_empty() {
+                info!("Cancelled {} order(s)", resp.canceled.len());
             }
-            // Resolve all resting orders as cancelled in state
-            for order_id in &order_ids {
-                state.resolve_resting_cancel(order_id);
+            for (id, err) in &resp.not_canceled {
+                warn!("Failed to cancel order {id}: {err}");
             }
         }
+        Err(e) => {
+            warn!("Failed to cancel resting orders: {e}");
+        }
     }
+    for order_id in order_ids {
+        state.commit(Mutation::RestingCancel { order_id });
+    }
+}
 
-    // --- Exit summary ---
-    info!("Computing exit summary...");
-    let active_prices = match fetch_active_positions(&data_client, trader_addr).await {
-        Ok(positions) => build_price_map(&positions),
-        Err(e) => {
-            warn!("Failed to fetch final positions for exit summary: {e}");
-            HashMap::new()
+/// Stop opening positions: from now on only sells go out, and resting buys
+/// are cancelled. Exits keep following the trader.
+async fn start_drain(
+    clob_ctx: Option<&ClobContext>,
+    state: &mut TradingState,
+    params: &mut CopyParams,
+) {
+    info!(
+        "Draining — no new buys; following exits of {} holding(s) until flat",
+        state.holdings.len()
+    );
+    params.draining = true;
+    if let Some(ctx) = clob_ctx {
+        cancel_resting(ctx, state, |r| r.side == OrderSide::Buy).await;
+    }
+}
+
+/// Drain requests from a running instance: SIGUSR1, on Unix. The channel
+/// closes immediately elsewhere.
+fn drain_requests() -> tokio::sync::mpsc::Receiver<()> {
+    let (tx, rx) = tokio::sync::mpsc::channel(1);
+    #[cfg(unix)]
+    tokio::spawn(async move {
+        use tokio::signal::unix::{SignalKind, signal};
+        let mut usr1 = match signal(SignalKind::user_defined1()) {
+            Ok(usr1) => usr1,
+            Err(e) => {
+                warn!("Can't listen for SIGUSR1, drain with --drain only: {e}");
+                return;
+            }
+        };
+        while usr1.recv().await.is_some() {
+            info!("SIGUSR1 received");
+            if tx.send(()).await.is_err() {
+                break;
+            }
+        }
+    });
+    #[cfg(not(unix))]
+    drop(tx);
+    rx
+}
+
+/// Take planned orders through what stands between the engine and the book:
+/// the drain and `--live-test-cap` gates, `[engine.depth_limit]`, and, live,
+/// TWAP slicing, the order splits and the fit to the wallet balance (or to
+/// `funds`, the shadow twin's own cash).
+///
+/// The live session and its shadow twin both go through it, so the twin's
+/// divergence measures execution, not a shorter pipeline.
+async fn order_pipeline(
+    orders: Vec<SimulatedOrder>,
+    state: &mut TradingState,
+    clob_ctx: Option<&ClobContext>,
+    funds: Option<Decimal>,
+    params: &CopyParams,
+) -> Vec<SimulatedOrder> {
+    let orders = params.admit(orders);
+    let orders = params.limit_to_depth(orders).await;
+    let orders = params.cap_spend(orders, state);
+    let orders = match clob_ctx {
+        Some(_) => {
+            let now_ms = clock::now().timestamp_millis();
+            executor::schedule_twap(orders, state, &params.executor, now_ms)
         }
+        None => orders,
     };
-    let held_assets: Vec<String> = state.holdings.keys().cloned().collect();
-    let latest_prices =
-        build_exit_price_map(&gamma_client, &active_prices, &held_assets).await?;
-    let summary = state.exit_summary(&latest_prices);
-    reporter::report_exit_summary(&summary);
+    let orders = executor::split_orders(orders, &params.executor, &params.engine);
+    match clob_ctx {
+        Some(ctx) => {
+            let min_buy_usd = params.engine.min_order_usd;
+            let orders =
+                executor::split_for_liquidity(ctx, orders, &params.executor, min_buy_usd).await;
+            match funds {
+                Some(funds) => {
+                    executor::fit_to_funds(ctx, orders, funds, &params.executor, min_buy_usd).await
+                }
+                None => executor::fit_to_balance(ctx, orders, &params.executor, min_buy_usd).await,
+            }
+        }
+        None => orders,
+    }
+}
 
-    Ok(())
+/// Plan the shadow twin's orders for the same signal and take them through
+/// `order_pipeline`, fitted to the twin's own cash rather than the live
+/// wallet's, then apply them in full, as dry-run would.
+#[allow(clippy::too_many_arguments)]
+async fn shadow_rebalance(
+    shadow: &mut Shadow,
+    clob_ctx: Option<&ClobContext>,
+    positions: &[Position],
+    exit_prices: &HashMap<String, f64>,
+    copy_pct: f64,
+    trader_short_id: &str,
+    params: &CopyParams,
+    engine: &EngineParams,
+) {
+    let orders = plan_rebalance(
+        positions,
+        shadow.state(),
+        exit_prices,
+        copy_pct,
+        params.max_trade_pct,
+        trader_short_id,
+        engine,
+    );
+    let funds = shadow.state().budget_remaining;
+    let orders = order_pipeline(orders, shadow.state_mut(), clob_ctx, Some(funds), params).await;
+    shadow.apply(&orders);
+}
+
+/// Value of tracked holdings at `prices`, falling back to average cost.
+fn holdings_value(state: &TradingState, prices: &HashMap<String, f64>) -> f64 {
+    state
+        .holdings
+        .iter()
+        .map(|(asset, h)| {
+            let price = prices.get(asset).copied();
+            money::to_f64(h.shares) * price.unwrap_or_else(|| money::to_f64(h.avg_cost))
+        })
+        .sum()
+}
+
+/// USDC balance plus tracked holdings at current Safe wallet prices.
+async fn wallet_capital(
+    data_client: &DataApi,
+    ctx: &ClobContext,
+    state: &TradingState,
+    filter: Option<&MarketFilter>,
+) -> Result<f64> {
+    let balance = executor::check_balance(ctx).await?;
+    let prices: HashMap<String, f64> = data_client
+        .fetch_active_positions(ctx.safe, filter)
+        .await?
+        .iter()
+        .map(|p| (p.asset.to_string(), p.cur_price.to_f64().unwrap_or(0.0)))
+        .collect();
+    Ok(balance + holdings_value(state, &prices))
+}
+
+async fn wait_for_leadership(
+    lease: &Lease,
+    wal_path: Option<&Path>,
+    persistence: &PersistenceConfig,
+    budget: f64,
+    interval: Duration,
+    health: &HealthMonitor,
+) -> bool {
+    let mut announced = false;
+    loop {
+        match lease.try_acquire() {
+            Ok(true) => {
+                info!("Acquired leader lease as {}", lease.holder());
+                return true;
+            }
+            Ok(false) => {
+                if !announced {
+                    let holder = lease.read().ok().flatten().map(|r| r.holder);
+                    info!(
+                        "Standby — lease held by {}, waiting for takeover",
+                        holder.as_deref().unwrap_or("unknown")
+                    );
+                    announced = true;
+                }
+                if let Some(path) = wal_path {
+                    match store::read_entries(persistence, path, budget) {
+                        Ok(entries) => {
+                            let mut shadow = TradingState::new(money::from_f64(budget));
+                            shadow.replay(&entries);
+                            info!(
+                                "Standby following leader WAL: {} entries, {} holding(s), ${:.2} remaining",
+                                entries.len(),
+                                shadow.holdings.len(),
+                                shadow.budget_remaining,
+                            );
+                        }
+                        Err(e) => warn!("Failed to read leader WAL: {e:#}"),
+                    }
+                }
+            }
+            Err(e) => warn!("Failed to acquire leader lease: {e:#}"),
+        }
+        health.record_cycle(budget);
+
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => {
+                info!("Shutdown signal received");
+                return false;
+            }
+            _ = tokio::time::sleep(interval) => {}
+        }
+    }
+}
+
+/// Delay until the next REST poll: the scheduler's interval, stretched to the
+/// fallback interval while WebSocket detection is live.
+fn poll_delay(scheduler: &PollScheduler, ws_live: bool, fallback: Duration) -> Duration {
+    if ws_live {
+        scheduler.interval().max(fallback)
+    } else {
+        scheduler.interval()
+    }
+}
+
+/// Next event from the WebSocket feed; never resolves when detection is off.
+async fn recv_ws(events: &mut Option<tokio::sync::mpsc::Receiver<WsEvent>>) -> Option<WsEvent> {
+    match events {
+        Some(events) => events.recv().await,
+        None => std::future::pending().await,
+    }
+}
+
+/// Next update from the CLOB user channel; never resolves without one.
+async fn fills_changed(ctx: Option<&ClobContext>) {
+    match ctx.and_then(|ctx| ctx.fills.as_ref()) {
+        Some(fills) => fills.changed().await,
+        None => std::future::pending().await,
+    }
+}
+
+async fn sleep_until_some(at: Option<Instant>) {
+    match at {
+        Some(at) => tokio::time::sleep_until(at).await,
+        None => std::future::pending().await,
+    }
 }
 
-/// One polling cycle: fetch recent trades, detect new ones, rebalance if needed.
+/// One polling cycle: fetch recent trades (when `rest_poll`), detect new ones,
+/// rebalance if needed. `pushed` are trades already detected on the WebSocket
+/// feed. Pending `overrides` also force a rebalance, and are taken once merged
+/// into it.
+///
+/// Returns paper-trading measurements when the cycle rebalanced.
+#[allow(clippy::too_many_arguments)]
 async fn poll_cycle(
-    client: &Client,
+    client: &DataApi,
     gamma: &GammaClient,
     clob_ctx: Option<&ClobContext>,
     addr: Address,
     trader_short_id: &str,
     state: &mut TradingState,
-    seen_hashes: &mut HashSet<String>,
-    copy_pct: f64,
-    max_trade_pct: f64,
-) -> Result<()> {
+    detections: &mut DetectionConsensus,
+    latency: &mut LatencyHistogram,
+    gaps: &mut GapDetector,
+    intents: &mut IntentTracker,
+    rest_poll: bool,
+    pushed: &[RawTrade],
+    prices: Option<&PriceCache>,
+    mut shadow: Option<&mut Shadow>,
+    params: &CopyParams,
+    overrides: &mut Option<Overrides>,
+    tap: &mut Tap,
+) -> Result<Option<PaperSample>> {
     // Check resting orders before computing new ones
     if let Some(ctx) = clob_ctx {
-        executor::check_resting_orders(ctx, state).await;
+        let reposts = executor::check_resting_orders(ctx, state, &params.executor).await;
+        // Re-posts pass the same gates as new orders (draining, --live-test-cap)
+        let reposts = params.cap_spend(params.admit(reposts), state);
+        if !reposts.is_empty() {
+            executor::submit_orders(ctx, state, &reposts, &params.executor).await;
+        }
     }
 
-    info!("Polling... (seen: {} hashes)", seen_hashes.len());
-    let trades = fetch_recent_trades(client, addr, 50).await?;
+    let mut new_hashes: Vec<String> = pushed.iter().map(|t| t.tx_hash.clone()).collect();
+    let mut new_timestamps: Vec<i64> = pushed.iter().map(|t| t.timestamp).collect();
+    let mut new_trades: Vec<RawTrade> = pushed.to_vec();
+    let mut force_rebalance = false;
+    let mut position_changes: Vec<TraderPositionChange> = Vec::new();
+    if rest_poll {
+        info!("Polling... (seen: {} hashes)", detections.len());
+        let events = fetch_detections(client, addr, params, tap).await?;
 
-    let mut new_hashes = Vec::new();
-    for trade in &trades {
-        let hash = format!("{}", trade.transaction_hash);
-        if seen_hashes.insert(hash.clone()) {
-            new_hashes.push(hash);
+        let mut rest_new = 0;
+        let fetched_at = clock::now();
+        for (hash, timestamp, event) in &events {
+            let Some(detection) =
+                detections.observe(DetectionSource::Rest, hash, *timestamp, fetched_at)
+            else {
+                continue;
+            };
+            rest_new += 1;
+            if let Some(ActivityEvent::PositionChange(change)) = event {
+                info!(
+                    "Trader {} of {:.2} in {} ({hash}) detected via {}",
+                    change.kind, change.size, change.condition_id, detection.source
+                );
+                if change.kind == PositionChangeKind::Conversion {
+                    // Converting takes the neg-risk adapter; we can only trade the result
+                    warn!("Trader conversion {hash}: the converted positions are copied at market");
+                }
+                position_changes.push(change.clone());
+                continue;
+            }
+            info!(
+                "Trade {} detected via {} ({:.1}s after execution)",
+                detection.tx_hash, detection.source, detection.latency_secs
+            );
+            new_hashes.push(hash.clone());
+            new_timestamps.push(*timestamp);
+            if let Some(ActivityEvent::Trade(trade)) = event {
+                new_trades.push(trade.clone());
+            }
         }
-    }
 
-    if new_hashes.is_empty() {
-        info!("No new trades");
-        return Ok(());
+        let timestamps: Vec<i64> = events.iter().map(|(_, ts, _)| *ts).collect();
+        let detected_gaps = gaps.observe(clock::now(), &timestamps, rest_new);
+        for gap in &detected_gaps {
+            warn!("Possible gap in trade stream: {gap}");
+        }
+        force_rebalance = params.rebalance_on_gap && !detected_gaps.is_empty();
     }
 
-    info!("Detected {} new trade(s), rebalancing...", new_hashes.len());
+    let ramp_due = params.ramp.as_ref().is_some_and(RampIn::advance);
+    let now_ms = clock::now().timestamp_millis();
+    let twap_due = state.twap_schedules.values().any(|s| s.next_at_ms <= now_ms);
+    let trigger = if !new_hashes.is_empty() {
+        info!("Detected {} new trade(s), rebalancing...", new_hashes.len());
+        EventTrigger::TradeDetected
+    } else if !position_changes.is_empty() {
+        info!("Trader split, merged or redeemed without trading, rebalancing...");
+        EventTrigger::PositionChange
+    } else if force_rebalance {
+        info!("No new trades, but forcing a full rebalance after the detected gap...");
+        EventTrigger::GapDetected
+    } else if overrides.is_some() {
+        info!("No new trades, applying manual overrides...");
+        EventTrigger::ManualOverride
+    } else if ramp_due {
+        info!("No new trades, taking the next ramp-in step...");
+        EventTrigger::RampIn
+    } else if twap_due {
+        info!("No new trades, posting the next TWAP slice(s)...");
+        EventTrigger::TwapDue
+    } else {
+        info!("No new trades");
+        if params.risk.is_enabled() && !state.holdings.is_empty() {
+            let price_map = tap.prices(held_price_map(gamma, state, prices)).await?;
+            check_kill_switch(clob_ctx, state, &price_map, params).await;
+            apply_risk_exits(clob_ctx, state, &price_map, params, trader_short_id).await;
+            check_daily_loss(state, &price_map, params);
+        }
+        return Ok(None);
+    };
 
-    let positions = fetch_active_positions(client, addr).await?;
+    let positions = tap.positions(async {
+        let mut positions =
+            client.fetch_active_positions(addr, params.trader_position_filter()).await?;
+        if let Some(prices) = prices {
+            // Our tokens and the ones just traded are the last to lose a subscription
+            let ours: Vec<String> = state
+                .holdings
+                .keys()
+                .cloned()
+                .chain(state.resting_orders.iter().map(|r| r.asset.clone()))
+                .collect();
+            prices.touch(ours.iter().cloned().chain(new_trades.iter().map(|t| t.asset.clone())));
+            prices.track(positions.iter().map(|p| p.asset.to_string()).chain(ours));
+            prices.mark_positions(&mut positions);
+        }
+        Ok(positions)
+    });
+    let positions = positions.await?;
     let active_prices = build_price_map(&positions);
 
-    let weights = compute_weights(&positions);
-    let running_budget = state.effective_capital(&active_prices);
-    let targets = compute_target_state(&weights, running_budget, copy_pct, max_trade_pct);
+    // Check the detected trades against the trader's positions after them
+    let pairs = TokenPairs::from_positions(&positions);
+    let mut trader_trades = Vec::with_capacity(new_trades.len());
+    for raw in new_trades {
+        let hash = raw.tx_hash.clone();
+        let (trade, issues) = trades::normalize(raw, &pairs, &positions);
+        for issue in &issues {
+            let action = if issue.is_fatal() { "ignored" } else { "corrected" };
+            warn!("Trade {hash}: {issue} ({action})");
+        }
+        trader_trades.extend(trade);
+    }
+    let trader_intents = intents.observe(&trader_trades, clock::now().timestamp());
+    for cluster in &trader_intents {
+        info!(
+            "Trader intent on {}: {} ({} trade(s) over {}s)",
+            cluster.condition_id,
+            cluster.intent,
+            cluster.trades,
+            cluster.last_timestamp - cluster.first_timestamp,
+        );
+    }
+    let verdicts = params.category_verdicts(&positions, tap).await;
+    let (positions, copy_pct) = params.select(positions);
 
     // Build price map with gamma fallback for held assets the trader exited
-    let held_assets: Vec<String> = state.holdings.keys().cloned().collect();
-    let price_map = build_exit_price_map(gamma, &active_prices, &held_assets).await?;
+    let price_map = tap.prices(async {
+        let held_assets: Vec<String> = state.holdings.keys().cloned().collect();
+        let mut price_map = build_exit_price_map(gamma, &active_prices, &held_assets).await?;
+        if let Some(prices) = prices {
+            prices.overlay(&mut price_map);
+        }
+        Ok(price_map)
+    });
+    let price_map = price_map.await?;
+    // Risk exits go first, so the rebalance sees their sells and cooldowns
+    check_kill_switch(clob_ctx, state, &price_map, params).await;
+    apply_risk_exits(clob_ctx, state, &price_map, params, trader_short_id).await;
+    check_daily_loss(state, &price_map, params);
 
-    let orders = compute_orders(&targets, state, state.budget_remaining, &price_map, trader_short_id);
+    let engine = params.cycle_engine(verdicts, intents.clusters());
+    params.load_market_rules(&positions, state).await;
+    let (orders, skipped_orders) = plan_rebalance_with_skips(
+        &positions,
+        state,
+        &price_map,
+        copy_pct,
+        params.max_trade_pct,
+        trader_short_id,
+        &engine,
+    );
+    let orders = match overrides.take() {
+        Some(overrides) => overrides.merge(orders, state, &positions, &price_map, &engine),
+        None => orders,
+    };
+    if let Some(shadow) = shadow.as_deref_mut() {
+        shadow.set_market_rules(state);
+        shadow_rebalance(
+            shadow,
+            clob_ctx,
+            &positions,
+            &price_map,
+            copy_pct,
+            trader_short_id,
+            params,
+            &engine,
+        )
+        .await;
+    }
+    let orders = order_pipeline(orders, state, clob_ctx, None, params).await;
 
-    if !orders.is_empty() {
-        let execution_results = if let Some(ctx) = clob_ctx {
-            let results = executor::execute_orders(ctx, &orders).await;
-            state.apply_execution_results(&orders, &results);
+    // Buys held back get an event too, so their reason is on record
+    if !orders.is_empty() || !skipped_orders.is_empty() {
+        let execution_results = if orders.is_empty() {
+            None
+        } else if let Some(ctx) = clob_ctx {
+            let results = executor::submit_orders(ctx, state, &orders, &params.executor).await;
+            if let Some(shadow) = shadow.as_deref_mut() {
+                let cost = shadow.record_execution(&orders, &results);
+                info!("Shadow: execution cost this cycle ${cost:+.4}");
+            }
             Some(results)
         } else {
-            state.apply_orders(&orders);
+            state.commit(Mutation::ApplyOrders {
+                orders: charge_taker_fees(&orders, state),
+            });
             None
         };
 
+        // Copy latency runs from each trade's exchange timestamp to our fill
+        let now = clock::now();
+        let filled = !orders.is_empty()
+            && execution_results.as_ref().is_none_or(|results| {
+                results.iter().any(|r| {
+                    matches!(
+                        r.status,
+                        ExecutionStatus::Filled | ExecutionStatus::PartialFill
+                    )
+                })
+            });
+        let mut copy_latency_secs = None;
+        if filled {
+            let now_ms = now.timestamp_millis();
+            for &ts in &new_timestamps {
+                latency.record((now_ms - ts * 1000) as f64 / 1000.0);
+            }
+            copy_latency_secs = new_timestamps
+                .iter()
+                .min()
+                .map(|&ts| ((now_ms - ts * 1000) as f64 / 1000.0).max(0.0));
+        }
+
         let event = CopytradeEvent {
-            timestamp: chrono::Utc::now().to_rfc3339(),
-            trigger: EventTrigger::TradeDetected,
+            timestamp: now.to_rfc3339(),
+            build: params.build.clone(),
+            strategy_id: params.executor.strategy_id.clone(),
+            trigger,
             detected_trade_hashes: new_hashes,
+            detected_trade_timestamps: new_timestamps,
+            trader_trades,
+            trader_intents,
+            position_changes,
+            copy_latency_secs,
             orders,
-            budget_remaining: state.budget_remaining,
-            total_spent: state.total_spent,
+            skipped_orders,
+            budget_remaining: money::to_f64(state.budget_remaining),
+            total_spent: money::to_f64(state.total_spent),
             execution_results,
         };
         reporter::report_event(&event);
-        state.total_events += 1;
+        state.record_event(&event);
     } else {
         info!("No rebalancing orders needed");
     }
 
-    Ok(())
-}
+    if let Some(shadow) = shadow {
+        let divergence = shadow.summary(state, &price_map);
+        info!(
+            "Shadow: live P&L ${:.2} vs simulated ${:.2} (divergence ${:+.2}, execution cost ${:.2})",
+            divergence.live_pnl,
+            divergence.simulated_pnl,
+            divergence.pnl_divergence,
+            divergence.execution_cost_usd
+        );
+    }
 
-/// Build a map of asset → current price from positions.
-fn build_price_map(
-    positions: &[polymarket_client_sdk::data::types::response::Position],
-) -> HashMap<String, f64> {
-    positions
-        .iter()
-        .map(|p| {
-            (
-                p.asset.to_string(),
-                p.cur_price.to_f64().unwrap_or(0.0),
-            )
-        })
-        .collect()
+    Ok(Some(PaperSample::measure(state, &positions, &price_map)))
 }
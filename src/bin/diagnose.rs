@@ -0,0 +1,899 @@
+//! diagnose — API and connectivity diagnostics.
+//!
+//! Consolidates the Phase 1/3 exploration probes into one binary with a
+//! subcommand per check. Every subcommand runs a sequence of steps and prints
+//! them as they complete; `--json` prints a single machine-readable report
+//! instead. Exits non-zero when any step fails.
+
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result, bail};
+use clap::{Parser, Subcommand};
+use polymarket_client_sdk::auth::{LocalSigner, Signer};
+use polymarket_client_sdk::clob::types::request::BalanceAllowanceRequest;
+use polymarket_client_sdk::clob::types::{Amount, OrderType, Side};
+use polymarket_client_sdk::{POLYGON, PRIVATE_KEY_VAR, derive_safe_wallet};
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal_macros::dec;
+use serde::Serialize;
+use serde_json::{Value, json};
+use tokio_tungstenite::tungstenite::Message;
+
+use polymarket_copytrade::clock;
+use polymarket_copytrade::config::{AppConfig, CONFIG_PATH, EndpointsConfig, SettingsConfig};
+use polymarket_copytrade::journal::{self, Journal};
+use polymarket_copytrade::secret::Secret;
+use polymarket_copytrade::selftest::{self, WsCheck};
+use polymarket_copytrade::ws::{Connection, Keepalive};
+use polymarket_copytrade::wscapture::CaptureWriter;
+use polymarket_copytrade::{CLOB_WS_MARKET_URL, RTDS_WS_URL, TRADER_ADDRESS, auth, prices, rtds};
+
+#[derive(Parser)]
+#[command(
+    name = "diagnose",
+    about = "Polymarket API and connectivity diagnostics"
+)]
+struct Cli {
+    /// Print one JSON report instead of human-readable steps
+    #[arg(long, global = true)]
+    json: bool,
+
+    /// Config file for endpoints and the private key (defaults apply if missing)
+    #[arg(long, global = true, default_value = CONFIG_PATH)]
+    config: PathBuf,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Data API /trades: response shape, pagination, filtering, latency, hash uniqueness
+    Trades {
+        /// Trader wallet address
+        #[arg(long, default_value = TRADER_ADDRESS)]
+        trader: String,
+        /// Number of requests in the latency sample
+        #[arg(long, default_value_t = 5)]
+        samples: u32,
+    },
+    /// Data API /positions: pagination, active positions, portfolio weights, fields
+    Positions {
+        /// Trader wallet address (default: the built-in target trader)
+        #[arg(long, conflicts_with = "mine")]
+        trader: Option<String>,
+        /// Inspect our own Safe wallet (requires the private key)
+        #[arg(long)]
+        mine: bool,
+    },
+    /// RTDS WebSocket: subscribe and count messages by topic
+    Rtds {
+        /// Listening window in seconds
+        #[arg(long, default_value_t = 30)]
+        secs: u64,
+    },
+    /// CLOB market WebSocket: subscribe to one asset and count events by type
+    ClobWs {
+        /// Token ID to subscribe to (default: auto-selected from the leaderboard)
+        #[arg(long)]
+        asset: Option<String>,
+        /// Listening window in seconds
+        #[arg(long, default_value_t = 30)]
+        secs: u64,
+    },
+    /// Reachability and latency of every REST and WebSocket endpoint
+    SelfTest,
+    /// CLOB auth, balance, unfillable limit order place/query/cancel
+    OrderRoundtrip {
+        /// Token ID to trade (default: auto-selected from the leaderboard)
+        #[arg(long)]
+        token: Option<String>,
+        /// Also place a $1.00 FAK market buy — costs real funds
+        #[arg(long)]
+        execute: bool,
+    },
+    /// Rebuild trading state from a `--journal` file and verify it against every snapshot
+    Replay {
+        /// Journal written by `copytrade --journal`
+        journal: PathBuf,
+    },
+}
+
+impl Command {
+    fn name(&self) -> &'static str {
+        match self {
+            Command::Trades { .. } => "trades",
+            Command::Positions { .. } => "positions",
+            Command::Rtds { .. } => "rtds",
+            Command::ClobWs { .. } => "clob-ws",
+            Command::SelfTest => "self-test",
+            Command::OrderRoundtrip { .. } => "order-roundtrip",
+            Command::Replay { .. } => "replay",
+        }
+    }
+}
+
+/// One completed diagnostic step.
+#[derive(Serialize)]
+struct Step {
+    name: String,
+    ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    latency_ms: Option<f64>,
+    details: Value,
+}
+
+/// Steps of one subcommand. Human mode prints each step as it is recorded.
+#[derive(Serialize)]
+struct Report {
+    command: &'static str,
+    ok: bool,
+    steps: Vec<Step>,
+    #[serde(skip)]
+    json: bool,
+}
+
+impl Report {
+    fn new(command: &'static str, json: bool) -> Self {
+        if !json {
+            println!("=== diagnose {command} ===\n");
+        }
+        Self {
+            command,
+            ok: true,
+            steps: Vec::new(),
+            json,
+        }
+    }
+
+    fn step(
+        &mut self,
+        name: impl Into<String>,
+        ok: bool,
+        latency: Option<Duration>,
+        details: Value,
+    ) {
+        let step = Step {
+            name: name.into(),
+            ok,
+            latency_ms: latency.map(|d| d.as_secs_f64() * 1000.0),
+            details,
+        };
+        if !self.json {
+            print_step(&step);
+        }
+        self.ok &= ok;
+        self.steps.push(step);
+    }
+
+    fn finish(self) -> Result<bool> {
+        if self.json {
+            println!("{}", serde_json::to_string_pretty(&self)?);
+        } else {
+            let verdict = if self.ok { "OK" } else { "FAILED" };
+            println!("=== diagnose {}: {verdict} ===", self.command);
+        }
+        Ok(self.ok)
+    }
+}
+
+fn print_step(step: &Step) {
+    let mark = if step.ok { "ok" } else { "FAIL" };
+    match step.latency_ms {
+        Some(ms) => println!("[{mark}] {} ({ms:.0}ms)", step.name),
+        None => println!("[{mark}] {}", step.name),
+    }
+    if let Value::Object(map) = &step.details {
+        for (key, value) in map {
+            match value {
+                Value::String(s) => println!("    {key}: {s}"),
+                Value::Array(items) if items.iter().any(Value::is_object) => {
+                    println!("    {key}:");
+                    for item in items {
+                        println!("      {item}");
+                    }
+                }
+                other => println!("    {key}: {other}"),
+            }
+        }
+    }
+    println!();
+}
+
+/// Shared client setup: endpoints and credentials from `config.toml`.
+struct Env {
+    http: reqwest::Client,
+    endpoints: EndpointsConfig,
+    settings: SettingsConfig,
+    private_key: Option<Secret<String>>,
+}
+
+impl Env {
+    fn load(path: &Path) -> Result<Self> {
+        dotenvy::dotenv().ok();
+        let config = if path.exists() {
+            Some(AppConfig::load(path)?)
+        } else {
+            None
+        };
+        let private_key = config
+            .as_ref()
+            .map(|c| c.account.private_key.clone())
+            .filter(|k| !k.is_empty())
+            .or_else(|| {
+                std::env::var(PRIVATE_KEY_VAR)
+                    .ok()
+                    .filter(|k| !k.is_empty())
+                    .map(Secret::new)
+            });
+        Ok(Self {
+            http: reqwest::Client::new(),
+            settings: config
+                .as_ref()
+                .map(|c| c.settings.clone())
+                .unwrap_or_default(),
+            endpoints: config.map(|c| c.endpoints).unwrap_or_default(),
+            private_key,
+        })
+    }
+
+    fn private_key(&self) -> Result<&Secret<String>> {
+        self.private_key
+            .as_ref()
+            .with_context(|| format!("no private key: run setup-account or set {PRIVATE_KEY_VAR}"))
+    }
+
+    /// GET a data API path, returning the JSON body and request latency.
+    async fn data_get(&self, path: &str, query: &[(&str, &str)]) -> Result<(Value, Duration)> {
+        let url = format!("{}{path}", self.endpoints.data_api);
+        let start = Instant::now();
+        let resp = self
+            .http
+            .get(&url)
+            .query(query)
+            .send()
+            .await
+            .with_context(|| format!("GET {url} failed"))?;
+        let latency = start.elapsed();
+        let status = resp.status();
+        if !status.is_success() {
+            bail!("GET {url}: HTTP {status}");
+        }
+        let body = resp.json().await.context("invalid JSON body")?;
+        Ok((body, latency))
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let env = Env::load(&cli.config)?;
+    let mut report = Report::new(cli.command.name(), cli.json);
+
+    let result = match cli.command {
+        Command::Trades { trader, samples } => trades(&env, &mut report, &trader, samples).await,
+        Command::Positions { trader, mine } => {
+            positions(&env, &mut report, trader.as_deref(), mine).await
+        }
+        Command::Rtds { secs } => rtds(&mut report, Duration::from_secs(secs)).await,
+        Command::ClobWs { asset, secs } => {
+            clob_ws(&env, &mut report, asset, Duration::from_secs(secs)).await
+        }
+        Command::SelfTest => self_test(&env, &mut report).await,
+        Command::OrderRoundtrip { token, execute } => {
+            order_roundtrip(&env, &mut report, token, execute).await
+        }
+        Command::Replay { journal } => replay(&mut report, &journal),
+    };
+    if let Err(e) = result {
+        report.step("error", false, None, json!({ "error": format!("{e:#}") }));
+    }
+
+    if !report.finish()? {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+async fn trades(env: &Env, report: &mut Report, trader: &str, samples: u32) -> Result<()> {
+    let (body, latency) = env.data_get("/trades", &[("user", trader)]).await?;
+    let arr = body
+        .as_array()
+        .context("/trades response is not an array")?;
+    let fields: Vec<&String> = arr
+        .first()
+        .and_then(Value::as_object)
+        .map(|o| o.keys().collect())
+        .unwrap_or_default();
+    report.step(
+        "fetch",
+        true,
+        Some(latency),
+        json!({ "trader": trader, "count": arr.len(), "fields": fields }),
+    );
+
+    let (page1, _) = env
+        .data_get("/trades", &[("user", trader), ("limit", "5")])
+        .await?;
+    let (page2, _) = env
+        .data_get(
+            "/trades",
+            &[("user", trader), ("limit", "5"), ("offset", "5")],
+        )
+        .await?;
+    let hashes = |page: &Value| -> HashSet<String> {
+        page.as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|t| t.get("transactionHash")?.as_str().map(String::from))
+            .collect()
+    };
+    let (h1, h2) = (hashes(&page1), hashes(&page2));
+    let overlap = h1.intersection(&h2).count();
+    report.step(
+        "pagination",
+        overlap == 0,
+        None,
+        json!({ "page1": h1.len(), "page2": h2.len(), "overlapping_hashes": overlap }),
+    );
+
+    let (buys, latency) = env
+        .data_get(
+            "/trades",
+            &[("user", trader), ("side", "BUY"), ("limit", "5")],
+        )
+        .await?;
+    let buys = buys.as_array().cloned().unwrap_or_default();
+    let all_buy = buys
+        .iter()
+        .all(|t| t.get("side").and_then(Value::as_str) == Some("BUY"));
+    report.step(
+        "side_filter",
+        all_buy,
+        Some(latency),
+        json!({ "count": buys.len(), "all_buy": all_buy }),
+    );
+
+    let mut latencies = Vec::new();
+    for _ in 0..samples.max(1) {
+        let (_, latency) = env
+            .data_get("/trades", &[("user", trader), ("limit", "1")])
+            .await?;
+        latencies.push(latency.as_secs_f64() * 1000.0);
+    }
+    let avg = latencies.iter().sum::<f64>() / latencies.len() as f64;
+    let max = latencies.iter().copied().fold(0.0, f64::max);
+    report.step(
+        "latency",
+        true,
+        None,
+        json!({ "samples": latencies.len(), "avg_ms": avg.round(), "max_ms": max.round() }),
+    );
+
+    let (body, _) = env
+        .data_get("/trades", &[("user", trader), ("limit", "100")])
+        .await?;
+    let arr = body.as_array().cloned().unwrap_or_default();
+    let mut unique = HashSet::new();
+    let (mut duplicates, mut missing) = (0, 0);
+    for trade in &arr {
+        match trade.get("transactionHash").and_then(Value::as_str) {
+            Some(hash) if !unique.insert(hash.to_string()) => duplicates += 1,
+            Some(_) => {}
+            None => missing += 1,
+        }
+    }
+    report.step(
+        "hash_uniqueness",
+        missing == 0,
+        None,
+        json!({
+            "trades": arr.len(),
+            "unique": unique.len(),
+            "duplicates": duplicates,
+            "missing": missing,
+        }),
+    );
+    Ok(())
+}
+
+async fn positions(env: &Env, report: &mut Report, trader: Option<&str>, mine: bool) -> Result<()> {
+    let user = if mine {
+        let signer = LocalSigner::from_str(env.private_key()?.expose())
+            .context("invalid private key")?
+            .with_chain_id(Some(POLYGON));
+        derive_safe_wallet(signer.address(), POLYGON)
+            .context("failed to derive Safe address")?
+            .to_string()
+    } else {
+        trader.unwrap_or(TRADER_ADDRESS).to_string()
+    };
+
+    let mut all: Vec<Value> = Vec::new();
+    let mut pages = 0;
+    let mut total_latency = Duration::ZERO;
+    let limit = 100;
+    loop {
+        let offset = (pages * limit).to_string();
+        let (body, latency) = env
+            .data_get(
+                "/positions",
+                &[("user", &user), ("limit", "100"), ("offset", &offset)],
+            )
+            .await?;
+        total_latency += latency;
+        pages += 1;
+        let arr = body.as_array().cloned().unwrap_or_default();
+        let len = arr.len();
+        all.extend(arr);
+        if len < limit {
+            break;
+        }
+    }
+    report.step(
+        "fetch",
+        true,
+        Some(total_latency),
+        json!({ "user": user, "pages": pages, "positions": all.len() }),
+    );
+
+    let mut active: Vec<(&Value, f64)> = all
+        .iter()
+        .filter_map(|p| {
+            let value = parse_f64(p, "currentValue")?;
+            (value > 0.0).then_some((p, value))
+        })
+        .collect();
+    active.sort_by(|a, b| b.1.total_cmp(&a.1));
+    let total: f64 = active.iter().map(|(_, v)| v).sum();
+    let top: Vec<Value> = active
+        .iter()
+        .take(10)
+        .map(|(p, value)| {
+            json!({
+                "title": p.get("title").and_then(Value::as_str).unwrap_or("?"),
+                "outcome": p.get("outcome").and_then(Value::as_str).unwrap_or("?"),
+                "value": (value * 100.0).round() / 100.0,
+                "weight_pct": (value / total * 10_000.0).round() / 100.0,
+                "cur_price": parse_f64(p, "curPrice").unwrap_or(0.0),
+            })
+        })
+        .collect();
+    report.step(
+        "weights",
+        true,
+        None,
+        json!({
+            "active": active.len(),
+            "total_value": (total * 100.0).round() / 100.0,
+            "top": top,
+        }),
+    );
+
+    const FIELDS: [&str; 12] = [
+        "proxyWallet",
+        "asset",
+        "conditionId",
+        "title",
+        "outcome",
+        "size",
+        "avgPrice",
+        "currentValue",
+        "curPrice",
+        "cashPnl",
+        "percentPnl",
+        "endDate",
+    ];
+    if let Some(first) = all.first() {
+        let missing: Vec<&str> = FIELDS
+            .iter()
+            .copied()
+            .filter(|f| first.get(*f).is_none())
+            .collect();
+        report.step(
+            "fields",
+            missing.is_empty(),
+            None,
+            json!({ "missing": missing }),
+        );
+    }
+    Ok(())
+}
+
+async fn rtds(report: &mut Report, window: Duration) -> Result<()> {
+    let start = Instant::now();
+    let mut conn = Connection::connect(RTDS_WS_URL, Keepalive::RTDS, None).await?;
+    report.step(
+        "connect",
+        true,
+        Some(start.elapsed()),
+        json!({ "url": RTDS_WS_URL }),
+    );
+
+    let subscriptions = [
+        json!({
+            "action": "subscribe",
+            "subscriptions": [{ "topic": "crypto_prices", "type": "update", "filters": "btcusdt" }]
+        }),
+        json!({
+            "action": "subscribe",
+            "subscriptions": [{ "topic": "activity", "type": "trades" }]
+        }),
+    ];
+    for sub in &subscriptions {
+        conn.send_text(sub.to_string()).await?;
+    }
+
+    let mut topics: BTreeMap<String, u64> = BTreeMap::new();
+    let messages = listen(&mut conn, "rtds", window, |text| {
+        let parsed: Value = serde_json::from_str(text).unwrap_or_default();
+        let topic = parsed
+            .get("topic")
+            .and_then(Value::as_str)
+            .unwrap_or("unknown");
+        let kind = parsed
+            .get("type")
+            .and_then(Value::as_str)
+            .unwrap_or("unknown");
+        *topics.entry(format!("{topic}/{kind}")).or_default() += 1;
+    })
+    .await?;
+    report.step(
+        "listen",
+        messages > 0,
+        None,
+        json!({ "secs": window.as_secs(), "messages": messages, "topics": topics }),
+    );
+    Ok(())
+}
+
+async fn clob_ws(
+    env: &Env,
+    report: &mut Report,
+    asset: Option<String>,
+    window: Duration,
+) -> Result<()> {
+    let asset = match asset {
+        Some(asset) => asset,
+        None => pick_token_id(env).await?,
+    };
+    let start = Instant::now();
+    let mut conn = Connection::connect(CLOB_WS_MARKET_URL, Keepalive::CLOB, None).await?;
+    report.step(
+        "connect",
+        true,
+        Some(start.elapsed()),
+        json!({ "url": CLOB_WS_MARKET_URL, "asset": asset }),
+    );
+
+    let sub = json!({ "type": "market", "assets_ids": [&asset], "custom_feature_enabled": true });
+    conn.send_text(sub.to_string()).await?;
+
+    const IDENTITY_FIELDS: [&str; 6] = ["maker", "taker", "user", "owner", "trader", "proxyWallet"];
+    let mut event_types: BTreeMap<String, u64> = BTreeMap::new();
+    let mut identity_fields: HashSet<&str> = HashSet::new();
+    let messages = listen(&mut conn, "clob_market", window, |text| {
+        let parsed: Value = serde_json::from_str(text).unwrap_or_default();
+        // The first frame after subscribing is an array of book snapshots
+        let events = match parsed {
+            Value::Array(items) => items,
+            other => vec![other],
+        };
+        for event in &events {
+            let kind = event
+                .get("event_type")
+                .and_then(Value::as_str)
+                .unwrap_or("unknown");
+            *event_types.entry(kind.to_string()).or_default() += 1;
+            for field in IDENTITY_FIELDS {
+                if event.get(field).is_some() {
+                    identity_fields.insert(field);
+                }
+            }
+        }
+    })
+    .await?;
+    let mut identity_fields: Vec<&str> = identity_fields.into_iter().collect();
+    identity_fields.sort();
+    report.step(
+        "listen",
+        messages > 0,
+        None,
+        json!({
+            "secs": window.as_secs(),
+            "messages": messages,
+            "event_types": event_types,
+            "trader_identity_fields": identity_fields,
+        }),
+    );
+    Ok(())
+}
+
+/// Read data frames for `window`, passing text frames to `on_text`. Frames are
+/// captured to `WS_CAPTURE` when set. Returns the number of data frames.
+async fn listen(
+    conn: &mut Connection,
+    channel: &str,
+    window: Duration,
+    mut on_text: impl FnMut(&str),
+) -> Result<u64> {
+    let mut capture = CaptureWriter::from_env()?;
+    let deadline = tokio::time::Instant::now() + window;
+    let mut messages = 0;
+    while let Ok(next) = tokio::time::timeout_at(deadline, conn.next()).await {
+        let Some(msg) = next? else {
+            break;
+        };
+        if let Some(capture) = &mut capture {
+            capture.record(channel, &msg)?;
+        }
+        messages += 1;
+        if let Message::Text(text) = &msg {
+            on_text(text.as_str());
+        }
+    }
+    Ok(messages)
+}
+
+async fn self_test(env: &Env, report: &mut Report) -> Result<()> {
+    let ws = [
+        WsCheck {
+            name: "rtds",
+            url: RTDS_WS_URL,
+            subscription: Some(rtds::trades_subscription()),
+            required: true,
+        },
+        WsCheck {
+            name: "clob_ws",
+            url: CLOB_WS_MARKET_URL,
+            subscription: Some(prices::market_subscription(&BTreeSet::new())),
+            required: true,
+        },
+    ];
+    for check in selftest::run(&env.endpoints, true, &ws).await {
+        let details = match &check.error {
+            None => json!({ "url": check.url }),
+            Some(e) => json!({ "url": check.url, "error": e, "hint": check.kind.guidance() }),
+        };
+        let latency = Duration::from_secs_f64(check.latency_ms / 1000.0);
+        report.step(check.name, check.ok, Some(latency), details);
+    }
+
+    let threshold = Duration::from_secs(env.settings.max_clock_skew_secs);
+    let start = Instant::now();
+    match clock::measure(&env.endpoints.clob_api).await {
+        Ok(sample) => report.step(
+            "clock_skew",
+            !sample.exceeds(threshold),
+            Some(start.elapsed()),
+            json!({
+                "offset_ms": sample.offset_ms,
+                "rtt_ms": sample.rtt_ms,
+                "threshold_secs": threshold.as_secs(),
+            }),
+        ),
+        Err(e) => report.step(
+            "clock_skew",
+            false,
+            Some(start.elapsed()),
+            json!({ "error": format!("{e:#}") }),
+        ),
+    }
+    Ok(())
+}
+
+fn replay(report: &mut Report, path: &Path) -> Result<()> {
+    let records = Journal::read(path)?;
+    report.step(
+        "read",
+        true,
+        None,
+        json!({ "path": path.display().to_string(), "records": records.len() }),
+    );
+
+    let replayed = journal::replay(&records)?;
+    report.step(
+        "replay",
+        true,
+        None,
+        json!({
+            "sessions": replayed.sessions,
+            "mutations": replayed.mutations,
+            "duplicates_skipped": replayed.duplicates,
+            "events": replayed.events,
+        }),
+    );
+    for mismatch in &replayed.mismatches {
+        report.step(
+            format!("snapshot at record {}", mismatch.record),
+            false,
+            None,
+            json!({ "diffs": mismatch.diffs }),
+        );
+    }
+    report.step(
+        "verify",
+        replayed.snapshots > 0 && replayed.mismatches.is_empty(),
+        None,
+        json!({
+            "snapshots": replayed.snapshots,
+            "mismatches": replayed.mismatches.len(),
+            "final_state": replayed.state,
+        }),
+    );
+    Ok(())
+}
+
+async fn order_roundtrip(
+    env: &Env,
+    report: &mut Report,
+    token: Option<String>,
+    execute: bool,
+) -> Result<()> {
+    let start = Instant::now();
+    let ctx = auth::authenticate(env.private_key()?, &env.endpoints.clob_api).await?;
+    report.step(
+        "auth",
+        true,
+        Some(start.elapsed()),
+        json!({ "eoa": ctx.eoa.to_string(), "safe": ctx.safe.to_string() }),
+    );
+
+    let start = Instant::now();
+    let bal = ctx
+        .client
+        .balance_allowance(BalanceAllowanceRequest::default())
+        .await
+        .context("failed to fetch balance")?;
+    let balance_usd = bal.balance.to_f64().unwrap_or(0.0) / 1_000_000.0;
+    report.step(
+        "balance",
+        true,
+        Some(start.elapsed()),
+        json!({ "usdc": balance_usd, "allowances": bal.allowances.len() }),
+    );
+
+    let token_id = match token {
+        Some(token) => token,
+        None => pick_token_id(env).await?,
+    };
+    let tick = ctx.client.tick_size(&token_id).await?;
+    let neg_risk = ctx.client.neg_risk(&token_id).await?;
+    report.step(
+        "market",
+        true,
+        None,
+        json!({
+            "token_id": token_id,
+            "tick_size": format!("{:?}", tick.minimum_tick_size),
+            "neg_risk": neg_risk.neg_risk,
+        }),
+    );
+
+    // Deliberately unfillable: BUY 5 shares at $0.01
+    let start = Instant::now();
+    let signable = ctx
+        .client
+        .limit_order()
+        .token_id(&token_id)
+        .price(dec!(0.01))
+        .size(dec!(5.0))
+        .side(Side::Buy)
+        .build()
+        .await?;
+    let signed = ctx.client.sign(&ctx.signer, signable).await?;
+    let posted = ctx.client.post_order(signed).await?;
+    report.step(
+        "limit_order",
+        posted.success && !posted.order_id.is_empty(),
+        Some(start.elapsed()),
+        json!({
+            "order_id": posted.order_id,
+            "status": format!("{:?}", posted.status),
+            "error": posted.error_msg,
+        }),
+    );
+
+    if !posted.order_id.is_empty() {
+        let start = Instant::now();
+        let order = ctx.client.order(&posted.order_id).await?;
+        report.step(
+            "query",
+            true,
+            Some(start.elapsed()),
+            json!({ "status": format!("{:?}", order.status), "size_matched": order.size_matched.to_string() }),
+        );
+
+        let start = Instant::now();
+        let cancel = ctx.client.cancel_order(&posted.order_id).await?;
+        report.step(
+            "cancel",
+            cancel.canceled.contains(&posted.order_id),
+            Some(start.elapsed()),
+            json!({ "canceled": cancel.canceled, "not_canceled": cancel.not_canceled }),
+        );
+    }
+
+    if execute {
+        if balance_usd < 1.0 {
+            bail!("insufficient balance (${balance_usd:.2}) for a $1.00 market order");
+        }
+        let start = Instant::now();
+        let signable = ctx
+            .client
+            .market_order()
+            .token_id(&token_id)
+            .side(Side::Buy)
+            .amount(Amount::usdc(dec!(1.00))?)
+            .order_type(OrderType::FAK)
+            .build()
+            .await?;
+        let signed = ctx.client.sign(&ctx.signer, signable).await?;
+        let posted = ctx.client.post_order(signed).await?;
+        report.step(
+            "market_order",
+            posted.success,
+            Some(start.elapsed()),
+            json!({
+                "order_id": posted.order_id,
+                "status": format!("{:?}", posted.status),
+                "making_amount": posted.making_amount.to_string(),
+                "taking_amount": posted.taking_amount.to_string(),
+                "error": posted.error_msg,
+            }),
+        );
+    }
+    Ok(())
+}
+
+/// Pick a liquid token: the top-volume trader's largest position priced
+/// between $0.05 and $0.95.
+async fn pick_token_id(env: &Env) -> Result<String> {
+    let (leaderboard, _) = env
+        .data_get(
+            "/v1/leaderboard",
+            &[("limit", "1"), ("orderBy", "vol"), ("timePeriod", "day")],
+        )
+        .await?;
+    let trader = leaderboard
+        .as_array()
+        .and_then(|a| a.first())
+        .and_then(|t| t.get("proxyWallet").or(t.get("address")))
+        .and_then(Value::as_str)
+        .context("could not find a trader on the leaderboard")?
+        .to_string();
+
+    let (positions, _) = env
+        .data_get(
+            "/positions",
+            &[
+                ("user", &trader),
+                ("limit", "5"),
+                ("sortBy", "value"),
+                ("sortOrder", "desc"),
+            ],
+        )
+        .await?;
+    positions
+        .as_array()
+        .into_iter()
+        .flatten()
+        .find_map(|p| {
+            let value = parse_f64(p, "currentValue")?;
+            let price = parse_f64(p, "curPrice")?;
+            (value > 0.0 && price > 0.05 && price < 0.95)
+                .then(|| p.get("asset")?.as_str().map(String::from))
+                .flatten()
+        })
+        .context("could not find a suitable active position with a token ID")
+}
+
+fn parse_f64(val: &Value, field: &str) -> Option<f64> {
+    val.get(field).and_then(|v| {
+        v.as_f64()
+            .or_else(|| v.as_str().and_then(|s| s.parse::<f64>().ok()))
+    })
+}
@@ -20,8 +20,8 @@ use polymarket_client_sdk::clob::{Client, Config};
 use polymarket_client_sdk::{POLYGON, derive_safe_wallet};
 use rust_decimal::prelude::ToPrimitive;
 
-use polymarket_copytrade::CLOB_API_BASE;
 use polymarket_copytrade::config::{AppConfig, CONFIG_PATH};
+use polymarket_copytrade::secret::Secret;
 
 #[derive(Parser)]
 #[command(
@@ -33,6 +33,11 @@ struct Cli {
     /// If omitted, reads interactively with hidden input (recommended).
     #[arg(long)]
     private_key: Option<String>,
+
+    /// Save the key to `[profile.<name>]` instead of `[account]`.
+    /// The profile is created if it does not exist.
+    #[arg(long)]
+    profile: Option<String>,
 }
 
 #[tokio::main]
@@ -51,7 +56,7 @@ async fn main() -> Result<()> {
     println!("=== Polymarket Copytrade — Account Setup ===\n");
 
     // ── Step 1: Read private key ───────────────────────────────────
-    let private_key = match cli.private_key {
+    let private_key = Secret::new(match cli.private_key {
         Some(key) => key,
         None => {
             let key = rpassword::prompt_password("Enter private key (hex): ")
@@ -61,11 +66,11 @@ async fn main() -> Result<()> {
             }
             key.trim().to_string()
         }
-    };
+    });
 
     // ── Step 2: Validate private key ───────────────────────────────
     println!("Validating private key...");
-    let signer = LocalSigner::from_str(&private_key)
+    let signer = LocalSigner::from_str(private_key.expose())
         .context("invalid private key — expected hex-encoded (with or without 0x prefix)")?
         .with_chain_id(Some(POLYGON));
 
@@ -79,7 +84,13 @@ async fn main() -> Result<()> {
     // ── Step 3: Authenticate with CLOB ─────────────────────────────
     println!("Authenticating with CLOB API...");
     let config = Config::builder().use_server_time(true).build();
-    let client = Client::new(CLOB_API_BASE, config)?
+    let clob_api = cli
+        .profile
+        .as_deref()
+        .and_then(|name| app_config.profile.get(name))
+        .and_then(|profile| profile.clob_api.clone())
+        .unwrap_or_else(|| app_config.endpoints.clob_api.clone());
+    let client = Client::new(&clob_api, config)?
         .authentication_builder(&signer)
         .signature_type(SignatureType::GnosisSafe)
         .authenticate()
@@ -104,8 +115,20 @@ async fn main() -> Result<()> {
     println!();
 
     // ── Step 5: Update private key in config.toml ──────────────────
-    println!("Updating private key in {}...", config_path.display());
-    app_config.account.private_key = private_key;
+    match &cli.profile {
+        Some(name) => {
+            println!("Updating private key for profile '{name}' in {}...", config_path.display());
+            app_config
+                .profile
+                .entry(name.clone())
+                .or_default()
+                .private_key = Some(private_key);
+        }
+        None => {
+            println!("Updating private key in {}...", config_path.display());
+            app_config.account.private_key = private_key;
+        }
+    }
     app_config.save(config_path)?;
     println!("  Config updated successfully");
     println!();
@@ -120,6 +143,9 @@ async fn main() -> Result<()> {
     println!();
     println!("Next steps:");
     println!("  cargo run --bin copytrade -- --dry-run \\");
+    if let Some(name) = &cli.profile {
+        println!("    --profile {name} \\");
+    }
     println!("    --trader-address <proxy_wallet> \\");
     println!("    --budget 1000 --copy-percentage 50 --max-trade-size 30");
 
@@ -0,0 +1,295 @@
+use std::time::Duration;
+
+use polymarket_client_sdk::types::Address;
+use polymarket_client_sdk::{POLYGON, contract_config};
+use serde::Deserialize;
+use serde_json::json;
+use tokio::sync::watch;
+use tokio_tungstenite::tungstenite::Message;
+
+use crate::types::OrderSide;
+use crate::ws::{Keepalive, ReconnectPolicy, WsFeed};
+
+/// `keccak256("OrderFilled(bytes32,address,address,uint256,uint256,uint256,uint256,uint256)")`,
+/// emitted by the CTF Exchange for every order filled in a match.
+pub const ORDER_FILLED_TOPIC: &str =
+    "0xd0a08e8c493f9c94f29311604c9de1b4e8c8d4c06bd0c789af57f2d65bfec0f6";
+
+/// Outcome tokens and USDC both have 6 decimals.
+const TOKEN_DECIMALS: f64 = 1_000_000.0;
+
+/// Protocol pings keep the subscription alive; fills themselves can be hours
+/// apart, so there is no idle watchdog.
+const KEEPALIVE: Keepalive = Keepalive::WsPing(Duration::from_secs(20));
+
+/// A fill of the trader's order, decoded from an `OrderFilled` log.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChainFill {
+    pub tx_hash: String,
+    /// Token ID (decimal), as in the data API.
+    pub asset: String,
+    pub side: OrderSide,
+    pub size: f64,
+    pub price: f64,
+    pub block_number: u64,
+    /// Block timestamp in seconds, when the node includes it with the log.
+    pub timestamp: Option<i64>,
+}
+
+/// The CTF Exchange and the neg-risk CTF Exchange on Polygon.
+pub fn exchanges() -> Vec<Address> {
+    [false, true]
+        .into_iter()
+        .filter_map(|neg_risk| contract_config(POLYGON, neg_risk))
+        .map(|config| config.exchange)
+        .collect()
+}
+
+/// An address as a 32-byte log topic.
+fn address_topic(address: Address) -> String {
+    format!("0x{:0>64}", hex_digits(&address.to_string()))
+}
+
+fn hex_digits(value: &str) -> String {
+    value.trim_start_matches("0x").to_ascii_lowercase()
+}
+
+/// `eth_subscribe` frame for `OrderFilled` logs whose maker is `wallet`.
+///
+/// The exchange emits one log per filled order with the order's owner as
+/// `maker`, including the taker order (whose `taker` is the exchange itself),
+/// so filtering on `maker` sees every fill of the wallet's orders once.
+pub fn logs_subscription(wallet: Address) -> String {
+    let exchanges: Vec<String> = exchanges().iter().map(|a| a.to_string()).collect();
+    json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "eth_subscribe",
+        "params": ["logs", {
+            "address": exchanges,
+            "topics": [ORDER_FILLED_TOPIC, null, address_topic(wallet)],
+        }],
+    })
+    .to_string()
+}
+
+#[derive(Deserialize)]
+struct Notification {
+    params: NotificationParams,
+}
+
+#[derive(Deserialize)]
+struct NotificationParams {
+    result: Log,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Log {
+    topics: Vec<String>,
+    data: String,
+    block_number: String,
+    transaction_hash: String,
+    #[serde(default)]
+    block_timestamp: Option<String>,
+    /// Set when a reorg dropped a previously delivered log.
+    #[serde(default)]
+    removed: bool,
+}
+
+/// A 32-byte word as a `u128`, or `None` if it doesn't fit.
+fn word_u128(word: &str) -> Option<u128> {
+    let (high, low) = word.split_at(word.len().checked_sub(32)?);
+    if high.chars().any(|c| c != '0') {
+        return None;
+    }
+    u128::from_str_radix(low, 16).ok()
+}
+
+/// A 32-byte word as a decimal string (token IDs use the full 256 bits).
+fn word_decimal(word: &str) -> Option<String> {
+    let mut digits: Vec<u8> = vec![0];
+    for c in word.chars() {
+        let mut carry = c.to_digit(16)?;
+        // digits (little-endian, base 10) = digits * 16 + carry
+        for d in digits.iter_mut() {
+            let v = *d as u32 * 16 + carry;
+            *d = (v % 10) as u8;
+            carry = v / 10;
+        }
+        while carry > 0 {
+            digits.push((carry % 10) as u8);
+            carry /= 10;
+        }
+    }
+    while digits.len() > 1 && digits.last() == Some(&0) {
+        digits.pop();
+    }
+    Some(digits.iter().rev().map(|d| char::from(b'0' + d)).collect())
+}
+
+fn quantity(value: &str) -> Option<u64> {
+    u64::from_str_radix(&hex_digits(value), 16).ok()
+}
+
+/// Decode an `eth_subscription` frame into a fill of `wallet`'s order. Other
+/// frames (the subscription ID reply, other events or makers, logs removed by
+/// a reorg) yield `None`.
+pub fn parse_fill(msg: &Message, wallet: Address) -> Option<ChainFill> {
+    let Message::Text(text) = msg else {
+        return None;
+    };
+    let log = serde_json::from_str::<Notification>(text.as_str())
+        .ok()?
+        .params
+        .result;
+    if log.removed
+        || log.topics.len() < 3
+        || !log.topics[0].eq_ignore_ascii_case(ORDER_FILLED_TOPIC)
+        || !log.topics[2].eq_ignore_ascii_case(&address_topic(wallet))
+    {
+        return None;
+    }
+
+    // makerAssetId, takerAssetId, makerAmountFilled, takerAmountFilled, fee
+    let data = hex_digits(&log.data);
+    if data.len() < 64 * 4 {
+        return None;
+    }
+    let word = |i: usize| &data[i * 64..(i + 1) * 64];
+    let maker_amount = word_u128(word(2))? as f64;
+    let taker_amount = word_u128(word(3))? as f64;
+    if maker_amount <= 0.0 || taker_amount <= 0.0 {
+        return None;
+    }
+    // Asset ID 0 is USDC: the maker paid USDC for tokens (a buy)
+    let (side, asset, shares, usdc) = if word_u128(word(0)) == Some(0) {
+        (
+            OrderSide::Buy,
+            word_decimal(word(1))?,
+            taker_amount,
+            maker_amount,
+        )
+    } else {
+        (
+            OrderSide::Sell,
+            word_decimal(word(0))?,
+            maker_amount,
+            taker_amount,
+        )
+    };
+
+    Some(ChainFill {
+        tx_hash: log.transaction_hash,
+        asset,
+        side,
+        size: shares / TOKEN_DECIMALS,
+        price: usdc / shares,
+        block_number: quantity(&log.block_number)?,
+        timestamp: log
+            .block_timestamp
+            .as_deref()
+            .and_then(quantity)
+            .map(|ts| ts as i64),
+    })
+}
+
+/// Reconnecting feed of `wallet`'s `OrderFilled` logs from a Polygon node's
+/// WebSocket RPC at `url`.
+///
+/// Returns the subscription sender alongside the feed; the feed stops when it
+/// is dropped.
+pub fn fills_feed(url: &str, wallet: Address) -> (WsFeed, watch::Sender<Vec<String>>) {
+    let (tx, rx) = watch::channel(vec![logs_subscription(wallet)]);
+    let feed = WsFeed {
+        name: "polygon",
+        url: url.to_string(),
+        keepalive: KEEPALIVE,
+        idle_timeout: None,
+        policy: ReconnectPolicy::default(),
+        subscriptions: rx,
+    };
+    (feed, tx)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    const WALLET: &str = "0xdb27bf2ac5d428a9c63dbc914611036855a6c56e";
+
+    fn word(value: u128) -> String {
+        format!("{value:064x}")
+    }
+
+    fn log_frame(maker: &str, data: &str, removed: bool) -> Message {
+        let text = json!({
+            "jsonrpc": "2.0",
+            "method": "eth_subscription",
+            "params": {
+                "subscription": "0x1",
+                "result": {
+                    "address": "0x4bfb41d5b3570defd03c39a9a4d8de6bd8b8982e",
+                    "topics": [
+                        ORDER_FILLED_TOPIC,
+                        format!("0x{}", word(7)),
+                        format!("0x{:0>64}", maker.trim_start_matches("0x")),
+                        format!("0x{:0>64}", "4bfb41d5b3570defd03c39a9a4d8de6bd8b8982e"),
+                    ],
+                    "data": data,
+                    "blockNumber": "0x4c4b40",
+                    "transactionHash": "0xabc",
+                    "logIndex": "0x3",
+                    "blockTimestamp": "0x6989f0fd",
+                    "removed": removed
+                }
+            }
+        });
+        Message::Text(text.to_string().into())
+    }
+
+    #[test]
+    fn decodes_the_wallets_fills() {
+        let wallet = Address::from_str(WALLET).unwrap();
+        // Token ID above u128, to exercise the decimal conversion
+        let token = "1".repeat(64);
+        let buy = format!(
+            "0x{}{token}{}{}{}",
+            word(0),
+            word(2_400_000),
+            word(4_000_000),
+            word(0)
+        );
+        let fill = parse_fill(&log_frame(WALLET, &buy, false), wallet).unwrap();
+        assert_eq!(fill.side, OrderSide::Buy);
+        assert_eq!(
+            fill.asset,
+            "7719472615821079694904732333912527190217998977709370935963838933860875309329"
+        );
+        assert_eq!((fill.size, fill.price), (4.0, 0.6));
+        assert_eq!(
+            (fill.block_number, fill.timestamp),
+            (5_000_000, Some(1_770_647_805))
+        );
+
+        let sell = format!(
+            "0x{}{}{}{}{}",
+            word(123),
+            word(0),
+            word(10_000_000),
+            word(2_500_000),
+            word(0)
+        );
+        let fill = parse_fill(&log_frame(WALLET, &sell, false), wallet).unwrap();
+        assert_eq!((fill.side, fill.asset.as_str()), (OrderSide::Sell, "123"));
+        assert_eq!((fill.size, fill.price), (10.0, 0.25));
+
+        // Other makers, reorged logs, and the subscription reply are ignored
+        let other = "0x0000000000000000000000000000000000000001";
+        assert!(parse_fill(&log_frame(other, &buy, false), wallet).is_none());
+        assert!(parse_fill(&log_frame(WALLET, &buy, true), wallet).is_none());
+        let reply = Message::Text(r#"{"jsonrpc":"2.0","id":1,"result":"0x1"}"#.into());
+        assert!(parse_fill(&reply, wallet).is_none());
+    }
+}
@@ -0,0 +1,173 @@
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use polymarket_client_sdk::clob::{Client as ClobClient, Config as ClobConfig};
+
+/// Server time has one-second resolution, so offsets below this are noise.
+const RESOLUTION_MS: i64 = 1_000;
+
+/// Correction applied by `now()`, in milliseconds (server minus local).
+static OFFSET_MS: AtomicI64 = AtomicI64::new(0);
+
+/// Current time, corrected for the last measured skew against the CLOB.
+///
+/// Use this instead of `Utc::now()` wherever local time is compared with
+/// exchange timestamps (trade latency, cooldowns, market end dates) so every
+/// module agrees on the server's notion of time.
+pub fn now() -> DateTime<Utc> {
+    Utc::now() + chrono::Duration::milliseconds(offset_ms())
+}
+
+/// Correction currently applied by `now()`, in milliseconds.
+pub fn offset_ms() -> i64 {
+    OFFSET_MS.load(Ordering::Relaxed)
+}
+
+/// Make `now()` read `at` from here on (replaying a recorded session). Time
+/// keeps running from there.
+pub fn pin(at: DateTime<Utc>) {
+    OFFSET_MS.store((at - Utc::now()).num_milliseconds(), Ordering::Relaxed);
+}
+
+/// Parse a span like `90s`, `45m`, `8h`, `2d`, or `1h30m`.
+pub fn parse_span(s: &str) -> Result<Duration> {
+    let mut total = 0u64;
+    let mut digits = String::new();
+    for c in s.trim().chars() {
+        if c.is_ascii_digit() {
+            digits.push(c);
+            continue;
+        }
+        let unit = match c {
+            's' => 1,
+            'm' => 60,
+            'h' => 3600,
+            'd' => 86_400,
+            _ => anyhow::bail!("invalid duration {s:?}: unknown unit {c:?} (use s, m, h, d)"),
+        };
+        let Ok(n) = digits.parse::<u64>() else {
+            anyhow::bail!("invalid duration {s:?}: expected a number before {c:?}");
+        };
+        total = n
+            .checked_mul(unit)
+            .and_then(|secs| total.checked_add(secs))
+            .with_context(|| format!("invalid duration {s:?}: too long"))?;
+        digits.clear();
+    }
+    if !digits.is_empty() || total == 0 {
+        anyhow::bail!("invalid duration {s:?}: expected e.g. 90s, 45m, 8h, 2d, or 1h30m");
+    }
+    Ok(Duration::from_secs(total))
+}
+
+/// One clock comparison against the CLOB server.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SkewSample {
+    /// Server minus local time, in milliseconds (positive: local clock is behind).
+    pub offset_ms: i64,
+    /// Round trip of the `/time` request, in milliseconds.
+    pub rtt_ms: i64,
+}
+
+impl SkewSample {
+    /// Estimate the offset from a server timestamp (seconds) received between
+    /// two local readings (milliseconds). The server time is assumed to be taken
+    /// at the round-trip midpoint, and the middle of its one-second bucket.
+    pub fn estimate(sent_ms: i64, received_ms: i64, server_secs: i64) -> Self {
+        let midpoint_ms = sent_ms + (received_ms - sent_ms) / 2;
+        Self {
+            offset_ms: server_secs * 1000 + RESOLUTION_MS / 2 - midpoint_ms,
+            rtt_ms: received_ms - sent_ms,
+        }
+    }
+
+    /// Whether the skew exceeds `threshold`.
+    pub fn exceeds(&self, threshold: Duration) -> bool {
+        self.offset_ms.unsigned_abs() > threshold.as_millis() as u64
+    }
+}
+
+/// Measure the local clock against the CLOB `/time` endpoint (no auth needed).
+pub async fn measure(clob_host: &str) -> Result<SkewSample> {
+    let client = ClobClient::new(clob_host, ClobConfig::default())?;
+    let sent_ms = Utc::now().timestamp_millis();
+    let server_secs = client.server_time().await?;
+    let received_ms = Utc::now().timestamp_millis();
+    Ok(SkewSample::estimate(sent_ms, received_ms, server_secs))
+}
+
+/// Apply a measurement to `now()`, warning if the skew exceeds `threshold`.
+///
+/// Offsets within the server's one-second resolution are treated as zero.
+pub fn apply(sample: SkewSample, threshold: Duration) {
+    let offset = if sample.offset_ms.abs() < RESOLUTION_MS {
+        0
+    } else {
+        sample.offset_ms
+    };
+    OFFSET_MS.store(offset, Ordering::Relaxed);
+    if sample.exceeds(threshold) {
+        tracing::warn!(
+            "Local clock is {:+.1}s off CLOB server time (rtt {}ms, threshold {}s) — \
+             correcting internally; fix NTP sync on this machine",
+            sample.offset_ms as f64 / 1000.0,
+            sample.rtt_ms,
+            threshold.as_secs()
+        );
+    } else {
+        tracing::debug!(
+            "Clock skew vs CLOB: {:+}ms (rtt {}ms)",
+            sample.offset_ms,
+            sample.rtt_ms
+        );
+    }
+}
+
+/// Measure and apply, logging (not failing) when the server is unreachable.
+pub async fn sync(clob_host: &str, threshold: Duration) -> Option<SkewSample> {
+    match measure(clob_host).await {
+        Ok(sample) => {
+            apply(sample, threshold);
+            Some(sample)
+        }
+        Err(e) => {
+            tracing::warn!("Clock skew check failed: {e:#}");
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn estimate_uses_round_trip_midpoint() {
+        // Local clock 3s behind: request sent at 10.0s, answered at 10.2s,
+        // server said 13s (i.e. somewhere in [13.0, 14.0))
+        let sample = SkewSample::estimate(10_000, 10_200, 13);
+        assert_eq!(sample.rtt_ms, 200);
+        assert_eq!(sample.offset_ms, 13_500 - 10_100);
+        assert!(sample.exceeds(Duration::from_secs(2)));
+        assert!(!sample.exceeds(Duration::from_secs(5)));
+
+        let ahead = SkewSample::estimate(20_000, 20_100, 15);
+        assert_eq!(ahead.offset_ms, -4_550);
+        assert!(ahead.exceeds(Duration::from_secs(2)));
+    }
+
+    #[test]
+    fn parses_spans() {
+        assert_eq!(parse_span("8h").unwrap(), Duration::from_secs(8 * 3600));
+        assert_eq!(parse_span("1h30m").unwrap(), Duration::from_secs(5400));
+        assert_eq!(parse_span("2d").unwrap(), Duration::from_secs(172_800));
+        assert!(parse_span("8").is_err());
+        assert!(parse_span("8x").is_err());
+        assert!(parse_span("h").is_err());
+        assert!(parse_span("0m").is_err());
+        assert!(parse_span("99999999999999999d").is_err());
+        assert!(parse_span("18446744073709551615s1s").is_err());
+    }
+}
@@ -1,7 +1,19 @@
+use std::collections::BTreeMap;
 use std::path::Path;
+use std::time::Duration;
 
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::engine::MarketWeighting;
+use crate::filters::{CategoryVerdicts, MarketFilters};
+use crate::intent::TradeCluster;
+use crate::secret::{REDACTED, Secret};
+use crate::tags::TagRules;
+use crate::{
+    CLOB_API_BASE, CLOB_WS_MARKET_URL, CLOB_WS_USER_URL, DATA_API_BASE, GAMMA_API_BASE, RTDS_WS_URL,
+};
 
 /// Default config file path.
 pub const CONFIG_PATH: &str = "config.toml";
@@ -12,13 +24,41 @@ pub struct AppConfig {
     pub account: AccountConfig,
     #[serde(default)]
     pub settings: SettingsConfig,
+    #[serde(default)]
+    pub endpoints: EndpointsConfig,
+    #[serde(default)]
+    pub engine: EngineParams,
+    #[serde(default)]
+    pub executor: ExecutorParams,
+    /// Criteria for `--graduate` to switch a dry-run session to live.
+    #[serde(default)]
+    pub graduation: GraduationConfig,
+    /// P&L milestone and position move notifications.
+    #[serde(default)]
+    pub milestones: MilestonesConfig,
+    /// Per-position stop-loss and take-profit exits.
+    #[serde(default)]
+    pub risk: RiskConfig,
+    /// Where state mutations are persisted (`[persistence]`).
+    #[serde(default)]
+    pub persistence: PersistenceConfig,
+    /// Retries of failed API requests (`[retry]`).
+    #[serde(default)]
+    pub retry: RetryConfig,
+    /// Rate limit overrides (`[rate_limits]`): endpoint → requests per 10s window.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub rate_limits: BTreeMap<String, u32>,
+    /// Named profiles (`[profile.<name>]`), selected via `--profile`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub profile: BTreeMap<String, ProfileConfig>,
 }
 
 /// Account credentials.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AccountConfig {
-    /// Hex-encoded private key (with or without 0x prefix).
-    pub private_key: String,
+    /// Hex-encoded private key (with or without 0x prefix). Redacted when
+    /// printed or serialized; only `save` writes it out.
+    pub private_key: Secret<String>,
 }
 
 /// Runtime settings.
@@ -27,20 +67,1054 @@ pub struct SettingsConfig {
     /// Polling interval in seconds for trade detection.
     #[serde(default = "default_poll_interval")]
     pub poll_interval_secs: u64,
+    /// Upper bound in seconds for the poll interval while backing off from
+    /// data API errors.
+    #[serde(default = "default_max_poll_interval")]
+    pub max_poll_interval_secs: u64,
+    /// Factor the poll interval is multiplied by after each failed cycle
+    /// (applied twice for rate limit responses).
+    #[serde(default = "default_poll_backoff_multiplier")]
+    pub poll_backoff_multiplier: f64,
+    /// Run a full rebalance when a gap in the trade stream is detected, even if
+    /// no new trades were seen (gaps are always logged as warnings).
+    #[serde(default)]
+    pub rebalance_on_gap: bool,
+    /// Warn when the local clock differs from CLOB server time by more than
+    /// this many seconds (the bot corrects for the skew either way).
+    #[serde(default = "default_max_clock_skew")]
+    pub max_clock_skew_secs: u64,
+    /// How often, in seconds, to re-measure clock skew while running.
+    #[serde(default = "default_clock_check_interval")]
+    pub clock_check_interval_secs: u64,
+    /// Detect the trader's trades on the RTDS `activity/trades` WebSocket and
+    /// rebalance as they arrive. REST polling continues as a fallback.
+    #[serde(default = "default_ws_detection")]
+    pub ws_detection: bool,
+    /// REST poll interval in seconds while the WebSocket feed is connected
+    /// (`poll_interval_secs` applies while it is down).
+    #[serde(default = "default_ws_fallback_poll_interval")]
+    pub ws_fallback_poll_secs: u64,
+    /// Detect the trader's fills from CTF Exchange `OrderFilled` logs on
+    /// Polygon, via `endpoints.polygon_ws`. Runs alongside the other detectors.
+    #[serde(default)]
+    pub chain_detection: bool,
+    /// Poll the data API `/activity` endpoint instead of `/trades`, so splits,
+    /// merges, redemptions and conversions also trigger a rebalance.
+    #[serde(default)]
+    pub activity_detection: bool,
+    /// Live mode: track our order fills on the CLOB user-channel WebSocket
+    /// instead of sleeping and querying each order's status.
+    #[serde(default = "default_ws_fills")]
+    pub ws_fills: bool,
+    /// Mark held and target tokens at live prices from the CLOB market
+    /// WebSocket instead of the prices reported with the trader's positions.
+    #[serde(default = "default_ws_prices")]
+    pub ws_prices: bool,
+    /// Most tokens subscribed on the market WebSocket at once. Beyond it, the
+    /// tokens the trader hasn't traded for longest are left out and marked at
+    /// API prices.
+    #[serde(default = "default_ws_prices_max_assets")]
+    pub ws_prices_max_assets: usize,
+    /// Consecutive data API errors or stale responses before failing over to
+    /// `endpoints.data_api_secondary`.
+    #[serde(default = "default_data_api_failover_errors")]
+    pub data_api_failover_errors: u32,
+    /// A trades response is stale when it lacks a trade known (from another
+    /// detector or endpoint) for this many seconds.
+    #[serde(default = "default_data_api_stale")]
+    pub data_api_stale_secs: u64,
+    /// While failed over, retry the primary data API every this many seconds
+    /// and switch back once it answers.
+    #[serde(default = "default_data_api_failback")]
+    pub data_api_failback_secs: u64,
+    /// Raise a critical alert after this many consecutive failed poll cycles,
+    /// and every this many after (see `backoff::ErrorBudget`). 0 disables.
+    #[serde(default = "default_poll_error_alert_threshold")]
+    pub poll_error_alert_threshold: u32,
+    /// The trader's trades on one market less than this many seconds apart
+    /// are read as one intent (accumulate, unwind, flip) in the events.
+    #[serde(default = "default_intent_window")]
+    pub intent_window_secs: u64,
+}
+
+fn default_intent_window() -> u64 {
+    60
+}
+
+fn default_poll_error_alert_threshold() -> u32 {
+    5
 }
 
 fn default_poll_interval() -> u64 {
     10
 }
 
+fn default_max_poll_interval() -> u64 {
+    120
+}
+
+fn default_poll_backoff_multiplier() -> f64 {
+    2.0
+}
+
+fn default_max_clock_skew() -> u64 {
+    2
+}
+
+fn default_clock_check_interval() -> u64 {
+    600
+}
+
+fn default_ws_detection() -> bool {
+    true
+}
+
+fn default_ws_fallback_poll_interval() -> u64 {
+    60
+}
+
+fn default_ws_fills() -> bool {
+    true
+}
+
+fn default_ws_prices() -> bool {
+    true
+}
+
+fn default_ws_prices_max_assets() -> usize {
+    500
+}
+
+fn default_data_api_failover_errors() -> u32 {
+    3
+}
+
+fn default_data_api_stale() -> u64 {
+    120
+}
+
+fn default_data_api_failback() -> u64 {
+    300
+}
+
 impl Default for SettingsConfig {
     fn default() -> Self {
         Self {
             poll_interval_secs: default_poll_interval(),
+            max_poll_interval_secs: default_max_poll_interval(),
+            poll_backoff_multiplier: default_poll_backoff_multiplier(),
+            rebalance_on_gap: false,
+            max_clock_skew_secs: default_max_clock_skew(),
+            clock_check_interval_secs: default_clock_check_interval(),
+            ws_detection: default_ws_detection(),
+            ws_fallback_poll_secs: default_ws_fallback_poll_interval(),
+            chain_detection: false,
+            activity_detection: false,
+            ws_fills: default_ws_fills(),
+            ws_prices: default_ws_prices(),
+            ws_prices_max_assets: default_ws_prices_max_assets(),
+            data_api_failover_errors: default_data_api_failover_errors(),
+            data_api_stale_secs: default_data_api_stale(),
+            data_api_failback_secs: default_data_api_failback(),
+            poll_error_alert_threshold: default_poll_error_alert_threshold(),
+            intent_window_secs: default_intent_window(),
+        }
+    }
+}
+
+/// Paper-to-live criteria (`[graduation]`), all of which must hold.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GraduationConfig {
+    /// Minimum dry-run duration in days.
+    #[serde(default = "default_graduation_min_days")]
+    pub min_days: f64,
+    /// Simulated P&L (% of budget) must be above this.
+    #[serde(default)]
+    pub min_pnl_pct: f64,
+    /// Mean allocation tracking error vs the trader (%) must be at most this.
+    #[serde(default = "default_graduation_max_tracking_error")]
+    pub max_tracking_error_pct: f64,
+}
+
+fn default_graduation_min_days() -> f64 {
+    7.0
+}
+
+fn default_graduation_max_tracking_error() -> f64 {
+    10.0
+}
+
+impl Default for GraduationConfig {
+    fn default() -> Self {
+        Self {
+            min_days: default_graduation_min_days(),
+            min_pnl_pct: 0.0,
+            max_tracking_error_pct: default_graduation_max_tracking_error(),
+        }
+    }
+}
+
+/// State persistence (`[persistence]`): the backend behind `--wal`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PersistenceConfig {
+    #[serde(default)]
+    pub backend: StoreBackend,
+    /// Keep reported events in the store itself (WAL lines, or a table of the
+    /// SQLite database) instead of the rotated event log. Neither is ever
+    /// compacted, so the store then grows with every event.
+    #[serde(default)]
+    pub events_in_store: bool,
+    /// Rotation and retention of the event log (`[persistence.events]`).
+    #[serde(default)]
+    pub events: EventRetention,
+}
+
+impl PersistenceConfig {
+    /// Retention of the event log beside the store, or `None` when events go
+    /// into the store (`events_in_store`).
+    pub fn event_log(&self) -> Option<EventRetention> {
+        (!self.events_in_store).then(|| self.events.clone())
+    }
+}
+
+/// Rotation and retention of the event log (see `eventlog::EventLog`).
+///
+/// The active file is rotated once it reaches `max_file_mb`. Rotated files are
+/// gzipped with `compress`, and deleted once older than `max_age_days` or,
+/// oldest first, while together they exceed `max_total_mb`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EventRetention {
+    #[serde(default = "default_event_file_mb")]
+    pub max_file_mb: f64,
+    #[serde(default = "default_event_compress")]
+    pub compress: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_age_days: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_total_mb: Option<f64>,
+}
+
+impl Default for EventRetention {
+    fn default() -> Self {
+        Self {
+            max_file_mb: default_event_file_mb(),
+            compress: default_event_compress(),
+            max_age_days: None,
+            max_total_mb: None,
+        }
+    }
+}
+
+fn default_event_file_mb() -> f64 {
+    50.0
+}
+
+fn default_event_compress() -> bool {
+    true
+}
+
+/// `StateStore` implementations selectable from the config (see
+/// `store::open_store`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StoreBackend {
+    /// JSON-lines write-ahead log at the `--wal` path (`store::FileStore`).
+    #[default]
+    File,
+    /// SQLite database at the `--wal` path (`store::SqliteStore`).
+    Sqlite,
+}
+
+/// Thresholds for milestone alerts (`[milestones]`). 0 disables a check.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MilestonesConfig {
+    /// Alert whenever total P&L (% of budget) crosses a multiple of this step.
+    #[serde(default)]
+    pub pnl_step_pct: f64,
+    /// Alert when a holding's unrealized P&L (% of its cost) moves past ± this.
+    #[serde(default)]
+    pub position_move_pct: f64,
+}
+
+/// Per-position exit rules (`[risk]`), in percent of a holding's average cost.
+/// Unset rules are off.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RiskConfig {
+    /// Sell a holding once its price is this far below its average cost.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stop_loss_pct: Option<f64>,
+    /// Sell a holding once its price is this far above its average cost.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub take_profit_pct: Option<f64>,
+    /// Kill switch: once effective capital falls this far (percent) below its
+    /// peak, stop buying and cancel resting orders.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_drawdown_pct: Option<f64>,
+    /// Also sell every holding when the kill switch trips.
+    #[serde(default)]
+    pub liquidate_on_kill: bool,
+    /// Pause order generation until the next UTC day once the day's P&L
+    /// (realized and unrealized) is below minus this many USD.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_daily_loss_usd: Option<f64>,
+    /// Once realized P&L reaches this many USD, take it out of the working
+    /// budget as locked profit, which is no longer risked.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lock_profit_usd: Option<f64>,
+}
+
+impl RiskConfig {
+    pub fn is_enabled(&self) -> bool {
+        self.stop_loss_pct.is_some()
+            || self.take_profit_pct.is_some()
+            || self.max_drawdown_pct.is_some()
+            || self.max_daily_loss_usd.is_some()
+    }
+}
+
+/// API base URLs. Defaults to the Polymarket production endpoints.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EndpointsConfig {
+    #[serde(default = "default_data_api")]
+    pub data_api: String,
+    /// Mirror or self-hosted indexer to fail over to when `data_api` errors or
+    /// serves stale trades.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data_api_secondary: Option<String>,
+    #[serde(default = "default_gamma_api")]
+    pub gamma_api: String,
+    #[serde(default = "default_clob_api")]
+    pub clob_api: String,
+    #[serde(default = "default_rtds_ws")]
+    pub rtds_ws: String,
+    #[serde(default = "default_clob_user_ws")]
+    pub clob_user_ws: String,
+    #[serde(default = "default_clob_market_ws")]
+    pub clob_market_ws: String,
+    /// Polygon RPC WebSocket (`eth_subscribe`) for `settings.chain_detection`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub polygon_ws: Option<String>,
+}
+
+/// Placeholder written over redacted values (see `AppConfig::redacted`).
+/// Built-in endpoints pass through; any other URL keeps only its origin.
+fn redact_url(url: &str) -> String {
+    let defaults = [
+        DATA_API_BASE,
+        GAMMA_API_BASE,
+        CLOB_API_BASE,
+        RTDS_WS_URL,
+        CLOB_WS_USER_URL,
+        CLOB_WS_MARKET_URL,
+    ];
+    if defaults.contains(&url) {
+        return url.to_string();
+    }
+    match url::Url::parse(url) {
+        Ok(parsed) => {
+            let origin = parsed.origin().ascii_serialization();
+            let bare = parsed.username().is_empty()
+                && parsed.password().is_none()
+                && matches!(parsed.path(), "" | "/")
+                && parsed.query().is_none();
+            if bare {
+                origin
+            } else {
+                format!("{origin}/{REDACTED}")
+            }
+        }
+        Err(_) => REDACTED.to_string(),
+    }
+}
+
+fn default_data_api() -> String {
+    DATA_API_BASE.to_string()
+}
+
+fn default_gamma_api() -> String {
+    GAMMA_API_BASE.to_string()
+}
+
+fn default_clob_api() -> String {
+    CLOB_API_BASE.to_string()
+}
+
+fn default_rtds_ws() -> String {
+    RTDS_WS_URL.to_string()
+}
+
+fn default_clob_user_ws() -> String {
+    CLOB_WS_USER_URL.to_string()
+}
+
+fn default_clob_market_ws() -> String {
+    CLOB_WS_MARKET_URL.to_string()
+}
+
+impl Default for EndpointsConfig {
+    fn default() -> Self {
+        Self {
+            data_api: default_data_api(),
+            data_api_secondary: None,
+            gamma_api: default_gamma_api(),
+            clob_api: default_clob_api(),
+            rtds_ws: default_rtds_ws(),
+            clob_user_ws: default_clob_user_ws(),
+            clob_market_ws: default_clob_market_ws(),
+            polygon_ws: None,
+        }
+    }
+}
+
+/// Tunable constants for the rebalancing engine (`[engine]`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EngineParams {
+    /// Minimum buy notional in USD — the CLOB rejects buys below $1. Sells have no minimum.
+    #[serde(default = "default_min_order_usd")]
+    pub min_order_usd: f64,
+    /// Round order shares down to whole numbers. Full position exits still sell
+    /// the entire (possibly fractional) balance so no dust is left behind.
+    #[serde(default)]
+    pub whole_shares: bool,
+    /// Fraction (0–1] of the gap to each target closed per poll cycle. Below 1.0,
+    /// holdings converge gradually instead of chasing every adjustment at once.
+    /// Steps under `min_order_usd` go the full distance so positions still converge;
+    /// full exits are never smoothed.
+    #[serde(default = "default_rebalance_fraction")]
+    pub rebalance_fraction: f64,
+    /// Hysteresis band below target (fraction of target shares): only buy when
+    /// holdings are under target by more than this, e.g. 0.05 = 5%.
+    #[serde(default)]
+    pub buy_band: f64,
+    /// Hysteresis band above target: only sell when over target by more than this.
+    /// Full exits ignore the band.
+    #[serde(default)]
+    pub sell_band: f64,
+    /// How long an asset is blacklisted from re-buys after a risk rule exits it
+    /// (see `TradingState::block_rebuy`), even if the trader still holds it.
+    #[serde(default = "default_rebuy_cooldown_secs")]
+    pub rebuy_cooldown_secs: u64,
+    /// Per-asset/market/event tags carried into orders and reports (`[engine.tags]`).
+    #[serde(default, skip_serializing_if = "TagRules::is_empty")]
+    pub tags: TagRules,
+    /// Markets to copy or refuse, by event, market, or title (`[engine.filters]`).
+    #[serde(default, skip_serializing_if = "MarketFilters::is_empty")]
+    pub filters: MarketFilters,
+    /// Shrink the per-market cap as markets near their end date (`[engine.end_scaling]`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub end_scaling: Option<EndScaling>,
+    /// Cap buys to a share of the ask depth near the best ask (`[engine.depth_limit]`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub depth_limit: Option<DepthLimit>,
+    /// Opt-in extra allocation for the trader's losing positions (`[engine.averaging_down]`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub averaging_down: Option<AveragingDown>,
+    /// Skip buys while the price is more than this fraction above the trader's
+    /// average entry (0.10 = 10%), instead of chasing a market that ran away.
+    /// Sells are unaffected.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_entry_premium: Option<f64>,
+    /// Cap on distinct assets held (or with resting buys). Buys that would open
+    /// a position beyond it are skipped; existing positions are still topped up.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_open_positions: Option<usize>,
+    /// Skip buys in markets priced below this (near-resolved longshots).
+    /// Holdings are kept and sells still follow the trader.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_copy_price: Option<f64>,
+    /// Skip buys in markets priced above this (near-resolved locks).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_copy_price: Option<f64>,
+    /// Size targets off at most this multiple of the initial budget (1.0 = the
+    /// budget itself), so gains beyond it stay in cash instead of compounding
+    /// into larger positions. Losses still shrink the sizing. Unset = all of
+    /// the effective capital.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_capital_multiple: Option<f64>,
+    /// Merge complete sets held (shares of both outcomes of a market, e.g.
+    /// after the trader flips sides) back into USDC after each cycle, instead
+    /// of carrying both. Dry-run only: merges are on-chain transactions.
+    #[serde(default)]
+    pub merge_complete_sets: bool,
+    /// Net the targets of a market's two outcomes against each other, so we
+    /// hold only the difference instead of both sides (see
+    /// `engine::net_opposite_outcomes`).
+    #[serde(default)]
+    pub net_opposite_outcomes: bool,
+    /// Leave the trader's complete sets (shares held on both outcomes of a
+    /// market) out of the weights, so their splits and merges don't move our
+    /// allocations (see `engine::complete_sets`).
+    #[serde(default)]
+    pub complete_sets_as_cash: bool,
+    /// Hold buys in a market while the trader's open cluster of trades on it
+    /// reads as an unwind (`intent::TradeIntent::Unwind`), instead of buying
+    /// into a position they are getting out of. Sells still follow.
+    #[serde(default)]
+    pub hold_while_unwinding: bool,
+    /// Share (0–1] of the total target value deployed while `--ramp-in` builds
+    /// the initial portfolio. Set per cycle at runtime, never from the config.
+    #[serde(skip)]
+    pub ramp_in: Option<f64>,
+    /// `[engine.filters.categories]` verdicts on this cycle's events. Set per
+    /// cycle at runtime, never from the config.
+    #[serde(skip)]
+    pub category_verdicts: CategoryVerdicts,
+    /// The trader's open trade clusters (`intent::IntentTracker::clusters`).
+    /// Set per cycle at runtime, never from the config.
+    #[serde(skip)]
+    pub trader_intents: Vec<TradeCluster>,
+}
+
+/// Sub-budget for following the trader's conviction adds to losing positions.
+///
+/// `reserve_pct` of running capital is set aside from regular sizing. Positions
+/// the trader is adding to at a loss (current price at least `min_loss_pct` below
+/// their average entry, with an open cluster of buys) may then exceed the
+/// `max_trade_pct` cap by up to `max_extra_pct` of running capital each, drawn
+/// from the reserve — largest trader weights first. The extra is held, not added
+/// to, once the trader stops adding. All fractions are 0.0–1.0.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AveragingDown {
+    pub reserve_pct: f64,
+    pub max_extra_pct: f64,
+    #[serde(default)]
+    pub min_loss_pct: f64,
+}
+
+/// Liquidity-aware buy sizing from the CLOB order book.
+///
+/// A buy may take at most `max_depth_pct` (0.0–1.0) of the shares offered within
+/// `ticks` ticks of the best ask; larger buys are cut to that (see `depth`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DepthLimit {
+    pub max_depth_pct: f64,
+    #[serde(default = "default_depth_ticks")]
+    pub ticks: u32,
+}
+
+fn default_depth_ticks() -> u32 {
+    5
+}
+
+/// Per-market cap scaling toward a market's end date.
+///
+/// Outside the last `window_hours` the cap (`max_trade_pct` of running capital) is
+/// unchanged. Inside it, the cap shrinks to `min_factor` of its full size at the end
+/// date along `(remaining / window) ^ exponent` — `exponent = 1` is linear, larger
+/// values cut size earlier, smaller values later.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EndScaling {
+    pub window_hours: f64,
+    #[serde(default)]
+    pub min_factor: f64,
+    #[serde(default = "default_end_scaling_exponent")]
+    pub exponent: f64,
+}
+
+fn default_end_scaling_exponent() -> f64 {
+    1.0
+}
+
+impl EndScaling {
+    /// Cap multiplier with `remaining` time left until the market's end date.
+    pub fn factor(&self, remaining: chrono::Duration) -> f64 {
+        let window_secs = self.window_hours * 3600.0;
+        if window_secs <= 0.0 {
+            return 1.0;
+        }
+        let remaining_secs = remaining.num_seconds() as f64;
+        let progress = (remaining_secs / window_secs).clamp(0.0, 1.0);
+        let min = self.min_factor.clamp(0.0, 1.0);
+        min + (1.0 - min) * progress.powf(self.exponent)
+    }
+}
+
+impl EngineParams {
+    pub fn rebuy_cooldown(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.rebuy_cooldown_secs)
+    }
+}
+
+fn default_min_order_usd() -> f64 {
+    1.00
+}
+
+fn default_rebalance_fraction() -> f64 {
+    1.0
+}
+
+fn default_rebuy_cooldown_secs() -> u64 {
+    3600
+}
+
+impl Default for EngineParams {
+    fn default() -> Self {
+        Self {
+            min_order_usd: default_min_order_usd(),
+            whole_shares: false,
+            rebalance_fraction: default_rebalance_fraction(),
+            buy_band: 0.0,
+            sell_band: 0.0,
+            rebuy_cooldown_secs: default_rebuy_cooldown_secs(),
+            tags: TagRules::default(),
+            filters: MarketFilters::default(),
+            end_scaling: None,
+            depth_limit: None,
+            averaging_down: None,
+            max_entry_premium: None,
+            max_open_positions: None,
+            min_copy_price: None,
+            max_copy_price: None,
+            max_capital_multiple: None,
+            merge_complete_sets: false,
+            net_opposite_outcomes: false,
+            complete_sets_as_cash: false,
+            hold_while_unwinding: false,
+            ramp_in: None,
+            category_verdicts: CategoryVerdicts::default(),
+            trader_intents: Vec::new(),
+        }
+    }
+}
+
+/// Tunable constants for live order execution (`[executor]`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExecutorParams {
+    /// Pace of all CLOB requests (`[executor.clob_rate]`): order posts, status
+    /// checks, cancels, balance queries, and book fetches share one budget.
+    #[serde(default)]
+    pub clob_rate: ClobRate,
+    /// Delay before checking order fill status.
+    #[serde(default = "default_fill_check_delay_ms")]
+    pub fill_check_delay_ms: u64,
+    /// Skip all buys when the USDC balance is below this amount.
+    #[serde(default = "default_min_balance_usd")]
+    pub min_balance_usd: f64,
+    /// Split orders larger than this many shares into equal child orders.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_order_shares: Option<f64>,
+    /// Split orders whose notional exceeds this many USD into equal child orders.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_order_usd: Option<f64>,
+    /// When an order is more than this multiple of the top-of-book size, send only
+    /// the top level as a marketable chunk and rest the remainder at that price.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub liquidity_split_ratio: Option<f64>,
+    /// Strategy identifier for auditing. The CLOB order payload has no client
+    /// metadata field (`owner` is the API key), so the ID is attached to order logs
+    /// and event lines, which carry the exchange order IDs for joining with exports.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub strategy_id: Option<String>,
+    /// Stop posting the rest of a batch after this many consecutive failed orders
+    /// (auth broken, geo block, nonce issues) and raise a critical alert. 0 disables.
+    #[serde(default = "default_max_consecutive_failures")]
+    pub max_consecutive_failures: u32,
+    /// How far past the detection price to set the limit, in price units (0.02 =
+    /// 2¢): buys post at `price + max_chase`, sells at `price - max_chase`.
+    #[serde(default)]
+    pub max_chase: f64,
+    /// Price limits off the live order book instead of the detection price
+    /// (`[executor.book_pricing]`); `max_chase` is then unused.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub book_pricing: Option<BookPricing>,
+    /// Most an order may fill away from its intended price, in percent (2.0 =
+    /// 2%). Limits are held within it, and orders whose best opposing price is
+    /// already past it are skipped.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_slippage_pct: Option<f64>,
+    /// Slippage cap for `[risk]` exits (stop-loss, take-profit, kill-switch
+    /// liquidation), in percent, in place of `max_slippage_pct`. Unset: exits
+    /// aren't capped, so a forced sell isn't skipped in a falling market.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub risk_exit_slippage_pct: Option<f64>,
+    /// Post large orders as time-spaced slices (`[executor.twap]`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub twap: Option<Twap>,
+    /// Cancel resting orders older than this many seconds, returning the budget
+    /// reserved for buys.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resting_ttl_secs: Option<u64>,
+    /// Re-post the unfilled part of an expired order at the current top of book.
+    #[serde(default)]
+    pub reprice_expired: bool,
+    /// Cancel a resting order and re-post it at the touch once the best ask
+    /// (buys) or bid (sells) is more than this many ticks past its limit. Costs a
+    /// book fetch per resting order each cycle.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reprice_after_ticks: Option<u32>,
+    /// What to do with our open CLOB orders at live startup.
+    #[serde(default)]
+    pub startup_orders: StartupOrders,
+}
+
+/// Handling of the open orders found on the wallet at live startup
+/// (`[executor] startup_orders`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StartupOrders {
+    /// Cancel every open order on the wallet.
+    #[default]
+    CancelAll,
+    /// Track open orders in our markets as resting orders, cancelling only
+    /// those older than `resting_ttl_secs`; orders in other markets (another
+    /// strategy on the same wallet) are left alone.
+    Adopt,
+}
+
+/// Token bucket shared by every CLOB request (see `ratelimit::CLOB_LIMITER`).
+///
+/// Requests are spaced to `requests_per_sec` on average, with up to `burst`
+/// sent back to back after a quiet spell.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClobRate {
+    #[serde(default = "default_clob_requests_per_sec")]
+    pub requests_per_sec: f64,
+    #[serde(default = "default_clob_burst")]
+    pub burst: u32,
+}
+
+impl Default for ClobRate {
+    fn default() -> Self {
+        Self {
+            requests_per_sec: default_clob_requests_per_sec(),
+            burst: default_clob_burst(),
+        }
+    }
+}
+
+fn default_clob_requests_per_sec() -> f64 {
+    10.0
+}
+
+fn default_clob_burst() -> u32 {
+    10
+}
+
+/// How failed requests are retried (see `retry::with_retry`).
+///
+/// The top-level fields apply to every operation; `[retry.post_order]`,
+/// `[retry.order_status]` and `[retry.fetch]` override them field by field for
+/// order posting, CLOB order status checks, and `api` fetches respectively.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RetryConfig {
+    #[serde(flatten)]
+    pub default: RetryPolicy,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub post_order: Option<RetryOverride>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub order_status: Option<RetryOverride>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fetch: Option<RetryOverride>,
+}
+
+impl RetryConfig {
+    /// The policy for `operation`: the defaults with its overrides applied.
+    ///
+    /// Order posts don't retry network errors unless `[retry.post_order]`
+    /// lists `network` itself: a post that timed out may still have landed, and
+    /// posting it again would double the order.
+    pub fn policy(&self, operation: RetryOperation) -> RetryPolicy {
+        let overrides = match operation {
+            RetryOperation::PostOrder => &self.post_order,
+            RetryOperation::OrderStatus => &self.order_status,
+            RetryOperation::Fetch => &self.fetch,
+        };
+        let mut policy = self.default.clone();
+        if operation == RetryOperation::PostOrder {
+            policy.retry_on.retain(|class| *class != ErrorClass::Network);
+        }
+        if let Some(o) = overrides {
+            policy.max_attempts = o.max_attempts.unwrap_or(policy.max_attempts);
+            policy.base_backoff_ms = o.base_backoff_ms.unwrap_or(policy.base_backoff_ms);
+            policy.multiplier = o.multiplier.unwrap_or(policy.multiplier);
+            policy.jitter = o.jitter.unwrap_or(policy.jitter);
+            if let Some(retry_on) = &o.retry_on {
+                policy.retry_on = retry_on.clone();
+            }
+        }
+        policy
+    }
+}
+
+/// An operation with its own retry policy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RetryOperation {
+    PostOrder,
+    OrderStatus,
+    Fetch,
+}
+
+/// Attempts and backoff for one operation. Attempt `n` (from 0) that fails
+/// with a `retry_on` error waits `base_backoff_ms * multiplier^n`, give or
+/// take up to `jitter` of it, before the next.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RetryPolicy {
+    /// Total attempts, including the first.
+    #[serde(default = "default_retry_max_attempts")]
+    pub max_attempts: u32,
+    #[serde(default = "default_retry_base_backoff_ms")]
+    pub base_backoff_ms: u64,
+    #[serde(default = "default_retry_multiplier")]
+    pub multiplier: f64,
+    /// Fraction of each delay randomized away, 0 to 1 (0.2 = ±20%).
+    #[serde(default = "default_retry_jitter")]
+    pub jitter: f64,
+    /// Error classes worth retrying; anything else fails at once.
+    #[serde(default = "default_retry_on")]
+    pub retry_on: Vec<ErrorClass>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: default_retry_max_attempts(),
+            base_backoff_ms: default_retry_base_backoff_ms(),
+            multiplier: default_retry_multiplier(),
+            jitter: default_retry_jitter(),
+            retry_on: default_retry_on(),
+        }
+    }
+}
+
+/// Per-operation `RetryPolicy` fields; unset ones keep the `[retry]` value.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RetryOverride {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_attempts: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base_backoff_ms: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub multiplier: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub jitter: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry_on: Option<Vec<ErrorClass>>,
+}
+
+/// Kinds of request failure, told apart by the error message (see
+/// `retry::classify`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorClass {
+    /// HTTP 429.
+    RateLimited,
+    /// HTTP 5xx.
+    Server,
+    /// Timeouts and connection failures.
+    Network,
+}
+
+fn default_retry_max_attempts() -> u32 {
+    3
+}
+
+fn default_retry_base_backoff_ms() -> u64 {
+    500
+}
+
+fn default_retry_multiplier() -> f64 {
+    2.0
+}
+
+fn default_retry_jitter() -> f64 {
+    0.2
+}
+
+fn default_retry_on() -> Vec<ErrorClass> {
+    vec![ErrorClass::RateLimited, ErrorClass::Server, ErrorClass::Network]
+}
+
+/// Time-sliced execution (see `executor::schedule_twap`).
+///
+/// Orders over `min_order_usd` are posted as `slices` equal slices, one per
+/// poll cycle and at least `interval_secs` apart, so a thin book has time to
+/// refill between them.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Twap {
+    pub min_order_usd: f64,
+    #[serde(default = "default_twap_slices")]
+    pub slices: u32,
+    #[serde(default = "default_twap_interval")]
+    pub interval_secs: u64,
+}
+
+fn default_twap_slices() -> u32 {
+    4
+}
+
+fn default_twap_interval() -> u64 {
+    30
+}
+
+/// Order-book limit pricing (see `executor::book_limit_price`).
+///
+/// Buys post at the best ask plus `ticks` ticks and sells at the best bid minus
+/// them, but never more than `max_premium` (price units) worse than the
+/// detection price.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BookPricing {
+    #[serde(default)]
+    pub ticks: u32,
+    pub max_premium: f64,
+}
+
+impl ExecutorParams {
+    /// The furthest a limit may be from the detection price, in price units.
+    pub fn max_limit_offset(&self) -> f64 {
+        self.book_pricing
+            .as_ref()
+            .map_or(self.max_chase, |pricing| pricing.max_premium)
+    }
+
+    /// These parameters for orders placed by `[risk]` exits: slippage capped at
+    /// `risk_exit_slippage_pct` instead of `max_slippage_pct`.
+    pub fn for_risk_exit(&self) -> ExecutorParams {
+        ExecutorParams {
+            max_slippage_pct: self.risk_exit_slippage_pct,
+            ..self.clone()
+        }
+    }
+}
+
+fn default_max_consecutive_failures() -> u32 {
+    5
+}
+
+fn default_fill_check_delay_ms() -> u64 {
+    2000
+}
+
+fn default_min_balance_usd() -> f64 {
+    1.00
+}
+
+impl Default for ExecutorParams {
+    fn default() -> Self {
+        Self {
+            clob_rate: ClobRate::default(),
+            fill_check_delay_ms: default_fill_check_delay_ms(),
+            min_balance_usd: default_min_balance_usd(),
+            max_order_shares: None,
+            max_order_usd: None,
+            liquidity_split_ratio: None,
+            strategy_id: None,
+            max_consecutive_failures: default_max_consecutive_failures(),
+            max_chase: 0.0,
+            book_pricing: None,
+            max_slippage_pct: None,
+            risk_exit_slippage_pct: None,
+            twap: None,
+            resting_ttl_secs: None,
+            reprice_expired: false,
+            reprice_after_ticks: None,
+            startup_orders: StartupOrders::default(),
+        }
+    }
+}
+
+impl ExecutorParams {
+    pub fn fill_check_delay(&self) -> Duration {
+        Duration::from_millis(self.fill_check_delay_ms)
+    }
+}
+
+/// How the copytrade budget is set: a fixed amount, or a share of the wallet.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BudgetSpec {
+    /// Fixed budget in USD.
+    Usd(f64),
+    /// Percentage (0-100] of the wallet value (USDC + holdings) at startup.
+    WalletPct(f64),
+}
+
+impl BudgetSpec {
+    /// Budget for a wallet worth `wallet_value` USD.
+    pub fn resolve(self, wallet_value: f64) -> f64 {
+        match self {
+            BudgetSpec::Usd(usd) => usd,
+            BudgetSpec::WalletPct(pct) => wallet_value * pct / 100.0,
+        }
+    }
+}
+
+impl std::fmt::Display for BudgetSpec {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BudgetSpec::Usd(usd) => write!(f, "{usd}"),
+            BudgetSpec::WalletPct(pct) => write!(f, "{pct}% of wallet"),
         }
     }
 }
 
+/// Parses `250` (USD), `25%` (of wallet value), or `auto` (100% of wallet value).
+impl std::str::FromStr for BudgetSpec {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+        let spec = if s.eq_ignore_ascii_case("auto") {
+            BudgetSpec::WalletPct(100.0)
+        } else if let Some(pct) = s.strip_suffix('%') {
+            let pct: f64 = pct
+                .trim()
+                .parse()
+                .with_context(|| format!("invalid budget percentage {s:?}"))?;
+            BudgetSpec::WalletPct(pct)
+        } else {
+            BudgetSpec::Usd(
+                s.parse()
+                    .with_context(|| format!("invalid budget {s:?} (expected USD, N%, or auto)"))?,
+            )
+        };
+        match spec {
+            BudgetSpec::Usd(usd) if usd <= 0.0 => anyhow::bail!("budget must be positive"),
+            BudgetSpec::WalletPct(pct) if !(pct > 0.0 && pct <= 100.0) => {
+                anyhow::bail!("budget percentage must be in (0, 100]")
+            }
+            _ => Ok(spec),
+        }
+    }
+}
+
+/// A named profile. Every field is optional and overrides the top-level value
+/// (or, for copytrade parameters, provides a default for the CLI argument).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProfileConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub private_key: Option<Secret<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub poll_interval_secs: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trader_address: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub budget: Option<f64>,
+    /// Budget as a percentage of wallet value (USDC + holdings) at startup;
+    /// alternative to `budget`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub budget_pct: Option<f64>,
+    /// Re-base a percentage budget on the current wallet value every 24 hours.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rebase_budget_daily: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub copy_percentage: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_trade_size: Option<f64>,
+    /// Only copy these markets (condition IDs) — single-market copy mode.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub markets: Option<Vec<String>>,
+    /// Only copy markets in these events (slugs).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub event_slugs: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub market_weighting: Option<MarketWeighting>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data_api: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gamma_api: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub clob_api: Option<String>,
+}
+
 impl AppConfig {
     /// Load config from the given TOML file path.
     pub fn load(path: &Path) -> Result<Self> {
@@ -51,11 +1125,282 @@ impl AppConfig {
         Ok(config)
     }
 
-    /// Write config to the given TOML file path.
+    /// Write config to the given TOML file path. Private keys are redacted when
+    /// the config is serialized, so they are written back in explicitly here.
     pub fn save(&self, path: &Path) -> Result<()> {
-        let contents = toml::to_string_pretty(self).context("failed to serialize config")?;
+        let mut value = toml::Value::try_from(self).context("failed to serialize config")?;
+        let table = value.as_table_mut().context("config is not a table")?;
+        if let Some(account) = table.get_mut("account").and_then(|a| a.as_table_mut()) {
+            let key = self.account.private_key.expose().clone();
+            account.insert("private_key".to_string(), key.into());
+        }
+        if let Some(profiles) = table.get_mut("profile").and_then(|p| p.as_table_mut()) {
+            for (name, profile) in &self.profile {
+                if let Some(key) = &profile.private_key
+                    && let Some(entry) = profiles.get_mut(name).and_then(|p| p.as_table_mut())
+                {
+                    entry.insert("private_key".to_string(), key.expose().clone().into());
+                }
+            }
+        }
+        let contents = toml::to_string_pretty(&value).context("failed to serialize config")?;
         std::fs::write(path, contents)
             .with_context(|| format!("failed to write {}", path.display()))?;
         Ok(())
     }
+
+    /// Copy safe to share: custom endpoint URLs are cut to their origin, since RPC
+    /// and indexer URLs often embed an API key. (Private keys are `Secret`s, which
+    /// never serialize.)
+    pub fn redacted(&self) -> Self {
+        let mut config = self.clone();
+        let endpoints = &mut config.endpoints;
+        for url in [
+            &mut endpoints.data_api,
+            &mut endpoints.gamma_api,
+            &mut endpoints.clob_api,
+            &mut endpoints.rtds_ws,
+            &mut endpoints.clob_user_ws,
+            &mut endpoints.clob_market_ws,
+        ] {
+            *url = redact_url(url);
+        }
+        for url in [&mut endpoints.data_api_secondary, &mut endpoints.polygon_ws]
+            .into_iter()
+            .flatten()
+        {
+            *url = redact_url(url);
+        }
+        for profile in config.profile.values_mut() {
+            for url in [
+                &mut profile.data_api,
+                &mut profile.gamma_api,
+                &mut profile.clob_api,
+            ]
+            .into_iter()
+            .flatten()
+            {
+                *url = redact_url(url);
+            }
+        }
+        config
+    }
+
+    /// Short fingerprint of the settings: the first 12 hex digits of the SHA-256
+    /// of the `redacted` config, so it is the same whatever the private key or
+    /// endpoint API keys. Recorded in events as `BuildInfo::config_hash`.
+    pub fn hash(&self) -> Result<String> {
+        let contents = toml::to_string(&self.redacted()).context("failed to serialize config")?;
+        let digest = Sha256::digest(contents.as_bytes());
+        Ok(digest.iter().take(6).map(|b| format!("{b:02x}")).collect())
+    }
+
+    /// Apply the named profile's overrides to `account`, `settings`, and `endpoints`.
+    ///
+    /// Returns the selected profile (empty when `name` is `None`) so callers can
+    /// use its copytrade parameters as CLI defaults. Errors on unknown profile names.
+    pub fn apply_profile(&mut self, name: Option<&str>) -> Result<ProfileConfig> {
+        let Some(name) = name else {
+            return Ok(ProfileConfig::default());
+        };
+        let profile = self.profile.get(name).cloned().with_context(|| {
+            let known: Vec<&str> = self.profile.keys().map(String::as_str).collect();
+            format!("unknown profile '{name}' (known: {})", known.join(", "))
+        })?;
+
+        if let Some(key) = &profile.private_key {
+            self.account.private_key = key.clone();
+        }
+        if let Some(secs) = profile.poll_interval_secs {
+            self.settings.poll_interval_secs = secs;
+        }
+        if let Some(url) = &profile.data_api {
+            self.endpoints.data_api = url.clone();
+        }
+        if let Some(url) = &profile.gamma_api {
+            self.endpoints.gamma_api = url.clone();
+        }
+        if let Some(url) = &profile.clob_api {
+            self.endpoints.clob_api = url.clone();
+        }
+        Ok(profile)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = r#"
+[account]
+private_key = "0xbase"
+
+[settings]
+poll_interval_secs = 10
+
+[profile.staging]
+private_key = "0xstaging"
+poll_interval_secs = 5
+budget = 10.0
+data_api = "http://localhost:8080"
+
+[profile.prod]
+trader_address = "0xabc"
+
+[profile.specialist]
+markets = ["0xc1", "0xc2"]
+market_weighting = "portfolio"
+"#;
+
+    #[test]
+    fn budget_spec_parses_usd_percent_and_auto() {
+        assert_eq!("250".parse::<BudgetSpec>().unwrap(), BudgetSpec::Usd(250.0));
+        assert_eq!(
+            "25%".parse::<BudgetSpec>().unwrap(),
+            BudgetSpec::WalletPct(25.0)
+        );
+        assert_eq!(
+            "auto".parse::<BudgetSpec>().unwrap(),
+            BudgetSpec::WalletPct(100.0)
+        );
+        assert!("0".parse::<BudgetSpec>().is_err());
+        assert!("150%".parse::<BudgetSpec>().is_err());
+        assert!("lots".parse::<BudgetSpec>().is_err());
+        assert_eq!(BudgetSpec::WalletPct(25.0).resolve(400.0), 100.0);
+        assert_eq!(BudgetSpec::Usd(50.0).resolve(400.0), 50.0);
+    }
+
+    #[test]
+    fn no_profile_keeps_base_values() {
+        let mut config: AppConfig = toml::from_str(SAMPLE).unwrap();
+        let profile = config.apply_profile(None).unwrap();
+        assert!(profile.budget.is_none());
+        assert_eq!(config.account.private_key.expose(), "0xbase");
+        assert_eq!(config.endpoints.data_api, DATA_API_BASE);
+    }
+
+    #[test]
+    fn profile_overrides_base_values() {
+        let mut config: AppConfig = toml::from_str(SAMPLE).unwrap();
+        let profile = config.apply_profile(Some("staging")).unwrap();
+        assert_eq!(profile.budget, Some(10.0));
+        assert_eq!(config.account.private_key.expose(), "0xstaging");
+        assert_eq!(config.settings.poll_interval_secs, 5);
+        assert_eq!(config.endpoints.data_api, "http://localhost:8080");
+        assert_eq!(config.endpoints.clob_api, CLOB_API_BASE);
+    }
+
+    #[test]
+    fn partial_profile_inherits_base_values() {
+        let mut config: AppConfig = toml::from_str(SAMPLE).unwrap();
+        let profile = config.apply_profile(Some("prod")).unwrap();
+        assert_eq!(profile.trader_address.as_deref(), Some("0xabc"));
+        assert_eq!(config.account.private_key.expose(), "0xbase");
+        assert_eq!(config.settings.poll_interval_secs, 10);
+    }
+
+    #[test]
+    fn private_keys_only_leave_through_save() {
+        let config: AppConfig = toml::from_str(SAMPLE).unwrap();
+        let debug = format!("{config:?}");
+        let serialized = toml::to_string(&config).unwrap();
+        for key in ["0xbase", "0xstaging"] {
+            assert!(!debug.contains(key), "{key} in Debug output");
+            assert!(!serialized.contains(key), "{key} serialized");
+        }
+        assert!(serialized.contains(REDACTED));
+
+        let path =
+            std::env::temp_dir().join(format!("copytrade-config-{}.toml", std::process::id()));
+        config.save(&path).unwrap();
+        let saved = AppConfig::load(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(saved.account.private_key.expose(), "0xbase");
+        let staging = saved.profile["staging"].private_key.as_ref().unwrap();
+        assert_eq!(staging.expose(), "0xstaging");
+    }
+
+    #[test]
+    fn config_hash_tracks_settings_not_secrets() {
+        let config: AppConfig = toml::from_str(SAMPLE).unwrap();
+        let hash = config.hash().unwrap();
+        assert_eq!(hash.len(), 12);
+
+        let mut rekeyed = config.clone();
+        rekeyed.account.private_key = Secret::new("0xother".to_string());
+        assert_eq!(rekeyed.hash().unwrap(), hash);
+
+        let mut retuned = config;
+        retuned.engine.min_order_usd += 1.0;
+        assert_ne!(retuned.hash().unwrap(), hash);
+    }
+
+    #[test]
+    fn engine_and_executor_params_default_when_absent() {
+        let config: AppConfig = toml::from_str(SAMPLE).unwrap();
+        assert_eq!(config.engine.min_order_usd, 1.0);
+        assert!(!config.engine.whole_shares);
+        let post = config.retry.policy(RetryOperation::PostOrder);
+        assert_eq!(post.max_attempts, 3);
+        assert!(!post.retry_on.contains(&ErrorClass::Network));
+        assert_eq!(config.executor.fill_check_delay(), Duration::from_secs(2));
+        assert!(config.executor.max_order_usd.is_none());
+    }
+
+    #[test]
+    fn engine_and_executor_params_override() {
+        let toml = format!(
+            "{SAMPLE}\n[engine]\nmin_order_usd = 5.0\n\n[retry]\nmax_attempts = 5\n\n\
+             [retry.post_order]\nmax_attempts = 2\nretry_on = [\"network\"]\n\n\
+             [executor]\nmax_slippage_pct = 2.0\n"
+        );
+        let config: AppConfig = toml::from_str(&toml).unwrap();
+        assert_eq!(config.engine.min_order_usd, 5.0);
+        // Risk exits aren't held to the regular slippage cap
+        assert_eq!(config.executor.max_slippage_pct, Some(2.0));
+        assert_eq!(config.executor.for_risk_exit().max_slippage_pct, None);
+        let fetch = config.retry.policy(RetryOperation::Fetch);
+        assert_eq!(fetch.max_attempts, 5);
+        assert_eq!(fetch.retry_on.len(), 3);
+        let post = config.retry.policy(RetryOperation::PostOrder);
+        assert_eq!(post.max_attempts, 2);
+        assert_eq!(post.base_backoff_ms, 500);
+        assert_eq!(post.retry_on, vec![ErrorClass::Network]);
+    }
+
+    #[test]
+    fn profile_market_selection() {
+        let mut config: AppConfig = toml::from_str(SAMPLE).unwrap();
+        let profile = config.apply_profile(Some("specialist")).unwrap();
+        assert_eq!(profile.markets.unwrap(), vec!["0xc1", "0xc2"]);
+        assert!(profile.event_slugs.is_none());
+        assert_eq!(profile.market_weighting, Some(MarketWeighting::Portfolio));
+    }
+
+    #[test]
+    fn end_scaling_curve() {
+        let linear = EndScaling {
+            window_hours: 10.0,
+            min_factor: 0.2,
+            exponent: 1.0,
+        };
+        let hours = chrono::Duration::hours;
+        assert_eq!(linear.factor(hours(24)), 1.0);
+        assert_eq!(linear.factor(hours(10)), 1.0);
+        assert!((linear.factor(hours(5)) - 0.6).abs() < 1e-9);
+        assert_eq!(linear.factor(hours(0)), 0.2);
+        assert_eq!(linear.factor(hours(-3)), 0.2);
+
+        let steep = EndScaling {
+            exponent: 2.0,
+            ..linear
+        };
+        assert!((steep.factor(hours(5)) - 0.4).abs() < 1e-9);
+    }
+
+    #[test]
+    fn unknown_profile_errors() {
+        let mut config: AppConfig = toml::from_str(SAMPLE).unwrap();
+        assert!(config.apply_profile(Some("missing")).is_err());
+    }
 }
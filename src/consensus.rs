@@ -0,0 +1,208 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use crate::latency::{LatencyHistogram, LatencySummary};
+
+/// Where a trade detection came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DetectionSource {
+    /// Polling the data API `/trades` endpoint.
+    Rest,
+    /// A WebSocket feed.
+    Ws,
+    /// Polygon `OrderFilled` logs from an RPC node.
+    Chain,
+}
+
+impl std::fmt::Display for DetectionSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DetectionSource::Rest => write!(f, "rest"),
+            DetectionSource::Ws => write!(f, "ws"),
+            DetectionSource::Chain => write!(f, "chain"),
+        }
+    }
+}
+
+/// First detection of a trade, returned by `DetectionConsensus::observe`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Detection {
+    pub tx_hash: String,
+    pub source: DetectionSource,
+    /// Exchange trade timestamp → detection time, in seconds.
+    pub latency_secs: f64,
+}
+
+/// Per-source detection statistics, included in the exit summary.
+#[derive(Debug, Clone, Serialize)]
+pub struct SourceSummary {
+    /// Trades this source reported (first or not).
+    pub detections: u64,
+    /// Trades this source reported first.
+    pub wins: u64,
+    /// Trade timestamp → detection time for every report from this source.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub latency: Option<LatencySummary>,
+    /// Mean delay behind the winning source when this source was not first.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mean_lag_secs: Option<f64>,
+}
+
+#[derive(Debug, Default)]
+struct SourceStats {
+    detections: u64,
+    wins: u64,
+    latency: LatencyHistogram,
+    lag_total_secs: f64,
+    lag_count: u64,
+}
+
+/// Deduplicates trade detections across sources by transaction hash.
+///
+/// The first source to report a hash wins: `observe` returns the detection and
+/// the caller acts on it. Later reports of the same hash only feed the per-source
+/// latency and lag statistics.
+#[derive(Debug, Default)]
+pub struct DetectionConsensus {
+    /// Hashes known before detection started (never counted).
+    seeded: HashSet<String>,
+    /// Hash → (winning source, detection time).
+    first: HashMap<String, (DetectionSource, DateTime<Utc>)>,
+    stats: BTreeMap<DetectionSource, SourceStats>,
+}
+
+impl DetectionConsensus {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Mark a hash as already handled (e.g. trades that predate the session).
+    pub fn seed(&mut self, tx_hash: String) {
+        self.seeded.insert(tx_hash);
+    }
+
+    /// Number of hashes seen, seeded or detected.
+    pub fn len(&self) -> usize {
+        self.seeded.len() + self.first.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Record that `source` saw trade `tx_hash` (exchange timestamp `trade_ts`,
+    /// in seconds) at `at`. Returns the detection if no source reported it before.
+    pub fn observe(
+        &mut self,
+        source: DetectionSource,
+        tx_hash: &str,
+        trade_ts: i64,
+        at: DateTime<Utc>,
+    ) -> Option<Detection> {
+        if self.seeded.contains(tx_hash) {
+            return None;
+        }
+        let latency_secs = ((at.timestamp_millis() - trade_ts * 1000) as f64 / 1000.0).max(0.0);
+        let stats = self.stats.entry(source).or_default();
+
+        if let Some(&(winner, first_at)) = self.first.get(tx_hash) {
+            // Repeat reports from the same source (e.g. overlapping REST pages)
+            // are not new detections
+            if winner != source {
+                stats.detections += 1;
+                stats.latency.record(latency_secs);
+                stats.lag_total_secs += (at - first_at).num_milliseconds().max(0) as f64 / 1000.0;
+                stats.lag_count += 1;
+            }
+            return None;
+        }
+
+        stats.detections += 1;
+        stats.wins += 1;
+        stats.latency.record(latency_secs);
+        self.first.insert(tx_hash.to_string(), (source, at));
+        Some(Detection {
+            tx_hash: tx_hash.to_string(),
+            source,
+            latency_secs,
+        })
+    }
+
+    /// Per-source statistics, or `None` if nothing was detected.
+    pub fn summary(&self) -> Option<BTreeMap<DetectionSource, SourceSummary>> {
+        if self.stats.is_empty() {
+            return None;
+        }
+        Some(
+            self.stats
+                .iter()
+                .map(|(&source, s)| {
+                    let summary = SourceSummary {
+                        detections: s.detections,
+                        wins: s.wins,
+                        latency: s.latency.summary(),
+                        mean_lag_secs: (s.lag_count > 0)
+                            .then(|| s.lag_total_secs / s.lag_count as f64),
+                    };
+                    (source, summary)
+                })
+                .collect(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(secs: i64) -> DateTime<Utc> {
+        DateTime::from_timestamp(secs, 0).unwrap()
+    }
+
+    #[test]
+    fn first_source_wins_and_later_sources_record_lag() {
+        let mut consensus = DetectionConsensus::new();
+        consensus.seed("0xold".to_string());
+        assert!(
+            consensus
+                .observe(DetectionSource::Rest, "0xold", 900, at(1_000))
+                .is_none()
+        );
+
+        let detection = consensus
+            .observe(DetectionSource::Ws, "0xa", 1_000, at(1_002))
+            .unwrap();
+        assert_eq!(detection.source, DetectionSource::Ws);
+        assert_eq!(detection.latency_secs, 2.0);
+        // REST sees the same trade 8s later; WS repeats are ignored
+        assert!(
+            consensus
+                .observe(DetectionSource::Rest, "0xa", 1_000, at(1_010))
+                .is_none()
+        );
+        assert!(
+            consensus
+                .observe(DetectionSource::Ws, "0xa", 1_000, at(1_011))
+                .is_none()
+        );
+        // REST wins a trade the WS feed missed
+        assert!(
+            consensus
+                .observe(DetectionSource::Rest, "0xb", 1_020, at(1_025))
+                .is_some()
+        );
+
+        let summary = consensus.summary().unwrap();
+        let ws = &summary[&DetectionSource::Ws];
+        assert_eq!((ws.detections, ws.wins), (1, 1));
+        assert!(ws.mean_lag_secs.is_none());
+        let rest = &summary[&DetectionSource::Rest];
+        assert_eq!((rest.detections, rest.wins), (2, 1));
+        assert_eq!(rest.mean_lag_secs, Some(8.0));
+        assert_eq!(rest.latency.as_ref().unwrap().max_secs, 10.0);
+        assert_eq!(consensus.len(), 3);
+    }
+}
@@ -0,0 +1,185 @@
+use std::collections::HashMap;
+
+use polymarket_client_sdk::auth::state::State;
+use polymarket_client_sdk::clob::Client as ClobClient;
+use rust_decimal::Decimal;
+use tracing::{info, warn};
+
+use crate::api::fetch_order_book;
+use crate::config::{DepthLimit, EngineParams};
+use crate::engine;
+use crate::money;
+use crate::types::{OrderSide, SimulatedOrder};
+
+/// A token's order book as `(price, size)` levels: asks from the lowest
+/// price, bids from the highest.
+#[derive(Debug, Clone)]
+pub struct OrderBook {
+    pub asks: Vec<(f64, f64)>,
+    pub bids: Vec<(f64, f64)>,
+    pub tick_size: f64,
+}
+
+impl OrderBook {
+    pub fn new(mut asks: Vec<(f64, f64)>, mut bids: Vec<(f64, f64)>, tick_size: f64) -> Self {
+        asks.sort_by(|a, b| a.0.total_cmp(&b.0));
+        bids.sort_by(|a, b| b.0.total_cmp(&a.0));
+        Self {
+            asks,
+            bids,
+            tick_size,
+        }
+    }
+
+    /// Shares offered within `ticks` ticks of the best ask.
+    pub fn ask_depth(&self, ticks: u32) -> f64 {
+        let Some(&(best, _)) = self.asks.first() else {
+            return 0.0;
+        };
+        // Half a tick of slack absorbs float error in the level prices
+        let limit = best + (f64::from(ticks) + 0.5) * self.tick_size;
+        self.asks
+            .iter()
+            .take_while(|(price, _)| *price <= limit)
+            .map(|(_, size)| size)
+            .sum()
+    }
+}
+
+/// Cut a buy to `limit.max_depth_pct` of the ask depth in `book`, rounded down
+/// like the engine's orders (`engine::round_shares`). `None` when what is left
+/// costs less than `engine.min_order_usd` (or the book has no asks). Sells pass
+/// through unchanged.
+pub fn cap_buy(
+    order: SimulatedOrder,
+    book: &OrderBook,
+    limit: &DepthLimit,
+    engine: &EngineParams,
+) -> Option<SimulatedOrder> {
+    if order.side != OrderSide::Buy {
+        return Some(order);
+    }
+    let depth_shares = money::from_f64(book.ask_depth(limit.ticks) * limit.max_depth_pct);
+    if order.shares <= depth_shares {
+        return Some(order);
+    }
+    let max_shares = engine::round_shares(depth_shares, engine);
+    let cost_usd = max_shares * order.price;
+    if max_shares <= Decimal::ZERO || cost_usd < money::from_f64(engine.min_order_usd) {
+        info!(
+            "Skipping buy of \"{}\" ({}): {max_shares:.2} shares of book depth allowed, under the minimum",
+            order.market.title, order.market.outcome
+        );
+        return None;
+    }
+    info!(
+        "Capping buy of \"{}\" ({}) from {:.2} to {max_shares:.2} shares ({:.0}% of book depth)",
+        order.market.title,
+        order.market.outcome,
+        order.shares,
+        limit.max_depth_pct * 100.0
+    );
+    Some(SimulatedOrder {
+        shares: max_shares,
+        cost_usd,
+        ..order
+    })
+}
+
+/// Apply `cap_buy` to every buy in `orders`, fetching each token's book once.
+/// Buys whose book can't be fetched are kept as-is.
+pub async fn limit_buys<S: State>(
+    clob: &ClobClient<S>,
+    orders: Vec<SimulatedOrder>,
+    limit: &DepthLimit,
+    engine: &EngineParams,
+) -> Vec<SimulatedOrder> {
+    let mut books: HashMap<String, Option<OrderBook>> = HashMap::new();
+    let mut out = Vec::with_capacity(orders.len());
+    for order in orders {
+        if order.side != OrderSide::Buy {
+            out.push(order);
+            continue;
+        }
+        let asset = order.market.asset.clone();
+        if !books.contains_key(&asset) {
+            let book = match fetch_order_book(clob, &asset).await {
+                Ok(book) => Some(book),
+                Err(e) => {
+                    warn!("{e:#} — not limiting buys to book depth");
+                    None
+                }
+            };
+            books.insert(asset.clone(), book);
+        }
+        match &books[&asset] {
+            Some(book) => out.extend(cap_buy(order, book, limit, engine)),
+            None => out.push(order),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::MarketPosition;
+    use rust_decimal_macros::dec;
+
+    fn buy(shares: Decimal, price: Decimal) -> SimulatedOrder {
+        SimulatedOrder {
+            market: MarketPosition {
+                condition_id: String::new(),
+                asset: "a1".to_string(),
+                title: String::new(),
+                outcome: String::new(),
+                outcome_index: 0,
+                event_slug: String::new(),
+                tags: Vec::new(),
+                opposite_asset: String::new(),
+            },
+            side: OrderSide::Buy,
+            shares,
+            price,
+            cost_usd: shares * price,
+        }
+    }
+
+    #[test]
+    fn caps_buys_to_depth_near_best_ask() {
+        let book = OrderBook::new(
+            vec![(0.55, 500.0), (0.50, 100.0), (0.52, 100.0)],
+            vec![(0.48, 50.0)],
+            0.01,
+        );
+        // Levels within 2 ticks of $0.50: 0.50 and 0.52
+        assert!((book.ask_depth(2) - 200.0).abs() < 1e-9);
+        assert!((book.ask_depth(5) - 700.0).abs() < 1e-9);
+
+        let limit = DepthLimit {
+            max_depth_pct: 0.25,
+            ticks: 2,
+        };
+        let engine = EngineParams::default();
+        let capped = cap_buy(buy(dec!(120), dec!(0.5)), &book, &limit, &engine).unwrap();
+        assert_eq!((capped.shares, capped.cost_usd), (dec!(50), dec!(25)));
+        let uncapped = cap_buy(buy(dec!(40), dec!(0.5)), &book, &limit, &engine).unwrap();
+        assert_eq!(uncapped.shares, dec!(40));
+        // 50 shares at $0.01 is under the $1 minimum
+        assert!(cap_buy(buy(dec!(120), dec!(0.01)), &book, &limit, &engine).is_none());
+
+        // The cap is rounded down to the share increment, or to whole shares
+        let third = DepthLimit {
+            max_depth_pct: 1.0 / 3.0,
+            ticks: 2,
+        };
+        let capped = cap_buy(buy(dec!(120), dec!(0.5)), &book, &third, &engine).unwrap();
+        assert_eq!(capped.shares, dec!(66.66));
+        let whole = EngineParams {
+            whole_shares: true,
+            ..EngineParams::default()
+        };
+        let capped = cap_buy(buy(dec!(120), dec!(0.5)), &book, &third, &whole).unwrap();
+        assert_eq!((capped.shares, capped.cost_usd), (dec!(66), dec!(33)));
+    }
+}
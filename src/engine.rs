@@ -1,24 +1,100 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+
+use chrono::{DateTime, NaiveDate, Utc};
 
 use polymarket_client_sdk::data::types::response::Position;
-use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use rust_decimal::prelude::{RoundingStrategy, ToPrimitive};
+use rust_decimal_macros::dec;
+use serde::{Deserialize, Serialize};
 use tracing::{info, warn};
 
+use crate::config::{AveragingDown, EndScaling, EngineParams};
+use crate::filters::{CategoryVerdicts, MarketFilters};
+use crate::intent::{TradeCluster, TradeIntent};
+use crate::money;
 use crate::state::TradingState;
-use crate::types::{MarketPosition, OrderSide, SimulatedOrder, TargetAllocation};
-
-/// Minimum order value in USD — Polymarket CLOB rejects orders below $1 notional.
-const MIN_ORDER_USD: f64 = 1.00;
+use crate::types::{MarketPosition, OrderSide, SimulatedOrder, SkippedOrder, TargetAllocation};
 
 /// Extract a `MarketPosition` from an SDK `Position`.
-fn extract_market(pos: &Position) -> MarketPosition {
+pub(crate) fn extract_market(pos: &Position) -> MarketPosition {
     MarketPosition {
-        condition_id: format!("{}", pos.condition_id),
+        condition_id: pos.condition_id.to_string(),
         asset: pos.asset.to_string(),
         title: pos.title.clone(),
         outcome: pos.outcome.clone(),
         outcome_index: pos.outcome_index,
         event_slug: pos.event_slug.clone(),
+        tags: Vec::new(),
+        opposite_asset: pos.opposite_asset.clone(),
+    }
+}
+
+/// How targets are weighted when copying only some of the trader's markets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MarketWeighting {
+    /// Weights are renormalized across the selected markets, so they share the
+    /// whole copy budget.
+    #[default]
+    Selected,
+    /// Each selected market keeps its weight within the trader's full portfolio;
+    /// the budget share of unselected markets stays in cash.
+    Portfolio,
+}
+
+/// Single/multi-market copy mode: the subset of the trader's markets to copy.
+#[derive(Debug, Clone, Default)]
+pub struct MarketSelection {
+    pub condition_ids: HashSet<String>,
+    pub event_slugs: HashSet<String>,
+    pub weighting: MarketWeighting,
+}
+
+impl MarketSelection {
+    /// Whether `pos` is in one of the selected markets or events.
+    pub fn matches(&self, pos: &Position) -> bool {
+        self.selects_market(&pos.condition_id.to_string())
+            || self.event_slugs.contains(&pos.event_slug)
+    }
+
+    /// Whether `condition_id` is one of the selected markets. Condition IDs are
+    /// hex, so case doesn't matter.
+    pub fn selects_market(&self, condition_id: &str) -> bool {
+        self.condition_ids
+            .iter()
+            .any(|id| id.eq_ignore_ascii_case(condition_id))
+    }
+
+    /// Keep only the selected positions, returning them with the factor to apply
+    /// to `copy_pct`.
+    ///
+    /// With `Portfolio` weighting the factor is the selected share of the trader's
+    /// total value, so `compute_weights` over the subset yields the same per-market
+    /// targets as over the full portfolio. `positions` must then be unfiltered.
+    pub fn apply(&self, positions: &[Position]) -> (Vec<Position>, f64) {
+        let selected: Vec<Position> = positions
+            .iter()
+            .filter(|p| self.matches(p))
+            .cloned()
+            .collect();
+        let scale = match self.weighting {
+            MarketWeighting::Selected => 1.0,
+            MarketWeighting::Portfolio => {
+                let value = |ps: &[Position]| -> f64 {
+                    ps.iter()
+                        .map(|p| p.current_value.to_f64().unwrap_or(0.0))
+                        .sum()
+                };
+                let total = value(positions);
+                if total > 0.0 {
+                    value(&selected) / total
+                } else {
+                    0.0
+                }
+            }
+        };
+        (selected, scale)
     }
 }
 
@@ -27,10 +103,21 @@ fn extract_market(pos: &Position) -> MarketPosition {
 /// Returns `(MarketPosition, weight, cur_price)` tuples where weight is
 /// `current_value / total_portfolio_value`.
 pub fn compute_weights(positions: &[Position]) -> Vec<(MarketPosition, f64, f64)> {
-    let total_value: f64 = positions
-        .iter()
-        .map(|p| p.current_value.to_f64().unwrap_or(0.0))
-        .sum();
+    compute_net_weights(positions, &HashMap::new())
+}
+
+/// `compute_weights` with `sets` shares of each asset (see `complete_sets`)
+/// left out of both the position's value and the total.
+pub fn compute_net_weights(
+    positions: &[Position],
+    sets: &HashMap<String, f64>,
+) -> Vec<(MarketPosition, f64, f64)> {
+    let net_value = |p: &Position| {
+        let value = p.current_value.to_f64().unwrap_or(0.0);
+        let set_shares = sets.get(&p.asset).copied().unwrap_or(0.0);
+        (value - set_shares * p.cur_price.to_f64().unwrap_or(0.0)).max(0.0)
+    };
+    let total_value: f64 = positions.iter().map(net_value).sum();
 
     if total_value <= 0.0 {
         return Vec::new();
@@ -39,108 +126,223 @@ pub fn compute_weights(positions: &[Position]) -> Vec<(MarketPosition, f64, f64)
     positions
         .iter()
         .map(|p| {
-            let value = p.current_value.to_f64().unwrap_or(0.0);
-            let weight = value / total_value;
+            let weight = net_value(p) / total_value;
             let price = p.cur_price.to_f64().unwrap_or(0.0);
             (extract_market(p), weight, price)
         })
         .collect()
 }
 
+/// Shares of each asset the trader holds as complete sets: both outcomes of a
+/// binary market, up to the smaller side.
+///
+/// A set always pays out $1, so it is cash in another form. Splitting USDC into
+/// sets or merging them back leaves the trader's exposure unchanged, and
+/// counting sets in the weights would read a split as buying both outcomes and
+/// selling everything else, and a merge as the reverse.
+pub fn complete_sets(positions: &[Position]) -> HashMap<String, f64> {
+    let mut by_market: HashMap<String, Vec<&Position>> = HashMap::new();
+    for p in positions {
+        by_market
+            .entry(p.condition_id.to_string())
+            .or_default()
+            .push(p);
+    }
+    let mut sets = HashMap::new();
+    for outcomes in by_market.into_values() {
+        let [a, b] = outcomes[..] else {
+            continue;
+        };
+        let shares = a.size.min(b.size).to_f64().unwrap_or(0.0);
+        if shares > 0.0 {
+            sets.insert(a.asset.clone(), shares);
+            sets.insert(b.asset.clone(), shares);
+        }
+    }
+    sets
+}
+
 /// Compute the target state (allocation per market) given weights and parameters.
 ///
 /// `max_trade_pct` is the maximum fraction (0.0–1.0) of `budget` allocatable to
-/// any single market position.
+/// any single market position. Markets `filters` refuses get no target.
 pub fn compute_target_state(
     weights: &[(MarketPosition, f64, f64)],
-    budget: f64,
+    budget: Decimal,
     copy_pct: f64,
     max_trade_pct: f64,
+    filters: &MarketFilters,
 ) -> Vec<TargetAllocation> {
-    let max_per_market = max_trade_pct * budget;
+    let max_per_market = money::from_f64(max_trade_pct) * budget;
+    let scale = budget * money::from_f64(copy_pct);
     weights
         .iter()
+        .filter(|(market, _, _)| filters.allows(market))
         .map(|(market, weight, cur_price)| {
-            let raw_target = weight * budget * copy_pct;
-            let target_usd = raw_target.min(max_per_market);
-            let target_shares = if *cur_price > 0.0 {
-                target_usd / cur_price
-            } else {
-                0.0
-            };
+            let raw_target = money::from_f64(*weight) * scale;
+            // Rounded to what USDC can hold, which also drops the error of
+            // `f64` weights (a third of $90 is $30, not $29.99999997)
+            let target_usd = raw_target
+                .min(max_per_market)
+                .round_dp(money::USDC_DECIMALS);
+            let cur_price = money::from_f64(*cur_price);
+            let target_shares = shares_at(target_usd, cur_price);
 
             TargetAllocation {
                 market: market.clone(),
                 trader_weight: *weight,
                 target_value_usd: target_usd,
                 target_shares,
-                cur_price: *cur_price,
+                cur_price,
+                trader_entry: None,
             }
         })
         .collect()
 }
 
+/// Shares `usd` buys at `price` (none at a price of zero or below).
+fn shares_at(usd: Decimal, price: Decimal) -> Decimal {
+    if price > Decimal::ZERO {
+        usd / price
+    } else {
+        Decimal::ZERO
+    }
+}
+
+/// Price increment assumed for markets whose tick size hasn't been looked up
+/// (`TradingState::tick_sizes`): the CLOB's usual 0.01.
+pub const DEFAULT_TICK_SIZE: Decimal = dec!(0.01);
+
+/// Smallest share amount an order can carry (the CLOB takes two share decimals).
+pub const SHARE_INCREMENT: Decimal = dec!(0.01);
+
 /// Compute the diff between target allocations and current holdings, producing
 /// simulated orders. Processes sells first (to free budget), then buys.
 ///
 /// `price_map` provides real market prices for assets the trader has exited.
 /// Used instead of `avg_cost` to get accurate realized P&L on exits.
+///
+/// Prices are rounded to each market's tick (`TradingState::tick_size`) and
+/// shares to `SHARE_INCREMENT` (whole shares with `whole_shares`), so orders
+/// carry exactly what the executor posts.
 pub fn compute_orders(
     targets: &[TargetAllocation],
     state: &TradingState,
-    budget_remaining: f64,
+    budget_remaining: Decimal,
     price_map: &HashMap<String, f64>,
     trader_short_id: &str,
+    params: &EngineParams,
 ) -> Vec<SimulatedOrder> {
+    let (orders, _) = compute_orders_with_skips(
+        targets,
+        state,
+        budget_remaining,
+        price_map,
+        trader_short_id,
+        params,
+    );
+    orders
+}
+
+/// `compute_orders`, also returning the buys held back by a re-buy cooldown or
+/// `max_open_positions`, with the reason, for the cycle's event.
+pub fn compute_orders_with_skips(
+    targets: &[TargetAllocation],
+    state: &TradingState,
+    budget_remaining: Decimal,
+    price_map: &HashMap<String, f64>,
+    trader_short_id: &str,
+    params: &EngineParams,
+) -> (Vec<SimulatedOrder>, Vec<SkippedOrder>) {
     let mut sells = Vec::new();
     let mut buys = Vec::new();
+    let mut skipped = Vec::new();
+
+    // Manually excluded assets have no target, so held ones are exited
+    let targets = targets
+        .iter()
+        .filter(|t| !state.excluded.contains(&t.market.asset));
 
     // Build a set of target assets for detecting exits
     let target_assets: std::collections::HashSet<&str> =
-        targets.iter().map(|t| t.market.asset.as_str()).collect();
+        targets.clone().map(|t| t.market.asset.as_str()).collect();
+
+    let now_ms = crate::clock::now().timestamp_millis();
+    let min_order_usd = money::from_f64(params.min_order_usd);
 
     // For each target, compare with effective holdings (includes resting orders)
     for target in targets {
         let held_shares = state.effective_held_shares(&target.market.asset);
+        let target_shares = target.target_shares;
+        let price = round_to_tick(target.cur_price, state.tick_size(&target.market.asset));
+        if within_band(target_shares, held_shares, params) {
+            continue;
+        }
 
-        let diff = target.target_shares - held_shares;
-
-        if diff > 0.0 {
-            // Need to buy more — subject to $1 minimum notional
-            let cost = diff * target.cur_price;
-            if cost >= MIN_ORDER_USD {
-                buys.push(SimulatedOrder {
-                    market: target.market.clone(),
-                    side: OrderSide::Buy,
-                    shares: diff,
-                    price: target.cur_price,
-                    cost_usd: cost,
+        let diff = round_shares(
+            smooth_step(target_shares - held_shares, target.cur_price, params),
+            params,
+        );
+
+        if diff > Decimal::ZERO {
+            // Tripped kill switch: positions only shrink from here. The daily
+            // loss pause holds them until it ends
+            if state.killed || state.buys_paused(now_ms) {
+                continue;
+            }
+            let buy = SimulatedOrder {
+                market: target.market.clone(),
+                side: OrderSide::Buy,
+                shares: diff,
+                price,
+                cost_usd: diff * price,
+            };
+            if state.rebuy_blocked(&target.market.asset, now_ms) {
+                info!(
+                    "[{trader_short_id}] Skipping buy of \"{}\" ({}) — re-buy cooldown after risk exit",
+                    target.market.title, target.market.outcome
+                );
+                skipped.push(SkippedOrder {
+                    order: buy,
+                    reason: "re-buy cooldown after risk exit".to_string(),
                 });
+                continue;
+            }
+            // Need to buy more — subject to minimum notional
+            if buy.cost_usd >= min_order_usd {
+                buys.push(buy);
             }
-        } else if diff < 0.0 {
+        } else if diff < Decimal::ZERO && !state.pinned.contains(&target.market.asset) {
             // Need to sell some — no minimum for sells (CLOB allows closing below $1)
             let sell_shares = -diff;
-            let proceeds = sell_shares * target.cur_price;
+            let proceeds = sell_shares * price;
             sells.push(SimulatedOrder {
                 market: target.market.clone(),
                 side: OrderSide::Sell,
                 shares: sell_shares,
-                price: target.cur_price,
+                price,
                 cost_usd: proceeds,
             });
         }
     }
 
     // Sell holdings that the trader has exited entirely
+    let mut full_exits = 0;
     for (asset, held) in &state.holdings {
-        if !target_assets.contains(asset.as_str()) && held.shares > 0.0 {
-            // Use effective shares to account for any resting sell orders
-            let effective = state.effective_held_shares(asset);
-            if effective <= 0.0 {
+        if !target_assets.contains(asset.as_str())
+            && held.shares > Decimal::ZERO
+            && !state.pinned.contains(asset)
+        {
+            // Use effective shares to account for any resting sell orders. Exits
+            // sell the whole balance down to the share increment, even with
+            // `whole_shares`, so only sub-increment dust can be left behind.
+            let effective =
+                money::round_toward_zero(state.effective_held_shares(asset), SHARE_INCREMENT);
+            if effective <= Decimal::ZERO {
                 continue; // already covered by a resting sell
             }
             let price = match price_map.get(asset) {
-                Some(&p) => p,
+                Some(&p) => round_to_tick(money::from_f64(p), state.tick_size(asset)),
                 None => {
                     warn!(
                         "[{trader_short_id}] No market price for exited asset {} ({}), skipping sell",
@@ -149,8 +351,10 @@ pub fn compute_orders(
                     continue;
                 }
             };
-            let reason = if price == 0.0 || price == 1.0 {
+            let reason = if price == Decimal::ZERO || price == Decimal::ONE {
                 "resolved"
+            } else if state.excluded.contains(asset) {
+                "excluded"
             } else {
                 "trader exited"
             };
@@ -159,6 +363,7 @@ pub fn compute_orders(
                 held.title, held.outcome
             );
             let proceeds = effective * price;
+            full_exits += 1;
             sells.push(SimulatedOrder {
                 market: MarketPosition {
                     condition_id: String::new(),
@@ -167,6 +372,8 @@ pub fn compute_orders(
                     outcome: held.outcome.clone(),
                     outcome_index: 0,
                     event_slug: String::new(),
+                    tags: state.asset_tags.get(asset).cloned().unwrap_or_default(),
+                    opposite_asset: held.opposite_asset.clone(),
                 },
                 side: OrderSide::Sell,
                 shares: effective,
@@ -176,46 +383,673 @@ pub fn compute_orders(
         }
     }
 
-    // Process sells first (frees budget), then buys (consumes budget)
+    // Process sells first (frees budget), then buys (consumes budget). Both
+    // are budgeted net of their taker fee
     let mut orders = Vec::new();
     let mut available = budget_remaining;
 
     // All sells go through — they free budget
     for sell in sells {
-        available += sell.cost_usd;
+        available += sell.cost_usd - taker_fee(&sell, state);
         orders.push(sell);
     }
 
-    // Buys are capped by available budget
+    // Buys are capped by available budget, and new positions by `max_open_positions`
+    let mut open_positions = open_positions(state).saturating_sub(full_exits);
     for buy in buys {
-        if available < MIN_ORDER_USD {
+        if available < min_order_usd {
             break;
         }
-        if buy.cost_usd <= available {
-            available -= buy.cost_usd;
+        let opens = state.effective_held_shares(&buy.market.asset) <= Decimal::ZERO;
+        if opens && let Some(max) = params.max_open_positions {
+            if open_positions >= max {
+                info!(
+                    "[{trader_short_id}] Skipping buy of \"{}\" ({}) — max_open_positions ({max}) reached",
+                    buy.market.title, buy.market.outcome
+                );
+                skipped.push(SkippedOrder {
+                    order: buy,
+                    reason: format!("max_open_positions ({max}) reached"),
+                });
+                continue;
+            }
+            open_positions += 1;
+        }
+        let fee_rate = state.fee_rate_bps(&buy.market.asset);
+        let per_share = buy.price + money::taker_fee_usd(fee_rate, Decimal::ONE, buy.price);
+        if buy.shares * per_share <= available {
+            available -= buy.shares * per_share;
             orders.push(buy);
         } else {
             // Partial fill: buy what we can afford
-            let affordable_shares = available / buy.price;
+            let affordable_shares = round_shares(available / per_share, params);
             let cost = affordable_shares * buy.price;
-            if cost >= MIN_ORDER_USD {
+            if cost >= min_order_usd {
                 orders.push(SimulatedOrder {
                     shares: affordable_shares,
                     cost_usd: cost,
                     ..buy
                 });
-                available -= cost;
+                available -= affordable_shares * per_share;
             }
         }
     }
 
+    (orders, skipped)
+}
+
+/// Taker fee of `order` at its market's rate (`TradingState::fee_rates`).
+fn taker_fee(order: &SimulatedOrder, state: &TradingState) -> Decimal {
+    money::taker_fee_usd(
+        state.fee_rate_bps(&order.market.asset),
+        order.shares,
+        order.price,
+    )
+}
+
+/// Fold each order's taker fee into its cost and price, as live books orders
+/// that fill on posting: buys cost more, sells bring in less. Dry-run fills
+/// every order at once, so it pays the fee on all of them.
+pub fn charge_taker_fees(orders: &[SimulatedOrder], state: &TradingState) -> Vec<SimulatedOrder> {
     orders
+        .iter()
+        .map(|order| {
+            let fee = taker_fee(order, state);
+            let cost_usd = match order.side {
+                OrderSide::Buy => order.cost_usd + fee,
+                OrderSide::Sell => order.cost_usd - fee,
+            };
+            SimulatedOrder {
+                price: money::ratio(cost_usd, order.shares).unwrap_or(order.price),
+                cost_usd,
+                ..order.clone()
+            }
+        })
+        .collect()
+}
+
+/// Fit buys into `available` USD, in order: a buy that doesn't fit is cut to
+/// what does (rounded down by `round_shares`), or dropped below
+/// `min_order_usd`. Sells pass through.
+pub fn cap_buys(
+    orders: Vec<SimulatedOrder>,
+    mut available: Decimal,
+    params: &EngineParams,
+) -> Vec<SimulatedOrder> {
+    let min_order_usd = money::from_f64(params.min_order_usd);
+    let mut capped = Vec::with_capacity(orders.len());
+    for order in orders {
+        if order.side == OrderSide::Sell || order.cost_usd <= available {
+            if order.side == OrderSide::Buy {
+                available -= order.cost_usd;
+            }
+            capped.push(order);
+            continue;
+        }
+        let shares = money::ratio(available.max(Decimal::ZERO), order.price).unwrap_or_default();
+        let shares = round_shares(shares, params);
+        let cost = shares * order.price;
+        if shares <= Decimal::ZERO || cost < min_order_usd {
+            info!(
+                "Dropping buy of \"{}\": ${:.2} left is under the ${min_order_usd} minimum",
+                order.market.title,
+                available.max(Decimal::ZERO)
+            );
+            continue;
+        }
+        available -= cost;
+        capped.push(SimulatedOrder {
+            shares,
+            cost_usd: cost,
+            ..order
+        });
+    }
+    capped
+}
+
+/// Distinct assets held or with resting buys.
+fn open_positions(state: &TradingState) -> usize {
+    let resting = state.resting_orders.iter().map(|r| &r.asset);
+    let assets: HashSet<&String> = state.holdings.keys().chain(resting).collect();
+    assets
+        .into_iter()
+        .filter(|asset| state.effective_held_shares(asset) > Decimal::ZERO)
+        .count()
+}
+
+/// Whether holdings are close enough to target to skip trading: under target by
+/// at most `buy_band`, or over it by at most `sell_band` (fractions of target).
+fn within_band(target_shares: Decimal, held_shares: Decimal, params: &EngineParams) -> bool {
+    let diff = target_shares - held_shares;
+    if diff > Decimal::ZERO {
+        diff <= money::from_f64(params.buy_band) * target_shares
+    } else {
+        -diff <= money::from_f64(params.sell_band) * target_shares
+    }
+}
+
+/// Scale a signed share delta by `rebalance_fraction`. Steps whose notional would
+/// fall below `min_order_usd` take the full delta, so holdings don't creep toward
+/// the target in ever-smaller orders.
+fn smooth_step(shares: Decimal, price: Decimal, params: &EngineParams) -> Decimal {
+    let fraction = params.rebalance_fraction;
+    if fraction >= 1.0 {
+        return shares;
+    }
+    let step = shares * money::from_f64(fraction);
+    if (step * price).abs() < money::from_f64(params.min_order_usd) {
+        shares
+    } else {
+        step
+    }
+}
+
+/// Smallest share step of an order: one share with `whole_shares`, else
+/// `SHARE_INCREMENT`.
+pub fn share_increment(params: &EngineParams) -> Decimal {
+    if params.whole_shares {
+        Decimal::ONE
+    } else {
+        SHARE_INCREMENT
+    }
+}
+
+/// Round a signed share delta toward zero: to whole shares when `whole_shares`
+/// is enabled, else to `SHARE_INCREMENT`.
+pub fn round_shares(shares: Decimal, params: &EngineParams) -> Decimal {
+    money::round_toward_zero(shares, share_increment(params))
+}
+
+/// Round a market `price` to the nearest multiple of `tick` (halves up), kept
+/// within one tick of 0 and 1 like the CLOB requires. Resolved prices (0 and 1)
+/// are left as they are.
+pub fn round_to_tick(price: Decimal, tick: Decimal) -> Decimal {
+    if price <= Decimal::ZERO || price >= Decimal::ONE || tick <= Decimal::ZERO {
+        return price;
+    }
+    let ticks = (price / tick).round_dp_with_strategy(0, RoundingStrategy::MidpointAwayFromZero);
+    (ticks * tick).clamp(tick, Decimal::ONE - tick)
+}
+
+/// Build a map of asset → current price from positions.
+pub fn build_price_map(positions: &[Position]) -> HashMap<String, f64> {
+    positions
+        .iter()
+        .map(|p| (p.asset.to_string(), p.cur_price.to_f64().unwrap_or(0.0)))
+        .collect()
+}
+
+/// Parse a position's `end_date`: RFC 3339, or a bare date (taken as midnight UTC).
+pub fn parse_end_date(end_date: &str) -> Option<DateTime<Utc>> {
+    if let Ok(dt) = DateTime::parse_from_rfc3339(end_date) {
+        return Some(dt.with_timezone(&Utc));
+    }
+    NaiveDate::parse_from_str(end_date, "%Y-%m-%d")
+        .ok()
+        .and_then(|d| d.and_hms_opt(0, 0, 0))
+        .map(|dt| dt.and_utc())
+}
+
+/// Record the trader's average entry price on each target (data API `avgPrice`,
+/// which Polymarket derives from the trader's buys of that asset).
+pub fn attach_trader_entries(targets: &mut [TargetAllocation], positions: &[Position]) {
+    let entries: HashMap<&str, Decimal> = positions
+        .iter()
+        .map(|p| (p.asset.as_str(), p.avg_price))
+        .collect();
+    for target in targets {
+        target.trader_entry = entries.get(target.market.asset.as_str()).copied();
+    }
+}
+
+/// Shrink each target's cap (`max_per_market`) by `EndScaling::factor` for the time
+/// left until its market's end date. Markets without a parseable end date keep the
+/// full cap.
+pub fn apply_end_scaling(
+    targets: &mut [TargetAllocation],
+    positions: &[Position],
+    max_per_market: Decimal,
+    scaling: &EndScaling,
+    now: DateTime<Utc>,
+) {
+    let end_dates: HashMap<&str, &str> = positions
+        .iter()
+        .map(|p| (p.asset.as_str(), p.end_date.as_str()))
+        .collect();
+    for target in targets {
+        let Some(end) = end_dates
+            .get(target.market.asset.as_str())
+            .and_then(|d| parse_end_date(d))
+        else {
+            continue;
+        };
+        let cap = max_per_market * money::from_f64(scaling.factor(end - now));
+        if target.target_value_usd > cap {
+            target.target_value_usd = cap;
+            target.target_shares = shares_at(cap, target.cur_price);
+        }
+    }
+}
+
+/// Hold targets at current holdings (no buys) for markets whose price has run
+/// more than `max_premium` above the trader's average entry (see
+/// `attach_trader_entries`). Returns the skipped targets' `(title, premium)`.
+pub fn apply_runaway_guard(
+    targets: &mut [TargetAllocation],
+    state: &TradingState,
+    max_premium: f64,
+) -> Vec<(String, f64)> {
+    let mut skipped = Vec::new();
+    for target in targets {
+        let Some(premium) = target.entry_premium() else {
+            continue;
+        };
+        if premium <= max_premium {
+            continue;
+        }
+        let held = state.effective_held_shares(&target.market.asset);
+        if target.target_shares > held {
+            target.target_shares = held;
+            target.target_value_usd = held * target.cur_price;
+            skipped.push((target.market.title.clone(), premium));
+        }
+    }
+    skipped
+}
+
+/// Drop the targets of events `[engine.filters.categories]` refused this cycle,
+/// and hold those of unverified events at current holdings (no buys). Runs
+/// after sizing, so the refused share of the budget stays in cash. Returns the
+/// held targets' titles.
+pub fn apply_category_verdicts(
+    targets: &mut Vec<TargetAllocation>,
+    state: &TradingState,
+    verdicts: &CategoryVerdicts,
+) -> Vec<String> {
+    targets.retain(|t| !verdicts.refused.contains(&t.market.event_slug));
+    let mut held_back = Vec::new();
+    for target in targets
+        .iter_mut()
+        .filter(|t| verdicts.unverified.contains(&t.market.event_slug))
+    {
+        let held = state.effective_held_shares(&target.market.asset);
+        if target.target_shares > held {
+            target.target_shares = held;
+            target.target_value_usd = held * target.cur_price;
+            held_back.push(target.market.title.clone());
+        }
+    }
+    held_back
+}
+
+/// Hold targets at current holdings (no buys) for markets where the trader's
+/// open cluster of trades (`EngineParams::trader_intents`) is an unwind: they
+/// are getting out, so a buy now would be undone by the next cycles. Returns
+/// the held targets' titles.
+pub fn apply_trader_intents(
+    targets: &mut [TargetAllocation],
+    state: &TradingState,
+    clusters: &[TradeCluster],
+) -> Vec<String> {
+    // Condition IDs are hex: compare them lowercased
+    let unwinding: HashSet<String> = clusters
+        .iter()
+        .filter(|c| c.intent == TradeIntent::Unwind)
+        .map(|c| c.condition_id.to_ascii_lowercase())
+        .collect();
+    let mut held_back = Vec::new();
+    for target in targets
+        .iter_mut()
+        .filter(|t| unwinding.contains(&t.market.condition_id.to_ascii_lowercase()))
+    {
+        let held = state.effective_held_shares(&target.market.asset);
+        if target.target_shares > held {
+            target.target_shares = held;
+            target.target_value_usd = held * target.cur_price;
+            held_back.push(target.market.title.clone());
+        }
+    }
+    held_back
+}
+
+/// Hold targets at current holdings (no buys) for markets priced outside
+/// `[min_price, max_price]`, where little is left to win or the position is a
+/// longshot. Returns the skipped targets' `(title, price)`.
+pub fn apply_price_band(
+    targets: &mut [TargetAllocation],
+    state: &TradingState,
+    min_price: Option<f64>,
+    max_price: Option<f64>,
+) -> Vec<(String, f64)> {
+    let mut skipped = Vec::new();
+    for target in targets {
+        let price = target.cur_price;
+        let outside = min_price.is_some_and(|min| price < money::from_f64(min))
+            || max_price.is_some_and(|max| price > money::from_f64(max));
+        if !outside {
+            continue;
+        }
+        let held = state.effective_held_shares(&target.market.asset);
+        if target.target_shares > held {
+            target.target_shares = held;
+            target.target_value_usd = held * price;
+            skipped.push((target.market.title.clone(), money::to_f64(price)));
+        }
+    }
+    skipped
+}
+
+/// Deploy only `fraction` of the total target value, largest trader weights
+/// first: targets past that share are held at current holdings (no buys, and
+/// no sells either), and the one straddling it gets what is left. Returns how
+/// many targets were held back.
+pub fn apply_ramp_in(
+    targets: &mut [TargetAllocation],
+    state: &TradingState,
+    fraction: f64,
+) -> usize {
+    let total: Decimal = targets.iter().map(|t| t.target_value_usd).sum();
+    let mut allowance = total * money::from_f64(fraction);
+    let mut order: Vec<usize> = (0..targets.len()).collect();
+    order.sort_by(|&a, &b| {
+        targets[b]
+            .trader_weight
+            .total_cmp(&targets[a].trader_weight)
+    });
+
+    let mut deferred = 0;
+    for i in order {
+        let target = &mut targets[i];
+        if target.target_value_usd <= allowance {
+            allowance -= target.target_value_usd;
+            continue;
+        }
+        let held = state.effective_held_shares(&target.market.asset);
+        let partial = shares_at(allowance, target.cur_price);
+        allowance = Decimal::ZERO;
+        if partial.max(held) < target.target_shares {
+            target.target_shares = partial.max(held);
+            target.target_value_usd = target.target_shares * target.cur_price;
+            deferred += 1;
+        }
+    }
+    deferred
+}
+
+/// Net the targets of both outcomes of one market against each other.
+///
+/// A YES share and a NO share together always pay out $1, so holding both
+/// sides only locks up budget. When the trader holds both outcomes, the
+/// smaller target is subtracted from both: the larger side keeps the
+/// difference and the smaller one drops to zero, so any of it we hold is sold
+/// before buying the other side. Returns the netted markets' `(title, shares)`.
+pub fn net_opposite_outcomes(targets: &mut [TargetAllocation]) -> Vec<(String, Decimal)> {
+    let mut by_market: HashMap<&str, Vec<usize>> = HashMap::new();
+    for (i, target) in targets.iter().enumerate() {
+        if !target.market.condition_id.is_empty() {
+            by_market
+                .entry(target.market.condition_id.as_str())
+                .or_default()
+                .push(i);
+        }
+    }
+    let mut pairs: Vec<(usize, usize)> = by_market
+        .into_values()
+        .filter_map(|outcomes| match outcomes[..] {
+            [a, b] => Some((a, b)),
+            _ => None,
+        })
+        .collect();
+    pairs.sort_unstable();
+
+    let mut netted = Vec::new();
+    for (a, b) in pairs {
+        let overlap = targets[a].target_shares.min(targets[b].target_shares);
+        if overlap <= Decimal::ZERO {
+            continue;
+        }
+        for i in [a, b] {
+            let target = &mut targets[i];
+            target.target_shares -= overlap;
+            target.target_value_usd = target.target_shares * target.cur_price;
+        }
+        netted.push((targets[a].market.title.clone(), overlap));
+    }
+    netted
+}
+
+/// Lift the per-market cap for positions the trader is averaging down on, funded
+/// from the `AveragingDown` reserve (see its docs).
+///
+/// The trader averages down when an open cluster of their trades (`clusters`,
+/// `EngineParams::trader_intents`) adds shares to a position they hold at a loss:
+/// the adds go in at the current price, below their average entry. Once the cluster
+/// closes, the extra already held is kept rather than sold back down to the cap,
+/// but not added to.
+///
+/// `running_budget` sizes the reserve and the per-market extra; `uncapped_scale` is
+/// the regular `budget * copy_pct`, so `trader_weight * uncapped_scale` is the target
+/// before the cap.
+pub fn apply_averaging_down(
+    targets: &mut [TargetAllocation],
+    positions: &[Position],
+    state: &TradingState,
+    clusters: &[TradeCluster],
+    running_budget: Decimal,
+    uncapped_scale: Decimal,
+    cfg: &AveragingDown,
+) {
+    let losing: HashSet<&str> = positions
+        .iter()
+        .filter(|p| {
+            let avg = p.avg_price.to_f64().unwrap_or(0.0);
+            let cur = p.cur_price.to_f64().unwrap_or(0.0);
+            avg > 0.0 && cur <= avg * (1.0 - cfg.min_loss_pct)
+        })
+        .map(|p| p.asset.as_str())
+        .collect();
+    let adding: HashSet<&str> = clusters
+        .iter()
+        .flat_map(|c| &c.net_shares)
+        .filter(|(_, net)| **net > Decimal::ZERO)
+        .map(|(asset, _)| asset.as_str())
+        .collect();
+    // Value held above the capped target: extra granted while the trader was adding
+    let held_extra = |t: &TargetAllocation| {
+        state.effective_held_shares(&t.market.asset) * t.cur_price - t.target_value_usd
+    };
+
+    let mut candidates: Vec<usize> = (0..targets.len())
+        .filter(|&i| {
+            let t = &targets[i];
+            losing.contains(t.market.asset.as_str())
+                && (adding.contains(t.market.asset.as_str()) || held_extra(t) > Decimal::ZERO)
+                && t.cur_price > Decimal::ZERO
+                && uncapped(t, uncapped_scale) > t.target_value_usd
+        })
+        .collect();
+    candidates.sort_by(|&a, &b| {
+        targets[b]
+            .trader_weight
+            .total_cmp(&targets[a].trader_weight)
+    });
+
+    let mut reserve = running_budget * money::from_f64(cfg.reserve_pct);
+    let max_extra = running_budget * money::from_f64(cfg.max_extra_pct);
+    for i in candidates {
+        if reserve <= Decimal::ZERO {
+            break;
+        }
+        let target = &mut targets[i];
+        let clipped = uncapped(target, uncapped_scale) - target.target_value_usd;
+        let mut extra = clipped.min(max_extra).min(reserve);
+        if !adding.contains(target.market.asset.as_str()) {
+            extra = extra.min(held_extra(target));
+        }
+        reserve -= extra;
+        target.target_value_usd += extra;
+        target.target_shares = target.target_value_usd / target.cur_price;
+    }
+}
+
+/// A target's value before the per-market cap, at `scale` (budget * `copy_pct`).
+fn uncapped(target: &TargetAllocation, scale: Decimal) -> Decimal {
+    money::from_f64(target.trader_weight) * scale
+}
+
+/// Capital the targets are sized off: the effective capital, capped at
+/// `max_capital_multiple` times the initial budget.
+pub fn sizing_capital(
+    state: &TradingState,
+    prices: &HashMap<String, f64>,
+    params: &EngineParams,
+) -> Decimal {
+    let capital = state.effective_capital(prices);
+    match params.max_capital_multiple {
+        Some(multiple) => capital.min(state.initial_budget * money::from_f64(multiple)),
+        None => capital,
+    }
+}
+
+/// Full rebalancing pass: weights → targets (sized off effective capital) → orders.
+///
+/// `exit_prices` supplements the prices in `positions` for held assets the trader
+/// has exited (see `api::build_exit_price_map`).
+pub fn plan_rebalance(
+    positions: &[Position],
+    state: &TradingState,
+    exit_prices: &HashMap<String, f64>,
+    copy_pct: f64,
+    max_trade_pct: f64,
+    trader_short_id: &str,
+    params: &EngineParams,
+) -> Vec<SimulatedOrder> {
+    let (orders, _) = plan_rebalance_with_skips(
+        positions,
+        state,
+        exit_prices,
+        copy_pct,
+        max_trade_pct,
+        trader_short_id,
+        params,
+    );
+    orders
+}
+
+/// `plan_rebalance`, also returning the buys `compute_orders_with_skips` held
+/// back.
+pub fn plan_rebalance_with_skips(
+    positions: &[Position],
+    state: &TradingState,
+    exit_prices: &HashMap<String, f64>,
+    copy_pct: f64,
+    max_trade_pct: f64,
+    trader_short_id: &str,
+    params: &EngineParams,
+) -> (Vec<SimulatedOrder>, Vec<SkippedOrder>) {
+    let active_prices = build_price_map(positions);
+    let sets = if params.complete_sets_as_cash {
+        complete_sets(positions)
+    } else {
+        HashMap::new()
+    };
+    let mut weights = compute_net_weights(positions, &sets);
+    if !params.tags.is_empty() {
+        for (market, _, _) in &mut weights {
+            market.tags = params.tags.tags_for(market);
+        }
+    }
+    let running_budget = sizing_capital(state, &active_prices, params);
+    // The averaging-down reserve is excluded from regular sizing
+    let reserve_pct = params
+        .averaging_down
+        .as_ref()
+        .map_or(0.0, |a| a.reserve_pct);
+    let sizing_budget = running_budget * money::from_f64(1.0 - reserve_pct);
+    let mut targets = compute_target_state(
+        &weights,
+        sizing_budget,
+        copy_pct,
+        max_trade_pct,
+        &params.filters,
+    );
+    for title in apply_category_verdicts(&mut targets, state, &params.category_verdicts) {
+        info!("Skipping buy of \"{title}\": its event's categories couldn't be looked up");
+    }
+    attach_trader_entries(&mut targets, positions);
+    if let Some(averaging) = &params.averaging_down {
+        apply_averaging_down(
+            &mut targets,
+            positions,
+            state,
+            &params.trader_intents,
+            running_budget,
+            sizing_budget * money::from_f64(copy_pct),
+            averaging,
+        );
+    }
+    if let Some(scaling) = &params.end_scaling {
+        apply_end_scaling(
+            &mut targets,
+            positions,
+            money::from_f64(max_trade_pct) * sizing_budget,
+            scaling,
+            crate::clock::now(),
+        );
+    }
+    if params.net_opposite_outcomes {
+        for (title, shares) in net_opposite_outcomes(&mut targets) {
+            info!("Netting \"{title}\": {shares:.2} shares held on both outcomes");
+        }
+    }
+    if params.hold_while_unwinding {
+        for title in apply_trader_intents(&mut targets, state, &params.trader_intents) {
+            info!("Skipping buy of \"{title}\": the trader is unwinding it");
+        }
+    }
+    if params.min_copy_price.is_some() || params.max_copy_price.is_some() {
+        let (min, max) = (params.min_copy_price, params.max_copy_price);
+        for (title, price) in apply_price_band(&mut targets, state, min, max) {
+            info!("Skipping buy of \"{title}\": price {price:.3} is outside the copy band");
+        }
+    }
+    if let Some(max_premium) = params.max_entry_premium {
+        for (title, premium) in apply_runaway_guard(&mut targets, state, max_premium) {
+            info!(
+                "Skipping buy of \"{title}\": price is {:.0}% above the trader's entry",
+                premium * 100.0
+            );
+        }
+    }
+
+    if let Some(fraction) = params.ramp_in {
+        let deferred = apply_ramp_in(&mut targets, state, fraction);
+        if deferred > 0 {
+            info!(
+                "Ramp-in: deploying {:.0}% of the targets, {deferred} market(s) held back",
+                fraction * 100.0
+            );
+        }
+    }
+
+    let mut price_map = exit_prices.clone();
+    price_map.extend(active_prices);
+    compute_orders_with_skips(
+        &targets,
+        state,
+        state.budget_remaining,
+        &price_map,
+        trader_short_id,
+        params,
+    )
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::testkit::{hold, make_market, make_target};
     use crate::types::{HeldPosition, RestingOrder};
+    use crate::wal::Mutation;
     use serde_json::json;
 
     fn approx_eq(a: f64, b: f64) -> bool {
@@ -223,6 +1057,7 @@ mod tests {
     }
 
     /// Build an SDK `Position` via JSON deserialization (struct is #[non_exhaustive]).
+    #[allow(clippy::too_many_arguments)]
     fn make_test_position(
         asset: &str,
         condition_id: &str,
@@ -263,17 +1098,6 @@ mod tests {
         .expect("valid test Position JSON")
     }
 
-    fn make_market(asset: &str) -> MarketPosition {
-        MarketPosition {
-            condition_id: String::new(),
-            asset: asset.to_string(),
-            title: String::new(),
-            outcome: String::new(),
-            outcome_index: 0,
-            event_slug: String::new(),
-        }
-    }
-
     // ── compute_weights ────────────────────────────────────────────
 
     #[test]
@@ -288,7 +1112,7 @@ mod tests {
         let w = compute_weights(&[pos]);
         assert_eq!(w.len(), 1);
         assert!(approx_eq(w[0].1, 1.0));
-        assert!(approx_eq(w[0].2, 0.50));
+        assert!(approx_eq(w[0].2, 0.5));
     }
 
     #[test]
@@ -344,31 +1168,35 @@ mod tests {
     #[test]
     fn target_basic() {
         let weights = vec![(make_market("a1"), 0.5, 0.50)];
-        let targets = compute_target_state(&weights, 1000.0, 1.0, 1.0);
+        let targets =
+            compute_target_state(&weights, dec!(1000), 1.0, 1.0, &MarketFilters::default());
         assert_eq!(targets.len(), 1);
-        assert!(approx_eq(targets[0].target_value_usd, 500.0));
-        assert!(approx_eq(targets[0].target_shares, 1000.0)); // 500 / 0.50
+        assert_eq!(targets[0].target_value_usd, dec!(500));
+        assert_eq!(targets[0].target_shares, dec!(1000)); // 500 / 0.50
     }
 
     #[test]
     fn target_copy_percentage() {
         let weights = vec![(make_market("a1"), 1.0, 0.50)];
-        let targets = compute_target_state(&weights, 1000.0, 0.5, 1.0);
-        assert!(approx_eq(targets[0].target_value_usd, 500.0));
+        let targets =
+            compute_target_state(&weights, dec!(1000), 0.5, 1.0, &MarketFilters::default());
+        assert_eq!(targets[0].target_value_usd, dec!(500));
     }
 
     #[test]
     fn target_max_trade_caps() {
         let weights = vec![(make_market("a1"), 1.0, 0.50)];
-        let targets = compute_target_state(&weights, 1000.0, 1.0, 0.30);
-        assert!(approx_eq(targets[0].target_value_usd, 300.0)); // capped at 30%
+        let targets =
+            compute_target_state(&weights, dec!(1000), 1.0, 0.30, &MarketFilters::default());
+        assert_eq!(targets[0].target_value_usd, dec!(300)); // capped at 30%
     }
 
     #[test]
     fn target_zero_price() {
         let weights = vec![(make_market("a1"), 1.0, 0.0)];
-        let targets = compute_target_state(&weights, 1000.0, 1.0, 1.0);
-        assert!(approx_eq(targets[0].target_shares, 0.0));
+        let targets =
+            compute_target_state(&weights, dec!(1000), 1.0, 1.0, &MarketFilters::default());
+        assert_eq!(targets[0].target_shares, dec!(0));
     }
 
     #[test]
@@ -378,15 +1206,16 @@ mod tests {
             (make_market("a2"), 0.3, 0.60),
             (make_market("a3"), 0.2, 0.80),
         ];
-        let targets = compute_target_state(&weights, 1000.0, 1.0, 1.0);
+        let targets =
+            compute_target_state(&weights, dec!(1000), 1.0, 1.0, &MarketFilters::default());
         assert_eq!(targets.len(), 3);
-        assert!(approx_eq(targets[0].target_value_usd, 500.0));
-        assert!(approx_eq(targets[1].target_value_usd, 300.0));
-        assert!(approx_eq(targets[2].target_value_usd, 200.0));
+        assert_eq!(targets[0].target_value_usd, dec!(500));
+        assert_eq!(targets[1].target_value_usd, dec!(300));
+        assert_eq!(targets[2].target_value_usd, dec!(200));
         // Shares = usd / price
-        assert!(approx_eq(targets[0].target_shares, 1250.0));
-        assert!(approx_eq(targets[1].target_shares, 500.0));
-        assert!(approx_eq(targets[2].target_shares, 250.0));
+        assert_eq!(targets[0].target_shares, dec!(1250));
+        assert_eq!(targets[1].target_shares, dec!(500));
+        assert_eq!(targets[2].target_shares, dec!(250));
     }
 
     #[test]
@@ -395,43 +1224,39 @@ mod tests {
         m.title = "My Market".to_string();
         m.outcome = "Yes".to_string();
         let weights = vec![(m, 1.0, 0.50)];
-        let targets = compute_target_state(&weights, 100.0, 1.0, 1.0);
+        let targets =
+            compute_target_state(&weights, dec!(100), 1.0, 1.0, &MarketFilters::default());
         assert_eq!(targets[0].market.asset, "xyz");
         assert_eq!(targets[0].market.title, "My Market");
         assert_eq!(targets[0].market.outcome, "Yes");
         assert!(approx_eq(targets[0].trader_weight, 1.0));
-        assert!(approx_eq(targets[0].cur_price, 0.50));
+        assert_eq!(targets[0].cur_price, dec!(0.5));
     }
 
     // ── compute_orders ─────────────────────────────────────────────
 
     #[test]
     fn orders_initial_replication() {
-        let state = TradingState::new(1000.0);
+        let state = TradingState::new(dec!(1000));
         let targets = vec![
-            TargetAllocation {
-                market: make_market("a1"),
-                trader_weight: 0.5,
-                target_value_usd: 500.0,
-                target_shares: 1000.0,
-                cur_price: 0.50,
-            },
-            TargetAllocation {
-                market: make_market("a2"),
-                trader_weight: 0.5,
-                target_value_usd: 500.0,
-                target_shares: 500.0,
-                cur_price: 1.0,
-            },
+            make_target("a1", 0.5, dec!(1000), dec!(0.5)),
+            make_target("a2", 0.5, dec!(500), dec!(1)),
         ];
-        let orders = compute_orders(&targets, &state, 1000.0, &HashMap::new(), "test");
+        let orders = compute_orders(
+            &targets,
+            &state,
+            dec!(1000),
+            &HashMap::new(),
+            "test",
+            &EngineParams::default(),
+        );
         assert_eq!(orders.len(), 2);
         assert!(orders.iter().all(|o| o.side == OrderSide::Buy));
     }
 
     #[test]
     fn orders_sell_before_buy() {
-        let mut state = TradingState::new(1000.0);
+        let mut state = TradingState::new(dec!(1000));
         // Hold 20 shares of a1
         state.holdings.insert(
             "a1".to_string(),
@@ -439,28 +1264,24 @@ mod tests {
                 asset: "a1".to_string(),
                 title: String::new(),
                 outcome: String::new(),
-                shares: 20.0,
-                total_cost: 10.0,
-                avg_cost: 0.50,
+                shares: dec!(20),
+                total_cost: dec!(10),
+                avg_cost: dec!(0.5),
+                opposite_asset: String::new(),
             },
         );
         let targets = vec![
-            TargetAllocation {
-                market: make_market("a1"),
-                trader_weight: 0.5,
-                target_value_usd: 5.0,
-                target_shares: 10.0,
-                cur_price: 0.50,
-            },
-            TargetAllocation {
-                market: make_market("a2"),
-                trader_weight: 0.5,
-                target_value_usd: 5.0,
-                target_shares: 10.0,
-                cur_price: 0.50,
-            },
+            make_target("a1", 0.5, dec!(10), dec!(0.5)),
+            make_target("a2", 0.5, dec!(10), dec!(0.5)),
         ];
-        let orders = compute_orders(&targets, &state, 0.0, &HashMap::new(), "test");
+        let orders = compute_orders(
+            &targets,
+            &state,
+            Decimal::ZERO,
+            &HashMap::new(),
+            "test",
+            &EngineParams::default(),
+        );
         // First order should be a sell (sells come before buys)
         assert!(!orders.is_empty());
         assert_eq!(orders[0].side, OrderSide::Sell);
@@ -469,140 +1290,155 @@ mod tests {
 
     #[test]
     fn orders_exit_sell_trader_exited() {
-        let mut state = TradingState::new(1000.0);
+        let mut state = TradingState::new(dec!(1000));
         state.holdings.insert(
             "a1".to_string(),
             HeldPosition {
                 asset: "a1".to_string(),
                 title: "Exited Market".to_string(),
                 outcome: "Yes".to_string(),
-                shares: 10.0,
-                total_cost: 5.0,
-                avg_cost: 0.50,
+                shares: dec!(10),
+                total_cost: dec!(5),
+                avg_cost: dec!(0.5),
+                opposite_asset: String::new(),
             },
         );
         // No targets (trader has exited), but price_map has the asset
         let mut price_map = HashMap::new();
         price_map.insert("a1".to_string(), 0.60);
-        let orders = compute_orders(&[], &state, 1000.0, &price_map, "test");
+        let orders = compute_orders(
+            &[],
+            &state,
+            dec!(1000),
+            &price_map,
+            "test",
+            &EngineParams::default(),
+        );
         assert_eq!(orders.len(), 1);
         assert_eq!(orders[0].side, OrderSide::Sell);
         assert_eq!(orders[0].market.asset, "a1");
-        assert!(approx_eq(orders[0].shares, 10.0));
-        assert!(approx_eq(orders[0].price, 0.60));
+        assert_eq!(orders[0].shares, dec!(10));
+        assert_eq!(orders[0].price, dec!(0.6));
     }
 
     #[test]
     fn orders_exit_sell_resolved_zero() {
-        let mut state = TradingState::new(1000.0);
+        let mut state = TradingState::new(dec!(1000));
         state.holdings.insert(
             "a1".to_string(),
             HeldPosition {
                 asset: "a1".to_string(),
                 title: "Resolved".to_string(),
                 outcome: "Yes".to_string(),
-                shares: 10.0,
-                total_cost: 5.0,
-                avg_cost: 0.50,
+                shares: dec!(10),
+                total_cost: dec!(5),
+                avg_cost: dec!(0.5),
+                opposite_asset: String::new(),
             },
         );
         let mut price_map = HashMap::new();
         price_map.insert("a1".to_string(), 0.0);
-        let orders = compute_orders(&[], &state, 1000.0, &price_map, "test");
+        let orders = compute_orders(
+            &[],
+            &state,
+            dec!(1000),
+            &price_map,
+            "test",
+            &EngineParams::default(),
+        );
         assert_eq!(orders.len(), 1);
         assert_eq!(orders[0].side, OrderSide::Sell);
-        assert!(approx_eq(orders[0].price, 0.0));
-        assert!(approx_eq(orders[0].cost_usd, 0.0)); // no proceeds
+        assert_eq!(orders[0].price, Decimal::ZERO);
+        assert_eq!(orders[0].cost_usd, Decimal::ZERO); // no proceeds
     }
 
     #[test]
     fn orders_min_order_usd_buy() {
-        let state = TradingState::new(1000.0);
+        let state = TradingState::new(dec!(1000));
         // Target buy worth $0.50 — below $1 minimum
-        let targets = vec![TargetAllocation {
-            market: make_market("a1"),
-            trader_weight: 1.0,
-            target_value_usd: 0.50,
-            target_shares: 1.0,
-            cur_price: 0.50,
-        }];
-        let orders = compute_orders(&targets, &state, 1000.0, &HashMap::new(), "test");
+        let targets = vec![make_target("a1", 1.0, dec!(1), dec!(0.5))];
+        let orders = compute_orders(
+            &targets,
+            &state,
+            dec!(1000),
+            &HashMap::new(),
+            "test",
+            &EngineParams::default(),
+        );
         assert!(orders.is_empty()); // skipped due to minimum
     }
 
     #[test]
     fn orders_no_minimum_for_sells() {
-        let mut state = TradingState::new(1000.0);
+        let mut state = TradingState::new(dec!(1000));
         state.holdings.insert(
             "a1".to_string(),
             HeldPosition {
                 asset: "a1".to_string(),
                 title: String::new(),
                 outcome: String::new(),
-                shares: 10.0,
-                total_cost: 5.0,
-                avg_cost: 0.50,
+                shares: dec!(10),
+                total_cost: dec!(5),
+                avg_cost: dec!(0.5),
+                opposite_asset: String::new(),
             },
         );
         // Target 9 shares → sell 1 share at $0.50 = $0.50 proceeds (below $1)
-        let targets = vec![TargetAllocation {
-            market: make_market("a1"),
-            trader_weight: 1.0,
-            target_value_usd: 4.5,
-            target_shares: 9.0,
-            cur_price: 0.50,
-        }];
-        let orders = compute_orders(&targets, &state, 1000.0, &HashMap::new(), "test");
+        let targets = vec![make_target("a1", 1.0, dec!(9), dec!(0.5))];
+        let orders = compute_orders(
+            &targets,
+            &state,
+            dec!(1000),
+            &HashMap::new(),
+            "test",
+            &EngineParams::default(),
+        );
         assert_eq!(orders.len(), 1);
         assert_eq!(orders[0].side, OrderSide::Sell);
-        assert!(approx_eq(orders[0].shares, 1.0));
+        assert_eq!(orders[0].shares, dec!(1));
     }
 
     #[test]
     fn orders_budget_exhaustion_partial() {
-        let state = TradingState::new(5.0);
+        let state = TradingState::new(dec!(5));
         let targets = vec![
-            TargetAllocation {
-                market: make_market("a1"),
-                trader_weight: 0.5,
-                target_value_usd: 3.0,
-                target_shares: 6.0,
-                cur_price: 0.50,
-            },
-            TargetAllocation {
-                market: make_market("a2"),
-                trader_weight: 0.5,
-                target_value_usd: 4.0,
-                target_shares: 8.0,
-                cur_price: 0.50,
-            },
+            make_target("a1", 0.5, dec!(6), dec!(0.5)),
+            make_target("a2", 0.5, dec!(8), dec!(0.5)),
         ];
-        let orders = compute_orders(&targets, &state, 5.0, &HashMap::new(), "test");
+        let orders = compute_orders(
+            &targets,
+            &state,
+            dec!(5),
+            &HashMap::new(),
+            "test",
+            &EngineParams::default(),
+        );
         // First buy: $3 (full), second buy: $2 remaining (partial)
         assert_eq!(orders.len(), 2);
-        assert!(approx_eq(orders[0].cost_usd, 3.0));
-        assert!(approx_eq(orders[1].cost_usd, 2.0));
-        assert!(approx_eq(orders[1].shares, 4.0)); // $2 / $0.50
+        assert_eq!(orders[0].cost_usd, dec!(3));
+        assert_eq!(orders[1].cost_usd, dec!(2));
+        assert_eq!(orders[1].shares, dec!(4)); // $2 / $0.50
     }
 
     #[test]
     fn orders_budget_exhaustion_complete() {
-        let state = TradingState::new(0.50);
-        let targets = vec![TargetAllocation {
-            market: make_market("a1"),
-            trader_weight: 1.0,
-            target_value_usd: 5.0,
-            target_shares: 10.0,
-            cur_price: 0.50,
-        }];
+        let state = TradingState::new(dec!(0.5));
+        let targets = vec![make_target("a1", 1.0, dec!(10), dec!(0.5))];
         // $0.50 budget — below $1 minimum, no buys possible
-        let orders = compute_orders(&targets, &state, 0.50, &HashMap::new(), "test");
+        let orders = compute_orders(
+            &targets,
+            &state,
+            dec!(0.5),
+            &HashMap::new(),
+            "test",
+            &EngineParams::default(),
+        );
         assert!(orders.is_empty());
     }
 
     #[test]
     fn orders_resting_prevents_duplicate() {
-        let mut state = TradingState::new(1000.0);
+        let mut state = TradingState::new(dec!(1000));
         // Resting buy for 5 shares of a1
         state.resting_orders.push(RestingOrder {
             order_id: "order1".to_string(),
@@ -610,36 +1446,40 @@ mod tests {
             title: String::new(),
             outcome: String::new(),
             side: OrderSide::Buy,
-            shares: 5.0,
-            price: 0.50,
-            cost_usd: 2.50,
+            shares: dec!(5),
+            price: dec!(0.5),
+            cost_usd: dec!(2.5),
+            opposite_asset: String::new(),
+            placed_at_ms: 0,
         });
         // Target 10 shares → effective held = 5 (resting), need 5 more
-        let targets = vec![TargetAllocation {
-            market: make_market("a1"),
-            trader_weight: 1.0,
-            target_value_usd: 5.0,
-            target_shares: 10.0,
-            cur_price: 0.50,
-        }];
-        let orders = compute_orders(&targets, &state, 1000.0, &HashMap::new(), "test");
+        let targets = vec![make_target("a1", 1.0, dec!(10), dec!(0.5))];
+        let orders = compute_orders(
+            &targets,
+            &state,
+            dec!(1000),
+            &HashMap::new(),
+            "test",
+            &EngineParams::default(),
+        );
         assert_eq!(orders.len(), 1);
         assert_eq!(orders[0].side, OrderSide::Buy);
-        assert!(approx_eq(orders[0].shares, 5.0)); // only 5 more, not 10
+        assert_eq!(orders[0].shares, dec!(5)); // only 5 more, not 10
     }
 
     #[test]
     fn orders_resting_sell_covers_exit() {
-        let mut state = TradingState::new(1000.0);
+        let mut state = TradingState::new(dec!(1000));
         state.holdings.insert(
             "a1".to_string(),
             HeldPosition {
                 asset: "a1".to_string(),
                 title: "Exited".to_string(),
                 outcome: "Yes".to_string(),
-                shares: 10.0,
-                total_cost: 5.0,
-                avg_cost: 0.50,
+                shares: dec!(10),
+                total_cost: dec!(5),
+                avg_cost: dec!(0.5),
+                opposite_asset: String::new(),
             },
         );
         // Resting sell covers all held shares
@@ -649,33 +1489,937 @@ mod tests {
             title: "Exited".to_string(),
             outcome: "Yes".to_string(),
             side: OrderSide::Sell,
-            shares: 10.0,
-            price: 0.50,
-            cost_usd: 5.0,
+            shares: dec!(10),
+            price: dec!(0.5),
+            cost_usd: dec!(5),
+            opposite_asset: String::new(),
+            placed_at_ms: 0,
         });
         let mut price_map = HashMap::new();
         price_map.insert("a1".to_string(), 0.60);
         // No targets (trader exited) — but resting sell already covers it
-        let orders = compute_orders(&[], &state, 1000.0, &price_map, "test");
+        let orders = compute_orders(
+            &[],
+            &state,
+            dec!(1000),
+            &price_map,
+            "test",
+            &EngineParams::default(),
+        );
         assert!(orders.is_empty()); // effective_held_shares = 10 - 10 = 0
     }
 
+    #[test]
+    fn orders_custom_min_order_usd() {
+        let state = TradingState::new(dec!(1000));
+        // $3 buy — above the default $1 minimum but below a configured $5 minimum
+        let targets = vec![make_target("a1", 1.0, dec!(6), dec!(0.5))];
+        let params = EngineParams {
+            min_order_usd: 5.0,
+            ..EngineParams::default()
+        };
+        let orders = compute_orders(
+            &targets,
+            &state,
+            dec!(1000),
+            &HashMap::new(),
+            "test",
+            &params,
+        );
+        assert!(orders.is_empty());
+        let orders = compute_orders(
+            &targets,
+            &state,
+            dec!(1000),
+            &HashMap::new(),
+            "test",
+            &EngineParams::default(),
+        );
+        assert_eq!(orders.len(), 1);
+    }
+
+    #[test]
+    fn orders_whole_shares_rounds_down() {
+        let mut state = TradingState::new(dec!(1000));
+        state.holdings.insert(
+            "a2".to_string(),
+            HeldPosition {
+                asset: "a2".to_string(),
+                title: "Market a2".to_string(),
+                outcome: "Yes".to_string(),
+                shares: dec!(10),
+                total_cost: dec!(5),
+                avg_cost: dec!(0.5),
+                opposite_asset: String::new(),
+            },
+        );
+        state.holdings.insert(
+            "gone".to_string(),
+            HeldPosition {
+                asset: "gone".to_string(),
+                title: "Exited".to_string(),
+                outcome: "Yes".to_string(),
+                shares: dec!(3.7),
+                total_cost: dec!(1.85),
+                avg_cost: dec!(0.5),
+                opposite_asset: String::new(),
+            },
+        );
+        let targets = vec![
+            make_target("a1", 0.5, dec!(9.8), dec!(0.5)),
+            make_target("a2", 0.5, dec!(6.6), dec!(0.5)),
+        ];
+        let price_map = HashMap::from([("gone".to_string(), 0.40)]);
+        let params = EngineParams {
+            whole_shares: true,
+            ..EngineParams::default()
+        };
+        let orders = compute_orders(&targets, &state, dec!(1000), &price_map, "test", &params);
+
+        let buy = orders.iter().find(|o| o.market.asset == "a1").unwrap();
+        assert_eq!(buy.shares, dec!(9));
+        assert_eq!(buy.cost_usd, dec!(4.5));
+        // Sell of 3.4 shares rounds toward zero to 3
+        let sell = orders.iter().find(|o| o.market.asset == "a2").unwrap();
+        assert_eq!(sell.shares, dec!(3));
+        // Full exits still sell the fractional balance
+        let exit = orders.iter().find(|o| o.market.asset == "gone").unwrap();
+        assert_eq!(exit.shares, dec!(3.7));
+    }
+
+    #[test]
+    fn orders_round_to_tick_and_share_increment() {
+        let mut state = TradingState::new(dec!(1000));
+        state.tick_sizes.insert("fine".to_string(), dec!(0.001));
+        state.holdings.insert(
+            "gone".to_string(),
+            HeldPosition {
+                asset: "gone".to_string(),
+                title: "Exited".to_string(),
+                outcome: "Yes".to_string(),
+                shares: dec!(3.456),
+                total_cost: dec!(1.7),
+                avg_cost: dec!(0.5),
+                opposite_asset: String::new(),
+            },
+        );
+        let targets = vec![
+            make_target("coarse", 0.5, dec!(20.1234), dec!(0.5371)),
+            make_target("fine", 0.5, dec!(20.1234), dec!(0.5371)),
+        ];
+        let price_map = HashMap::from([("gone".to_string(), 0.4049)]);
+        let params = EngineParams::default();
+        let orders = compute_orders(&targets, &state, dec!(1000), &price_map, "test", &params);
+
+        let coarse = orders.iter().find(|o| o.market.asset == "coarse").unwrap();
+        assert_eq!((coarse.shares, coarse.price), (dec!(20.12), dec!(0.54)));
+        assert_eq!(coarse.cost_usd, dec!(10.8648));
+        let fine = orders.iter().find(|o| o.market.asset == "fine").unwrap();
+        assert_eq!((fine.shares, fine.price), (dec!(20.12), dec!(0.537)));
+        let exit = orders.iter().find(|o| o.market.asset == "gone").unwrap();
+        assert_eq!((exit.shares, exit.price), (dec!(3.45), dec!(0.40)));
+
+        assert_eq!(round_to_tick(dec!(0.996), dec!(0.01)), dec!(0.99));
+        assert_eq!(round_to_tick(dec!(0.125), dec!(0.01)), dec!(0.13));
+        assert_eq!(round_to_tick(dec!(1), dec!(0.01)), Decimal::ONE);
+        assert_eq!(round_to_tick(dec!(0), dec!(0.01)), Decimal::ZERO);
+    }
+
+    #[test]
+    fn orders_rebalance_fraction_moves_partway() {
+        let mut state = TradingState::new(dec!(1000));
+        state.holdings.insert(
+            "a2".to_string(),
+            HeldPosition {
+                asset: "a2".to_string(),
+                title: "Market a2".to_string(),
+                outcome: "Yes".to_string(),
+                shares: dec!(100),
+                total_cost: dec!(50),
+                avg_cost: dec!(0.5),
+                opposite_asset: String::new(),
+            },
+        );
+        let targets = vec![
+            make_target("a1", 0.5, dec!(100), dec!(0.5)),
+            make_target("a2", 0.5, dec!(40), dec!(0.5)),
+            // Half of a 3-share gap is under $1 — goes the full distance
+            make_target("a3", 0.0, dec!(3), dec!(0.5)),
+        ];
+        let params = EngineParams {
+            rebalance_fraction: 0.5,
+            ..EngineParams::default()
+        };
+        let orders = compute_orders(
+            &targets,
+            &state,
+            dec!(1000),
+            &HashMap::new(),
+            "test",
+            &params,
+        );
+
+        let buy = orders.iter().find(|o| o.market.asset == "a1").unwrap();
+        assert_eq!(buy.shares, dec!(50));
+        let sell = orders.iter().find(|o| o.market.asset == "a2").unwrap();
+        assert_eq!(sell.side, OrderSide::Sell);
+        assert_eq!(sell.shares, dec!(30));
+        let small = orders.iter().find(|o| o.market.asset == "a3").unwrap();
+        assert_eq!(small.shares, dec!(3));
+    }
+
+    #[test]
+    fn orders_hysteresis_bands() {
+        let mut state = TradingState::new(dec!(1000));
+        for (asset, shares) in [
+            ("a1", dec!(95)),
+            ("a2", dec!(106)),
+            ("a3", dec!(89)),
+            ("a4", dec!(111)),
+        ] {
+            state.holdings.insert(
+                asset.to_string(),
+                HeldPosition {
+                    asset: asset.to_string(),
+                    title: format!("Market {asset}"),
+                    outcome: "Yes".to_string(),
+                    shares,
+                    total_cost: shares * dec!(0.5),
+                    avg_cost: dec!(0.5),
+                    opposite_asset: String::new(),
+                },
+            );
+        }
+        let targets: Vec<TargetAllocation> = ["a1", "a2", "a3", "a4"]
+            .iter()
+            .map(|asset| make_target(asset, 0.25, dec!(100), dec!(0.5)))
+            .collect();
+        let params = EngineParams {
+            buy_band: 0.10,
+            sell_band: 0.10,
+            ..EngineParams::default()
+        };
+        let orders = compute_orders(
+            &targets,
+            &state,
+            dec!(1000),
+            &HashMap::new(),
+            "test",
+            &params,
+        );
+
+        // 5% under / 6% over stay put; 11% under / 11% over trade back to target
+        assert_eq!(orders.len(), 2);
+        let buy = orders.iter().find(|o| o.market.asset == "a3").unwrap();
+        assert_eq!(buy.side, OrderSide::Buy);
+        assert_eq!(buy.shares, dec!(11));
+        let sell = orders.iter().find(|o| o.market.asset == "a4").unwrap();
+        assert_eq!(sell.side, OrderSide::Sell);
+        assert_eq!(sell.shares, dec!(11));
+    }
+
+    #[test]
+    fn orders_rebuy_cooldown_blocks_buys() {
+        let mut state = TradingState::new(dec!(1000));
+        let now_ms = chrono::Utc::now().timestamp_millis();
+        state.start_rebuy_cooldown("a1", now_ms + 60_000);
+        state.start_rebuy_cooldown("a2", now_ms - 1);
+        let targets: Vec<TargetAllocation> = ["a1", "a2"]
+            .iter()
+            .map(|asset| make_target(asset, 0.5, dec!(100), dec!(0.5)))
+            .collect();
+        let orders = compute_orders(
+            &targets,
+            &state,
+            dec!(1000),
+            &HashMap::new(),
+            "test",
+            &EngineParams::default(),
+        );
+        // a1 still cooling down; a2's cooldown has expired
+        assert_eq!(orders.len(), 1);
+        assert_eq!(orders[0].market.asset, "a2");
+    }
+
+    #[test]
+    fn orders_respect_pins_and_exclusions() {
+        let mut state = TradingState::new(dec!(1000));
+        for asset in ["pinned", "excluded", "gone"] {
+            hold(&mut state, asset, dec!(100), dec!(0.5));
+        }
+        state.commit(Mutation::Pin {
+            asset: "pinned".to_string(),
+            pinned: true,
+        });
+        state.commit(Mutation::Pin {
+            asset: "gone".to_string(),
+            pinned: true,
+        });
+        state.commit(Mutation::Exclude {
+            asset: "excluded".to_string(),
+            excluded: true,
+        });
+        // Targets below holdings for pinned/excluded; "gone" was exited by the trader
+        let targets: Vec<TargetAllocation> = ["pinned", "excluded"]
+            .iter()
+            .map(|asset| make_target(asset, 0.5, dec!(50), dec!(0.5)))
+            .collect();
+        let prices = HashMap::from([
+            ("excluded".to_string(), 0.50),
+            ("gone".to_string(), 0.50),
+        ]);
+        let orders = compute_orders(
+            &targets,
+            &state,
+            dec!(1000),
+            &prices,
+            "test",
+            &EngineParams::default(),
+        );
+        // Pinned holdings are never sold; the excluded one is sold in full
+        assert_eq!(orders.len(), 1);
+        assert_eq!(orders[0].market.asset, "excluded");
+        assert_eq!(orders[0].side, OrderSide::Sell);
+        assert_eq!(orders[0].shares, dec!(100));
+    }
+
+    #[test]
+    fn cap_buys_fits_buys_in_order() {
+        let order = |asset: &str, side, shares: Decimal| SimulatedOrder {
+            market: make_market(asset),
+            side,
+            shares,
+            price: dec!(0.5),
+            cost_usd: shares * dec!(0.50),
+        };
+        let orders = vec![
+            order("a1", OrderSide::Buy, dec!(20)),
+            order("a2", OrderSide::Sell, dec!(100)),
+            order("a3", OrderSide::Buy, dec!(20)),
+            order("a4", OrderSide::Buy, dec!(20)),
+        ];
+        let shares = |capped: Vec<SimulatedOrder>| -> Vec<(String, Decimal)> {
+            capped
+                .into_iter()
+                .map(|o| (o.market.asset, o.shares))
+                .collect()
+        };
+        // $10 + $4.50 of a3, then under the $1 minimum for a4
+        let params = EngineParams::default();
+        assert_eq!(
+            shares(cap_buys(orders.clone(), dec!(14.5), &params)),
+            [
+                ("a1".to_string(), dec!(20)),
+                ("a2".to_string(), dec!(100)),
+                ("a3".to_string(), dec!(9))
+            ]
+        );
+        // $4.557 left buys 9.114 shares, rounded down to the increment
+        let capped = cap_buys(orders.clone(), dec!(14.557), &params);
+        assert_eq!((capped[2].shares, capped[2].cost_usd), (dec!(9.11), dec!(4.555)));
+        // ... or to whole shares, and a cut that rounds to nothing is dropped
+        let whole = EngineParams {
+            whole_shares: true,
+            min_order_usd: 0.0,
+            ..EngineParams::default()
+        };
+        let capped = cap_buys(orders, dec!(10.4), &whole);
+        assert_eq!(
+            shares(capped),
+            [("a1".to_string(), dec!(20)), ("a2".to_string(), dec!(100))]
+        );
+    }
+
+    #[test]
+    fn orders_max_open_positions_caps_new_buys() {
+        let mut state = TradingState::new(dec!(1000));
+        for asset in ["held", "exited"] {
+            hold(&mut state, asset, dec!(10), dec!(0.5));
+        }
+        let targets: Vec<TargetAllocation> = ["held", "a1", "a2"]
+            .iter()
+            .map(|asset| make_target(asset, 0.3, dec!(100), dec!(0.5)))
+            .collect();
+        let prices = HashMap::from([("exited".to_string(), 0.50)]);
+        let params = EngineParams {
+            max_open_positions: Some(2),
+            ..EngineParams::default()
+        };
+        let (orders, skipped) =
+            compute_orders_with_skips(&targets, &state, dec!(990), &prices, "test", &params);
+        // The exit frees a slot for a1; held is still topped up, a2 doesn't fit
+        let assets: Vec<(&str, OrderSide)> = orders
+            .iter()
+            .map(|o| (o.market.asset.as_str(), o.side))
+            .collect();
+        assert_eq!(
+            assets,
+            [
+                ("exited", OrderSide::Sell),
+                ("held", OrderSide::Buy),
+                ("a1", OrderSide::Buy)
+            ]
+        );
+        assert_eq!(skipped.len(), 1);
+        assert_eq!(skipped[0].order.market.asset, "a2");
+        assert_eq!(skipped[0].reason, "max_open_positions (2) reached");
+    }
+
+    #[test]
+    fn plan_rebalance_tags_orders() {
+        let positions = vec![
+            make_test_position("a1", "c1", "Finals", "Yes", 0, "nba-finals", 0.50, 50.0),
+            make_test_position("a2", "c2", "Election", "Yes", 0, "election", 0.50, 50.0),
+        ];
+        let mut state = TradingState::new(dec!(100));
+        state.holdings.insert(
+            "gone".to_string(),
+            HeldPosition {
+                asset: "gone".to_string(),
+                title: "Exited".to_string(),
+                outcome: "Yes".to_string(),
+                shares: dec!(2),
+                total_cost: dec!(1),
+                avg_cost: dec!(0.5),
+                opposite_asset: String::new(),
+            },
+        );
+        state
+            .asset_tags
+            .insert("gone".to_string(), vec!["old".to_string()]);
+        let mut params = EngineParams::default();
+        params
+            .tags
+            .events
+            .insert("nba-finals".to_string(), vec!["NBA".to_string()]);
+        let exit_prices = HashMap::from([("gone".to_string(), 0.40)]);
+        let orders = plan_rebalance(&positions, &state, &exit_prices, 1.0, 1.0, "test", &params);
+
+        let tags_of = |asset: &str| {
+            let order = orders.iter().find(|o| o.market.asset == asset).unwrap();
+            order.market.tags.clone()
+        };
+        assert_eq!(tags_of("a1"), vec!["NBA"]);
+        assert!(tags_of("a2").is_empty());
+        // Exit sells carry the tags remembered for the holding
+        assert_eq!(tags_of("gone"), vec!["old"]);
+    }
+
+    #[test]
+    fn capital_multiple_caps_compounding() {
+        let positions = vec![make_test_position(
+            "a", "ca", "Market A", "Yes", 0, "e", 0.80, 80.0,
+        )];
+        // Half the budget bought 100 shares at $0.50, now worth $80
+        let mut state = TradingState::new(dec!(100));
+        state.budget_remaining = dec!(50);
+        state.holdings.insert(
+            "a".to_string(),
+            HeldPosition {
+                asset: "a".to_string(),
+                title: "Market A".to_string(),
+                outcome: "Yes".to_string(),
+                shares: dec!(100),
+                total_cost: dec!(50),
+                avg_cost: dec!(0.5),
+                opposite_asset: String::new(),
+            },
+        );
+        let buy = |params: &EngineParams| {
+            let orders = plan_rebalance(&positions, &state, &HashMap::new(), 1.0, 1.0, "t", params);
+            orders.iter().map(|o| o.shares).sum::<Decimal>()
+        };
+        let mut params = EngineParams::default();
+        // $130 of capital: 162.5 shares
+        assert_eq!(buy(&params), dec!(62.5));
+        params.max_capital_multiple = Some(1.0);
+        assert_eq!(buy(&params), dec!(25));
+        params.max_capital_multiple = Some(1.2);
+        assert_eq!(buy(&params), dec!(50));
+        // Below the cap, losses still shrink the sizing
+        let prices = HashMap::from([("a".to_string(), 0.20)]);
+        assert_eq!(sizing_capital(&state, &prices, &params), dec!(70));
+    }
+
+    #[test]
+    fn buys_are_budgeted_with_taker_fees() {
+        let positions = vec![make_test_position(
+            "a", "ca", "Market A", "Yes", 0, "e", 0.50, 50.0,
+        )];
+        let mut state = TradingState::new(dec!(10));
+        // 10% of min(p, 1 - p): 5¢ a share at 50¢
+        state.fee_rates.insert("a".to_string(), 1000);
+        let params = EngineParams::default();
+        let orders = plan_rebalance(&positions, &state, &HashMap::new(), 1.0, 1.0, "t", &params);
+        // 20 shares ($10) would leave nothing for the fee: $10 buys 18.18 at 55¢
+        assert_eq!(orders[0].shares, dec!(18.18));
+        assert_eq!(orders[0].cost_usd, dec!(9.09));
+
+        // Dry-run books the fee into the fill
+        let charged = charge_taker_fees(&orders, &state);
+        assert_eq!(charged[0].cost_usd, dec!(9.999));
+        assert_eq!(charged[0].price, dec!(0.55));
+        state.apply_orders(&charged);
+        assert_eq!(state.budget_remaining, dec!(0.001));
+    }
+
+    #[test]
+    fn trader_splits_leave_weights_unchanged() {
+        let before = vec![make_test_position(
+            "a", "ca", "Market A", "Yes", 0, "e", 0.60, 60.0,
+        )];
+        // The trader splits $100 into 100 YES + 100 NO of market B
+        let mut after = before.clone();
+        after.push(make_test_position(
+            "b-yes", "cb", "Market B", "Yes", 0, "e", 0.30, 30.0,
+        ));
+        after.push(make_test_position(
+            "b-no", "cb", "Market B", "No", 1, "e", 0.70, 70.0,
+        ));
+
+        let sets = complete_sets(&after);
+        assert_eq!(sets.len(), 2);
+        assert!(approx_eq(sets["b-yes"], 100.0));
+        assert!(!sets.contains_key("a"));
+
+        let state = TradingState::new(dec!(100));
+        let plan = |positions: &[Position], params: &EngineParams| -> Vec<(String, Decimal)> {
+            plan_rebalance(positions, &state, &HashMap::new(), 1.0, 1.0, "test", params)
+                .into_iter()
+                .map(|o| (o.market.asset, o.shares))
+                .collect()
+        };
+        let mut params = EngineParams {
+            complete_sets_as_cash: true,
+            ..EngineParams::default()
+        };
+        assert_eq!(plan(&before, &params), plan(&after, &params));
+
+        // Counted as positions, the split dilutes market A
+        params.complete_sets_as_cash = false;
+        let a_shares = |orders: Vec<(String, Decimal)>| {
+            orders.into_iter().find(|(asset, _)| asset == "a").unwrap().1
+        };
+        assert!(a_shares(plan(&after, &params)) < a_shares(plan(&before, &params)));
+    }
+
+    #[test]
+    fn plan_rebalance_nets_opposite_outcomes() {
+        let positions = vec![
+            make_test_position("yes", "c1", "Finals", "Yes", 0, "e", 0.60, 60.0),
+            make_test_position("no", "c1", "Finals", "No", 1, "e", 0.40, 20.0),
+        ];
+        let mut state = TradingState::new(dec!(100));
+        state.holdings.insert(
+            "no".to_string(),
+            HeldPosition {
+                asset: "no".to_string(),
+                title: "Finals".to_string(),
+                outcome: "No".to_string(),
+                shares: dec!(50),
+                total_cost: dec!(20),
+                avg_cost: dec!(0.4),
+                opposite_asset: "yes".to_string(),
+            },
+        );
+        // Target netting on its own: the trader's sets still count as deployed
+        let mut params = EngineParams {
+            net_opposite_outcomes: true,
+            complete_sets_as_cash: false,
+            ..EngineParams::default()
+        };
+        let summary = |orders: Vec<SimulatedOrder>| -> Vec<(String, OrderSide, Decimal)> {
+            orders
+                .into_iter()
+                .map(|o| (o.market.asset, o.side, o.shares))
+                .collect()
+        };
+
+        // $120 capital: 150 YES and 75 NO net to 75 YES, so the NO is sold
+        let orders = plan_rebalance(
+            &positions,
+            &state,
+            &HashMap::new(),
+            1.0,
+            1.0,
+            "test",
+            &params,
+        );
+        let orders = summary(orders);
+        assert_eq!(orders.len(), 2);
+        assert_eq!((orders[0].0.as_str(), orders[0].1), ("no", OrderSide::Sell));
+        assert_eq!(orders[0].2, dec!(50));
+        assert_eq!((orders[1].0.as_str(), orders[1].1), ("yes", OrderSide::Buy));
+        assert_eq!(orders[1].2, dec!(75));
+
+        params.net_opposite_outcomes = false;
+        let orders = plan_rebalance(
+            &positions,
+            &state,
+            &HashMap::new(),
+            1.0,
+            1.0,
+            "test",
+            &params,
+        );
+        assert!(
+            summary(orders)
+                .iter()
+                .all(|(_, side, _)| *side == OrderSide::Buy)
+        );
+    }
+
+    #[test]
+    fn end_scaling_shrinks_caps_near_end_date() {
+        let now = DateTime::parse_from_rfc3339("2026-03-01T00:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let mut near = make_test_position("a1", "c1", "Near", "Yes", 0, "e", 0.50, 50.0);
+        near.end_date = "2026-03-01T12:00:00Z".to_string();
+        let mut far = make_test_position("a2", "c2", "Far", "Yes", 0, "e", 0.50, 50.0);
+        far.end_date = "2026-06-30".to_string();
+        let mut undated = make_test_position("a3", "c3", "Undated", "Yes", 0, "e", 0.50, 50.0);
+        undated.end_date = String::new();
+        let positions = vec![near, far, undated];
+        let weights = compute_weights(&positions);
+        let mut targets =
+            compute_target_state(&weights, dec!(300), 1.0, 0.5, &MarketFilters::default());
+        let scaling = EndScaling {
+            window_hours: 24.0,
+            min_factor: 0.0,
+            exponent: 1.0,
+        };
+        apply_end_scaling(&mut targets, &positions, dec!(150), &scaling, now);
+
+        // 12h of a 24h window left → half of the $150 cap, below the $100 target
+        assert_eq!(targets[0].target_value_usd, dec!(75));
+        assert_eq!(targets[0].target_shares, dec!(150));
+        assert_eq!(targets[1].target_value_usd, dec!(100));
+        assert_eq!(targets[2].target_value_usd, dec!(100));
+    }
+
+    #[test]
+    fn runaway_guard_blocks_buys_only() {
+        // Trader entered at $0.50 everywhere (see make_test_position)
+        let positions = vec![
+            make_test_position("a1", "c1", "Ran", "Yes", 0, "e", 0.60, 50.0),
+            make_test_position("a2", "c2", "Near", "Yes", 0, "e", 0.54, 50.0),
+            make_test_position("a3", "c3", "Held", "Yes", 0, "e", 0.70, 50.0),
+        ];
+        let mut state = TradingState::new(dec!(300));
+        hold(&mut state, "a3", dec!(40), dec!(0.5));
+        let weights = compute_weights(&positions);
+        let mut targets =
+            compute_target_state(&weights, dec!(300), 1.0, 1.0, &MarketFilters::default());
+        attach_trader_entries(&mut targets, &positions);
+        assert_eq!(targets[0].trader_entry.unwrap(), dec!(0.5));
+        let skipped = apply_runaway_guard(&mut targets, &state, 0.10);
+
+        // a1 (+20%) and a3 (+40%) get no buys; a2 (+8%) is within the premium
+        let mut titles: Vec<&str> = skipped.iter().map(|(t, _)| t.as_str()).collect();
+        titles.sort();
+        assert_eq!(titles, ["Held", "Ran"]);
+        let target = |asset: &str| targets.iter().find(|t| t.market.asset == asset).unwrap();
+        assert_eq!(target("a1").target_shares, dec!(0));
+        assert_eq!(target("a2").target_value_usd, dec!(100));
+        // a3 (+40%) stays at current holdings rather than adding
+        assert_eq!(target("a3").target_shares, dec!(40));
+    }
+
+    #[test]
+    fn price_band_skips_near_resolved_markets() {
+        let positions = vec![
+            make_test_position("a1", "c1", "Longshot", "Yes", 0, "e", 0.02, 50.0),
+            make_test_position("a2", "c2", "Open", "Yes", 0, "e", 0.50, 50.0),
+            make_test_position("a3", "c3", "Lock", "Yes", 0, "e", 0.98, 50.0),
+        ];
+        let mut state = TradingState::new(dec!(300));
+        hold(&mut state, "a3", dec!(40), dec!(0.9));
+        let weights = compute_weights(&positions);
+        let mut targets =
+            compute_target_state(&weights, dec!(300), 1.0, 1.0, &MarketFilters::default());
+        let skipped = apply_price_band(&mut targets, &state, Some(0.03), Some(0.97));
+
+        let titles: Vec<&str> = skipped.iter().map(|(t, _)| t.as_str()).collect();
+        assert_eq!(titles, ["Longshot", "Lock"]);
+        assert_eq!(targets[0].target_shares, dec!(0));
+        assert_eq!(targets[1].target_value_usd, dec!(100));
+        // The held lock is kept, not sold
+        assert_eq!(targets[2].target_shares, dec!(40));
+    }
+
+    #[test]
+    fn unwinding_markets_are_held_when_enabled() {
+        let positions = vec![
+            make_test_position("a1", "c1", "Steady", "Yes", 0, "e", 0.50, 50.0),
+            make_test_position("a2", "c2", "Unwinding", "Yes", 0, "e", 0.50, 50.0),
+        ];
+        let state = TradingState::new(dec!(100));
+        let unwind = TradeCluster {
+            // Matched whatever its case
+            condition_id: "C2".to_string(),
+            intent: TradeIntent::Unwind,
+            trades: 2,
+            first_timestamp: 0,
+            last_timestamp: 30,
+            net_shares: [("a2".to_string(), dec!(-40))].into(),
+        };
+        let mut params = EngineParams {
+            trader_intents: vec![unwind],
+            ..EngineParams::default()
+        };
+        let plan = |params: &EngineParams| {
+            plan_rebalance(
+                &positions,
+                &state,
+                &HashMap::new(),
+                1.0,
+                1.0,
+                "test",
+                params,
+            )
+        };
+        assert_eq!(plan(&params).len(), 2);
+
+        params.hold_while_unwinding = true;
+        let orders = plan(&params);
+        assert_eq!(orders.len(), 1);
+        assert_eq!(orders[0].market.asset, "a1");
+    }
+
+    #[test]
+    fn category_verdicts_apply_after_sizing() {
+        let positions = vec![
+            make_test_position("a1", "c1", "Finals", "Yes", 0, "nba-finals", 0.50, 50.0),
+            make_test_position("a2", "c2", "Election", "Yes", 0, "election", 0.50, 50.0),
+            make_test_position("a3", "c3", "Oscars", "Yes", 0, "oscars", 0.50, 50.0),
+        ];
+        let state = TradingState::new(dec!(90));
+        let mut params = EngineParams::default();
+        params
+            .category_verdicts
+            .refused
+            .insert("election".to_string());
+        params
+            .category_verdicts
+            .unverified
+            .insert("oscars".to_string());
+        let orders = plan_rebalance(
+            &positions,
+            &state,
+            &HashMap::new(),
+            1.0,
+            1.0,
+            "test",
+            &params,
+        );
+
+        // Only the allowed market is bought, with its third of the budget
+        assert_eq!(orders.len(), 1);
+        assert_eq!(orders[0].market.asset, "a1");
+        assert_eq!(orders[0].shares, dec!(60));
+    }
+
+    #[test]
+    fn ramp_in_deploys_largest_weights_first() {
+        let positions = vec![
+            make_test_position("a1", "c1", "Small", "Yes", 0, "e", 0.50, 20.0),
+            make_test_position("a2", "c2", "Large", "Yes", 0, "e", 0.50, 50.0),
+            make_test_position("a3", "c3", "Medium", "Yes", 0, "e", 0.50, 30.0),
+        ];
+        let mut state = TradingState::new(dec!(100));
+        hold(&mut state, "a1", dec!(10), dec!(0.5));
+        let weights = compute_weights(&positions);
+        let mut targets =
+            compute_target_state(&weights, dec!(100), 1.0, 1.0, &MarketFilters::default());
+        // $60 of $100: all of Large ($50), $10 of Medium, none of Small
+        assert_eq!(apply_ramp_in(&mut targets, &state, 0.6), 2);
+        assert_eq!(targets[1].target_value_usd, dec!(50));
+        assert_eq!(targets[2].target_value_usd, dec!(10));
+        // Small is held where it is, not sold
+        assert_eq!(targets[0].target_shares, dec!(10));
+    }
+
+    #[test]
+    fn averaging_down_lifts_cap_when_trader_adds_at_a_loss() {
+        // Trader entered everything at $0.50; a1/a2/a4 now trade at a loss, a3 at a gain
+        let positions = vec![
+            make_test_position("a1", "c1", "Big loser", "Yes", 0, "e", 0.40, 60.0),
+            make_test_position("a2", "c2", "Loser", "Yes", 0, "e", 0.40, 30.0),
+            make_test_position("a3", "c3", "Winner", "Yes", 0, "e", 0.60, 10.0),
+            make_test_position("a4", "c4", "Idle loser", "Yes", 0, "e", 0.40, 30.0),
+        ];
+        // The trader is adding to a1 and a3; a2 was averaged down earlier and is
+        // still held above the cap
+        let adds = |asset: &str, condition_id: &str| TradeCluster {
+            condition_id: condition_id.to_string(),
+            intent: TradeIntent::Accumulate,
+            trades: 2,
+            first_timestamp: 0,
+            last_timestamp: 30,
+            net_shares: [(asset.to_string(), dec!(50))].into(),
+        };
+        let clusters = vec![adds("a1", "c1"), adds("a3", "c3")];
+        let mut state = TradingState::new(dec!(125));
+        hold(&mut state, "a2", dec!(55), dec!(0.45));
+        let weights = compute_weights(&positions);
+        // $100 regular budget, 20% cap → a1/a2/a4 clipped at $20
+        let mut targets =
+            compute_target_state(&weights, dec!(100), 1.0, 0.2, &MarketFilters::default());
+        let cfg = AveragingDown {
+            reserve_pct: 0.25,
+            max_extra_pct: 0.2,
+            min_loss_pct: 0.1,
+        };
+        let winner = targets
+            .iter()
+            .find(|t| t.market.asset == "a3")
+            .unwrap()
+            .target_value_usd;
+        apply_averaging_down(
+            &mut targets,
+            &positions,
+            &state,
+            &clusters,
+            dec!(125),
+            dec!(100),
+            &cfg,
+        );
+
+        let value = |asset: &str| {
+            targets
+                .iter()
+                .find(|t| t.market.asset == asset)
+                .unwrap()
+                .target_value_usd
+        };
+        // Reserve $31.25, max $25 extra each: a1 (clipped by $26) gets $25; a2
+        // keeps the $2 it holds above the cap; a4 isn't being added to; the
+        // winner isn't at a loss
+        assert_eq!(value("a1"), dec!(45));
+        assert_eq!(value("a2"), dec!(22));
+        assert_eq!(value("a3"), winner);
+        assert_eq!(value("a4"), dec!(20));
+    }
+
+    #[test]
+    fn orders_whole_shares_respects_min_notional() {
+        let state = TradingState::new(dec!(1000));
+        // 1.9 shares at $0.60 = $1.14, but 1 whole share is only $0.60
+        let targets = vec![make_target("a1", 1.0, dec!(1.9), dec!(0.6))];
+        let params = EngineParams {
+            whole_shares: true,
+            ..EngineParams::default()
+        };
+        let orders = compute_orders(
+            &targets,
+            &state,
+            dec!(1000),
+            &HashMap::new(),
+            "test",
+            &params,
+        );
+        assert!(orders.is_empty());
+
+        // Partial-budget buys are also rounded down before the notional check
+        let targets = vec![make_target("a1", 1.0, dec!(60), dec!(0.5))];
+        let orders = compute_orders(
+            &targets,
+            &state,
+            dec!(5.2),
+            &HashMap::new(),
+            "test",
+            &params,
+        );
+        assert_eq!(orders.len(), 1);
+        assert_eq!(orders[0].shares, dec!(10));
+        assert_eq!(orders[0].cost_usd, dec!(5));
+    }
+
     #[test]
     fn orders_missing_exit_price_skips() {
-        let mut state = TradingState::new(1000.0);
+        let mut state = TradingState::new(dec!(1000));
         state.holdings.insert(
             "a1".to_string(),
             HeldPosition {
                 asset: "a1".to_string(),
                 title: "Unknown".to_string(),
                 outcome: "Yes".to_string(),
-                shares: 10.0,
-                total_cost: 5.0,
-                avg_cost: 0.50,
+                shares: dec!(10),
+                total_cost: dec!(5),
+                avg_cost: dec!(0.5),
+                opposite_asset: String::new(),
             },
         );
         // No targets and no price_map entry → should skip (with warning)
-        let orders = compute_orders(&[], &state, 1000.0, &HashMap::new(), "test");
+        let orders = compute_orders(
+            &[],
+            &state,
+            dec!(1000),
+            &HashMap::new(),
+            "test",
+            &EngineParams::default(),
+        );
         assert!(orders.is_empty());
     }
+
+    // ── market selection ───────────────────────────────────────────
+
+    fn selection(ids: &[&str], slugs: &[&str], weighting: MarketWeighting) -> MarketSelection {
+        MarketSelection {
+            condition_ids: ids.iter().map(|s| s.to_string()).collect(),
+            event_slugs: slugs.iter().map(|s| s.to_string()).collect(),
+            weighting,
+        }
+    }
+
+    #[test]
+    fn selection_filters_by_condition_or_event() {
+        let positions = vec![
+            make_test_position("a1", "c1", "T1", "Yes", 0, "nba", 0.50, 30.0),
+            make_test_position("a2", "c2", "T2", "Yes", 0, "nfl", 0.50, 30.0),
+            make_test_position("a3", "c3", "T3", "Yes", 0, "nhl", 0.50, 40.0),
+        ];
+        // Condition IDs match whatever their case
+        let sel = selection(&["C1"], &["nhl"], MarketWeighting::Selected);
+        let (selected, scale) = sel.apply(&positions);
+        let assets: Vec<&str> = selected.iter().map(|p| p.asset.as_str()).collect();
+        assert_eq!(assets, vec!["a1", "a3"]);
+        assert_eq!(scale, 1.0);
+    }
+
+    #[test]
+    fn portfolio_weighting_preserves_full_portfolio_targets() {
+        let positions = vec![
+            make_test_position("a1", "c1", "T1", "Yes", 0, "s", 0.50, 25.0),
+            make_test_position("a2", "c2", "T2", "Yes", 0, "s", 0.50, 75.0),
+        ];
+        let (selected, scale) =
+            selection(&["c1"], &[], MarketWeighting::Portfolio).apply(&positions);
+        assert!(approx_eq(scale, 0.25));
+
+        // Selected market gets 25% of the copy budget, the rest stays in cash
+        let weights = compute_weights(&selected);
+        let targets = compute_target_state(
+            &weights,
+            dec!(1000),
+            1.0 * scale,
+            1.0,
+            &MarketFilters::default(),
+        );
+        assert_eq!(targets.len(), 1);
+        assert_eq!(targets[0].target_value_usd, dec!(250));
+
+        // Selected weighting puts the whole budget into the one market
+        let (selected, scale) =
+            selection(&["c1"], &[], MarketWeighting::Selected).apply(&positions);
+        let targets = compute_target_state(
+            &compute_weights(&selected),
+            dec!(1000),
+            scale,
+            1.0,
+            &MarketFilters::default(),
+        );
+        assert_eq!(targets[0].target_value_usd, dec!(1000));
+    }
 }
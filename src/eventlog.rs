@@ -0,0 +1,250 @@
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use anyhow::{Context, Result};
+use flate2::Compression;
+use flate2::write::GzEncoder;
+use serde::Serialize;
+use tracing::{info, warn};
+
+use crate::config::EventRetention;
+
+/// Size-rotated JSON-lines log of reported events, kept next to the WAL
+/// (`[persistence.events]`).
+///
+/// Appends go to the active file. Once it reaches `max_file_mb` it is renamed
+/// to `<name>.<UTC timestamp>`, gzipped (to `<name>.<timestamp>.gz`) when
+/// `compress` is set, and the rotated files are pruned by `max_age_days` and
+/// `max_total_mb`. Compression and pruning run on tokio's blocking pool when
+/// called from the runtime, so the write that triggered them doesn't wait.
+pub struct EventLog {
+    path: PathBuf,
+    file: File,
+    len: u64,
+    retention: EventRetention,
+}
+
+impl EventLog {
+    /// Open (or create) the active file at `path`.
+    pub fn open(path: &Path, retention: EventRetention) -> Result<Self> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("failed to open {}", path.display()))?;
+        let len = file.metadata().map(|m| m.len()).unwrap_or(0);
+        Ok(Self {
+            path: path.to_path_buf(),
+            file,
+            len,
+            retention,
+        })
+    }
+
+    /// Event log path for the WAL at `wal`: `<wal>.events`.
+    pub fn path_for(wal: &Path) -> PathBuf {
+        let mut name = wal.file_name().unwrap_or_default().to_os_string();
+        name.push(".events");
+        wal.with_file_name(name)
+    }
+
+    /// Append `event` as one line, rotating afterwards if the file is full.
+    pub fn append<T: Serialize>(&mut self, event: &T) -> Result<()> {
+        let mut line = serde_json::to_string(event).context("failed to serialize event")?;
+        line.push('\n');
+        self.file
+            .write_all(line.as_bytes())
+            .context("failed to append event")?;
+        self.len += line.len() as u64;
+        if self.len as f64 >= self.retention.max_file_mb * 1_000_000.0 {
+            self.rotate()?;
+        }
+        Ok(())
+    }
+
+    /// Move the active file aside and start a new one, then compress and prune
+    /// (in the background on a tokio runtime).
+    fn rotate(&mut self) -> Result<()> {
+        let stamp = chrono::Utc::now().format("%Y%m%dT%H%M%S%3fZ");
+        let mut name = self.path.file_name().unwrap_or_default().to_os_string();
+        name.push(format!(".{stamp}"));
+        let rotated = self.path.with_file_name(name);
+        std::fs::rename(&self.path, &rotated)
+            .with_context(|| format!("failed to rotate {}", self.path.display()))?;
+        *self = Self::open(&self.path, self.retention.clone())?;
+        info!("Rotated event log to {}", rotated.display());
+
+        let (path, retention) = (self.path.clone(), self.retention.clone());
+        let finish = move || {
+            if retention.compress
+                && let Err(e) = gzip(&rotated)
+            {
+                warn!("{e:#}");
+            }
+            if let Err(e) = prune(&path, &retention) {
+                warn!("{e:#}");
+            }
+        };
+        match tokio::runtime::Handle::try_current() {
+            Ok(runtime) => drop(runtime.spawn_blocking(finish)),
+            Err(_) => finish(),
+        }
+        Ok(())
+    }
+}
+
+/// Compress `path` to `<path>.gz` and remove it.
+fn gzip(path: &Path) -> Result<()> {
+    let mut name = path.as_os_str().to_os_string();
+    name.push(".gz");
+    let compressed = PathBuf::from(name);
+    let mut input =
+        File::open(path).with_context(|| format!("failed to open {}", path.display()))?;
+    let output = File::create(&compressed)
+        .with_context(|| format!("failed to create {}", compressed.display()))?;
+    let mut encoder = GzEncoder::new(output, Compression::default());
+    std::io::copy(&mut input, &mut encoder)
+        .and_then(|_| encoder.finish())
+        .with_context(|| format!("failed to compress {}", path.display()))?;
+    std::fs::remove_file(path).with_context(|| format!("failed to remove {}", path.display()))
+}
+
+/// Delete the rotated files of the event log at `path` past the `retention`
+/// limits.
+fn prune(path: &Path, retention: &EventRetention) -> Result<()> {
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let mut prefix = path.file_name().unwrap_or_default().to_os_string();
+    prefix.push(".");
+    let prefix = prefix.to_string_lossy().into_owned();
+
+    let mut rotated = Vec::new();
+    let entries =
+        std::fs::read_dir(dir).with_context(|| format!("failed to list {}", dir.display()))?;
+    for entry in entries.flatten() {
+        if !entry.file_name().to_string_lossy().starts_with(&prefix) {
+            continue;
+        }
+        let Ok(meta) = entry.metadata() else {
+            continue;
+        };
+        let modified = meta.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+        rotated.push((entry.path(), modified, meta.len()));
+    }
+    for path in expired_files(rotated, retention, SystemTime::now()) {
+        match std::fs::remove_file(&path) {
+            Ok(()) => info!("Deleted old event log {}", path.display()),
+            Err(e) => warn!("Failed to delete old event log {}: {e}", path.display()),
+        }
+    }
+    Ok(())
+}
+
+/// Rotated files (path, last modified, bytes) that `retention` drops as of
+/// `now`: those older than `max_age_days`, then the oldest until the rest fit
+/// in `max_total_mb`.
+pub fn expired_files(
+    mut files: Vec<(PathBuf, SystemTime, u64)>,
+    retention: &EventRetention,
+    now: SystemTime,
+) -> Vec<PathBuf> {
+    files.sort_by_key(|(_, modified, _)| *modified);
+    let max_age = retention
+        .max_age_days
+        .map(|days| Duration::from_secs(days * 86_400));
+    let mut total: u64 = files.iter().map(|(_, _, len)| len).sum();
+    let mut expired = Vec::new();
+    for (path, modified, len) in files {
+        let too_old =
+            max_age.is_some_and(|max| now.duration_since(modified).unwrap_or_default() > max);
+        let over_size = retention
+            .max_total_mb
+            .is_some_and(|mb| total as f64 > mb * 1_000_000.0);
+        if too_old || over_size {
+            total -= len;
+            expired.push(path);
+        }
+    }
+    expired
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rotates_and_prunes_by_age_and_size() {
+        let dir = std::env::temp_dir().join(format!("copytrade-events-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = EventLog::path_for(&dir.join("state.wal"));
+        assert!(path.ends_with("state.wal.events"));
+
+        let retention = EventRetention {
+            max_file_mb: 0.000_05,
+            compress: false,
+            max_age_days: None,
+            max_total_mb: None,
+        };
+        let mut log = EventLog::open(&path, retention.clone()).unwrap();
+        log.append(&serde_json::json!({"event": "x".repeat(60)}))
+            .unwrap();
+        assert_eq!(std::fs::metadata(&path).unwrap().len(), 0);
+        let rotated = std::fs::read_dir(&dir).unwrap().count();
+        assert_eq!(rotated, 2);
+
+        // Compressed in-process (inline outside a runtime) to `<rotated>.gz`
+        let mut log = EventLog::open(
+            &path,
+            EventRetention {
+                compress: true,
+                ..retention.clone()
+            },
+        )
+        .unwrap();
+        log.append(&serde_json::json!({"event": "y".repeat(60)}))
+            .unwrap();
+        let gz = std::fs::read_dir(&dir)
+            .unwrap()
+            .flatten()
+            .find(|e| e.file_name().to_string_lossy().ends_with(".gz"))
+            .unwrap();
+        let mut decoded = String::new();
+        std::io::Read::read_to_string(
+            &mut flate2::read::GzDecoder::new(File::open(gz.path()).unwrap()),
+            &mut decoded,
+        )
+        .unwrap();
+        assert!(decoded.contains(&"y".repeat(60)));
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let now = SystemTime::now();
+        let day = Duration::from_secs(86_400);
+        let files = vec![
+            (PathBuf::from("c"), now - day, 400),
+            (PathBuf::from("a"), now - day * 10, 400),
+            (PathBuf::from("b"), now - day * 3, 400),
+        ];
+        let by_age = EventRetention {
+            max_age_days: Some(7),
+            ..retention.clone()
+        };
+        assert_eq!(
+            expired_files(files.clone(), &by_age, now),
+            vec![PathBuf::from("a")]
+        );
+        // 1200 bytes against an 800-byte cap: the oldest one goes
+        let by_size = EventRetention {
+            max_total_mb: Some(0.000_8),
+            ..retention
+        };
+        assert_eq!(
+            expired_files(files, &by_size, now),
+            vec![PathBuf::from("a")]
+        );
+    }
+}
@@ -1,30 +1,41 @@
-use std::time::Duration;
+use std::collections::HashMap;
 
 use anyhow::Result;
-use polymarket_client_sdk::clob::types::request::BalanceAllowanceRequest;
-use polymarket_client_sdk::clob::types::{OrderStatusType, Side as ClobSide};
+use polymarket_client_sdk::auth::state::State;
+use polymarket_client_sdk::clob::Client as ClobClient;
+use polymarket_client_sdk::clob::types::request::{
+    BalanceAllowanceRequest, OrderBookSummaryRequest, OrdersRequest,
+    TradesRequest as ClobTradesRequest,
+};
+use polymarket_client_sdk::clob::types::response::OpenOrderResponse;
+use polymarket_client_sdk::clob::types::{OrderStatusType, Side as ClobSide, TraderSide};
+use polymarket_client_sdk::data::types::MarketFilter;
 use rust_decimal::Decimal;
 use rust_decimal::prelude::ToPrimitive;
-use tracing::{info, warn};
+use sha2::{Digest, Sha256};
+use tracing::{debug, error, info, warn};
 
+use crate::api::{fetch_fee_rate_bps, fetch_order_book};
 use crate::auth::ClobContext;
+use crate::config::{BookPricing, EngineParams, ExecutorParams, RetryOperation};
+use crate::depth::OrderBook;
+use crate::engine::{self, SHARE_INCREMENT};
+use crate::fills::{FillBook, OrderFill, OrderState};
+use crate::lease;
+use crate::money;
+use crate::ratelimit;
+use crate::reporter;
+use crate::retry;
 use crate::state::TradingState;
-use crate::types::{ExecutionResult, ExecutionStatus, OrderSide, SimulatedOrder};
-
-/// Delay between consecutive order submissions to avoid rate limits.
-const INTER_ORDER_DELAY: Duration = Duration::from_millis(200);
-
-/// Delay before checking order fill status.
-const FILL_CHECK_DELAY: Duration = Duration::from_secs(2);
-
-/// Maximum retry attempts for transient errors.
-const MAX_RETRIES: u32 = 3;
-
-/// Base backoff delay for retries (doubles each attempt).
-const BASE_BACKOFF: Duration = Duration::from_millis(500);
+use crate::types::{
+    AlertEvent, AlertKind, AlertSeverity, ExecutionResult, ExecutionStatus, MarketPosition,
+    OrderSide, PendingOrder, RestingOrder, SimulatedOrder, TwapSchedule,
+};
+use crate::wal::Mutation;
 
 /// Check USDC balance, returning the amount in dollars.
 pub async fn check_balance(ctx: &ClobContext) -> Result<f64> {
+    ratelimit::acquire_clob("clob:/balance-allowance").await;
     let bal = ctx
         .client
         .balance_allowance(BalanceAllowanceRequest::default())
@@ -34,40 +45,164 @@ pub async fn check_balance(ctx: &ClobContext) -> Result<f64> {
     Ok(raw / 1_000_000.0)
 }
 
-/// Convert f64 price to Decimal truncated to 2 decimal places.
-fn f64_to_price(val: f64) -> Result<Decimal> {
+/// Lowest and highest limit prices the CLOB accepts.
+const MIN_LIMIT_PRICE: f64 = 0.01;
+const MAX_LIMIT_PRICE: f64 = 0.99;
+
+/// Limit price for `order`: its detection price moved `max_chase` against us
+/// (up for buys, down for sells), within the CLOB's price range.
+pub fn limit_price(order: &SimulatedOrder, max_chase: f64) -> f64 {
+    let price = money::to_f64(order.price);
+    let price = match order.side {
+        OrderSide::Buy => price + max_chase,
+        OrderSide::Sell => price - max_chase,
+    };
+    if max_chase > 0.0 {
+        price.clamp(MIN_LIMIT_PRICE, MAX_LIMIT_PRICE)
+    } else {
+        price
+    }
+}
+
+/// Limit price for `order` from its token's order book: the best ask plus
+/// `pricing.ticks` ticks for buys, the best bid minus them for sells, at most
+/// `pricing.max_premium` worse than the detection price. `None` when that side
+/// of the book is empty.
+pub fn book_limit_price(
+    order: &SimulatedOrder,
+    book: &OrderBook,
+    pricing: &BookPricing,
+) -> Option<f64> {
+    let offset = f64::from(pricing.ticks) * book.tick_size;
+    let intended = money::to_f64(order.price);
+    let price = match order.side {
+        OrderSide::Buy => (book.asks.first()?.0 + offset).min(intended + pricing.max_premium),
+        OrderSide::Sell => (book.bids.first()?.0 - offset).max(intended - pricing.max_premium),
+    };
+    Some(price.clamp(MIN_LIMIT_PRICE, MAX_LIMIT_PRICE))
+}
+
+/// Hold `limit` within `max_slippage_pct` percent of the order's intended
+/// price, so no part of it fills worse; the rest rests at the capped limit.
+/// `Err` with the reason to skip the order when the touch in `book` (best ask
+/// for buys, best bid for sells) is already past that.
+pub fn cap_slippage(
+    order: &SimulatedOrder,
+    book: Option<&OrderBook>,
+    limit: f64,
+    max_slippage_pct: f64,
+) -> std::result::Result<f64, String> {
+    let slippage = max_slippage_pct / 100.0;
+    let intended = money::to_f64(order.price);
+    let (worst, touch) = match order.side {
+        OrderSide::Buy => (
+            intended * (1.0 + slippage),
+            book.and_then(|b| b.asks.first()),
+        ),
+        OrderSide::Sell => (
+            intended * (1.0 - slippage),
+            book.and_then(|b| b.bids.first()),
+        ),
+    };
+    let past = |price: f64| match order.side {
+        OrderSide::Buy => price > worst + 1e-9,
+        OrderSide::Sell => price < worst - 1e-9,
+    };
+    if let Some(&(price, _)) = touch
+        && past(price)
+    {
+        let moved = (price / intended - 1.0).abs() * 100.0;
+        return Err(format!(
+            "slippage: best {} ${price:.4} is {moved:.1}% from ${:.4} (max {max_slippage_pct}%)",
+            if order.side == OrderSide::Buy { "ask" } else { "bid" },
+            order.price
+        ));
+    }
+    Ok(if past(limit) { worst } else { limit })
+}
+
+/// Limit price to post `order` at: from the order book with
+/// `[executor.book_pricing]`, else `limit_price`, held within
+/// `max_slippage_pct` (see `cap_slippage`, whose `Err` skips the order).
+/// Falls back to `limit_price` when the book can't be fetched or is empty on
+/// that side.
+async fn execution_price(
+    ctx: &ClobContext,
+    order: &SimulatedOrder,
+    params: &ExecutorParams,
+) -> std::result::Result<f64, String> {
+    let fallback = limit_price(order, params.max_chase);
+    if params.book_pricing.is_none() && params.max_slippage_pct.is_none() {
+        return Ok(fallback);
+    }
+    let book = match fetch_order_book(&ctx.client, &order.market.asset).await {
+        Ok(book) => Some(book),
+        Err(e) => {
+            warn!("{e:#} — pricing without the book");
+            None
+        }
+    };
+    let priced = params
+        .book_pricing
+        .as_ref()
+        .zip(book.as_ref())
+        .and_then(|(pricing, book)| book_limit_price(order, book, pricing));
+    let limit = match priced {
+        Some(price) => price,
+        None => {
+            if params.book_pricing.is_some() && book.is_some() {
+                warn!(
+                    "No {} side in the book for \"{}\" — pricing at ${fallback:.4}",
+                    if order.side == OrderSide::Buy { "ask" } else { "bid" },
+                    order.market.title
+                );
+            }
+            fallback
+        }
+    };
+    match params.max_slippage_pct {
+        Some(max) => cap_slippage(order, book.as_ref(), limit, max),
+        None => Ok(limit),
+    }
+}
+
+/// Convert f64 price to Decimal truncated to `decimals` places (the tick size's),
+/// after rounding off float noise so 0.29 stays 0.29.
+fn f64_to_price(val: f64, decimals: u32) -> Result<Decimal> {
     let d = Decimal::from_f64_retain(val)
         .ok_or_else(|| anyhow::anyhow!("cannot convert price {val} to Decimal"))?;
-    Ok(d.trunc_with_scale(2))
+    Ok(d.round_dp(decimals + 6).trunc_with_scale(decimals))
 }
 
-/// Convert f64 shares to Decimal truncated to 2 decimal places.
-fn f64_to_shares(val: f64) -> Result<Decimal> {
-    let d = Decimal::from_f64_retain(val)
-        .ok_or_else(|| anyhow::anyhow!("cannot convert shares {val} to Decimal"))?;
-    let truncated = d.trunc_with_scale(2);
+/// After a post rejected over its tick size, drop the cached tick sizes (ours
+/// and the SDK's) so the next order for the token re-reads them: a market's tick
+/// narrows as its price nears 0 or 1.
+fn forget_stale_tick(ctx: &ClobContext, token_id: &str, err: &str) {
+    if err.to_lowercase().contains("tick") {
+        ctx.market_rules.forget(token_id);
+        ctx.client.invalidate_internal_caches();
+    }
+}
+
+/// Shares truncated to the CLOB's 2 decimal places.
+fn lot_shares(val: Decimal) -> Result<Decimal> {
+    let truncated = val.trunc_with_scale(2);
     if truncated.is_zero() {
         anyhow::bail!("shares truncated to zero from {val}");
     }
     Ok(truncated)
 }
 
-/// Check if an error message indicates a transient/retryable failure.
-fn is_transient_error(err_str: &str) -> bool {
-    let lower = err_str.to_lowercase();
-    lower.contains("429")
-        || lower.contains("too many requests")
-        || lower.contains("500")
-        || lower.contains("502")
-        || lower.contains("503")
-        || lower.contains("504")
-        || lower.contains("internal server error")
-        || lower.contains("bad gateway")
-        || lower.contains("service unavailable")
-        || lower.contains("gateway timeout")
-        || lower.contains("timeout")
-        || lower.contains("connection")
-        || lower.contains("timed out")
+/// Query an order's status, retrying under the `[retry.order_status]` policy.
+async fn query_order(
+    ctx: &ClobContext,
+    order_id: &str,
+) -> polymarket_client_sdk::Result<OpenOrderResponse> {
+    retry::with_retry(RetryOperation::OrderStatus, "GET /order", || async {
+        ratelimit::acquire_clob("clob:GET /order").await;
+        ctx.client.order(order_id).await
+    })
+    .await
 }
 
 /// Map our internal `OrderSide` to the CLOB SDK `Side`.
@@ -78,13 +213,316 @@ fn to_clob_side(side: OrderSide) -> ClobSide {
     }
 }
 
+/// Map a CLOB SDK `Side` to our `OrderSide` (`None` for an unknown side).
+fn from_clob_side(side: ClobSide) -> Option<OrderSide> {
+    match side {
+        ClobSide::Buy => Some(OrderSide::Buy),
+        ClobSide::Sell => Some(OrderSide::Sell),
+        _ => None,
+    }
+}
+
+/// Split orders that exceed the configured per-order caps into equal child orders.
+///
+/// The CLOB market metadata only exposes a minimum order size, so the share and
+/// notional caps come from `params.max_order_shares` / `params.max_order_usd`.
+/// Children are rounded like the engine's orders (`engine::round_shares`), and the
+/// rounding remainder is spread one increment at a time over the first ones, so
+/// none exceeds the cap. Child orders keep the parent's side and order (sells stay
+/// ahead of buys), so the result can be passed straight to `execute_orders` and
+/// `apply_execution_results`.
+pub fn split_orders(
+    orders: Vec<SimulatedOrder>,
+    params: &ExecutorParams,
+    engine: &EngineParams,
+) -> Vec<SimulatedOrder> {
+    if params.max_order_shares.is_none() && params.max_order_usd.is_none() {
+        return orders;
+    }
+
+    let mut out = Vec::with_capacity(orders.len());
+    for order in orders {
+        let by_usd = params
+            .max_order_usd
+            .and_then(|max_usd| money::ratio(money::from_f64(max_usd), order.price));
+        let cap = match (params.max_order_shares.map(money::from_f64), by_usd) {
+            (Some(shares), Some(usd)) => shares.min(usd),
+            (Some(cap), None) | (None, Some(cap)) => cap,
+            (None, None) => Decimal::MAX,
+        };
+        if order.shares <= cap {
+            out.push(order);
+            continue;
+        }
+        let cap = engine::round_shares(cap, engine);
+        if cap <= Decimal::ZERO {
+            out.push(order);
+            continue;
+        }
+
+        let parts = (order.shares / cap).ceil();
+        let shares = engine::round_shares(order.shares / parts, engine);
+        info!(
+            "Splitting {} order for \"{}\" ({} shares) into {parts} orders of {shares:.2} shares",
+            order.side.label(),
+            order.market.title,
+            order.shares,
+        );
+        let increment = engine::share_increment(engine);
+        let mut remainder = order.shares - shares * parts;
+        for _ in 0..parts.to_u64().unwrap_or(1) {
+            let extra = remainder.min(increment);
+            remainder -= extra;
+            let shares = shares + extra;
+            out.push(SimulatedOrder {
+                shares,
+                cost_usd: shares * order.price,
+                ..order.clone()
+            });
+        }
+    }
+    out
+}
+
+/// Best opposing price level for an order: the lowest ask for buys, the highest bid for sells.
+#[derive(Debug, Clone, Copy)]
+pub struct TopOfBook {
+    pub price: Decimal,
+    pub size: Decimal,
+}
+
+/// Split a marketable order that dwarfs the top-of-book liquidity.
+///
+/// Returns a marketable chunk sized to the top level, plus the remainder as a second
+/// order at the top-of-book price so it rests instead of sweeping deeper levels.
+/// A remainder below the market's `min_shares` stays in the chunk, as one order at
+/// the top-of-book price. Orders that aren't marketable, fit within `ratio` × top
+/// size, or whose buy chunk would fall below `min_buy_usd` are returned unchanged.
+pub fn split_by_liquidity(
+    order: SimulatedOrder,
+    top: TopOfBook,
+    ratio: f64,
+    min_buy_usd: f64,
+    min_shares: Decimal,
+) -> Vec<SimulatedOrder> {
+    let marketable = match order.side {
+        OrderSide::Buy => order.price >= top.price,
+        OrderSide::Sell => order.price <= top.price,
+    };
+    let chunk_cost = top.size * top.price;
+    if !marketable
+        || top.size <= Decimal::ZERO
+        || order.shares <= money::from_f64(ratio) * top.size
+        || (order.side == OrderSide::Buy && chunk_cost < money::from_f64(min_buy_usd))
+    {
+        return vec![order];
+    }
+
+    let remainder = order.shares - top.size;
+    if remainder < min_shares {
+        info!(
+            "Order for \"{}\" ({} shares) exceeds top-of-book liquidity ({} @ ${}) — \
+             limiting it to ${} (remainder {remainder:.2} below the market minimum)",
+            order.market.title, order.shares, top.size, top.price, top.price,
+        );
+        return vec![SimulatedOrder {
+            price: top.price,
+            cost_usd: order.shares * top.price,
+            ..order
+        }];
+    }
+    info!(
+        "Order for \"{}\" ({} shares) exceeds top-of-book liquidity ({} @ ${}) — \
+         splitting into marketable {} + resting {remainder:.2}",
+        order.market.title, order.shares, top.size, top.price, top.size,
+    );
+    vec![
+        SimulatedOrder {
+            shares: top.size,
+            price: top.price,
+            cost_usd: chunk_cost,
+            ..order.clone()
+        },
+        SimulatedOrder {
+            shares: remainder,
+            price: top.price,
+            cost_usd: remainder * top.price,
+            ..order
+        },
+    ]
+}
+
+/// Fetch the best opposing level for `order` from the CLOB order book.
+async fn fetch_top_of_book(ctx: &ClobContext, order: &SimulatedOrder) -> Result<Option<TopOfBook>> {
+    let request = OrderBookSummaryRequest::builder()
+        .token_id(order.market.asset.clone())
+        .build();
+    ratelimit::acquire_clob("clob:/book").await;
+    let book = ctx.client.order_book(&request).await?;
+    let levels = match order.side {
+        OrderSide::Buy => &book.asks,
+        OrderSide::Sell => &book.bids,
+    };
+    let best = levels
+        .iter()
+        .map(|l| (l.price, l.size))
+        .reduce(|best, level| {
+            let better = match order.side {
+                OrderSide::Buy => level.0 < best.0,
+                OrderSide::Sell => level.0 > best.0,
+            };
+            if better { level } else { best }
+        });
+    Ok(best.map(|(price, size)| TopOfBook { price, size }))
+}
+
+/// Apply `split_by_liquidity` to every order using live order book data.
+///
+/// No-op unless `params.liquidity_split_ratio` is set. Orders whose book can't be
+/// fetched are kept as-is.
+pub async fn split_for_liquidity(
+    ctx: &ClobContext,
+    orders: Vec<SimulatedOrder>,
+    params: &ExecutorParams,
+    min_buy_usd: f64,
+) -> Vec<SimulatedOrder> {
+    let Some(ratio) = params.liquidity_split_ratio else {
+        return orders;
+    };
+
+    let mut out = Vec::with_capacity(orders.len());
+    for order in orders {
+        match fetch_top_of_book(ctx, &order).await {
+            Ok(Some(top)) => {
+                // Without the rules, any remainder is posted on its own
+                let rules = ctx.market_rules.get(&ctx.client, &order.market.asset);
+                let min_shares = match rules.await {
+                    Ok(rules) => money::from_f64(rules.min_order_size),
+                    Err(_) => Decimal::ZERO,
+                };
+                out.extend(split_by_liquidity(order, top, ratio, min_buy_usd, min_shares));
+            }
+            Ok(None) => out.push(order),
+            Err(e) => {
+                warn!(
+                    "Failed to fetch order book for \"{}\": {e} — sending order unsplit",
+                    order.market.title
+                );
+                out.push(order);
+            }
+        }
+    }
+    out
+}
+
+/// The taker fee rate of `token_id`, in bps, paced by the CLOB rate limiter.
+/// Assumes no fee (with a warning) when the query fails.
+pub async fn fee_rate_bps<S: State>(clob: &ClobClient<S>, token_id: &str) -> u32 {
+    match fetch_fee_rate_bps(clob, token_id).await {
+        Ok(rate) => rate,
+        Err(e) => {
+            warn!("{e:#} — assuming no fee");
+            0
+        }
+    }
+}
+
+/// Shrink or drop the buys the USDC balance can't cover once taker fees are
+/// added, so a fully deployed budget doesn't have its last buys rejected.
+///
+/// Orders are kept as-is when the balance can't be read (the balance guard in
+/// `execute_orders` then decides).
+pub async fn fit_to_balance(
+    ctx: &ClobContext,
+    orders: Vec<SimulatedOrder>,
+    params: &ExecutorParams,
+    min_buy_usd: f64,
+) -> Vec<SimulatedOrder> {
+    if !orders.iter().any(|o| o.side == OrderSide::Buy) {
+        return orders;
+    }
+    let balance = match check_balance(ctx).await {
+        Ok(balance) => balance,
+        Err(e) => {
+            warn!("Failed to check balance: {e} — not fitting buys to it");
+            return orders;
+        }
+    };
+    fit_to_funds(ctx, orders, money::from_f64(balance), params, min_buy_usd).await
+}
+
+/// `fit_to_balance` against `funds` instead of the wallet's USDC balance, as
+/// the shadow twin fits its buys to its own cash.
+pub async fn fit_to_funds(
+    ctx: &ClobContext,
+    orders: Vec<SimulatedOrder>,
+    funds: Decimal,
+    params: &ExecutorParams,
+    min_buy_usd: f64,
+) -> Vec<SimulatedOrder> {
+    let mut fee_rates = HashMap::new();
+    for order in &orders {
+        let asset = &order.market.asset;
+        if !fee_rates.contains_key(asset) {
+            fee_rates.insert(asset.clone(), fee_rate_bps(&ctx.client, asset).await);
+        }
+    }
+    fit_buys(orders, funds, &fee_rates, params.max_limit_offset(), min_buy_usd)
+}
+
+/// Fit buys to `balance` at their limit prices plus taker fees. Sells come
+/// first and are assumed to fill, funding the buys after them. A buy that
+/// doesn't fit is cut to what does, or dropped below `min_buy_usd`.
+fn fit_buys(
+    orders: Vec<SimulatedOrder>,
+    balance: Decimal,
+    fee_rates: &HashMap<String, u32>,
+    max_chase: f64,
+    min_buy_usd: f64,
+) -> Vec<SimulatedOrder> {
+    let min_buy_usd = money::from_f64(min_buy_usd);
+    let mut available = balance;
+    let mut fitted = Vec::with_capacity(orders.len());
+    for mut order in orders {
+        let rate = fee_rates.get(&order.market.asset).copied().unwrap_or(0);
+        let limit = money::from_f64(limit_price(&order, max_chase));
+        let fee_per_share = money::taker_fee_usd(rate, Decimal::ONE, limit);
+        let per_share = limit + fee_per_share;
+        match order.side {
+            OrderSide::Sell => available += order.shares * (limit - fee_per_share),
+            OrderSide::Buy if order.shares * per_share > available => {
+                let fits = money::ratio(available.max(Decimal::ZERO), per_share);
+                let shares = money::round_toward_zero(fits.unwrap_or_default(), SHARE_INCREMENT);
+                if shares * order.price < min_buy_usd {
+                    warn!(
+                        "Dropping buy of \"{}\" ({}): ${available:.2} left can't cover it with fees",
+                        order.market.title, order.market.outcome
+                    );
+                    continue;
+                }
+                info!(
+                    "Cutting buy of \"{}\" ({}) from {:.2} to {shares:.2} shares to fit ${available:.2} with fees",
+                    order.market.title, order.market.outcome, order.shares
+                );
+                order.shares = shares;
+                order.cost_usd = shares * order.price;
+                available -= shares * per_share;
+            }
+            OrderSide::Buy => available -= order.shares * per_share,
+        }
+        fitted.push(order);
+    }
+    fitted
+}
+
 /// Execute a list of simulated orders on the CLOB, returning results for each.
 ///
 /// Orders are processed sequentially (sells first, then buys — matching engine output order).
-/// A balance guard skips all buys if the account has < $1 USDC.
+/// A balance guard skips all buys if the account has less than `params.min_balance_usd` USDC.
 pub async fn execute_orders(
     ctx: &ClobContext,
     orders: &[SimulatedOrder],
+    params: &ExecutorParams,
 ) -> Vec<ExecutionResult> {
     let mut results = Vec::with_capacity(orders.len());
 
@@ -100,8 +538,11 @@ pub async fn execute_orders(
         match check_balance(ctx).await {
             Ok(balance) => {
                 info!("USDC balance: ${balance:.2}");
-                if balance < 1.0 {
-                    warn!("Balance ${balance:.2} < $1.00 — skipping all buy orders");
+                if balance < params.min_balance_usd {
+                    warn!(
+                        "Balance ${balance:.2} < ${:.2} — skipping all buy orders",
+                        params.min_balance_usd
+                    );
                     skip_buys = true;
                 }
             }
@@ -112,68 +553,805 @@ pub async fn execute_orders(
         }
     }
 
+    let mut guard = FailureGuard::new(params.max_consecutive_failures);
     for (idx, order) in orders.iter().enumerate() {
+        if guard.tripped() {
+            results.push(ExecutionResult {
+                order_index: idx,
+                status: ExecutionStatus::Skipped,
+                order_id: String::new(),
+                filled_shares: Decimal::ZERO,
+                filled_cost_usd: Decimal::ZERO,
+                error_msg: Some("executor halted after consecutive failures".into()),
+            });
+            continue;
+        }
+
         // Skip buys if balance guard triggered
         if order.side == OrderSide::Buy && skip_buys {
             results.push(ExecutionResult {
                 order_index: idx,
                 status: ExecutionStatus::Skipped,
                 order_id: String::new(),
-                filled_shares: 0.0,
-                filled_cost_usd: 0.0,
+                filled_shares: Decimal::ZERO,
+                filled_cost_usd: Decimal::ZERO,
                 error_msg: Some("insufficient balance".into()),
             });
             continue;
         }
 
-        let result = execute_single_order(ctx, idx, order).await;
+        let mut result = execute_single_order(ctx, idx, order, params).await;
+        // Fills reported right after posting took liquidity: add the estimated
+        // taker fee (resting remainders fill later as maker, fee-free)
+        if let Some(price) = money::ratio(result.filled_cost_usd, result.filled_shares) {
+            let rate = fee_rate_bps(&ctx.client, &order.market.asset).await;
+            let fee = money::taker_fee_usd(rate, result.filled_shares, price);
+            match order.side {
+                OrderSide::Buy => result.filled_cost_usd += fee,
+                OrderSide::Sell => result.filled_cost_usd -= fee,
+            }
+        }
+        if guard.record(result.status) {
+            let message = format!(
+                "{} consecutive order failures (last: {}) — skipping the remaining {} order(s) this cycle",
+                guard.streak,
+                result.error_msg.as_deref().unwrap_or("unknown error"),
+                orders.len() - idx - 1,
+            );
+            error!("Executor halted: {message}");
+            reporter::report_alert(&AlertEvent {
+                timestamp: crate::clock::now().to_rfc3339(),
+                severity: AlertSeverity::Critical,
+                kind: AlertKind::ExecutorHalted,
+                message,
+            });
+        }
         results.push(result);
+    }
 
-        // Delay between orders to avoid rate limits (except after the last one)
-        if idx + 1 < orders.len() {
-            tokio::time::sleep(INTER_ORDER_DELAY).await;
-        }
+    results
+}
+
+/// Client order IDs for a batch planned in order cycle `cycle`: a hash of the
+/// cycle, token, side and shares of each order, and of how many identical
+/// orders precede it (split orders repeat). Planning the same batch again
+/// before the cycle is settled, as after a crash, gives the same IDs.
+pub fn client_order_ids(cycle: u64, orders: &[SimulatedOrder]) -> Vec<String> {
+    let mut seen: HashMap<String, u32> = HashMap::new();
+    orders
+        .iter()
+        .map(|order| {
+            let key = format!(
+                "{cycle}|{}|{}|{:.2}",
+                order.market.asset,
+                order.side.label(),
+                order.shares
+            );
+            let repeat = seen.entry(key.clone()).or_default();
+            let digest = Sha256::digest(format!("{key}|{repeat}").as_bytes());
+            *repeat += 1;
+            digest.iter().take(8).map(|b| format!("{b:02x}")).collect()
+        })
+        .collect()
+}
+
+/// Post `orders` and book their results without risking a double order if the
+/// process dies in between.
+///
+/// The batch is recorded as `Mutation::SubmitOrders`, keyed by client order ID,
+/// before anything is posted, and booking its results settles it. Submissions
+/// a crash left pending are reconciled first (`reconcile_pending_orders`); an
+/// order whose ID is still pending after that was already submitted, so it is
+/// skipped rather than posted again. Returns one result per order in `orders`.
+pub async fn submit_orders(
+    ctx: &ClobContext,
+    state: &mut TradingState,
+    orders: &[SimulatedOrder],
+    params: &ExecutorParams,
+) -> Vec<ExecutionResult> {
+    reconcile_pending_orders(ctx, state).await;
+
+    let cycle = state.order_cycle;
+    let ids = client_order_ids(cycle, orders);
+    let (fresh, resubmitted): (Vec<usize>, Vec<usize>) =
+        (0..orders.len()).partition(|&i| !state.pending_orders.contains_key(&ids[i]));
+    let mut results: Vec<ExecutionResult> = (0..orders.len())
+        .map(|i| ExecutionResult {
+            order_index: i,
+            status: ExecutionStatus::Skipped,
+            order_id: String::new(),
+            filled_shares: Decimal::ZERO,
+            filled_cost_usd: Decimal::ZERO,
+            error_msg: Some(format!("already submitted as {} before a restart", ids[i])),
+        })
+        .collect();
+    for &i in &resubmitted {
+        warn!(
+            "Skipping {} {:.2} shares of \"{}\" — already submitted as {} and not yet reconciled",
+            orders[i].side.label(),
+            orders[i].shares,
+            orders[i].market.title,
+            ids[i]
+        );
+    }
+    if fresh.is_empty() {
+        return results;
     }
 
+    let fresh_orders: Vec<SimulatedOrder> = fresh.iter().map(|&i| orders[i].clone()).collect();
+    let fresh_ids: Vec<String> = fresh.iter().map(|&i| ids[i].clone()).collect();
+    let submitted_at = crate::clock::now().timestamp();
+    let pending = fresh_ids.iter().zip(&fresh_orders).map(|(id, order)| {
+        let pending = PendingOrder {
+            order: order.clone(),
+            submitted_at,
+        };
+        (id.clone(), pending)
+    });
+    state.commit(Mutation::SubmitOrders {
+        cycle,
+        orders: pending.collect(),
+    });
+    let executed = execute_orders(ctx, &fresh_orders, params).await;
+    state.commit(Mutation::ApplyExecutionResults {
+        orders: fresh_orders,
+        results: executed.clone(),
+        client_order_ids: fresh_ids,
+    });
+    for (result, &i) in executed.into_iter().zip(&fresh) {
+        results[i] = ExecutionResult {
+            order_index: i,
+            ..result
+        };
+    }
     results
 }
 
+/// One of our orders open on the CLOB, as reconciliation matches it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OpenOrder {
+    pub order_id: String,
+    /// Condition ID of the order's market.
+    pub market: String,
+    pub asset: String,
+    pub outcome: String,
+    pub side: OrderSide,
+    pub original_size: Decimal,
+    pub size_matched: Decimal,
+    pub price: Decimal,
+    /// Unix seconds.
+    pub created_at: i64,
+}
+
+/// One of our fills on the CLOB, as reconciliation matches it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OwnFill {
+    pub asset: String,
+    pub side: OrderSide,
+    pub shares: Decimal,
+    pub price: Decimal,
+    /// Unix seconds.
+    pub matched_at: i64,
+}
+
+/// Allowance for clock skew between our submission time and the CLOB's.
+const RECONCILE_SLACK_SECS: i64 = 5;
+
+/// Shares a posted order may fall short of its planned size (lot rounding).
+const RECONCILE_SIZE_TOLERANCE: Decimal = SHARE_INCREMENT;
+
+/// Settle the submissions a crash left pending (`TradingState::pending_orders`)
+/// against our orders and fills on the CLOB, before stale orders are
+/// cancelled. A lookup error leaves them pending for the next batch.
+pub async fn reconcile_pending_orders(ctx: &ClobContext, state: &mut TradingState) {
+    if state.pending_orders.is_empty() {
+        return;
+    }
+    info!(
+        "Reconciling {} order(s) submitted before a restart...",
+        state.pending_orders.len()
+    );
+    let (ids, pending): (Vec<String>, Vec<PendingOrder>) = state
+        .pending_orders
+        .iter()
+        .map(|(id, p)| (id.clone(), p.clone()))
+        .unzip();
+    let mut since: HashMap<&str, i64> = HashMap::new();
+    for p in &pending {
+        let asset_since = since.entry(&p.order.market.asset).or_insert(p.submitted_at);
+        *asset_since = (*asset_since).min(p.submitted_at);
+    }
+    let mut open = Vec::new();
+    let mut fills = Vec::new();
+    for (asset, since) in since {
+        let lookup = async {
+            let request = OrdersRequest::builder().asset_id(asset).build();
+            open.extend(fetch_open_orders(ctx, &request).await?);
+            let after = since - RECONCILE_SLACK_SECS;
+            fills.extend(fetch_own_fills(ctx, asset, after).await?);
+            polymarket_client_sdk::Result::Ok(())
+        };
+        if let Err(e) = lookup.await {
+            warn!("Failed to reconcile pending orders: {e} — keeping them pending");
+            return;
+        }
+    }
+    let results = reconcile_orders(&pending, open, fills);
+    for (p, result) in pending.iter().zip(&results) {
+        info!(
+            "Reconciled {} {:.2} shares of \"{}\": {:?}{}",
+            p.order.side.label(),
+            p.order.shares,
+            p.order.market.title,
+            result.status,
+            result
+                .error_msg
+                .as_deref()
+                .map(|e| format!(" ({e})"))
+                .unwrap_or_default()
+        );
+    }
+    state.commit(Mutation::ApplyExecutionResults {
+        orders: pending.into_iter().map(|p| p.order).collect(),
+        results,
+        client_order_ids: ids,
+    });
+}
+
+/// Execution results for `pending` submissions, from our `open` orders and
+/// `fills` on their tokens.
+///
+/// A submission matches an open order of its token and side, placed since it
+/// was submitted, of its size (less lot rounding): it is tracked as resting,
+/// with what that order already matched. Otherwise the fills of its token and
+/// side since then are booked to it, up to its size. With neither it never
+/// reached the CLOB and fails. Each open order and fill is claimed once.
+pub fn reconcile_orders(
+    pending: &[PendingOrder],
+    mut open: Vec<OpenOrder>,
+    mut fills: Vec<OwnFill>,
+) -> Vec<ExecutionResult> {
+    let mut results: Vec<Option<ExecutionResult>> = vec![None; pending.len()];
+    // Open orders first, so their matched shares aren't booked to others
+    for (i, p) in pending.iter().enumerate() {
+        let order = &p.order;
+        let since = p.submitted_at - RECONCILE_SLACK_SECS;
+        let Some(pos) = open.iter().position(|o| {
+            o.asset == order.market.asset
+                && o.side == order.side
+                && o.created_at >= since
+                && o.original_size <= order.shares
+                && o.original_size >= order.shares - RECONCILE_SIZE_TOLERANCE
+        }) else {
+            continue;
+        };
+        let found = open.remove(pos);
+        claim_fills(&mut fills, order, since, found.size_matched);
+        let status = if found.size_matched > Decimal::ZERO {
+            ExecutionStatus::PartialFill
+        } else {
+            ExecutionStatus::Resting
+        };
+        results[i] = Some(ExecutionResult {
+            order_index: i,
+            status,
+            order_id: found.order_id,
+            filled_shares: found.size_matched,
+            filled_cost_usd: found.size_matched * found.price,
+            error_msg: None,
+        });
+    }
+    pending
+        .iter()
+        .zip(results)
+        .enumerate()
+        .map(|(i, (p, result))| {
+            if let Some(result) = result {
+                return result;
+            }
+            let order = &p.order;
+            let since = p.submitted_at - RECONCILE_SLACK_SECS;
+            let (shares, cost) = claim_fills(&mut fills, order, since, order.shares);
+            let (status, error_msg) = if shares <= Decimal::ZERO {
+                let msg = "not found on the CLOB after restart".to_string();
+                (ExecutionStatus::Failed, Some(msg))
+            } else if shares >= order.shares - RECONCILE_SIZE_TOLERANCE {
+                (ExecutionStatus::Filled, None)
+            } else {
+                (ExecutionStatus::PartialFill, None)
+            };
+            ExecutionResult {
+                order_index: i,
+                status,
+                order_id: String::new(),
+                filled_shares: shares,
+                filled_cost_usd: cost,
+                error_msg,
+            }
+        })
+        .collect()
+}
+
+/// Take up to `shares` from the `fills` of `order`'s token and side since
+/// `since`, returning the shares and cost taken.
+fn claim_fills(
+    fills: &mut Vec<OwnFill>,
+    order: &SimulatedOrder,
+    since: i64,
+    shares: Decimal,
+) -> (Decimal, Decimal) {
+    let mut taken = Decimal::ZERO;
+    let mut cost = Decimal::ZERO;
+    for fill in fills.iter_mut() {
+        if taken >= shares {
+            break;
+        }
+        if fill.asset != order.market.asset || fill.side != order.side || fill.matched_at < since
+        {
+            continue;
+        }
+        let take = fill.shares.min(shares - taken);
+        fill.shares -= take;
+        taken += take;
+        cost += take * fill.price;
+    }
+    fills.retain(|fill| fill.shares > Decimal::ZERO);
+    (taken, cost)
+}
+
+/// Cursor of the page after the last one (base64 of `-1`).
+const END_CURSOR: &str = "LTE=";
+
+/// Our open orders matching `request`, every page.
+async fn fetch_open_orders(
+    ctx: &ClobContext,
+    request: &OrdersRequest,
+) -> polymarket_client_sdk::Result<Vec<OpenOrder>> {
+    let mut open = Vec::new();
+    let mut cursor = None;
+    loop {
+        let page = retry::with_retry(RetryOperation::OrderStatus, "GET /data/orders", || {
+            let cursor = cursor.clone();
+            async move {
+                ratelimit::acquire_clob("clob:GET /data/orders").await;
+                ctx.client.orders(request, cursor).await
+            }
+        })
+        .await?;
+        open.extend(page.data.into_iter().filter_map(|o| {
+            Some(OpenOrder {
+                side: from_clob_side(o.side)?,
+                order_id: o.id,
+                market: o.market,
+                asset: o.asset_id,
+                outcome: o.outcome,
+                original_size: o.original_size,
+                size_matched: o.size_matched,
+                price: o.price,
+                created_at: o.created_at.timestamp(),
+            })
+        }));
+        if page.next_cursor.is_empty() || page.next_cursor == END_CURSOR {
+            return Ok(open);
+        }
+        cursor = Some(page.next_cursor);
+    }
+}
+
+/// Our fills on `token_id` since `after` (unix seconds, the first page): the
+/// taker side of trades we took, our maker orders in trades we made.
+async fn fetch_own_fills(
+    ctx: &ClobContext,
+    token_id: &str,
+    after: i64,
+) -> polymarket_client_sdk::Result<Vec<OwnFill>> {
+    let request = ClobTradesRequest::builder()
+        .asset_id(token_id)
+        .after(after)
+        .build();
+    let page = retry::with_retry(RetryOperation::OrderStatus, "GET /data/trades", || async {
+        ratelimit::acquire_clob("clob:GET /data/trades").await;
+        ctx.client.trades(&request, None).await
+    })
+    .await?;
+    let mut fills = Vec::new();
+    for trade in page.data {
+        let matched_at = trade.match_time.timestamp();
+        if trade.trader_side == TraderSide::Taker {
+            if let Some(side) = from_clob_side(trade.side) {
+                fills.push(OwnFill {
+                    asset: trade.asset_id,
+                    side,
+                    shares: trade.size,
+                    price: trade.price,
+                    matched_at,
+                });
+            }
+            continue;
+        }
+        for maker in trade.maker_orders {
+            if maker.maker_address != ctx.safe {
+                continue;
+            }
+            if let Some(side) = from_clob_side(maker.side) {
+                fills.push(OwnFill {
+                    asset: maker.asset_id,
+                    side,
+                    shares: maker.matched_amount,
+                    price: maker.price,
+                    matched_at,
+                });
+            }
+        }
+    }
+    Ok(fills)
+}
+
+/// Our open orders at startup, sorted for `StartupOrders::Adopt`.
+#[derive(Debug, Default, PartialEq)]
+pub struct StartupOrderTriage {
+    /// In our markets: tracked as resting orders.
+    pub adopt: Vec<OpenOrder>,
+    /// In our markets but older than `resting_ttl_secs`: cancelled.
+    pub stale: Vec<OpenOrder>,
+    /// In other markets: left alone.
+    pub foreign: usize,
+}
+
+/// Sort our `open` orders for adoption. An order is in our markets when we
+/// hold or track orders on its token, or its market is one of `markets` (the
+/// copied condition IDs). Orders already tracked are skipped.
+pub fn triage_open_orders(
+    open: Vec<OpenOrder>,
+    state: &TradingState,
+    markets: &[String],
+    ttl_secs: Option<u64>,
+    now: i64,
+) -> StartupOrderTriage {
+    let mut triage = StartupOrderTriage::default();
+    let tracked = |asset: &str| state.resting_orders.iter().any(|r| r.asset == asset);
+    for order in open {
+        if state.resting_orders.iter().any(|r| r.order_id == order.order_id) {
+            continue;
+        }
+        let ours = state.holdings.contains_key(&order.asset)
+            || tracked(&order.asset)
+            || markets.contains(&order.market);
+        if !ours {
+            triage.foreign += 1;
+        } else if ttl_secs.is_some_and(|ttl| now - order.created_at > ttl as i64) {
+            triage.stale.push(order);
+        } else {
+            triage.adopt.push(order);
+        }
+    }
+    triage
+}
+
+/// Take over our open CLOB orders at startup instead of cancelling them all
+/// (`StartupOrders::Adopt`): orders in our markets are tracked as resting
+/// orders, the stale ones among them cancelled, and orders in other markets,
+/// such as another strategy's on the same wallet, left alone.
+pub async fn adopt_open_orders(
+    ctx: &ClobContext,
+    state: &mut TradingState,
+    params: &ExecutorParams,
+    filter: Option<&MarketFilter>,
+) {
+    info!("Adopting open orders from previous runs...");
+    let open = match fetch_open_orders(ctx, &OrdersRequest::default()).await {
+        Ok(open) => open,
+        Err(e) => {
+            warn!("Failed to fetch open orders: {e} — none adopted");
+            return;
+        }
+    };
+    let markets = match filter {
+        Some(MarketFilter::Markets(ids)) => ids.as_slice(),
+        _ => &[],
+    };
+    let now = crate::clock::now().timestamp();
+    let triage = triage_open_orders(open, state, markets, params.resting_ttl_secs, now);
+    if triage.foreign > 0 {
+        info!(
+            "Leaving {} open order(s) in other markets alone",
+            triage.foreign
+        );
+    }
+
+    if !triage.stale.is_empty() {
+        info!("Cancelling {} stale order(s)...", triage.stale.len());
+        let ids: Vec<&str> = triage.stale.iter().map(|o| o.order_id.as_str()).collect();
+        ratelimit::acquire_clob("clob:DELETE /orders").await;
+        match ctx.client.cancel_orders(&ids).await {
+            Ok(resp) => {
+                for (id, err) in &resp.not_canceled {
+                    warn!("Failed to cancel stale order {id}: {err}");
+                }
+            }
+            Err(e) => warn!("Failed to cancel stale orders: {e}"),
+        }
+    }
+
+    if triage.adopt.is_empty() {
+        return;
+    }
+    let mut orders = Vec::with_capacity(triage.adopt.len());
+    let mut results = Vec::with_capacity(triage.adopt.len());
+    for (i, open) in triage.adopt.into_iter().enumerate() {
+        let shares = open.original_size - open.size_matched;
+        let holding = state.holdings.get(&open.asset);
+        let market = MarketPosition {
+            condition_id: open.market.clone(),
+            asset: open.asset.clone(),
+            title: holding.map_or_else(|| open.market.clone(), |h| h.title.clone()),
+            outcome: open.outcome.clone(),
+            outcome_index: 0,
+            event_slug: String::new(),
+            tags: state.asset_tags.get(&open.asset).cloned().unwrap_or_default(),
+            opposite_asset: holding.map(|h| h.opposite_asset.clone()).unwrap_or_default(),
+        };
+        info!(
+            "Adopting {} {shares:.2} shares of \"{}\" at ${:.4} ({})",
+            open.side.label(),
+            market.title,
+            open.price,
+            open.order_id
+        );
+        orders.push(SimulatedOrder {
+            market,
+            side: open.side,
+            shares,
+            price: open.price,
+            cost_usd: shares * open.price,
+        });
+        results.push(ExecutionResult {
+            order_index: i,
+            status: ExecutionStatus::Resting,
+            order_id: open.order_id,
+            filled_shares: Decimal::ZERO,
+            filled_cost_usd: Decimal::ZERO,
+            error_msg: None,
+        });
+    }
+    state.commit(Mutation::ApplyExecutionResults {
+        orders,
+        results,
+        client_order_ids: Vec::new(),
+    });
+}
+
+/// Counts consecutive failed orders within one batch.
+struct FailureGuard {
+    limit: u32,
+    streak: u32,
+}
+
+impl FailureGuard {
+    /// `limit = 0` never trips.
+    fn new(limit: u32) -> Self {
+        Self { limit, streak: 0 }
+    }
+
+    fn tripped(&self) -> bool {
+        self.limit > 0 && self.streak >= self.limit
+    }
+
+    /// Record an order outcome; returns `true` when this failure trips the guard.
+    fn record(&mut self, status: ExecutionStatus) -> bool {
+        if status == ExecutionStatus::Failed {
+            self.streak += 1;
+            self.limit > 0 && self.streak == self.limit
+        } else {
+            self.streak = 0;
+            false
+        }
+    }
+}
+
+/// Cut orders worth more than `[executor.twap] min_order_usd` to one slice
+/// per poll cycle, at least `interval_secs` apart (live only).
+///
+/// The first such order on an asset starts a schedule of `slices` equal
+/// slices in `state.twap_schedules`; each later cycle posts the next slice of
+/// whatever the engine still plans on that asset, and the last slice takes all
+/// of it. Orders on a scheduled asset are held back until the interval has
+/// passed and the previous slice no longer rests on the book (it hasn't
+/// refilled). Each slice is an ordinary order, booked and tracked as resting
+/// on its own. Schedules whose asset is no longer ordered once due are dropped.
+pub fn schedule_twap(
+    orders: Vec<SimulatedOrder>,
+    state: &mut TradingState,
+    params: &ExecutorParams,
+    now_ms: i64,
+) -> Vec<SimulatedOrder> {
+    let Some(twap) = &params.twap else {
+        return orders;
+    };
+    let interval_ms = (twap.interval_secs as i64).saturating_mul(1000);
+    let mut ordered = Vec::new();
+    let mut sliced = Vec::with_capacity(orders.len());
+    for order in orders {
+        let asset = order.market.asset.clone();
+        ordered.push(asset.clone());
+        let schedule = state
+            .twap_schedules
+            .get(&asset)
+            .filter(|s| s.side == order.side)
+            .cloned();
+        let Some(mut schedule) = schedule else {
+            if state.twap_schedules.contains_key(&asset) {
+                state.commit(Mutation::Twap {
+                    asset: asset.clone(),
+                    schedule: None,
+                });
+            }
+            let slices = twap.slices.max(1);
+            let shares = money::round_toward_zero(
+                order.shares / Decimal::from(slices),
+                SHARE_INCREMENT,
+            );
+            if order.cost_usd <= money::from_f64(twap.min_order_usd)
+                || slices < 2
+                || shares <= Decimal::ZERO
+            {
+                sliced.push(order);
+                continue;
+            }
+            info!(
+                "TWAP {} of \"{}\" ({:.2} shares): {slices} slices of {shares:.2}, {}s apart",
+                order.side.label(),
+                order.market.title,
+                order.shares,
+                twap.interval_secs,
+            );
+            state.commit(Mutation::Twap {
+                asset,
+                schedule: Some(TwapSchedule {
+                    side: order.side,
+                    slice_shares: shares,
+                    remaining: slices - 1,
+                    next_at_ms: now_ms + interval_ms,
+                }),
+            });
+            sliced.push(SimulatedOrder {
+                shares,
+                cost_usd: shares * order.price,
+                ..order
+            });
+            continue;
+        };
+        let resting = state
+            .resting_orders
+            .iter()
+            .any(|r| r.asset == asset && r.side == order.side);
+        if now_ms < schedule.next_at_ms || resting {
+            info!(
+                "TWAP {} of \"{}\": holding {:.2} shares for the next slice",
+                order.side.label(),
+                order.market.title,
+                order.shares,
+            );
+            continue;
+        }
+        schedule.remaining -= 1;
+        schedule.next_at_ms = now_ms + interval_ms;
+        let (shares, schedule) = if schedule.remaining == 0 {
+            (order.shares, None)
+        } else {
+            (order.shares.min(schedule.slice_shares), Some(schedule))
+        };
+        state.commit(Mutation::Twap { asset, schedule });
+        sliced.push(SimulatedOrder {
+            shares,
+            cost_usd: shares * order.price,
+            ..order
+        });
+    }
+    let mut dropped: Vec<String> = state
+        .twap_schedules
+        .iter()
+        .filter(|(asset, s)| !ordered.contains(asset) && now_ms >= s.next_at_ms)
+        .map(|(asset, _)| asset.clone())
+        .collect();
+    dropped.sort();
+    for asset in dropped {
+        state.commit(Mutation::Twap {
+            asset,
+            schedule: None,
+        });
+    }
+    sliced
+}
+
 /// Execute a single order with retry logic.
 async fn execute_single_order(
     ctx: &ClobContext,
     index: usize,
     order: &SimulatedOrder,
+    params: &ExecutorParams,
 ) -> ExecutionResult {
-    let price = match f64_to_price(order.price) {
+    // Fills without a reported price are assumed at the limit (worst case)
+    let limit = match execution_price(ctx, order, params).await {
+        Ok(limit) => limit,
+        Err(reason) => {
+            warn!(
+                "Skipping {} of \"{}\" ({}): {reason}",
+                order.side.label(),
+                order.market.title,
+                order.market.outcome
+            );
+            return ExecutionResult {
+                order_index: index,
+                status: ExecutionStatus::Skipped,
+                order_id: String::new(),
+                filled_shares: Decimal::ZERO,
+                filled_cost_usd: Decimal::ZERO,
+                error_msg: Some(reason),
+            };
+        }
+    };
+    let token_id = &order.market.asset;
+
+    // Round onto the token's tick grid and skip what the CLOB would reject,
+    // rather than posting it and counting a failure
+    let mut price_decimals = 2;
+    let limit = match ctx.market_rules.get(&ctx.client, token_id).await {
+        Ok(rules) => {
+            let rounded = rules.round_price(limit, order.side);
+            if let Some(reason) = rules.violation(money::to_f64(order.shares), rounded) {
+                warn!(
+                    "Skipping {} of \"{}\" ({}): {reason}",
+                    order.side.label(),
+                    order.market.title,
+                    order.market.outcome
+                );
+                return ExecutionResult {
+                    order_index: index,
+                    status: ExecutionStatus::Skipped,
+                    order_id: String::new(),
+                    filled_shares: Decimal::ZERO,
+                    filled_cost_usd: Decimal::ZERO,
+                    error_msg: Some(reason),
+                };
+            }
+            price_decimals = rules.price_decimals();
+            rounded
+        }
+        Err(e) => {
+            warn!("{e:#} — posting without pre-trade checks");
+            limit
+        }
+    };
+    let price = match f64_to_price(limit, price_decimals) {
         Ok(p) => p,
         Err(e) => {
             return ExecutionResult {
                 order_index: index,
                 status: ExecutionStatus::Failed,
                 order_id: String::new(),
-                filled_shares: 0.0,
-                filled_cost_usd: 0.0,
+                filled_shares: Decimal::ZERO,
+                filled_cost_usd: Decimal::ZERO,
                 error_msg: Some(format!("price conversion: {e}")),
             };
         }
     };
 
-    let shares = match f64_to_shares(order.shares) {
+    let shares = match lot_shares(order.shares) {
         Ok(s) => s,
         Err(e) => {
             return ExecutionResult {
                 order_index: index,
                 status: ExecutionStatus::Failed,
                 order_id: String::new(),
-                filled_shares: 0.0,
-                filled_cost_usd: 0.0,
+                filled_shares: Decimal::ZERO,
+                filled_cost_usd: Decimal::ZERO,
                 error_msg: Some(format!("shares conversion: {e}")),
             };
         }
     };
 
     let side = to_clob_side(order.side);
-    let token_id = &order.market.asset;
 
     info!(
         "Placing {} order: {} shares @ ${} — \"{}\" ({})",
@@ -185,16 +1363,19 @@ async fn execute_single_order(
     );
 
     // Build, sign, and post with retry for transient errors
-    let post_resp = match build_sign_post_with_retry(ctx, token_id, price, shares, side).await {
+    let posted = build_sign_post_with_retry(ctx, token_id, price, shares, side).await;
+    let post_resp = match posted {
         Ok(resp) => resp,
         Err(e) => {
+            let msg = format!("{e}");
+            forget_stale_tick(ctx, token_id, &msg);
             return ExecutionResult {
                 order_index: index,
                 status: ExecutionStatus::Failed,
                 order_id: String::new(),
-                filled_shares: 0.0,
-                filled_cost_usd: 0.0,
-                error_msg: Some(format!("{e}")),
+                filled_shares: Decimal::ZERO,
+                filled_cost_usd: Decimal::ZERO,
+                error_msg: Some(msg),
             };
         }
     };
@@ -204,41 +1385,58 @@ async fn execute_single_order(
             .error_msg
             .unwrap_or_else(|| format!("status: {}", post_resp.status));
         warn!("Order post failed: {msg}");
+        forget_stale_tick(ctx, token_id, &msg);
         return ExecutionResult {
             order_index: index,
             status: ExecutionStatus::Failed,
             order_id: post_resp.order_id,
-            filled_shares: 0.0,
-            filled_cost_usd: 0.0,
+            filled_shares: Decimal::ZERO,
+            filled_cost_usd: Decimal::ZERO,
             error_msg: Some(msg),
         };
     }
 
     let order_id = post_resp.order_id.clone();
+    if let Some(strategy_id) = &params.strategy_id {
+        info!("Order {order_id} posted for strategy {strategy_id}");
+    }
 
     // If already matched at post time, return immediately
     if post_resp.status == OrderStatusType::Matched {
-        let filled_shares = shares.to_f64().unwrap_or(order.shares);
-        let filled_cost = filled_shares * order.price;
-        info!("Order {order_id} filled immediately ({filled_shares} shares, ${filled_cost:.2})");
+        let filled_cost = shares * price;
+        info!("Order {order_id} filled immediately ({shares} shares, ${filled_cost:.2})");
         return ExecutionResult {
             order_index: index,
             status: ExecutionStatus::Filled,
             order_id,
-            filled_shares,
+            filled_shares: shares,
             filled_cost_usd: filled_cost,
             error_msg: None,
         };
     }
 
-    // Wait and check fill status
-    tokio::time::sleep(FILL_CHECK_DELAY).await;
+    // With the user channel up, wait for its events instead of sleeping and
+    // querying; an order it says nothing about is resting, not filled
+    match ctx.fills.as_ref().filter(|fills| fills.is_connected()) {
+        Some(fills) => {
+            let fill = fills.wait(&order_id, params.fill_check_delay()).await;
+            if fills.is_connected() {
+                let result = result_from_fill(index, order_id, fill, price);
+                if result.status != ExecutionStatus::Resting {
+                    fills.forget(&result.order_id);
+                }
+                return result;
+            }
+            // Dropped while waiting: fall back to querying
+        }
+        None => tokio::time::sleep(params.fill_check_delay()).await,
+    }
 
-    match ctx.client.order(&order_id).await {
+    match query_order(ctx, &order_id).await {
         Ok(status) => {
-            let size_matched = status.size_matched.to_f64().unwrap_or(0.0);
-            let original_size = status.original_size.to_f64().unwrap_or(order.shares);
-            let fill_price = status.price.to_f64().unwrap_or(order.price);
+            let size_matched = status.size_matched;
+            let original_size = status.original_size;
+            let fill_price = status.price;
 
             match status.status {
                 OrderStatusType::Matched => {
@@ -254,7 +1452,7 @@ async fn execute_single_order(
                     }
                 }
                 OrderStatusType::Live => {
-                    if size_matched > 0.0 {
+                    if size_matched > Decimal::ZERO {
                         let filled_cost = size_matched * fill_price;
                         info!(
                             "Order {order_id} partially filled ({size_matched}/{original_size} shares, ${filled_cost:.2})"
@@ -273,15 +1471,15 @@ async fn execute_single_order(
                             order_index: index,
                             status: ExecutionStatus::Resting,
                             order_id,
-                            filled_shares: 0.0,
-                            filled_cost_usd: 0.0,
+                            filled_shares: Decimal::ZERO,
+                            filled_cost_usd: Decimal::ZERO,
                             error_msg: None,
                         }
                     }
                 }
                 OrderStatusType::Canceled | OrderStatusType::Unmatched => {
                     let filled_cost = size_matched * fill_price;
-                    if size_matched > 0.0 {
+                    if size_matched > Decimal::ZERO {
                         info!(
                             "Order {order_id} cancelled with partial fill ({size_matched} shares, ${filled_cost:.2})"
                         );
@@ -299,8 +1497,8 @@ async fn execute_single_order(
                             order_index: index,
                             status: ExecutionStatus::Failed,
                             order_id,
-                            filled_shares: 0.0,
-                            filled_cost_usd: 0.0,
+                            filled_shares: Decimal::ZERO,
+                            filled_cost_usd: Decimal::ZERO,
                             error_msg: Some(format!("order {}", status.status)),
                         }
                     }
@@ -311,29 +1509,39 @@ async fn execute_single_order(
                         "Order {order_id} in unexpected status {} — assuming filled",
                         status.status
                     );
-                    let filled_shares = shares.to_f64().unwrap_or(order.shares);
-                    let filled_cost = filled_shares * order.price;
+                    let filled_cost = shares * price;
                     ExecutionResult {
                         order_index: index,
                         status: ExecutionStatus::Filled,
                         order_id,
-                        filled_shares,
+                        filled_shares: shares,
                         filled_cost_usd: filled_cost,
                         error_msg: None,
                     }
                 }
             }
         }
+        Err(e) if ctx.fills.is_some() => {
+            // The user channel will report the outcome once it reconnects
+            warn!("Failed to check order {order_id} status: {e} — tracking as resting");
+            ExecutionResult {
+                order_index: index,
+                status: ExecutionStatus::Resting,
+                order_id,
+                filled_shares: Decimal::ZERO,
+                filled_cost_usd: Decimal::ZERO,
+                error_msg: Some(format!("status check failed: {e}")),
+            }
+        }
         Err(e) => {
             // Status query failed but post succeeded — optimistic assumption
             warn!("Failed to check order {order_id} status: {e} — assuming filled");
-            let filled_shares = shares.to_f64().unwrap_or(order.shares);
-            let filled_cost = filled_shares * order.price;
+            let filled_cost = shares * price;
             ExecutionResult {
                 order_index: index,
                 status: ExecutionStatus::Filled,
                 order_id,
-                filled_shares,
+                filled_shares: shares,
                 filled_cost_usd: filled_cost,
                 error_msg: Some(format!("status check failed: {e}")),
             }
@@ -341,7 +1549,52 @@ async fn execute_single_order(
     }
 }
 
-/// Build, sign, and post a limit order with exponential backoff retry for transient errors.
+/// Execution result for a posted order from what the user channel reported
+/// within the fill check delay (`None`: nothing yet).
+fn result_from_fill(
+    index: usize,
+    order_id: String,
+    fill: Option<OrderFill>,
+    limit: Decimal,
+) -> ExecutionResult {
+    let (state, matched, fill_price) = match &fill {
+        Some(fill) => (fill.state, fill.matched(), fill.fill_price().unwrap_or(limit)),
+        None => (OrderState::Live, Decimal::ZERO, limit),
+    };
+    let filled_cost = matched * fill_price;
+    let (status, error_msg) = match state {
+        OrderState::Filled => {
+            info!("Order {order_id} fully filled ({matched} shares, ${filled_cost:.2})");
+            (ExecutionStatus::Filled, None)
+        }
+        _ if matched > Decimal::ZERO => {
+            let still = if state == OrderState::Live { "still live" } else { "cancelled" };
+            info!(
+                "Order {order_id} partially filled ({matched} shares, ${filled_cost:.2}), {still}"
+            );
+            (ExecutionStatus::PartialFill, None)
+        }
+        OrderState::Live => {
+            info!("Order {order_id} resting on book (no fills reported)");
+            (ExecutionStatus::Resting, None)
+        }
+        OrderState::Cancelled => {
+            warn!("Order {order_id} cancelled with no fills");
+            (ExecutionStatus::Failed, Some("order cancelled".to_string()))
+        }
+    };
+    ExecutionResult {
+        order_index: index,
+        status,
+        order_id,
+        filled_shares: matched,
+        filled_cost_usd: filled_cost,
+        error_msg,
+    }
+}
+
+/// Build, sign, and post a limit order, retrying under the `[retry.post_order]`
+/// policy.
 ///
 /// Re-builds and re-signs on each retry attempt since `SignedOrder` is not `Clone`.
 async fn build_sign_post_with_retry(
@@ -351,9 +1604,10 @@ async fn build_sign_post_with_retry(
     shares: Decimal,
     side: ClobSide,
 ) -> Result<polymarket_client_sdk::clob::types::response::PostOrderResponse> {
+    let policy = retry::policy(RetryOperation::PostOrder);
     let mut last_err: Option<anyhow::Error> = None;
 
-    for attempt in 0..MAX_RETRIES {
+    for attempt in 0..policy.max_attempts {
         let signable = ctx
             .client
             .limit_order()
@@ -371,16 +1625,18 @@ async fn build_sign_post_with_retry(
             .await
             .map_err(|e| anyhow::anyhow!("sign order: {e}"))?;
 
+        lease::check_fence()?;
+        ratelimit::acquire_clob("clob:POST /order").await;
         match ctx.client.post_order(signed).await {
             Ok(resp) => return Ok(resp),
             Err(e) => {
                 let err_str = e.to_string();
-                if is_transient_error(&err_str) && attempt + 1 < MAX_RETRIES {
-                    let delay = BASE_BACKOFF * 2u32.pow(attempt);
+                if retry::is_retryable(&policy, &err_str) && attempt + 1 < policy.max_attempts {
+                    let delay = retry::delay(&policy, attempt, retry::jitter_sample());
                     warn!(
                         "Transient error posting order (attempt {}/{}): {err_str} — retrying in {:?}",
                         attempt + 1,
-                        MAX_RETRIES,
+                        policy.max_attempts,
                         delay,
                     );
                     tokio::time::sleep(delay).await;
@@ -395,15 +1651,78 @@ async fn build_sign_post_with_retry(
     Err(last_err.unwrap_or_else(|| anyhow::anyhow!("retry exhausted")))
 }
 
+/// Resolve resting orders that the user channel reports filled or cancelled.
+///
+/// A partially filled order's remainder rests under the same order ID, so only
+/// the shares matched beyond those already applied count toward it.
+pub fn resolve_resting_from_fills(fills: &FillBook, state: &mut TradingState) {
+    let resting: Vec<(String, Decimal, Decimal)> = state
+        .resting_orders
+        .iter()
+        .map(|r| (r.order_id.clone(), r.shares, r.price))
+        .collect();
+
+    for (order_id, shares, price) in resting {
+        let Some(fill) = fills.get(&order_id) else {
+            continue;
+        };
+        if !fill.state.is_terminal() {
+            continue;
+        }
+        let applied = fill
+            .original_size
+            .map_or(Decimal::ZERO, |size| (size - shares).max(Decimal::ZERO));
+        let filled_shares = (fill.matched() - applied).max(Decimal::ZERO);
+        if filled_shares > Decimal::ZERO {
+            let fill_price = fill.fill_price().unwrap_or(price);
+            info!("Resting order {order_id} filled ({filled_shares} shares @ ${fill_price:.2})");
+            state.commit(Mutation::RestingFill {
+                order_id: order_id.clone(),
+                filled_shares,
+                fill_price,
+            });
+        } else {
+            info!("Resting order {order_id} cancelled with no fills");
+            state.commit(Mutation::RestingCancel {
+                order_id: order_id.clone(),
+            });
+        }
+        fills.forget(&order_id);
+    }
+}
+
 /// Check all resting orders and resolve any that have filled or been cancelled.
 ///
-/// Queries the CLOB API for each resting order's current status. Updates TradingState:
+/// Resolves from the user channel while it's connected; otherwise (or after it
+/// reconnected and may have missed events) queries the CLOB API for each
+/// resting order's current status. Updates TradingState:
 /// - Filled → moves to holdings (budget already reserved for buys)
 /// - Cancelled → returns reserved budget (buys), removes tracking
 /// - Still resting → no change
-pub async fn check_resting_orders(ctx: &ClobContext, state: &mut TradingState) {
+///
+/// Orders past `resting_ttl_secs`, or that the market has moved away from, are
+/// cancelled first (see `replace_resting_orders`). Returns the orders to
+/// re-post them with, for the caller to gate like any new order and submit.
+pub async fn check_resting_orders(
+    ctx: &ClobContext,
+    state: &mut TradingState,
+    params: &ExecutorParams,
+) -> Vec<SimulatedOrder> {
     if state.resting_orders.is_empty() {
-        return;
+        return Vec::new();
+    }
+
+    let reposts = replace_resting_orders(ctx, state, params).await;
+    if state.resting_orders.is_empty() {
+        return reposts;
+    }
+
+    if let Some(fills) = &ctx.fills
+        && fills.is_connected()
+        && !fills.needs_resync()
+    {
+        resolve_resting_from_fills(fills, state);
+        return reposts;
     }
 
     info!(
@@ -418,21 +1737,26 @@ pub async fn check_resting_orders(ctx: &ClobContext, state: &mut TradingState) {
         .map(|r| r.order_id.clone())
         .collect();
 
-    for order_id in order_ids {
-        match ctx.client.order(&order_id).await {
+    let mut synced = true;
+    for order_id in &order_ids {
+        match query_order(ctx, order_id).await {
             Ok(status) => {
-                let size_matched = status.size_matched.to_f64().unwrap_or(0.0);
-                let fill_price = status.price.to_f64().unwrap_or(0.0);
+                let size_matched = status.size_matched;
+                let fill_price = status.price;
 
                 match status.status {
                     OrderStatusType::Matched => {
                         info!(
                             "Resting order {order_id} filled ({size_matched} shares @ ${fill_price:.2})"
                         );
-                        state.resolve_resting_fill(&order_id, size_matched, fill_price);
+                        state.commit(Mutation::RestingFill {
+                            order_id: order_id.clone(),
+                            filled_shares: size_matched,
+                            fill_price,
+                        });
                     }
                     OrderStatusType::Live => {
-                        if size_matched > 0.0 {
+                        if size_matched > Decimal::ZERO {
                             // Partial fill on a still-live order — don't resolve yet,
                             // wait for full fill or cancellation
                             info!(
@@ -442,14 +1766,20 @@ pub async fn check_resting_orders(ctx: &ClobContext, state: &mut TradingState) {
                         // else: still fully resting, no action needed
                     }
                     OrderStatusType::Canceled | OrderStatusType::Unmatched => {
-                        if size_matched > 0.0 {
+                        if size_matched > Decimal::ZERO {
                             info!(
                                 "Resting order {order_id} cancelled with partial fill ({size_matched} shares)"
                             );
-                            state.resolve_resting_fill(&order_id, size_matched, fill_price);
+                            state.commit(Mutation::RestingFill {
+                                order_id: order_id.clone(),
+                                filled_shares: size_matched,
+                                fill_price,
+                            });
                         } else {
                             info!("Resting order {order_id} cancelled with no fills");
-                            state.resolve_resting_cancel(&order_id);
+                            state.commit(Mutation::RestingCancel {
+                                order_id: order_id.clone(),
+                            });
                         }
                     }
                     _ => {
@@ -463,16 +1793,266 @@ pub async fn check_resting_orders(ctx: &ClobContext, state: &mut TradingState) {
             Err(e) => {
                 warn!("Failed to check resting order {order_id}: {e}");
                 // Leave it tracked — will retry next cycle
+                synced = false;
             }
         }
     }
 
+    if let Some(fills) = &ctx.fills {
+        // Orders resolved here no longer need tracking on the user channel
+        for order_id in &order_ids {
+            if !state.resting_orders.iter().any(|r| &r.order_id == order_id) {
+                fills.forget(order_id);
+            }
+        }
+        if synced {
+            fills.mark_synced();
+        }
+    }
+
     if !state.resting_orders.is_empty() {
         info!(
             "{} order(s) still resting on book",
             state.resting_orders.len()
         );
     }
+    reposts
+}
+
+/// Cancel, and where configured re-post, resting orders ahead of their status
+/// check: orders older than `resting_ttl_secs` (re-posted with
+/// `reprice_expired`), and orders whose touch has moved more than
+/// `reprice_after_ticks` ticks past their limit (always re-posted). Shares an
+/// order matched before its cancel are booked and the rest of a buy's
+/// reservation returns to the budget. An order whose cancel isn't confirmed
+/// stays tracked for the regular status check. Returns the re-posts, sells
+/// first (see `reprice_order`).
+async fn replace_resting_orders(
+    ctx: &ClobContext,
+    state: &mut TradingState,
+    params: &ExecutorParams,
+) -> Vec<SimulatedOrder> {
+    if params.resting_ttl_secs.is_none() && params.reprice_after_ticks.is_none() {
+        return Vec::new();
+    }
+    let now_ms = crate::clock::now().timestamp_millis();
+    let mut stale: Vec<(RestingOrder, bool, Option<OrderBook>)> = Vec::new();
+    for resting in state.resting_orders.clone() {
+        if let Some(ttl_secs) = params.resting_ttl_secs
+            && resting.is_expired(ttl_secs, now_ms)
+        {
+            info!(
+                "Resting order {} expired after {ttl_secs}s — cancelling",
+                resting.order_id
+            );
+            stale.push((resting, params.reprice_expired, None));
+            continue;
+        }
+        let Some(ticks) = params.reprice_after_ticks else {
+            continue;
+        };
+        let book = match fetch_order_book(&ctx.client, &resting.asset).await {
+            Ok(book) => book,
+            Err(e) => {
+                warn!("{e:#} — can't tell if the market moved away from {}", resting.order_id);
+                continue;
+            }
+        };
+        if moved_away(&resting, &book, ticks) {
+            if past_chase_bound(&resting, &book, params.max_limit_offset()) {
+                debug!(
+                    "Resting order {} is past the chase bound from ${:.4} — leaving it",
+                    resting.order_id, resting.price
+                );
+                continue;
+            }
+            info!(
+                "Market moved more than {ticks} tick(s) past resting order {} at ${:.4} — \
+                 replacing it",
+                resting.order_id, resting.price
+            );
+            stale.push((resting, true, Some(book)));
+        }
+    }
+
+    let mut reposts = Vec::new();
+    for (resting, reprice, book) in stale {
+        if let Some(remaining) = cancel_resting(ctx, state, &resting).await
+            && reprice
+            && remaining > Decimal::ZERO
+        {
+            reposts.push((resting, remaining, book));
+        }
+    }
+
+    // Budget freed by the cancels, shared by the re-posted buys
+    let mut budget = state.budget_remaining;
+    let mut orders = Vec::new();
+    for (resting, shares, book) in reposts {
+        let book = match book {
+            Some(book) => book,
+            None => match fetch_order_book(&ctx.client, &resting.asset).await {
+                Ok(book) => book,
+                Err(e) => {
+                    warn!("{e:#} — not repricing \"{}\"", resting.title);
+                    continue;
+                }
+            },
+        };
+        let tags = state.asset_tags.get(&resting.asset).cloned().unwrap_or_default();
+        let max_offset = params.max_limit_offset();
+        let Some(mut order) = reprice_order(&resting, shares, &book, max_offset, tags) else {
+            warn!(
+                "Book for \"{}\" is empty or past the chase bound from ${:.4} — not repricing",
+                resting.title, resting.price
+            );
+            continue;
+        };
+        if order.side == OrderSide::Buy {
+            if order.cost_usd > budget {
+                // Buy only what the budget covers
+                let fits = money::ratio(budget, order.price).unwrap_or_default();
+                order.shares = money::round_toward_zero(fits.max(Decimal::ZERO), SHARE_INCREMENT);
+                order.cost_usd = order.shares * order.price;
+            }
+            budget -= order.cost_usd;
+        }
+        if order.shares > Decimal::ZERO {
+            info!(
+                "Re-posting {} {:.2} shares of \"{}\" from ${:.4}",
+                order.side.label(),
+                order.shares,
+                resting.title,
+                order.price
+            );
+            orders.push(order);
+        }
+    }
+    // Sells first, as `execute_orders` expects
+    orders.sort_by_key(|o| o.side == OrderSide::Buy);
+    orders
+}
+
+/// Cancel a tracked resting order and book what it matched before the cancel.
+/// Returns the shares left unfilled, or `None` (the order stays tracked) when
+/// the cancel isn't confirmed or its fills can't be checked.
+async fn cancel_resting(
+    ctx: &ClobContext,
+    state: &mut TradingState,
+    resting: &RestingOrder,
+) -> Option<Decimal> {
+    let order_id = &resting.order_id;
+    ratelimit::acquire_clob("clob:DELETE /order").await;
+    match ctx.client.cancel_order(order_id).await {
+        Ok(resp) if resp.canceled.iter().any(|id| id == order_id) => {}
+        Ok(resp) => {
+            let reason = resp.not_canceled.get(order_id).map_or("not cancelled", |e| e);
+            warn!("Failed to cancel resting order {order_id}: {reason}");
+            return None;
+        }
+        Err(e) => {
+            warn!("Failed to cancel resting order {order_id}: {e}");
+            return None;
+        }
+    }
+
+    let status = match query_order(ctx, order_id).await {
+        Ok(status) => status,
+        Err(e) => {
+            // Still tracked: the next status check resolves the cancellation
+            warn!("Cancelled resting order {order_id} but failed to check its fills: {e}");
+            return None;
+        }
+    };
+    // A partial fill's remainder rests under the same ID, so only shares
+    // matched beyond those already applied belong to this order
+    let applied = (status.original_size - resting.shares).max(Decimal::ZERO);
+    let filled_shares = (status.size_matched - applied).max(Decimal::ZERO);
+    if filled_shares > Decimal::ZERO {
+        let fill_price = status.price;
+        info!(
+            "Resting order {order_id} cancelled after filling {filled_shares} of {} shares",
+            resting.shares
+        );
+        state.commit(Mutation::RestingFill {
+            order_id: order_id.clone(),
+            filled_shares,
+            fill_price,
+        });
+    } else {
+        info!("Resting order {order_id} cancelled with no fills");
+        state.commit(Mutation::RestingCancel {
+            order_id: order_id.clone(),
+        });
+    }
+    if let Some(fills) = &ctx.fills {
+        fills.forget(order_id);
+    }
+    Some(resting.shares - filled_shares)
+}
+
+/// Whether the touch has moved more than `ticks` ticks past `resting`'s limit
+/// (the best ask above a buy, the best bid below a sell), so it can't fill
+/// unless the market comes back. `false` when that side of the book is empty.
+pub fn moved_away(resting: &RestingOrder, book: &OrderBook, ticks: u32) -> bool {
+    let allowed = f64::from(ticks) * book.tick_size + 1e-9;
+    let price = money::to_f64(resting.price);
+    match resting.side {
+        OrderSide::Buy => book.asks.first().is_some_and(|&(ask, _)| ask - price > allowed),
+        OrderSide::Sell => book.bids.first().is_some_and(|&(bid, _)| price - bid > allowed),
+    }
+}
+
+/// Whether the touch in `book` (best ask for buys, best bid for sells) is more
+/// than `max_offset` (`ExecutorParams::max_limit_offset`) past `resting`'s
+/// decision price, so no re-post within the chase bounds could fill. `false`
+/// when that side of the book is empty.
+pub fn past_chase_bound(resting: &RestingOrder, book: &OrderBook, max_offset: f64) -> bool {
+    let allowed = max_offset + 1e-9;
+    let price = money::to_f64(resting.price);
+    match resting.side {
+        OrderSide::Buy => book.asks.first().is_some_and(|&(ask, _)| ask - price > allowed),
+        OrderSide::Sell => book.bids.first().is_some_and(|&(bid, _)| price - bid > allowed),
+    }
+}
+
+/// Order re-posting `shares` of a cancelled resting order. It keeps the
+/// original decision price (`resting.price`), so the executor measures
+/// `max_chase`, `book_pricing.max_premium` and `max_slippage_pct` from it
+/// again rather than from the touch, and repeated reprices can't walk the limit
+/// away. `None` when the opposing side of `book` is empty or its touch is past
+/// those bounds (see `past_chase_bound`).
+pub fn reprice_order(
+    resting: &RestingOrder,
+    shares: Decimal,
+    book: &OrderBook,
+    max_offset: f64,
+    tags: Vec<String>,
+) -> Option<SimulatedOrder> {
+    let touch = match resting.side {
+        OrderSide::Buy => book.asks.first(),
+        OrderSide::Sell => book.bids.first(),
+    };
+    if touch.is_none() || past_chase_bound(resting, book, max_offset) {
+        return None;
+    }
+    let price = resting.price;
+    Some(SimulatedOrder {
+        market: MarketPosition {
+            condition_id: String::new(),
+            asset: resting.asset.clone(),
+            title: resting.title.clone(),
+            outcome: resting.outcome.clone(),
+            outcome_index: 0,
+            event_slug: String::new(),
+            tags,
+            opposite_asset: resting.opposite_asset.clone(),
+        },
+        side: resting.side,
+        shares,
+        price,
+        cost_usd: shares * price,
+    })
 }
 
 impl OrderSide {
@@ -483,3 +2063,555 @@ impl OrderSide {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::{FileStore, StateStore};
+    use crate::types::MarketPosition;
+    use rust_decimal_macros::dec;
+
+    fn make_order(side: OrderSide, shares: Decimal, price: Decimal) -> SimulatedOrder {
+        SimulatedOrder {
+            market: MarketPosition {
+                condition_id: "cond".to_string(),
+                asset: "a1".to_string(),
+                title: "Market".to_string(),
+                outcome: "Yes".to_string(),
+                outcome_index: 0,
+                event_slug: "event".to_string(),
+                tags: Vec::new(),
+                opposite_asset: String::new(),
+            },
+            side,
+            shares,
+            price,
+            cost_usd: shares * price,
+        }
+    }
+
+    #[test]
+    fn failure_guard_trips_on_consecutive_failures() {
+        let mut guard = FailureGuard::new(3);
+        assert!(!guard.record(ExecutionStatus::Failed));
+        assert!(!guard.record(ExecutionStatus::Failed));
+        // A success resets the streak
+        assert!(!guard.record(ExecutionStatus::Filled));
+        assert!(!guard.record(ExecutionStatus::Failed));
+        assert!(!guard.record(ExecutionStatus::Failed));
+        assert!(!guard.tripped());
+        assert!(guard.record(ExecutionStatus::Failed));
+        assert!(guard.tripped());
+
+        let mut disabled = FailureGuard::new(0);
+        for _ in 0..10 {
+            assert!(!disabled.record(ExecutionStatus::Failed));
+        }
+        assert!(!disabled.tripped());
+    }
+
+    #[test]
+    fn resting_remainder_resolves_from_user_channel() {
+        use crate::fills::UserEvent;
+        use crate::types::RestingOrder;
+
+        let mut state = TradingState::new(dec!(100));
+        // 10-share buy at $0.50: 4 filled at post time, the remaining 6 rest
+        state.add_resting_order(RestingOrder {
+            order_id: "o1".to_string(),
+            asset: "a1".to_string(),
+            title: "Market".to_string(),
+            outcome: "Yes".to_string(),
+            side: OrderSide::Buy,
+            shares: dec!(6),
+            price: dec!(0.5),
+            cost_usd: dec!(3),
+            opposite_asset: String::new(),
+            placed_at_ms: 0,
+        });
+        let fills = FillBook::new();
+        let order = |kind: &str, matched: Decimal| UserEvent::Order {
+            order_id: "o1".to_string(),
+            kind: kind.to_string(),
+            original_size: dec!(10),
+            size_matched: matched,
+            price: dec!(0.5),
+        };
+
+        fills.apply(order("PLACEMENT", dec!(4)));
+        fills.apply(order("UPDATE", dec!(7)));
+        resolve_resting_from_fills(&fills, &mut state);
+        assert_eq!(state.resting_orders.len(), 1, "still live");
+
+        // Cancelled after 3 more shares matched: only those are new
+        fills.apply(order("CANCELLATION", dec!(7)));
+        resolve_resting_from_fills(&fills, &mut state);
+        assert!(state.resting_orders.is_empty());
+        assert_eq!(state.holdings["a1"].shares, dec!(3));
+        assert!(fills.get("o1").is_none());
+    }
+
+    #[test]
+    fn pending_orders_reconcile_against_open_orders_and_fills() {
+        // Split orders repeat, yet get distinct IDs; a new cycle gets new ones
+        let batch = vec![
+            make_order(OrderSide::Buy, dec!(10), dec!(0.5)),
+            make_order(OrderSide::Buy, dec!(10), dec!(0.5)),
+            make_order(OrderSide::Sell, dec!(4), dec!(0.5)),
+        ];
+        let ids = client_order_ids(7, &batch);
+        assert_eq!(ids, client_order_ids(7, &batch));
+        assert_ne!(ids[0], ids[1]);
+        assert_ne!(ids[0], client_order_ids(8, &batch)[0]);
+
+        let pending: Vec<PendingOrder> = batch
+            .iter()
+            .map(|order| PendingOrder {
+                order: order.clone(),
+                submitted_at: 1_000,
+            })
+            .collect();
+        // The first buy rests with 3 matched (after lot rounding), the second
+        // filled as taker, the sell never reached the CLOB
+        let open = vec![OpenOrder {
+            order_id: "o1".to_string(),
+            market: "cond".to_string(),
+            asset: "a1".to_string(),
+            outcome: "Yes".to_string(),
+            side: OrderSide::Buy,
+            original_size: dec!(9.99),
+            size_matched: dec!(3),
+            price: dec!(0.51),
+            created_at: 1_001,
+        }];
+        let fill = |shares, matched_at| OwnFill {
+            asset: "a1".to_string(),
+            side: OrderSide::Buy,
+            shares,
+            price: dec!(0.52),
+            matched_at,
+        };
+        let fills = vec![fill(dec!(3), 1_001), fill(dec!(10), 1_002), fill(dec!(5), 900)];
+        let results = reconcile_orders(&pending, open, fills);
+        assert_eq!(results[0].status, ExecutionStatus::PartialFill);
+        assert_eq!((results[0].order_id.as_str(), results[0].filled_shares), ("o1", dec!(3)));
+        assert_eq!(results[1].status, ExecutionStatus::Filled);
+        assert_eq!(results[1].filled_cost_usd, dec!(5.2));
+        assert_eq!(results[2].status, ExecutionStatus::Failed);
+
+        // Booking the results settles the submissions and closes the cycle
+        let mut state = TradingState::new(dec!(100));
+        state.apply_mutation(&Mutation::SubmitOrders {
+            cycle: 0,
+            orders: ids.iter().cloned().zip(pending.clone()).collect(),
+        });
+        assert_eq!(state.pending_orders.len(), 3);
+        state.apply_mutation(&Mutation::ApplyExecutionResults {
+            orders: batch,
+            results,
+            client_order_ids: ids,
+        });
+        assert!(state.pending_orders.is_empty());
+        assert_eq!(state.order_cycle, 1);
+        assert_eq!(state.holdings["a1"].shares, dec!(13));
+        assert_eq!(state.resting_orders[0].shares, dec!(7));
+    }
+
+    #[test]
+    fn startup_triage_adopts_orders_in_our_markets() {
+        let mut state = TradingState::new(dec!(100));
+        state.apply_orders(&[make_order(OrderSide::Buy, dec!(10), dec!(0.5))]);
+        let mut tracked = make_order(OrderSide::Sell, dec!(5), dec!(0.6));
+        tracked.market.asset = "a4".to_string();
+        state.apply_execution_results(
+            &[tracked],
+            &[ExecutionResult {
+                order_index: 0,
+                status: ExecutionStatus::Resting,
+                order_id: "o5".to_string(),
+                filled_shares: Decimal::ZERO,
+                filled_cost_usd: Decimal::ZERO,
+                error_msg: None,
+            }],
+        );
+        let open_order = |id: &str, market: &str, asset: &str, created_at| OpenOrder {
+            order_id: id.to_string(),
+            market: market.to_string(),
+            asset: asset.to_string(),
+            outcome: "Yes".to_string(),
+            side: OrderSide::Buy,
+            original_size: dec!(10),
+            size_matched: Decimal::ZERO,
+            price: dec!(0.4),
+            created_at,
+        };
+        let open = vec![
+            // Held token
+            open_order("o1", "cond", "a1", 990),
+            // Copied market
+            open_order("o2", "copied", "a2", 990),
+            // Another strategy's
+            open_order("o3", "other", "a3", 990),
+            // Held token, past the TTL
+            open_order("o4", "cond", "a1", 100),
+            // Already tracked, and another on its token
+            open_order("o5", "cond", "a4", 990),
+            open_order("o6", "cond", "a4", 990),
+        ];
+        let markets = vec!["copied".to_string()];
+        let triage = triage_open_orders(open, &state, &markets, Some(60), 1_000);
+        let ids = |orders: &[OpenOrder]| orders.iter().map(|o| o.order_id.clone()).collect();
+        let adopted: Vec<String> = ids(&triage.adopt);
+        assert_eq!(adopted, vec!["o1", "o2", "o6"]);
+        let stale: Vec<String> = ids(&triage.stale);
+        assert_eq!(stale, vec!["o4"]);
+        assert_eq!(triage.foreign, 1);
+
+        // Without a TTL nothing is stale
+        let triage = triage_open_orders(
+            vec![open_order("o4", "cond", "a1", 100)],
+            &state,
+            &[],
+            None,
+            1_000,
+        );
+        assert_eq!((triage.adopt.len(), triage.stale.len()), (1, 0));
+    }
+
+    #[test]
+    fn limit_price_chases_against_us_within_range() {
+        let buy = make_order(OrderSide::Buy, dec!(10), dec!(0.5));
+        let sell = make_order(OrderSide::Sell, dec!(10), dec!(0.5));
+        assert_eq!(limit_price(&buy, 0.0), 0.50);
+        assert!((limit_price(&buy, 0.03) - 0.53).abs() < 1e-9);
+        assert!((limit_price(&sell, 0.03) - 0.47).abs() < 1e-9);
+
+        let high = make_order(OrderSide::Buy, dec!(10), dec!(0.98));
+        let low = make_order(OrderSide::Sell, dec!(10), dec!(0.02));
+        assert_eq!(limit_price(&high, 0.05), 0.99);
+        assert_eq!(limit_price(&low, 0.05), 0.01);
+    }
+
+    #[test]
+    fn book_limit_price_follows_the_touch_within_premium() {
+        let book = OrderBook::new(vec![(0.53, 100.0), (0.55, 50.0)], vec![(0.49, 80.0)], 0.01);
+        let pricing = BookPricing {
+            ticks: 1,
+            max_premium: 0.03,
+        };
+        // Stale detection price of $0.50: buy at the ask + 1 tick, capped at $0.53
+        let buy = make_order(OrderSide::Buy, dec!(10), dec!(0.5));
+        assert!((book_limit_price(&buy, &book, &pricing).unwrap() - 0.53).abs() < 1e-9);
+        let buy = make_order(OrderSide::Buy, dec!(10), dec!(0.52));
+        assert!((book_limit_price(&buy, &book, &pricing).unwrap() - 0.54).abs() < 1e-9);
+        let sell = make_order(OrderSide::Sell, dec!(10), dec!(0.5));
+        assert!((book_limit_price(&sell, &book, &pricing).unwrap() - 0.48).abs() < 1e-9);
+
+        let no_bids = OrderBook::new(vec![(0.53, 100.0)], Vec::new(), 0.01);
+        assert!(book_limit_price(&sell, &no_bids, &pricing).is_none());
+    }
+
+    #[test]
+    fn twap_posts_one_slice_per_interval() {
+        let params = ExecutorParams {
+            twap: Some(crate::config::Twap {
+                min_order_usd: 20.0,
+                slices: 3,
+                interval_secs: 30,
+            }),
+            ..ExecutorParams::default()
+        };
+        let path =
+            std::env::temp_dir().join(format!("copytrade-twap-{}.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let mut state = TradingState::new(dec!(1000));
+        state.attach_store(Box::new(FileStore::open(&path, 1000.0, None).unwrap()));
+        let order = |shares| make_order(OrderSide::Buy, shares, dec!(0.5));
+
+        // $50 order: a third now
+        let first = schedule_twap(vec![order(dec!(100))], &mut state, &params, 0);
+        assert_eq!(first[0].shares, dec!(33.33));
+        // Before the interval, the rest waits
+        assert!(schedule_twap(vec![order(dec!(66.67))], &mut state, &params, 10_000).is_empty());
+        // A slice still resting holds the schedule too
+        state.add_resting_order(RestingOrder {
+            order_id: "o1".into(),
+            asset: "a1".into(),
+            title: String::new(),
+            outcome: String::new(),
+            side: OrderSide::Buy,
+            shares: dec!(33.33),
+            price: dec!(0.5),
+            cost_usd: dec!(16.665),
+            opposite_asset: String::new(),
+            placed_at_ms: 0,
+        });
+        assert!(schedule_twap(vec![order(dec!(66.67))], &mut state, &params, 30_000).is_empty());
+        state.resting_orders.clear();
+        let second = schedule_twap(vec![order(dec!(66.67))], &mut state, &params, 30_000);
+        assert_eq!(second[0].shares, dec!(33.33));
+        // The schedule is in the log, so a restart resumes it
+        let entries = FileStore::open(&path, 1000.0, None).unwrap().load().unwrap();
+        let mut restored = TradingState::new(dec!(1000));
+        restored.replay(&entries);
+        assert_eq!(restored.twap_schedules, state.twap_schedules);
+        assert_eq!(restored.twap_schedules["a1"].remaining, 1);
+        // The last slice takes whatever is still planned
+        let last = schedule_twap(vec![order(dec!(40))], &mut state, &params, 60_000);
+        assert_eq!(last[0].shares, dec!(40));
+        assert!(state.twap_schedules.is_empty());
+
+        // Small orders pass through untouched
+        let small = schedule_twap(vec![order(dec!(10))], &mut state, &params, 90_000);
+        assert_eq!(small[0].shares, dec!(10));
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn cap_slippage_clamps_or_skips() {
+        let buy = make_order(OrderSide::Buy, dec!(10), dec!(0.5));
+        let sell = make_order(OrderSide::Sell, dec!(10), dec!(0.5));
+        let book = OrderBook::new(vec![(0.51, 100.0)], vec![(0.45, 100.0)], 0.01);
+
+        // 4%: a $0.53 buy limit comes down to $0.52; the ask is within it
+        let limit = cap_slippage(&buy, Some(&book), 0.53, 4.0).unwrap();
+        assert!((limit - 0.52).abs() < 1e-9);
+        assert!((cap_slippage(&buy, Some(&book), 0.51, 4.0).unwrap() - 0.51).abs() < 1e-9);
+        // The best bid is already 10% down
+        let reason = cap_slippage(&sell, Some(&book), 0.45, 4.0).unwrap_err();
+        assert!(reason.contains("best bid $0.4500 is 10.0%"));
+        // Without a book, only the limit is held in
+        assert!((cap_slippage(&sell, None, 0.45, 4.0).unwrap() - 0.48).abs() < 1e-9);
+    }
+
+    #[test]
+    fn reprices_stay_bounded_by_the_decision_price() {
+        let resting = RestingOrder {
+            order_id: "o1".to_string(),
+            asset: "a1".to_string(),
+            title: "Market".to_string(),
+            outcome: "Yes".to_string(),
+            side: OrderSide::Buy,
+            shares: dec!(20),
+            price: dec!(0.48),
+            cost_usd: dec!(9.6),
+            opposite_asset: "a2".to_string(),
+            placed_at_ms: 1_000_000,
+        };
+        assert!(!resting.is_expired(600, 1_000_000 + 600_000));
+        assert!(resting.is_expired(600, 1_000_000 + 600_001));
+
+        // The re-post keeps the decision price, for the executor to chase from
+        let book = OrderBook::new(vec![(0.52, 100.0)], vec![(0.47, 100.0)], 0.01);
+        let order = reprice_order(&resting, dec!(15), &book, 0.05, Vec::new()).unwrap();
+        assert_eq!(order.side, OrderSide::Buy);
+        assert_eq!(order.market.opposite_asset, "a2");
+        assert_eq!(order.price, dec!(0.48));
+        assert_eq!(order.cost_usd, dec!(7.2));
+        // The ask is 4 cents up: out of reach with a 3-cent chase
+        assert!(past_chase_bound(&resting, &book, 0.03));
+        assert!(reprice_order(&resting, dec!(15), &book, 0.03, Vec::new()).is_none());
+
+        let sell = RestingOrder {
+            side: OrderSide::Sell,
+            ..resting
+        };
+        let order = reprice_order(&sell, dec!(15), &book, 0.01, Vec::new()).unwrap();
+        assert_eq!(order.price, dec!(0.48));
+        let no_bids = OrderBook::new(vec![(0.52, 100.0)], Vec::new(), 0.01);
+        assert!(reprice_order(&sell, dec!(15), &no_bids, 0.05, Vec::new()).is_none());
+    }
+
+    #[test]
+    fn moved_away_counts_ticks_past_the_limit() {
+        let buy = RestingOrder {
+            order_id: "o1".to_string(),
+            asset: "a1".to_string(),
+            title: String::new(),
+            outcome: String::new(),
+            side: OrderSide::Buy,
+            shares: dec!(10),
+            price: dec!(0.5),
+            cost_usd: dec!(5),
+            opposite_asset: String::new(),
+            placed_at_ms: 0,
+        };
+        let book = OrderBook::new(vec![(0.52, 100.0)], vec![(0.47, 100.0)], 0.01);
+        // Ask is 2 ticks above the buy
+        assert!(moved_away(&buy, &book, 1));
+        assert!(!moved_away(&buy, &book, 2));
+        // Bid is 3 ticks below the sell
+        let sell = RestingOrder {
+            side: OrderSide::Sell,
+            ..buy.clone()
+        };
+        assert!(moved_away(&sell, &book, 2));
+        assert!(!moved_away(&sell, &book, 3));
+        // An empty side never counts as moved
+        let no_asks = OrderBook::new(Vec::new(), vec![(0.47, 100.0)], 0.01);
+        assert!(!moved_away(&buy, &no_asks, 0));
+    }
+
+    #[test]
+    fn split_orders_noop_without_caps() {
+        let orders = vec![make_order(OrderSide::Buy, dec!(1000), dec!(0.5))];
+        let split = split_orders(orders, &ExecutorParams::default(), &EngineParams::default());
+        assert_eq!(split.len(), 1);
+        assert_eq!(split[0].shares, dec!(1000));
+    }
+
+    #[test]
+    fn split_orders_by_share_cap() {
+        let params = ExecutorParams {
+            max_order_shares: Some(100.0),
+            ..ExecutorParams::default()
+        };
+        let orders = vec![
+            make_order(OrderSide::Sell, dec!(250), dec!(0.4)),
+            make_order(OrderSide::Buy, dec!(50), dec!(0.5)),
+        ];
+        let split = split_orders(orders, &params, &EngineParams::default());
+        assert_eq!(split.len(), 4);
+        // Sell split into 3 lot-sized parts, still ahead of the buy
+        let shares: Vec<Decimal> = split[..3].iter().map(|o| o.shares).collect();
+        assert_eq!(shares, vec![dec!(83.34), dec!(83.33), dec!(83.33)]);
+        for child in &split[..3] {
+            assert_eq!(child.side, OrderSide::Sell);
+            assert_eq!(child.cost_usd, child.shares * dec!(0.40));
+        }
+        assert_eq!(split[3].side, OrderSide::Buy);
+        assert_eq!(split[3].shares, dec!(50));
+    }
+
+    #[test]
+    fn split_orders_keep_every_child_within_the_cap() {
+        let params = ExecutorParams {
+            max_order_shares: Some(10.01),
+            ..ExecutorParams::default()
+        };
+        let order = || vec![make_order(OrderSide::Buy, dec!(100.09), dec!(0.5))];
+        let split = split_orders(order(), &params, &EngineParams::default());
+        assert_eq!(split.len(), 10);
+        assert!(split.iter().all(|o| o.shares <= dec!(10.01)));
+        assert_eq!(split.iter().map(|o| o.shares).sum::<Decimal>(), dec!(100.09));
+
+        // Whole shares: a 10.5 cap rounds to 10, and 95 shares go out as 10 near-equal parts
+        let whole = EngineParams {
+            whole_shares: true,
+            ..EngineParams::default()
+        };
+        let params = ExecutorParams {
+            max_order_shares: Some(10.5),
+            ..ExecutorParams::default()
+        };
+        let split = split_orders(
+            vec![make_order(OrderSide::Buy, dec!(95), dec!(0.5))],
+            &params,
+            &whole,
+        );
+        let shares: Vec<Decimal> = split.iter().map(|o| o.shares).collect();
+        let expected: Vec<Decimal> = [dec!(10); 5].into_iter().chain([dec!(9); 5]).collect();
+        assert_eq!(shares, expected);
+    }
+
+    #[test]
+    fn split_orders_by_notional_cap() {
+        let params = ExecutorParams {
+            max_order_shares: Some(1000.0),
+            max_order_usd: Some(20.0),
+            ..ExecutorParams::default()
+        };
+        // $50 notional at $0.50 → 100 shares, cap is 40 shares → 3 parts
+        let orders = vec![make_order(OrderSide::Buy, dec!(100), dec!(0.5))];
+        let split = split_orders(orders, &params, &EngineParams::default());
+        assert_eq!(split.len(), 3);
+        let total: Decimal = split.iter().map(|o| o.cost_usd).sum();
+        assert!((total - dec!(50)).abs() < dec!(0.000001));
+        assert!(split.iter().all(|o| o.cost_usd <= dec!(20)));
+    }
+
+    #[test]
+    fn split_by_liquidity_chunks_large_buy() {
+        let order = make_order(OrderSide::Buy, dec!(100), dec!(0.55));
+        let top = TopOfBook {
+            price: dec!(0.52),
+            size: dec!(20),
+        };
+        let split = split_by_liquidity(order.clone(), top, 2.0, 1.0, dec!(5));
+        assert_eq!(split.len(), 2);
+        assert_eq!(split[0].shares, dec!(20));
+        assert_eq!(split[0].price, dec!(0.52));
+        assert_eq!(split[1].shares, dec!(80));
+        // Remainder rests at the top-of-book price, not the original limit
+        assert_eq!(split[1].price, dec!(0.52));
+        assert_eq!(split[1].cost_usd, dec!(41.6));
+
+        // A remainder below the market minimum stays with the marketable chunk
+        let deep = TopOfBook {
+            price: dec!(0.52),
+            size: dec!(97),
+        };
+        let split = split_by_liquidity(order, deep, 1.0, 1.0, dec!(5));
+        assert_eq!(split.len(), 1);
+        assert_eq!(split[0].shares, dec!(100));
+        assert_eq!(split[0].price, dec!(0.52));
+        assert_eq!(split[0].cost_usd, dec!(52));
+    }
+
+    #[test]
+    fn split_by_liquidity_keeps_small_or_passive_orders() {
+        let top = TopOfBook {
+            price: dec!(0.5),
+            size: dec!(60),
+        };
+        // Within ratio × top size
+        let split = split_by_liquidity(
+            make_order(OrderSide::Buy, dec!(100), dec!(0.5)),
+            top,
+            2.0,
+            1.0,
+            Decimal::ZERO,
+        );
+        assert_eq!(split.len(), 1);
+        // Sell priced above the best bid isn't marketable
+        let split = split_by_liquidity(
+            make_order(OrderSide::Sell, dec!(500), dec!(0.55)),
+            top,
+            2.0,
+            1.0,
+            Decimal::ZERO,
+        );
+        assert_eq!(split.len(), 1);
+        // Buy chunk would be below the minimum notional
+        let thin = TopOfBook {
+            price: dec!(0.5),
+            size: dec!(1),
+        };
+        let split = split_by_liquidity(
+            make_order(OrderSide::Buy, dec!(100), dec!(0.5)),
+            thin,
+            2.0,
+            1.0,
+            Decimal::ZERO,
+        );
+        assert_eq!(split.len(), 1);
+    }
+
+    #[test]
+    fn fit_buys_reserves_taker_fees() {
+        let rates = HashMap::from([("a1".to_string(), 1000)]);
+        let orders = vec![
+            make_order(OrderSide::Sell, dec!(10), dec!(0.5)),
+            make_order(OrderSide::Buy, dec!(20), dec!(0.5)),
+            make_order(OrderSide::Buy, dec!(10), dec!(0.5)),
+            make_order(OrderSide::Buy, dec!(10), dec!(0.5)),
+        ];
+
+        // $10 + $4.50 net from the sell; the first buy takes $11 with fees,
+        // the second is cut to the $3.50 left, the third is dropped
+        let fitted = fit_buys(orders, dec!(10), &rates, 0.0, 1.0);
+        let shares: Vec<Decimal> = fitted.iter().map(|o| o.shares).collect();
+        assert_eq!(shares, [dec!(10), dec!(20), dec!(6.36)]);
+        assert_eq!(fitted[2].cost_usd, dec!(3.18));
+    }
+}
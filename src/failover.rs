@@ -0,0 +1,364 @@
+use std::future::Future;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use polymarket_client_sdk::data::Client;
+use polymarket_client_sdk::data::types::MarketFilter;
+use polymarket_client_sdk::data::types::response::{Activity, Position, Trade};
+use polymarket_client_sdk::types::Address;
+use serde::Serialize;
+use tracing::{info, warn};
+
+use crate::api;
+
+/// When to switch data API endpoints.
+#[derive(Debug, Clone, Copy)]
+pub struct FailoverPolicy {
+    /// Consecutive errors or stale responses before failing over.
+    pub max_failures: u32,
+    /// A trade known from elsewhere for this long but missing from a trades
+    /// response marks the response stale.
+    pub stale_after: Duration,
+    /// How long to stay on the secondary before probing the primary again.
+    pub failback_after: Duration,
+}
+
+/// What a request to one endpoint returned.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Outcome {
+    Ok,
+    /// Succeeded, but is missing trades seen elsewhere.
+    Stale,
+    Error(String),
+}
+
+/// Request counters of one endpoint, for logs and reports.
+#[derive(Debug, Clone, Serialize)]
+pub struct EndpointHealth {
+    pub name: &'static str,
+    pub url: String,
+    pub requests: u64,
+    pub failures: u64,
+    pub stale: u64,
+    pub consecutive_failures: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+}
+
+/// Picks the data API endpoint for each request from recent outcomes.
+///
+/// The primary is used until it fails `max_failures` times in a row, then the
+/// secondary. While on the secondary, the primary is probed every
+/// `failback_after` and taken back on its first good response.
+pub struct Failover {
+    endpoints: Vec<EndpointHealth>,
+    active: usize,
+    policy: FailoverPolicy,
+    /// Next time to try the primary while failed over.
+    probe_at: Option<Instant>,
+    /// Newest trade timestamp known from any source, and when it was learned.
+    newest_trade: Option<(i64, Instant)>,
+}
+
+impl Failover {
+    /// `urls` in priority order: the primary first.
+    pub fn new(urls: &[&str], policy: FailoverPolicy) -> Self {
+        let endpoints = urls
+            .iter()
+            .enumerate()
+            .map(|(i, url)| EndpointHealth {
+                name: if i == 0 { "primary" } else { "secondary" },
+                url: url.to_string(),
+                requests: 0,
+                failures: 0,
+                stale: 0,
+                consecutive_failures: 0,
+                last_error: None,
+            })
+            .collect();
+        Self {
+            endpoints,
+            active: 0,
+            policy,
+            probe_at: None,
+            newest_trade: None,
+        }
+    }
+
+    /// Endpoint to send the next request to.
+    pub fn route_at(&mut self, now: Instant) -> usize {
+        match self.probe_at {
+            Some(at) if self.active != 0 && now >= at => 0,
+            _ => self.active,
+        }
+    }
+
+    /// Record the outcome of a request to endpoint `idx`. Returns whether the
+    /// request should be retried on another endpoint.
+    pub fn record_at(&mut self, idx: usize, outcome: Outcome, now: Instant) -> bool {
+        let endpoint = &mut self.endpoints[idx];
+        endpoint.requests += 1;
+        match outcome {
+            Outcome::Ok => {
+                endpoint.consecutive_failures = 0;
+                if idx != self.active {
+                    info!(
+                        "Data API {} ({}) healthy again — failing back",
+                        endpoint.name, endpoint.url
+                    );
+                    self.active = idx;
+                    self.probe_at = None;
+                }
+                false
+            }
+            Outcome::Stale | Outcome::Error(_) => {
+                endpoint.consecutive_failures += 1;
+                if let Outcome::Error(e) = outcome {
+                    endpoint.failures += 1;
+                    endpoint.last_error = Some(e);
+                } else {
+                    endpoint.stale += 1;
+                }
+                if idx != self.active {
+                    // Failed fail-back probe: try again later, serve from the active one
+                    self.probe_at = Some(now + self.policy.failback_after);
+                    return true;
+                }
+                if self.endpoints.len() < 2
+                    || self.endpoints[idx].consecutive_failures < self.policy.max_failures
+                {
+                    return false;
+                }
+                let next = (idx + 1) % self.endpoints.len();
+                warn!(
+                    "Data API {} ({}) failed {} times in a row — failing over to {} ({})",
+                    self.endpoints[idx].name,
+                    self.endpoints[idx].url,
+                    self.endpoints[idx].consecutive_failures,
+                    self.endpoints[next].name,
+                    self.endpoints[next].url,
+                );
+                self.endpoints[next].consecutive_failures = 0;
+                self.active = next;
+                self.probe_at = (next != 0).then(|| now + self.policy.failback_after);
+                true
+            }
+        }
+    }
+
+    /// Record a trade timestamp learned from any source (a WebSocket feed or a
+    /// trades response).
+    pub fn note_trade_at(&mut self, timestamp: i64, now: Instant) {
+        if self.newest_trade.is_none_or(|(ts, _)| timestamp > ts) {
+            self.newest_trade = Some((timestamp, now));
+        }
+    }
+
+    /// Whether a trades response whose newest trade is `newest` is missing a
+    /// trade known for at least `stale_after`.
+    pub fn is_stale_at(&self, newest: Option<i64>, now: Instant) -> bool {
+        self.newest_trade.is_some_and(|(ts, learned_at)| {
+            now.duration_since(learned_at) >= self.policy.stale_after
+                && newest.is_none_or(|newest| newest < ts)
+        })
+    }
+
+    pub fn active(&self) -> &EndpointHealth {
+        &self.endpoints[self.active]
+    }
+
+    pub fn endpoints(&self) -> &[EndpointHealth] {
+        &self.endpoints
+    }
+}
+
+/// Data API client over a primary and an optional secondary endpoint (a mirror
+/// or self-hosted indexer), failing over between them (see `Failover`).
+pub struct DataApi {
+    clients: Vec<Client>,
+    failover: Mutex<Failover>,
+}
+
+impl DataApi {
+    pub fn new(primary: &str, secondary: Option<&str>, policy: FailoverPolicy) -> Result<Self> {
+        let urls: Vec<&str> = std::iter::once(primary).chain(secondary).collect();
+        let clients = urls
+            .iter()
+            .map(|url| Client::new(url))
+            .collect::<Result<_, _>>()?;
+        Ok(Self {
+            clients,
+            failover: Mutex::new(Failover::new(&urls, policy)),
+        })
+    }
+
+    /// Name of the endpoint currently serving requests (`primary`/`secondary`).
+    pub fn active(&self) -> &'static str {
+        self.failover.lock().unwrap().active().name
+    }
+
+    pub fn endpoints(&self) -> Vec<EndpointHealth> {
+        self.failover.lock().unwrap().endpoints().to_vec()
+    }
+
+    /// Record a trade seen by another detector, so trades responses that lack
+    /// it can be recognized as stale.
+    pub fn note_trade(&self, timestamp: i64) {
+        self.failover
+            .lock()
+            .unwrap()
+            .note_trade_at(timestamp, Instant::now());
+    }
+
+    /// `api::fetch_active_positions` with failover.
+    pub async fn fetch_active_positions(
+        &self,
+        addr: Address,
+        filter: Option<&MarketFilter>,
+    ) -> Result<Vec<Position>> {
+        self.with_failover(
+            |idx| api::fetch_active_positions(&self.clients[idx], addr, filter),
+            |_| None,
+        )
+        .await
+    }
+
+    /// `api::fetch_recent_trades` with failover; a response missing known
+    /// trades counts as a failure.
+    pub async fn fetch_recent_trades(
+        &self,
+        addr: Address,
+        limit: i32,
+        filter: Option<&MarketFilter>,
+    ) -> Result<Vec<Trade>> {
+        let trades = self
+            .with_failover(
+                |idx| api::fetch_recent_trades(&self.clients[idx], addr, limit, filter),
+                |trades: &Vec<Trade>| Some(trades.iter().map(|t| t.timestamp).max()),
+            )
+            .await?;
+        if let Some(newest) = trades.iter().map(|t| t.timestamp).max() {
+            self.note_trade(newest);
+        }
+        Ok(trades)
+    }
+
+    /// `api::fetch_recent_activity` with failover; stale like trades responses.
+    pub async fn fetch_recent_activity(
+        &self,
+        addr: Address,
+        limit: i32,
+        filter: Option<&MarketFilter>,
+    ) -> Result<Vec<Activity>> {
+        let activity = self
+            .with_failover(
+                |idx| api::fetch_recent_activity(&self.clients[idx], addr, limit, filter),
+                |activity: &Vec<Activity>| Some(activity.iter().map(|a| a.timestamp).max()),
+            )
+            .await?;
+        if let Some(newest) = activity.iter().map(|a| a.timestamp).max() {
+            self.note_trade(newest);
+        }
+        Ok(activity)
+    }
+
+    /// Run `call` on the routed endpoint, retrying on another one when the
+    /// failover says so. `newest_trade` gives a response's newest trade
+    /// timestamp for the staleness check (`None`: not applicable).
+    async fn with_failover<T, F, Fut>(
+        &self,
+        call: F,
+        newest_trade: impl Fn(&T) -> Option<Option<i64>>,
+    ) -> Result<T>
+    where
+        F: Fn(usize) -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let mut attempts = 0;
+        loop {
+            let idx = self.failover.lock().unwrap().route_at(Instant::now());
+            let result = call(idx).await;
+            attempts += 1;
+            let mut failover = self.failover.lock().unwrap();
+            let now = Instant::now();
+            let outcome = match &result {
+                Ok(value) => match newest_trade(value) {
+                    Some(newest) if failover.is_stale_at(newest, now) => Outcome::Stale,
+                    _ => Outcome::Ok,
+                },
+                Err(e) => Outcome::Error(format!("{e:#}")),
+            };
+            let retry = failover.record_at(idx, outcome, now);
+            if !retry || attempts >= self.clients.len() {
+                return result;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn failover() -> Failover {
+        Failover::new(
+            &["https://primary", "https://secondary"],
+            FailoverPolicy {
+                max_failures: 2,
+                stale_after: Duration::from_secs(60),
+                failback_after: Duration::from_secs(300),
+            },
+        )
+    }
+
+    #[test]
+    fn fails_over_and_back() {
+        let mut f = failover();
+        let t0 = Instant::now();
+        let err = || Outcome::Error("timeout".to_string());
+
+        assert!(!f.record_at(0, err(), t0));
+        assert_eq!(f.route_at(t0), 0);
+        // Second consecutive failure: switch and retry on the secondary
+        assert!(f.record_at(0, err(), t0));
+        assert_eq!(f.route_at(t0), 1);
+        assert!(!f.record_at(1, Outcome::Ok, t0));
+
+        // Probe the primary after `failback_after`; a failed probe retries on
+        // the secondary and pushes the next probe back
+        let t1 = t0 + Duration::from_secs(300);
+        assert_eq!(f.route_at(t1), 0);
+        assert!(f.record_at(0, err(), t1));
+        assert_eq!(f.route_at(t1), 1);
+
+        let t2 = t1 + Duration::from_secs(300);
+        assert_eq!(f.route_at(t2), 0);
+        assert!(!f.record_at(0, Outcome::Ok, t2));
+        assert_eq!(f.active().name, "primary");
+        assert_eq!(f.route_at(t2 + Duration::from_secs(1_000)), 0);
+        assert_eq!(f.endpoints()[0].failures, 3);
+    }
+
+    #[test]
+    fn responses_missing_known_trades_are_stale() {
+        let mut f = failover();
+        let t0 = Instant::now();
+        assert!(!f.is_stale_at(None, t0));
+
+        f.note_trade_at(1_000, t0);
+        // Indexing lag within `stale_after` is tolerated
+        assert!(!f.is_stale_at(Some(990), t0 + Duration::from_secs(30)));
+        let later = t0 + Duration::from_secs(60);
+        assert!(f.is_stale_at(Some(990), later));
+        assert!(f.is_stale_at(None, later));
+        assert!(!f.is_stale_at(Some(1_000), later));
+
+        // A lone endpoint never fails over
+        let mut single = Failover::new(&["https://primary"], f.policy);
+        for _ in 0..5 {
+            assert!(!single.record_at(0, Outcome::Stale, later));
+        }
+        assert_eq!(single.endpoints()[0].stale, 5);
+    }
+}
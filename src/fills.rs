@@ -0,0 +1,542 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use polymarket_client_sdk::auth::{Credentials, ExposeSecret};
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use serde_json::json;
+use tokio::sync::{Notify, watch};
+use tokio::task::JoinHandle;
+use tokio_tungstenite::tungstenite::Message;
+
+use crate::ws::{Keepalive, ReconnectPolicy, WsEvent, WsFeed};
+
+/// Our own orders are rare, but the `PONG` replies to the 10s keepalive count
+/// as traffic, so a quiet connection is a dead one.
+const IDLE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Trade contributions held for orders not placed yet (see `Book::unplaced`).
+/// Most belong to counterparties' orders, which are never placed in our book,
+/// so the oldest are dropped past this.
+const MAX_UNPLACED: usize = 1024;
+
+/// Where an order stands according to the user channel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OrderState {
+    Live,
+    Filled,
+    Cancelled,
+}
+
+impl OrderState {
+    pub fn is_terminal(self) -> bool {
+        self != OrderState::Live
+    }
+}
+
+/// What the user channel has reported about one of our orders.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrderFill {
+    pub state: OrderState,
+    /// From order events; `None` until the placement is seen.
+    pub original_size: Option<Decimal>,
+    /// Cumulative matched shares.
+    pub size_matched: Decimal,
+    /// The order's limit price.
+    pub limit_price: Option<Decimal>,
+    /// Shares and USD from trade events, at the actual match prices.
+    traded_shares: Decimal,
+    traded_cost: Decimal,
+}
+
+impl OrderFill {
+    fn new() -> Self {
+        Self {
+            state: OrderState::Live,
+            original_size: None,
+            size_matched: Decimal::ZERO,
+            limit_price: None,
+            traded_shares: Decimal::ZERO,
+            traded_cost: Decimal::ZERO,
+        }
+    }
+
+    /// Average match price from trade events, else the limit price.
+    pub fn fill_price(&self) -> Option<Decimal> {
+        if self.traded_shares > Decimal::ZERO {
+            Some(self.traded_cost / self.traded_shares)
+        } else {
+            self.limit_price
+        }
+    }
+
+    /// Whether any shares have matched.
+    pub fn has_fills(&self) -> bool {
+        self.size_matched > Decimal::ZERO || self.traded_shares > Decimal::ZERO
+    }
+
+    /// Matched shares by the best available count.
+    pub fn matched(&self) -> Decimal {
+        self.size_matched.max(self.traded_shares)
+    }
+}
+
+/// An event from the CLOB user channel (`event_type` `order` or `trade`).
+#[derive(Debug, Clone, PartialEq)]
+pub enum UserEvent {
+    Order {
+        order_id: String,
+        /// `PLACEMENT`, `UPDATE`, or `CANCELLATION`.
+        kind: String,
+        original_size: Decimal,
+        size_matched: Decimal,
+        price: Decimal,
+    },
+    Trade {
+        trade_id: String,
+        /// `MATCHED`, `MINED`, `CONFIRMED`, `RETRYING`, or `FAILED`.
+        status: String,
+        /// `(order_id, shares, price)` for each of our orders in the match.
+        fills: Vec<(String, Decimal, Decimal)>,
+    },
+}
+
+#[derive(Deserialize)]
+struct RawEvent {
+    event_type: String,
+    #[serde(default)]
+    id: String,
+    #[serde(default, rename = "type")]
+    kind: String,
+    #[serde(default)]
+    status: String,
+    #[serde(default)]
+    original_size: Option<String>,
+    #[serde(default)]
+    size_matched: Option<String>,
+    #[serde(default)]
+    size: Option<String>,
+    #[serde(default)]
+    price: Option<String>,
+    #[serde(default)]
+    taker_order_id: Option<String>,
+    #[serde(default)]
+    maker_orders: Vec<RawMakerOrder>,
+}
+
+#[derive(Deserialize)]
+struct RawMakerOrder {
+    order_id: String,
+    matched_amount: String,
+    price: String,
+}
+
+fn num(value: Option<&str>) -> Decimal {
+    value.and_then(|v| v.parse().ok()).unwrap_or_default()
+}
+
+/// Parse a user-channel frame (a single event or an array of them). Unknown
+/// event types and malformed entries are skipped.
+pub fn parse_events(msg: &Message) -> Vec<UserEvent> {
+    let Message::Text(text) = msg else {
+        return Vec::new();
+    };
+    let Ok(value) = serde_json::from_str::<serde_json::Value>(text.as_str()) else {
+        return Vec::new();
+    };
+    let values = match value {
+        serde_json::Value::Array(values) => values,
+        value => vec![value],
+    };
+    values
+        .into_iter()
+        .filter_map(|v| serde_json::from_value::<RawEvent>(v).ok())
+        .filter_map(|raw| match raw.event_type.as_str() {
+            "order" => Some(UserEvent::Order {
+                order_id: raw.id,
+                kind: raw.kind.to_ascii_uppercase(),
+                original_size: num(raw.original_size.as_deref()),
+                size_matched: num(raw.size_matched.as_deref()),
+                price: num(raw.price.as_deref()),
+            }),
+            "trade" => {
+                // Our order is either the taker or one of the makers; the
+                // others in the match belong to counterparties, held by
+                // `FillBook` only until they age out (`MAX_UNPLACED`)
+                let mut fills: Vec<(String, Decimal, Decimal)> = raw
+                    .maker_orders
+                    .iter()
+                    .map(|m| {
+                        let shares = num(Some(&m.matched_amount));
+                        (m.order_id.clone(), shares, num(Some(&m.price)))
+                    })
+                    .collect();
+                if let Some(taker) = raw.taker_order_id {
+                    let shares = num(raw.size.as_deref());
+                    fills.push((taker, shares, num(raw.price.as_deref())));
+                }
+                Some(UserEvent::Trade {
+                    trade_id: raw.id,
+                    status: raw.status.to_ascii_uppercase(),
+                    fills,
+                })
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// One order's part in a trade event.
+struct TradeFill {
+    trade_id: String,
+    order_id: String,
+    failed: bool,
+    shares: Decimal,
+    price: Decimal,
+}
+
+#[derive(Default)]
+struct Book {
+    orders: HashMap<String, OrderFill>,
+    /// `trade_id:order_id` contributions already counted.
+    counted: HashSet<String>,
+    /// Trade contributions for orders whose placement hasn't been seen (the
+    /// trade event can arrive first), applied when it is. Oldest first.
+    unplaced: VecDeque<TradeFill>,
+}
+
+impl Book {
+    /// Count a match on first sight (later statuses repeat it) and take it back
+    /// once if it fails.
+    fn apply_trade(&mut self, trade: TradeFill) {
+        let Some(fill) = self.orders.get_mut(&trade.order_id) else {
+            if self.unplaced.len() == MAX_UNPLACED {
+                self.unplaced.pop_front();
+            }
+            self.unplaced.push_back(trade);
+            return;
+        };
+        let key = format!("{}:{}", trade.trade_id, trade.order_id);
+        if trade.failed != self.counted.contains(&key) {
+            return;
+        }
+        if trade.failed {
+            fill.traded_shares -= trade.shares;
+            fill.traded_cost -= trade.shares * trade.price;
+            // The order events counted the match too
+            fill.size_matched = (fill.size_matched - trade.shares).max(Decimal::ZERO);
+            if fill.state == OrderState::Filled
+                && fill
+                    .original_size
+                    .is_some_and(|size| fill.size_matched < size)
+            {
+                fill.state = OrderState::Live;
+            }
+            self.counted.remove(&key);
+        } else {
+            fill.traded_shares += trade.shares;
+            fill.traded_cost += trade.shares * trade.price;
+            self.counted.insert(key);
+        }
+    }
+
+    /// Apply the trade contributions held for `order_id`, now that it's placed.
+    fn apply_unplaced(&mut self, order_id: &str) {
+        let (held, rest): (VecDeque<_>, VecDeque<_>) = std::mem::take(&mut self.unplaced)
+            .into_iter()
+            .partition(|trade| trade.order_id == order_id);
+        self.unplaced = rest;
+        for trade in held {
+            self.apply_trade(trade);
+        }
+    }
+}
+
+/// Our order and fill state as streamed by the CLOB user channel.
+///
+/// Cheap to clone; all clones share the same book. `wait` lets the executor
+/// await a fill instead of sleeping and querying, and `changed` wakes the
+/// polling loop so resting orders resolve as events arrive.
+#[derive(Clone, Default)]
+pub struct FillBook {
+    book: Arc<Mutex<Book>>,
+    notify: Arc<Notify>,
+    connected: Arc<AtomicBool>,
+    /// Set on reconnect: events during the outage were missed.
+    resync: Arc<AtomicBool>,
+}
+
+impl FillBook {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Connect to the user channel at `url` with our API `credentials` and keep
+    /// the book updated on a background task.
+    pub fn spawn(url: &str, credentials: &Credentials) -> (Self, JoinHandle<()>) {
+        let subscription = json!({
+            "auth": {
+                "apiKey": credentials.key().to_string(),
+                "secret": credentials.secret().expose_secret(),
+                "passphrase": credentials.passphrase().expose_secret(),
+            },
+            "type": "user",
+            "markets": [],
+        });
+        let (sub_tx, sub_rx) = watch::channel(vec![subscription.to_string()]);
+        let feed = WsFeed {
+            name: "clob_user",
+            url: url.to_string(),
+            keepalive: Keepalive::CLOB,
+            idle_timeout: Some(IDLE_TIMEOUT),
+            policy: ReconnectPolicy::default(),
+            subscriptions: sub_rx,
+        };
+        let (mut events, _) = feed.spawn();
+        let book = Self::new();
+        let handle = tokio::spawn({
+            let book = book.clone();
+            async move {
+                // Keep the subscription sender alive for the feed's lifetime
+                let _sub_tx = sub_tx;
+                while let Some(event) = events.recv().await {
+                    book.handle(event);
+                }
+            }
+        });
+        (book, handle)
+    }
+
+    fn handle(&self, event: WsEvent) {
+        match event {
+            WsEvent::Connected => self.connected.store(true, Ordering::Relaxed),
+            WsEvent::Reconnected { .. } => {
+                self.connected.store(true, Ordering::Relaxed);
+                self.resync.store(true, Ordering::Relaxed);
+                self.notify.notify_waiters();
+            }
+            WsEvent::Disconnected { .. } => self.connected.store(false, Ordering::Relaxed),
+            WsEvent::Message(msg) => {
+                for event in parse_events(&msg) {
+                    self.apply(event);
+                }
+            }
+        }
+    }
+
+    /// Whether the user channel is currently connected (events are flowing).
+    pub fn is_connected(&self) -> bool {
+        self.connected.load(Ordering::Relaxed)
+    }
+
+    /// Whether the feed reconnected since order state was last re-read over
+    /// REST, so events may have been missed.
+    pub fn needs_resync(&self) -> bool {
+        self.resync.load(Ordering::Relaxed)
+    }
+
+    /// Order state was re-read over REST after a reconnect.
+    pub fn mark_synced(&self) {
+        self.resync.store(false, Ordering::Relaxed);
+    }
+
+    pub fn apply(&self, event: UserEvent) {
+        let mut book = self.book.lock().unwrap();
+        match event {
+            UserEvent::Order {
+                order_id,
+                kind,
+                original_size,
+                size_matched,
+                price,
+            } => {
+                let first_seen = !book.orders.contains_key(&order_id);
+                let fill = book
+                    .orders
+                    .entry(order_id.clone())
+                    .or_insert_with(OrderFill::new);
+                if original_size > Decimal::ZERO {
+                    fill.original_size = Some(original_size);
+                }
+                if price > Decimal::ZERO {
+                    fill.limit_price = Some(price);
+                }
+                fill.size_matched = fill.size_matched.max(size_matched);
+                if kind == "CANCELLATION" {
+                    fill.state = OrderState::Cancelled;
+                } else if fill
+                    .original_size
+                    .is_some_and(|size| fill.size_matched >= size)
+                {
+                    fill.state = OrderState::Filled;
+                }
+                if first_seen {
+                    book.apply_unplaced(&order_id);
+                }
+            }
+            UserEvent::Trade {
+                trade_id,
+                status,
+                fills,
+            } => {
+                let failed = status == "FAILED";
+                for (order_id, shares, price) in fills {
+                    book.apply_trade(TradeFill {
+                        trade_id: trade_id.clone(),
+                        order_id,
+                        failed,
+                        shares,
+                        price,
+                    });
+                }
+            }
+        }
+        drop(book);
+        self.notify.notify_waiters();
+    }
+
+    pub fn get(&self, order_id: &str) -> Option<OrderFill> {
+        self.book.lock().unwrap().orders.get(order_id).cloned()
+    }
+
+    /// Stop tracking an order once its outcome has been applied.
+    pub fn forget(&self, order_id: &str) {
+        let mut book = self.book.lock().unwrap();
+        book.orders.remove(order_id);
+        book.counted
+            .retain(|key| !key.ends_with(&format!(":{order_id}")));
+    }
+
+    /// Wait up to `timeout` for `order_id` to fill or be cancelled. Returns the
+    /// latest state either way (`None` if the channel never mentioned it).
+    pub async fn wait(&self, order_id: &str, timeout: Duration) -> Option<OrderFill> {
+        let deadline = tokio::time::Instant::now() + timeout;
+        loop {
+            let notified = self.notify.notified();
+            if let Some(fill) = self.get(order_id)
+                && fill.state.is_terminal()
+            {
+                return Some(fill);
+            }
+            if tokio::time::timeout_at(deadline, notified).await.is_err() {
+                return self.get(order_id);
+            }
+        }
+    }
+
+    /// Resolves after the next update to the book.
+    pub async fn changed(&self) {
+        self.notify.notified().await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn frame(value: serde_json::Value) -> Message {
+        Message::Text(value.to_string().into())
+    }
+
+    #[test]
+    fn parses_order_and_trade_events() {
+        let events = parse_events(&frame(json!([
+            {
+                "event_type": "order", "id": "o1", "type": "PLACEMENT",
+                "original_size": "10", "size_matched": "0", "price": "0.57",
+                "asset_id": "a1", "side": "BUY"
+            },
+            {
+                "event_type": "trade", "id": "t1", "status": "MATCHED",
+                "taker_order_id": "x", "size": "4", "price": "0.55",
+                "maker_orders": [{ "order_id": "o1", "matched_amount": "4", "price": "0.56" }]
+            },
+            { "event_type": "price_change" }
+        ])));
+        assert_eq!(events.len(), 2);
+        assert!(matches!(&events[0], UserEvent::Order { order_id, .. } if order_id == "o1"));
+        let UserEvent::Trade { fills, .. } = &events[1] else {
+            panic!("expected trade");
+        };
+        assert_eq!(fills[0], ("o1".to_string(), dec!(4), dec!(0.56)));
+    }
+
+    #[tokio::test]
+    async fn book_tracks_fills_and_cancellations() {
+        let book = FillBook::new();
+        let order = |id: &str, kind: &str, matched: Decimal| UserEvent::Order {
+            order_id: id.to_string(),
+            kind: kind.to_string(),
+            original_size: dec!(10),
+            size_matched: matched,
+            price: dec!(0.50),
+        };
+        let trade = |id: &str, status: &str, shares: Decimal, price: Decimal| UserEvent::Trade {
+            trade_id: id.to_string(),
+            status: status.to_string(),
+            fills: vec![("o1".to_string(), shares, price)],
+        };
+
+        book.apply(order("o1", "PLACEMENT", Decimal::ZERO));
+        book.apply(trade("t1", "MATCHED", dec!(6), dec!(0.48)));
+        // Later statuses of the same match aren't counted again
+        book.apply(trade("t1", "MINED", dec!(6), dec!(0.48)));
+        book.apply(order("o1", "UPDATE", dec!(6)));
+        let fill = book.get("o1").unwrap();
+        assert_eq!(fill.state, OrderState::Live);
+        assert_eq!(fill.matched(), dec!(6));
+        assert_eq!(fill.fill_price(), Some(dec!(0.48)));
+
+        // A failed match is taken back, from the order's matched size too
+        book.apply(trade("t2", "MATCHED", dec!(4), dec!(0.49)));
+        book.apply(order("o1", "UPDATE", dec!(10)));
+        assert_eq!(book.get("o1").unwrap().state, OrderState::Filled);
+        book.apply(trade("t2", "FAILED", dec!(4), dec!(0.49)));
+        let fill = book.get("o1").unwrap();
+        assert_eq!(fill.state, OrderState::Live);
+        assert_eq!(fill.size_matched, dec!(6));
+        assert_eq!(fill.matched(), dec!(6));
+
+        let waiter = {
+            let book = book.clone();
+            tokio::spawn(async move { book.wait("o1", Duration::from_secs(5)).await })
+        };
+        tokio::task::yield_now().await;
+        book.apply(order("o1", "CANCELLATION", dec!(6)));
+        let fill = waiter.await.unwrap().unwrap();
+        assert_eq!(fill.state, OrderState::Cancelled);
+
+        // Nothing heard about an order: wait times out with nothing
+        assert!(book.wait("o2", Duration::from_millis(10)).await.is_none());
+    }
+
+    #[test]
+    fn trades_before_placement_are_applied_when_placed() {
+        let book = FillBook::new();
+        book.apply(UserEvent::Trade {
+            trade_id: "t1".to_string(),
+            status: "MATCHED".to_string(),
+            fills: vec![
+                ("o1".to_string(), dec!(4), dec!(0.52)),
+                ("theirs".to_string(), dec!(4), dec!(0.52)),
+            ],
+        });
+        assert!(book.get("o1").is_none());
+
+        book.apply(UserEvent::Order {
+            order_id: "o1".to_string(),
+            kind: "PLACEMENT".to_string(),
+            original_size: dec!(10),
+            size_matched: dec!(4),
+            price: dec!(0.55),
+        });
+        let fill = book.get("o1").unwrap();
+        assert_eq!(fill.matched(), dec!(4));
+        assert_eq!(fill.fill_price(), Some(dec!(0.52)));
+        // Only the placed order's contribution is taken off the buffer
+        assert_eq!(book.book.lock().unwrap().unplaced.len(), 1);
+    }
+}
@@ -0,0 +1,218 @@
+use std::collections::{HashMap, HashSet};
+
+use regex::Regex;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::types::MarketPosition;
+
+/// Markets to copy or refuse, by event, market, or title.
+///
+/// Configured under `[engine.filters]`. With any `include` rule set, only
+/// matching markets are copied; markets matching an `exclude` rule never are.
+/// Refused markets get no target, so held ones are sold like trader exits,
+/// and their share of the budget stays in cash.
+///
+/// `categories` rules apply on top, through per-cycle `CategoryVerdicts` (they
+/// need Gamma lookups; see `api::EventCategories`).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MarketFilters {
+    #[serde(default, skip_serializing_if = "FilterRules::is_empty")]
+    pub include: FilterRules,
+    #[serde(default, skip_serializing_if = "FilterRules::is_empty")]
+    pub exclude: FilterRules,
+    #[serde(default, skip_serializing_if = "CategoryRules::is_empty")]
+    pub categories: CategoryRules,
+}
+
+/// Gamma categories to copy or refuse (`[engine.filters.categories]`), matched
+/// case-insensitively against an event's tag slugs and labels and its category.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CategoryRules {
+    /// With any set, only events in one of these are copied.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub include: Vec<String>,
+    /// Events in any of these are never copied.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exclude: Vec<String>,
+}
+
+impl CategoryRules {
+    pub fn is_empty(&self) -> bool {
+        self.include.is_empty() && self.exclude.is_empty()
+    }
+
+    /// Whether an event with these (lowercase) `categories` may be copied.
+    pub fn allows(&self, categories: &[String]) -> bool {
+        let any = |rules: &[String]| {
+            rules
+                .iter()
+                .any(|rule| categories.contains(&rule.to_lowercase()))
+        };
+        (self.include.is_empty() || any(&self.include)) && !any(&self.exclude)
+    }
+
+    /// Verdicts on the events `slugs`, given the categories of those `known`.
+    /// Events that couldn't be looked up can't be shown to pass, so they are
+    /// unverified rather than copied.
+    pub fn verdicts(
+        &self,
+        slugs: &[String],
+        known: &HashMap<String, Vec<String>>,
+    ) -> CategoryVerdicts {
+        let mut verdicts = CategoryVerdicts::default();
+        if self.is_empty() {
+            return verdicts;
+        }
+        for slug in slugs {
+            match known.get(slug) {
+                Some(categories) if self.allows(categories) => {}
+                Some(_) => {
+                    verdicts.refused.insert(slug.clone());
+                }
+                None => {
+                    verdicts.unverified.insert(slug.clone());
+                }
+            }
+        }
+        verdicts
+    }
+}
+
+/// One cycle's `[engine.filters.categories]` verdicts, by event slug.
+///
+/// Applied to the targets after sizing (`engine::apply_category_verdicts`), so
+/// like other refused markets, their share of the budget stays in cash.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CategoryVerdicts {
+    /// Events the rules refuse: their markets get no target.
+    pub refused: HashSet<String>,
+    /// Events whose categories couldn't be looked up: their markets are held
+    /// at current holdings (no buys) until a lookup succeeds.
+    pub unverified: HashSet<String>,
+}
+
+/// One side of `[engine.filters]`: a market matches if any rule does.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FilterRules {
+    /// Event slugs.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub events: Vec<String>,
+    /// Condition IDs.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub markets: Vec<String>,
+    /// Regular expressions matched against market titles.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub titles: Vec<TitlePattern>,
+}
+
+/// A title regex, compiled when the config is loaded.
+#[derive(Debug, Clone)]
+pub struct TitlePattern(Regex);
+
+impl<'de> Deserialize<'de> for TitlePattern {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let pattern = String::deserialize(deserializer)?;
+        Regex::new(&pattern)
+            .map(TitlePattern)
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+impl Serialize for TitlePattern {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.0.as_str())
+    }
+}
+
+impl FilterRules {
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty() && self.markets.is_empty() && self.titles.is_empty()
+    }
+
+    fn matches(&self, market: &MarketPosition) -> bool {
+        self.events.contains(&market.event_slug)
+            || self.markets.contains(&market.condition_id)
+            || self.titles.iter().any(|t| t.0.is_match(&market.title))
+    }
+}
+
+impl MarketFilters {
+    pub fn is_empty(&self) -> bool {
+        self.include.is_empty() && self.exclude.is_empty() && self.categories.is_empty()
+    }
+
+    /// Whether `market` may be copied (`categories` aside).
+    pub fn allows(&self, market: &MarketPosition) -> bool {
+        (self.include.is_empty() || self.include.matches(market)) && !self.exclude.matches(market)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn market(condition_id: &str, event_slug: &str, title: &str) -> MarketPosition {
+        MarketPosition {
+            condition_id: condition_id.to_string(),
+            asset: String::new(),
+            title: title.to_string(),
+            outcome: "Yes".to_string(),
+            outcome_index: 0,
+            event_slug: event_slug.to_string(),
+            tags: Vec::new(),
+            opposite_asset: String::new(),
+        }
+    }
+
+    #[test]
+    fn include_then_exclude() {
+        let filters: MarketFilters = toml::from_str(
+            r#"
+[include]
+events = ["nba-finals"]
+titles = ["^Bitcoin"]
+
+[exclude]
+markets = ["c2"]
+titles = ["(?i)up or down"]
+"#,
+        )
+        .unwrap();
+        assert!(filters.allows(&market("c1", "nba-finals", "Game 7")));
+        assert!(!filters.allows(&market("c2", "nba-finals", "Game 6")));
+        assert!(filters.allows(&market("c3", "btc", "Bitcoin above 100k?")));
+        assert!(!filters.allows(&market("c4", "btc", "Bitcoin Up or Down - 3PM ET")));
+        assert!(!filters.allows(&market("c5", "nfl", "Super Bowl")));
+
+        assert!(MarketFilters::default().allows(&market("c5", "nfl", "Super Bowl")));
+        let invalid = toml::from_str::<MarketFilters>("[exclude]\ntitles = [\"(\"]");
+        assert!(invalid.is_err());
+    }
+
+    #[test]
+    fn category_rules() {
+        let rules = CategoryRules {
+            include: vec!["NBA".to_string(), "nfl".to_string()],
+            exclude: vec!["politics".to_string()],
+        };
+        let categories = |tags: &[&str]| tags.iter().map(|t| t.to_string()).collect::<Vec<_>>();
+        assert!(rules.allows(&categories(&["sports", "nba"])));
+        assert!(!rules.allows(&categories(&["nba", "politics"])));
+        assert!(!rules.allows(&categories(&["crypto"])));
+        assert!(CategoryRules::default().allows(&[]));
+
+        // Gamma down for "b": it's held, not copied past the exclude rule
+        let slugs = ["a", "b", "c"].map(String::from);
+        let known = HashMap::from([
+            ("a".to_string(), categories(&["nba"])),
+            ("c".to_string(), categories(&["nba", "politics"])),
+        ]);
+        let verdicts = rules.verdicts(&slugs, &known);
+        assert_eq!(verdicts.refused, HashSet::from(["c".to_string()]));
+        assert_eq!(verdicts.unverified, HashSet::from(["b".to_string()]));
+        assert_eq!(
+            CategoryRules::default().verdicts(&slugs, &HashMap::new()),
+            CategoryVerdicts::default()
+        );
+    }
+}
@@ -0,0 +1,148 @@
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+
+/// A suspected hole in the trade stream.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Gap {
+    /// A fetch returned a full page of trades, all newer than the newest trade seen
+    /// before — older unseen trades may have fallen off the page.
+    PageOverflow {
+        limit: usize,
+        oldest_fetched_ts: i64,
+        newest_seen_ts: i64,
+    },
+    /// Successful fetches were further apart than expected (API errors, stalls,
+    /// detector disconnects), so trades in between may have been missed.
+    FetchOutage { secs: u64 },
+}
+
+impl std::fmt::Display for Gap {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Gap::PageOverflow {
+                limit,
+                oldest_fetched_ts,
+                newest_seen_ts,
+            } => write!(
+                f,
+                "all {limit} fetched trades are new (oldest at {oldest_fetched_ts}, last seen at {newest_seen_ts}) — older trades may have been missed"
+            ),
+            Gap::FetchOutage { secs } => {
+                write!(f, "no successful trade fetch for {secs}s")
+            }
+        }
+    }
+}
+
+/// Tracks trade timestamps across fetches to detect missed trades.
+pub struct GapDetector {
+    limit: usize,
+    max_fetch_interval: Duration,
+    newest_seen_ts: Option<i64>,
+    last_fetch_at: Option<DateTime<Utc>>,
+}
+
+impl GapDetector {
+    /// `limit` is the page size passed to the trades request; `max_fetch_interval`
+    /// is the longest expected time between successful fetches.
+    pub fn new(limit: usize, max_fetch_interval: Duration) -> Self {
+        Self {
+            limit,
+            max_fetch_interval,
+            newest_seen_ts: None,
+            last_fetch_at: None,
+        }
+    }
+
+    /// Record a successful fetch at `now` and return any gaps it reveals.
+    ///
+    /// `timestamps` are the exchange timestamps of every fetched trade (seen or
+    /// not); `new_count` is how many of them were not seen before.
+    pub fn observe(
+        &mut self,
+        now: DateTime<Utc>,
+        timestamps: &[i64],
+        new_count: usize,
+    ) -> Vec<Gap> {
+        let mut gaps = Vec::new();
+
+        if let Some(last) = self.last_fetch_at {
+            let elapsed = (now - last).to_std().unwrap_or_default();
+            if elapsed > self.max_fetch_interval {
+                gaps.push(Gap::FetchOutage {
+                    secs: elapsed.as_secs(),
+                });
+            }
+        }
+
+        if let (Some(newest_seen_ts), Some(&oldest_fetched_ts)) =
+            (self.newest_seen_ts, timestamps.iter().min())
+            && timestamps.len() >= self.limit
+            && new_count >= timestamps.len()
+            && oldest_fetched_ts > newest_seen_ts
+        {
+            gaps.push(Gap::PageOverflow {
+                limit: self.limit,
+                oldest_fetched_ts,
+                newest_seen_ts,
+            });
+        }
+
+        if let Some(&max) = timestamps.iter().max() {
+            self.newest_seen_ts = Some(self.newest_seen_ts.map_or(max, |n| n.max(max)));
+        }
+        self.last_fetch_at = Some(now);
+        gaps
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(secs: i64) -> DateTime<Utc> {
+        DateTime::from_timestamp(secs, 0).unwrap()
+    }
+
+    #[test]
+    fn first_fetch_never_reports_gap() {
+        let mut detector = GapDetector::new(3, Duration::from_secs(30));
+        assert!(detector.observe(at(1_000), &[900, 950, 990], 3).is_empty());
+    }
+
+    #[test]
+    fn full_page_of_new_trades_is_overflow() {
+        let mut detector = GapDetector::new(3, Duration::from_secs(30));
+        detector.observe(at(1_000), &[900, 950, 990], 3);
+        // Partial overlap: not a gap
+        assert!(
+            detector
+                .observe(at(1_010), &[990, 1_000, 1_005], 2)
+                .is_empty()
+        );
+        // Full page, all newer than anything seen
+        let gaps = detector.observe(at(1_020), &[1_011, 1_015, 1_019], 3);
+        assert_eq!(
+            gaps,
+            vec![Gap::PageOverflow {
+                limit: 3,
+                oldest_fetched_ts: 1_011,
+                newest_seen_ts: 1_005,
+            }]
+        );
+        // Fewer than `limit` trades can't have overflowed
+        assert!(detector.observe(at(1_030), &[1_025, 1_028], 2).is_empty());
+    }
+
+    #[test]
+    fn long_interval_between_fetches_is_outage() {
+        let mut detector = GapDetector::new(50, Duration::from_secs(30));
+        detector.observe(at(1_000), &[], 0);
+        assert!(detector.observe(at(1_020), &[], 0).is_empty());
+        assert_eq!(
+            detector.observe(at(1_120), &[], 0),
+            vec![Gap::FetchOutage { secs: 100 }]
+        );
+    }
+}
@@ -0,0 +1,313 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use polymarket_client_sdk::data::types::response::Position;
+use rust_decimal::prelude::ToPrimitive;
+use serde::{Deserialize, Serialize};
+
+use crate::config::GraduationConfig;
+use crate::state::TradingState;
+
+/// Paper-trading measurements taken after a rebalancing cycle.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PaperSample {
+    /// Simulated total P&L as a percentage of the initial budget.
+    pub pnl_pct: f64,
+    /// Allocation tracking error vs the trader, in percent (see `tracking_error_pct`).
+    pub tracking_error_pct: Option<f64>,
+    /// Prices the sample was measured at (asset → price).
+    pub prices: HashMap<String, f64>,
+}
+
+impl PaperSample {
+    /// Measure `state` against the trader's copied `positions` at `prices`.
+    pub fn measure(
+        state: &TradingState,
+        positions: &[Position],
+        prices: &HashMap<String, f64>,
+    ) -> Self {
+        Self {
+            pnl_pct: state.exit_summary(prices).pnl_percent,
+            tracking_error_pct: tracking_error_pct(state, positions, prices),
+            prices: prices.clone(),
+        }
+    }
+}
+
+/// Allocation distance between our holdings and the trader's positions, in
+/// percent: half the sum of absolute weight differences (0 = identical mix,
+/// 100 = nothing in common). `None` when neither side holds anything.
+pub fn tracking_error_pct(
+    state: &TradingState,
+    positions: &[Position],
+    prices: &HashMap<String, f64>,
+) -> Option<f64> {
+    let trader: HashMap<String, f64> = positions
+        .iter()
+        .map(|p| {
+            let value = p.current_value.to_f64().unwrap_or(0.0);
+            (p.asset.to_string(), value)
+        })
+        .collect();
+    let ours: HashMap<String, f64> = state
+        .holdings
+        .iter()
+        .map(|(asset, h)| {
+            let price = prices.get(asset).copied();
+            let price = price.unwrap_or(crate::money::to_f64(h.avg_cost));
+            (asset.clone(), crate::money::to_f64(h.shares) * price)
+        })
+        .collect();
+    let trader_total: f64 = trader.values().sum();
+    let ours_total: f64 = ours.values().sum();
+    match (trader_total > 0.0, ours_total > 0.0) {
+        (false, false) => return None,
+        (true, false) | (false, true) => return Some(100.0),
+        (true, true) => {}
+    }
+
+    let assets: std::collections::HashSet<&String> = trader.keys().chain(ours.keys()).collect();
+    let distance: f64 = assets
+        .into_iter()
+        .map(|asset| {
+            let w_trader = trader.get(asset).copied().unwrap_or(0.0) / trader_total;
+            let w_ours = ours.get(asset).copied().unwrap_or(0.0) / ours_total;
+            (w_trader - w_ours).abs()
+        })
+        .sum();
+    Some(distance / 2.0 * 100.0)
+}
+
+/// Where a paper session stands against the graduation criteria.
+#[derive(Debug, Clone, Serialize)]
+pub struct GraduationStatus {
+    pub days: f64,
+    /// Latest simulated P&L percentage (`None` before the first rebalance).
+    pub pnl_pct: Option<f64>,
+    /// Mean tracking error over the session (`None` before the first rebalance).
+    pub tracking_error_pct: Option<f64>,
+    /// Criteria not yet met; empty once the session may graduate.
+    pub unmet: Vec<String>,
+}
+
+impl GraduationStatus {
+    pub fn is_met(&self) -> bool {
+        self.unmet.is_empty()
+    }
+}
+
+/// Tracks a dry-run session against the `[graduation]` criteria. Kept on
+/// `TradingState::paper_track` (`Mutation::PaperStart`/`PaperSample`), so the
+/// evidence survives a restart from the WAL.
+#[derive(Debug, Clone)]
+pub struct PaperTrack {
+    started_at: DateTime<Utc>,
+    pnl_pct: Option<f64>,
+    tracking_error_total: f64,
+    tracking_error_count: u64,
+    prices: HashMap<String, f64>,
+}
+
+impl PaperTrack {
+    pub fn new(started_at: DateTime<Utc>) -> Self {
+        Self {
+            started_at,
+            pnl_pct: None,
+            tracking_error_total: 0.0,
+            tracking_error_count: 0,
+            prices: HashMap::new(),
+        }
+    }
+
+    pub fn record(&mut self, sample: PaperSample) {
+        self.pnl_pct = Some(sample.pnl_pct);
+        if let Some(te) = sample.tracking_error_pct {
+            self.tracking_error_total += te;
+            self.tracking_error_count += 1;
+        }
+        self.prices = sample.prices;
+    }
+
+    /// Prices of the latest sample, for valuing the paper state.
+    pub fn prices(&self) -> &HashMap<String, f64> {
+        &self.prices
+    }
+
+    /// Evaluate the criteria at `now`.
+    pub fn evaluate(&self, criteria: &GraduationConfig, now: DateTime<Utc>) -> GraduationStatus {
+        let days = (now - self.started_at).num_seconds().max(0) as f64 / 86_400.0;
+        let tracking_error_pct = (self.tracking_error_count > 0)
+            .then(|| self.tracking_error_total / self.tracking_error_count as f64);
+
+        let mut unmet = Vec::new();
+        if days < criteria.min_days {
+            unmet.push(format!("{days:.2} of {} days", criteria.min_days));
+        }
+        match self.pnl_pct {
+            Some(pnl) if pnl > criteria.min_pnl_pct => {}
+            Some(pnl) => unmet.push(format!(
+                "P&L {pnl:+.2}% not above {:+.2}%",
+                criteria.min_pnl_pct
+            )),
+            None => unmet.push("no simulated trades yet".to_string()),
+        }
+        match tracking_error_pct {
+            Some(te) if te <= criteria.max_tracking_error_pct => {}
+            Some(te) => unmet.push(format!(
+                "tracking error {te:.1}% above {:.1}%",
+                criteria.max_tracking_error_pct
+            )),
+            None => unmet.push("tracking error not measured yet".to_string()),
+        }
+
+        GraduationStatus {
+            days,
+            pnl_pct: self.pnl_pct,
+            tracking_error_pct,
+            unmet,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testkit::hold;
+    use crate::wal::{Mutation, WalEntry};
+    use rust_decimal_macros::dec;
+    use serde_json::json;
+
+    fn position(asset: &str, current_value: f64) -> Position {
+        serde_json::from_value(json!({
+            "proxyWallet": "0x0000000000000000000000000000000000000001",
+            "asset": asset,
+            "conditionId": "0xc1",
+            "size": "10",
+            "avgPrice": "0.50",
+            "initialValue": "5",
+            "currentValue": current_value.to_string(),
+            "cashPnl": "0",
+            "percentPnl": "0",
+            "totalBought": "10",
+            "realizedPnl": "0",
+            "percentRealizedPnl": "0",
+            "curPrice": "0.50",
+            "redeemable": false,
+            "mergeable": false,
+            "title": "",
+            "slug": "test-market",
+            "icon": "",
+            "eventSlug": "",
+            "outcome": "Yes",
+            "outcomeIndex": 0,
+            "oppositeOutcome": "No",
+            "oppositeAsset": "0xopposite",
+            "endDate": "2025-12-31",
+            "negativeRisk": false
+        }))
+        .expect("valid test Position JSON")
+    }
+
+    #[test]
+    fn tracking_error_is_half_l1_weight_distance() {
+        let positions = [position("1", 60.0), position("2", 40.0)];
+        let mut state = TradingState::new(dec!(100));
+        assert_eq!(
+            tracking_error_pct(&state, &positions, &HashMap::new()),
+            Some(100.0)
+        );
+
+        // Same 60/40 mix at a different scale
+        hold(&mut state, "1", dec!(6), dec!(0.5));
+        hold(&mut state, "2", dec!(4), dec!(0.5));
+        let te = tracking_error_pct(&state, &positions, &HashMap::new()).unwrap();
+        assert!(te.abs() < 1e-9);
+
+        // Extra 10 shares of an asset the trader doesn't hold: 50% of our value
+        hold(&mut state, "3", dec!(10), dec!(0.5));
+        let te = tracking_error_pct(&state, &positions, &HashMap::new()).unwrap();
+        assert!((te - 50.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn criteria_all_required() {
+        let criteria = GraduationConfig {
+            min_days: 2.0,
+            min_pnl_pct: 0.0,
+            max_tracking_error_pct: 10.0,
+        };
+        let start = DateTime::from_timestamp(0, 0).unwrap();
+        let mut track = PaperTrack::new(start);
+        let status = track.evaluate(&criteria, start + chrono::Duration::days(3));
+        assert_eq!(status.unmet.len(), 2);
+
+        track.record(PaperSample {
+            pnl_pct: 1.5,
+            tracking_error_pct: Some(4.0),
+            prices: HashMap::new(),
+        });
+        track.record(PaperSample {
+            pnl_pct: 2.0,
+            tracking_error_pct: Some(8.0),
+            prices: HashMap::new(),
+        });
+        assert!(
+            !track
+                .evaluate(&criteria, start + chrono::Duration::days(1))
+                .is_met()
+        );
+        let status = track.evaluate(&criteria, start + chrono::Duration::days(3));
+        assert!(status.is_met());
+        assert_eq!(status.tracking_error_pct, Some(6.0));
+
+        track.record(PaperSample {
+            pnl_pct: -0.5,
+            tracking_error_pct: None,
+            prices: HashMap::new(),
+        });
+        let status = track.evaluate(&criteria, start + chrono::Duration::days(3));
+        assert_eq!(
+            status.unmet,
+            vec!["P&L -0.50% not above +0.00%".to_string()]
+        );
+    }
+
+    #[test]
+    fn paper_track_survives_wal_replay() {
+        let criteria = GraduationConfig {
+            min_days: 2.0,
+            min_pnl_pct: 0.0,
+            max_tracking_error_pct: 10.0,
+        };
+        let start = DateTime::from_timestamp(0, 0).unwrap();
+        let mutations = [
+            Mutation::PaperStart { started_at: start },
+            Mutation::PaperSample {
+                sample: PaperSample {
+                    pnl_pct: 1.5,
+                    tracking_error_pct: Some(4.0),
+                    prices: HashMap::from([("a1".to_string(), 0.6)]),
+                },
+            },
+        ];
+        // Through JSON, as read back from the log
+        let entries: Vec<WalEntry> = mutations
+            .iter()
+            .enumerate()
+            .map(|(seq, mutation)| WalEntry {
+                seq: seq as u64,
+                mutation: serde_json::from_str(&serde_json::to_string(mutation).unwrap())
+                    .unwrap(),
+                acked: true,
+            })
+            .collect();
+
+        let mut state = TradingState::new(dec!(100));
+        state.replay(&entries);
+        let track = state.paper_track.as_ref().unwrap();
+        let status = track.evaluate(&criteria, start + chrono::Duration::days(3));
+        assert!(status.is_met());
+        assert_eq!(status.pnl_pct, Some(1.5));
+        assert_eq!(track.prices()["a1"], 0.6);
+    }
+}
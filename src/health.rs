@@ -0,0 +1,326 @@
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tracing::{info, warn};
+
+use crate::ratelimit::{self, EndpointUsage};
+use crate::types::RestingOrderStatus;
+
+/// Result of one subsystem check.
+#[derive(Debug, Clone, Serialize)]
+pub struct Check {
+    pub ok: bool,
+    pub detail: String,
+}
+
+/// Body of the `/healthz` response.
+#[derive(Debug, Clone, Serialize)]
+pub struct HealthReport {
+    pub ok: bool,
+    pub checks: BTreeMap<&'static str, Check>,
+    /// Current poll interval, stretched above the configured one while backing
+    /// off from API errors.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub poll_interval_secs: Option<f64>,
+    /// Per-endpoint request counts against Polymarket rate limits (informational).
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub rate_limits: BTreeMap<&'static str, EndpointUsage>,
+    /// Tracked resting orders as of the last cycle, oldest first (informational).
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub resting_orders: Vec<RestingOrderStatus>,
+}
+
+/// Last known status of each subsystem, updated by the main loop.
+#[derive(Debug)]
+struct HealthState {
+    started_at: DateTime<Utc>,
+    data_api: Option<Result<(), String>>,
+    /// Data API endpoint serving requests, when not the primary.
+    data_api_endpoint: Option<&'static str>,
+    /// `None` in dry-run mode (no CLOB session).
+    clob_auth: Option<Result<(), String>>,
+    ws_connected: Option<bool>,
+    last_cycle_at: Option<DateTime<Utc>>,
+    budget_remaining: f64,
+    poll_interval: Option<Duration>,
+    resting_orders: Vec<RestingOrderStatus>,
+}
+
+/// Shared, cheaply cloneable handle to the health state.
+#[derive(Debug, Clone)]
+pub struct HealthMonitor {
+    inner: Arc<Mutex<HealthState>>,
+    max_cycle_age: Duration,
+}
+
+impl HealthMonitor {
+    /// `max_cycle_age` is how long without a completed poll cycle before the
+    /// process is reported unhealthy (typically a few poll intervals).
+    pub fn new(max_cycle_age: Duration, budget: f64) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(HealthState {
+                started_at: Utc::now(),
+                data_api: None,
+                data_api_endpoint: None,
+                clob_auth: None,
+                ws_connected: None,
+                last_cycle_at: None,
+                budget_remaining: budget,
+                poll_interval: None,
+                resting_orders: Vec::new(),
+            })),
+            max_cycle_age,
+        }
+    }
+
+    fn with_state(&self, f: impl FnOnce(&mut HealthState)) {
+        if let Ok(mut state) = self.inner.lock() {
+            f(&mut state);
+        }
+    }
+
+    /// Record the outcome of the latest data API request.
+    pub fn record_data_api(&self, result: Result<(), String>) {
+        self.with_state(|s| s.data_api = Some(result));
+    }
+
+    /// Record which data API endpoint is serving requests (`primary` or
+    /// `secondary`, see `failover::DataApi`).
+    pub fn record_data_api_endpoint(&self, endpoint: &'static str) {
+        self.with_state(|s| s.data_api_endpoint = (endpoint != "primary").then_some(endpoint));
+    }
+
+    /// Record whether the CLOB session is authenticated (live mode only).
+    pub fn record_clob_auth(&self, result: Result<(), String>) {
+        self.with_state(|s| s.clob_auth = Some(result));
+    }
+
+    /// Record whether a WebSocket trade detector (RTDS or on-chain logs) is
+    /// connected. Only called when one is enabled, so the check reads "not
+    /// used" otherwise.
+    pub fn record_ws_connected(&self, connected: bool) {
+        self.with_state(|s| s.ws_connected = Some(connected));
+    }
+
+    /// Record a completed poll cycle and the budget after it.
+    pub fn record_cycle(&self, budget_remaining: f64) {
+        self.with_state(|s| {
+            s.last_cycle_at = Some(Utc::now());
+            s.budget_remaining = budget_remaining;
+        });
+    }
+
+    /// Record the current (possibly backed-off) poll interval.
+    pub fn record_poll_interval(&self, interval: Duration) {
+        self.with_state(|s| s.poll_interval = Some(interval));
+    }
+
+    /// Record the tracked resting orders (see `TradingState::resting_order_status`).
+    pub fn record_resting_orders(&self, orders: Vec<RestingOrderStatus>) {
+        self.with_state(|s| s.resting_orders = orders);
+    }
+
+    /// Evaluate every subsystem check at `now`.
+    pub fn report_at(&self, now: DateTime<Utc>) -> HealthReport {
+        let mut checks = BTreeMap::new();
+        let Ok(state) = self.inner.lock() else {
+            checks.insert("state", check(false, "health state lock poisoned"));
+            return HealthReport {
+                ok: false,
+                checks,
+                poll_interval_secs: None,
+                rate_limits: BTreeMap::new(),
+                resting_orders: Vec::new(),
+            };
+        };
+
+        checks.insert(
+            "data_api",
+            match &state.data_api {
+                None => check(true, "no requests yet"),
+                Some(Ok(())) => match state.data_api_endpoint {
+                    Some(endpoint) => {
+                        check(true, &format!("reachable (failed over to {endpoint})"))
+                    }
+                    None => check(true, "reachable"),
+                },
+                Some(Err(e)) => check(false, e),
+            },
+        );
+        checks.insert(
+            "clob_auth",
+            match &state.clob_auth {
+                None => check(true, "not used (dry-run)"),
+                Some(Ok(())) => check(true, "authenticated"),
+                Some(Err(e)) => check(false, e),
+            },
+        );
+        checks.insert(
+            "ws",
+            match state.ws_connected {
+                None => check(true, "not used"),
+                Some(true) => check(true, "connected"),
+                Some(false) => check(false, "disconnected"),
+            },
+        );
+
+        let since = state.last_cycle_at.unwrap_or(state.started_at);
+        let age = (now - since).to_std().unwrap_or_default();
+        let label = if state.last_cycle_at.is_some() {
+            "last cycle"
+        } else {
+            "started"
+        };
+        checks.insert(
+            "last_cycle",
+            check(
+                age <= self.max_cycle_age,
+                &format!("{label} {}s ago", age.as_secs()),
+            ),
+        );
+
+        let budget = state.budget_remaining;
+        checks.insert(
+            "budget",
+            check(
+                budget.is_finite() && budget >= -0.01,
+                &format!("${budget:.2} remaining"),
+            ),
+        );
+
+        let ok = checks.values().all(|c| c.ok);
+        HealthReport {
+            ok,
+            checks,
+            poll_interval_secs: state.poll_interval.map(|d| d.as_secs_f64()),
+            rate_limits: ratelimit::USAGE.snapshot(),
+            resting_orders: state.resting_orders.clone(),
+        }
+    }
+}
+
+fn check(ok: bool, detail: &str) -> Check {
+    Check {
+        ok,
+        detail: detail.to_string(),
+    }
+}
+
+/// Serve `GET /healthz` on `listener` until the task is dropped.
+///
+/// Responds `200` when every check passes and `503` otherwise, with the
+/// `HealthReport` as a JSON body — suitable for Kubernetes liveness/readiness probes.
+pub async fn serve(listener: TcpListener, monitor: HealthMonitor) {
+    if let Ok(addr) = listener.local_addr() {
+        info!("Health check listening on http://{addr}/healthz");
+    }
+    loop {
+        let (mut stream, _) = match listener.accept().await {
+            Ok(conn) => conn,
+            Err(e) => {
+                warn!("Health check accept failed: {e}");
+                continue;
+            }
+        };
+        let monitor = monitor.clone();
+        tokio::spawn(async move {
+            let mut buf = [0u8; 1024];
+            let n = stream.read(&mut buf).await.unwrap_or(0);
+            let request = String::from_utf8_lossy(&buf[..n]);
+            let path = request
+                .lines()
+                .next()
+                .and_then(|line| line.strip_prefix("GET "))
+                .and_then(|rest| rest.split_whitespace().next());
+
+            let (status, body) = match path {
+                Some("/healthz") => {
+                    let report = monitor.report_at(Utc::now());
+                    let status = if report.ok {
+                        "200 OK"
+                    } else {
+                        "503 Service Unavailable"
+                    };
+                    (status, serde_json::to_string(&report).unwrap_or_default())
+                }
+                _ => ("404 Not Found", String::from("{\"error\":\"not found\"}")),
+            };
+            let response = format!(
+                "HTTP/1.1 {status}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                body.len()
+            );
+            let _ = stream.write_all(response.as_bytes()).await;
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fresh_monitor_is_healthy() {
+        let monitor = HealthMonitor::new(Duration::from_secs(30), 100.0);
+        let report = monitor.report_at(Utc::now());
+        assert!(report.ok);
+        assert_eq!(report.checks.len(), 5);
+    }
+
+    #[test]
+    fn failing_subsystems_reported() {
+        let monitor = HealthMonitor::new(Duration::from_secs(30), 100.0);
+        monitor.record_data_api(Err("timeout".to_string()));
+        monitor.record_clob_auth(Ok(()));
+        monitor.record_cycle(-5.0);
+        monitor.record_poll_interval(Duration::from_secs(40));
+        let report = monitor.report_at(Utc::now());
+        assert_eq!(report.poll_interval_secs, Some(40.0));
+        assert!(!report.ok);
+        assert!(!report.checks["data_api"].ok);
+        assert_eq!(report.checks["data_api"].detail, "timeout");
+        assert!(report.checks["clob_auth"].ok);
+        assert!(!report.checks["budget"].ok);
+        assert!(report.checks["last_cycle"].ok);
+    }
+
+    #[test]
+    fn stale_cycle_unhealthy() {
+        let monitor = HealthMonitor::new(Duration::from_secs(30), 100.0);
+        monitor.record_cycle(100.0);
+        let later = Utc::now() + chrono::Duration::seconds(31);
+        let report = monitor.report_at(later);
+        assert!(!report.ok);
+        assert!(!report.checks["last_cycle"].ok);
+    }
+
+    #[tokio::test]
+    async fn serves_healthz_over_http() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let monitor = HealthMonitor::new(Duration::from_secs(30), 100.0);
+        monitor.record_data_api(Err("down".to_string()));
+        tokio::spawn(serve(listener, monitor));
+
+        let get = |path: &'static str| async move {
+            let mut stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+            stream
+                .write_all(format!("GET {path} HTTP/1.1\r\nHost: x\r\n\r\n").as_bytes())
+                .await
+                .unwrap();
+            let mut response = String::new();
+            stream.read_to_string(&mut response).await.unwrap();
+            response
+        };
+
+        let response = get("/healthz").await;
+        assert!(response.starts_with("HTTP/1.1 503"));
+        assert!(response.contains("\"data_api\":{\"ok\":false,\"detail\":\"down\"}"));
+        assert!(get("/other").await.starts_with("HTTP/1.1 404"));
+    }
+}
@@ -0,0 +1,193 @@
+use std::collections::{BTreeMap, HashMap};
+
+use rust_decimal::Decimal;
+use serde::Serialize;
+
+use crate::money;
+use crate::types::{OrderSide, TraderTrade};
+
+/// What a cluster of the trader's trades on one market amounts to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TradeIntent {
+    /// Adding to the market: no outcome's shares went down.
+    Accumulate,
+    /// Getting out: no outcome's shares went up.
+    Unwind,
+    /// Swapping sides: shares of one outcome sold, the other's bought.
+    Flip,
+}
+
+impl std::fmt::Display for TradeIntent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TradeIntent::Accumulate => write!(f, "accumulate"),
+            TradeIntent::Unwind => write!(f, "unwind"),
+            TradeIntent::Flip => write!(f, "flip"),
+        }
+    }
+}
+
+/// A rapid sequence of the trader's trades on one market, read as one intent.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TradeCluster {
+    pub condition_id: String,
+    pub intent: TradeIntent,
+    pub trades: usize,
+    /// Exchange timestamps (unix seconds) of the first and last trade.
+    pub first_timestamp: i64,
+    pub last_timestamp: i64,
+    /// Token ID → net change in the trader's shares over the cluster.
+    pub net_shares: BTreeMap<String, Decimal>,
+}
+
+impl TradeCluster {
+    /// `trades` must be on one market and non-empty.
+    fn from_trades(trades: &[TraderTrade]) -> Self {
+        let mut net_shares: BTreeMap<String, Decimal> = BTreeMap::new();
+        for trade in trades {
+            *net_shares.entry(trade.asset.clone()).or_default() +=
+                money::from_f64(trade.share_delta());
+        }
+        let bought = net_shares.values().any(|net| *net > Decimal::ZERO);
+        let sold = net_shares.values().any(|net| *net < Decimal::ZERO);
+        let intent = match (bought, sold) {
+            (true, true) => TradeIntent::Flip,
+            (true, false) => TradeIntent::Accumulate,
+            (false, true) => TradeIntent::Unwind,
+            // Round trip back to where it started: the latest trade's direction
+            (false, false) => match trades.last().map(|t| t.side) {
+                Some(OrderSide::Sell) => TradeIntent::Unwind,
+                _ => TradeIntent::Accumulate,
+            },
+        };
+        Self {
+            condition_id: trades[0].condition_id.clone(),
+            intent,
+            trades: trades.len(),
+            first_timestamp: trades.iter().map(|t| t.timestamp).min().unwrap_or_default(),
+            last_timestamp: trades.iter().map(|t| t.timestamp).max().unwrap_or_default(),
+            net_shares,
+        }
+    }
+}
+
+/// Groups the trader's trades into clusters across poll cycles.
+///
+/// A trade joins its market's open cluster when it comes within `window_secs`
+/// of the cluster's last trade, and starts a new one otherwise.
+#[derive(Debug)]
+pub struct IntentTracker {
+    window_secs: i64,
+    /// Condition ID → trades of its open cluster, oldest first.
+    open: HashMap<String, Vec<TraderTrade>>,
+}
+
+impl IntentTracker {
+    pub fn new(window_secs: u64) -> Self {
+        Self {
+            window_secs: i64::try_from(window_secs).unwrap_or(i64::MAX),
+            open: HashMap::new(),
+        }
+    }
+
+    /// Add newly detected `trades` and return the clusters of the markets they
+    /// touched, as they stand now. Clusters with no trade in the window before
+    /// `now` (unix seconds) are dropped.
+    pub fn observe(&mut self, trades: &[TraderTrade], now: i64) -> Vec<TradeCluster> {
+        let mut trades = trades.to_vec();
+        trades.sort_by_key(|t| t.timestamp);
+        let mut touched: Vec<String> = Vec::new();
+        for trade in trades {
+            let cluster = self.open.entry(trade.condition_id.clone()).or_default();
+            let last = cluster.iter().map(|t| t.timestamp).max();
+            if last.is_some_and(|last| (trade.timestamp - last).abs() > self.window_secs) {
+                cluster.clear();
+            }
+            if !touched.contains(&trade.condition_id) {
+                touched.push(trade.condition_id.clone());
+            }
+            cluster.push(trade);
+        }
+        let window = self.window_secs;
+        self.open.retain(|condition, cluster| {
+            touched.contains(condition)
+                || cluster
+                    .iter()
+                    .any(|t| now.saturating_sub(t.timestamp) <= window)
+        });
+        touched
+            .iter()
+            .filter_map(|condition| self.open.get(condition))
+            .map(|cluster| TradeCluster::from_trades(cluster))
+            .collect()
+    }
+
+    /// The trader's open clusters, one per market, for planning (see
+    /// `EngineParams::trader_intents`).
+    pub fn clusters(&self) -> Vec<TradeCluster> {
+        let mut clusters: Vec<TradeCluster> = self
+            .open
+            .values()
+            .filter(|cluster| !cluster.is_empty())
+            .map(|cluster| TradeCluster::from_trades(cluster))
+            .collect();
+        clusters.sort_by(|a, b| a.condition_id.cmp(&b.condition_id));
+        clusters
+    }
+
+    /// Intent of the trader's open cluster on `condition_id`, if any.
+    pub fn intent(&self, condition_id: &str) -> Option<TradeIntent> {
+        self.open
+            .get(condition_id)
+            .filter(|cluster| !cluster.is_empty())
+            .map(|cluster| TradeCluster::from_trades(cluster).intent)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trade(asset: &str, side: OrderSide, size: f64, timestamp: i64) -> TraderTrade {
+        TraderTrade {
+            tx_hash: format!("0x{asset}{timestamp}"),
+            condition_id: "c1".to_string(),
+            outcome_index: if asset == "yes" { 0 } else { 1 },
+            asset: asset.to_string(),
+            side,
+            size,
+            price: 0.5,
+            timestamp,
+        }
+    }
+
+    #[test]
+    fn rapid_trades_cluster_into_one_intent() {
+        let mut tracker = IntentTracker::new(60);
+        let clusters = tracker.observe(&[trade("yes", OrderSide::Buy, 10.0, 1_000)], 1_000);
+        assert_eq!(clusters[0].intent, TradeIntent::Accumulate);
+
+        // Sold YES and bought NO within the window, over two cycles: a flip
+        tracker.observe(&[trade("yes", OrderSide::Sell, 25.0, 1_030)], 1_030);
+        let clusters = tracker.observe(&[trade("no", OrderSide::Buy, 15.0, 1_050)], 1_050);
+        assert_eq!(clusters.len(), 1);
+        let flip = &clusters[0];
+        assert_eq!((flip.intent, flip.trades), (TradeIntent::Flip, 3));
+        assert_eq!((flip.first_timestamp, flip.last_timestamp), (1_000, 1_050));
+        assert_eq!(flip.net_shares["yes"], Decimal::from(-15));
+        assert_eq!(tracker.intent("c1"), Some(TradeIntent::Flip));
+        assert_eq!(tracker.clusters(), vec![flip.clone()]);
+
+        // After a pause, a new cluster starts
+        let clusters = tracker.observe(&[trade("no", OrderSide::Sell, 5.0, 1_200)], 1_200);
+        assert_eq!(
+            (clusters[0].intent, clusters[0].trades),
+            (TradeIntent::Unwind, 1)
+        );
+
+        // Quiet markets are forgotten
+        assert!(tracker.observe(&[], 1_300).is_empty());
+        assert_eq!(tracker.intent("c1"), None);
+    }
+}
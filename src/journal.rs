@@ -0,0 +1,454 @@
+use std::collections::{BTreeMap, HashSet};
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+use crate::money;
+use crate::state::TradingState;
+use crate::types::CopytradeEvent;
+use crate::wal::{self, Mutation};
+
+/// Money and share totals within this of each other are considered equal.
+const TOLERANCE: f64 = 1e-6;
+
+/// The parts of `TradingState` that replay must reproduce exactly.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StateSnapshot {
+    pub budget_remaining: f64,
+    pub total_spent: f64,
+    pub total_sell_proceeds: f64,
+    pub realized_pnl: f64,
+    pub total_events: u64,
+    pub total_orders: u64,
+    /// Asset → `[shares, total_cost]`.
+    pub holdings: BTreeMap<String, [f64; 2]>,
+    /// Resting order IDs, sorted.
+    pub resting_orders: Vec<String>,
+}
+
+impl StateSnapshot {
+    pub fn of(state: &TradingState) -> Self {
+        let mut resting_orders: Vec<String> = state
+            .resting_orders
+            .iter()
+            .map(|r| r.order_id.clone())
+            .collect();
+        resting_orders.sort();
+        Self {
+            budget_remaining: money::to_f64(state.budget_remaining),
+            total_spent: money::to_f64(state.total_spent),
+            total_sell_proceeds: money::to_f64(state.total_sell_proceeds),
+            realized_pnl: money::to_f64(state.realized_pnl),
+            total_events: state.total_events,
+            total_orders: state.total_orders,
+            holdings: state
+                .holdings
+                .iter()
+                .map(|(asset, h)| {
+                    let shares = money::to_f64(h.shares);
+                    (asset.clone(), [shares, money::to_f64(h.total_cost)])
+                })
+                .collect(),
+            resting_orders,
+        }
+    }
+
+    /// Fields of `self` (the replayed state) that differ from `recorded`.
+    pub fn diff(&self, recorded: &StateSnapshot) -> Vec<String> {
+        let mut diffs = Vec::new();
+        let mut money = |name: &str, replayed: f64, recorded: f64| {
+            if (replayed - recorded).abs() > TOLERANCE {
+                diffs.push(format!("{name}: replayed {replayed}, recorded {recorded}"));
+            }
+        };
+        money(
+            "budget_remaining",
+            self.budget_remaining,
+            recorded.budget_remaining,
+        );
+        money("total_spent", self.total_spent, recorded.total_spent);
+        money(
+            "total_sell_proceeds",
+            self.total_sell_proceeds,
+            recorded.total_sell_proceeds,
+        );
+        money("realized_pnl", self.realized_pnl, recorded.realized_pnl);
+        for (name, replayed, recorded) in [
+            ("total_events", self.total_events, recorded.total_events),
+            ("total_orders", self.total_orders, recorded.total_orders),
+        ] {
+            if replayed != recorded {
+                diffs.push(format!("{name}: replayed {replayed}, recorded {recorded}"));
+            }
+        }
+        let assets: std::collections::BTreeSet<&String> = self
+            .holdings
+            .keys()
+            .chain(recorded.holdings.keys())
+            .collect();
+        for asset in assets {
+            let replayed = self.holdings.get(asset).copied().unwrap_or_default();
+            let expected = recorded.holdings.get(asset).copied().unwrap_or_default();
+            if replayed
+                .iter()
+                .zip(expected)
+                .any(|(a, b)| (a - b).abs() > TOLERANCE)
+            {
+                diffs.push(format!(
+                    "holding {asset}: replayed {replayed:?}, recorded {expected:?}"
+                ));
+            }
+        }
+        if self.resting_orders != recorded.resting_orders {
+            diffs.push(format!(
+                "resting orders: replayed {:?}, recorded {:?}",
+                self.resting_orders, recorded.resting_orders
+            ));
+        }
+        diffs
+    }
+}
+
+/// One line of the journal file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum JournalRecord {
+    /// A process started trading. `resumed` sessions continue the previous
+    /// session's state (restored from the WAL); others start from `initial_budget`.
+    Session {
+        timestamp: String,
+        #[serde(with = "rust_decimal::serde::str")]
+        initial_budget: Decimal,
+        resumed: bool,
+    },
+    /// A state mutation, after it was applied. `wal_seq` is its WAL sequence
+    /// number, if a WAL is attached (a mutation re-applied from the WAL after a
+    /// crash may be recorded twice).
+    Mutation {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        wal_seq: Option<u64>,
+        mutation: Mutation,
+    },
+    /// A `CopytradeEvent` as reported on stdout.
+    Event { event: serde_json::Value },
+    /// State after the preceding records, for replay to verify against.
+    Snapshot { state: StateSnapshot },
+}
+
+/// Append-only JSON-lines journal of a trading session: every state mutation
+/// (execution results, resting-order resolutions, ...), every reported event,
+/// and a state snapshot after each event.
+///
+/// Unlike the WAL, the journal is never read back by the bot. It is an audit
+/// trail: `replay` rebuilds the state from the mutations alone and checks it
+/// against every snapshot. Writes are not synced; the WAL handles durability.
+pub struct Journal {
+    file: File,
+}
+
+impl Journal {
+    /// Open (or create) the journal at `path` for appending.
+    pub fn open(path: &Path) -> Result<Self> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("failed to open {}", path.display()))?;
+        Ok(Self { file })
+    }
+
+    pub fn record(&mut self, record: &JournalRecord) -> Result<()> {
+        let mut line =
+            serde_json::to_string(record).context("failed to serialize journal record")?;
+        line.push('\n');
+        self.file
+            .write_all(line.as_bytes())
+            .context("failed to append journal record")
+    }
+
+    pub fn record_mutation(&mut self, wal_seq: Option<u64>, mutation: &Mutation) -> Result<()> {
+        self.record(&JournalRecord::Mutation {
+            wal_seq,
+            mutation: mutation.clone(),
+        })
+    }
+
+    /// Record `event` followed by a snapshot of `state`.
+    pub fn record_event(&mut self, event: &CopytradeEvent, state: &TradingState) -> Result<()> {
+        self.record(&JournalRecord::Event {
+            event: serde_json::to_value(event).context("failed to serialize event")?,
+        })?;
+        self.record(&JournalRecord::Snapshot {
+            state: StateSnapshot::of(state),
+        })
+    }
+
+    /// Read every record of the journal at `path`. A truncated final line
+    /// (crash mid-write) is ignored. Decimals that older builds wrote as
+    /// floats are read as the strings written now (see `wal::upgrade_record`).
+    pub fn read(path: &Path) -> Result<Vec<JournalRecord>> {
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        let lines: Vec<&str> = contents.lines().collect();
+        let mut records = Vec::new();
+        for (idx, line) in lines.iter().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let record = serde_json::from_str(line).and_then(|mut record: serde_json::Value| {
+                if let Some(budget) = record.get_mut("initial_budget")
+                    && let Some(number) = budget.as_f64()
+                {
+                    *budget = number.to_string().into();
+                }
+                if let Some(mutation) = record.get_mut("mutation") {
+                    wal::decimals_to_strings(mutation);
+                }
+                serde_json::from_value(record)
+            });
+            match record {
+                Ok(record) => records.push(record),
+                Err(_) if idx + 1 == lines.len() => break,
+                Err(e) => {
+                    return Err(e).with_context(|| {
+                        format!("corrupt journal record at {}:{}", path.display(), idx + 1)
+                    });
+                }
+            }
+        }
+        Ok(records)
+    }
+}
+
+/// A snapshot the replayed state didn't match.
+#[derive(Debug, Clone, Serialize)]
+pub struct Mismatch {
+    /// Index of the snapshot record in the journal.
+    pub record: usize,
+    pub diffs: Vec<String>,
+}
+
+/// Outcome of `replay`.
+#[derive(Debug, Clone, Serialize)]
+pub struct ReplayReport {
+    pub sessions: usize,
+    pub mutations: usize,
+    /// Mutations recorded twice (re-applied from the WAL) and skipped.
+    pub duplicates: usize,
+    pub events: usize,
+    pub snapshots: usize,
+    pub mismatches: Vec<Mismatch>,
+    /// State at the end of the journal.
+    pub state: StateSnapshot,
+}
+
+/// Rebuild `TradingState` from the journal's mutations and check it against
+/// every recorded snapshot.
+///
+/// Errors if the journal doesn't start at a fresh session: a resumed session's
+/// earlier history is missing, so its state can't be rebuilt.
+pub fn replay(records: &[JournalRecord]) -> Result<ReplayReport> {
+    let mut state: Option<TradingState> = None;
+    let mut seen_seqs: HashSet<u64> = HashSet::new();
+    let mut report = ReplayReport {
+        sessions: 0,
+        mutations: 0,
+        duplicates: 0,
+        events: 0,
+        snapshots: 0,
+        mismatches: Vec::new(),
+        state: StateSnapshot::of(&TradingState::new(Decimal::ZERO)),
+    };
+    for (idx, record) in records.iter().enumerate() {
+        if let JournalRecord::Session {
+            initial_budget,
+            resumed,
+            ..
+        } = record
+        {
+            report.sessions += 1;
+            if !resumed {
+                // A fresh session also starts a fresh WAL (sequence numbers restart)
+                state = Some(TradingState::new(*initial_budget));
+                seen_seqs.clear();
+            } else if state.is_none() {
+                anyhow::bail!(
+                    "journal starts with a resumed session (record {idx}); its earlier history is missing"
+                );
+            }
+            continue;
+        }
+        let state = state
+            .as_mut()
+            .with_context(|| format!("record {idx} precedes the first session"))?;
+        match record {
+            JournalRecord::Session { .. } => unreachable!(),
+            JournalRecord::Mutation { wal_seq, mutation } => {
+                if let Some(seq) = wal_seq
+                    && !seen_seqs.insert(*seq)
+                {
+                    report.duplicates += 1;
+                    continue;
+                }
+                state.apply_mutation(mutation);
+                report.mutations += 1;
+            }
+            JournalRecord::Event { .. } => {
+                state.total_events += 1;
+                report.events += 1;
+            }
+            JournalRecord::Snapshot { state: recorded } => {
+                report.snapshots += 1;
+                let diffs = StateSnapshot::of(state).diff(recorded);
+                if !diffs.is_empty() {
+                    report.mismatches.push(Mismatch { record: idx, diffs });
+                }
+            }
+        }
+    }
+    if let Some(state) = &state {
+        report.state = StateSnapshot::of(state);
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{BuildInfo, EventTrigger, MarketPosition, OrderSide, SimulatedOrder};
+    use rust_decimal_macros::dec;
+
+    fn temp_path(name: &str) -> std::path::PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "copytrade-journal-{name}-{}.jsonl",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_file(&path);
+        path
+    }
+
+    fn buy(asset: &str, shares: Decimal, price: Decimal) -> SimulatedOrder {
+        SimulatedOrder {
+            market: MarketPosition {
+                condition_id: "0xc1".to_string(),
+                asset: asset.to_string(),
+                title: String::new(),
+                outcome: "Yes".to_string(),
+                outcome_index: 0,
+                event_slug: String::new(),
+                tags: vec![],
+                opposite_asset: String::new(),
+            },
+            side: OrderSide::Buy,
+            shares,
+            price,
+            cost_usd: shares * price,
+        }
+    }
+
+    fn event(orders: Vec<SimulatedOrder>, state: &TradingState) -> CopytradeEvent {
+        CopytradeEvent {
+            timestamp: "2026-01-01T00:00:00Z".to_string(),
+            build: BuildInfo::default(),
+            strategy_id: None,
+            trigger: EventTrigger::InitialReplication,
+            detected_trade_hashes: vec![],
+            detected_trade_timestamps: vec![],
+            trader_trades: vec![],
+            trader_intents: vec![],
+            position_changes: vec![],
+            copy_latency_secs: None,
+            orders,
+            skipped_orders: Vec::new(),
+            budget_remaining: money::to_f64(state.budget_remaining),
+            total_spent: money::to_f64(state.total_spent),
+            execution_results: None,
+        }
+    }
+
+    #[test]
+    fn replay_rebuilds_the_journaled_state() {
+        let path = temp_path("replay");
+        let mut state = TradingState::new(dec!(100));
+        state.attach_journal(Journal::open(&path).unwrap(), false);
+        let orders = vec![
+            buy("a1", dec!(10), dec!(0.50)),
+            buy("a2", dec!(4), dec!(0.25)),
+        ];
+        state.commit(Mutation::ApplyOrders {
+            orders: orders.clone(),
+        });
+        state.record_event(&event(orders, &state));
+        state.commit(Mutation::RebaseBudget { budget: dec!(80) });
+        state.record_event(&event(vec![], &state));
+        drop(state);
+
+        let records = Journal::read(&path).unwrap();
+        let report = replay(&records).unwrap();
+        assert_eq!(
+            (
+                report.sessions,
+                report.mutations,
+                report.events,
+                report.snapshots
+            ),
+            (1, 2, 2, 2)
+        );
+        assert!(report.mismatches.is_empty(), "{:?}", report.mismatches);
+        assert_eq!(report.state.holdings.len(), 2);
+        assert!((report.state.budget_remaining - 74.0).abs() < 1e-9);
+
+        // A tampered snapshot is caught
+        let mut tampered = records.clone();
+        if let Some(JournalRecord::Snapshot { state }) = tampered.last_mut() {
+            state.realized_pnl += 1.0;
+        }
+        let report = replay(&tampered).unwrap();
+        assert_eq!(report.mismatches.len(), 1);
+        assert!(report.mismatches[0].diffs[0].starts_with("realized_pnl"));
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn duplicate_wal_mutations_apply_once() {
+        let mutation = Mutation::ApplyOrders {
+            orders: vec![buy("a1", dec!(10), dec!(0.50))],
+        };
+        let session = |resumed| JournalRecord::Session {
+            timestamp: String::new(),
+            initial_budget: dec!(100),
+            resumed,
+        };
+        let applied = |seq| JournalRecord::Mutation {
+            wal_seq: Some(seq),
+            mutation: mutation.clone(),
+        };
+        // Crash after journaling #0 but before its WAL ack: the restart
+        // re-applies it from the WAL and journals it again
+        let records = vec![session(false), applied(0), session(true), applied(0)];
+        let report = replay(&records).unwrap();
+        assert_eq!((report.mutations, report.duplicates), (1, 1));
+        assert!((report.state.budget_remaining - 95.0).abs() < 1e-9);
+        assert_eq!(report.state.holdings["a1"], [10.0, 5.0]);
+
+        assert!(replay(&records[2..]).is_err());
+    }
+
+    #[test]
+    fn float_decimals_from_older_builds_read_back() {
+        let path = temp_path("floats");
+        let lines = [
+            r#"{"type":"session","timestamp":"","initial_budget":100.0,"resumed":false}"#,
+            r#"{"type":"mutation","mutation":{"kind":"rebase_budget","budget":80.5}}"#,
+        ];
+        std::fs::write(&path, lines.join("\n") + "\n").unwrap();
+        let report = replay(&Journal::read(&path).unwrap()).unwrap();
+        assert_eq!(report.mutations, 1);
+        assert!((report.state.budget_remaining - 80.5).abs() < 1e-9);
+        let _ = std::fs::remove_file(&path);
+    }
+}
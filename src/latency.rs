@@ -0,0 +1,129 @@
+use serde::Serialize;
+
+/// Upper bounds (seconds) of the copy-latency histogram buckets; a final
+/// overflow bucket catches everything slower.
+pub const LATENCY_BUCKETS_SECS: [f64; 9] = [1.0, 2.0, 5.0, 10.0, 20.0, 30.0, 60.0, 120.0, 300.0];
+
+/// One histogram bucket. `le_secs` is `None` for the overflow bucket.
+#[derive(Debug, Clone, Serialize)]
+pub struct LatencyBucket {
+    pub le_secs: Option<f64>,
+    pub count: u64,
+}
+
+/// Session copy-latency distribution, included in the exit summary.
+#[derive(Debug, Clone, Serialize)]
+pub struct LatencySummary {
+    pub count: u64,
+    pub min_secs: f64,
+    pub mean_secs: f64,
+    pub p50_secs: f64,
+    pub p90_secs: f64,
+    pub p99_secs: f64,
+    pub max_secs: f64,
+    pub buckets: Vec<LatencyBucket>,
+}
+
+/// Copy latency samples: the trader's exchange trade timestamp to our fill time.
+#[derive(Debug, Clone, Default)]
+pub struct LatencyHistogram {
+    samples: Vec<f64>,
+}
+
+impl LatencyHistogram {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record one latency sample. Negative values (clock skew) are clamped to zero.
+    pub fn record(&mut self, secs: f64) {
+        if secs.is_finite() {
+            self.samples.push(secs.max(0.0));
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    /// Summarize the recorded samples, or `None` if there are none.
+    pub fn summary(&self) -> Option<LatencySummary> {
+        if self.samples.is_empty() {
+            return None;
+        }
+        let mut sorted = self.samples.clone();
+        sorted.sort_by(f64::total_cmp);
+
+        let mut buckets: Vec<LatencyBucket> = LATENCY_BUCKETS_SECS
+            .iter()
+            .map(|&le| LatencyBucket {
+                le_secs: Some(le),
+                count: 0,
+            })
+            .collect();
+        buckets.push(LatencyBucket {
+            le_secs: None,
+            count: 0,
+        });
+        for &s in &sorted {
+            let idx = LATENCY_BUCKETS_SECS
+                .iter()
+                .position(|&le| s <= le)
+                .unwrap_or(LATENCY_BUCKETS_SECS.len());
+            buckets[idx].count += 1;
+        }
+
+        Some(LatencySummary {
+            count: sorted.len() as u64,
+            min_secs: sorted[0],
+            mean_secs: sorted.iter().sum::<f64>() / sorted.len() as f64,
+            p50_secs: percentile(&sorted, 0.50),
+            p90_secs: percentile(&sorted, 0.90),
+            p99_secs: percentile(&sorted, 0.99),
+            max_secs: sorted[sorted.len() - 1],
+            buckets,
+        })
+    }
+}
+
+/// Nearest-rank percentile of an ascending, non-empty slice.
+fn percentile(sorted: &[f64], q: f64) -> f64 {
+    let rank = (q * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_histogram_has_no_summary() {
+        assert!(LatencyHistogram::new().summary().is_none());
+    }
+
+    #[test]
+    fn summary_percentiles_and_buckets() {
+        let mut hist = LatencyHistogram::new();
+        for secs in 1..=10 {
+            hist.record(secs as f64);
+        }
+        hist.record(500.0);
+        hist.record(-2.0); // clock skew → 0
+        let summary = hist.summary().unwrap();
+
+        assert_eq!(summary.count, 12);
+        assert_eq!(summary.min_secs, 0.0);
+        assert_eq!(summary.max_secs, 500.0);
+        assert_eq!(summary.p50_secs, 5.0);
+        assert_eq!(summary.p90_secs, 10.0);
+        assert_eq!(summary.p99_secs, 500.0);
+        // ≤1s: 0 and 1; ≤2s: 2; ≤5s: 3,4,5; ≤10s: 6..=10; overflow: 500
+        let counts: Vec<u64> = summary.buckets.iter().map(|b| b.count).collect();
+        assert_eq!(counts, vec![2, 1, 3, 5, 0, 0, 0, 0, 0, 1]);
+        assert!(summary.buckets.last().unwrap().le_secs.is_none());
+    }
+}
@@ -0,0 +1,337 @@
+use std::fs::OpenOptions;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, LazyLock, RwLock};
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+/// The lease every order post is fenced by, once installed.
+static FENCE: LazyLock<RwLock<Option<Arc<Lease>>>> = LazyLock::new(Default::default);
+
+/// Fence every later order post with `lease` (see `check_fence`).
+pub fn install_fence(lease: Arc<Lease>) {
+    *FENCE.write().unwrap_or_else(|e| e.into_inner()) = Some(lease);
+}
+
+/// `Lease::check_fence` of the installed lease; always passes without one.
+pub fn check_fence() -> Result<()> {
+    let fence = FENCE.read().unwrap_or_else(|e| e.into_inner()).clone();
+    fence.map_or(Ok(()), |lease| lease.check_fence())
+}
+
+/// Contents of the lease file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LeaseRecord {
+    pub holder: String,
+    /// Unix timestamp (milliseconds) after which the lease may be taken over.
+    pub expires_at_ms: i64,
+    /// Fencing token: bumped on every change of holder, kept on renewal.
+    #[serde(default)]
+    pub token: u64,
+}
+
+/// File-based leader lease for hot-standby deployments.
+///
+/// Instances sharing a lease file (e.g. on a shared volume) call `try_acquire`
+/// periodically; only the holder of an unexpired lease acts as leader. The leader
+/// renews from a background task (`spawn_renewal`) so a long cycle can't let it
+/// expire. Each read-check-write runs holding a sidecar `.lock` file created
+/// with `O_EXCL`, so two standbys racing for an expired lease can't both win.
+///
+/// Every takeover bumps the record's fencing `token`. Once installed with
+/// `install_fence`, the lease is checked before each order post, which fails
+/// once the file names another holder or token, or the lease has expired.
+pub struct Lease {
+    path: PathBuf,
+    holder: String,
+    ttl: Duration,
+    /// Token of the lease we hold (0 = not held).
+    token: AtomicU64,
+    /// Set by the renewal task once another instance took the lease.
+    lost: AtomicBool,
+}
+
+impl Lease {
+    pub fn new(path: &Path, holder: &str, ttl: Duration) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            holder: holder.to_string(),
+            ttl,
+            token: AtomicU64::new(0),
+            lost: AtomicBool::new(false),
+        }
+    }
+
+    pub fn holder(&self) -> &str {
+        &self.holder
+    }
+
+    /// Read the current lease, if any.
+    pub fn read(&self) -> Result<Option<LeaseRecord>> {
+        if !self.path.exists() {
+            return Ok(None);
+        }
+        let contents = std::fs::read_to_string(&self.path)
+            .with_context(|| format!("failed to read {}", self.path.display()))?;
+        // An empty or half-written file counts as no lease
+        Ok(serde_json::from_str(&contents).ok())
+    }
+
+    /// Acquire the lease, or renew it if we already hold it.
+    ///
+    /// Returns `false` if another instance holds an unexpired lease.
+    pub fn try_acquire(&self) -> Result<bool> {
+        self.try_acquire_at(chrono::Utc::now().timestamp_millis())
+    }
+
+    fn try_acquire_at(&self, now_ms: i64) -> Result<bool> {
+        let _lock = self.lock()?;
+        let current = self.read()?;
+        if let Some(current) = &current
+            && current.holder != self.holder
+            && current.expires_at_ms > now_ms
+        {
+            self.token.store(0, Ordering::SeqCst);
+            return Ok(false);
+        }
+
+        // Renewing keeps our token; anything else (takeover, a restart under the
+        // same identity) fences off the previous holder with a new one
+        let held = self.token.load(Ordering::SeqCst);
+        let token = match &current {
+            Some(current) if current.holder == self.holder && current.token == held && held > 0 => {
+                held
+            }
+            Some(current) => current.token + 1,
+            None => held + 1,
+        };
+        let record = LeaseRecord {
+            holder: self.holder.clone(),
+            expires_at_ms: now_ms + self.ttl.as_millis() as i64,
+            token,
+        };
+        self.write(&record)?;
+        self.token.store(token, Ordering::SeqCst);
+        Ok(true)
+    }
+
+    /// Fail unless we still hold the lease under the token we acquired it with.
+    /// Called before every order post.
+    pub fn check_fence(&self) -> Result<()> {
+        self.check_fence_at(chrono::Utc::now().timestamp_millis())
+    }
+
+    fn check_fence_at(&self, now_ms: i64) -> Result<()> {
+        let token = self.token.load(Ordering::SeqCst);
+        if token == 0 || self.is_lost() {
+            anyhow::bail!("leader lease not held by {}", self.holder);
+        }
+        match self.read()? {
+            Some(r) if r.holder == self.holder && r.token == token && r.expires_at_ms > now_ms => {
+                Ok(())
+            }
+            Some(r) if r.holder == self.holder && r.token == token => {
+                anyhow::bail!("leader lease expired (token {token})")
+            }
+            Some(r) => anyhow::bail!(
+                "leader lease taken over by {} (token {}, ours {token})",
+                r.holder,
+                r.token
+            ),
+            None => anyhow::bail!("leader lease file is gone"),
+        }
+    }
+
+    /// Whether the renewal task found the lease taken by another instance.
+    pub fn is_lost(&self) -> bool {
+        self.lost.load(Ordering::SeqCst)
+    }
+
+    /// Renew the lease every `ttl / 3` on a blocking thread, independent of the
+    /// poll loop. Stops, marking the lease lost, once another instance holds it.
+    pub fn spawn_renewal(self: &Arc<Self>) -> tokio::task::JoinHandle<()> {
+        let lease = Arc::clone(self);
+        tokio::spawn(async move {
+            let interval = lease.ttl / 3;
+            loop {
+                tokio::time::sleep(interval).await;
+                let renewing = Arc::clone(&lease);
+                match tokio::task::spawn_blocking(move || renewing.try_acquire()).await {
+                    Ok(Ok(true)) => {}
+                    Ok(Ok(false)) => {
+                        warn!("Lost leader lease to another instance");
+                        lease.lost.store(true, Ordering::SeqCst);
+                        return;
+                    }
+                    Ok(Err(e)) => warn!("Failed to renew leader lease: {e:#}"),
+                    Err(e) => warn!("Leader lease renewal task failed: {e}"),
+                }
+            }
+        })
+    }
+
+    /// Give up the lease so a standby can take over immediately. The record is
+    /// kept, expired, so the next holder's token still counts up from ours.
+    pub fn release(&self) -> Result<()> {
+        let _lock = self.lock()?;
+        let token = self.token.swap(0, Ordering::SeqCst);
+        if let Some(record) = self.read()?
+            && record.holder == self.holder
+            && record.token == token
+        {
+            self.write(&LeaseRecord {
+                expires_at_ms: 0,
+                ..record
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Replace the lease file through a temp file + rename.
+    fn write(&self, record: &LeaseRecord) -> Result<()> {
+        let tmp = self
+            .path
+            .with_extension(format!("{}.tmp", std::process::id()));
+        let contents = serde_json::to_string(record).context("failed to serialize lease")?;
+        std::fs::write(&tmp, contents)
+            .with_context(|| format!("failed to write {}", tmp.display()))?;
+        std::fs::rename(&tmp, &self.path)
+            .with_context(|| format!("failed to replace {}", self.path.display()))
+    }
+
+    /// Take the sidecar `.lock` file, created with `O_EXCL` so only one
+    /// instance holds it; removed when the guard drops. A lock file left by a
+    /// crashed instance is broken after `STALE_LOCK`.
+    fn lock(&self) -> Result<LockGuard> {
+        let path = self.path.with_extension("lock");
+        let deadline = Instant::now() + LOCK_WAIT;
+        loop {
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(_) => return Ok(LockGuard(path)),
+                Err(e) if e.kind() == ErrorKind::AlreadyExists => {
+                    let stale = std::fs::metadata(&path)
+                        .and_then(|m| m.modified())
+                        .is_ok_and(|at| at.elapsed().is_ok_and(|age| age > STALE_LOCK));
+                    if stale {
+                        let _ = std::fs::remove_file(&path);
+                        continue;
+                    }
+                    if Instant::now() > deadline {
+                        anyhow::bail!("timed out waiting for {}", path.display());
+                    }
+                    std::thread::sleep(Duration::from_millis(10));
+                }
+                Err(e) => {
+                    return Err(e).with_context(|| format!("failed to create {}", path.display()));
+                }
+            }
+        }
+    }
+}
+
+/// How long `Lease::lock` waits for another instance's read-check-write.
+const LOCK_WAIT: Duration = Duration::from_secs(5);
+
+/// Age past which a lock file is taken to be left by a crashed instance.
+const STALE_LOCK: Duration = Duration::from_secs(30);
+
+/// Removes the lock file when dropped.
+struct LockGuard(PathBuf);
+
+impl Drop for LockGuard {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_path(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "copytrade-lease-{name}-{}.json",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_file(&path);
+        path
+    }
+
+    #[test]
+    fn only_one_holder_until_expiry() {
+        let path = temp_path("expiry");
+        let ttl = Duration::from_secs(30);
+        let a = Lease::new(&path, "a", ttl);
+        let b = Lease::new(&path, "b", ttl);
+
+        assert!(a.try_acquire_at(1_000).unwrap());
+        assert!(!b.try_acquire_at(2_000).unwrap());
+        // Leader renews, pushing expiry out
+        assert!(a.try_acquire_at(20_000).unwrap());
+        assert!(!b.try_acquire_at(40_000).unwrap());
+        // Leader stops renewing — standby takes over after expiry
+        assert!(b.try_acquire_at(50_001).unwrap());
+        assert!(!a.try_acquire_at(50_002).unwrap());
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn takeover_fences_off_the_previous_holder() {
+        let path = temp_path("fence");
+        let ttl = Duration::from_secs(30);
+        let a = Lease::new(&path, "a", ttl);
+        let b = Lease::new(&path, "b", ttl);
+
+        assert!(a.try_acquire_at(1_000).unwrap());
+        a.check_fence_at(2_000).unwrap();
+        // Expired: a stalled leader may not post even before anyone takes over
+        assert!(a.check_fence_at(31_001).is_err());
+        assert!(b.try_acquire_at(31_001).unwrap());
+        assert_eq!(b.read().unwrap().unwrap().token, 2);
+        assert!(a.check_fence_at(31_002).is_err());
+        b.check_fence_at(31_002).unwrap();
+        let _ = std::fs::remove_file(&path);
+
+        // Racing for a free lease, exactly one instance wins
+        let path = temp_path("race");
+        let winners = std::thread::scope(|scope| {
+            let racers: Vec<_> = (0..8)
+                .map(|i| {
+                    let path = &path;
+                    scope.spawn(move || {
+                        Lease::new(path, &format!("r{i}"), ttl)
+                            .try_acquire_at(1_000)
+                            .unwrap()
+                    })
+                })
+                .collect();
+            racers
+                .into_iter()
+                .map(|r| r.join().unwrap_or(false))
+                .filter(|&won| won)
+                .count()
+        });
+        assert_eq!(winners, 1);
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn release_hands_over_immediately() {
+        let path = temp_path("release");
+        let ttl = Duration::from_secs(30);
+        let a = Lease::new(&path, "a", ttl);
+        let b = Lease::new(&path, "b", ttl);
+
+        assert!(a.try_acquire().unwrap());
+        // Only the holder can release
+        b.release().unwrap();
+        assert!(!b.try_acquire().unwrap());
+        a.release().unwrap();
+        assert!(b.try_acquire().unwrap());
+        let _ = std::fs::remove_file(&path);
+    }
+}
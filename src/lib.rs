@@ -1,11 +1,52 @@
+pub mod aggregate;
 pub mod api;
+pub mod artifacts;
 pub mod auth;
+pub mod backoff;
+pub mod backtest;
+pub mod chain;
+pub mod clock;
 pub mod config;
+pub mod consensus;
+pub mod depth;
 pub mod engine;
+pub mod eventlog;
 pub mod executor;
+pub mod failover;
+pub mod filters;
+pub mod fills;
+pub mod gap;
+pub mod graduation;
+pub mod health;
+pub mod intent;
+pub mod journal;
+pub mod latency;
+pub mod lease;
+pub mod market_rules;
+pub mod milestones;
+pub mod money;
+pub mod overrides;
+pub mod prices;
+pub mod ratelimit;
+pub mod recording;
 pub mod reporter;
+pub mod retry;
+pub mod risk;
+pub mod rtds;
+pub mod secret;
+pub mod selftest;
+pub mod shadow;
 pub mod state;
+pub mod store;
+pub mod stress;
+pub mod tags;
+#[cfg(any(test, feature = "testkit"))]
+pub mod testkit;
+pub mod trades;
 pub mod types;
+pub mod wal;
+pub mod ws;
+pub mod wscapture;
 
 /// Target trader: DrPufferfish — high-volume sports bettor
 pub const TRADER_ADDRESS: &str = "0xdb27bf2ac5d428a9c63dbc914611036855a6c56e";
@@ -13,6 +54,9 @@ pub const TRADER_ADDRESS: &str = "0xdb27bf2ac5d428a9c63dbc914611036855a6c56e";
 /// Polymarket data API base URL (public, no auth required)
 pub const DATA_API_BASE: &str = "https://data-api.polymarket.com";
 
+/// Polymarket gamma API base URL (market metadata, public)
+pub const GAMMA_API_BASE: &str = "https://gamma-api.polymarket.com";
+
 /// RTDS WebSocket URL (real-time data service)
 pub const RTDS_WS_URL: &str = "wss://ws-live-data.polymarket.com";
 
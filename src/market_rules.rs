@@ -0,0 +1,115 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use anyhow::Result;
+use polymarket_client_sdk::auth::state::State;
+use polymarket_client_sdk::clob::Client as ClobClient;
+
+use crate::api::fetch_market_rules;
+use crate::types::OrderSide;
+
+/// Trading constraints of one CLOB token, checked before posting so that an
+/// order the CLOB would reject is skipped instead of failing.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MarketRules {
+    /// Price increment.
+    pub tick_size: f64,
+    /// Whether the market settles through the neg-risk exchange. The SDK signs
+    /// orders for the right exchange from the same lookup.
+    pub neg_risk: bool,
+    /// Smallest order accepted, in shares.
+    pub min_order_size: f64,
+}
+
+impl MarketRules {
+    /// Decimal places of a price on the tick grid (2 for a 0.01 tick).
+    pub fn price_decimals(&self) -> u32 {
+        (-self.tick_size.log10()).round().max(0.0) as u32
+    }
+
+    /// `price` moved onto the tick grid without making it worse — down for
+    /// buys, up for sells — and kept within one tick of 0 and 1.
+    pub fn round_price(&self, price: f64, side: OrderSide) -> f64 {
+        let ticks = price / self.tick_size;
+        let ticks = match side {
+            OrderSide::Buy => (ticks + 1e-9).floor(),
+            OrderSide::Sell => (ticks - 1e-9).ceil(),
+        };
+        let scale = 10f64.powi(self.price_decimals() as i32);
+        let rounded = (ticks * self.tick_size * scale).round() / scale;
+        rounded.clamp(self.tick_size, 1.0 - self.tick_size)
+    }
+
+    /// Why the CLOB would reject `shares` at `price`, if it would.
+    pub fn violation(&self, shares: f64, price: f64) -> Option<String> {
+        if shares + 1e-9 < self.min_order_size {
+            return Some(format!(
+                "below market minimum: {shares:.2} shares (min {})",
+                self.min_order_size
+            ));
+        }
+        if price < self.tick_size - 1e-9 || price > 1.0 - self.tick_size + 1e-9 {
+            return Some(format!(
+                "price ${price:.4} outside the market's range for tick {}",
+                self.tick_size
+            ));
+        }
+        None
+    }
+}
+
+/// `MarketRules` by token, looked up once per token for the session.
+#[derive(Debug, Default)]
+pub struct MarketRulesCache {
+    rules: Mutex<HashMap<String, MarketRules>>,
+}
+
+impl MarketRulesCache {
+    /// The rules of `token_id`, fetched on first use.
+    pub async fn get<S: State>(&self, clob: &ClobClient<S>, token_id: &str) -> Result<MarketRules> {
+        if let Some(rules) = self.rules.lock().unwrap().get(token_id) {
+            return Ok(*rules);
+        }
+        let rules = fetch_market_rules(clob, token_id).await?;
+        self.rules
+            .lock()
+            .unwrap()
+            .insert(token_id.to_string(), rules);
+        Ok(rules)
+    }
+
+    /// Drop the cached rules of `token_id`, after a tick size change.
+    pub fn forget(&self, token_id: &str) {
+        self.rules.lock().unwrap().remove(token_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rounds_to_tick_and_flags_small_orders() {
+        let rules = MarketRules {
+            tick_size: 0.01,
+            neg_risk: false,
+            min_order_size: 5.0,
+        };
+        assert_eq!(rules.price_decimals(), 2);
+        assert_eq!(rules.round_price(0.537, OrderSide::Buy), 0.53);
+        assert_eq!(rules.round_price(0.531, OrderSide::Sell), 0.54);
+        assert_eq!(rules.round_price(0.53, OrderSide::Sell), 0.53);
+        assert_eq!(rules.round_price(0.999, OrderSide::Sell), 0.99);
+
+        let fine = MarketRules {
+            tick_size: 0.001,
+            ..rules
+        };
+        assert_eq!(fine.price_decimals(), 3);
+        assert_eq!(fine.round_price(0.9875, OrderSide::Buy), 0.987);
+
+        assert!(rules.violation(5.0, 0.5).is_none());
+        assert!(rules.violation(4.99, 0.5).unwrap().contains("minimum"));
+        assert!(rules.violation(10.0, 0.995).unwrap().contains("range"));
+    }
+}
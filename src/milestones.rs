@@ -0,0 +1,223 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+
+use crate::config::MilestonesConfig;
+use crate::state::TradingState;
+use crate::types::{AlertEvent, AlertKind, AlertSeverity};
+
+/// Raises alerts when P&L crosses the `[milestones]` thresholds.
+///
+/// Total P&L alerts each time it crosses a multiple of `pnl_step_pct`, in
+/// either direction. Falling back toward zero takes an extra half step, so
+/// P&L hovering around a milestone doesn't alert every cycle. A holding
+/// alerts once when its P&L moves past ±`position_move_pct`, and again only
+/// after coming back within half of it.
+#[derive(Debug)]
+pub struct MilestoneTracker {
+    config: MilestonesConfig,
+    /// Milestone last reported, in steps from zero.
+    pnl_level: i64,
+    /// Direction (±1) of each holding's last move alert.
+    moved: HashMap<String, i64>,
+}
+
+impl MilestoneTracker {
+    pub fn new(config: MilestonesConfig) -> Self {
+        Self {
+            config,
+            pnl_level: 0,
+            moved: HashMap::new(),
+        }
+    }
+
+    /// Whether any check is enabled.
+    pub fn is_enabled(&self) -> bool {
+        self.config.pnl_step_pct > 0.0 || self.config.position_move_pct > 0.0
+    }
+
+    /// Check `state` at `prices`. Skipped while a holding has no price, since
+    /// it would count as worthless.
+    pub fn observe(
+        &mut self,
+        state: &TradingState,
+        prices: &HashMap<String, f64>,
+        now: DateTime<Utc>,
+    ) -> Vec<AlertEvent> {
+        if !state
+            .holdings
+            .keys()
+            .all(|asset| prices.contains_key(asset))
+        {
+            return Vec::new();
+        }
+        let summary = state.exit_summary(prices);
+        let mut alerts = Vec::new();
+        let alert = |severity, kind, message| AlertEvent {
+            timestamp: now.to_rfc3339(),
+            severity,
+            kind,
+            message,
+        };
+
+        let step = self.config.pnl_step_pct;
+        if step > 0.0 {
+            let level = next_pnl_level(self.pnl_level, summary.pnl_percent, step);
+            if level != self.pnl_level {
+                let outward = level * self.pnl_level >= 0 && level.abs() > self.pnl_level.abs();
+                let message = if outward {
+                    format!("Total P&L reached {:+}%", level as f64 * step)
+                } else {
+                    let milestone = (level + self.pnl_level.signum()) as f64 * step;
+                    format!("Total P&L fell back inside {milestone:+}%")
+                };
+                let severity = if level < self.pnl_level {
+                    AlertSeverity::Warning
+                } else {
+                    AlertSeverity::Info
+                };
+                let message = format!(
+                    "{message} (now {:+.1}%, ${:+.2})",
+                    summary.pnl_percent, summary.total_pnl
+                );
+                alerts.push(alert(severity, AlertKind::PnlMilestone, message));
+                self.pnl_level = level;
+            }
+        }
+
+        let threshold = self.config.position_move_pct;
+        if threshold > 0.0 {
+            self.moved
+                .retain(|asset, _| state.holdings.contains_key(asset));
+            for holding in &summary.holdings {
+                let cost = holding.shares * holding.avg_cost;
+                if cost <= 0.0 {
+                    continue;
+                }
+                let pct = holding.unrealized_pnl / cost * 100.0;
+                if pct.abs() < threshold / 2.0 {
+                    self.moved.remove(&holding.asset);
+                    continue;
+                }
+                let direction = if pct >= threshold {
+                    1
+                } else if pct <= -threshold {
+                    -1
+                } else {
+                    continue;
+                };
+                if self.moved.insert(holding.asset.clone(), direction) == Some(direction) {
+                    continue;
+                }
+                let (severity, verb) = if direction > 0 {
+                    (AlertSeverity::Info, "up")
+                } else {
+                    (AlertSeverity::Warning, "down")
+                };
+                let message = format!(
+                    "{} ({}) is {verb} {:.1}% (${:+.2} on ${:.2} cost)",
+                    holding.title,
+                    holding.outcome,
+                    pct.abs(),
+                    holding.unrealized_pnl,
+                    cost
+                );
+                alerts.push(alert(severity, AlertKind::PositionMove, message));
+            }
+        }
+        alerts
+    }
+}
+
+/// The milestone level for `pnl_pct`, given the last reported `current`.
+fn next_pnl_level(current: i64, pnl_pct: f64, step: f64) -> i64 {
+    let level = (pnl_pct / step).trunc() as i64;
+    let toward_zero = level * current >= 0 && level.abs() < current.abs();
+    if !toward_zero {
+        return level;
+    }
+    // Half a step of hysteresis on the way back
+    let slack = current.signum() as f64 * step / 2.0;
+    ((pnl_pct + slack) / step).trunc() as i64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::HeldPosition;
+    use rust_decimal::Decimal;
+    use rust_decimal_macros::dec;
+
+    fn config(pnl_step_pct: f64, position_move_pct: f64) -> MilestonesConfig {
+        MilestonesConfig {
+            pnl_step_pct,
+            position_move_pct,
+        }
+    }
+
+    fn state_holding(asset: &str, shares: Decimal, avg_cost: Decimal) -> TradingState {
+        let mut state = TradingState::new(dec!(100));
+        state.seed_holding(HeldPosition {
+            asset: asset.to_string(),
+            title: "Market A".to_string(),
+            outcome: "Yes".to_string(),
+            shares,
+            total_cost: shares * avg_cost,
+            avg_cost,
+            opposite_asset: String::new(),
+        });
+        state
+    }
+
+    fn kinds(alerts: &[AlertEvent]) -> Vec<AlertKind> {
+        alerts.iter().map(|a| a.kind).collect()
+    }
+
+    #[test]
+    fn pnl_milestones_with_hysteresis() {
+        assert_eq!(next_pnl_level(0, 4.9, 5.0), 0);
+        assert_eq!(next_pnl_level(0, 12.0, 5.0), 2);
+        assert_eq!(next_pnl_level(2, 9.0, 5.0), 2);
+        assert_eq!(next_pnl_level(2, 7.0, 5.0), 1);
+        assert_eq!(next_pnl_level(1, -6.0, 5.0), -1);
+        assert_eq!(next_pnl_level(-1, -3.0, 5.0), -1);
+        assert_eq!(next_pnl_level(-1, -2.0, 5.0), 0);
+
+        // 100 shares at $0.50 on a $100 budget: each cent is +1% total P&L
+        let state = state_holding("a1", dec!(100), dec!(0.5));
+        let mut tracker = MilestoneTracker::new(config(5.0, 0.0));
+        let at = |price: f64| HashMap::from([("a1".to_string(), price)]);
+        let now = Utc::now();
+        assert!(tracker.observe(&state, &at(0.53), now).is_empty());
+        let alerts = tracker.observe(&state, &at(0.56), now);
+        assert_eq!(kinds(&alerts), [AlertKind::PnlMilestone]);
+        assert!(alerts[0].message.starts_with("Total P&L reached +5%"));
+        assert!(tracker.observe(&state, &at(0.54), now).is_empty());
+        let alerts = tracker.observe(&state, &at(0.52), now);
+        assert!(
+            alerts[0]
+                .message
+                .starts_with("Total P&L fell back inside +5%")
+        );
+        assert_eq!(alerts[0].severity, AlertSeverity::Warning);
+        // Missing prices would read as a total loss
+        assert!(tracker.observe(&state, &HashMap::new(), now).is_empty());
+    }
+
+    #[test]
+    fn position_moves_alert_once_until_rearmed() {
+        let state = state_holding("a1", dec!(10), dec!(0.5));
+        let mut tracker = MilestoneTracker::new(config(0.0, 20.0));
+        let at = |price: f64| HashMap::from([("a1".to_string(), price)]);
+        let now = Utc::now();
+        assert!(tracker.observe(&state, &at(0.55), now).is_empty());
+        let alerts = tracker.observe(&state, &at(0.30), now);
+        assert_eq!(kinds(&alerts), [AlertKind::PositionMove]);
+        assert!(alerts[0].message.contains("is down 40.0%"));
+        assert!(tracker.observe(&state, &at(0.35), now).is_empty());
+        // Back within 10%, then past +20%
+        assert!(tracker.observe(&state, &at(0.52), now).is_empty());
+        let alerts = tracker.observe(&state, &at(0.61), now);
+        assert_eq!(alerts[0].severity, AlertSeverity::Info);
+    }
+}
@@ -0,0 +1,73 @@
+//! Money math. Budgets, share counts, order prices and P&L are `Decimal`, like
+//! the CLOB API's, so fills book exactly and repeated trading doesn't drift;
+//! so are the targets sized off them. Weights and market marks stay `f64` and
+//! meet money only here.
+
+use rust_decimal::Decimal;
+use rust_decimal::prelude::{FromPrimitive, RoundingStrategy, ToPrimitive};
+
+/// Decimal places kept when an `f64` becomes money: far below any tick or
+/// share increment, and enough to drop float noise like `9.9999999999`.
+const F64_DECIMALS: u32 = 9;
+
+/// Decimal places of USDC, the collateral: finer USD amounts can't be held.
+pub const USDC_DECIMALS: u32 = 6;
+
+/// `value` as money, rounded to `F64_DECIMALS` places (0 if not finite).
+pub fn from_f64(value: f64) -> Decimal {
+    Decimal::from_f64(value)
+        .unwrap_or_default()
+        .round_dp(F64_DECIMALS)
+}
+
+/// `value` as an `f64`, for valuations and reports.
+pub fn to_f64(value: Decimal) -> f64 {
+    value.to_f64().unwrap_or(0.0)
+}
+
+/// Round `value` toward zero to a multiple of `increment`.
+pub fn round_toward_zero(value: Decimal, increment: Decimal) -> Decimal {
+    if increment <= Decimal::ZERO {
+        return value;
+    }
+    (value / increment).round_dp_with_strategy(0, RoundingStrategy::ToZero) * increment
+}
+
+/// `numerator / denominator`, or `None` when the denominator is zero
+/// (`Decimal` division panics on it).
+pub fn ratio(numerator: Decimal, denominator: Decimal) -> Option<Decimal> {
+    numerator.checked_div(denominator)
+}
+
+/// Taker fee in USD for `shares` matched at `price`: the CLOB charges
+/// `fee_rate_bps` on the lesser of the price and its complement.
+pub fn taker_fee_usd(fee_rate_bps: u32, shares: Decimal, price: Decimal) -> Decimal {
+    let rate = Decimal::from(fee_rate_bps) / Decimal::from(10_000);
+    rate * price.min(Decimal::ONE - price).max(Decimal::ZERO) * shares
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn conversions_drop_float_noise() {
+        assert_eq!(from_f64(0.1 + 0.2), dec!(0.3));
+        assert_eq!(from_f64(9.9999999999), dec!(10));
+        assert_eq!(from_f64(f64::NAN), Decimal::ZERO);
+        assert_eq!(round_toward_zero(dec!(-2.349), dec!(0.01)), dec!(-2.34));
+        assert_eq!(round_toward_zero(dec!(7.5), Decimal::ONE), dec!(7));
+        assert_eq!(ratio(Decimal::ONE, Decimal::ZERO), None);
+        // Ten 10¢ buys add up to exactly $1, where f64 falls short
+        let spent: Decimal = (0..10).map(|_| from_f64(0.1)).sum();
+        assert_eq!(spent, Decimal::ONE);
+    }
+
+    #[test]
+    fn taker_fees_charge_the_cheaper_side() {
+        // 10% of min(p, 1 - p): 5¢ a share at 50¢
+        assert_eq!(taker_fee_usd(1000, dec!(10), dec!(0.50)), dec!(0.5));
+        assert_eq!(taker_fee_usd(1000, dec!(10), dec!(0.90)), dec!(0.1));
+    }
+}
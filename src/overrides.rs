@@ -0,0 +1,443 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use polymarket_client_sdk::data::types::response::Position;
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use tracing::{info, warn};
+
+use crate::clock;
+use crate::config::EngineParams;
+use crate::engine::{extract_market, round_shares, round_to_tick};
+use crate::money;
+use crate::state::TradingState;
+use crate::types::{MarketPosition, OrderSide, SimulatedOrder};
+use crate::wal::Mutation;
+
+/// Manual targets, orders, and controls dropped in as a JSON file (`--overrides`).
+///
+/// ```json
+/// {
+///   "targets": [{ "asset": "123", "shares": 0 }, { "asset": "456", "usd": 25 }],
+///   "orders": [{ "asset": "789", "side": "Buy", "shares": 20, "price": 0.42 }],
+///   "pin": ["321"],
+///   "exclude": ["654"]
+/// }
+/// ```
+///
+/// A target replaces the trader-derived target for its asset (0 exits). An
+/// order is placed as given. Either way the engine's own orders for the asset
+/// are dropped for that cycle. Pins and exclusions persist (see
+/// `apply_controls`) until `unpin`/`unexclude`.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct Overrides {
+    #[serde(default)]
+    pub targets: Vec<TargetOverride>,
+    #[serde(default)]
+    pub orders: Vec<OrderOverride>,
+    /// Assets never to sell automatically.
+    #[serde(default)]
+    pub pin: Vec<String>,
+    #[serde(default)]
+    pub unpin: Vec<String>,
+    /// Assets to sell and never re-enter.
+    #[serde(default)]
+    pub exclude: Vec<String>,
+    #[serde(default)]
+    pub unexclude: Vec<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct TargetOverride {
+    pub asset: String,
+    /// Target shares. Takes precedence over `usd`.
+    #[serde(default)]
+    pub shares: Option<Decimal>,
+    /// Target value in USD, at `price`.
+    #[serde(default)]
+    pub usd: Option<Decimal>,
+    /// Price to trade at. Defaults to the cycle's market price.
+    #[serde(default)]
+    pub price: Option<Decimal>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct OrderOverride {
+    pub asset: String,
+    pub side: OrderSide,
+    pub shares: Decimal,
+    /// Defaults to the cycle's market price.
+    #[serde(default)]
+    pub price: Option<Decimal>,
+}
+
+impl Overrides {
+    /// Read and consume the file at `path`, renaming it to `<path>.applied`
+    /// (or `<path>.rejected` when it doesn't parse or `validate`). `None` when
+    /// there is no file.
+    pub fn take(path: &Path) -> Result<Option<Self>> {
+        let contents = match std::fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => {
+                return Err(e).with_context(|| format!("failed to read {}", path.display()));
+            }
+        };
+        let parsed: Result<Self> = serde_json::from_str(&contents)
+            .map_err(anyhow::Error::from)
+            .and_then(|overrides: Self| overrides.validate().map(|()| overrides))
+            .with_context(|| format!("failed to parse {}", path.display()));
+        let suffix = if parsed.is_ok() {
+            "applied"
+        } else {
+            "rejected"
+        };
+        std::fs::rename(path, with_suffix(path, suffix))
+            .with_context(|| format!("failed to move {} aside", path.display()))?;
+        parsed.map(Some)
+    }
+
+    /// Check what a hand-written file can get wrong: prices strictly between 0
+    /// and 1, and no negative shares or USD amounts.
+    fn validate(&self) -> Result<()> {
+        let prices = self.targets.iter().map(|t| (&t.asset, t.price));
+        for (asset, price) in prices.chain(self.orders.iter().map(|o| (&o.asset, o.price))) {
+            if price.is_some_and(|p| p <= Decimal::ZERO || p >= Decimal::ONE) {
+                anyhow::bail!("override for {asset}: price must be between 0 and 1");
+            }
+        }
+        let targets = self.targets.iter();
+        let amounts = targets.flat_map(|t| [(&t.asset, t.shares), (&t.asset, t.usd)]);
+        let amounts = amounts.chain(self.orders.iter().map(|o| (&o.asset, Some(o.shares))));
+        for (asset, amount) in amounts {
+            if amount.is_some_and(|a| a < Decimal::ZERO) {
+                anyhow::bail!("override for {asset}: shares and usd can't be negative");
+            }
+        }
+        Ok(())
+    }
+
+    /// Record the pins and exclusions in `state` (and its write-ahead log).
+    ///
+    /// The engine never sells a pinned asset (trader exits and risk exits
+    /// included), and gives an excluded asset no target, so it is sold and not
+    /// bought again. Pinning an asset lifts its exclusion and vice versa.
+    pub fn apply_controls(&self, state: &mut TradingState) {
+        let pins = self.pin.iter().map(|a| (a, true));
+        for (asset, pinned) in pins.chain(self.unpin.iter().map(|a| (a, false))) {
+            info!("{} {asset}", if pinned { "Pinned" } else { "Unpinned" });
+            let asset = asset.clone();
+            state.commit(Mutation::Pin { asset, pinned });
+        }
+        let exclusions = self.exclude.iter().map(|a| (a, true));
+        for (asset, excluded) in exclusions.chain(self.unexclude.iter().map(|a| (a, false))) {
+            info!(
+                "{} {asset}",
+                if excluded { "Excluded" } else { "Re-admitted" }
+            );
+            let asset = asset.clone();
+            state.commit(Mutation::Exclude { asset, excluded });
+        }
+    }
+
+    /// Assets whose engine orders the overrides replace.
+    fn assets(&self) -> HashSet<&str> {
+        let targets = self.targets.iter().map(|t| t.asset.as_str());
+        targets
+            .chain(self.orders.iter().map(|o| o.asset.as_str()))
+            .collect()
+    }
+
+    /// Merge the overrides into the engine's `orders`: the overrides' orders
+    /// replace the engine's for their assets, sells come first, and buys are
+    /// kept (overrides first) while the budget and sells' proceeds cover them,
+    /// net of taker fees. Manual orders are rounded like the engine's, and buys
+    /// pass its gates: none while the kill switch has tripped or buys are
+    /// paused, and none under `min_order_usd`. Every dropped buy is logged.
+    pub fn merge(
+        &self,
+        orders: Vec<SimulatedOrder>,
+        state: &TradingState,
+        positions: &[Position],
+        prices: &HashMap<String, f64>,
+        params: &EngineParams,
+    ) -> Vec<SimulatedOrder> {
+        let overridden = self.assets();
+        let market_price = |asset: &String| prices.get(asset).copied().map(money::from_f64);
+        let mut manual = Vec::new();
+        for target in &self.targets {
+            let Some(price) = target.price.or_else(|| market_price(&target.asset)) else {
+                warn!("Override for {}: no price, skipping", target.asset);
+                continue;
+            };
+            let target_shares = match (target.shares, target.usd) {
+                (Some(shares), _) => Some(shares),
+                (None, Some(usd)) => money::ratio(usd, price),
+                _ => None,
+            };
+            let Some(target_shares) = target_shares else {
+                warn!(
+                    "Override for {}: needs shares, or usd at a price",
+                    target.asset
+                );
+                continue;
+            };
+            let held = state.effective_held_shares(&target.asset);
+            let diff = target_shares.max(Decimal::ZERO) - held;
+            // Sells never go past what is held
+            let (side, shares) = if diff > Decimal::ZERO {
+                (OrderSide::Buy, diff)
+            } else {
+                (OrderSide::Sell, (-diff).min(held))
+            };
+            let asset = &target.asset;
+            manual.extend(order(state, positions, params, asset, side, shares, price));
+        }
+        for o in &self.orders {
+            let Some(price) = o.price.or_else(|| market_price(&o.asset)) else {
+                warn!("Override order for {}: no price, skipping", o.asset);
+                continue;
+            };
+            let shares = match o.side {
+                OrderSide::Buy => o.shares,
+                OrderSide::Sell => o.shares.min(state.effective_held_shares(&o.asset)),
+            };
+            manual.extend(order(
+                state, positions, params, &o.asset, o.side, shares, price,
+            ));
+        }
+        for o in &manual {
+            info!(
+                "Manual override: {:?} {:.2} shares of \"{}\" ({}) at {:.4}",
+                o.side, o.shares, o.market.title, o.market.outcome, o.price
+            );
+        }
+
+        let engine = orders
+            .into_iter()
+            .filter(|o| !overridden.contains(o.market.asset.as_str()));
+        let (sells, buys): (Vec<_>, Vec<_>) = manual
+            .into_iter()
+            .chain(engine)
+            .partition(|o| o.side == OrderSide::Sell);
+        let fee = |o: &SimulatedOrder| {
+            money::taker_fee_usd(state.fee_rate_bps(&o.market.asset), o.shares, o.price)
+        };
+        let proceeds: Decimal = sells.iter().map(|o| o.cost_usd - fee(o)).sum();
+        let mut available = state.budget_remaining + proceeds;
+        let min_order_usd = money::from_f64(params.min_order_usd);
+        let now_ms = clock::now().timestamp_millis();
+        let mut merged = sells;
+        for buy in buys {
+            let cost = buy.cost_usd + fee(&buy);
+            let reason = if state.killed {
+                "the kill switch has tripped".to_string()
+            } else if state.buys_paused(now_ms) {
+                "buys are paused".to_string()
+            } else if buy.cost_usd < min_order_usd {
+                format!("${:.2} is under the ${min_order_usd} minimum", buy.cost_usd)
+            } else if cost > available {
+                format!("${cost:.2} with fees is over the ${available:.2} left")
+            } else {
+                available -= cost;
+                merged.push(buy);
+                continue;
+            };
+            info!(
+                "Dropping buy of \"{}\" ({}): {reason}",
+                buy.market.title, buy.market.outcome
+            );
+        }
+        merged
+    }
+}
+
+/// A manual order, rounded like the engine's: shares toward zero by
+/// `round_shares`, the price to the market's tick.
+fn order(
+    state: &TradingState,
+    positions: &[Position],
+    params: &EngineParams,
+    asset: &str,
+    side: OrderSide,
+    shares: Decimal,
+    price: Decimal,
+) -> Option<SimulatedOrder> {
+    let shares = round_shares(shares, params);
+    let price = round_to_tick(price, state.tick_size(asset));
+    if shares <= Decimal::ZERO {
+        return None;
+    }
+    Some(SimulatedOrder {
+        market: market(state, positions, asset),
+        side,
+        shares,
+        price,
+        cost_usd: shares * price,
+    })
+}
+
+/// Market metadata from the trader's positions or our holdings.
+fn market(state: &TradingState, positions: &[Position], asset: &str) -> MarketPosition {
+    if let Some(pos) = positions.iter().find(|p| p.asset == asset) {
+        return extract_market(pos);
+    }
+    let held = state.holdings.get(asset);
+    MarketPosition {
+        condition_id: String::new(),
+        asset: asset.to_string(),
+        title: held.map_or_else(|| format!("asset {asset}"), |h| h.title.clone()),
+        outcome: held.map(|h| h.outcome.clone()).unwrap_or_default(),
+        outcome_index: 0,
+        event_slug: String::new(),
+        tags: state.asset_tags.get(asset).cloned().unwrap_or_default(),
+        opposite_asset: held.map(|h| h.opposite_asset.clone()).unwrap_or_default(),
+    }
+}
+
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".");
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::HeldPosition;
+    use rust_decimal_macros::dec;
+
+    fn engine_buy(asset: &str, shares: Decimal, price: Decimal) -> SimulatedOrder {
+        SimulatedOrder {
+            market: MarketPosition {
+                condition_id: String::new(),
+                asset: asset.to_string(),
+                title: String::new(),
+                outcome: String::new(),
+                outcome_index: 0,
+                event_slug: String::new(),
+                tags: Vec::new(),
+                opposite_asset: String::new(),
+            },
+            side: OrderSide::Buy,
+            shares,
+            price,
+            cost_usd: shares * price,
+        }
+    }
+
+    #[test]
+    fn overrides_replace_engine_orders() {
+        let mut state = TradingState::new(dec!(30));
+        state.seed_holding(HeldPosition {
+            asset: "held".to_string(),
+            title: "Held market".to_string(),
+            outcome: "Yes".to_string(),
+            shares: dec!(20),
+            total_cost: dec!(10),
+            avg_cost: dec!(0.5),
+            opposite_asset: String::new(),
+        });
+        let overrides: Overrides = serde_json::from_str(
+            r#"{
+                "targets": [{ "asset": "held", "shares": 0 }, { "asset": "a1", "usd": 10 }],
+                "orders": [{ "asset": "a2", "side": "Buy", "shares": 10, "price": 0.5 }]
+            }"#,
+        )
+        .unwrap();
+        let prices = HashMap::from([("held".to_string(), 0.6), ("a1".to_string(), 0.25)]);
+        let engine = vec![
+            engine_buy("a1", dec!(100), dec!(0.25)),
+            engine_buy("a3", dec!(40), dec!(0.5)),
+            engine_buy("a4", dec!(20), dec!(0.5)),
+        ];
+
+        let params = EngineParams::default();
+        let merged = overrides.merge(engine, &state, &[], &prices, &params);
+        let summary: Vec<(&str, OrderSide, Decimal)> = merged
+            .iter()
+            .map(|o| (o.market.asset.as_str(), o.side, o.cost_usd))
+            .collect();
+        // $20 cash + $12 from the exit: a1 and a2 first, then a4 fits but a3 doesn't
+        assert_eq!(
+            summary,
+            [
+                ("held", OrderSide::Sell, dec!(12)),
+                ("a1", OrderSide::Buy, dec!(10)),
+                ("a2", OrderSide::Buy, dec!(5)),
+                ("a4", OrderSide::Buy, dec!(10)),
+            ]
+        );
+        assert_eq!(merged[0].market.title, "Held market");
+    }
+
+    #[test]
+    fn target_sells_stop_at_holdings_and_bad_files_are_rejected() {
+        let mut state = TradingState::new(dec!(30));
+        state.seed_holding(HeldPosition {
+            asset: "held".to_string(),
+            title: "Held market".to_string(),
+            outcome: "Yes".to_string(),
+            shares: dec!(20),
+            total_cost: dec!(10),
+            avg_cost: dec!(0.5),
+            opposite_asset: String::new(),
+        });
+        let overrides = Overrides {
+            targets: vec![TargetOverride {
+                asset: "held".to_string(),
+                shares: Some(dec!(-50)),
+                usd: None,
+                price: Some(dec!(0.5)),
+            }],
+            ..Default::default()
+        };
+        let params = EngineParams::default();
+        let merged = overrides.merge(Vec::new(), &state, &[], &HashMap::new(), &params);
+        assert_eq!(merged[0].shares, dec!(20));
+
+        let path =
+            std::env::temp_dir().join(format!("copytrade-overrides-{}.json", std::process::id()));
+        for bad in [
+            r#"{ "orders": [{ "asset": "a", "side": "Buy", "shares": 10, "price": 1.5 }] }"#,
+            r#"{ "targets": [{ "asset": "a", "usd": 10, "price": 0 }] }"#,
+            r#"{ "targets": [{ "asset": "a", "shares": -5 }] }"#,
+        ] {
+            std::fs::write(&path, bad).unwrap();
+            assert!(Overrides::take(&path).is_err());
+            assert!(with_suffix(&path, "rejected").exists());
+        }
+        std::fs::remove_file(with_suffix(&path, "rejected")).unwrap();
+    }
+
+    #[test]
+    fn manual_buys_are_rounded_gated_and_budgeted_with_fees() {
+        let mut state = TradingState::new(dec!(10));
+        state.fee_rates.insert("a1".to_string(), 1000);
+        state.fee_rates.insert("a3".to_string(), 1000);
+        let overrides: Overrides = serde_json::from_str(
+            r#"{ "orders": [
+                { "asset": "a1", "side": "Buy", "shares": 10.005, "price": 0.503 },
+                { "asset": "a2", "side": "Buy", "shares": 1, "price": 0.5 },
+                { "asset": "a3", "side": "Buy", "shares": 8.5, "price": 0.5 }
+            ] }"#,
+        )
+        .unwrap();
+        let params = EngineParams::default();
+        // a1 costs $5 + $0.50 in fees; a2 is under the $1 minimum; a3's $4.25
+        // would fit the $4.50 left, but not with its $0.425 fee
+        let merged = overrides.merge(Vec::new(), &state, &[], &HashMap::new(), &params);
+        let summary: Vec<(&str, Decimal, Decimal)> = merged
+            .iter()
+            .map(|o| (o.market.asset.as_str(), o.shares, o.price))
+            .collect();
+        assert_eq!(summary, [("a1", dec!(10), dec!(0.5))]);
+
+        // No manual buys while the daily loss pause holds
+        state.paused_until = Some(clock::now() + chrono::Duration::hours(1));
+        let merged = overrides.merge(Vec::new(), &state, &[], &HashMap::new(), &params);
+        assert!(merged.is_empty());
+    }
+}
@@ -0,0 +1,473 @@
+use std::collections::{BTreeSet, HashMap};
+use std::sync::{Arc, Mutex};
+
+use polymarket_client_sdk::data::types::response::Position;
+use rust_decimal::Decimal;
+use rust_decimal::prelude::FromPrimitive;
+use serde::Deserialize;
+use serde_json::json;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use tokio_tungstenite::tungstenite::Message;
+use tracing::info;
+
+use crate::ws::{Keepalive, ReconnectPolicy, WsEvent, WsFeed};
+
+/// Above this spread the midpoint says little about where the market trades,
+/// so the last trade price is used instead (as on polymarket.com).
+pub const MAX_MIDPOINT_SPREAD: f64 = 0.10;
+
+/// Latest market data for one token.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Mark {
+    pub best_bid: Option<f64>,
+    pub best_ask: Option<f64>,
+    pub last_trade: Option<f64>,
+}
+
+impl Mark {
+    pub fn midpoint(&self) -> Option<f64> {
+        Some((self.best_bid? + self.best_ask?) / 2.0)
+    }
+
+    /// The midpoint while the spread is at most `MAX_MIDPOINT_SPREAD`, else the
+    /// last trade price (falling back to the midpoint without one).
+    pub fn price(&self) -> Option<f64> {
+        let tight = match (self.best_bid, self.best_ask) {
+            (Some(bid), Some(ask)) => ask - bid <= MAX_MIDPOINT_SPREAD + 1e-9,
+            _ => false,
+        };
+        let price = if tight {
+            self.midpoint()
+        } else {
+            self.last_trade.or(self.midpoint())
+        };
+        price.filter(|p| *p > 0.0 && *p < 1.0)
+    }
+}
+
+/// An update from the CLOB market channel, for one token.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MarketUpdate {
+    /// Top of book (from `book` snapshots, `price_change`, and `best_bid_ask`).
+    Quote {
+        asset: String,
+        best_bid: Option<f64>,
+        best_ask: Option<f64>,
+    },
+    LastTrade {
+        asset: String,
+        price: f64,
+    },
+}
+
+#[derive(Deserialize)]
+struct RawEvent {
+    event_type: String,
+    #[serde(default)]
+    asset_id: String,
+    #[serde(default)]
+    price: Option<String>,
+    #[serde(default)]
+    best_bid: Option<String>,
+    #[serde(default)]
+    best_ask: Option<String>,
+    #[serde(default)]
+    bids: Vec<RawLevel>,
+    #[serde(default)]
+    asks: Vec<RawLevel>,
+    #[serde(default)]
+    price_changes: Vec<RawPriceChange>,
+}
+
+#[derive(Deserialize)]
+struct RawLevel {
+    price: String,
+}
+
+#[derive(Deserialize)]
+struct RawPriceChange {
+    asset_id: String,
+    #[serde(default)]
+    best_bid: Option<String>,
+    #[serde(default)]
+    best_ask: Option<String>,
+}
+
+fn num(value: Option<&str>) -> Option<f64> {
+    value?.parse().ok().filter(|p: &f64| *p > 0.0)
+}
+
+/// Parse a market-channel frame (a single event or an array of them, as after
+/// subscribing). Other event types are skipped.
+pub fn parse_updates(msg: &Message) -> Vec<MarketUpdate> {
+    let Message::Text(text) = msg else {
+        return Vec::new();
+    };
+    let Ok(value) = serde_json::from_str::<serde_json::Value>(text.as_str()) else {
+        return Vec::new();
+    };
+    let values = match value {
+        serde_json::Value::Array(values) => values,
+        value => vec![value],
+    };
+    let mut updates = Vec::new();
+    for raw in values
+        .into_iter()
+        .filter_map(|v| serde_json::from_value::<RawEvent>(v).ok())
+    {
+        match raw.event_type.as_str() {
+            "book" => {
+                // Level order isn't guaranteed, so take the extremes
+                let prices = |levels: &[RawLevel]| {
+                    levels
+                        .iter()
+                        .filter_map(|l| num(Some(&l.price)))
+                        .collect::<Vec<f64>>()
+                };
+                updates.push(MarketUpdate::Quote {
+                    asset: raw.asset_id,
+                    best_bid: prices(&raw.bids).into_iter().reduce(f64::max),
+                    best_ask: prices(&raw.asks).into_iter().reduce(f64::min),
+                });
+            }
+            "best_bid_ask" => updates.push(MarketUpdate::Quote {
+                asset: raw.asset_id,
+                best_bid: num(raw.best_bid.as_deref()),
+                best_ask: num(raw.best_ask.as_deref()),
+            }),
+            "price_change" => {
+                // Entries without the top of book say nothing about the mark
+                updates.extend(
+                    raw.price_changes
+                        .into_iter()
+                        .filter(|c| c.best_bid.is_some() || c.best_ask.is_some())
+                        .map(|c| MarketUpdate::Quote {
+                            asset: c.asset_id,
+                            best_bid: num(c.best_bid.as_deref()),
+                            best_ask: num(c.best_ask.as_deref()),
+                        }),
+                );
+            }
+            "last_trade_price" => {
+                if let Some(price) = num(raw.price.as_deref()) {
+                    updates.push(MarketUpdate::LastTrade {
+                        asset: raw.asset_id,
+                        price,
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+    updates
+}
+
+/// Subscription frame for the market channel.
+pub fn market_subscription(assets: &BTreeSet<String>) -> String {
+    json!({ "type": "market", "assets_ids": assets, "custom_feature_enabled": true }).to_string()
+}
+
+#[derive(Default)]
+struct Marks {
+    marks: HashMap<String, Mark>,
+    tracked: BTreeSet<String>,
+    connected: bool,
+    /// Requested tokens → the `touch` that last used them (0 = never).
+    last_used: HashMap<String, u64>,
+    touches: u64,
+}
+
+/// Live prices from the CLOB market channel, shared by every consumer of the
+/// poll cycle's price map.
+///
+/// Cheap to clone. Marks are only served while the feed is connected; they are
+/// dropped on disconnect and rebuilt from the book snapshots sent on reconnect.
+#[derive(Clone, Default)]
+pub struct PriceCache {
+    marks: Arc<Mutex<Marks>>,
+    subscriptions: Option<Arc<watch::Sender<Vec<String>>>>,
+    /// Most tokens subscribed at once (`settings.ws_prices_max_assets`).
+    max_assets: Option<usize>,
+}
+
+impl PriceCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Connect to the market channel at `url`, subscribing to at most
+    /// `max_assets` tokens at once. No assets are subscribed until `track` is
+    /// called.
+    pub fn spawn(url: &str, max_assets: usize) -> (Self, JoinHandle<()>) {
+        let (tx, rx) = watch::channel(Vec::new());
+        let feed = WsFeed {
+            name: "clob_market",
+            url: url.to_string(),
+            keepalive: Keepalive::CLOB,
+            idle_timeout: None,
+            policy: ReconnectPolicy::default(),
+            subscriptions: rx,
+        };
+        let (mut events, _) = feed.spawn();
+        let cache = Self {
+            marks: Arc::default(),
+            subscriptions: Some(Arc::new(tx)),
+            max_assets: Some(max_assets),
+        };
+        let handle = tokio::spawn({
+            let cache = cache.clone();
+            async move {
+                while let Some(event) = events.recv().await {
+                    cache.handle(event);
+                }
+            }
+        });
+        (cache, handle)
+    }
+
+    fn handle(&self, event: WsEvent) {
+        match event {
+            WsEvent::Connected | WsEvent::Reconnected { .. } => {
+                self.marks.lock().unwrap().connected = true;
+            }
+            WsEvent::Disconnected { .. } => {
+                let mut marks = self.marks.lock().unwrap();
+                marks.connected = false;
+                marks.marks.clear();
+            }
+            WsEvent::Message(msg) => {
+                for update in parse_updates(&msg) {
+                    self.apply(update);
+                }
+            }
+        }
+    }
+
+    pub fn apply(&self, update: MarketUpdate) {
+        let mut marks = self.marks.lock().unwrap();
+        match update {
+            MarketUpdate::Quote {
+                asset,
+                best_bid,
+                best_ask,
+            } => {
+                let mark = marks.marks.entry(asset).or_default();
+                mark.best_bid = best_bid;
+                mark.best_ask = best_ask;
+            }
+            MarketUpdate::LastTrade { asset, price } => {
+                marks.marks.entry(asset).or_default().last_trade = Some(price);
+            }
+        }
+    }
+
+    /// Count `assets` as used now (tokens we hold or have orders on, tokens the
+    /// trader just traded), so `track` keeps them over the cap.
+    pub fn touch(&self, assets: impl IntoIterator<Item = String>) {
+        let mut marks = self.marks.lock().unwrap();
+        marks.touches += 1;
+        let now = marks.touches;
+        for asset in assets {
+            marks.last_used.insert(asset, now);
+        }
+    }
+
+    /// Subscribe to `assets`, the tokens we hold or target (resubscribing only
+    /// when the set changed), so tokens no longer requested are dropped. Beyond
+    /// `max_assets`, the least recently used are left out (see `touch`); on a
+    /// tie, subscribed tokens keep their place.
+    pub fn track(&self, assets: impl IntoIterator<Item = String>) {
+        let requested: BTreeSet<String> = assets.into_iter().collect();
+        let mut marks = self.marks.lock().unwrap();
+        marks.last_used.retain(|asset, _| requested.contains(asset));
+        for asset in &requested {
+            marks.last_used.entry(asset.clone()).or_insert(0);
+        }
+        let assets: BTreeSet<String> = match self.max_assets {
+            Some(max) if requested.len() > max => {
+                let mut by_use: Vec<(&String, u64, bool)> = marks
+                    .last_used
+                    .iter()
+                    .map(|(asset, used)| (asset, *used, marks.tracked.contains(asset)))
+                    .collect();
+                by_use.sort_by(|a, b| (b.1, b.2).cmp(&(a.1, a.2)).then(a.0.cmp(b.0)));
+                by_use
+                    .into_iter()
+                    .take(max)
+                    .map(|(asset, ..)| asset.clone())
+                    .collect()
+            }
+            _ => requested.clone(),
+        };
+        if marks.tracked == assets {
+            return;
+        }
+        if assets.len() < requested.len() {
+            info!(
+                "Subscribing to {} of {} tokens (ws_prices_max_assets); the rest use API prices",
+                assets.len(),
+                requested.len()
+            );
+        }
+        marks.marks.retain(|asset, _| assets.contains(asset));
+        if let Some(tx) = &self.subscriptions {
+            let frames = if assets.is_empty() {
+                Vec::new()
+            } else {
+                vec![market_subscription(&assets)]
+            };
+            tx.send_replace(frames);
+        }
+        marks.tracked = assets;
+    }
+
+    /// Current price of `asset` (see `Mark::price`), if known.
+    pub fn price(&self, asset: &str) -> Option<f64> {
+        let marks = self.marks.lock().unwrap();
+        if self.subscriptions.is_some() && !marks.connected {
+            return None;
+        }
+        marks.marks.get(asset)?.price()
+    }
+
+    /// Replace the prices in `prices` with live marks where known.
+    pub fn overlay(&self, prices: &mut HashMap<String, f64>) {
+        for (asset, price) in prices.iter_mut() {
+            if let Some(mark) = self.price(asset) {
+                *price = mark;
+            }
+        }
+    }
+
+    /// Re-mark `positions` at live prices (`cur_price`, and `current_value` to
+    /// match), so weights, targets, and capital use them.
+    pub fn mark_positions(&self, positions: &mut [Position]) {
+        for p in positions {
+            if let Some(mark) = self.price(&p.asset).and_then(Decimal::from_f64) {
+                p.cur_price = mark;
+                p.current_value = p.size * mark;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(value: serde_json::Value) -> Message {
+        Message::Text(value.to_string().into())
+    }
+
+    #[test]
+    fn parses_market_channel_events() {
+        let updates = parse_updates(&frame(json!([
+            {
+                "event_type": "book", "asset_id": "a1", "market": "0xc1",
+                "bids": [{ "price": "0.40", "size": "10" }, { "price": "0.45", "size": "5" }],
+                "asks": [{ "price": "0.52", "size": "10" }, { "price": "0.48", "size": "5" }]
+            },
+            { "event_type": "last_trade_price", "asset_id": "a1", "price": "0.47", "size": "3" },
+            {
+                "event_type": "price_change", "market": "0xc1",
+                "price_changes": [{ "asset_id": "a2", "price": "0.3", "size": "1", "side": "BUY",
+                                    "best_bid": "0.30", "best_ask": "0.33" }]
+            },
+            { "event_type": "tick_size_change", "asset_id": "a1" }
+        ])));
+        assert_eq!(
+            updates,
+            vec![
+                MarketUpdate::Quote {
+                    asset: "a1".to_string(),
+                    best_bid: Some(0.45),
+                    best_ask: Some(0.48),
+                },
+                MarketUpdate::LastTrade {
+                    asset: "a1".to_string(),
+                    price: 0.47,
+                },
+                MarketUpdate::Quote {
+                    asset: "a2".to_string(),
+                    best_bid: Some(0.30),
+                    best_ask: Some(0.33),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn mark_uses_midpoint_unless_the_spread_is_wide() {
+        let cache = PriceCache::new();
+        for update in [
+            MarketUpdate::Quote {
+                asset: "a1".to_string(),
+                best_bid: Some(0.45),
+                best_ask: Some(0.49),
+            },
+            MarketUpdate::LastTrade {
+                asset: "a1".to_string(),
+                price: 0.50,
+            },
+            MarketUpdate::Quote {
+                asset: "a2".to_string(),
+                best_bid: Some(0.20),
+                best_ask: Some(0.60),
+            },
+            MarketUpdate::LastTrade {
+                asset: "a2".to_string(),
+                price: 0.25,
+            },
+        ] {
+            cache.apply(update);
+        }
+        assert!((cache.price("a1").unwrap() - 0.47).abs() < 1e-9);
+        assert_eq!(cache.price("a2"), Some(0.25));
+        assert_eq!(cache.price("a3"), None);
+
+        let mut prices = HashMap::from([("a1".to_string(), 0.40), ("a3".to_string(), 0.90)]);
+        cache.overlay(&mut prices);
+        assert!((prices["a1"] - 0.47).abs() < 1e-9);
+        assert_eq!(prices["a3"], 0.90);
+
+        // Untracked assets are dropped
+        cache.track(["a2".to_string()]);
+        assert_eq!(cache.price("a1"), None);
+    }
+
+    #[test]
+    fn tracking_over_the_cap_evicts_the_least_recently_used() {
+        let cache = PriceCache {
+            max_assets: Some(2),
+            ..PriceCache::new()
+        };
+        let tracked = || -> Vec<String> {
+            cache
+                .marks
+                .lock()
+                .unwrap()
+                .tracked
+                .iter()
+                .cloned()
+                .collect()
+        };
+        let assets = |list: &[&str]| list.iter().map(|a| a.to_string()).collect::<Vec<_>>();
+
+        // The held token is used every cycle; untouched tokens come last
+        cache.touch(assets(&["held"]));
+        cache.track(assets(&["held", "a", "b"]));
+        assert_eq!(tracked(), assets(&["a", "held"]));
+        // The trader trades b: a has gone longest without use
+        cache.touch(assets(&["held", "b"]));
+        cache.track(assets(&["held", "a", "b"]));
+        assert_eq!(tracked(), assets(&["b", "held"]));
+        // Ties keep the subscribed token
+        cache.touch(assets(&["held", "a", "b"]));
+        cache.track(assets(&["held", "a", "b"]));
+        assert_eq!(tracked(), assets(&["b", "held"]));
+        // A closed position frees its slot
+        cache.track(assets(&["a", "b"]));
+        assert_eq!(tracked(), assets(&["a", "b"]));
+    }
+}
@@ -0,0 +1,296 @@
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::sync::{LazyLock, Mutex};
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use tracing::warn;
+
+/// Polymarket rate limits are enforced over 10-second windows.
+pub const RATE_WINDOW: Duration = Duration::from_secs(10);
+
+/// Endpoints we call, with their default request limit per `RATE_WINDOW`.
+///
+/// Approximations of Polymarket's published limits; override them under
+/// `[rate_limits]` in `config.toml` when the documented values change.
+pub const DEFAULT_LIMITS: &[(&str, u32)] = &[
+    ("data:/positions", 150),
+    ("data:/trades", 75),
+    ("data:/activity", 75),
+    ("gamma:/markets", 125),
+    ("gamma:/events", 100),
+    ("clob:POST /order", 2400),
+    ("clob:GET /order", 900),
+    ("clob:DELETE /order", 3000),
+    ("clob:DELETE /orders", 1000),
+    ("clob:DELETE /cancel-all", 250),
+    ("clob:/book", 1500),
+    ("clob:/prices-history", 1000),
+    ("clob:/balance-allowance", 125),
+];
+
+/// Usage of one endpoint over the trailing window.
+#[derive(Debug, Clone, Serialize)]
+pub struct EndpointUsage {
+    pub requests: u32,
+    pub limit: u32,
+    /// `requests / limit`; above 1.0 expect 429s.
+    pub utilization: f64,
+    /// Highest request count seen in any window this session.
+    pub peak_requests: u32,
+}
+
+/// Sliding-window request counts per endpoint.
+#[derive(Debug, Default)]
+pub struct RateUsage {
+    inner: Mutex<UsageState>,
+}
+
+#[derive(Debug, Default)]
+struct UsageState {
+    limits: HashMap<String, u32>,
+    requests: HashMap<&'static str, VecDeque<Instant>>,
+    peaks: HashMap<&'static str, u32>,
+}
+
+impl RateUsage {
+    /// Override the default limits (endpoint → requests per `RATE_WINDOW`).
+    pub fn set_limits(&self, overrides: &BTreeMap<String, u32>) {
+        if let Ok(mut state) = self.inner.lock() {
+            for (endpoint, limit) in overrides {
+                state.limits.insert(endpoint.clone(), *limit);
+            }
+        }
+    }
+
+    /// Count one request to `endpoint`.
+    pub fn record(&self, endpoint: &'static str) {
+        self.record_at(endpoint, Instant::now());
+    }
+
+    fn record_at(&self, endpoint: &'static str, now: Instant) {
+        let Ok(mut state) = self.inner.lock() else {
+            return;
+        };
+        let window = state.requests.entry(endpoint).or_default();
+        prune(window, now);
+        window.push_back(now);
+        let count = window.len() as u32;
+        let peak = state.peaks.entry(endpoint).or_default();
+        *peak = (*peak).max(count);
+    }
+
+    /// Current usage of every endpoint called this session.
+    pub fn snapshot(&self) -> BTreeMap<&'static str, EndpointUsage> {
+        self.snapshot_at(Instant::now())
+    }
+
+    fn snapshot_at(&self, now: Instant) -> BTreeMap<&'static str, EndpointUsage> {
+        let Ok(mut state) = self.inner.lock() else {
+            return BTreeMap::new();
+        };
+        let UsageState {
+            limits,
+            requests,
+            peaks,
+        } = &mut *state;
+        requests
+            .iter_mut()
+            .map(|(&endpoint, window)| {
+                prune(window, now);
+                let requests = window.len() as u32;
+                let limit = limit_for(limits, endpoint);
+                let usage = EndpointUsage {
+                    requests,
+                    limit,
+                    utilization: if limit > 0 {
+                        requests as f64 / limit as f64
+                    } else {
+                        0.0
+                    },
+                    peak_requests: peaks.get(endpoint).copied().unwrap_or(requests),
+                };
+                (endpoint, usage)
+            })
+            .collect()
+    }
+}
+
+fn prune(window: &mut VecDeque<Instant>, now: Instant) {
+    while window
+        .front()
+        .is_some_and(|&t| now.duration_since(t) >= RATE_WINDOW)
+    {
+        window.pop_front();
+    }
+}
+
+fn limit_for(overrides: &HashMap<String, u32>, endpoint: &str) -> u32 {
+    overrides.get(endpoint).copied().unwrap_or_else(|| {
+        DEFAULT_LIMITS
+            .iter()
+            .find(|(e, _)| *e == endpoint)
+            .map_or(0, |(_, limit)| *limit)
+    })
+}
+
+/// Process-wide usage counters, recorded by every API wrapper.
+pub static USAGE: LazyLock<RateUsage> = LazyLock::new(RateUsage::default);
+
+/// Count one request to `endpoint` in the process-wide counters.
+pub fn record(endpoint: &'static str) {
+    USAGE.record(endpoint);
+}
+
+/// Token bucket pacing requests to an API (see `CLOB_LIMITER`).
+///
+/// Each request takes a token; tokens refill at `rate` per second up to
+/// `burst`. A request that finds none reserves the next one and waits for it,
+/// so concurrent callers queue in arrival order without holding the lock.
+#[derive(Debug)]
+pub struct TokenBucket {
+    inner: Mutex<BucketState>,
+}
+
+#[derive(Debug)]
+struct BucketState {
+    rate: f64,
+    burst: f64,
+    /// Negative while requests are queued for refills.
+    tokens: f64,
+    last: Instant,
+}
+
+impl TokenBucket {
+    pub fn new(rate: f64, burst: u32) -> Self {
+        Self {
+            inner: Mutex::new(BucketState {
+                rate,
+                burst: f64::from(burst),
+                tokens: f64::from(burst),
+                last: Instant::now(),
+            }),
+        }
+    }
+
+    /// Change the rate and burst (from `[executor.clob_rate]`), refilling the bucket.
+    pub fn configure(&self, rate: f64, burst: u32) {
+        if let Ok(mut state) = self.inner.lock() {
+            *state = BucketState {
+                rate,
+                burst: f64::from(burst),
+                tokens: f64::from(burst),
+                last: Instant::now(),
+            };
+        }
+    }
+
+    /// Wait for a token.
+    pub async fn acquire(&self) {
+        let wait = self.reserve_at(Instant::now());
+        if !wait.is_zero() {
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    /// Take a token at `now`, returning how long to wait until it is available.
+    fn reserve_at(&self, now: Instant) -> Duration {
+        let Ok(mut state) = self.inner.lock() else {
+            return Duration::ZERO;
+        };
+        let elapsed = now.saturating_duration_since(state.last).as_secs_f64();
+        state.tokens = (state.tokens + elapsed * state.rate).min(state.burst);
+        state.last = state.last.max(now);
+        state.tokens -= 1.0;
+        if state.tokens >= 0.0 || state.rate <= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-state.tokens / state.rate)
+        }
+    }
+}
+
+/// Process-wide pacing of CLOB requests, shared by order posting, status
+/// checks, cancels, balance queries, and book fetches. Configured at startup
+/// from `[executor.clob_rate]`.
+pub static CLOB_LIMITER: LazyLock<TokenBucket> = LazyLock::new(|| {
+    let defaults = crate::config::ClobRate::default();
+    TokenBucket::new(defaults.requests_per_sec, defaults.burst)
+});
+
+/// Wait for the CLOB rate limiter, then count one request to `endpoint` (a
+/// `clob:` endpoint).
+pub async fn acquire_clob(endpoint: &'static str) {
+    CLOB_LIMITER.acquire().await;
+    record(endpoint);
+}
+
+/// Warn about endpoints at or above `threshold` utilization (e.g. 0.8).
+pub fn warn_near_limits(threshold: f64) {
+    for (endpoint, usage) in USAGE.snapshot() {
+        if usage.limit > 0 && usage.utilization >= threshold {
+            warn!(
+                "Rate limit: {endpoint} at {}/{} requests per {}s ({:.0}%)",
+                usage.requests,
+                usage.limit,
+                RATE_WINDOW.as_secs(),
+                usage.utilization * 100.0
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sliding_window_counts_and_peaks() {
+        let usage = RateUsage::default();
+        let start = Instant::now();
+        for i in 0..30 {
+            usage.record_at("data:/trades", start + Duration::from_millis(i * 100));
+        }
+        let snap = usage.snapshot_at(start + Duration::from_secs(5));
+        let trades = &snap["data:/trades"];
+        assert_eq!(trades.requests, 30);
+        assert_eq!(trades.limit, 75);
+        assert!((trades.utilization - 0.4).abs() < 1e-9);
+
+        // Requests older than the window drop out; the peak is kept
+        let later = usage.snapshot_at(start + Duration::from_secs(13));
+        assert_eq!(later["data:/trades"].requests, 0);
+        assert_eq!(later["data:/trades"].peak_requests, 30);
+    }
+
+    #[test]
+    fn token_bucket_spends_burst_then_paces() {
+        let bucket = TokenBucket::new(5.0, 2);
+        let start = Instant::now();
+        let at = |ms| start + Duration::from_millis(ms);
+        assert_eq!(bucket.reserve_at(at(0)), Duration::ZERO);
+        assert_eq!(bucket.reserve_at(at(0)), Duration::ZERO);
+        // Empty: each further request queues 200ms behind the previous one
+        let third = bucket.reserve_at(at(0));
+        let fourth = bucket.reserve_at(at(0));
+        assert!(third.abs_diff(Duration::from_millis(200)) < Duration::from_millis(5));
+        assert!(fourth.abs_diff(Duration::from_millis(400)) < Duration::from_millis(5));
+        // After a quiet spell the bucket refills, but no further than the burst
+        assert_eq!(bucket.reserve_at(at(10_000)), Duration::ZERO);
+        assert_eq!(bucket.reserve_at(at(10_000)), Duration::ZERO);
+        assert!(!bucket.reserve_at(at(10_000)).is_zero());
+    }
+
+    #[test]
+    fn configured_limits_override_defaults() {
+        let usage = RateUsage::default();
+        usage.set_limits(&BTreeMap::from([("data:/trades".to_string(), 10)]));
+        usage.record("data:/trades");
+        usage.record("clob:/unknown");
+        let snap = usage.snapshot();
+        assert_eq!(snap["data:/trades"].limit, 10);
+        assert!((snap["data:/trades"].utilization - 0.1).abs() < 1e-9);
+        // Endpoints without a known limit report zero utilization
+        assert_eq!(snap["clob:/unknown"].limit, 0);
+        assert_eq!(snap["clob:/unknown"].utilization, 0.0);
+    }
+}
@@ -0,0 +1,389 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use polymarket_client_sdk::data::types::response::{Activity, Position, Trade};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+use crate::clock;
+use crate::trades::RawTrade;
+
+/// Which part of the session a recorded cycle is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CycleKind {
+    InitialReplication,
+    /// Seeding the dedup set from the trader's recent trades.
+    Seed,
+    Poll,
+}
+
+/// The API inputs of one cycle, in the data API's JSON shape.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CycleRecord {
+    pub kind: CycleKind,
+    /// `clock::now()` when the cycle started, in milliseconds.
+    pub timestamp_ms: i64,
+    /// Whether the cycle polled `/trades` (else it only acted on `pushed`).
+    pub rest_poll: bool,
+    /// Trades detected on the WebSocket feeds before the cycle.
+    #[serde(default)]
+    pub pushed: Vec<RawTrade>,
+    /// `/trades` response. `None` when not fetched.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trades: Option<Vec<Value>>,
+    /// `/activity` response (in place of `/trades` with
+    /// `settings.activity_detection`). `None` when not fetched.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub activity: Option<Vec<Value>>,
+    /// `/positions` response, re-marked at live prices. `None` when not fetched.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub positions: Option<Vec<Value>>,
+    /// Exit price map (gamma fallback for exited holdings, live marks).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prices: Option<HashMap<String, f64>>,
+    /// Gamma categories of the trader's events (`[engine.filters.categories]`).
+    /// `None` when not looked up.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub categories: Option<HashMap<String, Vec<String>>>,
+    /// The fetch that failed the cycle.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl CycleRecord {
+    pub fn new(kind: CycleKind, rest_poll: bool, pushed: &[RawTrade]) -> Self {
+        Self {
+            kind,
+            timestamp_ms: clock::now().timestamp_millis(),
+            rest_poll,
+            pushed: pushed.to_vec(),
+            trades: None,
+            activity: None,
+            positions: None,
+            prices: None,
+            categories: None,
+            error: None,
+        }
+    }
+}
+
+/// An SDK `Trade` back in the `/trades` JSON shape (the SDK type is
+/// deserialize-only).
+pub fn trade_json(trade: &Trade) -> Value {
+    json!({
+        "proxyWallet": trade.proxy_wallet.to_string(),
+        "side": trade.side,
+        "asset": trade.asset,
+        "conditionId": trade.condition_id,
+        "size": trade.size.to_string(),
+        "price": trade.price.to_string(),
+        "timestamp": trade.timestamp,
+        "title": trade.title,
+        "slug": trade.slug,
+        "icon": trade.icon,
+        "eventSlug": trade.event_slug,
+        "outcome": trade.outcome,
+        "outcomeIndex": trade.outcome_index,
+        "name": trade.name,
+        "pseudonym": trade.pseudonym,
+        "bio": trade.bio,
+        "profileImage": trade.profile_image,
+        "profileImageOptimized": trade.profile_image_optimized,
+        "transactionHash": trade.transaction_hash
+    })
+}
+
+/// An SDK `Activity` back in the `/activity` JSON shape.
+pub fn activity_json(activity: &Activity) -> Value {
+    json!({
+        "proxyWallet": activity.proxy_wallet.to_string(),
+        "timestamp": activity.timestamp,
+        "conditionId": activity.condition_id,
+        "type": activity.activity_type,
+        "size": activity.size.to_string(),
+        "usdcSize": activity.usdc_size.to_string(),
+        "transactionHash": activity.transaction_hash,
+        "price": activity.price.map(|p| p.to_string()),
+        "asset": activity.asset,
+        "side": activity.side,
+        "outcomeIndex": activity.outcome_index,
+        "title": activity.title,
+        "slug": activity.slug,
+        "icon": activity.icon,
+        "eventSlug": activity.event_slug,
+        "outcome": activity.outcome,
+        "name": activity.name,
+        "pseudonym": activity.pseudonym,
+        "bio": activity.bio,
+        "profileImage": activity.profile_image,
+        "profileImageOptimized": activity.profile_image_optimized
+    })
+}
+
+/// An SDK `Position` back in the `/positions` JSON shape.
+pub fn position_json(position: &Position) -> Value {
+    json!({
+        "proxyWallet": position.proxy_wallet.to_string(),
+        "asset": position.asset,
+        "conditionId": position.condition_id,
+        "size": position.size.to_string(),
+        "avgPrice": position.avg_price.to_string(),
+        "initialValue": position.initial_value.to_string(),
+        "currentValue": position.current_value.to_string(),
+        "cashPnl": position.cash_pnl.to_string(),
+        "percentPnl": position.percent_pnl.to_string(),
+        "totalBought": position.total_bought.to_string(),
+        "realizedPnl": position.realized_pnl.to_string(),
+        "percentRealizedPnl": position.percent_realized_pnl.to_string(),
+        "curPrice": position.cur_price.to_string(),
+        "redeemable": position.redeemable,
+        "mergeable": position.mergeable,
+        "title": position.title,
+        "slug": position.slug,
+        "icon": position.icon,
+        "eventSlug": position.event_slug,
+        "eventId": position.event_id,
+        "outcome": position.outcome,
+        "outcomeIndex": position.outcome_index,
+        "oppositeOutcome": position.opposite_outcome,
+        "oppositeAsset": position.opposite_asset,
+        "endDate": position.end_date,
+        "negativeRisk": position.negative_risk
+    })
+}
+
+fn parse_all<T: serde::de::DeserializeOwned>(values: &[Value]) -> Result<Vec<T>> {
+    values
+        .iter()
+        .map(|v| serde_json::from_value(v.clone()).context("malformed recorded response"))
+        .collect()
+}
+
+/// Where a cycle's API inputs come from.
+///
+/// Each input is requested through the tap with the (not yet awaited) fetch
+/// for it. `Off` awaits the fetch, `Record` also stores the response in its
+/// record, and `Replay` returns the recorded response without fetching.
+pub enum Tap {
+    Off,
+    Record(CycleRecord),
+    Replay(CycleRecord),
+}
+
+impl Tap {
+    /// `/trades` response.
+    pub async fn trades(
+        &mut self,
+        fetch: impl Future<Output = Result<Vec<Trade>>>,
+    ) -> Result<Vec<Trade>> {
+        match self {
+            Tap::Off => fetch.await,
+            Tap::Record(record) => {
+                let trades = record_outcome(record, fetch.await)?;
+                record.trades = Some(trades.iter().map(trade_json).collect());
+                Ok(trades)
+            }
+            Tap::Replay(record) => parse_all(recorded(record, "trades", &record.trades)?),
+        }
+    }
+
+    /// `/activity` response.
+    pub async fn activity(
+        &mut self,
+        fetch: impl Future<Output = Result<Vec<Activity>>>,
+    ) -> Result<Vec<Activity>> {
+        match self {
+            Tap::Off => fetch.await,
+            Tap::Record(record) => {
+                let activity = record_outcome(record, fetch.await)?;
+                record.activity = Some(activity.iter().map(activity_json).collect());
+                Ok(activity)
+            }
+            Tap::Replay(record) => parse_all(recorded(record, "activity", &record.activity)?),
+        }
+    }
+
+    /// `/positions` response.
+    pub async fn positions(
+        &mut self,
+        fetch: impl Future<Output = Result<Vec<Position>>>,
+    ) -> Result<Vec<Position>> {
+        match self {
+            Tap::Off => fetch.await,
+            Tap::Record(record) => {
+                let positions = record_outcome(record, fetch.await)?;
+                record.positions = Some(positions.iter().map(position_json).collect());
+                Ok(positions)
+            }
+            Tap::Replay(record) => parse_all(recorded(record, "positions", &record.positions)?),
+        }
+    }
+
+    /// Exit price map.
+    pub async fn prices(
+        &mut self,
+        fetch: impl Future<Output = Result<HashMap<String, f64>>>,
+    ) -> Result<HashMap<String, f64>> {
+        match self {
+            Tap::Off => fetch.await,
+            Tap::Record(record) => {
+                let prices = record_outcome(record, fetch.await)?;
+                record.prices = Some(prices.clone());
+                Ok(prices)
+            }
+            Tap::Replay(record) => Ok(recorded(record, "prices", &record.prices)?.clone()),
+        }
+    }
+
+    /// Event categories. The lookup itself never fails (events it couldn't look
+    /// up are left out), so recording it doesn't fail the cycle.
+    pub async fn categories(
+        &mut self,
+        lookup: impl Future<Output = HashMap<String, Vec<String>>>,
+    ) -> Result<HashMap<String, Vec<String>>> {
+        match self {
+            Tap::Off => Ok(lookup.await),
+            Tap::Record(record) => {
+                let categories = lookup.await;
+                record.categories = Some(categories.clone());
+                Ok(categories)
+            }
+            Tap::Replay(record) => Ok(recorded(record, "categories", &record.categories)?.clone()),
+        }
+    }
+
+    /// The record to write, when recording.
+    pub fn into_record(self) -> Option<CycleRecord> {
+        match self {
+            Tap::Record(record) => Some(record),
+            _ => None,
+        }
+    }
+}
+
+fn record_outcome<T>(record: &mut CycleRecord, outcome: Result<T>) -> Result<T> {
+    if let Err(e) = &outcome {
+        record.error = Some(format!("{e:#}"));
+    }
+    outcome
+}
+
+/// A recorded input, or the error the cycle failed with instead.
+fn recorded<'a, T>(record: &CycleRecord, what: &str, input: &'a Option<T>) -> Result<&'a T> {
+    match (input, &record.error) {
+        (Some(input), _) => Ok(input),
+        (None, Some(error)) => Err(anyhow::anyhow!("{error} (recorded)")),
+        (None, None) => anyhow::bail!(
+            "no {what} recorded for this {:?} cycle — the replay diverged from the recording",
+            record.kind
+        ),
+    }
+}
+
+/// Writes one `cycle-NNNNNN.json` file per cycle into a directory.
+#[derive(Debug)]
+pub struct Recorder {
+    dir: PathBuf,
+    next: u64,
+}
+
+impl Recorder {
+    /// Create `dir` if needed. Fails if it already holds a recording.
+    pub fn create(dir: &Path) -> Result<Self> {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("failed to create {}", dir.display()))?;
+        if !cycle_files(dir)?.is_empty() {
+            anyhow::bail!("{} already holds a recording", dir.display());
+        }
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            next: 1,
+        })
+    }
+
+    pub fn write(&mut self, record: &CycleRecord) -> Result<()> {
+        let path = self.dir.join(format!("cycle-{:06}.json", self.next));
+        let contents = serde_json::to_string_pretty(record)?;
+        std::fs::write(&path, contents)
+            .with_context(|| format!("failed to write {}", path.display()))?;
+        self.next += 1;
+        Ok(())
+    }
+}
+
+fn cycle_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for entry in
+        std::fs::read_dir(dir).with_context(|| format!("failed to read {}", dir.display()))?
+    {
+        let path = entry?.path();
+        let name = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or_default();
+        if name.starts_with("cycle-") && name.ends_with(".json") {
+            files.push(path);
+        }
+    }
+    // Zero-padded sequence numbers sort in cycle order
+    files.sort();
+    Ok(files)
+}
+
+/// Read a recording back, in cycle order.
+pub fn read_recording(dir: &Path) -> Result<Vec<CycleRecord>> {
+    cycle_files(dir)?
+        .iter()
+        .map(|path| {
+            let contents = std::fs::read_to_string(path)
+                .with_context(|| format!("failed to read {}", path.display()))?;
+            serde_json::from_str(&contents)
+                .with_context(|| format!("failed to parse {}", path.display()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testkit::{make_position, make_trade};
+
+    async fn fetch_panics<T>() -> Result<T> {
+        panic!("replay must not fetch")
+    }
+
+    #[tokio::test]
+    async fn replays_recorded_inputs() {
+        let dir = std::env::temp_dir().join(format!("recording-test-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let mut recorder = Recorder::create(&dir).unwrap();
+
+        let trade = make_trade("a1", "c1", "BUY", 10.0, 0.45, 1_700_000_000, "0xabc");
+        let position = make_position("a1", "c1", "Market A", 10.0, 0.5);
+        let mut tap = Tap::Record(CycleRecord::new(CycleKind::Poll, true, &[]));
+        tap.trades(async { Ok(vec![trade.clone()]) }).await.unwrap();
+        tap.positions(async { Ok(vec![position.clone()]) })
+            .await
+            .unwrap();
+        let failed = tap.prices(async { anyhow::bail!("gamma down") }).await;
+        assert!(failed.is_err());
+        recorder.write(&tap.into_record().unwrap()).unwrap();
+        assert!(Recorder::create(&dir).is_err());
+
+        let records = read_recording(&dir).unwrap();
+        assert_eq!(records.len(), 1);
+        let mut tap = Tap::Replay(records[0].clone());
+        let trades = tap.trades(fetch_panics()).await.unwrap();
+        assert_eq!(trade_json(&trades[0]), trade_json(&trade));
+        let positions = tap.positions(fetch_panics()).await.unwrap();
+        assert_eq!(position_json(&positions[0]), position_json(&position));
+        // The failure replays in place of the missing response
+        let err = tap.prices(fetch_panics()).await.unwrap_err();
+        assert!(err.to_string().contains("gamma down"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
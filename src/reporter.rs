@@ -1,4 +1,4 @@
-use crate::types::{CopytradeEvent, ExitSummary};
+use crate::types::{AlertEvent, CopytradeEvent, ExitSummary, ModeTransitionEvent};
 
 /// Emit a copytrade event as a single JSON line to stdout.
 pub fn report_event(event: &CopytradeEvent) {
@@ -7,6 +7,20 @@ pub fn report_event(event: &CopytradeEvent) {
     }
 }
 
+/// Emit an alert as a single JSON line to stdout.
+pub fn report_alert(alert: &AlertEvent) {
+    if let Ok(json) = serde_json::to_string(alert) {
+        println!("{json}");
+    }
+}
+
+/// Emit a mode transition as a single JSON line to stdout.
+pub fn report_mode_transition(event: &ModeTransitionEvent) {
+    if let Ok(json) = serde_json::to_string(event) {
+        println!("{json}");
+    }
+}
+
 /// Emit the exit summary as pretty-printed JSON to stdout.
 pub fn report_exit_summary(summary: &ExitSummary) {
     if let Ok(json) = serde_json::to_string_pretty(summary) {
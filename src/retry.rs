@@ -0,0 +1,233 @@
+use std::collections::hash_map::RandomState;
+use std::fmt::Display;
+use std::hash::BuildHasher;
+use std::sync::{LazyLock, RwLock};
+use std::time::Duration;
+
+use polymarket_client_sdk::error::StatusCode;
+use tracing::warn;
+
+use crate::config::{ErrorClass, RetryConfig, RetryOperation, RetryPolicy};
+
+/// Process-wide retry policies, configured at startup from `[retry]`.
+static POLICIES: LazyLock<RwLock<RetryConfig>> = LazyLock::new(Default::default);
+
+/// Replace the retry policies (from `[retry]`).
+pub fn configure(config: &RetryConfig) {
+    if let Ok(mut policies) = POLICIES.write() {
+        *policies = config.clone();
+    }
+}
+
+/// The configured policy for `operation`.
+pub fn policy(operation: RetryOperation) -> RetryPolicy {
+    POLICIES
+        .read()
+        .map(|policies| policies.policy(operation))
+        .unwrap_or_default()
+}
+
+/// Longest wait between attempts; `delay` never exceeds it, and policies whose
+/// backoff would are rejected at startup.
+pub const MAX_DELAY: Duration = Duration::from_secs(3600);
+
+/// The HTTP status in an error message: a three-digit code followed by its
+/// reason phrase ("503 Service Unavailable") or after "status"/"HTTP"
+/// ("HTTP 503"). Other numbers, like a size in a rejection message, don't count.
+pub fn status_code(err: &str) -> Option<StatusCode> {
+    let lower = err.to_lowercase();
+    let bytes = lower.as_bytes();
+    let digit = |i: usize| bytes.get(i).is_some_and(u8::is_ascii_digit);
+    (0..lower.len().saturating_sub(2)).find_map(|start| {
+        if !(digit(start) && digit(start + 1) && digit(start + 2))
+            || (start > 0 && digit(start - 1))
+            || digit(start + 3)
+        {
+            return None;
+        }
+        let code = StatusCode::from_u16(lower[start..start + 3].parse().ok()?).ok()?;
+        let before = lower[..start].trim_end();
+        let after = lower[start + 3..].trim_start();
+        let phrase = code.canonical_reason().map(str::to_lowercase);
+        let labelled = before.ends_with("status") || before.ends_with("http");
+        (labelled || phrase.is_some_and(|phrase| after.starts_with(&phrase))).then_some(code)
+    })
+}
+
+/// Classify a request error by its message, or `None` when it is none of the
+/// known transient kinds (a rejected order, a bad request).
+pub fn classify(err: &str) -> Option<ErrorClass> {
+    let lower = err.to_lowercase();
+    let status = status_code(&lower);
+    if crate::backoff::is_rate_limited(&lower) {
+        Some(ErrorClass::RateLimited)
+    } else if status.is_some_and(|code| code.is_server_error())
+        || lower.contains("internal server error")
+        || lower.contains("bad gateway")
+        || lower.contains("service unavailable")
+        || lower.contains("gateway timeout")
+    {
+        Some(ErrorClass::Server)
+    } else if status.is_none()
+        && (lower.contains("timeout")
+            || lower.contains("timed out")
+            || lower.contains("connection"))
+    {
+        Some(ErrorClass::Network)
+    } else {
+        None
+    }
+}
+
+/// Whether `policy` retries an error with message `err`.
+pub fn is_retryable(policy: &RetryPolicy, err: &str) -> bool {
+    classify(err).is_some_and(|class| policy.retry_on.contains(&class))
+}
+
+/// Delay after failed attempt `attempt` (from 0), with `sample` in `[0, 1)`
+/// placing it within the jitter band. Capped at `MAX_DELAY`.
+pub fn delay(policy: &RetryPolicy, attempt: u32, sample: f64) -> Duration {
+    let exponent = i32::try_from(attempt).unwrap_or(i32::MAX);
+    let base = policy.base_backoff_ms as f64 * policy.multiplier.powi(exponent);
+    let spread = 1.0 + policy.jitter * (2.0 * sample - 1.0);
+    Duration::try_from_secs_f64((base * spread).max(0.0) / 1000.0)
+        .map_or(MAX_DELAY, |wait| wait.min(MAX_DELAY))
+}
+
+/// Check a policy from `[retry]`: at least one attempt, a finite multiplier of
+/// at least 1, jitter between 0 and 1, and a longest backoff within
+/// `MAX_DELAY`.
+pub fn validate(policy: &RetryPolicy) -> anyhow::Result<()> {
+    if policy.max_attempts == 0 {
+        anyhow::bail!("max_attempts must be at least 1");
+    }
+    if !policy.multiplier.is_finite() || policy.multiplier < 1.0 {
+        anyhow::bail!("multiplier must be a finite number of at least 1");
+    }
+    if !(0.0..=1.0).contains(&policy.jitter) {
+        anyhow::bail!("jitter must be between 0 and 1");
+    }
+    // The wait after the second-to-last attempt, at the top of the jitter band
+    let exponent = i32::try_from(policy.max_attempts.saturating_sub(2)).unwrap_or(i32::MAX);
+    let longest_ms =
+        policy.base_backoff_ms as f64 * policy.multiplier.powi(exponent) * (1.0 + policy.jitter);
+    if !longest_ms.is_finite() || longest_ms > MAX_DELAY.as_millis() as f64 {
+        anyhow::bail!(
+            "backoff grows past {}s; lower max_attempts, base_backoff_ms or multiplier",
+            MAX_DELAY.as_secs()
+        );
+    }
+    Ok(())
+}
+
+/// A uniform sample in `[0, 1)` for jitter. `RandomState` is randomly keyed
+/// per instance, which is plenty to spread out concurrent retries.
+pub fn jitter_sample() -> f64 {
+    (RandomState::new().hash_one(0u8) >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// Run `attempt` under the policy for `operation`, retrying the errors it
+/// retries after a jittered backoff. `what` names the request in warnings.
+/// Returns the first success, or the last error.
+pub async fn with_retry<T, E, Fut>(
+    operation: RetryOperation,
+    what: &str,
+    mut attempt: impl FnMut() -> Fut,
+) -> Result<T, E>
+where
+    E: Display,
+    Fut: Future<Output = Result<T, E>>,
+{
+    let policy = policy(operation);
+    let mut n = 0;
+    loop {
+        match attempt().await {
+            Ok(value) => return Ok(value),
+            Err(e) => {
+                let err_str = e.to_string();
+                if n + 1 >= policy.max_attempts || !is_retryable(&policy, &err_str) {
+                    return Err(e);
+                }
+                let wait = delay(&policy, n, jitter_sample());
+                n += 1;
+                warn!(
+                    "Transient error on {what} (attempt {n}/{}): {err_str} — retrying in {wait:?}",
+                    policy.max_attempts
+                );
+                tokio::time::sleep(wait).await;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classifies_errors_and_jitters_backoff() {
+        assert_eq!(
+            classify("HTTP 429 Too Many Requests"),
+            Some(ErrorClass::RateLimited)
+        );
+        assert_eq!(classify("502 Bad Gateway"), Some(ErrorClass::Server));
+        assert_eq!(
+            classify("connection reset by peer"),
+            Some(ErrorClass::Network)
+        );
+        assert_eq!(classify("order rejected: not enough balance"), None);
+        assert_eq!(
+            classify("error(503 Service Unavailable) making POST call to /order with busy"),
+            Some(ErrorClass::Server)
+        );
+        assert_eq!(classify("HTTP status 500"), Some(ErrorClass::Server));
+        // Numbers that aren't a status, and statuses that aren't transient
+        assert_eq!(classify("order rejected: size 500 over max 5029"), None);
+        assert_eq!(
+            classify("error(400 Bad Request) making call: connection"),
+            None
+        );
+        assert_eq!(
+            status_code("error(404 Not Found) making GET call").map(|code| code.as_u16()),
+            Some(404)
+        );
+
+        let policy = RetryPolicy {
+            retry_on: vec![ErrorClass::Network],
+            ..Default::default()
+        };
+        assert!(is_retryable(&policy, "request timed out"));
+        assert!(!is_retryable(&policy, "503 Service Unavailable"));
+
+        // 500ms doubling, ±20%
+        assert_eq!(delay(&policy, 0, 0.5), Duration::from_millis(500));
+        assert_eq!(delay(&policy, 2, 0.5), Duration::from_millis(2000));
+        assert_eq!(delay(&policy, 2, 0.0), Duration::from_millis(1600));
+        assert_eq!(delay(&policy, 2, 1.0), Duration::from_millis(2400));
+        for _ in 0..100 {
+            let sample = jitter_sample();
+            assert!((0.0..1.0).contains(&sample));
+        }
+    }
+
+    #[test]
+    fn rejects_policies_that_never_post_or_overflow() {
+        assert!(validate(&RetryPolicy::default()).is_ok());
+        let policy = |max_attempts, multiplier, jitter| RetryPolicy {
+            max_attempts,
+            multiplier,
+            jitter,
+            ..Default::default()
+        };
+        assert!(validate(&policy(0, 2.0, 0.2)).is_err());
+        assert!(validate(&policy(3, f64::NAN, 0.2)).is_err());
+        assert!(validate(&policy(3, f64::INFINITY, 0.2)).is_err());
+        assert!(validate(&policy(3, 2.0, f64::NAN)).is_err());
+        assert!(validate(&policy(1000, 10.0, 0.2)).is_err());
+
+        // An unchecked policy still never panics or waits past the cap
+        let wild = policy(u32::MAX, 1e300, 1.0);
+        assert_eq!(delay(&wild, 5_000, 1.0), MAX_DELAY);
+        assert!(delay(&policy(3, f64::NAN, 0.2), 1, 0.5) <= MAX_DELAY);
+    }
+}
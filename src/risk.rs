@@ -0,0 +1,183 @@
+use std::collections::HashMap;
+
+use rust_decimal::Decimal;
+use serde::Serialize;
+
+use crate::config::RiskConfig;
+use crate::money;
+use crate::state::TradingState;
+use crate::types::{HeldPosition, MarketPosition, OrderSide, SimulatedOrder};
+
+/// Which `[risk]` rule closed a position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RiskRule {
+    StopLoss,
+    TakeProfit,
+}
+
+impl std::fmt::Display for RiskRule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RiskRule::StopLoss => write!(f, "stop-loss"),
+            RiskRule::TakeProfit => write!(f, "take-profit"),
+        }
+    }
+}
+
+/// A full exit of one holding, independent of the trader.
+#[derive(Debug, Clone)]
+pub struct RiskExit {
+    pub rule: RiskRule,
+    /// Price move from the average cost, in percent.
+    pub move_pct: f64,
+    pub order: SimulatedOrder,
+}
+
+/// Sell orders for every holding past its stop-loss or take-profit at `prices`.
+///
+/// Holdings without a price, already exiting through resting sells, in a
+/// resolved market (price 0 or 1, left to redemption), or pinned are skipped.
+pub fn plan_exits(
+    state: &TradingState,
+    prices: &HashMap<String, f64>,
+    config: &RiskConfig,
+) -> Vec<RiskExit> {
+    let mut exits = Vec::new();
+    for (asset, held) in &state.holdings {
+        let Some(&price) = prices.get(asset) else {
+            continue;
+        };
+        if state.pinned.contains(asset) {
+            continue;
+        }
+        let price = money::from_f64(price);
+        let shares = state.effective_held_shares(asset);
+        if shares <= Decimal::ZERO
+            || held.avg_cost <= Decimal::ZERO
+            || price <= Decimal::ZERO
+            || price >= Decimal::ONE
+        {
+            continue;
+        }
+        let move_pct = money::to_f64((price / held.avg_cost - Decimal::ONE) * Decimal::ONE_HUNDRED);
+        let rule = if config.stop_loss_pct.is_some_and(|sl| move_pct <= -sl) {
+            RiskRule::StopLoss
+        } else if config.take_profit_pct.is_some_and(|tp| move_pct >= tp) {
+            RiskRule::TakeProfit
+        } else {
+            continue;
+        };
+        exits.push(RiskExit {
+            rule,
+            move_pct,
+            order: exit_order(state, held, shares, price),
+        });
+    }
+    exits.sort_by(|a, b| a.order.market.asset.cmp(&b.order.market.asset));
+    exits
+}
+
+/// Sell orders for every holding at `prices`, for the kill switch. Skips the
+/// same holdings as `plan_exits`.
+pub fn liquidation_orders(
+    state: &TradingState,
+    prices: &HashMap<String, f64>,
+) -> Vec<SimulatedOrder> {
+    let mut orders: Vec<SimulatedOrder> = state
+        .holdings
+        .iter()
+        .filter_map(|(asset, held)| {
+            let price = money::from_f64(*prices.get(asset)?);
+            let shares = state.effective_held_shares(asset);
+            let tradable = price > Decimal::ZERO && price < Decimal::ONE;
+            (shares > Decimal::ZERO && tradable && !state.pinned.contains(asset))
+                .then(|| exit_order(state, held, shares, price))
+        })
+        .collect();
+    orders.sort_by(|a, b| a.market.asset.cmp(&b.market.asset));
+    orders
+}
+
+fn exit_order(
+    state: &TradingState,
+    held: &HeldPosition,
+    shares: Decimal,
+    price: Decimal,
+) -> SimulatedOrder {
+    SimulatedOrder {
+        market: MarketPosition {
+            condition_id: String::new(),
+            asset: held.asset.clone(),
+            title: held.title.clone(),
+            outcome: held.outcome.clone(),
+            outcome_index: 0,
+            event_slug: String::new(),
+            tags: state
+                .asset_tags
+                .get(&held.asset)
+                .cloned()
+                .unwrap_or_default(),
+            opposite_asset: held.opposite_asset.clone(),
+        },
+        side: OrderSide::Sell,
+        shares,
+        price,
+        cost_usd: shares * price,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testkit::hold;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn exits_positions_past_thresholds() {
+        let mut state = TradingState::new(dec!(100));
+        hold(&mut state, "down", dec!(10), dec!(0.50));
+        hold(&mut state, "flat", dec!(10), dec!(0.50));
+        hold(&mut state, "up", dec!(10), dec!(0.40));
+        hold(&mut state, "resolved", dec!(10), dec!(0.50));
+        hold(&mut state, "unpriced", dec!(10), dec!(0.50));
+        let prices = HashMap::from([
+            ("down".to_string(), 0.35),
+            ("flat".to_string(), 0.45),
+            ("up".to_string(), 0.65),
+            ("resolved".to_string(), 0.0),
+        ]);
+        let config = RiskConfig {
+            stop_loss_pct: Some(25.0),
+            take_profit_pct: Some(50.0),
+            ..Default::default()
+        };
+
+        let exits = plan_exits(&state, &prices, &config);
+        let rules: Vec<(&str, RiskRule)> = exits
+            .iter()
+            .map(|e| (e.order.market.asset.as_str(), e.rule))
+            .collect();
+        assert_eq!(
+            rules,
+            [("down", RiskRule::StopLoss), ("up", RiskRule::TakeProfit)]
+        );
+        assert_eq!(exits[0].move_pct, -30.0);
+        assert_eq!(exits[1].order.side, OrderSide::Sell);
+        assert_eq!(exits[1].order.cost_usd, dec!(6.5));
+
+        // Rules are independent
+        let stop_only = RiskConfig {
+            take_profit_pct: None,
+            ..config
+        };
+        assert_eq!(plan_exits(&state, &prices, &stop_only).len(), 1);
+
+        let liquidation = liquidation_orders(&state, &prices);
+        let assets: Vec<&str> = liquidation
+            .iter()
+            .map(|o| o.market.asset.as_str())
+            .collect();
+        assert_eq!(assets, ["down", "flat", "up"]);
+    }
+}
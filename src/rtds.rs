@@ -0,0 +1,158 @@
+use std::time::Duration;
+
+use serde::Deserialize;
+use serde_json::json;
+use tokio::sync::watch;
+use tokio_tungstenite::tungstenite::Message;
+
+use crate::ws::{Keepalive, ReconnectPolicy, WsFeed};
+
+/// The `activity/trades` firehose carries dozens of trades per second, so a
+/// quiet connection has silently stopped delivering (see EXPLORATION.md 1C).
+pub const IDLE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// A trade from the RTDS `activity/trades` topic (same fields as data API
+/// `/trades`; only those used for detection are kept).
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RtdsTrade {
+    pub transaction_hash: String,
+    pub proxy_wallet: String,
+    pub asset: String,
+    pub condition_id: String,
+    #[serde(default)]
+    pub outcome_index: Option<i32>,
+    pub side: String,
+    pub size: f64,
+    pub price: f64,
+    /// Exchange timestamp, in seconds.
+    pub timestamp: i64,
+}
+
+#[derive(Deserialize)]
+struct Envelope {
+    topic: String,
+    #[serde(rename = "type")]
+    kind: String,
+    payload: serde_json::Value,
+}
+
+/// Subscription frame for the `activity/trades` topic. RTDS can't filter by
+/// wallet server-side, so every trade on the platform is delivered.
+pub fn trades_subscription() -> String {
+    json!({
+        "action": "subscribe",
+        "subscriptions": [{ "topic": "activity", "type": "trades" }]
+    })
+    .to_string()
+}
+
+/// Parse a frame as an `activity/trades` message from `wallet` (case-insensitive).
+/// Other topics, other wallets, and malformed payloads yield `None`.
+pub fn parse_trade(msg: &Message, wallet: &str) -> Option<RtdsTrade> {
+    let Message::Text(text) = msg else {
+        return None;
+    };
+    let envelope: Envelope = serde_json::from_str(text.as_str()).ok()?;
+    if envelope.topic != "activity" || envelope.kind != "trades" {
+        return None;
+    }
+    // Check the wallet before deserializing the full payload: almost every
+    // firehose trade belongs to someone else
+    let proxy_wallet = envelope.payload.get("proxyWallet")?.as_str()?;
+    if !proxy_wallet.eq_ignore_ascii_case(wallet) {
+        return None;
+    }
+    serde_json::from_value(envelope.payload).ok()
+}
+
+/// Reconnecting feed of the `activity/trades` topic.
+///
+/// Returns the subscription sender alongside the feed; the feed stops when it
+/// is dropped.
+pub fn trades_feed(url: &str) -> (WsFeed, watch::Sender<Vec<String>>) {
+    let (tx, rx) = watch::channel(vec![trades_subscription()]);
+    let feed = WsFeed {
+        name: "rtds",
+        url: url.to_string(),
+        keepalive: Keepalive::RTDS,
+        idle_timeout: Some(IDLE_TIMEOUT),
+        policy: ReconnectPolicy::default(),
+        subscriptions: rx,
+    };
+    (feed, tx)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(topic: &str, wallet: &str) -> Message {
+        let text = json!({
+            "topic": topic,
+            "type": "trades",
+            "connection_id": "c1",
+            "payload": {
+                "asset": "123",
+                "conditionId": "0xc1",
+                "outcomeIndex": 0,
+                "eventSlug": "e",
+                "outcome": "Yes",
+                "price": 0.24,
+                "side": "BUY",
+                "size": 40.5,
+                "timestamp": 1_770_690_301,
+                "title": "t",
+                "transactionHash": "0xabc",
+                "proxyWallet": wallet
+            }
+        });
+        Message::Text(text.to_string().into())
+    }
+
+    #[test]
+    fn parses_only_the_target_wallets_trades() {
+        let wallet = "0xdb27bf2ac5d428a9c63dbc914611036855a6c56e";
+        let trade = parse_trade(&frame("activity", &wallet.to_uppercase()), wallet).unwrap();
+        assert_eq!(trade.transaction_hash, "0xabc");
+        assert_eq!(trade.timestamp, 1_770_690_301);
+        assert_eq!((trade.size, trade.price), (40.5, 0.24));
+
+        assert!(parse_trade(&frame("activity", "0x01"), wallet).is_none());
+        assert!(parse_trade(&frame("comments", wallet), wallet).is_none());
+        assert!(parse_trade(&Message::Text("not json".into()), wallet).is_none());
+    }
+
+    #[tokio::test]
+    async fn detects_trades_from_a_replayed_capture() {
+        use crate::wscapture::{CaptureWriter, read_capture, replay};
+        use futures_util::StreamExt;
+
+        let path = std::env::temp_dir().join(format!(
+            "copytrade-rtds-capture-{}.jsonl",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_file(&path);
+        let wallet = "0xdb27bf2ac5d428a9c63dbc914611036855a6c56e";
+        let mut writer = CaptureWriter::create(&path).unwrap();
+        let frames = [
+            ("rtds", Message::Pong(Vec::new().into())),
+            ("rtds", frame("activity", "0x01")),
+            ("rtds", frame("activity", wallet)),
+            ("clob_market", frame("activity", wallet)),
+            ("rtds", frame("comments", wallet)),
+        ];
+        for (at_ms, (channel, msg)) in (1_000..).zip(&frames) {
+            writer.record_at(at_ms, channel, msg).unwrap();
+        }
+
+        // The firehose as the feed saw it: only the trader's trade comes out
+        let trades: Vec<RtdsTrade> = replay(read_capture(&path).unwrap(), "rtds", None)
+            .filter_map(|msg| async move { parse_trade(&msg.ok()?, wallet) })
+            .collect()
+            .await;
+        assert_eq!(trades.len(), 1);
+        assert_eq!(trades[0].transaction_hash, "0xabc");
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,277 @@
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use polymarket_client_sdk::clob::{Client as ClobClient, Config as ClobConfig};
+use polymarket_client_sdk::data::Client as DataClient;
+use polymarket_client_sdk::gamma::Client as GammaClient;
+use serde::Serialize;
+use tokio_tungstenite::tungstenite::Message;
+
+use crate::config::EndpointsConfig;
+use crate::ws::{Connection, Keepalive};
+
+/// Per-check timeout; a slower endpoint counts as unreachable.
+pub const CHECK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long a WebSocket check waits after subscribing for the server to reject
+/// the subscription. Silence means it was accepted: a feed may be quiet.
+pub const SUBSCRIBE_GRACE: Duration = Duration::from_secs(2);
+
+/// A WebSocket endpoint to check.
+#[derive(Debug, Clone)]
+pub struct WsCheck<'a> {
+    pub name: &'a str,
+    pub url: &'a str,
+    /// Frame sent after connecting, as the feed would; the check fails if the
+    /// server closes the connection or answers with an error.
+    pub subscription: Option<String>,
+    /// Whether the bot cannot run without this feed.
+    pub required: bool,
+}
+
+/// Which API an endpoint check targets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EndpointKind {
+    DataApi,
+    Gamma,
+    ClobRest,
+    WebSocket,
+}
+
+impl EndpointKind {
+    /// What to check when this endpoint is unreachable.
+    pub fn guidance(self) -> &'static str {
+        match self {
+            EndpointKind::DataApi => {
+                "check network/DNS access and `[endpoints] data_api` in config.toml"
+            }
+            EndpointKind::Gamma => {
+                "check network/DNS access and `[endpoints] gamma_api` in config.toml"
+            }
+            EndpointKind::ClobRest => {
+                "check `[endpoints] clob_api` in config.toml; the CLOB is geoblocked in some regions"
+            }
+            EndpointKind::WebSocket => {
+                "check that outbound WebSocket (wss://) traffic is allowed by proxies and firewalls"
+            }
+        }
+    }
+}
+
+/// Result of one endpoint check.
+#[derive(Debug, Clone, Serialize)]
+pub struct EndpointCheck {
+    pub name: String,
+    pub kind: EndpointKind,
+    pub url: String,
+    /// Whether the bot cannot run without this endpoint.
+    pub required: bool,
+    pub ok: bool,
+    pub latency_ms: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+async fn check<F, T>(
+    name: &str,
+    kind: EndpointKind,
+    url: &str,
+    required: bool,
+    probe: F,
+) -> EndpointCheck
+where
+    F: Future<Output = Result<T>>,
+{
+    let start = Instant::now();
+    let error = match tokio::time::timeout(CHECK_TIMEOUT, probe).await {
+        Ok(Ok(_)) => None,
+        Ok(Err(e)) => Some(format!("{e:#}")),
+        Err(_) => Some(format!("timed out after {}s", CHECK_TIMEOUT.as_secs())),
+    };
+    EndpointCheck {
+        name: name.to_string(),
+        kind,
+        url: url.to_string(),
+        required,
+        ok: error.is_none(),
+        latency_ms: start.elapsed().as_secs_f64() * 1000.0,
+        error,
+    }
+}
+
+/// Check every endpoint concurrently.
+///
+/// The data API and gamma are always required. The CLOB REST API is required
+/// only when `clob_required` (live mode). `ws` lists the WebSocket feeds in use.
+pub async fn run(
+    endpoints: &EndpointsConfig,
+    clob_required: bool,
+    ws: &[WsCheck<'_>],
+) -> Vec<EndpointCheck> {
+    let data = check(
+        "data_api",
+        EndpointKind::DataApi,
+        &endpoints.data_api,
+        true,
+        async { Ok(DataClient::new(&endpoints.data_api)?.health().await?) },
+    );
+    let gamma = check(
+        "gamma",
+        EndpointKind::Gamma,
+        &endpoints.gamma_api,
+        true,
+        async { Ok(GammaClient::new(&endpoints.gamma_api)?.status().await?) },
+    );
+    let clob = check(
+        "clob",
+        EndpointKind::ClobRest,
+        &endpoints.clob_api,
+        clob_required,
+        async {
+            Ok(ClobClient::new(&endpoints.clob_api, ClobConfig::default())?
+                .ok()
+                .await?)
+        },
+    );
+    let ws = futures_util::future::join_all(ws.iter().map(|feed| {
+        check(
+            feed.name,
+            EndpointKind::WebSocket,
+            feed.url,
+            feed.required,
+            subscribe(feed),
+        )
+    }));
+
+    let (data, gamma, clob, ws) = tokio::join!(data, gamma, clob, ws);
+    let mut checks = vec![data, gamma, clob];
+    checks.extend(ws);
+    checks
+}
+
+/// Connect to `feed`, send its subscription, and wait `SUBSCRIBE_GRACE` for
+/// a rejection.
+async fn subscribe(feed: &WsCheck<'_>) -> Result<()> {
+    let mut conn = Connection::connect(feed.url, Keepalive::None, None).await?;
+    if let Some(subscription) = &feed.subscription {
+        conn.send_text(subscription.as_str()).await?;
+        if let Ok(reply) = tokio::time::timeout(SUBSCRIBE_GRACE, conn.next()).await {
+            match reply? {
+                None => anyhow::bail!("connection closed after subscribing"),
+                Some(Message::Text(text)) => {
+                    if let Ok(reply) = serde_json::from_str::<serde_json::Value>(text.as_str())
+                        && let Some(error) = reply.get("error")
+                    {
+                        anyhow::bail!("subscription rejected: {error}");
+                    }
+                }
+                Some(_) => {}
+            }
+        }
+    }
+    conn.close().await
+}
+
+/// Fail with per-endpoint guidance if any required endpoint is unreachable.
+pub fn ensure_reachable(checks: &[EndpointCheck]) -> Result<()> {
+    let failures: Vec<String> = checks
+        .iter()
+        .filter(|c| c.required && !c.ok)
+        .map(|c| {
+            format!(
+                "  {} ({}): {} — {}",
+                c.name,
+                c.url,
+                c.error.as_deref().unwrap_or("unreachable"),
+                c.kind.guidance()
+            )
+        })
+        .collect();
+    if failures.is_empty() {
+        return Ok(());
+    }
+    anyhow::bail!(
+        "connectivity self-test failed (use --skip-self-test to bypass):\n{}",
+        failures.join("\n")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn unreachable_endpoints_fail_with_guidance() {
+        // Nothing listens on port 9 locally, so every check fails fast
+        let endpoints = EndpointsConfig {
+            data_api: "http://127.0.0.1:9/".to_string(),
+            data_api_secondary: None,
+            gamma_api: "http://127.0.0.1:9/".to_string(),
+            clob_api: "http://127.0.0.1:9/".to_string(),
+            rtds_ws: "ws://127.0.0.1:9".to_string(),
+            clob_user_ws: "ws://127.0.0.1:9".to_string(),
+            clob_market_ws: "ws://127.0.0.1:9".to_string(),
+            polygon_ws: None,
+        };
+        let clob_ws = WsCheck {
+            name: "clob_ws",
+            url: "ws://127.0.0.1:9",
+            subscription: None,
+            required: true,
+        };
+        let checks = run(&endpoints, false, &[clob_ws]).await;
+        assert_eq!(checks.len(), 4);
+        assert!(checks.iter().all(|c| !c.ok && c.error.is_some()));
+
+        let err = ensure_reachable(&checks).unwrap_err().to_string();
+        assert!(err.contains("data_api (http://127.0.0.1:9/)"));
+        assert!(err.contains("gamma_api"));
+        assert!(err.contains("clob_ws"));
+        // The CLOB REST API is optional outside live mode
+        assert!(!err.contains("  clob ("));
+    }
+
+    #[tokio::test]
+    async fn ws_check_fails_when_the_subscription_is_rejected() {
+        use futures_util::{SinkExt, StreamExt};
+        use tokio::net::TcpListener;
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            for reply in [r#"{"id":1,"result":"0x1"}"#, r#"{"id":1,"error":"bad"}"#] {
+                let (stream, _) = listener.accept().await.unwrap();
+                let mut ws = tokio_tungstenite::accept_async(stream).await.unwrap();
+                ws.next().await;
+                ws.send(Message::Text(reply.into())).await.unwrap();
+                while ws.next().await.is_some() {}
+            }
+        });
+
+        let feed = WsCheck {
+            name: "polygon_ws",
+            url: &url,
+            subscription: Some("subscribe".to_string()),
+            required: true,
+        };
+        assert!(subscribe(&feed).await.is_ok());
+        let err = subscribe(&feed).await.unwrap_err().to_string();
+        assert!(err.contains("subscription rejected"), "{err}");
+    }
+
+    #[test]
+    fn optional_failures_pass() {
+        let checks = vec![EndpointCheck {
+            name: "clob".to_string(),
+            kind: EndpointKind::ClobRest,
+            url: "https://clob.polymarket.com".to_string(),
+            required: false,
+            ok: false,
+            latency_ms: 3.0,
+            error: Some("HTTP 403".to_string()),
+        }];
+        assert!(ensure_reachable(&checks).is_ok());
+    }
+}
@@ -0,0 +1,229 @@
+use std::collections::HashMap;
+
+use rust_decimal::Decimal;
+use serde::Serialize;
+
+use crate::money;
+use crate::state::TradingState;
+use crate::types::{ExecutionResult, ExecutionStatus, OrderSide, SimulatedOrder};
+
+/// Live vs simulated execution, included in the exit summary with `--shadow`.
+#[derive(Debug, Clone, Serialize)]
+pub struct ShadowSummary {
+    /// Total P&L of the simulated twin.
+    pub simulated_pnl: f64,
+    /// Total P&L of the live session.
+    pub live_pnl: f64,
+    /// `live_pnl - simulated_pnl`: the real-world cost of execution (negative
+    /// when live trails the simulation).
+    pub pnl_divergence: f64,
+    /// Live orders compared against their simulated fill price.
+    pub orders_compared: u64,
+    pub orders_filled: u64,
+    pub filled_notional_usd: f64,
+    /// Extra cost of live fills vs the simulated price (positive = worse).
+    pub execution_cost_usd: f64,
+    /// `execution_cost_usd` per filled notional, in basis points.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub slippage_bps: Option<f64>,
+    /// Orders not (fully) filled at submission: resting, failed, or skipped.
+    pub orders_unfilled: u64,
+    pub unfilled_notional_usd: f64,
+}
+
+/// Simulated twin of a live session, fed the same signals.
+///
+/// The twin plans and applies its own orders exactly as dry-run would, while
+/// live execution results are compared order by order against the price the
+/// simulation assumes.
+pub struct Shadow {
+    state: TradingState,
+    orders_compared: u64,
+    orders_filled: u64,
+    filled_notional_usd: Decimal,
+    execution_cost_usd: Decimal,
+    orders_unfilled: u64,
+    unfilled_notional_usd: Decimal,
+}
+
+impl Shadow {
+    /// Start a twin with the live session's budget and holdings.
+    pub fn new(live: &TradingState) -> Self {
+        let mut state = TradingState::new(live.initial_budget);
+        for position in live.holdings.values() {
+            state.seed_holding(position.clone());
+        }
+        Self {
+            state,
+            orders_compared: 0,
+            orders_filled: 0,
+            filled_notional_usd: Decimal::ZERO,
+            execution_cost_usd: Decimal::ZERO,
+            orders_unfilled: 0,
+            unfilled_notional_usd: Decimal::ZERO,
+        }
+    }
+
+    /// The simulated state, for planning the twin's orders.
+    pub fn state(&self) -> &TradingState {
+        &self.state
+    }
+
+    /// The simulated state, for the twin's order pipeline (TWAP schedules,
+    /// spend cap).
+    pub fn state_mut(&mut self) -> &mut TradingState {
+        &mut self.state
+    }
+
+    /// Round prices and budget fees with the live session's tick sizes and fee
+    /// rates, so the twin plans the same orders.
+    pub fn set_market_rules(&mut self, live: &TradingState) {
+        self.state.tick_sizes.clone_from(&live.tick_sizes);
+        self.state.fee_rates.clone_from(&live.fee_rates);
+    }
+
+    /// Apply the twin's own planned orders in full, taker fees included, as
+    /// dry-run does.
+    pub fn apply(&mut self, orders: &[SimulatedOrder]) {
+        let orders = crate::engine::charge_taker_fees(orders, &self.state);
+        self.state.apply_orders(&orders);
+        self.state.total_events += 1;
+    }
+
+    /// Compare live results with the planned price of each order. Returns the
+    /// execution cost of this batch in USD.
+    pub fn record_execution(
+        &mut self,
+        orders: &[SimulatedOrder],
+        results: &[ExecutionResult],
+    ) -> Decimal {
+        let mut batch_cost = Decimal::ZERO;
+        for result in results {
+            let Some(order) = orders.get(result.order_index) else {
+                continue;
+            };
+            self.orders_compared += 1;
+            let filled = matches!(
+                result.status,
+                ExecutionStatus::Filled | ExecutionStatus::PartialFill
+            ) && result.filled_shares > Decimal::ZERO;
+            if filled {
+                let simulated_cost = result.filled_shares * order.price;
+                let cost = match order.side {
+                    OrderSide::Buy => result.filled_cost_usd - simulated_cost,
+                    OrderSide::Sell => simulated_cost - result.filled_cost_usd,
+                };
+                self.orders_filled += 1;
+                self.filled_notional_usd += simulated_cost;
+                batch_cost += cost;
+            }
+            let unfilled_shares = (order.shares - result.filled_shares).max(Decimal::ZERO);
+            if !filled || result.status == ExecutionStatus::PartialFill {
+                self.orders_unfilled += 1;
+                self.unfilled_notional_usd += unfilled_shares * order.price;
+            }
+        }
+        self.execution_cost_usd += batch_cost;
+        batch_cost
+    }
+
+    /// Divergence between `live` and the twin, valued at `prices`.
+    pub fn summary(&self, live: &TradingState, prices: &HashMap<String, f64>) -> ShadowSummary {
+        let simulated_pnl = self.state.exit_summary(prices).total_pnl;
+        let live_pnl = live.exit_summary(prices).total_pnl;
+        ShadowSummary {
+            simulated_pnl,
+            live_pnl,
+            pnl_divergence: live_pnl - simulated_pnl,
+            orders_compared: self.orders_compared,
+            orders_filled: self.orders_filled,
+            filled_notional_usd: money::to_f64(self.filled_notional_usd),
+            execution_cost_usd: money::to_f64(self.execution_cost_usd),
+            slippage_bps: money::ratio(self.execution_cost_usd, self.filled_notional_usd)
+                .map(|ratio| money::to_f64(ratio) * 10_000.0),
+            orders_unfilled: self.orders_unfilled,
+            unfilled_notional_usd: money::to_f64(self.unfilled_notional_usd),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::MarketPosition;
+    use rust_decimal_macros::dec;
+
+    fn order(side: OrderSide, shares: Decimal, price: Decimal) -> SimulatedOrder {
+        SimulatedOrder {
+            market: MarketPosition {
+                condition_id: "c1".to_string(),
+                asset: "a1".to_string(),
+                title: String::new(),
+                outcome: String::new(),
+                outcome_index: 0,
+                event_slug: String::new(),
+                tags: Vec::new(),
+                opposite_asset: String::new(),
+            },
+            side,
+            shares,
+            price,
+            cost_usd: shares * price,
+        }
+    }
+
+    fn result(
+        index: usize,
+        status: ExecutionStatus,
+        filled_shares: Decimal,
+        cost: Decimal,
+    ) -> ExecutionResult {
+        ExecutionResult {
+            order_index: index,
+            status,
+            order_id: format!("o{index}"),
+            filled_shares,
+            filled_cost_usd: cost,
+            error_msg: None,
+        }
+    }
+
+    #[test]
+    fn execution_cost_and_pnl_divergence() {
+        let live = TradingState::new(dec!(100));
+        let mut shadow = Shadow::new(&live);
+        let orders = vec![
+            order(OrderSide::Buy, dec!(10), dec!(0.50)),
+            order(OrderSide::Sell, dec!(4), dec!(0.60)),
+            order(OrderSide::Buy, dec!(10), dec!(0.20)),
+        ];
+        shadow.apply(&orders[..1]);
+
+        // Buy filled 1c worse, sell 2c worse, third order rested
+        let cost = shadow.record_execution(
+            &orders,
+            &[
+                result(0, ExecutionStatus::Filled, dec!(10), dec!(5.10)),
+                result(1, ExecutionStatus::Filled, dec!(4), dec!(2.32)),
+                result(2, ExecutionStatus::Resting, Decimal::ZERO, Decimal::ZERO),
+            ],
+        );
+        assert_eq!(cost, dec!(0.18));
+
+        let prices = HashMap::from([("a1".to_string(), 0.55)]);
+        let summary = shadow.summary(&live, &prices);
+        assert_eq!(
+            (
+                summary.orders_compared,
+                summary.orders_filled,
+                summary.orders_unfilled
+            ),
+            (3, 2, 1)
+        );
+        assert!((summary.unfilled_notional_usd - 2.0).abs() < 1e-9);
+        assert!((summary.filled_notional_usd - 7.4).abs() < 1e-9);
+        // The twin holds 10 shares bought at 0.50, now worth 0.55
+        assert!((summary.simulated_pnl - 0.5).abs() < 1e-9);
+        assert!((summary.pnl_divergence + 0.5).abs() < 1e-9);
+    }
+}
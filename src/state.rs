@@ -1,9 +1,37 @@
-use std::collections::HashMap;
-
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use anyhow::{Context, Result, bail};
+use chrono::{DateTime, Days, NaiveDate, Utc};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use tracing::warn;
+
+use crate::artifacts::LedgerWriter;
+use crate::graduation::PaperTrack;
+use crate::journal::{Journal, JournalRecord, StateSnapshot};
+use crate::money;
 use crate::types::{
-    ExecutionResult, ExecutionStatus, ExitSummary, HeldPosition, HoldingSummary, OrderSide,
-    RestingOrder, SimulatedOrder,
+    CopytradeEvent, ExecutionResult, ExecutionStatus, ExitSummary, HeldPosition, HoldingSummary, MarketPosition,
+    LedgerEntry, OrderSide, PendingOrder, ResolutionRisk, RestingOrder, RestingOrderStatus,
+    SimulatedOrder, TagSummary, TwapSchedule,
 };
+use crate::store::StateStore;
+use crate::wal::{Mutation, WalEntry};
+
+/// Tolerance for checked mutations, for fill prices derived by division
+/// (`filled_cost / filled_shares`).
+const EPSILON: Decimal = dec!(0.000000001);
+
+/// Reject non-positive sizes and prices outside `[0, 1]`.
+fn validate_fill(shares: Decimal, price: Decimal) -> Result<()> {
+    if shares <= Decimal::ZERO {
+        bail!("invalid share count {shares}");
+    }
+    if !(Decimal::ZERO..=Decimal::ONE).contains(&price) {
+        bail!("invalid price {price}");
+    }
+    Ok(())
+}
 
 /// Tracks the bot's simulated trading state: holdings, budget, and P&L.
 pub struct TradingState {
@@ -11,74 +39,430 @@ pub struct TradingState {
     pub holdings: HashMap<String, HeldPosition>,
     /// Orders resting on the CLOB book (not yet filled).
     pub resting_orders: Vec<RestingOrder>,
-    pub initial_budget: f64,
-    pub budget_remaining: f64,
-    pub total_spent: f64,
-    pub total_sell_proceeds: f64,
-    pub realized_pnl: f64,
+    pub initial_budget: Decimal,
+    pub budget_remaining: Decimal,
+    pub total_spent: Decimal,
+    pub total_sell_proceeds: Decimal,
+    pub realized_pnl: Decimal,
     pub total_events: u64,
     pub total_orders: u64,
     pub total_buy_orders: u64,
     pub total_sell_orders: u64,
+    /// Where every fill applied this session is appended (orders and resting
+    /// fills), when `--artifacts` is set.
+    ledger: Option<LedgerWriter>,
+    /// Tags of every asset ordered this session, for reports (asset → tags).
+    pub asset_tags: HashMap<String, Vec<String>>,
+    /// Live orders submitted but not yet booked, by client order ID (see
+    /// `executor::submit_orders`). Left non-empty only by a crash mid-batch.
+    pub pending_orders: BTreeMap<String, PendingOrder>,
+    /// Live order batches settled so far: the cycle in client order IDs.
+    pub order_cycle: u64,
+    /// Price increment of each market looked up this session (asset → tick).
+    pub tick_sizes: HashMap<String, Decimal>,
+    /// Taker fee rate of each market looked up this session (asset → bps).
+    pub fee_rates: HashMap<String, u32>,
+    /// Assets blocked from re-buys after a risk exit → expiry (unix ms).
+    pub rebuy_cooldowns: HashMap<String, i64>,
+    /// Orders being sliced over poll cycles, by asset (`Mutation::Twap`).
+    pub twap_schedules: HashMap<String, TwapSchedule>,
+    /// Highest effective capital seen (see `record_capital`, `Mutation::PeakCapital`).
+    pub peak_capital: f64,
+    /// Set once the drawdown kill switch trips: no more buys.
+    pub killed: bool,
+    /// Progress of a `--graduate` paper session toward its criteria.
+    pub paper_track: Option<PaperTrack>,
+    /// UTC day tracked for the daily loss limit, with the effective capital at
+    /// its first measurement (see `daily_pnl`, `Mutation::DayStart`).
+    pub day_start: Option<(NaiveDate, f64)>,
+    /// Buys paused by the daily loss limit until this time (see
+    /// `buys_paused`).
+    pub paused_until: Option<DateTime<Utc>>,
+    /// Realized profit taken out of the working budget (see `lock_profit`),
+    /// no longer risked on buys.
+    pub locked_profit: Decimal,
+    /// Assets never sold automatically (manual pin).
+    pub pinned: BTreeSet<String>,
+    /// Assets sold and never re-entered (manual exclude).
+    pub excluded: BTreeSet<String>,
+    /// Optional store that `commit` records mutations to (write-ahead).
+    store: Option<Box<dyn StateStore>>,
+    /// Optional audit journal of mutations, events, and snapshots.
+    journal: Option<Journal>,
 }
 
 impl TradingState {
-    pub fn new(budget: f64) -> Self {
+    pub fn new(budget: Decimal) -> Self {
         Self {
             holdings: HashMap::new(),
             resting_orders: Vec::new(),
             initial_budget: budget,
             budget_remaining: budget,
-            total_spent: 0.0,
-            total_sell_proceeds: 0.0,
-            realized_pnl: 0.0,
+            total_spent: Decimal::ZERO,
+            total_sell_proceeds: Decimal::ZERO,
+            realized_pnl: Decimal::ZERO,
             total_events: 0,
             total_orders: 0,
             total_buy_orders: 0,
             total_sell_orders: 0,
+            ledger: None,
+            asset_tags: HashMap::new(),
+            pending_orders: BTreeMap::new(),
+            order_cycle: 0,
+            tick_sizes: HashMap::new(),
+            fee_rates: HashMap::new(),
+            rebuy_cooldowns: HashMap::new(),
+            twap_schedules: HashMap::new(),
+            peak_capital: money::to_f64(budget),
+            killed: false,
+            paper_track: None,
+            day_start: None,
+            paused_until: None,
+            locked_profit: Decimal::ZERO,
+            pinned: BTreeSet::new(),
+            excluded: BTreeSet::new(),
+            store: None,
+            journal: None,
+        }
+    }
+
+    /// Attach a state store. Subsequent `commit` calls and reported events are
+    /// recorded to it.
+    pub fn attach_store(&mut self, store: Box<dyn StateStore>) {
+        self.store = Some(store);
+    }
+
+    /// Attach an audit journal, starting a session in it. A `resumed` session
+    /// continues from state restored from the WAL (attach the journal before
+    /// replaying, so re-applied entries are journaled).
+    pub fn attach_journal(&mut self, mut journal: Journal, resumed: bool) {
+        let session = JournalRecord::Session {
+            timestamp: crate::clock::now().to_rfc3339(),
+            initial_budget: self.initial_budget,
+            resumed,
+        };
+        if let Err(e) = journal.record(&session) {
+            warn!("Failed to write journal session: {e:#}");
+        }
+        self.journal = Some(journal);
+    }
+
+    /// Attach the fill ledger. Subsequent fills are appended to it (attach it
+    /// before replaying, so re-applied fills are recorded).
+    pub fn attach_ledger(&mut self, ledger: LedgerWriter) {
+        self.ledger = Some(ledger);
+    }
+
+    /// Detach the fill ledger, to carry it over to a new state.
+    pub fn take_ledger(&mut self) -> Option<LedgerWriter> {
+        self.ledger.take()
+    }
+
+    /// Apply a mutation, recording it to the store first if one is attached.
+    ///
+    /// Store write failures are logged but do not block the mutation — the exchange
+    /// has already acted, so in-memory state must follow.
+    pub fn commit(&mut self, mutation: Mutation) {
+        let seq = match self.store.as_mut().map(|s| s.save(&mutation)) {
+            Some(Ok(seq)) => Some(seq),
+            Some(Err(e)) => {
+                warn!("Failed to write WAL intent: {e:#}");
+                None
+            }
+            None => None,
+        };
+        self.apply_mutation(&mutation);
+        self.journal_mutation(seq, &mutation);
+        if let (Some(store), Some(seq)) = (self.store.as_mut(), seq)
+            && let Err(e) = store.ack(seq)
+        {
+            warn!("Failed to write WAL ack for #{seq}: {e:#}");
+        }
+    }
+
+    /// Rebuild state from write-ahead log entries, in sequence order.
+    ///
+    /// Unacknowledged entries (intent written, process stopped before applying) are
+    /// applied too and acknowledged in the attached store. Returns how many there were.
+    pub fn replay(&mut self, entries: &[WalEntry]) -> usize {
+        let mut unacked = 0;
+        for entry in entries {
+            self.apply_mutation(&entry.mutation);
+            if !entry.acked {
+                unacked += 1;
+                // The crash may have come before the journal write
+                self.journal_mutation(Some(entry.seq), &entry.mutation);
+                if let Some(store) = self.store.as_mut()
+                    && let Err(e) = store.ack(entry.seq)
+                {
+                    warn!("Failed to write WAL ack for #{}: {e:#}", entry.seq);
+                }
+            }
+        }
+        unacked
+    }
+
+    fn journal_mutation(&mut self, seq: Option<u64>, mutation: &Mutation) {
+        if let Some(journal) = self.journal.as_mut()
+            && let Err(e) = journal.record_mutation(seq, mutation)
+        {
+            warn!("Failed to write journal mutation: {e:#}");
+        }
+    }
+
+    /// Count a reported event, storing it and journaling it with a snapshot of
+    /// the state.
+    pub fn record_event(&mut self, event: &CopytradeEvent) {
+        self.total_events += 1;
+        if let Some(store) = self.store.as_mut()
+            && let Err(e) = store.append_event(event)
+        {
+            warn!("Failed to store event: {e:#}");
+        }
+        if let Some(mut journal) = self.journal.take() {
+            if let Err(e) = journal.record_event(event, self) {
+                warn!("Failed to write journal event: {e:#}");
+            }
+            self.journal = Some(journal);
+        }
+    }
+
+    /// Journal a snapshot of the state (e.g. at shutdown, after the last event).
+    pub fn journal_snapshot(&mut self) {
+        let snapshot = JournalRecord::Snapshot {
+            state: StateSnapshot::of(self),
+        };
+        if let Some(journal) = self.journal.as_mut()
+            && let Err(e) = journal.record(&snapshot)
+        {
+            warn!("Failed to write journal snapshot: {e:#}");
         }
     }
 
+    /// Apply a mutation directly, without recording it.
+    pub fn apply_mutation(&mut self, mutation: &Mutation) {
+        match mutation {
+            Mutation::SeedHolding { position } => self.seed_holding(position.clone()),
+            Mutation::ApplyOrders { orders } => self.apply_orders(orders),
+            Mutation::SubmitOrders { orders, .. } => self.pending_orders.extend(orders.clone()),
+            Mutation::ApplyExecutionResults {
+                orders,
+                results,
+                client_order_ids,
+            } => {
+                self.apply_execution_results(orders, results);
+                self.settle_pending(client_order_ids);
+            }
+            Mutation::RestingFill {
+                order_id,
+                filled_shares,
+                fill_price,
+            } => self.resolve_resting_fill(order_id, *filled_shares, *fill_price),
+            Mutation::RestingCancel { order_id } => self.resolve_resting_cancel(order_id),
+            Mutation::MergeSets {
+                asset,
+                opposite_asset,
+                shares,
+            } => self.apply_merge(asset, opposite_asset, *shares),
+            Mutation::RebuyCooldown { asset, until_ms } => {
+                self.start_rebuy_cooldown(asset, *until_ms)
+            }
+            Mutation::DailyLossPause { until_ms } => {
+                self.paused_until = DateTime::from_timestamp_millis(*until_ms);
+            }
+            Mutation::DayStart { day, capital } => self.day_start = Some((*day, *capital)),
+            Mutation::RebaseBudget { budget } => self.rebase_budget(*budget),
+            Mutation::PeakCapital { capital } => self.peak_capital = *capital,
+            Mutation::KillSwitch { .. } => self.killed = true,
+            Mutation::LockProfit { amount } => self.apply_locked_profit(*amount),
+            Mutation::Pin { asset, pinned } => {
+                if *pinned {
+                    self.excluded.remove(asset);
+                    self.pinned.insert(asset.clone());
+                } else {
+                    self.pinned.remove(asset);
+                }
+            }
+            Mutation::Exclude { asset, excluded } => {
+                if *excluded {
+                    self.pinned.remove(asset);
+                    self.excluded.insert(asset.clone());
+                } else {
+                    self.excluded.remove(asset);
+                }
+            }
+            Mutation::Twap { asset, schedule } => match schedule {
+                Some(schedule) => {
+                    self.twap_schedules.insert(asset.clone(), schedule.clone());
+                }
+                None => {
+                    self.twap_schedules.remove(asset);
+                }
+            },
+            Mutation::PaperStart { started_at } => {
+                self.paper_track = Some(PaperTrack::new(*started_at));
+            }
+            Mutation::PaperSample { sample } => {
+                if let Some(track) = self.paper_track.as_mut() {
+                    track.record(sample.clone());
+                }
+            }
+        }
+    }
+
+    /// Add an existing position (e.g. from the Safe wallet), committing its cost basis.
+    pub fn seed_holding(&mut self, position: HeldPosition) {
+        self.budget_remaining -= position.total_cost;
+        self.total_spent += position.total_cost;
+        self.holdings.insert(position.asset.clone(), position);
+    }
+
+    /// Token ID of the other outcome in `asset`'s binary market, if known from
+    /// a holding of either outcome.
+    pub fn opposite_asset(&self, asset: &str) -> Option<&str> {
+        if let Some(held) = self.holdings.get(asset)
+            && !held.opposite_asset.is_empty()
+        {
+            return Some(&held.opposite_asset);
+        }
+        self.holdings
+            .values()
+            .find(|held| held.opposite_asset == asset)
+            .map(|held| held.asset.as_str())
+    }
+
+    /// Replace the initial budget, moving `budget_remaining` by the same amount.
+    /// Spending, proceeds, and reservations are unchanged, so a deposit or
+    /// withdrawal shows up as more or less cash to deploy.
+    pub fn rebase_budget(&mut self, budget: Decimal) {
+        let change = budget - self.initial_budget;
+        self.budget_remaining += change;
+        self.peak_capital += money::to_f64(change);
+        if let Some((_, start)) = &mut self.day_start {
+            *start += money::to_f64(change);
+        }
+        self.initial_budget = budget;
+    }
+
     /// Running budget: cash + current market value of all holdings + resting order value.
-    pub fn effective_capital(&self, prices: &HashMap<String, f64>) -> f64 {
-        let holdings_value: f64 = self
+    pub fn effective_capital(&self, prices: &HashMap<String, f64>) -> Decimal {
+        let holdings_value: Decimal = self
             .holdings
             .iter()
             .map(|(asset, held)| {
-                let price = prices.get(asset).copied().unwrap_or(held.avg_cost);
-                held.shares * price
+                let price = prices.get(asset).map(|&p| money::from_f64(p));
+                held.shares * price.unwrap_or(held.avg_cost)
             })
             .sum();
         // Include value of resting buy orders (budget was already deducted for these)
-        let resting_buy_value: f64 = self
+        let resting_buy_value: Decimal = self
             .resting_orders
             .iter()
             .filter(|r| r.side == OrderSide::Buy)
             .map(|r| {
-                let price = prices.get(&r.asset).copied().unwrap_or(r.price);
-                r.shares * price
+                let price = prices.get(&r.asset).map(|&p| money::from_f64(p));
+                r.shares * price.unwrap_or(r.price)
             })
             .sum();
         self.budget_remaining + holdings_value + resting_buy_value
     }
 
+    /// Raise the peak to `capital` if higher, and return the drawdown from the
+    /// peak in percent (0 at a new high). New highs are recorded in the
+    /// write-ahead log, so a restart measures drawdown from the same peak.
+    pub fn record_capital(&mut self, capital: f64) -> f64 {
+        if capital > self.peak_capital {
+            self.commit(Mutation::PeakCapital { capital });
+        }
+        if self.peak_capital <= 0.0 {
+            return 0.0;
+        }
+        (1.0 - capital / self.peak_capital) * 100.0
+    }
+
+    /// P&L since the start of `now`'s UTC day, realized and unrealized: the
+    /// change in effective `capital` since the day's first measurement, which
+    /// sets the baseline (0 on that call). The baseline is recorded in the
+    /// write-ahead log, so a restart mid-day keeps it.
+    pub fn daily_pnl(&mut self, capital: f64, now: DateTime<Utc>) -> f64 {
+        let today = now.date_naive();
+        match self.day_start {
+            Some((day, start)) if day == today => capital - start,
+            _ => {
+                self.commit(Mutation::DayStart {
+                    day: today,
+                    capital,
+                });
+                0.0
+            }
+        }
+    }
+
+    /// Pause buys until the next UTC midnight, returning it. Recorded in the
+    /// write-ahead log, so a restart doesn't lift the pause.
+    pub fn pause_for_day(&mut self, now: DateTime<Utc>) -> DateTime<Utc> {
+        let tomorrow = now.date_naive() + Days::new(1);
+        let until = tomorrow.and_time(chrono::NaiveTime::MIN).and_utc();
+        self.commit(Mutation::DailyLossPause {
+            until_ms: until.timestamp_millis(),
+        });
+        until
+    }
+
+    /// Whether the daily loss limit pauses buys at `now_ms`. Sells and risk
+    /// exits go on.
+    pub fn buys_paused(&self, now_ms: i64) -> bool {
+        self.paused_until
+            .is_some_and(|until| until.timestamp_millis() > now_ms)
+    }
+
+    /// Trip the drawdown kill switch, blocking all further buys. Recorded in the
+    /// write-ahead log, so a restarted bot stays stopped.
+    pub fn trip_kill_switch(&mut self, drawdown_pct: f64) {
+        self.commit(Mutation::KillSwitch { drawdown_pct });
+    }
+
+    /// Once realized P&L not yet locked reaches `threshold`, lock it: it leaves
+    /// the working budget (up to the cash on hand), taking the budget back to
+    /// its initial amount. Returns the amount locked.
+    pub fn lock_profit(&mut self, threshold: Decimal) -> Option<Decimal> {
+        let unlocked = self.realized_pnl - self.locked_profit;
+        let amount = unlocked.min(self.budget_remaining);
+        if unlocked < threshold || amount <= Decimal::ZERO {
+            return None;
+        }
+        self.commit(Mutation::LockProfit { amount });
+        Some(amount)
+    }
+
+    /// Locking profit is not a loss: the drawdown peak and the daily P&L
+    /// baseline move with the capital.
+    fn apply_locked_profit(&mut self, amount: Decimal) {
+        self.locked_profit += amount;
+        self.budget_remaining -= amount;
+        self.peak_capital -= money::to_f64(amount);
+        if let Some((_, start)) = &mut self.day_start {
+            *start -= money::to_f64(amount);
+        }
+    }
+
     /// Effective held shares for an asset, including resting order adjustments.
     ///
     /// Returns `holdings.shares + resting_buy_shares - resting_sell_shares` so the
     /// rebalancing engine doesn't generate duplicate orders for resting positions.
-    pub fn effective_held_shares(&self, asset: &str) -> f64 {
+    pub fn effective_held_shares(&self, asset: &str) -> Decimal {
         let held = self
             .holdings
             .get(asset)
             .map(|h| h.shares)
-            .unwrap_or(0.0);
-        let resting_buy: f64 = self
+            .unwrap_or_default();
+        let resting_buy: Decimal = self
             .resting_orders
             .iter()
             .filter(|r| r.asset == asset && r.side == OrderSide::Buy)
             .map(|r| r.shares)
             .sum();
-        let resting_sell: f64 = self
+        let resting_sell: Decimal = self
             .resting_orders
             .iter()
             .filter(|r| r.asset == asset && r.side == OrderSide::Sell)
@@ -102,8 +486,8 @@ impl TradingState {
     pub fn resolve_resting_fill(
         &mut self,
         order_id: &str,
-        filled_shares: f64,
-        fill_price: f64,
+        filled_shares: Decimal,
+        fill_price: Decimal,
     ) {
         let idx = match self.resting_orders.iter().position(|r| r.order_id == order_id) {
             Some(i) => i,
@@ -111,6 +495,7 @@ impl TradingState {
         };
         let resting = self.resting_orders.remove(idx);
         let filled_cost = filled_shares * fill_price;
+        let realized_before = self.realized_pnl;
 
         match resting.side {
             OrderSide::Buy => {
@@ -125,21 +510,22 @@ impl TradingState {
                 let asset_key = resting.asset.clone();
                 let held = self
                     .holdings
-                    .entry(resting.asset)
+                    .entry(resting.asset.clone())
                     .or_insert_with(|| HeldPosition {
                         asset: asset_key,
                         title: resting.title.clone(),
                         outcome: resting.outcome.clone(),
-                        shares: 0.0,
-                        total_cost: 0.0,
-                        avg_cost: 0.0,
+                        shares: Decimal::ZERO,
+                        total_cost: Decimal::ZERO,
+                        avg_cost: Decimal::ZERO,
+                        opposite_asset: resting.opposite_asset.clone(),
                     });
                 held.shares += filled_shares;
                 held.total_cost += filled_cost;
-                held.avg_cost = if held.shares > 0.0 {
+                held.avg_cost = if held.shares > Decimal::ZERO {
                     held.total_cost / held.shares
                 } else {
-                    0.0
+                    Decimal::ZERO
                 };
             }
             OrderSide::Sell => {
@@ -152,13 +538,40 @@ impl TradingState {
                     self.realized_pnl += pnl;
                     held.shares -= filled_shares;
                     held.total_cost -= held.avg_cost * filled_shares;
-                    if held.shares <= 0.0 {
+                    if held.shares <= Decimal::ZERO {
                         self.holdings.remove(&resting.asset);
                     }
                 }
             }
         }
         self.total_orders += 1;
+        let realized_pnl = self.realized_pnl - realized_before;
+        self.record_ledger(LedgerEntry {
+            timestamp: crate::clock::now().to_rfc3339(),
+            tags: self.asset_tags.get(&resting.asset).cloned().unwrap_or_default(),
+            asset: resting.asset,
+            title: resting.title,
+            outcome: resting.outcome,
+            side: resting.side,
+            shares: filled_shares,
+            price: fill_price,
+            cost_usd: filled_cost,
+            realized_pnl,
+        });
+    }
+
+    /// Budget reserved by resting buys.
+    pub fn reserved_buys(&self) -> Decimal {
+        self.resting_orders
+            .iter()
+            .filter(|r| r.side == OrderSide::Buy)
+            .map(|r| r.cost_usd)
+            .sum()
+    }
+
+    /// Spent on buys so far, resting buys included (sell proceeds aside).
+    pub fn committed_spend(&self) -> Decimal {
+        self.total_spent + self.reserved_buys()
     }
 
     /// Handle a resting order that was cancelled without filling.
@@ -178,51 +591,372 @@ impl TradingState {
     /// Apply a set of simulated orders to the trading state.
     pub fn apply_orders(&mut self, orders: &[SimulatedOrder]) {
         for order in orders {
-            match order.side {
-                OrderSide::Buy => {
-                    self.budget_remaining -= order.cost_usd;
-                    self.total_spent += order.cost_usd;
-                    self.total_buy_orders += 1;
-
-                    let held = self
-                        .holdings
-                        .entry(order.market.asset.clone())
-                        .or_insert_with(|| HeldPosition {
-                            asset: order.market.asset.clone(),
-                            title: order.market.title.clone(),
-                            outcome: order.market.outcome.clone(),
-                            shares: 0.0,
-                            total_cost: 0.0,
-                            avg_cost: 0.0,
-                        });
-                    held.shares += order.shares;
-                    held.total_cost += order.cost_usd;
-                    held.avg_cost = if held.shares > 0.0 {
-                        held.total_cost / held.shares
-                    } else {
-                        0.0
-                    };
+            self.apply_order(order);
+        }
+    }
+
+    /// Apply a single simulated order (unchecked — see `apply_order_checked`).
+    fn apply_order(&mut self, order: &SimulatedOrder) {
+        self.apply_fill(order);
+        match order.side {
+            OrderSide::Buy => self.total_buy_orders += 1,
+            OrderSide::Sell => self.total_sell_orders += 1,
+        }
+        self.total_orders += 1;
+    }
+
+    /// Update holdings, budget, and P&L for a fill, without touching order counters.
+    fn apply_fill(&mut self, order: &SimulatedOrder) {
+        self.remember_tags(&order.market);
+        let realized_before = self.realized_pnl;
+        match order.side {
+            OrderSide::Buy => {
+                self.budget_remaining -= order.cost_usd;
+                self.total_spent += order.cost_usd;
+
+                let held = self
+                    .holdings
+                    .entry(order.market.asset.clone())
+                    .or_insert_with(|| HeldPosition {
+                        asset: order.market.asset.clone(),
+                        title: order.market.title.clone(),
+                        outcome: order.market.outcome.clone(),
+                        shares: Decimal::ZERO,
+                        total_cost: Decimal::ZERO,
+                        avg_cost: Decimal::ZERO,
+                        opposite_asset: String::new(),
+                    });
+                if held.opposite_asset.is_empty() {
+                    held.opposite_asset = order.market.opposite_asset.clone();
                 }
-                OrderSide::Sell => {
-                    self.budget_remaining += order.cost_usd;
-                    self.total_sell_proceeds += order.cost_usd;
-                    self.total_sell_orders += 1;
-
-                    if let Some(held) = self.holdings.get_mut(&order.market.asset) {
-                        // Realized P&L = (sell_price - avg_cost) * shares
-                        let pnl = (order.price - held.avg_cost) * order.shares;
-                        self.realized_pnl += pnl;
-
-                        held.shares -= order.shares;
-                        held.total_cost -= held.avg_cost * order.shares;
-                        if held.shares <= 0.0 {
-                            self.holdings.remove(&order.market.asset);
-                        }
+                held.shares += order.shares;
+                held.total_cost += order.cost_usd;
+                held.avg_cost = if held.shares > Decimal::ZERO {
+                    held.total_cost / held.shares
+                } else {
+                    Decimal::ZERO
+                };
+            }
+            OrderSide::Sell => {
+                self.budget_remaining += order.cost_usd;
+                self.total_sell_proceeds += order.cost_usd;
+
+                if let Some(held) = self.holdings.get_mut(&order.market.asset) {
+                    // Realized P&L = (sell_price - avg_cost) * shares
+                    let pnl = (order.price - held.avg_cost) * order.shares;
+                    self.realized_pnl += pnl;
+
+                    held.shares -= order.shares;
+                    held.total_cost -= held.avg_cost * order.shares;
+                    if held.shares <= Decimal::ZERO {
+                        self.holdings.remove(&order.market.asset);
                     }
                 }
             }
-            self.total_orders += 1;
         }
+        let realized_pnl = self.realized_pnl - realized_before;
+        self.record_ledger(LedgerEntry {
+            timestamp: crate::clock::now().to_rfc3339(),
+            asset: order.market.asset.clone(),
+            title: order.market.title.clone(),
+            outcome: order.market.outcome.clone(),
+            side: order.side,
+            shares: order.shares,
+            price: order.price,
+            cost_usd: order.cost_usd,
+            realized_pnl,
+            tags: self
+                .asset_tags
+                .get(&order.market.asset)
+                .cloned()
+                .unwrap_or_default(),
+        });
+    }
+
+    fn record_ledger(&mut self, entry: LedgerEntry) {
+        if let Some(ledger) = self.ledger.as_mut()
+            && let Err(e) = ledger.append(&entry)
+        {
+            warn!("Failed to write ledger entry: {e:#}");
+        }
+    }
+
+    /// Complete sets held: for each market where both outcomes are held, the
+    /// two token IDs and the shares held on the smaller side. One share of each
+    /// outcome pays $1 together whatever the result, so the set is cash tied up.
+    pub fn held_sets(&self) -> Vec<(String, String, Decimal)> {
+        let mut sets: Vec<(String, String, Decimal)> = self
+            .holdings
+            .values()
+            .filter_map(|held| {
+                let opposite = self.holdings.get(&held.opposite_asset)?;
+                (held.asset < opposite.asset).then(|| {
+                    let shares = held.shares.min(opposite.shares);
+                    (held.asset.clone(), opposite.asset.clone(), shares)
+                })
+            })
+            .filter(|(_, _, shares)| *shares >= crate::engine::SHARE_INCREMENT)
+            .collect();
+        sets.sort_by(|a, b| a.0.cmp(&b.0));
+        sets
+    }
+
+    /// Merge every complete set held back into USDC (`[engine]
+    /// merge_complete_sets`), returning the title and shares of each merge.
+    pub fn merge_sets(&mut self) -> Vec<(String, Decimal)> {
+        let mut merged = Vec::new();
+        for (asset, opposite_asset, shares) in self.held_sets() {
+            merged.push((self.holdings[&asset].title.clone(), shares));
+            self.commit(Mutation::MergeSets {
+                asset,
+                opposite_asset,
+                shares,
+            });
+        }
+        merged
+    }
+
+    /// Book a merge as a sell of each side, splitting the $1 a set pays between
+    /// them in proportion to their average costs. It isn't an exchange order, so
+    /// the order counters are left alone.
+    fn apply_merge(&mut self, asset: &str, opposite_asset: &str, shares: Decimal) {
+        let (Some(held), Some(opposite)) =
+            (self.holdings.get(asset), self.holdings.get(opposite_asset))
+        else {
+            return;
+        };
+        let shares = shares.min(held.shares).min(opposite.shares);
+        let cost = held.avg_cost + opposite.avg_cost;
+        let price = money::ratio(held.avg_cost, cost).unwrap_or(dec!(0.5));
+        let sides = [(held.clone(), price), (opposite.clone(), Decimal::ONE - price)];
+        for (held, price) in sides {
+            let fill = SimulatedOrder {
+                market: MarketPosition {
+                    condition_id: String::new(),
+                    tags: self.asset_tags.get(&held.asset).cloned().unwrap_or_default(),
+                    asset: held.asset,
+                    title: held.title,
+                    outcome: held.outcome,
+                    outcome_index: 0,
+                    event_slug: String::new(),
+                    opposite_asset: held.opposite_asset,
+                },
+                side: OrderSide::Sell,
+                shares,
+                price,
+                cost_usd: shares * price,
+            };
+            self.apply_fill(&fill);
+        }
+    }
+
+    /// Tick size of `asset`'s market, or `engine::DEFAULT_TICK_SIZE` when it
+    /// hasn't been looked up.
+    pub fn tick_size(&self, asset: &str) -> Decimal {
+        self.tick_sizes
+            .get(asset)
+            .copied()
+            .unwrap_or(crate::engine::DEFAULT_TICK_SIZE)
+    }
+
+    /// Taker fee rate of `asset`'s market in bps, or no fee when it hasn't been
+    /// looked up.
+    pub fn fee_rate_bps(&self, asset: &str) -> u32 {
+        self.fee_rates.get(asset).copied().unwrap_or(0)
+    }
+
+    fn remember_tags(&mut self, market: &MarketPosition) {
+        if !market.tags.is_empty() {
+            self.asset_tags.insert(market.asset.clone(), market.tags.clone());
+        }
+    }
+
+    /// Block re-buys of `asset` until `until_ms` (unix ms). An existing, later
+    /// expiry is kept.
+    pub fn start_rebuy_cooldown(&mut self, asset: &str, until_ms: i64) {
+        let until = self.rebuy_cooldowns.entry(asset.to_string()).or_insert(until_ms);
+        *until = (*until).max(until_ms);
+    }
+
+    /// Blacklist `asset` from re-buys for `cooldown` after a risk rule exits it, so
+    /// the rebalancer doesn't re-enter on the next cycle while the trader still
+    /// holds it. Recorded in the write-ahead log, so the cooldown survives restarts.
+    pub fn block_rebuy(&mut self, asset: &str, cooldown: std::time::Duration) {
+        let until_ms = crate::clock::now().timestamp_millis() + cooldown.as_millis() as i64;
+        self.commit(Mutation::RebuyCooldown {
+            asset: asset.to_string(),
+            until_ms,
+        });
+    }
+
+    /// Whether re-buys of `asset` are blocked at `now_ms`.
+    pub fn rebuy_blocked(&self, asset: &str, now_ms: i64) -> bool {
+        self.rebuy_cooldowns
+            .get(asset)
+            .is_some_and(|&until| until > now_ms)
+    }
+
+    // ── Checked mutations ──────────────────────────────────────────
+    //
+    // Validating counterparts of the mutation methods. Each rejects inputs that
+    // would break accounting (overspending, overselling, unknown order IDs) and
+    // leaves the state untouched on error.
+
+    /// Apply a single filled order, rejecting invalid sizes, buys beyond
+    /// `budget_remaining`, and sells beyond the shares not already resting for sale.
+    pub fn apply_order_checked(&mut self, order: &SimulatedOrder) -> Result<()> {
+        validate_fill(order.shares, order.price)?;
+        if (order.cost_usd - order.shares * order.price).abs() > EPSILON {
+            bail!(
+                "cost ${} does not match {} shares @ ${}",
+                order.cost_usd,
+                order.shares,
+                order.price
+            );
+        }
+        match order.side {
+            OrderSide::Buy => {
+                if order.cost_usd > self.budget_remaining + EPSILON {
+                    bail!(
+                        "buy ${:.4} exceeds budget remaining ${:.4}",
+                        order.cost_usd,
+                        self.budget_remaining
+                    );
+                }
+            }
+            OrderSide::Sell => {
+                let sellable = self.sellable_shares(&order.market.asset);
+                if order.shares > sellable + EPSILON {
+                    bail!(
+                        "sell {} shares of {} exceeds sellable {sellable}",
+                        order.shares,
+                        order.market.asset
+                    );
+                }
+            }
+        }
+        self.apply_order(order);
+        Ok(())
+    }
+
+    /// Track a resting order, rejecting duplicate IDs, buys beyond
+    /// `budget_remaining`, and sells beyond the shares not already resting for sale.
+    pub fn add_resting_order_checked(&mut self, order: RestingOrder) -> Result<()> {
+        validate_fill(order.shares, order.price)?;
+        if self.resting_orders.iter().any(|r| r.order_id == order.order_id) {
+            bail!("resting order {} already tracked", order.order_id);
+        }
+        match order.side {
+            OrderSide::Buy => {
+                if order.cost_usd > self.budget_remaining + EPSILON {
+                    bail!(
+                        "resting buy ${:.4} exceeds budget remaining ${:.4}",
+                        order.cost_usd,
+                        self.budget_remaining
+                    );
+                }
+            }
+            OrderSide::Sell => {
+                let sellable = self.sellable_shares(&order.asset);
+                if order.shares > sellable + EPSILON {
+                    bail!(
+                        "resting sell {} shares of {} exceeds sellable {sellable}",
+                        order.shares,
+                        order.asset
+                    );
+                }
+            }
+        }
+        self.add_resting_order(order);
+        Ok(())
+    }
+
+    /// Resolve a resting order fill, rejecting unknown IDs and fills larger than
+    /// the resting size. Buy fills may not cost more than was reserved plus
+    /// `budget_remaining`.
+    pub fn resolve_resting_fill_checked(
+        &mut self,
+        order_id: &str,
+        filled_shares: Decimal,
+        fill_price: Decimal,
+    ) -> Result<()> {
+        validate_fill(filled_shares, fill_price)?;
+        let resting = self
+            .resting_orders
+            .iter()
+            .find(|r| r.order_id == order_id)
+            .with_context(|| format!("unknown resting order {order_id}"))?;
+        if filled_shares > resting.shares + EPSILON {
+            bail!(
+                "fill of {filled_shares} exceeds resting size {} for {order_id}",
+                resting.shares
+            );
+        }
+        if resting.side == OrderSide::Buy
+            && filled_shares * fill_price > resting.cost_usd + self.budget_remaining + EPSILON
+        {
+            bail!("fill cost exceeds reservation plus budget for {order_id}");
+        }
+        self.resolve_resting_fill(order_id, filled_shares, fill_price);
+        Ok(())
+    }
+
+    /// Resolve a resting order cancellation, rejecting unknown IDs.
+    pub fn resolve_resting_cancel_checked(&mut self, order_id: &str) -> Result<()> {
+        if !self.resting_orders.iter().any(|r| r.order_id == order_id) {
+            bail!("unknown resting order {order_id}");
+        }
+        self.resolve_resting_cancel(order_id);
+        Ok(())
+    }
+
+    /// Held shares not already committed to a resting sell.
+    fn sellable_shares(&self, asset: &str) -> Decimal {
+        let held = self.holdings.get(asset).map(|h| h.shares).unwrap_or_default();
+        let resting_sell: Decimal = self
+            .resting_orders
+            .iter()
+            .filter(|r| r.asset == asset && r.side == OrderSide::Sell)
+            .map(|r| r.shares)
+            .sum();
+        held - resting_sell
+    }
+
+    /// Verify accounting invariants:
+    ///
+    /// - cash reconciles: `budget_remaining = initial_budget - total_spent
+    ///   + total_sell_proceeds - reserved resting buys`
+    /// - every holding has positive shares and `total_cost ≈ avg_cost × shares`
+    /// - resting order IDs are unique
+    /// - order counters add up
+    pub fn check_invariants(&self) -> Result<()> {
+        let reserved = self.reserved_buys();
+        let expected = self.initial_budget - self.total_spent + self.total_sell_proceeds
+            - reserved
+            - self.locked_profit;
+        if (self.budget_remaining - expected).abs() > dec!(0.000001) {
+            bail!(
+                "budget_remaining {} != expected {expected}",
+                self.budget_remaining
+            );
+        }
+        for (asset, held) in &self.holdings {
+            if held.shares <= Decimal::ZERO {
+                bail!("holding {asset} has non-positive shares {}", held.shares);
+            }
+            if (held.total_cost - held.avg_cost * held.shares).abs() > dec!(0.000001) {
+                bail!("holding {asset} cost basis out of sync");
+            }
+        }
+        let mut ids: Vec<&str> = self.resting_orders.iter().map(|r| r.order_id.as_str()).collect();
+        ids.sort_unstable();
+        if ids.windows(2).any(|w| w[0] == w[1]) {
+            bail!("duplicate resting order IDs");
+        }
+        if self.total_buy_orders + self.total_sell_orders != self.total_orders {
+            bail!("order counters out of sync");
+        }
+        Ok(())
     }
 
     /// Apply live execution results to the trading state.
@@ -235,6 +969,10 @@ impl TradingState {
         orders: &[SimulatedOrder],
         results: &[ExecutionResult],
     ) {
+        // Resting orders carry no market info, so record tags up front
+        for order in orders {
+            self.remember_tags(&order.market);
+        }
         let filled_orders: Vec<SimulatedOrder> = results
             .iter()
             .filter(|r| {
@@ -246,11 +984,8 @@ impl TradingState {
                     market: original.market.clone(),
                     side: original.side,
                     shares: r.filled_shares,
-                    price: if r.filled_shares > 0.0 {
-                        r.filled_cost_usd / r.filled_shares
-                    } else {
-                        original.price
-                    },
+                    price: money::ratio(r.filled_cost_usd, r.filled_shares)
+                        .unwrap_or(original.price),
                     cost_usd: r.filled_cost_usd,
                 })
             })
@@ -259,6 +994,7 @@ impl TradingState {
         self.apply_orders(&filled_orders);
 
         // Track resting orders (budget reserved for buys, sells tracked for dedup)
+        let placed_at_ms = crate::clock::now().timestamp_millis();
         for result in results {
             if let Some(original) = orders.get(result.order_index) {
                 match result.status {
@@ -272,12 +1008,14 @@ impl TradingState {
                             shares: original.shares,
                             price: original.price,
                             cost_usd: original.cost_usd,
+                            opposite_asset: original.market.opposite_asset.clone(),
+                            placed_at_ms,
                         });
                     }
                     ExecutionStatus::PartialFill => {
                         // Track the unfilled remainder as a resting order
                         let remaining_shares = original.shares - result.filled_shares;
-                        if remaining_shares > 0.0 && !result.order_id.is_empty() {
+                        if remaining_shares > Decimal::ZERO && !result.order_id.is_empty() {
                             let remaining_cost = remaining_shares * original.price;
                             self.add_resting_order(RestingOrder {
                                 order_id: result.order_id.clone(),
@@ -288,6 +1026,8 @@ impl TradingState {
                                 shares: remaining_shares,
                                 price: original.price,
                                 cost_usd: remaining_cost,
+                                opposite_asset: original.market.opposite_asset.clone(),
+                                placed_at_ms,
                             });
                         }
                     }
@@ -297,52 +1037,154 @@ impl TradingState {
         }
     }
 
+    /// Drop the settled submissions `ids` from `pending_orders`, closing the
+    /// order cycle they were submitted in.
+    fn settle_pending(&mut self, ids: &[String]) {
+        if ids.is_empty() {
+            return;
+        }
+        for id in ids {
+            self.pending_orders.remove(id);
+        }
+        self.order_cycle += 1;
+    }
+
+    /// Binary risk of every holding: P&L if it resolves to 0 or to 1, sorted by
+    /// time to resolution (soonest first, unknown end dates last).
+    ///
+    /// `latest_prices` and `end_dates` map asset token ID → current price / end date.
+    pub fn resolution_risk(
+        &self,
+        latest_prices: &HashMap<String, f64>,
+        end_dates: &HashMap<String, DateTime<Utc>>,
+        now: DateTime<Utc>,
+    ) -> Vec<ResolutionRisk> {
+        let mut risks: Vec<(Option<DateTime<Utc>>, ResolutionRisk)> = self
+            .holdings
+            .values()
+            .map(|held| {
+                let end = end_dates.get(&held.asset).copied();
+                let risk = ResolutionRisk {
+                    asset: held.asset.clone(),
+                    title: held.title.clone(),
+                    outcome: held.outcome.clone(),
+                    end_date: end.map(|e| e.to_rfc3339()),
+                    hours_to_resolution: end.map(|e| (e - now).num_seconds() as f64 / 3600.0),
+                    shares: money::to_f64(held.shares),
+                    cur_price: latest_prices.get(&held.asset).copied().unwrap_or(0.0),
+                    cost_basis: money::to_f64(held.total_cost),
+                    pnl_at_0: money::to_f64(-held.total_cost),
+                    pnl_at_1: money::to_f64(held.shares - held.total_cost),
+                };
+                (end, risk)
+            })
+            .collect();
+        risks.sort_by(|(a_end, a), (b_end, b)| match (a_end, b_end) {
+            (Some(x), Some(y)) => x.cmp(y),
+            (Some(_), None) => std::cmp::Ordering::Less,
+            (None, Some(_)) => std::cmp::Ordering::Greater,
+            (None, None) => a.asset.cmp(&b.asset),
+        });
+        risks.into_iter().map(|(_, r)| r).collect()
+    }
+
+    /// Every tracked resting order with its age and distance from the market,
+    /// oldest first, so stuck orders stand out.
+    ///
+    /// `latest_prices` maps asset token ID → current price.
+    pub fn resting_order_status(
+        &self,
+        latest_prices: &HashMap<String, f64>,
+        now: DateTime<Utc>,
+    ) -> Vec<RestingOrderStatus> {
+        let now_ms = now.timestamp_millis();
+        let mut orders: Vec<RestingOrderStatus> = self
+            .resting_orders
+            .iter()
+            .map(|r| {
+                let cur_price = latest_prices.get(&r.asset).copied();
+                let price = money::to_f64(r.price);
+                RestingOrderStatus {
+                    order_id: r.order_id.clone(),
+                    asset: r.asset.clone(),
+                    title: r.title.clone(),
+                    side: r.side,
+                    shares: money::to_f64(r.shares),
+                    price,
+                    age_secs: (now_ms - r.placed_at_ms) / 1000,
+                    cur_price,
+                    distance: cur_price.map(|p| match r.side {
+                        OrderSide::Buy => p - price,
+                        OrderSide::Sell => price - p,
+                    }),
+                }
+            })
+            .collect();
+        orders.sort_by_key(|o| std::cmp::Reverse(o.age_secs));
+        orders
+    }
+
     /// Compute the exit summary with unrealized P&L based on latest prices.
     ///
     /// `latest_prices` maps asset token ID → current price.
     pub fn exit_summary(&self, latest_prices: &HashMap<String, f64>) -> ExitSummary {
         let mut holdings_summary = Vec::new();
-        let mut unrealized_pnl = 0.0;
+        let mut by_tag: BTreeMap<String, TagSummary> = BTreeMap::new();
+        let mut unrealized_pnl = Decimal::ZERO;
 
         for (asset, held) in &self.holdings {
             let cur_price = latest_prices.get(asset).copied().unwrap_or(0.0);
-            let current_value = held.shares * cur_price;
-            let position_unrealized = (cur_price - held.avg_cost) * held.shares;
-            unrealized_pnl += position_unrealized;
-
+            let current_value = money::to_f64(held.shares) * cur_price;
+            let unrealized = (money::from_f64(cur_price) - held.avg_cost) * held.shares;
+            unrealized_pnl += unrealized;
+            let position_unrealized = money::to_f64(unrealized);
+
+            let tags = self.asset_tags.get(asset).cloned().unwrap_or_default();
+            for tag in &tags {
+                let group = by_tag.entry(tag.clone()).or_default();
+                group.positions += 1;
+                group.current_value += current_value;
+                group.unrealized_pnl += position_unrealized;
+            }
             holdings_summary.push(HoldingSummary {
                 asset: held.asset.clone(),
                 title: held.title.clone(),
                 outcome: held.outcome.clone(),
-                shares: held.shares,
-                avg_cost: held.avg_cost,
+                shares: money::to_f64(held.shares),
+                avg_cost: money::to_f64(held.avg_cost),
                 cur_price,
                 current_value,
                 unrealized_pnl: position_unrealized,
+                tags,
             });
         }
 
         let total_pnl = self.realized_pnl + unrealized_pnl;
-        let pnl_percent = if self.initial_budget > 0.0 {
-            (total_pnl / self.initial_budget) * 100.0
-        } else {
-            0.0
-        };
+        let pnl_percent = money::ratio(total_pnl, self.initial_budget)
+            .map_or(0.0, |ratio| money::to_f64(ratio) * 100.0);
 
         ExitSummary {
-            initial_budget: self.initial_budget,
-            budget_remaining: self.budget_remaining,
-            total_spent: self.total_spent,
-            total_sell_proceeds: self.total_sell_proceeds,
-            realized_pnl: self.realized_pnl,
-            unrealized_pnl,
-            total_pnl,
+            initial_budget: money::to_f64(self.initial_budget),
+            budget_remaining: money::to_f64(self.budget_remaining),
+            locked_profit: money::to_f64(self.locked_profit),
+            total_spent: money::to_f64(self.total_spent),
+            total_sell_proceeds: money::to_f64(self.total_sell_proceeds),
+            realized_pnl: money::to_f64(self.realized_pnl),
+            unrealized_pnl: money::to_f64(unrealized_pnl),
+            total_pnl: money::to_f64(total_pnl),
             pnl_percent,
             total_events: self.total_events,
             total_orders: self.total_orders,
             total_buy_orders: self.total_buy_orders,
             total_sell_orders: self.total_sell_orders,
             holdings: holdings_summary,
+            build: None,
+            by_tag,
+            resolution_risk: Vec::new(),
+            resting_orders: Vec::new(),
+            copy_latency: None,
+            detection: None,
+            shadow: None,
         }
     }
 }
@@ -350,7 +1192,7 @@ impl TradingState {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::types::MarketPosition;
+    use crate::store::FileStore;
 
     fn approx_eq(a: f64, b: f64) -> bool {
         (a - b).abs() < 1e-6
@@ -364,10 +1206,12 @@ mod tests {
             outcome: String::new(),
             outcome_index: 0,
             event_slug: String::new(),
+            tags: Vec::new(),
+            opposite_asset: String::new(),
         }
     }
 
-    fn make_order(asset: &str, side: OrderSide, shares: f64, price: f64) -> SimulatedOrder {
+    fn make_order(asset: &str, side: OrderSide, shares: Decimal, price: Decimal) -> SimulatedOrder {
         SimulatedOrder {
             market: make_market(asset),
             side,
@@ -381,8 +1225,8 @@ mod tests {
         order_id: &str,
         asset: &str,
         side: OrderSide,
-        shares: f64,
-        price: f64,
+        shares: Decimal,
+        price: Decimal,
     ) -> RestingOrder {
         RestingOrder {
             order_id: order_id.to_string(),
@@ -393,6 +1237,8 @@ mod tests {
             shares,
             price,
             cost_usd: shares * price,
+            opposite_asset: String::new(),
+            placed_at_ms: 0,
         }
     }
 
@@ -400,12 +1246,12 @@ mod tests {
 
     #[test]
     fn new_initializes_correctly() {
-        let s = TradingState::new(500.0);
-        assert!(approx_eq(s.initial_budget, 500.0));
-        assert!(approx_eq(s.budget_remaining, 500.0));
-        assert!(approx_eq(s.total_spent, 0.0));
-        assert!(approx_eq(s.total_sell_proceeds, 0.0));
-        assert!(approx_eq(s.realized_pnl, 0.0));
+        let s = TradingState::new(dec!(500));
+        assert_eq!(s.initial_budget, dec!(500));
+        assert_eq!(s.budget_remaining, dec!(500));
+        assert_eq!(s.total_spent, Decimal::ZERO);
+        assert_eq!(s.total_sell_proceeds, Decimal::ZERO);
+        assert_eq!(s.realized_pnl, Decimal::ZERO);
         assert_eq!(s.total_events, 0);
         assert_eq!(s.total_orders, 0);
         assert!(s.holdings.is_empty());
@@ -416,244 +1262,398 @@ mod tests {
 
     #[test]
     fn effective_capital_empty() {
-        let s = TradingState::new(500.0);
+        let s = TradingState::new(dec!(500));
         let prices = HashMap::new();
-        assert!(approx_eq(s.effective_capital(&prices), 500.0));
+        assert_eq!(s.effective_capital(&prices), dec!(500));
     }
 
     #[test]
     fn effective_capital_with_holdings() {
-        let mut s = TradingState::new(300.0);
+        let mut s = TradingState::new(dec!(300));
         s.holdings.insert(
             "a1".to_string(),
             HeldPosition {
                 asset: "a1".to_string(),
                 title: String::new(),
                 outcome: String::new(),
-                shares: 10.0,
-                total_cost: 5.0,
-                avg_cost: 0.50,
+                shares: dec!(10),
+                total_cost: dec!(5),
+                avg_cost: dec!(0.5),
+                opposite_asset: String::new(),
             },
         );
         let mut prices = HashMap::new();
         prices.insert("a1".to_string(), 0.60);
         // 300 + 10*0.60 = 306
-        assert!(approx_eq(s.effective_capital(&prices), 306.0));
+        assert_eq!(s.effective_capital(&prices), dec!(306));
     }
 
     #[test]
     fn effective_capital_with_resting_buys() {
-        let mut s = TradingState::new(300.0);
+        let mut s = TradingState::new(dec!(300));
         s.resting_orders
-            .push(make_resting("o1", "a1", OrderSide::Buy, 10.0, 0.50));
+            .push(make_resting("o1", "a1", OrderSide::Buy, dec!(10), dec!(0.5)));
         let mut prices = HashMap::new();
         prices.insert("a1".to_string(), 0.60);
         // 300 + 10*0.60 (resting buy value at market price) = 306
-        assert!(approx_eq(s.effective_capital(&prices), 306.0));
+        assert_eq!(s.effective_capital(&prices), dec!(306));
     }
 
     #[test]
     fn effective_capital_missing_price_falls_back_to_avg_cost() {
-        let mut s = TradingState::new(300.0);
+        let mut s = TradingState::new(dec!(300));
         s.holdings.insert(
             "a1".to_string(),
             HeldPosition {
                 asset: "a1".to_string(),
                 title: String::new(),
                 outcome: String::new(),
-                shares: 10.0,
-                total_cost: 5.0,
-                avg_cost: 0.50,
+                shares: dec!(10),
+                total_cost: dec!(5),
+                avg_cost: dec!(0.5),
+                opposite_asset: String::new(),
             },
         );
         // No price in map → falls back to avg_cost (0.50)
         let prices = HashMap::new();
         // 300 + 10*0.50 = 305
-        assert!(approx_eq(s.effective_capital(&prices), 305.0));
+        assert_eq!(s.effective_capital(&prices), dec!(305));
     }
 
     // ── effective_held_shares ──────────────────────────────────────
 
     #[test]
     fn effective_held_shares_no_holdings() {
-        let s = TradingState::new(500.0);
-        assert!(approx_eq(s.effective_held_shares("a1"), 0.0));
+        let s = TradingState::new(dec!(500));
+        assert_eq!(s.effective_held_shares("a1"), Decimal::ZERO);
     }
 
     #[test]
     fn effective_held_shares_holdings_only() {
-        let mut s = TradingState::new(500.0);
+        let mut s = TradingState::new(dec!(500));
         s.holdings.insert(
             "a1".to_string(),
             HeldPosition {
                 asset: "a1".to_string(),
                 title: String::new(),
                 outcome: String::new(),
-                shares: 10.0,
-                total_cost: 5.0,
-                avg_cost: 0.50,
+                shares: dec!(10),
+                total_cost: dec!(5),
+                avg_cost: dec!(0.5),
+                opposite_asset: String::new(),
             },
         );
-        assert!(approx_eq(s.effective_held_shares("a1"), 10.0));
+        assert_eq!(s.effective_held_shares("a1"), dec!(10));
     }
 
     #[test]
     fn effective_held_shares_with_resting_buy() {
-        let mut s = TradingState::new(500.0);
+        let mut s = TradingState::new(dec!(500));
         s.holdings.insert(
             "a1".to_string(),
             HeldPosition {
                 asset: "a1".to_string(),
                 title: String::new(),
                 outcome: String::new(),
-                shares: 10.0,
-                total_cost: 5.0,
-                avg_cost: 0.50,
+                shares: dec!(10),
+                total_cost: dec!(5),
+                avg_cost: dec!(0.5),
+                opposite_asset: String::new(),
             },
         );
         s.resting_orders
-            .push(make_resting("o1", "a1", OrderSide::Buy, 5.0, 0.50));
-        assert!(approx_eq(s.effective_held_shares("a1"), 15.0));
+            .push(make_resting("o1", "a1", OrderSide::Buy, dec!(5), dec!(0.5)));
+        assert_eq!(s.effective_held_shares("a1"), dec!(15));
     }
 
     #[test]
     fn effective_held_shares_with_resting_sell() {
-        let mut s = TradingState::new(500.0);
+        let mut s = TradingState::new(dec!(500));
         s.holdings.insert(
             "a1".to_string(),
             HeldPosition {
                 asset: "a1".to_string(),
                 title: String::new(),
                 outcome: String::new(),
-                shares: 10.0,
-                total_cost: 5.0,
-                avg_cost: 0.50,
+                shares: dec!(10),
+                total_cost: dec!(5),
+                avg_cost: dec!(0.5),
+                opposite_asset: String::new(),
             },
         );
         s.resting_orders
-            .push(make_resting("o1", "a1", OrderSide::Sell, 3.0, 0.50));
-        assert!(approx_eq(s.effective_held_shares("a1"), 7.0));
+            .push(make_resting("o1", "a1", OrderSide::Sell, dec!(3), dec!(0.5)));
+        assert_eq!(s.effective_held_shares("a1"), dec!(7));
     }
 
     #[test]
     fn effective_held_shares_combined_buy_and_sell() {
-        let mut s = TradingState::new(500.0);
+        let mut s = TradingState::new(dec!(500));
         s.holdings.insert(
             "a1".to_string(),
             HeldPosition {
                 asset: "a1".to_string(),
                 title: String::new(),
                 outcome: String::new(),
-                shares: 10.0,
-                total_cost: 5.0,
-                avg_cost: 0.50,
+                shares: dec!(10),
+                total_cost: dec!(5),
+                avg_cost: dec!(0.5),
+                opposite_asset: String::new(),
             },
         );
         s.resting_orders
-            .push(make_resting("o1", "a1", OrderSide::Buy, 5.0, 0.50));
+            .push(make_resting("o1", "a1", OrderSide::Buy, dec!(5), dec!(0.5)));
         s.resting_orders
-            .push(make_resting("o2", "a1", OrderSide::Sell, 3.0, 0.50));
+            .push(make_resting("o2", "a1", OrderSide::Sell, dec!(3), dec!(0.5)));
         // 10 + 5 - 3 = 12
-        assert!(approx_eq(s.effective_held_shares("a1"), 12.0));
+        assert_eq!(s.effective_held_shares("a1"), dec!(12));
+    }
+
+    #[test]
+    fn exit_summary_groups_by_tag() {
+        let mut s = TradingState::new(dec!(100));
+        let mut tagged = make_order("a1", OrderSide::Buy, dec!(10), dec!(0.5));
+        tagged.market.tags = vec!["NBA".to_string(), "high-risk".to_string()];
+        let mut other = make_order("a2", OrderSide::Buy, dec!(10), dec!(0.4));
+        other.market.tags = vec!["NBA".to_string()];
+        s.apply_orders(&[tagged, other, make_order("a3", OrderSide::Buy, dec!(4), dec!(0.5))]);
+
+        let prices = HashMap::from([
+            ("a1".to_string(), 0.60),
+            ("a2".to_string(), 0.40),
+            ("a3".to_string(), 0.50),
+        ]);
+        let summary = s.exit_summary(&prices);
+        assert_eq!(summary.by_tag.len(), 2);
+        let nba = &summary.by_tag["NBA"];
+        assert_eq!(nba.positions, 2);
+        assert!(approx_eq(nba.current_value, 10.0));
+        assert!(approx_eq(nba.unrealized_pnl, 1.0));
+        assert_eq!(summary.by_tag["high-risk"].positions, 1);
+        let untagged = summary.holdings.iter().find(|h| h.asset == "a3").unwrap();
+        assert!(untagged.tags.is_empty());
+    }
+
+    #[test]
+    fn resolution_risk_sorted_by_end_date() {
+        let mut s = TradingState::new(dec!(100));
+        s.apply_orders(&[
+            make_order("late", OrderSide::Buy, dec!(10), dec!(0.5)),
+            make_order("soon", OrderSide::Buy, dec!(20), dec!(0.25)),
+            make_order("unknown", OrderSide::Buy, dec!(4), dec!(0.5)),
+        ]);
+        let now = DateTime::parse_from_rfc3339("2026-03-01T00:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let end_dates = HashMap::from([
+            ("late".to_string(), now + chrono::Duration::days(30)),
+            ("soon".to_string(), now + chrono::Duration::hours(6)),
+        ]);
+        let prices = HashMap::from([("soon".to_string(), 0.30)]);
+        let risks = s.resolution_risk(&prices, &end_dates, now);
+
+        let order: Vec<&str> = risks.iter().map(|r| r.asset.as_str()).collect();
+        assert_eq!(order, vec!["soon", "late", "unknown"]);
+        let soon = &risks[0];
+        assert!(approx_eq(soon.hours_to_resolution.unwrap(), 6.0));
+        assert!(approx_eq(soon.cur_price, 0.3));
+        assert!(approx_eq(soon.cost_basis, 5.0));
+        assert!(approx_eq(soon.pnl_at_0, -5.0));
+        assert!(approx_eq(soon.pnl_at_1, 15.0));
+        assert!(risks[2].end_date.is_none());
+    }
+
+    #[test]
+    fn complete_sets_merge_into_usdc() {
+        let mut s = TradingState::new(dec!(100));
+        let mut yes = make_order("yes", OrderSide::Buy, dec!(10), dec!(0.6));
+        yes.market.opposite_asset = "no".to_string();
+        let mut no = make_order("no", OrderSide::Buy, dec!(6), dec!(0.3));
+        no.market.opposite_asset = "yes".to_string();
+        s.apply_orders(&[yes, no, make_order("other", OrderSide::Buy, dec!(5), dec!(0.5))]);
+        assert_eq!(s.held_sets(), vec![("no".to_string(), "yes".to_string(), dec!(6))]);
+
+        let merged = s.merge_sets();
+        assert_eq!(merged.len(), 1);
+        assert_eq!(merged[0].1, dec!(6));
+        // Each set pays $1 against $0.90 of cost
+        assert_eq!(s.realized_pnl, dec!(0.6));
+        assert_eq!(s.budget_remaining, dec!(100) - dec!(6) - dec!(1.8) - dec!(2.5) + dec!(6));
+        assert!(!s.holdings.contains_key("no"));
+        assert_eq!(s.holdings["yes"].shares, dec!(4));
+        assert_eq!(s.holdings["yes"].avg_cost, dec!(0.6));
+        assert_eq!(s.total_orders, 3);
+        assert!(s.held_sets().is_empty());
+    }
+
+    #[test]
+    fn resting_order_status_oldest_first() {
+        let mut s = TradingState::new(dec!(100));
+        let now = DateTime::parse_from_rfc3339("2026-03-01T00:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        for (id, asset, side, price, age_secs) in [
+            ("new", "a1", OrderSide::Buy, dec!(0.40), 30),
+            ("stuck", "a2", OrderSide::Sell, dec!(0.70), 3_600),
+        ] {
+            s.add_resting_order(RestingOrder {
+                order_id: id.to_string(),
+                asset: asset.to_string(),
+                title: asset.to_string(),
+                outcome: "Yes".to_string(),
+                side,
+                shares: dec!(10),
+                price,
+                cost_usd: dec!(10) * price,
+                opposite_asset: String::new(),
+                placed_at_ms: now.timestamp_millis() - age_secs * 1000,
+            });
+        }
+        let prices = HashMap::from([("a2".to_string(), 0.55)]);
+        let status = s.resting_order_status(&prices, now);
+
+        let ids: Vec<&str> = status.iter().map(|o| o.order_id.as_str()).collect();
+        assert_eq!(ids, vec!["stuck", "new"]);
+        assert_eq!(status[0].age_secs, 3_600);
+        // The bid is 15¢ below the sell's limit
+        assert!(approx_eq(status[0].distance.unwrap(), 0.15));
+        assert_eq!((status[1].cur_price, status[1].distance), (None, None));
+    }
+
+    // ── Rebuy cooldowns ────────────────────────────────────────────
+
+    #[test]
+    fn rebuy_cooldown_expires_and_keeps_latest() {
+        let mut s = TradingState::new(dec!(100));
+        assert!(!s.rebuy_blocked("a1", 0));
+        s.apply_mutation(&Mutation::RebuyCooldown {
+            asset: "a1".to_string(),
+            until_ms: 5_000,
+        });
+        // An earlier expiry doesn't shorten the cooldown
+        s.start_rebuy_cooldown("a1", 2_000);
+        assert!(s.rebuy_blocked("a1", 4_999));
+        assert!(!s.rebuy_blocked("a1", 5_000));
+        assert!(!s.rebuy_blocked("a2", 0));
+    }
+
+    #[test]
+    fn holdings_carry_the_opposite_asset() {
+        let mut s = TradingState::new(dec!(100));
+        let mut buy = make_order("yes", OrderSide::Buy, dec!(10), dec!(0.4));
+        buy.market.opposite_asset = "no".to_string();
+        s.apply_orders(&[buy]);
+        assert_eq!(s.holdings["yes"].opposite_asset, "no");
+        assert_eq!(s.opposite_asset("yes"), Some("no"));
+        assert_eq!(s.opposite_asset("no"), Some("yes"));
+        assert_eq!(s.opposite_asset("other"), None);
+
+        // Resting buys pass it on when they fill
+        let mut resting = make_resting("r1", "b", OrderSide::Buy, dec!(5), dec!(0.5));
+        resting.opposite_asset = "b-no".to_string();
+        s.add_resting_order(resting);
+        s.resolve_resting_fill("r1", dec!(5), dec!(0.5));
+        assert_eq!(s.opposite_asset("b"), Some("b-no"));
     }
 
     // ── Resting Order Lifecycle ────────────────────────────────────
 
     #[test]
     fn resting_add_buy_reserves_budget() {
-        let mut s = TradingState::new(100.0);
-        s.add_resting_order(make_resting("o1", "a1", OrderSide::Buy, 10.0, 0.50));
-        assert!(approx_eq(s.budget_remaining, 95.0)); // 100 - 5
+        let mut s = TradingState::new(dec!(100));
+        s.add_resting_order(make_resting("o1", "a1", OrderSide::Buy, dec!(10), dec!(0.5)));
+        assert_eq!(s.budget_remaining, dec!(95)); // 100 - 5
         assert_eq!(s.resting_orders.len(), 1);
     }
 
     #[test]
     fn resting_add_sell_no_budget_change() {
-        let mut s = TradingState::new(100.0);
-        s.add_resting_order(make_resting("o1", "a1", OrderSide::Sell, 10.0, 0.50));
-        assert!(approx_eq(s.budget_remaining, 100.0));
+        let mut s = TradingState::new(dec!(100));
+        s.add_resting_order(make_resting("o1", "a1", OrderSide::Sell, dec!(10), dec!(0.5)));
+        assert_eq!(s.budget_remaining, dec!(100));
         assert_eq!(s.resting_orders.len(), 1);
     }
 
     #[test]
     fn resting_fill_buy() {
-        let mut s = TradingState::new(100.0);
-        s.add_resting_order(make_resting("o1", "a1", OrderSide::Buy, 10.0, 0.50));
-        assert!(approx_eq(s.budget_remaining, 95.0));
+        let mut s = TradingState::new(dec!(100));
+        s.add_resting_order(make_resting("o1", "a1", OrderSide::Buy, dec!(10), dec!(0.5)));
+        assert_eq!(s.budget_remaining, dec!(95));
 
-        s.resolve_resting_fill("o1", 10.0, 0.50);
+        s.resolve_resting_fill("o1", dec!(10), dec!(0.5));
         assert!(s.resting_orders.is_empty());
-        assert!(approx_eq(s.total_spent, 5.0));
+        assert_eq!(s.total_spent, dec!(5));
         assert_eq!(s.total_buy_orders, 1);
         let held = s.holdings.get("a1").unwrap();
-        assert!(approx_eq(held.shares, 10.0));
-        assert!(approx_eq(held.avg_cost, 0.50));
+        assert_eq!(held.shares, dec!(10));
+        assert_eq!(held.avg_cost, dec!(0.5));
     }
 
     #[test]
     fn resting_fill_buy_price_diff() {
-        let mut s = TradingState::new(100.0);
+        let mut s = TradingState::new(dec!(100));
         // Reserved at $0.50 per share (cost_usd = 5.0)
-        s.add_resting_order(make_resting("o1", "a1", OrderSide::Buy, 10.0, 0.50));
-        assert!(approx_eq(s.budget_remaining, 95.0));
+        s.add_resting_order(make_resting("o1", "a1", OrderSide::Buy, dec!(10), dec!(0.5)));
+        assert_eq!(s.budget_remaining, dec!(95));
 
         // Actually filled at $0.40 per share (cost = 4.0)
-        s.resolve_resting_fill("o1", 10.0, 0.40);
+        s.resolve_resting_fill("o1", dec!(10), dec!(0.4));
         // Over-reservation of $1.0 returned
-        assert!(approx_eq(s.budget_remaining, 96.0)); // 95 + (5.0 - 4.0)
-        assert!(approx_eq(s.total_spent, 4.0));
+        assert_eq!(s.budget_remaining, dec!(96)); // 95 + (5.0 - 4.0)
+        assert_eq!(s.total_spent, dec!(4));
     }
 
     #[test]
     fn resting_fill_sell() {
-        let mut s = TradingState::new(100.0);
+        let mut s = TradingState::new(dec!(100));
         s.holdings.insert(
             "a1".to_string(),
             HeldPosition {
                 asset: "a1".to_string(),
                 title: String::new(),
                 outcome: String::new(),
-                shares: 10.0,
-                total_cost: 5.0,
-                avg_cost: 0.50,
+                shares: dec!(10),
+                total_cost: dec!(5),
+                avg_cost: dec!(0.5),
+                opposite_asset: String::new(),
             },
         );
-        s.add_resting_order(make_resting("o1", "a1", OrderSide::Sell, 10.0, 0.60));
+        s.add_resting_order(make_resting("o1", "a1", OrderSide::Sell, dec!(10), dec!(0.6)));
 
-        s.resolve_resting_fill("o1", 10.0, 0.60);
-        assert!(approx_eq(s.budget_remaining, 106.0)); // 100 + 6.0 proceeds
-        assert!(approx_eq(s.total_sell_proceeds, 6.0));
-        assert!(approx_eq(s.realized_pnl, 1.0)); // (0.60 - 0.50) * 10
+        s.resolve_resting_fill("o1", dec!(10), dec!(0.6));
+        assert_eq!(s.budget_remaining, dec!(106)); // 100 + 6.0 proceeds
+        assert_eq!(s.total_sell_proceeds, dec!(6));
+        assert_eq!(s.realized_pnl, dec!(1)); // (0.60 - 0.50) * 10
         assert!(s.holdings.is_empty()); // fully sold
     }
 
     #[test]
     fn resting_cancel_buy_refunds_budget() {
-        let mut s = TradingState::new(100.0);
-        s.add_resting_order(make_resting("o1", "a1", OrderSide::Buy, 10.0, 0.50));
-        assert!(approx_eq(s.budget_remaining, 95.0));
+        let mut s = TradingState::new(dec!(100));
+        s.add_resting_order(make_resting("o1", "a1", OrderSide::Buy, dec!(10), dec!(0.5)));
+        assert_eq!(s.budget_remaining, dec!(95));
 
         s.resolve_resting_cancel("o1");
-        assert!(approx_eq(s.budget_remaining, 100.0)); // refunded
+        assert_eq!(s.budget_remaining, dec!(100)); // refunded
         assert!(s.resting_orders.is_empty());
     }
 
     #[test]
     fn resting_cancel_sell_no_budget_change() {
-        let mut s = TradingState::new(100.0);
-        s.add_resting_order(make_resting("o1", "a1", OrderSide::Sell, 10.0, 0.50));
+        let mut s = TradingState::new(dec!(100));
+        s.add_resting_order(make_resting("o1", "a1", OrderSide::Sell, dec!(10), dec!(0.5)));
 
         s.resolve_resting_cancel("o1");
-        assert!(approx_eq(s.budget_remaining, 100.0));
+        assert_eq!(s.budget_remaining, dec!(100));
         assert!(s.resting_orders.is_empty());
     }
 
     #[test]
     fn resting_unknown_order_id_noop() {
-        let mut s = TradingState::new(100.0);
-        s.resolve_resting_fill("nonexistent", 10.0, 0.50);
+        let mut s = TradingState::new(dec!(100));
+        s.resolve_resting_fill("nonexistent", dec!(10), dec!(0.5));
         s.resolve_resting_cancel("nonexistent");
-        assert!(approx_eq(s.budget_remaining, 100.0));
+        assert_eq!(s.budget_remaining, dec!(100));
         assert!(s.holdings.is_empty());
     }
 
@@ -661,22 +1661,22 @@ mod tests {
 
     #[test]
     fn apply_orders_buy() {
-        let mut s = TradingState::new(100.0);
-        let orders = vec![make_order("a1", OrderSide::Buy, 10.0, 0.50)];
+        let mut s = TradingState::new(dec!(100));
+        let orders = vec![make_order("a1", OrderSide::Buy, dec!(10), dec!(0.5))];
         s.apply_orders(&orders);
 
-        assert!(approx_eq(s.budget_remaining, 95.0));
-        assert!(approx_eq(s.total_spent, 5.0));
+        assert_eq!(s.budget_remaining, dec!(95));
+        assert_eq!(s.total_spent, dec!(5));
         assert_eq!(s.total_buy_orders, 1);
         assert_eq!(s.total_orders, 1);
         let held = s.holdings.get("a1").unwrap();
-        assert!(approx_eq(held.shares, 10.0));
-        assert!(approx_eq(held.avg_cost, 0.50));
+        assert_eq!(held.shares, dec!(10));
+        assert_eq!(held.avg_cost, dec!(0.5));
     }
 
     #[test]
     fn apply_orders_sell() {
-        let mut s = TradingState::new(100.0);
+        let mut s = TradingState::new(dec!(100));
         // First buy to establish position
         s.holdings.insert(
             "a1".to_string(),
@@ -684,250 +1684,254 @@ mod tests {
                 asset: "a1".to_string(),
                 title: String::new(),
                 outcome: String::new(),
-                shares: 10.0,
-                total_cost: 5.0,
-                avg_cost: 0.50,
+                shares: dec!(10),
+                total_cost: dec!(5),
+                avg_cost: dec!(0.5),
+                opposite_asset: String::new(),
             },
         );
-        let orders = vec![make_order("a1", OrderSide::Sell, 10.0, 0.60)];
+        let orders = vec![make_order("a1", OrderSide::Sell, dec!(10), dec!(0.6))];
         s.apply_orders(&orders);
 
-        assert!(approx_eq(s.budget_remaining, 106.0)); // 100 + 6.0
-        assert!(approx_eq(s.total_sell_proceeds, 6.0));
-        assert!(approx_eq(s.realized_pnl, 1.0)); // (0.60 - 0.50) * 10
+        assert_eq!(s.budget_remaining, dec!(106)); // 100 + 6.0
+        assert_eq!(s.total_sell_proceeds, dec!(6));
+        assert_eq!(s.realized_pnl, dec!(1)); // (0.60 - 0.50) * 10
         assert_eq!(s.total_sell_orders, 1);
         assert!(s.holdings.is_empty()); // fully sold → removed
     }
 
     #[test]
     fn apply_orders_full_sell_removes_position() {
-        let mut s = TradingState::new(100.0);
+        let mut s = TradingState::new(dec!(100));
         s.holdings.insert(
             "a1".to_string(),
             HeldPosition {
                 asset: "a1".to_string(),
                 title: String::new(),
                 outcome: String::new(),
-                shares: 5.0,
-                total_cost: 2.5,
-                avg_cost: 0.50,
+                shares: dec!(5),
+                total_cost: dec!(2.5),
+                avg_cost: dec!(0.5),
+                opposite_asset: String::new(),
             },
         );
-        s.apply_orders(&[make_order("a1", OrderSide::Sell, 5.0, 0.50)]);
-        assert!(s.holdings.get("a1").is_none());
+        s.apply_orders(&[make_order("a1", OrderSide::Sell, dec!(5), dec!(0.5))]);
+        assert!(!s.holdings.contains_key("a1"));
     }
 
     #[test]
     fn apply_orders_sell_funds_buy() {
-        let mut s = TradingState::new(0.0); // no cash
+        let mut s = TradingState::new(Decimal::ZERO); // no cash
         s.holdings.insert(
             "a1".to_string(),
             HeldPosition {
                 asset: "a1".to_string(),
                 title: String::new(),
                 outcome: String::new(),
-                shares: 10.0,
-                total_cost: 5.0,
-                avg_cost: 0.50,
+                shares: dec!(10),
+                total_cost: dec!(5),
+                avg_cost: dec!(0.5),
+                opposite_asset: String::new(),
             },
         );
         let orders = vec![
-            make_order("a1", OrderSide::Sell, 10.0, 0.50),
-            make_order("a2", OrderSide::Buy, 10.0, 0.50),
+            make_order("a1", OrderSide::Sell, dec!(10), dec!(0.5)),
+            make_order("a2", OrderSide::Buy, dec!(10), dec!(0.5)),
         ];
         s.apply_orders(&orders);
 
-        assert!(approx_eq(s.budget_remaining, 0.0)); // sell proceeds funded buy
-        assert!(s.holdings.get("a1").is_none());
+        assert_eq!(s.budget_remaining, Decimal::ZERO); // sell proceeds funded buy
+        assert!(!s.holdings.contains_key("a1"));
         let held = s.holdings.get("a2").unwrap();
-        assert!(approx_eq(held.shares, 10.0));
+        assert_eq!(held.shares, dec!(10));
     }
 
     #[test]
     fn apply_orders_buy_updates_avg_cost() {
-        let mut s = TradingState::new(1000.0);
+        let mut s = TradingState::new(dec!(1000));
         // Buy 10 at 0.40
-        s.apply_orders(&[make_order("a1", OrderSide::Buy, 10.0, 0.40)]);
+        s.apply_orders(&[make_order("a1", OrderSide::Buy, dec!(10), dec!(0.4))]);
         // Buy 10 more at 0.60
-        s.apply_orders(&[make_order("a1", OrderSide::Buy, 10.0, 0.60)]);
+        s.apply_orders(&[make_order("a1", OrderSide::Buy, dec!(10), dec!(0.6))]);
 
         let held = s.holdings.get("a1").unwrap();
-        assert!(approx_eq(held.shares, 20.0));
+        assert_eq!(held.shares, dec!(20));
         // avg_cost = (10*0.40 + 10*0.60) / 20 = 10 / 20 = 0.50
-        assert!(approx_eq(held.avg_cost, 0.50));
+        assert_eq!(held.avg_cost, dec!(0.5));
     }
 
     // ── apply_execution_results ────────────────────────────────────
 
     #[test]
     fn execution_filled() {
-        let mut s = TradingState::new(100.0);
-        let orders = vec![make_order("a1", OrderSide::Buy, 10.0, 0.50)];
+        let mut s = TradingState::new(dec!(100));
+        let orders = vec![make_order("a1", OrderSide::Buy, dec!(10), dec!(0.5))];
         let results = vec![ExecutionResult {
             order_index: 0,
             status: ExecutionStatus::Filled,
             order_id: "oid1".to_string(),
-            filled_shares: 10.0,
-            filled_cost_usd: 5.0,
+            filled_shares: dec!(10),
+            filled_cost_usd: dec!(5),
             error_msg: None,
         }];
         s.apply_execution_results(&orders, &results);
 
-        assert!(approx_eq(s.budget_remaining, 95.0));
-        assert!(approx_eq(s.total_spent, 5.0));
+        assert_eq!(s.budget_remaining, dec!(95));
+        assert_eq!(s.total_spent, dec!(5));
         let held = s.holdings.get("a1").unwrap();
-        assert!(approx_eq(held.shares, 10.0));
+        assert_eq!(held.shares, dec!(10));
         assert!(s.resting_orders.is_empty());
     }
 
     #[test]
     fn execution_partial_fill() {
-        let mut s = TradingState::new(100.0);
-        let orders = vec![make_order("a1", OrderSide::Buy, 10.0, 0.50)];
+        let mut s = TradingState::new(dec!(100));
+        let orders = vec![make_order("a1", OrderSide::Buy, dec!(10), dec!(0.5))];
         let results = vec![ExecutionResult {
             order_index: 0,
             status: ExecutionStatus::PartialFill,
             order_id: "oid1".to_string(),
-            filled_shares: 6.0,
-            filled_cost_usd: 3.0,
+            filled_shares: dec!(6),
+            filled_cost_usd: dec!(3),
             error_msg: None,
         }];
         s.apply_execution_results(&orders, &results);
 
         // 6 shares filled immediately
         let held = s.holdings.get("a1").unwrap();
-        assert!(approx_eq(held.shares, 6.0));
-        assert!(approx_eq(s.total_spent, 3.0));
+        assert_eq!(held.shares, dec!(6));
+        assert_eq!(s.total_spent, dec!(3));
         // Remaining 4 shares tracked as resting
         assert_eq!(s.resting_orders.len(), 1);
-        assert!(approx_eq(s.resting_orders[0].shares, 4.0));
+        assert_eq!(s.resting_orders[0].shares, dec!(4));
         assert_eq!(s.resting_orders[0].order_id, "oid1");
         // Budget: 100 - 3.0 (filled) - 2.0 (resting 4*0.50) = 95.0
-        assert!(approx_eq(s.budget_remaining, 95.0));
+        assert_eq!(s.budget_remaining, dec!(95));
     }
 
     #[test]
     fn execution_resting() {
-        let mut s = TradingState::new(100.0);
-        let orders = vec![make_order("a1", OrderSide::Buy, 10.0, 0.50)];
+        let mut s = TradingState::new(dec!(100));
+        let orders = vec![make_order("a1", OrderSide::Buy, dec!(10), dec!(0.5))];
         let results = vec![ExecutionResult {
             order_index: 0,
             status: ExecutionStatus::Resting,
             order_id: "oid1".to_string(),
-            filled_shares: 0.0,
-            filled_cost_usd: 0.0,
+            filled_shares: Decimal::ZERO,
+            filled_cost_usd: Decimal::ZERO,
             error_msg: None,
         }];
         s.apply_execution_results(&orders, &results);
 
         assert!(s.holdings.is_empty()); // nothing filled
         assert_eq!(s.resting_orders.len(), 1);
-        assert!(approx_eq(s.resting_orders[0].shares, 10.0));
+        assert_eq!(s.resting_orders[0].shares, dec!(10));
         // Budget reserved for resting buy
-        assert!(approx_eq(s.budget_remaining, 95.0));
+        assert_eq!(s.budget_remaining, dec!(95));
     }
 
     #[test]
     fn execution_failed() {
-        let mut s = TradingState::new(100.0);
-        let orders = vec![make_order("a1", OrderSide::Buy, 10.0, 0.50)];
+        let mut s = TradingState::new(dec!(100));
+        let orders = vec![make_order("a1", OrderSide::Buy, dec!(10), dec!(0.5))];
         let results = vec![ExecutionResult {
             order_index: 0,
             status: ExecutionStatus::Failed,
             order_id: String::new(),
-            filled_shares: 0.0,
-            filled_cost_usd: 0.0,
+            filled_shares: Decimal::ZERO,
+            filled_cost_usd: Decimal::ZERO,
             error_msg: Some("insufficient balance".to_string()),
         }];
         s.apply_execution_results(&orders, &results);
 
-        assert!(approx_eq(s.budget_remaining, 100.0)); // no change
+        assert_eq!(s.budget_remaining, dec!(100)); // no change
         assert!(s.holdings.is_empty());
         assert!(s.resting_orders.is_empty());
     }
 
     #[test]
     fn execution_skipped() {
-        let mut s = TradingState::new(100.0);
-        let orders = vec![make_order("a1", OrderSide::Buy, 10.0, 0.50)];
+        let mut s = TradingState::new(dec!(100));
+        let orders = vec![make_order("a1", OrderSide::Buy, dec!(10), dec!(0.5))];
         let results = vec![ExecutionResult {
             order_index: 0,
             status: ExecutionStatus::Skipped,
             order_id: String::new(),
-            filled_shares: 0.0,
-            filled_cost_usd: 0.0,
+            filled_shares: Decimal::ZERO,
+            filled_cost_usd: Decimal::ZERO,
             error_msg: None,
         }];
         s.apply_execution_results(&orders, &results);
 
-        assert!(approx_eq(s.budget_remaining, 100.0));
+        assert_eq!(s.budget_remaining, dec!(100));
         assert!(s.holdings.is_empty());
         assert!(s.resting_orders.is_empty());
     }
 
     #[test]
     fn execution_mixed_statuses() {
-        let mut s = TradingState::new(100.0);
+        let mut s = TradingState::new(dec!(100));
         let orders = vec![
-            make_order("a1", OrderSide::Buy, 10.0, 0.50),
-            make_order("a2", OrderSide::Buy, 8.0, 0.40),
-            make_order("a3", OrderSide::Buy, 5.0, 0.60),
+            make_order("a1", OrderSide::Buy, dec!(10), dec!(0.5)),
+            make_order("a2", OrderSide::Buy, dec!(8), dec!(0.4)),
+            make_order("a3", OrderSide::Buy, dec!(5), dec!(0.6)),
         ];
         let results = vec![
             ExecutionResult {
                 order_index: 0,
                 status: ExecutionStatus::Filled,
                 order_id: "o1".to_string(),
-                filled_shares: 10.0,
-                filled_cost_usd: 5.0,
+                filled_shares: dec!(10),
+                filled_cost_usd: dec!(5),
                 error_msg: None,
             },
             ExecutionResult {
                 order_index: 1,
                 status: ExecutionStatus::Resting,
                 order_id: "o2".to_string(),
-                filled_shares: 0.0,
-                filled_cost_usd: 0.0,
+                filled_shares: Decimal::ZERO,
+                filled_cost_usd: Decimal::ZERO,
                 error_msg: None,
             },
             ExecutionResult {
                 order_index: 2,
                 status: ExecutionStatus::Failed,
                 order_id: String::new(),
-                filled_shares: 0.0,
-                filled_cost_usd: 0.0,
+                filled_shares: Decimal::ZERO,
+                filled_cost_usd: Decimal::ZERO,
                 error_msg: Some("error".to_string()),
             },
         ];
         s.apply_execution_results(&orders, &results);
 
         // a1: filled → in holdings
-        assert!(approx_eq(s.holdings.get("a1").unwrap().shares, 10.0));
+        assert_eq!(s.holdings.get("a1").unwrap().shares, dec!(10));
         // a2: resting → tracked, budget reserved
         assert_eq!(s.resting_orders.len(), 1);
         assert_eq!(s.resting_orders[0].asset, "a2");
         // a3: failed → no effect
-        assert!(s.holdings.get("a3").is_none());
+        assert!(!s.holdings.contains_key("a3"));
         // Budget: 100 - 5.0 (a1 filled) - 3.2 (a2 resting: 8*0.40) = 91.8
-        assert!(approx_eq(s.budget_remaining, 91.8));
+        assert_eq!(s.budget_remaining, dec!(91.8));
     }
 
     // ── exit_summary ───────────────────────────────────────────────
 
     #[test]
     fn exit_summary_basic() {
-        let mut s = TradingState::new(100.0);
-        s.budget_remaining = 90.0;
-        s.total_spent = 10.0;
+        let mut s = TradingState::new(dec!(100));
+        s.budget_remaining = dec!(90);
+        s.total_spent = dec!(10);
         s.holdings.insert(
             "a1".to_string(),
             HeldPosition {
                 asset: "a1".to_string(),
                 title: "Test".to_string(),
                 outcome: "Yes".to_string(),
-                shares: 20.0,
-                total_cost: 10.0,
-                avg_cost: 0.50,
+                shares: dec!(20),
+                total_cost: dec!(10),
+                avg_cost: dec!(0.5),
+                opposite_asset: String::new(),
             },
         );
         let mut prices = HashMap::new();
@@ -944,18 +1948,19 @@ mod tests {
 
     #[test]
     fn exit_summary_with_realized_pnl() {
-        let mut s = TradingState::new(100.0);
-        s.realized_pnl = 5.0;
-        s.budget_remaining = 95.0;
+        let mut s = TradingState::new(dec!(100));
+        s.realized_pnl = dec!(5);
+        s.budget_remaining = dec!(95);
         s.holdings.insert(
             "a1".to_string(),
             HeldPosition {
                 asset: "a1".to_string(),
                 title: String::new(),
                 outcome: String::new(),
-                shares: 10.0,
-                total_cost: 5.0,
-                avg_cost: 0.50,
+                shares: dec!(10),
+                total_cost: dec!(5),
+                avg_cost: dec!(0.5),
+                opposite_asset: String::new(),
             },
         );
         let mut prices = HashMap::new();
@@ -970,16 +1975,17 @@ mod tests {
 
     #[test]
     fn exit_summary_missing_price_falls_back_to_zero() {
-        let mut s = TradingState::new(100.0);
+        let mut s = TradingState::new(dec!(100));
         s.holdings.insert(
             "a1".to_string(),
             HeldPosition {
                 asset: "a1".to_string(),
                 title: String::new(),
                 outcome: String::new(),
-                shares: 10.0,
-                total_cost: 5.0,
-                avg_cost: 0.50,
+                shares: dec!(10),
+                total_cost: dec!(5),
+                avg_cost: dec!(0.5),
+                opposite_asset: String::new(),
             },
         );
         let prices = HashMap::new(); // no price
@@ -992,12 +1998,341 @@ mod tests {
 
     #[test]
     fn exit_summary_empty_holdings() {
-        let mut s = TradingState::new(100.0);
-        s.realized_pnl = 3.0;
+        let mut s = TradingState::new(dec!(100));
+        s.realized_pnl = dec!(3);
 
         let summary = s.exit_summary(&HashMap::new());
         assert!(summary.holdings.is_empty());
         assert!(approx_eq(summary.unrealized_pnl, 0.0));
         assert!(approx_eq(summary.total_pnl, 3.0)); // realized only
     }
+
+    // ── Write-ahead log ────────────────────────────────────────────
+
+    #[test]
+    fn wal_replay_rebuilds_state() {
+        let path = std::env::temp_dir().join(format!(
+            "copytrade-state-wal-{}.jsonl",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_file(&path);
+
+        let mut live = TradingState::new(dec!(100));
+        live.attach_store(Box::new(FileStore::open(&path, 100.0, None).unwrap()));
+        live.commit(Mutation::ApplyOrders {
+            orders: vec![make_order("a1", OrderSide::Buy, dec!(10), dec!(0.5))],
+        });
+        let orders = vec![make_order("a2", OrderSide::Buy, dec!(4), dec!(0.5))];
+        live.commit(Mutation::ApplyExecutionResults {
+            orders,
+            results: vec![ExecutionResult {
+                order_index: 0,
+                status: ExecutionStatus::Resting,
+                order_id: "o1".to_string(),
+                filled_shares: Decimal::ZERO,
+                filled_cost_usd: Decimal::ZERO,
+                error_msg: None,
+            }],
+            client_order_ids: Vec::new(),
+        });
+        live.commit(Mutation::RestingFill {
+            order_id: "o1".to_string(),
+            filled_shares: dec!(4),
+            fill_price: dec!(0.4),
+        });
+        drop(live);
+
+        let mut restored = TradingState::new(dec!(100));
+        let mut store = FileStore::open(&path, 100.0, None).unwrap();
+        let entries = store.load().unwrap();
+        restored.attach_store(Box::new(store));
+        assert_eq!(restored.replay(&entries), 0);
+        assert_eq!(restored.budget_remaining, dec!(93.4)); // 100 - 5.0 - 1.6
+        assert_eq!(restored.holdings["a1"].shares, dec!(10));
+        assert_eq!(restored.holdings["a2"].avg_cost, dec!(0.4));
+        assert!(restored.resting_orders.is_empty());
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn replay_applies_unacked_entries() {
+        let mut s = TradingState::new(dec!(100));
+        let entries = vec![WalEntry {
+            seq: 0,
+            mutation: Mutation::ApplyOrders {
+                orders: vec![make_order("a1", OrderSide::Buy, dec!(10), dec!(0.5))],
+            },
+            acked: false,
+        }];
+        assert_eq!(s.replay(&entries), 1);
+        assert_eq!(s.budget_remaining, dec!(95));
+    }
+
+    #[test]
+    fn seed_holding_commits_cost_basis() {
+        let mut s = TradingState::new(dec!(100));
+        s.seed_holding(HeldPosition {
+            asset: "a1".to_string(),
+            title: String::new(),
+            outcome: String::new(),
+            shares: dec!(10),
+            total_cost: dec!(5),
+            avg_cost: dec!(0.5),
+            opposite_asset: String::new(),
+        });
+        assert_eq!(s.budget_remaining, dec!(95));
+        assert_eq!(s.total_spent, dec!(5));
+        assert!(s.holdings.contains_key("a1"));
+    }
+
+    #[test]
+    fn rebase_budget_keeps_spending() {
+        let mut s = TradingState::new(Decimal::ZERO);
+        s.commit(Mutation::RebaseBudget { budget: dec!(100) });
+        s.apply_orders(&[make_order("a1", OrderSide::Buy, dec!(20), dec!(0.5))]);
+        // Withdrawal shrinks the wallet-based budget; the $10 spent still counts
+        s.commit(Mutation::RebaseBudget { budget: dec!(60) });
+        assert_eq!(s.initial_budget, dec!(60));
+        assert_eq!(s.budget_remaining, dec!(50));
+        assert!(s.check_invariants().is_ok());
+    }
+
+    #[test]
+    fn locks_realized_profit_out_of_budget() {
+        let mut s = TradingState::new(dec!(100));
+        s.apply_orders(&[make_order("a1", OrderSide::Buy, dec!(40), dec!(0.5))]);
+        s.apply_orders(&[make_order("a1", OrderSide::Sell, dec!(20), dec!(0.8))]);
+        // $6 realized, under the $10 threshold
+        assert_eq!(s.lock_profit(dec!(10)), None);
+        s.apply_orders(&[make_order("a1", OrderSide::Sell, dec!(20), dec!(0.8))]);
+        s.record_capital(112.0);
+        assert_eq!(s.lock_profit(dec!(10)).unwrap(), dec!(12));
+        assert_eq!(s.budget_remaining, dec!(100));
+        assert!(approx_eq(s.peak_capital, 100.0));
+        assert!(s.check_invariants().is_ok());
+        // Nothing new to lock
+        assert_eq!(s.lock_profit(dec!(10)), None);
+        assert!(approx_eq(s.exit_summary(&HashMap::new()).locked_profit, 12.0));
+    }
+
+    // ── Checked mutations ──────────────────────────────────────────
+
+    #[test]
+    fn checked_buy_rejects_overspend() {
+        let mut s = TradingState::new(dec!(5));
+        let err = s.apply_order_checked(&make_order("a1", OrderSide::Buy, dec!(20), dec!(0.5)));
+        assert!(err.is_err());
+        assert_eq!(s.budget_remaining, dec!(5));
+        assert!(s.holdings.is_empty());
+    }
+
+    #[test]
+    fn checked_sell_rejects_oversell_including_resting() {
+        let mut s = TradingState::new(dec!(100));
+        s.apply_order_checked(&make_order("a1", OrderSide::Buy, dec!(10), dec!(0.5)))
+            .unwrap();
+        s.add_resting_order_checked(make_resting("o1", "a1", OrderSide::Sell, dec!(6), dec!(0.5)))
+            .unwrap();
+        // Only 4 shares are not already resting for sale
+        assert!(
+            s.apply_order_checked(&make_order("a1", OrderSide::Sell, dec!(5), dec!(0.5)))
+                .is_err()
+        );
+        s.apply_order_checked(&make_order("a1", OrderSide::Sell, dec!(4), dec!(0.5)))
+            .unwrap();
+    }
+
+    #[test]
+    fn drawdown_from_peak_capital() {
+        let mut s = TradingState::new(dec!(100));
+        assert!(approx_eq(s.record_capital(100.0), 0.0));
+        assert!(approx_eq(s.record_capital(120.0), 0.0));
+        assert!(approx_eq(s.record_capital(90.0), 25.0));
+        assert!(approx_eq(s.peak_capital, 120.0));
+        // The peak is a WAL mutation, so a replay restores it
+        let mut replayed = TradingState::new(dec!(100));
+        replayed.apply_mutation(&Mutation::PeakCapital { capital: 120.0 });
+        assert!(approx_eq(replayed.record_capital(90.0), 25.0));
+        // Re-basing the budget moves the peak with it
+        s.rebase_budget(dec!(200));
+        assert!(approx_eq(s.peak_capital, 220.0));
+
+        s.trip_kill_switch(25.0);
+        assert!(s.killed);
+    }
+
+    #[test]
+    fn daily_pnl_resets_each_utc_day() {
+        let mut s = TradingState::new(dec!(100));
+        let morning = "2025-06-01T08:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let evening = "2025-06-01T23:30:00Z".parse::<DateTime<Utc>>().unwrap();
+        let next_day = "2025-06-02T00:05:00Z".parse::<DateTime<Utc>>().unwrap();
+        assert!(approx_eq(s.daily_pnl(100.0, morning), 0.0));
+        assert!(approx_eq(s.daily_pnl(85.0, evening), -15.0));
+        assert!(approx_eq(s.daily_pnl(80.0, next_day), 0.0));
+        assert!(approx_eq(s.daily_pnl(82.0, next_day), 2.0));
+
+        let midnight = next_day - chrono::Duration::minutes(5);
+        assert_eq!(s.pause_for_day(evening), midnight);
+        assert_eq!(s.paused_until, Some(midnight));
+        assert!(s.buys_paused(evening.timestamp_millis()));
+        assert!(!s.buys_paused(next_day.timestamp_millis()));
+
+        // The pause and the day's baseline are WAL mutations, so a replay restores them
+        let mut replayed = TradingState::new(dec!(100));
+        replayed.apply_mutation(&Mutation::DailyLossPause {
+            until_ms: midnight.timestamp_millis(),
+        });
+        replayed.apply_mutation(&Mutation::DayStart {
+            day: next_day.date_naive(),
+            capital: 80.0,
+        });
+        assert_eq!(replayed.paused_until, Some(midnight));
+        assert!(approx_eq(replayed.daily_pnl(70.0, next_day), -10.0));
+    }
+
+    #[test]
+    fn checked_resting_rejects_duplicates_and_unknown_ids() {
+        let mut s = TradingState::new(dec!(100));
+        s.add_resting_order_checked(make_resting("o1", "a1", OrderSide::Buy, dec!(10), dec!(0.5)))
+            .unwrap();
+        let duplicate = make_resting("o1", "a1", OrderSide::Buy, dec!(1), dec!(0.5));
+        assert!(s.add_resting_order_checked(duplicate).is_err());
+        assert!(s.resolve_resting_fill_checked("nope", dec!(1), dec!(0.5)).is_err());
+        assert!(s.resolve_resting_cancel_checked("nope").is_err());
+        assert!(s.resolve_resting_fill_checked("o1", dec!(11), dec!(0.5)).is_err());
+        s.resolve_resting_fill_checked("o1", dec!(10), dec!(0.5)).unwrap();
+        s.check_invariants().unwrap();
+    }
+
+    #[test]
+    fn checked_rejects_invalid_price_and_size() {
+        let mut s = TradingState::new(dec!(100));
+        assert!(
+            s.apply_order_checked(&make_order("a1", OrderSide::Buy, dec!(-1), dec!(0.5)))
+                .is_err()
+        );
+        assert!(
+            s.apply_order_checked(&make_order("a1", OrderSide::Buy, dec!(1), dec!(1.5)))
+                .is_err()
+        );
+        assert!(
+            s.apply_order_checked(&make_order("a1", OrderSide::Buy, dec!(1), dec!(-0.5)))
+                .is_err()
+        );
+    }
+
+    mod proptests {
+        use super::*;
+        use proptest::prelude::*;
+
+        const ASSETS: [&str; 3] = ["a1", "a2", "a3"];
+
+        #[derive(Debug, Clone)]
+        enum Op {
+            Fill { asset: usize, side: OrderSide, shares: f64, price: f64 },
+            Rest { asset: usize, side: OrderSide, shares: f64, price: f64 },
+            RestingFill { pick: usize, frac: f64, price: f64 },
+            RestingCancel { pick: usize },
+        }
+
+        fn side() -> impl Strategy<Value = OrderSide> {
+            prop_oneof![Just(OrderSide::Buy), Just(OrderSide::Sell)]
+        }
+
+        fn op() -> impl Strategy<Value = Op> {
+            prop_oneof![
+                (0..ASSETS.len(), side(), 0.01f64..200.0, 0.0f64..=1.0).prop_map(
+                    |(asset, side, shares, price)| Op::Fill { asset, side, shares, price }
+                ),
+                (0..ASSETS.len(), side(), 0.01f64..200.0, 0.0f64..=1.0).prop_map(
+                    |(asset, side, shares, price)| Op::Rest { asset, side, shares, price }
+                ),
+                (0usize..8, 0.01f64..=1.0, 0.0f64..=1.0)
+                    .prop_map(|(pick, frac, price)| Op::RestingFill { pick, frac, price }),
+                (0usize..8).prop_map(|pick| Op::RestingCancel { pick }),
+            ]
+        }
+
+        /// Run an op through the checked API. Rejections are expected and ignored;
+        /// a rejected op must leave the state unchanged.
+        fn run(s: &mut TradingState, op: &Op, next_id: &mut u32) {
+            let before = (s.budget_remaining, s.total_orders, s.resting_orders.len());
+            let money = |value: &f64| crate::money::from_f64(*value);
+            let res = match op {
+                Op::Fill { asset, side, shares, price } => {
+                    let order = make_order(ASSETS[*asset], *side, money(shares), money(price));
+                    s.apply_order_checked(&order)
+                }
+                Op::Rest { asset, side, shares, price } => {
+                    *next_id += 1;
+                    s.add_resting_order_checked(make_resting(
+                        &format!("o{next_id}"),
+                        ASSETS[*asset],
+                        *side,
+                        money(shares),
+                        money(price),
+                    ))
+                }
+                Op::RestingFill { pick, frac, price } => match s.resting_orders.get(*pick) {
+                    Some(r) => {
+                        // Fills come in whole lots, as on the CLOB
+                        let lot = crate::engine::SHARE_INCREMENT;
+                        let shares = crate::money::round_toward_zero(r.shares * money(frac), lot);
+                        let id = r.order_id.clone();
+                        s.resolve_resting_fill_checked(&id, shares, money(price))
+                    }
+                    None => s.resolve_resting_fill_checked("missing", Decimal::ONE, money(price)),
+                },
+                Op::RestingCancel { pick } => match s.resting_orders.get(*pick) {
+                    Some(r) => {
+                        let id = r.order_id.clone();
+                        s.resolve_resting_cancel_checked(&id)
+                    }
+                    None => s.resolve_resting_cancel_checked("missing"),
+                },
+            };
+            if res.is_err() {
+                let after = (s.budget_remaining, s.total_orders, s.resting_orders.len());
+                assert_eq!(before, after, "rejected op mutated state: {op:?}");
+            }
+        }
+
+        proptest! {
+            #[test]
+            fn accounting_invariants_hold(
+                budget in 0.0f64..1000.0,
+                ops in proptest::collection::vec(op(), 1..60),
+            ) {
+                let mut s = TradingState::new(crate::money::from_f64(budget));
+                let mut next_id = 0;
+                for op in &ops {
+                    run(&mut s, op, &mut next_id);
+                    prop_assert!(s.check_invariants().is_ok(), "{:?}", s.check_invariants());
+                    prop_assert!(s.budget_remaining >= Decimal::ZERO);
+                    for asset in ASSETS {
+                        prop_assert!(s.effective_held_shares(asset) >= Decimal::ZERO);
+                    }
+                }
+            }
+
+            #[test]
+            fn cancelling_everything_releases_reservations(
+                budget in 1.0f64..1000.0,
+                ops in proptest::collection::vec(op(), 1..40),
+            ) {
+                let mut s = TradingState::new(crate::money::from_f64(budget));
+                let mut next_id = 0;
+                for op in &ops {
+                    run(&mut s, op, &mut next_id);
+                }
+                let ids: Vec<String> = s.resting_orders.iter().map(|r| r.order_id.clone()).collect();
+                for id in ids {
+                    s.resolve_resting_cancel_checked(&id).unwrap();
+                }
+                let expected = s.initial_budget - s.total_spent + s.total_sell_proceeds;
+                prop_assert_eq!(s.budget_remaining, expected);
+            }
+        }
+    }
 }
@@ -0,0 +1,507 @@
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use anyhow::{Context, Result};
+use rusqlite::{Connection, OpenFlags, OptionalExtension, params};
+
+use crate::config::{EventRetention, PersistenceConfig, StoreBackend};
+use crate::eventlog::EventLog;
+use crate::types::CopytradeEvent;
+use crate::wal::{self, Mutation, Wal, WalEntry};
+
+/// Durable storage for `TradingState`: the mutations that rebuild it, plus the
+/// events they were made for.
+///
+/// Mutations follow the write-ahead protocol of `TradingState::commit`: `save`
+/// records the intent before the mutation is applied and `ack` marks it
+/// applied, so `load` can return intents a crash left unacknowledged. New
+/// backends implement this trait and add a `StoreBackend` variant.
+pub trait StateStore: Send + Sync {
+    /// Every saved mutation, in sequence order.
+    fn load(&mut self) -> Result<Vec<WalEntry>>;
+
+    /// Durably record the intent to apply `mutation`, returning its sequence
+    /// number.
+    fn save(&mut self, mutation: &Mutation) -> Result<u64>;
+
+    /// Mark the mutation with sequence number `seq` as applied.
+    fn ack(&mut self, seq: u64) -> Result<()>;
+
+    /// Record a reported event. Events are not part of the replayed state.
+    fn append_event(&mut self, event: &CopytradeEvent) -> Result<()>;
+}
+
+/// The JSON-lines write-ahead log (`wal::Wal`) as a `StateStore`.
+///
+/// Events go to a rotated `EventLog` beside it, or to the WAL too with
+/// `[persistence] events_in_store`.
+pub struct FileStore {
+    wal: Wal,
+    path: PathBuf,
+    initial_budget: f64,
+    events: Option<EventLog>,
+}
+
+impl FileStore {
+    /// Open (or create) the log at `path` (see `Wal::open`), and the event log
+    /// at `EventLog::path_for(path)` when `events` is set.
+    pub fn open(path: &Path, initial_budget: f64, events: Option<EventRetention>) -> Result<Self> {
+        let (wal, _) = Wal::open(path, initial_budget)?;
+        let events = events
+            .map(|retention| EventLog::open(&EventLog::path_for(path), retention))
+            .transpose()?;
+        Ok(Self {
+            wal,
+            path: path.to_path_buf(),
+            initial_budget,
+            events,
+        })
+    }
+}
+
+impl StateStore for FileStore {
+    fn load(&mut self) -> Result<Vec<WalEntry>> {
+        Wal::read_entries(&self.path, self.initial_budget)
+    }
+
+    fn save(&mut self, mutation: &Mutation) -> Result<u64> {
+        self.wal.append_intent(mutation)
+    }
+
+    fn ack(&mut self, seq: u64) -> Result<()> {
+        self.wal.ack(seq)
+    }
+
+    fn append_event(&mut self, event: &CopytradeEvent) -> Result<()> {
+        match &mut self.events {
+            Some(log) => log.append(event),
+            None => self.wal.append_event(event),
+        }
+    }
+}
+
+/// A SQLite database as a `StateStore`: one row per mutation, acknowledged in
+/// place, and events in a rotated `EventLog` beside the database (or in their
+/// own table with `[persistence] events_in_store`).
+///
+/// The database runs in WAL journal mode with full syncs, so a saved intent
+/// survives a crash like a `Wal` line does.
+pub struct SqliteStore {
+    conn: Mutex<Connection>,
+    events: Option<EventLog>,
+}
+
+const SQLITE_SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS meta (key TEXT PRIMARY KEY, value TEXT NOT NULL);
+    CREATE TABLE IF NOT EXISTS mutations (
+        seq INTEGER PRIMARY KEY,
+        mutation TEXT NOT NULL,
+        acked INTEGER NOT NULL DEFAULT 0
+    );
+    CREATE TABLE IF NOT EXISTS events (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        event TEXT NOT NULL
+    );
+";
+
+impl SqliteStore {
+    /// Open (or create) the database at `path`. Errors if it was created with a
+    /// different `initial_budget`, or by a newer state schema version. Rows of
+    /// an older version are upgraded in place, in one transaction.
+    pub fn open(path: &Path, initial_budget: f64, events: Option<EventRetention>) -> Result<Self> {
+        let mut conn =
+            Connection::open(path).with_context(|| format!("failed to open {}", path.display()))?;
+        conn.pragma_update(None, "journal_mode", "WAL")?;
+        conn.pragma_update(None, "synchronous", "FULL")?;
+        conn.execute_batch(SQLITE_SCHEMA)
+            .with_context(|| format!("failed to create tables in {}", path.display()))?;
+        match sqlite_budget(&conn)? {
+            Some(budget) => {
+                if (budget - initial_budget).abs() > 1e-9 {
+                    anyhow::bail!(
+                        "state database {} was written with budget {budget}, but budget is {initial_budget}",
+                        path.display()
+                    );
+                }
+                let version = sqlite_version(&conn)?;
+                if version > wal::SCHEMA_VERSION {
+                    anyhow::bail!(
+                        "state database {} uses state schema v{version}, but this build reads v{}",
+                        path.display(),
+                        wal::SCHEMA_VERSION
+                    );
+                }
+                if version < wal::SCHEMA_VERSION {
+                    upgrade_sqlite(&mut conn, version)
+                        .with_context(|| format!("failed to upgrade {}", path.display()))?;
+                }
+            }
+            None => {
+                conn.execute(
+                    "INSERT INTO meta (key, value) VALUES ('initial_budget', ?1), ('version', ?2)",
+                    params![initial_budget.to_string(), wal::SCHEMA_VERSION.to_string()],
+                )?;
+            }
+        }
+        let events = events
+            .map(|retention| EventLog::open(&EventLog::path_for(path), retention))
+            .transpose()?;
+        Ok(Self {
+            conn: Mutex::new(conn),
+            events,
+        })
+    }
+
+    fn conn(&self) -> std::sync::MutexGuard<'_, Connection> {
+        self.conn.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Budget recorded in an open state database, if it has one.
+fn sqlite_budget(conn: &Connection) -> Result<Option<f64>> {
+    let budget: Option<String> = conn
+        .query_row(
+            "SELECT value FROM meta WHERE key = 'initial_budget'",
+            [],
+            |row| row.get(0),
+        )
+        .optional()?;
+    Ok(budget.and_then(|b| b.parse().ok()))
+}
+
+/// Rewrite every mutation of a state database at schema `from` with the WAL's
+/// `upgrade_record` steps, and record the current version.
+fn upgrade_sqlite(conn: &mut Connection, from: u32) -> Result<()> {
+    let tx = conn.transaction()?;
+    let rows: Vec<(i64, String)> = tx
+        .prepare("SELECT seq, mutation FROM mutations")?
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<rusqlite::Result<_>>()?;
+    for (seq, mutation) in rows {
+        let mutation: serde_json::Value = serde_json::from_str(&mutation)
+            .with_context(|| format!("corrupt mutation {seq} in the state database"))?;
+        let mut record = serde_json::json!({ "type": "intent", "seq": seq, "mutation": mutation });
+        for version in from..wal::SCHEMA_VERSION {
+            wal::upgrade_record(version, &mut record);
+        }
+        tx.execute(
+            "UPDATE mutations SET mutation = ?1 WHERE seq = ?2",
+            params![record["mutation"].to_string(), seq],
+        )?;
+    }
+    tx.execute(
+        "INSERT OR REPLACE INTO meta (key, value) VALUES ('version', ?1)",
+        params![wal::SCHEMA_VERSION.to_string()],
+    )?;
+    tx.commit()?;
+    Ok(())
+}
+
+/// Every mutation in an open state database, in sequence order.
+fn sqlite_entries(conn: &Connection) -> Result<Vec<WalEntry>> {
+    let mut stmt = conn.prepare("SELECT seq, mutation, acked FROM mutations ORDER BY seq")?;
+    let rows = stmt.query_map([], |row| {
+        Ok((
+            row.get::<_, i64>(0)?,
+            row.get::<_, String>(1)?,
+            row.get::<_, bool>(2)?,
+        ))
+    })?;
+    let mut entries = Vec::new();
+    for row in rows {
+        let (seq, mutation, acked) = row?;
+        let mutation = serde_json::from_str(&mutation)
+            .with_context(|| format!("corrupt mutation {seq} in the state database"))?;
+        entries.push(WalEntry {
+            seq: seq as u64,
+            mutation,
+            acked,
+        });
+    }
+    Ok(entries)
+}
+
+impl StateStore for SqliteStore {
+    fn load(&mut self) -> Result<Vec<WalEntry>> {
+        sqlite_entries(&self.conn())
+    }
+
+    fn save(&mut self, mutation: &Mutation) -> Result<u64> {
+        let json = serde_json::to_string(mutation).context("failed to serialize mutation")?;
+        let conn = self.conn();
+        let seq: i64 = conn.query_row(
+            "INSERT INTO mutations (seq, mutation)
+             VALUES ((SELECT COALESCE(MAX(seq) + 1, 0) FROM mutations), ?1)
+             RETURNING seq",
+            params![json],
+            |row| row.get(0),
+        )?;
+        Ok(seq as u64)
+    }
+
+    fn ack(&mut self, seq: u64) -> Result<()> {
+        self.conn().execute(
+            "UPDATE mutations SET acked = 1 WHERE seq = ?1",
+            params![seq as i64],
+        )?;
+        Ok(())
+    }
+
+    fn append_event(&mut self, event: &CopytradeEvent) -> Result<()> {
+        if let Some(log) = &mut self.events {
+            return log.append(event);
+        }
+        let json = serde_json::to_string(event).context("failed to serialize event")?;
+        self.conn()
+            .execute("INSERT INTO events (event) VALUES (?1)", params![json])?;
+        Ok(())
+    }
+}
+
+/// Open the store `[persistence] backend` selects at `location` (the `--wal`
+/// path).
+pub fn open_store(
+    config: &PersistenceConfig,
+    location: &Path,
+    initial_budget: f64,
+) -> Result<Box<dyn StateStore>> {
+    let events = config.event_log();
+    match config.backend {
+        StoreBackend::File => Ok(Box::new(FileStore::open(location, initial_budget, events)?)),
+        StoreBackend::Sqlite => Ok(Box::new(SqliteStore::open(
+            location,
+            initial_budget,
+            events,
+        )?)),
+    }
+}
+
+/// State schema version of a database's rows (0 for databases from before
+/// versioning).
+fn sqlite_version(conn: &Connection) -> Result<u32> {
+    let version: Option<String> = conn
+        .query_row("SELECT value FROM meta WHERE key = 'version'", [], |row| {
+            row.get(0)
+        })
+        .optional()?;
+    Ok(version.and_then(|v| v.parse().ok()).unwrap_or(0))
+}
+
+/// Budget the store at `location` was created with, if it exists (see
+/// `Wal::header_budget`).
+pub fn stored_budget(config: &PersistenceConfig, location: &Path) -> Result<Option<f64>> {
+    match config.backend {
+        StoreBackend::File => Wal::header_budget(location),
+        StoreBackend::Sqlite => match open_read_only(location)? {
+            Some(conn) => sqlite_budget(&conn),
+            None => Ok(None),
+        },
+    }
+}
+
+/// The mutations of the store at `location` without opening it for writing,
+/// as a hot standby follows its leader (see `Wal::read_entries`). No entries if
+/// it doesn't exist yet. Like the file log, a database of another schema
+/// version is refused: it can't be upgraded read-only.
+pub fn read_entries(
+    config: &PersistenceConfig,
+    location: &Path,
+    initial_budget: f64,
+) -> Result<Vec<WalEntry>> {
+    match config.backend {
+        StoreBackend::File => Wal::read_entries(location, initial_budget),
+        StoreBackend::Sqlite => match open_read_only(location)? {
+            Some(conn) => {
+                let version = sqlite_version(&conn)?;
+                if version != wal::SCHEMA_VERSION {
+                    anyhow::bail!(
+                        "state database {} uses state schema v{version}, but this build reads v{}",
+                        location.display(),
+                        wal::SCHEMA_VERSION
+                    );
+                }
+                sqlite_entries(&conn)
+            }
+            None => Ok(Vec::new()),
+        },
+    }
+}
+
+/// Delete the store at `location` (with SQLite's `-wal` and `-shm` files), as
+/// when a `--graduate` switch to live fails after its log was created. Its
+/// events are kept.
+pub fn remove_store(config: &PersistenceConfig, location: &Path) -> Result<()> {
+    let mut paths = vec![location.to_path_buf()];
+    if config.backend == StoreBackend::Sqlite {
+        for suffix in ["-wal", "-shm"] {
+            let mut name = location.as_os_str().to_os_string();
+            name.push(suffix);
+            paths.push(PathBuf::from(name));
+        }
+    }
+    for path in paths {
+        match std::fs::remove_file(&path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                return Err(e).with_context(|| format!("failed to remove {}", path.display()));
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+fn open_read_only(path: &Path) -> Result<Option<Connection>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let flags = OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX;
+    let conn = Connection::open_with_flags(path, flags)
+        .with_context(|| format!("failed to open {}", path.display()))?;
+    Ok(Some(conn))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{BuildInfo, EventTrigger};
+    use rust_decimal::Decimal;
+
+    #[test]
+    fn file_store_keeps_events_out_of_replay() {
+        let path =
+            std::env::temp_dir().join(format!("copytrade-store-{}.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let mut store = open_store(&PersistenceConfig::default(), &path, 100.0).unwrap();
+        let seq = store
+            .save(&Mutation::RestingCancel {
+                order_id: "o1".to_string(),
+            })
+            .unwrap();
+        store.ack(seq).unwrap();
+        let event = CopytradeEvent {
+            timestamp: "2026-01-01T00:00:00Z".to_string(),
+            build: BuildInfo::new("abc123".to_string()),
+            strategy_id: None,
+            trigger: EventTrigger::TradeDetected,
+            detected_trade_hashes: vec!["0xabc".to_string()],
+            detected_trade_timestamps: vec![],
+            trader_trades: vec![],
+            trader_intents: vec![],
+            position_changes: vec![],
+            copy_latency_secs: None,
+            orders: vec![],
+            skipped_orders: vec![],
+            budget_remaining: 100.0,
+            total_spent: 0.0,
+            execution_results: None,
+        };
+        store.append_event(&event).unwrap();
+        store
+            .save(&Mutation::LockProfit {
+                amount: Decimal::ONE,
+            })
+            .unwrap();
+
+        let entries = store.load().unwrap();
+        assert_eq!(entries.len(), 2);
+        assert!(entries[0].acked);
+        assert_eq!(entries[1].seq, 1);
+        assert!(!entries[1].acked);
+        // Events go to the event log beside the WAL by default
+        let contents = std::fs::read_to_string(&path).unwrap();
+        assert!(!contents.contains("\"type\":\"event\""));
+        let events_path = EventLog::path_for(&path);
+        let events = std::fs::read_to_string(&events_path).unwrap();
+        assert!(events.contains("\"config_hash\":\"abc123\""));
+        let _ = std::fs::remove_file(&path);
+        let _ = std::fs::remove_file(&events_path);
+    }
+
+    #[test]
+    fn sqlite_store_round_trips_mutations() {
+        let path =
+            std::env::temp_dir().join(format!("copytrade-store-{}.sqlite", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let config = PersistenceConfig {
+            backend: StoreBackend::Sqlite,
+            events_in_store: true,
+            ..PersistenceConfig::default()
+        };
+
+        let mut store = open_store(&config, &path, 100.0).unwrap();
+        let seq = store
+            .save(&Mutation::RestingCancel {
+                order_id: "o1".to_string(),
+            })
+            .unwrap();
+        store.ack(seq).unwrap();
+        store
+            .save(&Mutation::LockProfit {
+                amount: Decimal::new(125, 2),
+            })
+            .unwrap();
+        drop(store);
+
+        // Read-only, as a standby follows it, and reopened for writing
+        assert_eq!(stored_budget(&config, &path).unwrap(), Some(100.0));
+        let entries = read_entries(&config, &path, 100.0).unwrap();
+        assert_eq!(entries.len(), 2);
+        let mut store = open_store(&config, &path, 100.0).unwrap();
+        let entries = store.load().unwrap();
+        assert!(entries[0].acked);
+        assert_eq!(entries[1].seq, 1);
+        assert!(!entries[1].acked);
+        assert!(matches!(
+            entries[1].mutation,
+            Mutation::LockProfit { amount } if amount == Decimal::new(125, 2)
+        ));
+        assert_eq!(
+            store
+                .save(&Mutation::RestingCancel {
+                    order_id: "o2".into()
+                })
+                .unwrap(),
+            2
+        );
+        drop(store);
+        assert!(open_store(&config, &path, 50.0).is_err());
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn sqlite_store_upgrades_older_schemas() {
+        let path =
+            std::env::temp_dir().join(format!("copytrade-store-v1-{}.sqlite", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        drop(SqliteStore::open(&path, 100.0, None).unwrap());
+        // As written by a v1 build, with decimals as floats
+        let conn = Connection::open(&path).unwrap();
+        conn.execute_batch(
+            r#"UPDATE meta SET value = '1' WHERE key = 'version';
+               INSERT INTO mutations (seq, mutation, acked)
+               VALUES (0, '{"kind":"rebase_budget","budget":80.5}', 1);"#,
+        )
+        .unwrap();
+        drop(conn);
+
+        // A standby can't upgrade it read-only, so refuses it
+        let config = PersistenceConfig {
+            backend: StoreBackend::Sqlite,
+            ..PersistenceConfig::default()
+        };
+        assert!(read_entries(&config, &path, 100.0).is_err());
+
+        let mut store = SqliteStore::open(&path, 100.0, None).unwrap();
+        let entries = store.load().unwrap();
+        assert!(matches!(
+            entries[0].mutation,
+            Mutation::RebaseBudget { budget } if budget == Decimal::new(805, 1)
+        ));
+        drop(store);
+        // Already current: opens as it is
+        assert!(SqliteStore::open(&path, 100.0, None).is_ok());
+        assert_eq!(read_entries(&config, &path, 100.0).unwrap().len(), 1);
+        let _ = std::fs::remove_file(&path);
+    }
+}
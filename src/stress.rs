@@ -0,0 +1,212 @@
+use std::collections::HashMap;
+
+use serde::Serialize;
+
+use crate::config::EngineParams;
+use crate::engine::compute_orders;
+use crate::state::TradingState;
+use crate::wal::Mutation;
+
+/// An adverse scenario to run the current holdings through (`copytrade stress`).
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Scenario {
+    /// Every holding tagged `tag` resolves against us (price 0).
+    ResolveAgainst { tag: String },
+    /// Every price gaps `pct` percent against us.
+    PriceGap { pct: f64 },
+    /// The trader exits everything at once: the engine plans the exits, which
+    /// fill at prices gapped `gap_pct` percent against us by the rush out.
+    TraderLiquidates { gap_pct: f64 },
+}
+
+impl Scenario {
+    /// The standard set: holdings tagged `tag` resolve against us, prices gap
+    /// `gap_pct` percent, and the trader liquidates into that gap.
+    pub fn standard(tag: &str, gap_pct: f64) -> Vec<Self> {
+        vec![
+            Self::ResolveAgainst {
+                tag: tag.to_string(),
+            },
+            Self::PriceGap { pct: gap_pct },
+            Self::TraderLiquidates { gap_pct },
+        ]
+    }
+
+    /// `prices` after the shock, for the holdings of `state`.
+    fn shock(&self, state: &TradingState, prices: &HashMap<String, f64>) -> HashMap<String, f64> {
+        let gapped = |pct: f64| {
+            let factor = (1.0 - pct / 100.0).max(0.0);
+            prices
+                .iter()
+                .map(|(asset, p)| (asset.clone(), p * factor))
+                .collect()
+        };
+        match self {
+            Self::ResolveAgainst { tag } => {
+                let mut shocked = prices.clone();
+                for asset in state.holdings.keys() {
+                    let tags = state.asset_tags.get(asset).into_iter().flatten();
+                    if tags.into_iter().any(|t| t.eq_ignore_ascii_case(tag)) {
+                        shocked.insert(asset.clone(), 0.0);
+                    }
+                }
+                shocked
+            }
+            Self::PriceGap { pct } => gapped(*pct),
+            Self::TraderLiquidates { gap_pct } => gapped(*gap_pct),
+        }
+    }
+}
+
+/// P&L and cash of the state, marked at some prices.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Marks {
+    pub realized_pnl: f64,
+    pub unrealized_pnl: f64,
+    pub total_pnl: f64,
+    pub budget_remaining: f64,
+    /// Cash plus holdings and resting buys at those prices.
+    pub capital: f64,
+}
+
+impl Marks {
+    fn of(state: &TradingState, prices: &HashMap<String, f64>) -> Self {
+        let summary = state.exit_summary(prices);
+        Self {
+            realized_pnl: summary.realized_pnl,
+            unrealized_pnl: summary.unrealized_pnl,
+            total_pnl: summary.total_pnl,
+            budget_remaining: crate::money::to_f64(state.budget_remaining),
+            capital: crate::money::to_f64(state.effective_capital(prices)),
+        }
+    }
+}
+
+/// Outcome of one scenario, against the baseline.
+#[derive(Debug, Clone, Serialize)]
+pub struct ScenarioResult {
+    pub scenario: Scenario,
+    /// Holdings whose price the shock moved.
+    pub holdings_hit: usize,
+    /// Sells the engine planned (`trader_liquidates` only).
+    pub sell_orders: usize,
+    pub marks: Marks,
+    pub pnl_change: f64,
+    pub budget_change: f64,
+    pub capital_change: f64,
+}
+
+/// Baseline and scenario outcomes.
+#[derive(Debug, Clone, Serialize)]
+pub struct StressReport {
+    pub holdings: usize,
+    pub baseline: Marks,
+    pub scenarios: Vec<ScenarioResult>,
+}
+
+/// Run `scenarios` against the state built by `state`, marked at `prices`.
+/// Each scenario starts from a fresh state, so they don't compound.
+pub fn run(
+    state: impl Fn() -> TradingState,
+    prices: &HashMap<String, f64>,
+    scenarios: &[Scenario],
+    params: &EngineParams,
+) -> StressReport {
+    let start = state();
+    let baseline = Marks::of(&start, prices);
+    let scenarios = scenarios
+        .iter()
+        .map(|scenario| {
+            let mut state = state();
+            let shocked = scenario.shock(&state, prices);
+            let holdings_hit = state
+                .holdings
+                .keys()
+                .filter(|asset| shocked.get(*asset) != prices.get(*asset))
+                .count();
+            let mut sell_orders = 0;
+            if matches!(scenario, Scenario::TraderLiquidates { .. }) {
+                let budget = state.budget_remaining;
+                let orders = compute_orders(&[], &state, budget, &shocked, "stress", params);
+                sell_orders = orders.len();
+                state.apply_mutation(&Mutation::ApplyOrders { orders });
+            }
+            let marks = Marks::of(&state, &shocked);
+            ScenarioResult {
+                scenario: scenario.clone(),
+                holdings_hit,
+                sell_orders,
+                marks,
+                pnl_change: marks.total_pnl - baseline.total_pnl,
+                budget_change: marks.budget_remaining - baseline.budget_remaining,
+                capital_change: marks.capital - baseline.capital,
+            }
+        })
+        .collect();
+    StressReport {
+        holdings: start.holdings.len(),
+        baseline,
+        scenarios,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{MarketPosition, OrderSide, SimulatedOrder};
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn scenarios_shock_a_fresh_state_each() {
+        let buy = |asset: &str, tags: &[&str]| SimulatedOrder {
+            market: MarketPosition {
+                condition_id: String::new(),
+                asset: asset.to_string(),
+                title: asset.to_string(),
+                outcome: "Yes".to_string(),
+                outcome_index: 0,
+                event_slug: String::new(),
+                tags: tags.iter().map(|t| t.to_string()).collect(),
+                opposite_asset: String::new(),
+            },
+            side: OrderSide::Buy,
+            shares: dec!(10),
+            price: dec!(0.50),
+            cost_usd: dec!(5),
+        };
+        let state = || {
+            let mut state = TradingState::new(dec!(100));
+            state.apply_mutation(&Mutation::ApplyOrders {
+                orders: vec![buy("nba", &["Sports"]), buy("election", &["politics"])],
+            });
+            state
+        };
+        let prices = HashMap::from([("nba".to_string(), 0.60), ("election".to_string(), 0.40)]);
+        let report = run(
+            state,
+            &prices,
+            &Scenario::standard("sports", 20.0),
+            &EngineParams::default(),
+        );
+        assert_eq!(report.holdings, 2);
+        assert!((report.baseline.total_pnl - 0.0).abs() < 1e-9);
+        assert!((report.baseline.capital - 100.0).abs() < 1e-9);
+
+        let [resolve, gap, liquidate] = &report.scenarios[..] else {
+            panic!("three scenarios");
+        };
+        // 10 sports shares worth $6 go to 0
+        assert_eq!(resolve.holdings_hit, 1);
+        assert!((resolve.pnl_change + 6.0).abs() < 1e-9);
+        assert_eq!(resolve.budget_change, 0.0);
+        // $10 of holdings lose 20%
+        assert_eq!(gap.holdings_hit, 2);
+        assert!((gap.capital_change + 2.0).abs() < 1e-9);
+        // Sold into the gap: same loss, now realized and back in cash
+        assert_eq!(liquidate.sell_orders, 2);
+        assert!((liquidate.marks.realized_pnl + 2.0).abs() < 1e-9);
+        assert!((liquidate.budget_change - 8.0).abs() < 1e-9);
+        assert!(liquidate.marks.unrealized_pnl.abs() < 1e-9);
+    }
+}
@@ -0,0 +1,87 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::types::MarketPosition;
+
+/// User-defined tags (e.g. "NBA", "high-risk") keyed by asset, market, or event.
+///
+/// Configured under `[engine.tags]`. Tags are attached to target allocations and
+/// orders (`MarketPosition::tags`), remembered per held asset, and grouped in the
+/// exit summary.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TagRules {
+    /// Token ID → tags.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub assets: BTreeMap<String, Vec<String>>,
+    /// Condition ID → tags (both outcomes of the market).
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub markets: BTreeMap<String, Vec<String>>,
+    /// Event slug → tags (every market in the event).
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub events: BTreeMap<String, Vec<String>>,
+}
+
+impl TagRules {
+    pub fn is_empty(&self) -> bool {
+        self.assets.is_empty() && self.markets.is_empty() && self.events.is_empty()
+    }
+
+    /// All tags matching `market`, sorted and deduplicated.
+    pub fn tags_for(&self, market: &MarketPosition) -> Vec<String> {
+        let mut tags: Vec<String> = [
+            self.assets.get(&market.asset),
+            self.markets.get(&market.condition_id),
+            self.events.get(&market.event_slug),
+        ]
+        .into_iter()
+        .flatten()
+        .flatten()
+        .cloned()
+        .collect();
+        tags.sort();
+        tags.dedup();
+        tags
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tags_merge_asset_market_and_event_rules() {
+        let rules: TagRules = toml::from_str(
+            r#"
+[assets]
+a1 = ["high-risk"]
+
+[markets]
+c1 = ["finals", "high-risk"]
+
+[events]
+nba-finals = ["NBA"]
+"#,
+        )
+        .unwrap();
+        let market = MarketPosition {
+            condition_id: "c1".to_string(),
+            asset: "a1".to_string(),
+            title: "Finals".to_string(),
+            outcome: "Yes".to_string(),
+            outcome_index: 0,
+            event_slug: "nba-finals".to_string(),
+            tags: Vec::new(),
+            opposite_asset: String::new(),
+        };
+        assert_eq!(rules.tags_for(&market), vec!["NBA", "finals", "high-risk"]);
+
+        let other = MarketPosition {
+            asset: "a2".to_string(),
+            condition_id: "c2".to_string(),
+            event_slug: String::new(),
+            ..market
+        };
+        assert!(rules.tags_for(&other).is_empty());
+    }
+}
@@ -0,0 +1,515 @@
+//! Synthetic trader for offline end-to-end tests.
+//!
+//! `SyntheticTrader` evolves a portfolio over discrete steps — prices drift with
+//! configurable momentum, positions churn, markets exit or resolve — and emits the
+//! same SDK `Position` / `Trade` shapes the data API returns. `DrySession` drives
+//! the rebalancing pipeline against it exactly like the dry-run polling loop, with
+//! no network access. The `make_*` builders and `hold` are shared by unit tests.
+//!
+//! Available in unit tests and to downstream crates via the `testkit` feature.
+
+use std::collections::{HashMap, HashSet};
+
+use polymarket_client_sdk::data::types::response::{Position, Trade};
+use rust_decimal::Decimal;
+use serde_json::json;
+
+use crate::config::EngineParams;
+use crate::engine::plan_rebalance;
+use crate::state::TradingState;
+use crate::types::{HeldPosition, MarketPosition, SimulatedOrder, TargetAllocation};
+use crate::wal::Mutation;
+
+/// Placeholder proxy wallet used in synthetic payloads.
+pub const SYNTHETIC_WALLET: &str = "0x0000000000000000000000000000000000000001";
+
+/// Knobs controlling how the synthetic trader behaves each step.
+#[derive(Debug, Clone)]
+pub struct TraderBehavior {
+    /// Number of markets held at the start.
+    pub initial_markets: usize,
+    /// Fraction of the previous price move carried into the next one (0.0–1.0).
+    pub momentum: f64,
+    /// Standard size of a random price move per step.
+    pub volatility: f64,
+    /// Probability per held market per step of a resize trade.
+    pub churn: f64,
+    /// Probability per held market per step that the trader sells out entirely.
+    pub exit_rate: f64,
+    /// Probability per held market per step that the market resolves (price → 0 or 1).
+    pub resolve_rate: f64,
+    /// Probability per step of opening a new market.
+    pub entry_rate: f64,
+}
+
+impl Default for TraderBehavior {
+    fn default() -> Self {
+        Self {
+            initial_markets: 4,
+            momentum: 0.5,
+            volatility: 0.03,
+            churn: 0.2,
+            exit_rate: 0.05,
+            resolve_rate: 0.02,
+            entry_rate: 0.1,
+        }
+    }
+}
+
+/// One market in the synthetic trader's book.
+#[derive(Debug, Clone)]
+pub struct SyntheticMarket {
+    pub asset: String,
+    pub condition_id: String,
+    pub title: String,
+    pub price: f64,
+    pub shares: f64,
+    last_move: f64,
+}
+
+/// What the data API would return after a step.
+#[derive(Debug, Clone)]
+pub struct TraderSnapshot {
+    /// Active positions, filtered like `api::fetch_active_positions`.
+    pub positions: Vec<Position>,
+    /// Trades made during this step (newest first, like the trades endpoint).
+    pub trades: Vec<Trade>,
+}
+
+/// Deterministic, seedable trader simulation.
+pub struct SyntheticTrader {
+    pub behavior: TraderBehavior,
+    markets: Vec<SyntheticMarket>,
+    /// Last known price of every asset ever held — stands in for the gamma API.
+    last_prices: HashMap<String, f64>,
+    rng: SplitMix64,
+    next_market: u64,
+    next_trade: u64,
+    timestamp: i64,
+}
+
+impl SyntheticTrader {
+    pub fn new(behavior: TraderBehavior, seed: u64) -> Self {
+        let mut trader = Self {
+            behavior,
+            markets: Vec::new(),
+            last_prices: HashMap::new(),
+            rng: SplitMix64(seed),
+            next_market: 0,
+            next_trade: 0,
+            timestamp: 1_700_000_000,
+        };
+        for _ in 0..trader.behavior.initial_markets {
+            trader.open_market();
+        }
+        trader
+    }
+
+    /// Markets currently held (including ones that just resolved this step).
+    pub fn markets(&self) -> &[SyntheticMarket] {
+        &self.markets
+    }
+
+    /// Current snapshot without advancing time.
+    pub fn snapshot(&self) -> TraderSnapshot {
+        TraderSnapshot {
+            positions: self.active_positions(),
+            trades: Vec::new(),
+        }
+    }
+
+    /// Price lookup for any asset the trader ever held (gamma API stand-in).
+    pub fn exit_prices(&self) -> &HashMap<String, f64> {
+        &self.last_prices
+    }
+
+    /// Advance one step, returning the resulting positions and the trades made.
+    pub fn step(&mut self) -> TraderSnapshot {
+        self.timestamp += 60;
+        let mut trades = Vec::new();
+
+        // Resolved markets drop out of the book (redeemed)
+        self.markets.retain(|m| m.price > 0.0 && m.price < 1.0);
+
+        let b = self.behavior.clone();
+        let mut idx = 0;
+        while idx < self.markets.len() {
+            // Price drift with momentum
+            let shock = (self.rng.next_f64() - 0.5) * 2.0 * b.volatility;
+            let delta = b.momentum * self.markets[idx].last_move + shock;
+            let m = &mut self.markets[idx];
+            m.last_move = delta;
+            m.price = (m.price + delta).clamp(0.01, 0.99);
+
+            if self.rng.next_f64() < b.resolve_rate {
+                let won = self.rng.next_f64() < self.markets[idx].price;
+                self.markets[idx].price = if won { 1.0 } else { 0.0 };
+            } else if self.rng.next_f64() < b.exit_rate {
+                let shares = self.markets[idx].shares;
+                trades.push(self.trade(idx, "SELL", shares));
+                self.last_prices
+                    .insert(self.markets[idx].asset.clone(), self.markets[idx].price);
+                self.markets.remove(idx);
+                continue;
+            } else if self.rng.next_f64() < b.churn {
+                let factor = 0.5 + self.rng.next_f64();
+                let current = self.markets[idx].shares;
+                let target = (current * factor).max(1.0);
+                let (side, size) = if target > current {
+                    ("BUY", target - current)
+                } else {
+                    ("SELL", current - target)
+                };
+                self.markets[idx].shares = target;
+                trades.push(self.trade(idx, side, size));
+            }
+            let m = &self.markets[idx];
+            self.last_prices.insert(m.asset.clone(), m.price);
+            idx += 1;
+        }
+
+        if self.rng.next_f64() < b.entry_rate {
+            self.open_market();
+            let idx = self.markets.len() - 1;
+            let shares = self.markets[idx].shares;
+            trades.push(self.trade(idx, "BUY", shares));
+        }
+
+        trades.reverse();
+        TraderSnapshot {
+            positions: self.active_positions(),
+            trades,
+        }
+    }
+
+    fn open_market(&mut self) {
+        let n = self.next_market;
+        self.next_market += 1;
+        let price = 0.1 + self.rng.next_f64() * 0.8;
+        let shares = 10.0 + (self.rng.next_f64() * 490.0).round();
+        let market = SyntheticMarket {
+            asset: format!("sim-asset-{n}"),
+            condition_id: format!("sim-cond-{n}"),
+            title: format!("Synthetic market {n}"),
+            price,
+            shares,
+            last_move: 0.0,
+        };
+        self.last_prices.insert(market.asset.clone(), price);
+        self.markets.push(market);
+    }
+
+    fn trade(&mut self, idx: usize, side: &str, size: f64) -> Trade {
+        let n = self.next_trade;
+        self.next_trade += 1;
+        let m = &self.markets[idx];
+        make_trade(
+            &m.asset,
+            &m.condition_id,
+            side,
+            size,
+            m.price,
+            self.timestamp,
+            &format!("0xsimtx{n:060}"),
+        )
+    }
+
+    fn active_positions(&self) -> Vec<Position> {
+        self.markets
+            .iter()
+            .filter(|m| m.price > 0.0 && m.price < 1.0 && m.shares > 0.0)
+            .map(|m| make_position(&m.asset, &m.condition_id, &m.title, m.shares, m.price))
+            .collect()
+    }
+}
+
+/// Drives the dry-run rebalancing pipeline against a `SyntheticTrader`.
+///
+/// Mirrors the binary's flow: initial replication, then per step trade-hash dedup,
+/// a full rebalance when new trades appear, and `commit` of the simulated orders.
+pub struct DrySession {
+    pub trader: SyntheticTrader,
+    pub state: TradingState,
+    pub copy_pct: f64,
+    pub max_trade_pct: f64,
+    pub engine_params: EngineParams,
+    seen_hashes: HashSet<String>,
+    /// Orders produced by every rebalance, in order.
+    pub order_log: Vec<Vec<SimulatedOrder>>,
+}
+
+impl DrySession {
+    pub fn new(trader: SyntheticTrader, budget: f64, copy_pct: f64, max_trade_pct: f64) -> Self {
+        Self {
+            trader,
+            state: TradingState::new(crate::money::from_f64(budget)),
+            copy_pct,
+            max_trade_pct,
+            engine_params: EngineParams::default(),
+            seen_hashes: HashSet::new(),
+            order_log: Vec::new(),
+        }
+    }
+
+    /// Initial replication from the trader's current portfolio.
+    pub fn replicate(&mut self) {
+        let snapshot = self.trader.snapshot();
+        self.rebalance(&snapshot.positions);
+    }
+
+    /// Advance the trader one step and rebalance if it traded.
+    /// Returns `true` if new trades were detected.
+    pub fn step(&mut self) -> bool {
+        let snapshot = self.trader.step();
+        let mut detected = false;
+        for trade in &snapshot.trades {
+            detected |= self.seen_hashes.insert(trade.transaction_hash.clone());
+        }
+        // Resolutions produce no trades but still change the active set
+        let resolved =
+            self.state.holdings.keys().any(
+                |a| matches!(self.trader.exit_prices().get(a), Some(&p) if p == 0.0 || p == 1.0),
+            );
+        if detected || resolved {
+            self.rebalance(&snapshot.positions);
+        }
+        detected
+    }
+
+    fn rebalance(&mut self, positions: &[Position]) {
+        let orders = plan_rebalance(
+            positions,
+            &self.state,
+            self.trader.exit_prices(),
+            self.copy_pct,
+            self.max_trade_pct,
+            "synthe",
+            &self.engine_params,
+        );
+        if !orders.is_empty() {
+            self.state.commit(Mutation::ApplyOrders {
+                orders: orders.clone(),
+            });
+            self.state.total_events += 1;
+        }
+        self.order_log.push(orders);
+    }
+}
+
+/// Build an SDK `Position` via JSON deserialization (struct is `#[non_exhaustive]`).
+pub fn make_position(
+    asset: &str,
+    condition_id: &str,
+    title: &str,
+    shares: f64,
+    price: f64,
+) -> Position {
+    serde_json::from_value(json!({
+        "proxyWallet": SYNTHETIC_WALLET,
+        "asset": asset,
+        "conditionId": condition_id,
+        "size": shares.to_string(),
+        "avgPrice": price.to_string(),
+        "initialValue": (shares * price).to_string(),
+        "currentValue": (shares * price).to_string(),
+        "cashPnl": "0",
+        "percentPnl": "0",
+        "totalBought": shares.to_string(),
+        "realizedPnl": "0",
+        "percentRealizedPnl": "0",
+        "curPrice": price.to_string(),
+        "redeemable": false,
+        "mergeable": false,
+        "title": title,
+        "slug": asset,
+        "icon": "",
+        "eventSlug": asset,
+        "outcome": "Yes",
+        "outcomeIndex": 0,
+        "oppositeOutcome": "No",
+        "oppositeAsset": format!("{asset}-no"),
+        "endDate": "2099-12-31",
+        "negativeRisk": false
+    }))
+    .expect("valid synthetic Position JSON")
+}
+
+/// Build an SDK `Trade` via JSON deserialization (struct is `#[non_exhaustive]`).
+pub fn make_trade(
+    asset: &str,
+    condition_id: &str,
+    side: &str,
+    size: f64,
+    price: f64,
+    timestamp: i64,
+    transaction_hash: &str,
+) -> Trade {
+    serde_json::from_value(json!({
+        "proxyWallet": SYNTHETIC_WALLET,
+        "side": side,
+        "asset": asset,
+        "conditionId": condition_id,
+        "size": size.to_string(),
+        "price": price.to_string(),
+        "timestamp": timestamp,
+        "title": "",
+        "slug": asset,
+        "icon": "",
+        "eventSlug": asset,
+        "outcome": "Yes",
+        "outcomeIndex": 0,
+        "transactionHash": transaction_hash
+    }))
+    .expect("valid synthetic Trade JSON")
+}
+
+/// A bare `MarketPosition` for `asset` (no title, outcome, or tags).
+pub fn make_market(asset: &str) -> MarketPosition {
+    MarketPosition {
+        condition_id: String::new(),
+        asset: asset.to_string(),
+        title: String::new(),
+        outcome: String::new(),
+        outcome_index: 0,
+        event_slug: String::new(),
+        tags: Vec::new(),
+        opposite_asset: String::new(),
+    }
+}
+
+/// A target of `target_shares` of `asset` at `cur_price`, with no trader entry.
+pub fn make_target(
+    asset: &str,
+    trader_weight: f64,
+    target_shares: Decimal,
+    cur_price: Decimal,
+) -> TargetAllocation {
+    TargetAllocation {
+        market: make_market(asset),
+        trader_weight,
+        target_value_usd: target_shares * cur_price,
+        target_shares,
+        cur_price,
+        trader_entry: None,
+    }
+}
+
+/// Seed `state` with `shares` of `asset` bought at `avg_cost`.
+pub fn hold(state: &mut TradingState, asset: &str, shares: Decimal, avg_cost: Decimal) {
+    state.seed_holding(HeldPosition {
+        asset: asset.to_string(),
+        title: String::new(),
+        outcome: String::new(),
+        shares,
+        total_cost: shares * avg_cost,
+        avg_cost,
+        opposite_asset: String::new(),
+    });
+}
+
+/// Small deterministic PRNG so simulations are reproducible from a seed.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform in `[0, 1)`.
+    fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal::Decimal;
+
+    #[test]
+    fn same_seed_is_deterministic() {
+        let mut a = SyntheticTrader::new(TraderBehavior::default(), 7);
+        let mut b = SyntheticTrader::new(TraderBehavior::default(), 7);
+        for _ in 0..20 {
+            let sa = a.step();
+            let sb = b.step();
+            assert_eq!(sa.trades.len(), sb.trades.len());
+            assert_eq!(sa.positions.len(), sb.positions.len());
+        }
+    }
+
+    #[test]
+    fn snapshot_positions_are_active() {
+        let mut t = SyntheticTrader::new(
+            TraderBehavior {
+                resolve_rate: 0.3,
+                ..TraderBehavior::default()
+            },
+            3,
+        );
+        for _ in 0..30 {
+            for p in t.step().positions {
+                let price: f64 = p.cur_price.to_string().parse().unwrap();
+                assert!(price > 0.0 && price < 1.0);
+            }
+        }
+    }
+
+    #[test]
+    fn session_replicates_initial_portfolio() {
+        let trader = SyntheticTrader::new(TraderBehavior::default(), 1);
+        let markets = trader.markets().len();
+        let mut session = DrySession::new(trader, 1000.0, 1.0, 1.0);
+        session.replicate();
+        assert_eq!(session.state.holdings.len(), markets);
+        assert!(session.state.budget_remaining >= Decimal::ZERO);
+    }
+
+    #[test]
+    fn session_accounting_invariants_hold() {
+        for seed in 0..10 {
+            let behavior = TraderBehavior {
+                churn: 0.4,
+                exit_rate: 0.1,
+                resolve_rate: 0.05,
+                entry_rate: 0.3,
+                ..TraderBehavior::default()
+            };
+            let mut session =
+                DrySession::new(SyntheticTrader::new(behavior, seed), 500.0, 0.8, 0.4);
+            session.replicate();
+            for _ in 0..50 {
+                session.step();
+                let s = &session.state;
+                assert!(
+                    s.budget_remaining >= Decimal::ZERO,
+                    "seed {seed}: overspent"
+                );
+                assert!(s.holdings.values().all(|h| h.shares > Decimal::ZERO));
+                // Cash flows reconcile exactly: budget = initial - spent + proceeds
+                let expected = s.initial_budget - s.total_spent + s.total_sell_proceeds;
+                assert_eq!(s.budget_remaining, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn session_exits_positions_the_trader_left() {
+        let behavior = TraderBehavior {
+            churn: 0.0,
+            exit_rate: 1.0,
+            resolve_rate: 0.0,
+            entry_rate: 0.0,
+            ..TraderBehavior::default()
+        };
+        let mut session = DrySession::new(SyntheticTrader::new(behavior, 5), 1000.0, 1.0, 1.0);
+        session.replicate();
+        assert!(!session.state.holdings.is_empty());
+        assert!(session.step());
+        assert!(session.state.holdings.is_empty());
+    }
+}
@@ -0,0 +1,478 @@
+use std::collections::HashMap;
+
+use polymarket_client_sdk::data::types::response::{Activity, Position, Trade};
+use polymarket_client_sdk::data::types::{ActivityType, Side};
+use rust_decimal::prelude::ToPrimitive;
+use serde::{Deserialize, Serialize};
+
+use crate::chain::ChainFill;
+use crate::rtds::RtdsTrade;
+use crate::types::{OrderSide, PositionChangeKind, TraderPositionChange, TraderTrade};
+
+/// Why a detected trade was corrected or rejected by `normalize`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TradeIssue {
+    /// Size not positive or price outside (0, 1). Rejected.
+    InvalidFill,
+    /// The token belongs to a different market than the reported condition.
+    /// Rejected.
+    ConditionMismatch { token_condition: String },
+    /// Outcome index missing and the token isn't known. Rejected.
+    UnknownOutcome,
+    /// The reported outcome index disagreed with the token; the token wins.
+    OutcomeCorrected { reported: i32 },
+    /// A buy of an outcome the trader doesn't hold afterwards, while holding
+    /// the opposite one: the source reported the complementary side of a sell
+    /// of the held token. Flipped back (a sell of the held token at
+    /// `1 - price`).
+    OppositeToken,
+}
+
+impl TradeIssue {
+    /// Whether the trade is dropped rather than corrected.
+    pub fn is_fatal(&self) -> bool {
+        matches!(
+            self,
+            TradeIssue::InvalidFill
+                | TradeIssue::ConditionMismatch { .. }
+                | TradeIssue::UnknownOutcome
+        )
+    }
+}
+
+impl std::fmt::Display for TradeIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TradeIssue::InvalidFill => write!(f, "invalid size or price"),
+            TradeIssue::ConditionMismatch { token_condition } => {
+                write!(f, "token belongs to condition {token_condition}")
+            }
+            TradeIssue::UnknownOutcome => write!(f, "unknown outcome"),
+            TradeIssue::OutcomeCorrected { reported } => {
+                write!(
+                    f,
+                    "reported outcome index {reported} doesn't match the token"
+                )
+            }
+            TradeIssue::OppositeToken => write!(f, "reported the opposite token of the pair"),
+        }
+    }
+}
+
+/// A trade as reported by a detection source, before normalization.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RawTrade {
+    pub tx_hash: String,
+    pub condition_id: String,
+    pub outcome_index: Option<i32>,
+    pub asset: String,
+    pub side: OrderSide,
+    pub size: f64,
+    pub price: f64,
+    pub timestamp: i64,
+}
+
+impl RawTrade {
+    /// `None` for sides other than BUY/SELL (as for `from_rtds`).
+    pub fn from_rest(trade: &Trade) -> Option<Self> {
+        let side = match trade.side {
+            Side::Buy => OrderSide::Buy,
+            Side::Sell => OrderSide::Sell,
+            _ => return None,
+        };
+        Some(Self {
+            tx_hash: trade.transaction_hash.clone(),
+            condition_id: trade.condition_id.to_string(),
+            outcome_index: Some(trade.outcome_index),
+            asset: trade.asset.clone(),
+            side,
+            size: trade.size.to_f64().unwrap_or(0.0),
+            price: trade.price.to_f64().unwrap_or(0.0),
+            timestamp: trade.timestamp,
+        })
+    }
+
+    /// `None` for sides other than BUY/SELL.
+    pub fn from_rtds(trade: &RtdsTrade) -> Option<Self> {
+        let side = match trade.side.to_ascii_uppercase().as_str() {
+            "BUY" => OrderSide::Buy,
+            "SELL" => OrderSide::Sell,
+            _ => return None,
+        };
+        Some(Self {
+            tx_hash: trade.transaction_hash.clone(),
+            condition_id: trade.condition_id.clone(),
+            outcome_index: trade.outcome_index,
+            asset: trade.asset.clone(),
+            side,
+            size: trade.size,
+            price: trade.price,
+            timestamp: trade.timestamp,
+        })
+    }
+
+    /// Logs carry only the token, so the condition and outcome are left for
+    /// `normalize` to fill in from the known token pairs. Without a block
+    /// timestamp, `now` stands in.
+    pub fn from_chain(fill: &ChainFill, now: i64) -> Self {
+        Self {
+            tx_hash: fill.tx_hash.clone(),
+            condition_id: String::new(),
+            outcome_index: None,
+            asset: fill.asset.clone(),
+            side: fill.side,
+            size: fill.size,
+            price: fill.price,
+            timestamp: fill.timestamp.unwrap_or(now),
+        }
+    }
+}
+
+/// A `/activity` entry that bears on copying.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ActivityEvent {
+    Trade(RawTrade),
+    /// A split, merge, redemption or conversion: the trader's positions
+    /// changed without a trade.
+    PositionChange(TraderPositionChange),
+}
+
+impl ActivityEvent {
+    /// `None` for rewards (no position change), unknown activity types, and
+    /// trades missing the token, side or price.
+    pub fn from_activity(activity: &Activity) -> Option<Self> {
+        match activity.activity_type {
+            ActivityType::Trade => {
+                let side = match activity.side.as_ref()? {
+                    Side::Buy => OrderSide::Buy,
+                    Side::Sell => OrderSide::Sell,
+                    _ => return None,
+                };
+                Some(Self::Trade(RawTrade {
+                    tx_hash: activity.transaction_hash.clone(),
+                    condition_id: activity.condition_id.to_string(),
+                    outcome_index: activity.outcome_index,
+                    asset: activity.asset.clone()?,
+                    side,
+                    size: activity.size.to_f64().unwrap_or(0.0),
+                    price: activity.price?.to_f64().unwrap_or(0.0),
+                    timestamp: activity.timestamp,
+                }))
+            }
+            kind => {
+                let kind = match kind {
+                    ActivityType::Split => PositionChangeKind::Split,
+                    ActivityType::Merge => PositionChangeKind::Merge,
+                    ActivityType::Redeem => PositionChangeKind::Redeem,
+                    ActivityType::Conversion => PositionChangeKind::Conversion,
+                    _ => return None,
+                };
+                Some(Self::PositionChange(TraderPositionChange {
+                    tx_hash: activity.transaction_hash.clone(),
+                    condition_id: activity.condition_id.to_string(),
+                    kind,
+                    size: activity.size.to_f64().unwrap_or(0.0),
+                    timestamp: activity.timestamp,
+                }))
+            }
+        }
+    }
+
+    pub fn tx_hash(&self) -> &str {
+        match self {
+            Self::Trade(trade) => &trade.tx_hash,
+            Self::PositionChange(change) => &change.tx_hash,
+        }
+    }
+
+    pub fn timestamp(&self) -> i64 {
+        match self {
+            Self::Trade(trade) => trade.timestamp,
+            Self::PositionChange(change) => change.timestamp,
+        }
+    }
+}
+
+/// One token of an outcome pair.
+#[derive(Debug, Clone, PartialEq)]
+struct TokenInfo {
+    condition_id: String,
+    outcome_index: i32,
+    opposite: String,
+}
+
+/// Outcome tokens of each binary market, learned from the trader's positions
+/// (each position names its own token and the opposite one).
+#[derive(Debug, Default)]
+pub struct TokenPairs {
+    tokens: HashMap<String, TokenInfo>,
+}
+
+impl TokenPairs {
+    pub fn from_positions(positions: &[Position]) -> Self {
+        let mut tokens = HashMap::new();
+        for p in positions {
+            let condition_id = p.condition_id.to_string().to_ascii_lowercase();
+            tokens.insert(
+                p.asset.clone(),
+                TokenInfo {
+                    condition_id: condition_id.clone(),
+                    outcome_index: p.outcome_index,
+                    opposite: p.opposite_asset.clone(),
+                },
+            );
+            if !p.opposite_asset.is_empty() && matches!(p.outcome_index, 0 | 1) {
+                tokens
+                    .entry(p.opposite_asset.clone())
+                    .or_insert_with(|| TokenInfo {
+                        condition_id,
+                        outcome_index: 1 - p.outcome_index,
+                        opposite: p.asset.clone(),
+                    });
+            }
+        }
+        Self { tokens }
+    }
+}
+
+/// Normalize `trade` to its canonical `(condition_id, outcome_index)` and check
+/// it against the trader's `positions` after the trade.
+///
+/// The token ID is authoritative: a reported outcome index that disagrees with
+/// it is corrected, and a missing condition is taken from it. A buy of an
+/// outcome the trader doesn't hold, while holding the opposite outcome, is the
+/// mirror image of a sell of the held one (BUY NO @ p is SELL YES @ 1 - p), and
+/// is flipped to it. Returns the trade (unless an issue is fatal) and every
+/// issue found.
+pub fn normalize(
+    trade: RawTrade,
+    pairs: &TokenPairs,
+    positions: &[Position],
+) -> (Option<TraderTrade>, Vec<TradeIssue>) {
+    let mut issues = Vec::new();
+    if !(trade.size > 0.0 && trade.price > 0.0 && trade.price < 1.0) {
+        issues.push(TradeIssue::InvalidFill);
+        return (None, issues);
+    }
+
+    let known = pairs.tokens.get(&trade.asset);
+    let condition_id = match known {
+        Some(info) if trade.condition_id.is_empty() => info.condition_id.clone(),
+        _ => trade.condition_id.to_ascii_lowercase(),
+    };
+    let outcome_index = match (known, trade.outcome_index) {
+        (Some(info), _) if info.condition_id != condition_id => {
+            issues.push(TradeIssue::ConditionMismatch {
+                token_condition: info.condition_id.clone(),
+            });
+            return (None, issues);
+        }
+        (Some(info), Some(reported)) if reported != info.outcome_index => {
+            issues.push(TradeIssue::OutcomeCorrected { reported });
+            info.outcome_index
+        }
+        (Some(info), _) => info.outcome_index,
+        (None, Some(reported)) => reported,
+        (None, None) => {
+            issues.push(TradeIssue::UnknownOutcome);
+            return (None, issues);
+        }
+    };
+
+    let mut normalized = TraderTrade {
+        tx_hash: trade.tx_hash,
+        condition_id,
+        outcome_index,
+        asset: trade.asset,
+        side: trade.side,
+        size: trade.size,
+        price: trade.price,
+        timestamp: trade.timestamp,
+    };
+
+    if normalized.side == OrderSide::Buy
+        && let Some(info) = known
+        && !positions.iter().any(|p| p.asset == normalized.asset)
+        && positions.iter().any(|p| p.asset == info.opposite)
+    {
+        issues.push(TradeIssue::OppositeToken);
+        normalized.asset = info.opposite.clone();
+        normalized.outcome_index = 1 - normalized.outcome_index;
+        normalized.side = OrderSide::Sell;
+        normalized.price = 1.0 - normalized.price;
+    }
+    (Some(normalized), issues)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn position(asset: &str, opposite: &str, outcome_index: i32) -> Position {
+        serde_json::from_value(json!({
+            "proxyWallet": "0x0000000000000000000000000000000000000001",
+            "asset": asset,
+            "conditionId": "0xc1",
+            "size": "10",
+            "avgPrice": "0.50",
+            "initialValue": "5",
+            "currentValue": "5",
+            "cashPnl": "0",
+            "percentPnl": "0",
+            "totalBought": "10",
+            "realizedPnl": "0",
+            "percentRealizedPnl": "0",
+            "curPrice": "0.50",
+            "redeemable": false,
+            "mergeable": false,
+            "title": "",
+            "slug": "test-market",
+            "icon": "",
+            "eventSlug": "",
+            "outcome": "Yes",
+            "outcomeIndex": outcome_index,
+            "oppositeOutcome": "No",
+            "oppositeAsset": opposite,
+            "endDate": "2025-12-31",
+            "negativeRisk": false
+        }))
+        .expect("valid test Position JSON")
+    }
+
+    fn raw(
+        asset: &str,
+        condition_id: &str,
+        outcome_index: Option<i32>,
+        side: OrderSide,
+    ) -> RawTrade {
+        RawTrade {
+            tx_hash: "0xabc".to_string(),
+            condition_id: condition_id.to_string(),
+            outcome_index,
+            asset: asset.to_string(),
+            side,
+            size: 10.0,
+            price: 0.30,
+            timestamp: 1_000,
+        }
+    }
+
+    const C1: &str = "0xC1";
+
+    #[test]
+    fn token_id_decides_the_outcome() {
+        let positions = [position("yes", "no", 0)];
+        let pairs = TokenPairs::from_positions(&positions);
+
+        // Correct report (condition compared case-insensitively)
+        let (trade, issues) =
+            normalize(raw("yes", C1, Some(0), OrderSide::Buy), &pairs, &positions);
+        assert!(issues.is_empty());
+        assert_eq!(trade.unwrap().share_delta(), 10.0);
+
+        // Wrong outcome index for the token: corrected
+        let (trade, issues) =
+            normalize(raw("no", C1, Some(0), OrderSide::Sell), &pairs, &positions);
+        assert_eq!(issues, vec![TradeIssue::OutcomeCorrected { reported: 0 }]);
+        assert_eq!(trade.unwrap().outcome_index, 1);
+
+        // On-chain fills name only the token: condition and outcome from it
+        let (trade, issues) = normalize(raw("no", "", None, OrderSide::Sell), &pairs, &positions);
+        let trade = trade.unwrap();
+        assert!(issues.is_empty());
+        assert_eq!(
+            (trade.condition_id.as_str(), trade.outcome_index),
+            ("0xc1", 1)
+        );
+
+        // Token from another market: rejected
+        let (trade, issues) = normalize(
+            raw("yes", "0xc2", Some(0), OrderSide::Buy),
+            &pairs,
+            &positions,
+        );
+        assert!(trade.is_none() && issues[0].is_fatal());
+
+        // Unknown token without an outcome index: rejected
+        let (trade, issues) = normalize(
+            raw("other", "0xc3", None, OrderSide::Buy),
+            &pairs,
+            &positions,
+        );
+        assert!(trade.is_none());
+        assert_eq!(issues, vec![TradeIssue::UnknownOutcome]);
+    }
+
+    #[test]
+    fn buy_of_the_unheld_side_flips_to_a_sell_of_the_held_token() {
+        const YES: &str =
+            "71321045679252212594626385532706912750332728571942532289631379312455583992563";
+        const NO: &str =
+            "52114319501245915516055106046884209969926127482827954674443846427813813222426";
+        let positions = [position(YES, NO, 0)];
+        let pairs = TokenPairs::from_positions(&positions);
+
+        // BUY NO @ 0.30 while holding only YES: the trader sold YES @ 0.70
+        let (trade, issues) = normalize(raw(NO, C1, Some(1), OrderSide::Buy), &pairs, &positions);
+        assert_eq!(issues, vec![TradeIssue::OppositeToken]);
+        let trade = trade.unwrap();
+        assert_eq!((trade.asset.as_str(), trade.outcome_index), (YES, 0));
+        assert_eq!(trade.side, OrderSide::Sell);
+        assert!((trade.price - 0.70).abs() < 1e-9);
+        assert_eq!(trade.share_delta(), -10.0);
+
+        // Buys of the held token stand as reported
+        let (trade, issues) = normalize(raw(YES, C1, Some(0), OrderSide::Buy), &pairs, &positions);
+        assert!(issues.is_empty());
+        assert_eq!(trade.unwrap().share_delta(), 10.0);
+
+        // Sells of a token the trader no longer holds are ordinary exits
+        let (trade, issues) = normalize(raw(NO, C1, Some(1), OrderSide::Sell), &pairs, &positions);
+        assert!(issues.is_empty());
+        assert_eq!(trade.unwrap().asset, NO);
+    }
+
+    fn activity(kind: &str, hash: &str) -> Activity {
+        serde_json::from_value(json!({
+            "proxyWallet": "0x0000000000000000000000000000000000000001",
+            "timestamp": 1_700_000_000,
+            "conditionId": "0xc1",
+            "type": kind,
+            "size": "10",
+            "usdcSize": "5",
+            "transactionHash": hash,
+            "price": "0.5",
+            "asset": "a1",
+            "side": "BUY",
+            "outcomeIndex": 0
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn activity_events_cover_non_trade_changes() {
+        let Some(ActivityEvent::Trade(trade)) =
+            ActivityEvent::from_activity(&activity("TRADE", "0xt"))
+        else {
+            panic!("expected a trade");
+        };
+        assert_eq!(trade.asset, "a1");
+        assert_eq!(trade.side, OrderSide::Buy);
+        assert!((trade.price - 0.5).abs() < 1e-9);
+
+        let merge = ActivityEvent::from_activity(&activity("MERGE", "0xm")).unwrap();
+        assert_eq!(
+            merge,
+            ActivityEvent::PositionChange(TraderPositionChange {
+                tx_hash: "0xm".to_string(),
+                condition_id: "0xc1".to_string(),
+                kind: PositionChangeKind::Merge,
+                size: 10.0,
+                timestamp: 1_700_000_000,
+            })
+        );
+        assert_eq!(merge.tx_hash(), "0xm");
+        assert!(ActivityEvent::from_activity(&activity("REWARD", "0xr")).is_none());
+    }
+}
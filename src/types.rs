@@ -1,7 +1,17 @@
-use serde::Serialize;
+use std::collections::BTreeMap;
+
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+use crate::consensus::{DetectionSource, SourceSummary};
+use crate::graduation::GraduationStatus;
+use crate::intent::TradeCluster;
+use crate::latency::LatencySummary;
+use crate::money;
+use crate::shadow::ShadowSummary;
 
 /// Lightweight identifier for a market outcome, extracted from SDK Position.
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MarketPosition {
     pub condition_id: String,
     pub asset: String,
@@ -9,6 +19,12 @@ pub struct MarketPosition {
     pub outcome: String,
     pub outcome_index: i32,
     pub event_slug: String,
+    /// User-defined tags from `[engine.tags]`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Token ID of the other outcome of the binary market (empty when unknown).
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub opposite_asset: String,
 }
 
 /// Target allocation for one market position.
@@ -16,37 +32,105 @@ pub struct MarketPosition {
 pub struct TargetAllocation {
     pub market: MarketPosition,
     pub trader_weight: f64,
-    pub target_value_usd: f64,
-    pub target_shares: f64,
-    pub cur_price: f64,
+    #[serde(with = "rust_decimal::serde::str")]
+    pub target_value_usd: Decimal,
+    #[serde(with = "rust_decimal::serde::str")]
+    pub target_shares: Decimal,
+    #[serde(with = "rust_decimal::serde::str")]
+    pub cur_price: Decimal,
+    /// The trader's average entry price for this position, as reported with
+    /// their positions (`None` when unknown).
+    #[serde(
+        with = "rust_decimal::serde::str_option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub trader_entry: Option<Decimal>,
+}
+
+impl TargetAllocation {
+    /// How far the current price is above the trader's entry, as a fraction
+    /// (0.10 = 10% above; negative when below). `None` without a usable entry.
+    pub fn entry_premium(&self) -> Option<f64> {
+        self.trader_entry
+            .filter(|entry| *entry > Decimal::ZERO)
+            .map(|entry| money::to_f64(self.cur_price / entry) - 1.0)
+    }
 }
 
 /// Order direction.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum OrderSide {
     Buy,
     Sell,
 }
 
 /// A simulated order generated by the rebalancing engine.
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SimulatedOrder {
     pub market: MarketPosition,
     pub side: OrderSide,
-    pub shares: f64,
-    pub price: f64,
-    pub cost_usd: f64,
+    #[serde(with = "rust_decimal::serde::str")]
+    pub shares: Decimal,
+    #[serde(with = "rust_decimal::serde::str")]
+    pub price: Decimal,
+    #[serde(with = "rust_decimal::serde::str")]
+    pub cost_usd: Decimal,
+}
+
+/// A buy the engine planned but held back this cycle, with the reason (see
+/// `engine::compute_orders_with_skips`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SkippedOrder {
+    pub order: SimulatedOrder,
+    pub reason: String,
+}
+
+/// An order recorded as submitted before it was posted (`Mutation::SubmitOrders`),
+/// pending until its execution result is booked.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PendingOrder {
+    pub order: SimulatedOrder,
+    /// When it was submitted (unix seconds).
+    pub submitted_at: i64,
 }
 
 /// A position we currently hold (tracked in TradingState).
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HeldPosition {
     pub asset: String,
     pub title: String,
     pub outcome: String,
-    pub shares: f64,
-    pub total_cost: f64,
-    pub avg_cost: f64,
+    #[serde(with = "rust_decimal::serde::str")]
+    pub shares: Decimal,
+    #[serde(with = "rust_decimal::serde::str")]
+    pub total_cost: Decimal,
+    #[serde(with = "rust_decimal::serde::str")]
+    pub avg_cost: Decimal,
+    /// Token ID of the other outcome of the binary market (empty when unknown).
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub opposite_asset: String,
+}
+
+/// One fill applied to the state, for the end-of-run ledger.
+#[derive(Debug, Clone, Serialize)]
+pub struct LedgerEntry {
+    /// When the fill was applied (RFC 3339); replayed fills get the replay time.
+    pub timestamp: String,
+    pub asset: String,
+    pub title: String,
+    pub outcome: String,
+    pub side: OrderSide,
+    #[serde(with = "rust_decimal::serde::str")]
+    pub shares: Decimal,
+    #[serde(with = "rust_decimal::serde::str")]
+    pub price: Decimal,
+    #[serde(with = "rust_decimal::serde::str")]
+    pub cost_usd: Decimal,
+    /// P&L realized by a sell against the average cost (0 for buys).
+    #[serde(with = "rust_decimal::serde::str")]
+    pub realized_pnl: Decimal,
+    /// The market's tags, when known (see `TradingState::asset_tags`).
+    pub tags: Vec<String>,
 }
 
 /// What triggered a copytrade event.
@@ -54,10 +138,26 @@ pub struct HeldPosition {
 pub enum EventTrigger {
     InitialReplication,
     TradeDetected,
+    /// Forced rebalance after a suspected gap in the trade stream.
+    GapDetected,
+    /// A `[risk]` stop-loss or take-profit sold holdings on its own.
+    RiskExit,
+    /// The `[risk] max_drawdown_pct` kill switch tripped (orders: the
+    /// liquidation, if enabled).
+    KillSwitch,
+    /// A `--overrides` file forced a rebalance without new trades.
+    ManualOverride,
+    /// A `--ramp-in` step raised the share of the targets to deploy.
+    RampIn,
+    /// A split, merge, redemption or conversion by the trader, seen with
+    /// `settings.activity_detection`.
+    PositionChange,
+    /// The next slice of an `[executor.twap]` schedule came due without new trades.
+    TwapDue,
 }
 
 /// Status of a live order execution.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ExecutionStatus {
     Filled,
     PartialFill,
@@ -67,42 +167,242 @@ pub enum ExecutionStatus {
 }
 
 /// A resting order on the CLOB book that hasn't filled yet.
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RestingOrder {
     pub order_id: String,
     pub asset: String,
     pub title: String,
     pub outcome: String,
     pub side: OrderSide,
-    pub shares: f64,
-    pub price: f64,
-    pub cost_usd: f64,
+    #[serde(with = "rust_decimal::serde::str")]
+    pub shares: Decimal,
+    #[serde(with = "rust_decimal::serde::str")]
+    pub price: Decimal,
+    #[serde(with = "rust_decimal::serde::str")]
+    pub cost_usd: Decimal,
+    /// Carried to the holding when the order fills.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub opposite_asset: String,
+    /// When the order started resting (unix ms), for `resting_ttl_secs`. Set
+    /// when the execution result is applied, so a WAL replay restarts the age.
+    #[serde(default)]
+    pub placed_at_ms: i64,
+}
+
+impl RestingOrder {
+    /// Whether the order has rested longer than `ttl_secs` as of `now_ms`.
+    pub fn is_expired(&self, ttl_secs: u64, now_ms: i64) -> bool {
+        now_ms - self.placed_at_ms > (ttl_secs as i64).saturating_mul(1000)
+    }
+}
+
+/// A large order being posted one slice per poll cycle (`[executor.twap]`,
+/// see `executor::schedule_twap`).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TwapSchedule {
+    pub side: OrderSide,
+    #[serde(with = "rust_decimal::serde::str")]
+    pub slice_shares: Decimal,
+    /// Slices still to post.
+    pub remaining: u32,
+    /// Earliest time for the next slice (unix ms).
+    pub next_at_ms: i64,
 }
 
 /// Result of executing a single order on the CLOB.
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ExecutionResult {
     pub order_index: usize,
     pub status: ExecutionStatus,
     pub order_id: String,
-    pub filled_shares: f64,
-    pub filled_cost_usd: f64,
+    #[serde(with = "rust_decimal::serde::str")]
+    pub filled_shares: Decimal,
+    #[serde(with = "rust_decimal::serde::str")]
+    pub filled_cost_usd: Decimal,
     pub error_msg: Option<String>,
 }
 
+/// A detected trade of the copied trader, keyed by the canonical market outcome
+/// `(condition_id, outcome_index)` (see `trades::normalize`).
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TraderTrade {
+    pub tx_hash: String,
+    pub condition_id: String,
+    pub outcome_index: i32,
+    /// Token ID of `outcome_index`.
+    pub asset: String,
+    pub side: OrderSide,
+    pub size: f64,
+    pub price: f64,
+    /// Exchange timestamp, in seconds.
+    pub timestamp: i64,
+}
+
+impl TraderTrade {
+    /// Signed change in the trader's shares of the outcome.
+    pub fn share_delta(&self) -> f64 {
+        match self.side {
+            OrderSide::Buy => self.size,
+            OrderSide::Sell => -self.size,
+        }
+    }
+}
+
+/// How a `TraderPositionChange` moved the trader's tokens.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PositionChangeKind {
+    /// USDC split into one share of each outcome.
+    Split,
+    /// One share of each outcome merged back into USDC.
+    Merge,
+    /// Resolved tokens redeemed for their payout.
+    Redeem,
+    /// Neg-risk NO shares converted into YES shares of the event's other
+    /// markets plus USDC.
+    Conversion,
+}
+
+impl std::fmt::Display for PositionChangeKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PositionChangeKind::Split => write!(f, "split"),
+            PositionChangeKind::Merge => write!(f, "merge"),
+            PositionChangeKind::Redeem => write!(f, "redemption"),
+            PositionChangeKind::Conversion => write!(f, "conversion"),
+        }
+    }
+}
+
+/// A change to the copied trader's positions that isn't a trade (from the
+/// `/activity` endpoint). Not copied as such: see
+/// `engine::complete_sets` for how splits and merges are kept out of sizing.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TraderPositionChange {
+    pub tx_hash: String,
+    pub condition_id: String,
+    pub kind: PositionChangeKind,
+    /// Shares (sets, for splits and merges) involved.
+    pub size: f64,
+    /// Exchange timestamp, in seconds.
+    pub timestamp: i64,
+}
+
+/// Which code and settings produced an event or exit summary.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct BuildInfo {
+    /// Crate version.
+    pub version: &'static str,
+    /// Short git commit hash of the build (`"unknown"` outside a git checkout).
+    pub git_hash: &'static str,
+    /// `AppConfig::hash` of the effective config.
+    pub config_hash: String,
+}
+
+impl BuildInfo {
+    /// This build, running with the config whose hash is `config_hash`.
+    pub fn new(config_hash: String) -> Self {
+        Self {
+            version: env!("CARGO_PKG_VERSION"),
+            git_hash: env!("COPYTRADE_GIT_HASH"),
+            config_hash,
+        }
+    }
+}
+
 /// Per-event JSON log entry emitted to stdout.
 #[derive(Debug, Clone, Serialize)]
 pub struct CopytradeEvent {
     pub timestamp: String,
+    pub build: BuildInfo,
+    /// `[executor] strategy_id`, for attributing the orders below.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub strategy_id: Option<String>,
     pub trigger: EventTrigger,
     pub detected_trade_hashes: Vec<String>,
+    /// Exchange timestamps (unix seconds) of the detected trades, same order as the hashes.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub detected_trade_timestamps: Vec<i64>,
+    /// The detected trades that passed the consistency checks, normalized.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub trader_trades: Vec<TraderTrade>,
+    /// The trader's clusters of rapid trades on the markets traded above, each
+    /// read as one intent (see `intent::IntentTracker`).
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub trader_intents: Vec<TradeCluster>,
+    /// The trader's detected splits, merges, redemptions and conversions.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub position_changes: Vec<TraderPositionChange>,
+    /// Seconds from the earliest detected trade to our fill (or simulated apply).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub copy_latency_secs: Option<f64>,
     pub orders: Vec<SimulatedOrder>,
+    /// Buys the engine held back this cycle (`max_open_positions`, re-buy
+    /// cooldowns), with the reason.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub skipped_orders: Vec<SkippedOrder>,
     pub budget_remaining: f64,
     pub total_spent: f64,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub execution_results: Option<Vec<ExecutionResult>>,
 }
 
+/// Severity of an `AlertEvent`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertSeverity {
+    Info,
+    Warning,
+    Critical,
+}
+
+/// What an `AlertEvent` is about.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertKind {
+    /// The executor stopped a batch after too many consecutive order failures.
+    ExecutorHalted,
+    /// Total P&L crossed a `[milestones] pnl_step_pct` milestone.
+    PnlMilestone,
+    /// A holding's P&L moved past `[milestones] position_move_pct`.
+    PositionMove,
+    /// Buys are paused by `[risk] max_daily_loss_usd` (once, when the pause
+    /// starts).
+    DailyLossPause,
+    /// `[settings] poll_error_alert_threshold` consecutive poll cycles failed
+    /// (repeated while failures continue; info once they recover).
+    PollFailures,
+}
+
+/// Operational alert emitted to stdout as its own JSON line.
+#[derive(Debug, Clone, Serialize)]
+pub struct AlertEvent {
+    pub timestamp: String,
+    pub severity: AlertSeverity,
+    pub kind: AlertKind,
+    pub message: String,
+}
+
+/// Whether orders are simulated or placed on the CLOB.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TradingMode {
+    DryRun,
+    Live,
+}
+
+/// Emitted as its own JSON line when a session switches mode (`--graduate`).
+#[derive(Debug, Clone, Serialize)]
+pub struct ModeTransitionEvent {
+    pub timestamp: String,
+    pub from: TradingMode,
+    pub to: TradingMode,
+    /// The paper session's standing against the graduation criteria.
+    pub graduation: GraduationStatus,
+    /// Simulated state at the switch; the live session starts fresh.
+    pub paper_summary: ExitSummary,
+}
+
 /// Per-position summary in the exit report.
 #[derive(Debug, Clone, Serialize)]
 pub struct HoldingSummary {
@@ -114,6 +414,58 @@ pub struct HoldingSummary {
     pub cur_price: f64,
     pub current_value: f64,
     pub unrealized_pnl: f64,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+}
+
+/// Binary outcome exposure of one held position, for the resolution-risk report.
+#[derive(Debug, Clone, Serialize)]
+pub struct ResolutionRisk {
+    pub asset: String,
+    pub title: String,
+    pub outcome: String,
+    /// Market end date (RFC 3339), if known.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end_date: Option<String>,
+    /// Hours until the end date (negative once past, while awaiting resolution).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hours_to_resolution: Option<f64>,
+    pub shares: f64,
+    pub cur_price: f64,
+    pub cost_basis: f64,
+    /// P&L if the outcome resolves NO (shares worth $0).
+    pub pnl_at_0: f64,
+    /// P&L if the outcome resolves YES (shares worth $1).
+    pub pnl_at_1: f64,
+}
+
+/// One tracked resting order, for the health report and the exit summary.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RestingOrderStatus {
+    pub order_id: String,
+    pub asset: String,
+    pub title: String,
+    pub side: OrderSide,
+    pub shares: f64,
+    pub price: f64,
+    /// Seconds since the order started resting (restarts on a WAL replay).
+    pub age_secs: i64,
+    /// Latest price of the token, if known.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cur_price: Option<f64>,
+    /// How far the market is from the limit, against the order: the price
+    /// above a buy's limit, or below a sell's. Positive means it can't fill at
+    /// the current price.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub distance: Option<f64>,
+}
+
+/// Holdings grouped under one tag in the exit report.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TagSummary {
+    pub positions: usize,
+    pub current_value: f64,
+    pub unrealized_pnl: f64,
 }
 
 /// Exit summary emitted on shutdown.
@@ -121,6 +473,8 @@ pub struct HoldingSummary {
 pub struct ExitSummary {
     pub initial_budget: f64,
     pub budget_remaining: f64,
+    /// Realized profit locked out of the working budget (`[risk] lock_profit_usd`).
+    pub locked_profit: f64,
     pub total_spent: f64,
     pub total_sell_proceeds: f64,
     pub realized_pnl: f64,
@@ -132,4 +486,26 @@ pub struct ExitSummary {
     pub total_buy_orders: u64,
     pub total_sell_orders: u64,
     pub holdings: Vec<HoldingSummary>,
+    /// The build and config of the session (omitted outside a copytrade run).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub build: Option<BuildInfo>,
+    /// Holdings grouped by tag (omitted when no holding is tagged).
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub by_tag: BTreeMap<String, TagSummary>,
+    /// Holdings sorted by time to resolution (unknown end dates last).
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub resolution_risk: Vec<ResolutionRisk>,
+    /// Tracked resting orders, oldest first.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub resting_orders: Vec<RestingOrderStatus>,
+    /// Copy latency distribution over the session (omitted when nothing was copied).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub copy_latency: Option<LatencySummary>,
+    /// Per-source trade detection counts, wins, and latency (omitted when
+    /// nothing was detected).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detection: Option<BTreeMap<DetectionSource, SourceSummary>>,
+    /// Live vs simulated execution divergence (`--shadow` only).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub shadow: Option<ShadowSummary>,
 }
@@ -0,0 +1,527 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, NaiveDate, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+use crate::graduation::PaperSample;
+use crate::types::{ExecutionResult, HeldPosition, PendingOrder, SimulatedOrder, TwapSchedule};
+
+/// A `TradingState` mutation recorded in the write-ahead log.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Mutation {
+    /// Holding seeded from the Safe wallet at live startup.
+    SeedHolding { position: HeldPosition },
+    /// Simulated (dry-run) orders applied in full.
+    ApplyOrders { orders: Vec<SimulatedOrder> },
+    /// Live orders about to be posted in order cycle `cycle`, by client order
+    /// ID, so a restart can tell what may have reached the CLOB.
+    SubmitOrders {
+        cycle: u64,
+        orders: BTreeMap<String, PendingOrder>,
+    },
+    /// Live execution results for a batch of orders.
+    ApplyExecutionResults {
+        orders: Vec<SimulatedOrder>,
+        results: Vec<ExecutionResult>,
+        /// Client order IDs of the `SubmitOrders` entries these results settle.
+        #[serde(default)]
+        client_order_ids: Vec<String>,
+    },
+    /// A tracked resting order filled (fully, or partially before cancellation).
+    RestingFill {
+        order_id: String,
+        #[serde(with = "rust_decimal::serde::str")]
+        filled_shares: Decimal,
+        #[serde(with = "rust_decimal::serde::str")]
+        fill_price: Decimal,
+    },
+    /// A tracked resting order was cancelled without filling.
+    RestingCancel { order_id: String },
+    /// `shares` complete sets (one share of each outcome of a market) merged
+    /// back into USDC at $1 each.
+    MergeSets {
+        asset: String,
+        opposite_asset: String,
+        #[serde(with = "rust_decimal::serde::str")]
+        shares: Decimal,
+    },
+    /// Asset blacklisted from re-buys after a risk exit, until `until_ms` (unix ms).
+    RebuyCooldown { asset: String, until_ms: i64 },
+    /// Buys paused by the daily loss limit until `until_ms` (unix ms).
+    DailyLossPause { until_ms: i64 },
+    /// Daily P&L baseline: the effective capital first measured on UTC `day`.
+    DayStart { day: NaiveDate, capital: f64 },
+    /// Budget re-based (e.g. a percentage of wallet value re-measured at startup).
+    RebaseBudget {
+        #[serde(with = "rust_decimal::serde::str")]
+        budget: Decimal,
+    },
+    /// New high of the effective capital, the drawdown kill switch's peak.
+    PeakCapital { capital: f64 },
+    /// The drawdown kill switch tripped at `drawdown_pct` below peak capital.
+    KillSwitch { drawdown_pct: f64 },
+    /// Realized profit moved out of the working budget.
+    LockProfit {
+        #[serde(with = "rust_decimal::serde::str")]
+        amount: Decimal,
+    },
+    /// Asset pinned (never sold automatically) or unpinned.
+    Pin { asset: String, pinned: bool },
+    /// Asset excluded (sold, never re-entered) or re-admitted.
+    Exclude { asset: String, excluded: bool },
+    /// TWAP schedule of `asset` started or advanced, or ended (`None`).
+    Twap {
+        asset: String,
+        schedule: Option<TwapSchedule>,
+    },
+    /// A `--graduate` paper session started tracking its criteria.
+    PaperStart { started_at: DateTime<Utc> },
+    /// Paper-trading measurements after a dry-run rebalancing cycle.
+    PaperSample { sample: PaperSample },
+}
+
+/// Schema version of the records written by this build, stored in the header.
+///
+/// Bump it when a change to `WalRecord` or `Mutation` would make older logs
+/// read back wrong (a renamed field, a changed unit), and add the step that
+/// rewrites records of the previous version to `upgrade_record`. Additive
+/// changes with a serde default need no bump.
+pub const SCHEMA_VERSION: u32 = 2;
+
+/// One line of the log file.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum WalRecord {
+    /// First record of every log — pins the budget the log was written against,
+    /// and the schema version of the records after it (0 for logs from before
+    /// versioning).
+    Header {
+        #[serde(default)]
+        version: u32,
+        initial_budget: f64,
+    },
+    /// Written (and synced) before a mutation is applied.
+    Intent { seq: u64, mutation: Mutation },
+    /// Written after the mutation with the same `seq` has been applied.
+    Ack { seq: u64 },
+    /// A reported event, kept for auditing; not replayed.
+    Event { event: serde_json::Value },
+}
+
+/// A mutation read back from an existing log.
+#[derive(Debug, Clone)]
+pub struct WalEntry {
+    pub seq: u64,
+    pub mutation: Mutation,
+    /// `false` if the process stopped between writing the intent and applying it.
+    pub acked: bool,
+}
+
+/// Append-only JSON-lines write-ahead log for `TradingState` mutations.
+///
+/// Every mutation is written as an `intent` record and synced to disk before it is
+/// applied, then acknowledged with an `ack` record. Replaying the log in order on
+/// restart rebuilds the state, including intents that never got acknowledged.
+pub struct Wal {
+    file: File,
+    next_seq: u64,
+}
+
+impl Wal {
+    /// Open (or create) the log at `path`, returning it with all existing entries.
+    ///
+    /// Errors if an existing log was written against a different `initial_budget`.
+    /// A truncated final line (crash mid-write) is ignored.
+    pub fn open(path: &Path, initial_budget: f64) -> Result<(Self, Vec<WalEntry>)> {
+        let (entries, has_header, valid_len) = Self::load(path, initial_budget)?;
+
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("failed to open {}", path.display()))?;
+        // Drop a partially written trailing record so new appends start on a clean line
+        file.set_len(valid_len)
+            .with_context(|| format!("failed to truncate {}", path.display()))?;
+        let next_seq = entries.iter().map(|e| e.seq + 1).max().unwrap_or(0);
+        let mut wal = Self { file, next_seq };
+        if !has_header {
+            wal.write(&WalRecord::Header {
+                version: SCHEMA_VERSION,
+                initial_budget,
+            })?;
+        }
+        Ok((wal, entries))
+    }
+
+    /// Read the entries of an existing log without opening it for writing.
+    ///
+    /// Used by a hot standby to follow the leader's log. Returns no entries if the
+    /// file doesn't exist yet.
+    pub fn read_entries(path: &Path, initial_budget: f64) -> Result<Vec<WalEntry>> {
+        Ok(Self::load(path, initial_budget)?.0)
+    }
+
+    /// Budget pinned by the header of the log at `path`, if it exists and has one.
+    ///
+    /// A percentage budget isn't known until the wallet is valued, so its log is
+    /// opened against the header budget and re-based with `Mutation::RebaseBudget`.
+    pub fn header_budget(path: &Path) -> Result<Option<f64>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        let first = contents.lines().find(|line| !line.trim().is_empty());
+        Ok(match first.map(serde_json::from_str::<WalRecord>) {
+            Some(Ok(WalRecord::Header { initial_budget, .. })) => Some(initial_budget),
+            _ => None,
+        })
+    }
+
+    /// Parse the log at `path`, returning its entries, whether it has a header, and
+    /// the byte length of the well-formed prefix.
+    fn load(path: &Path, initial_budget: f64) -> Result<(Vec<WalEntry>, bool, u64)> {
+        let mut entries: Vec<WalEntry> = Vec::new();
+        // Sequence number → index in `entries`, to mark acks in constant time
+        let mut positions: HashMap<u64, usize> = HashMap::new();
+        let mut has_header = false;
+        let mut valid_len: u64 = 0;
+
+        if path.exists() {
+            let contents = std::fs::read_to_string(path)
+                .with_context(|| format!("failed to read {}", path.display()))?;
+            let lines: Vec<&str> = contents.split_inclusive('\n').collect();
+            for (idx, line) in lines.iter().enumerate() {
+                if line.trim().is_empty() {
+                    valid_len += line.len() as u64;
+                    continue;
+                }
+                let record: WalRecord = match serde_json::from_str(line) {
+                    Ok(r) => r,
+                    Err(_) if idx + 1 == lines.len() => break,
+                    Err(e) => {
+                        return Err(e).with_context(|| {
+                            format!("corrupt WAL record at {}:{}", path.display(), idx + 1)
+                        });
+                    }
+                };
+                valid_len += line.len() as u64;
+                match record {
+                    WalRecord::Header {
+                        version,
+                        initial_budget: b,
+                    } => {
+                        check_version(path, version)?;
+                        if (b - initial_budget).abs() > 1e-9 {
+                            anyhow::bail!(
+                                "WAL {} was written with budget {b}, but budget is {initial_budget}",
+                                path.display()
+                            );
+                        }
+                        has_header = true;
+                    }
+                    WalRecord::Intent { seq, mutation } => {
+                        positions.entry(seq).or_insert(entries.len());
+                        entries.push(WalEntry {
+                            seq,
+                            mutation,
+                            acked: false,
+                        });
+                    }
+                    WalRecord::Ack { seq } => {
+                        if let Some(&pos) = positions.get(&seq) {
+                            entries[pos].acked = true;
+                        }
+                    }
+                    WalRecord::Event { .. } => {}
+                }
+            }
+        }
+        Ok((entries, has_header, valid_len))
+    }
+
+    /// Durably record the intent to apply `mutation`, returning its sequence number.
+    pub fn append_intent(&mut self, mutation: &Mutation) -> Result<u64> {
+        let seq = self.next_seq;
+        self.write(&WalRecord::Intent {
+            seq,
+            mutation: mutation.clone(),
+        })?;
+        self.next_seq += 1;
+        Ok(seq)
+    }
+
+    /// Mark the mutation with sequence number `seq` as applied.
+    pub fn ack(&mut self, seq: u64) -> Result<()> {
+        self.write(&WalRecord::Ack { seq })
+    }
+
+    /// Append a reported event to the log. Events are skipped on replay.
+    pub fn append_event<T: Serialize>(&mut self, event: &T) -> Result<()> {
+        let event = serde_json::to_value(event).context("failed to serialize event")?;
+        self.write(&WalRecord::Event { event })
+    }
+
+    /// Upgrade the log at `path` to `SCHEMA_VERSION` in place (`copytrade migrate`),
+    /// keeping the original as `<path>.v<old version>.bak`. Returns the version it
+    /// was at; a log that is already current is left untouched.
+    pub fn migrate(path: &Path) -> Result<u32> {
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        let mut records = Vec::new();
+        for (idx, line) in contents.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let record: serde_json::Value = serde_json::from_str(line)
+                .with_context(|| format!("corrupt WAL record at {}:{}", path.display(), idx + 1))?;
+            records.push(record);
+        }
+        let header = records
+            .first()
+            .filter(|r| r["type"] == "header")
+            .with_context(|| format!("{} has no WAL header", path.display()))?;
+        let from = header["version"].as_u64().unwrap_or(0) as u32;
+        if from > SCHEMA_VERSION {
+            check_version(path, from)?;
+        }
+        if from == SCHEMA_VERSION {
+            return Ok(from);
+        }
+
+        for version in from..SCHEMA_VERSION {
+            for record in &mut records {
+                upgrade_record(version, record);
+            }
+        }
+        records[0]["version"] = SCHEMA_VERSION.into();
+        let mut upgraded = String::new();
+        for record in &records {
+            upgraded.push_str(
+                &serde_json::to_string(record).context("failed to serialize WAL record")?,
+            );
+            upgraded.push('\n');
+        }
+
+        let mut backup = path.as_os_str().to_os_string();
+        backup.push(format!(".v{from}.bak"));
+        std::fs::copy(path, &backup)
+            .with_context(|| format!("failed to back up {}", path.display()))?;
+        let mut staged = path.as_os_str().to_os_string();
+        staged.push(".migrating");
+        std::fs::write(&staged, upgraded)
+            .with_context(|| format!("failed to write {}", Path::new(&staged).display()))?;
+        std::fs::rename(&staged, path)
+            .with_context(|| format!("failed to replace {}", path.display()))?;
+        Ok(from)
+    }
+
+    fn write(&mut self, record: &WalRecord) -> Result<()> {
+        let mut line = serde_json::to_string(record).context("failed to serialize WAL record")?;
+        line.push('\n');
+        self.file
+            .write_all(line.as_bytes())
+            .context("failed to append WAL record")?;
+        self.file.sync_data().context("failed to sync WAL")?;
+        Ok(())
+    }
+}
+
+/// Refuse a log whose records this build can't read as written.
+fn check_version(path: &Path, version: u32) -> Result<()> {
+    if version < SCHEMA_VERSION {
+        anyhow::bail!(
+            "WAL {} uses state schema v{version}, older than this build's v{SCHEMA_VERSION}; \
+             upgrade it with `copytrade migrate --wal {}` (the original is kept as a backup)",
+            path.display(),
+            path.display()
+        );
+    }
+    if version > SCHEMA_VERSION {
+        anyhow::bail!(
+            "WAL {} uses state schema v{version}, newer than this build's v{SCHEMA_VERSION}; \
+             run the newer build, or start a new WAL",
+            path.display()
+        );
+    }
+    Ok(())
+}
+
+/// Rewrite one record (as JSON) from schema `version` to `version + 1`.
+pub(crate) fn upgrade_record(version: u32, record: &mut serde_json::Value) {
+    match version {
+        // v1 only added the header version; records are unchanged
+        0 => {}
+        // v2 writes `Decimal` fields as strings instead of floats
+        1 => {
+            if record["type"] == "intent" {
+                decimals_to_strings(&mut record["mutation"]);
+            }
+        }
+        _ => unreachable!("no upgrade from WAL schema v{version}"),
+    }
+}
+
+/// `Decimal` fields of `Mutation` and the types it carries, by name. None of
+/// them is also the name of an `f64` field there.
+const DECIMAL_FIELDS: [&str; 10] = [
+    "shares",
+    "price",
+    "cost_usd",
+    "total_cost",
+    "avg_cost",
+    "filled_shares",
+    "filled_cost_usd",
+    "fill_price",
+    "budget",
+    "amount",
+];
+
+/// Rewrite the `DECIMAL_FIELDS` of a mutation (as JSON) written as numbers to
+/// the strings `rust_decimal::serde::str` reads. Fields that already are
+/// strings are left alone, so a partly upgraded value is safe to pass again.
+pub(crate) fn decimals_to_strings(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::Object(fields) => {
+            for (name, field) in fields.iter_mut() {
+                if DECIMAL_FIELDS.contains(&name.as_str())
+                    && let Some(number) = field.as_f64()
+                {
+                    *field = number.to_string().into();
+                } else {
+                    decimals_to_strings(field);
+                }
+            }
+        }
+        serde_json::Value::Array(items) => items.iter_mut().for_each(decimals_to_strings),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_path(name: &str) -> std::path::PathBuf {
+        let path =
+            std::env::temp_dir().join(format!("copytrade-wal-{name}-{}.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        path
+    }
+
+    fn cancel(order_id: &str) -> Mutation {
+        Mutation::RestingCancel {
+            order_id: order_id.to_string(),
+        }
+    }
+
+    #[test]
+    fn roundtrip_tracks_acks() {
+        let path = temp_path("roundtrip");
+        {
+            let (mut wal, entries) = Wal::open(&path, 100.0).unwrap();
+            assert!(entries.is_empty());
+            let s0 = wal.append_intent(&cancel("o1")).unwrap();
+            wal.ack(s0).unwrap();
+            wal.append_intent(&cancel("o2")).unwrap(); // crash before ack
+        }
+        let (mut wal, entries) = Wal::open(&path, 100.0).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert!(entries[0].acked);
+        assert!(!entries[1].acked);
+        // Sequence numbers continue after the existing entries
+        assert_eq!(wal.append_intent(&cancel("o3")).unwrap(), 2);
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn read_entries_is_read_only() {
+        let path = temp_path("readonly");
+        assert!(Wal::read_entries(&path, 100.0).unwrap().is_empty());
+        assert!(!path.exists());
+        {
+            let (mut wal, _) = Wal::open(&path, 100.0).unwrap();
+            wal.append_intent(&cancel("o1")).unwrap();
+        }
+        let len = std::fs::metadata(&path).unwrap().len();
+        let entries = Wal::read_entries(&path, 100.0).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(std::fs::metadata(&path).unwrap().len(), len);
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn budget_mismatch_errors() {
+        let path = temp_path("budget");
+        assert_eq!(Wal::header_budget(&path).unwrap(), None);
+        drop(Wal::open(&path, 100.0).unwrap());
+        assert!(Wal::open(&path, 200.0).is_err());
+        assert_eq!(Wal::header_budget(&path).unwrap(), Some(100.0));
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn old_schema_refused_until_migrated() {
+        let path = temp_path("schema");
+        // A log from before versioning: no version in the header
+        let unversioned = [
+            r#"{"type":"header","initial_budget":100.0}"#,
+            r#"{"type":"intent","seq":0,"mutation":{"kind":"rebase_budget","budget":50.5}}"#,
+            r#"{"type":"ack","seq":0}"#,
+        ];
+        std::fs::write(&path, unversioned.join("\n") + "\n").unwrap();
+        let err = Wal::open(&path, 100.0).err().unwrap().to_string();
+        assert!(
+            err.contains("schema v0") && err.contains("copytrade migrate"),
+            "{err}"
+        );
+
+        assert_eq!(Wal::migrate(&path).unwrap(), 0);
+        let (_, entries) = Wal::open(&path, 100.0).unwrap();
+        assert_eq!(entries.len(), 1);
+        // v2 reads the float written by v1 back as a decimal string
+        assert!(matches!(
+            entries[0].mutation,
+            Mutation::RebaseBudget { budget } if budget == Decimal::new(505, 1)
+        ));
+        assert_eq!(Wal::migrate(&path).unwrap(), SCHEMA_VERSION);
+        let mut backup = path.as_os_str().to_os_string();
+        backup.push(".v0.bak");
+        let _ = std::fs::remove_file(&backup);
+
+        let newer = format!(
+            "{{\"type\":\"header\",\"version\":{},\"initial_budget\":100.0}}\n",
+            SCHEMA_VERSION + 1
+        );
+        std::fs::write(&path, newer).unwrap();
+        let err = Wal::open(&path, 100.0).err().unwrap().to_string();
+        assert!(err.contains("newer than this build"), "{err}");
+        assert!(Wal::migrate(&path).is_err());
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn truncated_last_line_ignored() {
+        let path = temp_path("truncated");
+        {
+            let (mut wal, _) = Wal::open(&path, 100.0).unwrap();
+            wal.append_intent(&cancel("o1")).unwrap();
+        }
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(b"{\"type\":\"intent\",\"se").unwrap();
+        let (mut wal, entries) = Wal::open(&path, 100.0).unwrap();
+        assert_eq!(entries.len(), 1);
+        // Appends after recovery produce a readable log
+        wal.append_intent(&cancel("o2")).unwrap();
+        let (_, entries) = Wal::open(&path, 100.0).unwrap();
+        assert_eq!(entries.len(), 2);
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,441 @@
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use futures_util::{SinkExt, StreamExt};
+use tokio::net::TcpStream;
+use tokio::sync::{mpsc, watch};
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream, connect_async};
+use tracing::{info, warn};
+
+/// How a server expects to be kept alive.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Keepalive {
+    /// Send text `"PING"` frames, answered by text `"PONG"` (CLOB channels).
+    TextPing(Duration),
+    /// Send protocol-level ping frames (RTDS).
+    WsPing(Duration),
+    None,
+}
+
+impl Keepalive {
+    /// CLOB market/user channels: text `"PING"` every 10s.
+    pub const CLOB: Self = Keepalive::TextPing(Duration::from_secs(10));
+    /// RTDS: ping frames every 5s.
+    pub const RTDS: Self = Keepalive::WsPing(Duration::from_secs(5));
+
+    fn interval(self) -> Option<Duration> {
+        match self {
+            Keepalive::TextPing(d) | Keepalive::WsPing(d) => Some(d),
+            Keepalive::None => None,
+        }
+    }
+}
+
+/// A WebSocket connection that handles keepalives and idle timeouts.
+///
+/// `next` sends keepalive pings when due and swallows the replies (text
+/// `"PONG"` and pong frames), so callers only see data frames.
+pub struct Connection {
+    ws: WebSocketStream<MaybeTlsStream<TcpStream>>,
+    keepalive: Keepalive,
+    idle_timeout: Option<Duration>,
+    next_ping: Option<Instant>,
+    last_frame: Instant,
+}
+
+enum Wake {
+    Frame(Option<Result<Message, tokio_tungstenite::tungstenite::Error>>),
+    Ping,
+    Idle,
+}
+
+impl Connection {
+    /// Connect to `url`. With `idle_timeout` set, `next` fails when no frame
+    /// (including keepalive replies) arrives for that long.
+    pub async fn connect(
+        url: &str,
+        keepalive: Keepalive,
+        idle_timeout: Option<Duration>,
+    ) -> Result<Self> {
+        let (ws, _) = connect_async(url)
+            .await
+            .with_context(|| format!("failed to connect to {url}"))?;
+        let now = Instant::now();
+        Ok(Self {
+            ws,
+            keepalive,
+            idle_timeout,
+            next_ping: keepalive.interval().map(|d| now + d),
+            last_frame: now,
+        })
+    }
+
+    pub async fn send_text(&mut self, text: impl Into<String>) -> Result<()> {
+        let text: String = text.into();
+        self.ws
+            .send(Message::Text(text.into()))
+            .await
+            .context("WebSocket send failed")
+    }
+
+    /// Next data frame, or `None` once the server closes the connection.
+    pub async fn next(&mut self) -> Result<Option<Message>> {
+        loop {
+            let next_ping = self.next_ping;
+            let idle_at = self.idle_timeout.map(|t| self.last_frame + t);
+            let wake = tokio::select! {
+                frame = self.ws.next() => Wake::Frame(frame),
+                _ = sleep_until(next_ping) => Wake::Ping,
+                _ = sleep_until(idle_at) => Wake::Idle,
+            };
+            match wake {
+                Wake::Ping => self.send_keepalive().await?,
+                Wake::Idle => anyhow::bail!(
+                    "no WebSocket frames for {}s",
+                    self.idle_timeout.unwrap_or_default().as_secs()
+                ),
+                Wake::Frame(None) | Wake::Frame(Some(Ok(Message::Close(_)))) => return Ok(None),
+                Wake::Frame(Some(Err(e))) => return Err(e).context("WebSocket read failed"),
+                Wake::Frame(Some(Ok(msg))) => {
+                    self.last_frame = Instant::now();
+                    match &msg {
+                        Message::Pong(_) | Message::Ping(_) => {}
+                        Message::Text(text) if text.as_str() == "PONG" => {}
+                        _ => return Ok(Some(msg)),
+                    }
+                }
+            }
+        }
+    }
+
+    async fn send_keepalive(&mut self) -> Result<()> {
+        let msg = match self.keepalive {
+            Keepalive::TextPing(_) => Message::Text("PING".into()),
+            Keepalive::WsPing(_) => Message::Ping(Vec::new().into()),
+            Keepalive::None => return Ok(()),
+        };
+        self.next_ping = self.keepalive.interval().map(|d| Instant::now() + d);
+        self.ws
+            .send(msg)
+            .await
+            .context("WebSocket keepalive failed")
+    }
+
+    /// Send a close frame and wait for the server to acknowledge it.
+    pub async fn close(mut self) -> Result<()> {
+        self.ws
+            .close(None)
+            .await
+            .context("WebSocket close failed")?;
+        while let Some(Ok(_)) = self.ws.next().await {}
+        Ok(())
+    }
+}
+
+async fn sleep_until(at: Option<Instant>) {
+    match at {
+        Some(at) => tokio::time::sleep_until(at).await,
+        None => std::future::pending().await,
+    }
+}
+
+/// Exponential backoff between reconnect attempts.
+#[derive(Debug, Clone)]
+pub struct ReconnectPolicy {
+    pub initial_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        Self {
+            initial_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(60),
+        }
+    }
+}
+
+impl ReconnectPolicy {
+    /// Delay before reconnect attempt number `attempt` (0-based).
+    pub fn delay(&self, attempt: u32) -> Duration {
+        self.initial_delay
+            .saturating_mul(1 << attempt.min(16))
+            .min(self.max_delay)
+    }
+}
+
+/// What a `WsFeed` reports to its consumer.
+#[derive(Debug, Clone, PartialEq)]
+pub enum WsEvent {
+    /// First successful connection; subscriptions have been sent.
+    Connected,
+    /// A data frame from the server (keepalive traffic is not forwarded).
+    Message(Message),
+    /// The connection dropped. Data derived from the feed may be stale until the
+    /// next `Reconnected`.
+    Disconnected { at: DateTime<Utc>, reason: String },
+    /// Connection restored after an outage of `outage` and subscriptions resent.
+    /// Servers that send snapshots on subscribe (e.g. CLOB `book` events) resync
+    /// here, so consumers should treat the following snapshots as authoritative.
+    Reconnected { outage: Duration },
+}
+
+/// A WebSocket connection that reconnects with backoff and resubscribes.
+///
+/// Subscriptions are the text frames to send after every connect, provided
+/// through a `watch` channel so the asset set can change at runtime. A change
+/// is sent on the live connection immediately and replayed on every reconnect.
+pub struct WsFeed {
+    pub name: &'static str,
+    pub url: String,
+    pub keepalive: Keepalive,
+    /// Reconnect when no frame arrives for this long.
+    pub idle_timeout: Option<Duration>,
+    pub policy: ReconnectPolicy,
+    pub subscriptions: watch::Receiver<Vec<String>>,
+}
+
+impl WsFeed {
+    /// Run the feed on a background task. It stops when the event receiver or
+    /// the subscription sender is dropped.
+    pub fn spawn(self) -> (mpsc::Receiver<WsEvent>, JoinHandle<()>) {
+        let (tx, rx) = mpsc::channel(1024);
+        let handle = tokio::spawn(self.run(tx));
+        (rx, handle)
+    }
+
+    async fn subscribe(&mut self, conn: &mut Connection) -> Result<()> {
+        let subscriptions = self.subscriptions.borrow_and_update().clone();
+        for sub in subscriptions {
+            conn.send_text(sub).await.context("subscribe failed")?;
+        }
+        Ok(())
+    }
+
+    async fn run(mut self, tx: mpsc::Sender<WsEvent>) {
+        let name = self.name;
+        let mut attempt: u32 = 0;
+        let mut ever_connected = false;
+        let mut down_since: Option<Instant> = None;
+
+        loop {
+            match Connection::connect(&self.url, self.keepalive, self.idle_timeout).await {
+                Ok(mut conn) => {
+                    let mut reason = self
+                        .subscribe(&mut conn)
+                        .await
+                        .err()
+                        .map(|e| format!("{e:#}"));
+
+                    if reason.is_none() {
+                        attempt = 0;
+                        let event = match down_since.take() {
+                            Some(since) if ever_connected => {
+                                let outage = since.elapsed();
+                                info!(
+                                    "WS {name}: reconnected after {:.1}s, resubscribed",
+                                    outage.as_secs_f64()
+                                );
+                                WsEvent::Reconnected { outage }
+                            }
+                            _ => {
+                                info!("WS {name}: connected");
+                                WsEvent::Connected
+                            }
+                        };
+                        ever_connected = true;
+                        if tx.send(event).await.is_err() {
+                            return;
+                        }
+                    }
+
+                    while reason.is_none() {
+                        tokio::select! {
+                            msg = conn.next() => match msg {
+                                Ok(Some(msg)) => {
+                                    if tx.send(WsEvent::Message(msg)).await.is_err() {
+                                        return;
+                                    }
+                                }
+                                Ok(None) => reason = Some("closed by server".to_string()),
+                                Err(e) => reason = Some(format!("{e:#}")),
+                            },
+                            changed = self.subscriptions.changed() => {
+                                if changed.is_err() {
+                                    let _ = conn.close().await;
+                                    return;
+                                }
+                                if let Err(e) = self.subscribe(&mut conn).await {
+                                    reason = Some(format!("{e:#}"));
+                                }
+                            }
+                        }
+                    }
+
+                    let reason = reason.unwrap_or_default();
+                    warn!("WS {name}: disconnected ({reason})");
+                    down_since = Some(Instant::now());
+                    let event = WsEvent::Disconnected {
+                        at: Utc::now(),
+                        reason,
+                    };
+                    if tx.send(event).await.is_err() {
+                        return;
+                    }
+                }
+                Err(e) => {
+                    warn!("WS {name}: connect attempt {} failed: {e:#}", attempt + 1);
+                    down_since.get_or_insert_with(Instant::now);
+                }
+            }
+
+            if tx.is_closed() {
+                return;
+            }
+            let delay = self.policy.delay(attempt);
+            attempt = attempt.saturating_add(1);
+            tokio::select! {
+                _ = tokio::time::sleep(delay) => {}
+                _ = tx.closed() => return,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+    use tokio_tungstenite::accept_async;
+
+    #[test]
+    fn reconnect_delay_doubles_up_to_max() {
+        let policy = ReconnectPolicy {
+            initial_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(5),
+        };
+        assert_eq!(policy.delay(0), Duration::from_millis(500));
+        assert_eq!(policy.delay(2), Duration::from_secs(2));
+        assert_eq!(policy.delay(10), Duration::from_secs(5));
+        assert_eq!(policy.delay(u32::MAX), Duration::from_secs(5));
+    }
+
+    #[tokio::test]
+    async fn text_keepalive_is_answered_and_swallowed() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut ws = accept_async(stream).await.unwrap();
+            let Some(Ok(Message::Text(ping))) = ws.next().await else {
+                panic!("expected keepalive");
+            };
+            ws.send(Message::Text("PONG".into())).await.unwrap();
+            ws.send(Message::Text("data".into())).await.unwrap();
+            while ws.next().await.is_some() {}
+            ping.to_string()
+        });
+
+        let mut conn = Connection::connect(
+            &format!("ws://{addr}"),
+            Keepalive::TextPing(Duration::from_millis(20)),
+            Some(Duration::from_secs(5)),
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            conn.next().await.unwrap(),
+            Some(Message::Text("data".into()))
+        );
+        conn.close().await.unwrap();
+        assert_eq!(server.await.unwrap(), "PING");
+    }
+
+    #[tokio::test]
+    async fn silent_server_hits_idle_timeout() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut ws = accept_async(stream).await.unwrap();
+            while ws.next().await.is_some() {}
+        });
+
+        let mut conn = Connection::connect(
+            &format!("ws://{addr}"),
+            Keepalive::None,
+            Some(Duration::from_millis(50)),
+        )
+        .await
+        .unwrap();
+        let err = conn.next().await.unwrap_err();
+        assert!(err.to_string().contains("no WebSocket frames"));
+    }
+
+    #[tokio::test]
+    async fn reconnects_and_resubscribes_after_server_close() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let mut received = Vec::new();
+            for reply in ["hello", "again"] {
+                let (stream, _) = listener.accept().await.unwrap();
+                let mut ws = accept_async(stream).await.unwrap();
+                let Some(Ok(Message::Text(sub))) = ws.next().await else {
+                    panic!("expected subscription");
+                };
+                received.push(sub.to_string());
+                ws.send(Message::Text(reply.into())).await.unwrap();
+                if reply == "hello" {
+                    ws.close(None).await.unwrap();
+                } else {
+                    // Keep the second connection open until the client leaves
+                    while ws.next().await.is_some() {}
+                }
+            }
+            received
+        });
+
+        let (sub_tx, sub_rx) = watch::channel(vec![r#"{"assets_ids":["a1"]}"#.to_string()]);
+        let feed = WsFeed {
+            name: "test",
+            url: format!("ws://{addr}"),
+            keepalive: Keepalive::None,
+            idle_timeout: None,
+            policy: ReconnectPolicy {
+                initial_delay: Duration::from_millis(10),
+                max_delay: Duration::from_millis(50),
+            },
+            subscriptions: sub_rx,
+        };
+        let (mut events, handle) = feed.spawn();
+
+        assert_eq!(events.recv().await, Some(WsEvent::Connected));
+        assert_eq!(
+            events.recv().await,
+            Some(WsEvent::Message(Message::Text("hello".into())))
+        );
+        assert!(matches!(
+            events.recv().await,
+            Some(WsEvent::Disconnected { .. })
+        ));
+        assert!(matches!(
+            events.recv().await,
+            Some(WsEvent::Reconnected { .. })
+        ));
+        assert_eq!(
+            events.recv().await,
+            Some(WsEvent::Message(Message::Text("again".into())))
+        );
+
+        drop(events);
+        drop(sub_tx);
+        handle.await.unwrap();
+        let received = server.await.unwrap();
+        assert_eq!(received, vec![r#"{"assets_ids":["a1"]}"#; 2]);
+    }
+}
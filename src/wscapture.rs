@@ -0,0 +1,224 @@
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use chrono::Utc;
+use futures_util::Stream;
+use serde::{Deserialize, Serialize};
+use tokio_tungstenite::tungstenite::protocol::CloseFrame;
+use tokio_tungstenite::tungstenite::protocol::frame::coding::CloseCode;
+use tokio_tungstenite::tungstenite::{self, Message};
+
+/// Env var naming the capture file for the WebSocket probes.
+pub const CAPTURE_ENV_VAR: &str = "WS_CAPTURE";
+
+/// Payload of one captured WebSocket frame.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Frame {
+    Text { data: String },
+    Binary { data: Vec<u8> },
+    Ping { data: Vec<u8> },
+    Pong { data: Vec<u8> },
+    Close { code: Option<u16>, reason: String },
+}
+
+impl Frame {
+    /// Convert a received message. Raw `Message::Frame`s are never yielded when
+    /// reading, so they are not captured.
+    pub fn from_message(msg: &Message) -> Option<Self> {
+        Some(match msg {
+            Message::Text(text) => Frame::Text {
+                data: text.to_string(),
+            },
+            Message::Binary(data) => Frame::Binary {
+                data: data.to_vec(),
+            },
+            Message::Ping(data) => Frame::Ping {
+                data: data.to_vec(),
+            },
+            Message::Pong(data) => Frame::Pong {
+                data: data.to_vec(),
+            },
+            Message::Close(frame) => Frame::Close {
+                code: frame.as_ref().map(|f| u16::from(f.code)),
+                reason: frame
+                    .as_ref()
+                    .map(|f| f.reason.to_string())
+                    .unwrap_or_default(),
+            },
+            Message::Frame(_) => return None,
+        })
+    }
+
+    pub fn to_message(&self) -> Message {
+        match self {
+            Frame::Text { data } => Message::Text(data.clone().into()),
+            Frame::Binary { data } => Message::Binary(data.clone().into()),
+            Frame::Ping { data } => Message::Ping(data.clone().into()),
+            Frame::Pong { data } => Message::Pong(data.clone().into()),
+            Frame::Close { code, reason } => Message::Close(code.map(|code| CloseFrame {
+                code: CloseCode::from(code),
+                reason: reason.clone().into(),
+            })),
+        }
+    }
+}
+
+/// One line of a capture file: a received frame, when, and on which channel.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CapturedFrame {
+    /// Receive time (Unix milliseconds).
+    pub at_ms: i64,
+    /// Source feed, e.g. `"rtds"` or `"clob_market"`.
+    pub channel: String,
+    #[serde(flatten)]
+    pub frame: Frame,
+}
+
+/// Appends received WebSocket frames to a JSON-lines capture file.
+pub struct CaptureWriter {
+    file: File,
+}
+
+impl CaptureWriter {
+    /// Create (or append to) the capture file at `path`.
+    pub fn create(path: &Path) -> Result<Self> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("failed to open {}", path.display()))?;
+        Ok(Self { file })
+    }
+
+    /// Open the capture file named by `WS_CAPTURE`, if set.
+    pub fn from_env() -> Result<Option<Self>> {
+        match std::env::var(CAPTURE_ENV_VAR) {
+            Ok(path) if !path.is_empty() => Self::create(Path::new(&path)).map(Some),
+            _ => Ok(None),
+        }
+    }
+
+    /// Record `msg`, received now on `channel`.
+    pub fn record(&mut self, channel: &str, msg: &Message) -> Result<()> {
+        self.record_at(Utc::now().timestamp_millis(), channel, msg)
+    }
+
+    pub fn record_at(&mut self, at_ms: i64, channel: &str, msg: &Message) -> Result<()> {
+        let Some(frame) = Frame::from_message(msg) else {
+            return Ok(());
+        };
+        let captured = CapturedFrame {
+            at_ms,
+            channel: channel.to_string(),
+            frame,
+        };
+        let mut line =
+            serde_json::to_string(&captured).context("failed to serialize captured frame")?;
+        line.push('\n');
+        self.file
+            .write_all(line.as_bytes())
+            .context("failed to append captured frame")
+    }
+}
+
+/// Read every frame of a capture file, in recorded order.
+pub fn read_capture(path: &Path) -> Result<Vec<CapturedFrame>> {
+    let contents = std::fs::read_to_string(path)
+        .with_context(|| format!("failed to read {}", path.display()))?;
+    contents
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(idx, line)| {
+            serde_json::from_str(line)
+                .with_context(|| format!("bad captured frame at {}:{}", path.display(), idx + 1))
+        })
+        .collect()
+}
+
+/// Replay captured frames of `channel` as a WebSocket read stream.
+///
+/// The stream has the same item type as the read half of a live connection, so
+/// consumers can be driven from capture files in tests. With `speed` set, frames
+/// are spaced by their recorded gaps divided by `speed`; with `None` they are
+/// yielded back to back, which keeps tests deterministic and fast.
+pub fn replay(
+    frames: Vec<CapturedFrame>,
+    channel: &str,
+    speed: Option<f64>,
+) -> impl Stream<Item = Result<Message, tungstenite::Error>> + use<> {
+    let frames: Vec<CapturedFrame> = frames
+        .into_iter()
+        .filter(|f| f.channel == channel)
+        .collect();
+    let state = (frames.into_iter(), None::<i64>);
+    futures_util::stream::unfold(state, move |(mut frames, prev_ms)| async move {
+        let next = frames.next()?;
+        if let (Some(speed), Some(prev_ms)) = (speed, prev_ms)
+            && speed > 0.0
+        {
+            let gap_ms = (next.at_ms - prev_ms).max(0) as f64 / speed;
+            tokio::time::sleep(Duration::from_secs_f64(gap_ms / 1000.0)).await;
+        }
+        Some((Ok(next.frame.to_message()), (frames, Some(next.at_ms))))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures_util::StreamExt;
+
+    #[tokio::test]
+    async fn capture_round_trips_through_replay() {
+        let path =
+            std::env::temp_dir().join(format!("copytrade-wscapture-{}.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let mut writer = CaptureWriter::create(&path).unwrap();
+        let book = r#"{"event_type":"book","asset_id":"a1"}"#;
+        writer
+            .record_at(1_000, "clob_market", &Message::Text(book.into()))
+            .unwrap();
+        writer
+            .record_at(1_050, "rtds", &Message::Text("{}".into()))
+            .unwrap();
+        writer
+            .record_at(1_100, "clob_market", &Message::Ping(vec![1, 2].into()))
+            .unwrap();
+        writer
+            .record_at(
+                1_200,
+                "clob_market",
+                &Message::Close(Some(CloseFrame {
+                    code: CloseCode::Away,
+                    reason: "bye".into(),
+                })),
+            )
+            .unwrap();
+
+        let frames = read_capture(&path).unwrap();
+        assert_eq!(frames.len(), 4);
+        assert_eq!(frames[0].at_ms, 1_000);
+
+        let replayed: Vec<Message> = replay(frames, "clob_market", None)
+            .map(Result::unwrap)
+            .collect()
+            .await;
+        assert_eq!(replayed.len(), 3);
+        assert_eq!(replayed[0], Message::Text(book.into()));
+        assert_eq!(replayed[1], Message::Ping(vec![1, 2].into()));
+        match &replayed[2] {
+            Message::Close(Some(frame)) => {
+                assert_eq!(frame.code, CloseCode::Away);
+                assert_eq!(frame.reason.as_str(), "bye");
+            }
+            other => panic!("expected close frame, got {other:?}"),
+        }
+        let _ = std::fs::remove_file(&path);
+    }
+}